@@ -0,0 +1,16 @@
+use color_eyre::eyre::Result;
+use namada_apps_lib::{cli, logging, masp_prover};
+use tracing_subscriber::filter::LevelFilter;
+
+#[tokio::main]
+pub async fn main() -> Result<()> {
+    // init error reporting
+    color_eyre::install()?;
+
+    // init logging
+    let _log_guard = logging::init_from_env_or(LevelFilter::INFO)?;
+
+    // run the MASP prover server
+    let args = cli::namada_masp_prover_cli();
+    masp_prover::run(args).await
+}
@@ -53,10 +53,43 @@ pub fn main() -> Result<()> {
                 let chain_ctx = ctx.take_chain_or_exit();
                 node::dump_db(chain_ctx.config.ledger, args);
             }
-            cmds::Ledger::RollBack(_) => {
+            cmds::Ledger::ExportDiffs(cmds::LedgerExportDiffs(args)) => {
                 let chain_ctx = ctx.take_chain_or_exit();
-                node::rollback(chain_ctx.config.ledger)
+                let out_file_path = args.out_file_path.clone();
+                let records = node::export_diffs(chain_ctx.config.ledger, args)
+                    .wrap_err("Failed to export the state diffs")?;
+                println!(
+                    "Exported {records} state diff records to {}",
+                    out_file_path.display()
+                );
+            }
+            cmds::Ledger::InspectDb(query) => {
+                let chain_ctx = ctx.take_chain_or_exit();
+                node::inspect_db(chain_ctx.config.ledger, query)
+                    .wrap_err("Failed to inspect the DB")?;
+            }
+            cmds::Ledger::MigrateDb(cmds::LedgerMigrateDb(args)) => {
+                let chain_ctx = ctx.take_chain_or_exit();
+                let backend = args.backend;
+                let (db_path, copied) =
+                    node::migrate_db(chain_ctx.config.ledger, args)
+                        .wrap_err("Failed to migrate the DB")?;
+                println!(
+                    "Copied {copied} entries into a new {backend} DB in {}. \
+                     To use it, replace the DB directory with it and set \
+                     `db_backend = \"{backend}\"` in the `[ledger.shell]` \
+                     section of the config.",
+                    db_path.display()
+                );
+            }
+            cmds::Ledger::RollBack(cmds::LedgerRollBack(args)) => {
+                let chain_ctx = ctx.take_chain_or_exit();
+                let dry_run = args.dry_run;
+                let rollback = node::rollback(chain_ctx.config.ledger, args)
                     .wrap_err("Failed to rollback the Namada node")?;
+                if dry_run {
+                    println!("{rollback}");
+                }
             }
             cmds::Ledger::UpdateDB(cmds::LedgerUpdateDB(args)) => {
                 #[cfg(not(feature = "migrations"))]
@@ -109,6 +142,23 @@ pub fn main() -> Result<()> {
                     &args.cf,
                 );
             }
+            cmds::Ledger::ReplayProtectionMigration(
+                cmds::LedgerReplayProtectionMigration(args),
+            ) => {
+                #[cfg(not(feature = "migrations"))]
+                {
+                    panic!(
+                        "This command is only available if built with the \
+                         \"migrations\" feature."
+                    )
+                }
+                let chain_ctx = ctx.take_chain_or_exit();
+                #[cfg(feature = "migrations")]
+                node::replay_protection_migration(
+                    chain_ctx.config.ledger,
+                    args.path,
+                );
+            }
         },
         cli::NamadaNode::Config(cmd, mut ctx) => match cmd {
             cmds::Config::Gen(cmds::ConfigGen) => {
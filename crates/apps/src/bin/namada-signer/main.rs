@@ -0,0 +1,16 @@
+use color_eyre::eyre::Result;
+use namada_apps_lib::{cli, logging, remote_signer};
+use tracing_subscriber::filter::LevelFilter;
+
+#[tokio::main]
+pub async fn main() -> Result<()> {
+    // init error reporting
+    color_eyre::install()?;
+
+    // init logging
+    let _log_guard = logging::init_from_env_or(LevelFilter::INFO)?;
+
+    // run the remote signer
+    let args = cli::namada_signer_cli();
+    remote_signer::run(args).await
+}
@@ -242,6 +242,7 @@ pub mod cmds {
                 .subcommand(TxUpdateAccount::def().display_order(1))
                 .subcommand(TxInitAccount::def().display_order(1))
                 .subcommand(TxRevealPk::def().display_order(1))
+                .subcommand(TxSweepDisposable::def().display_order(1))
                 // Governance transactions
                 .subcommand(TxInitProposal::def().display_order(1))
                 .subcommand(TxVoteProposal::def().display_order(1))
@@ -276,6 +277,9 @@ pub mod cmds {
                 .subcommand(
                     QueryShieldingRewardsEstimate::def().display_order(5),
                 )
+                .subcommand(
+                    QueryShieldingRewardsProjection::def().display_order(5),
+                )
                 .subcommand(QueryBonds::def().display_order(5))
                 .subcommand(QueryBondedStake::def().display_order(5))
                 .subcommand(QuerySlashes::def().display_order(5))
@@ -298,6 +302,7 @@ pub mod cmds {
                 // Actions
                 .subcommand(SignTx::def().display_order(6))
                 .subcommand(ShieldedSync::def().display_order(6))
+                .subcommand(ExportMaspSnapshot::def().display_order(6))
                 .subcommand(GenIbcShieldingTransfer::def().display_order(6))
                 // Utils
                 .subcommand(ClientUtils::def().display_order(7))
@@ -329,6 +334,8 @@ pub mod cmds {
             let tx_reactivate_validator =
                 Self::parse_with_ctx(matches, TxReactivateValidator);
             let tx_reveal_pk = Self::parse_with_ctx(matches, TxRevealPk);
+            let tx_sweep_disposable =
+                Self::parse_with_ctx(matches, TxSweepDisposable);
             let tx_init_proposal =
                 Self::parse_with_ctx(matches, TxInitProposal);
             let tx_vote_proposal =
@@ -361,6 +368,8 @@ pub mod cmds {
             let query_balance = Self::parse_with_ctx(matches, QueryBalance);
             let query_rewards_estimate =
                 Self::parse_with_ctx(matches, QueryShieldingRewardsEstimate);
+            let query_rewards_projection =
+                Self::parse_with_ctx(matches, QueryShieldingRewardsProjection);
             let query_bonds = Self::parse_with_ctx(matches, QueryBonds);
             let query_bonded_stake =
                 Self::parse_with_ctx(matches, QueryBondedStake);
@@ -394,6 +403,8 @@ pub mod cmds {
             let add_to_eth_bridge_pool =
                 Self::parse_with_ctx(matches, AddToEthBridgePool);
             let shielded_sync = Self::parse_with_ctx(matches, ShieldedSync);
+            let export_masp_snapshot =
+                Self::parse_with_ctx(matches, ExportMaspSnapshot);
             let gen_ibc_shielding =
                 Self::parse_with_ctx(matches, GenIbcShieldingTransfer);
             let utils = SubCmd::parse(matches).map(Self::WithoutContext);
@@ -406,6 +417,7 @@ pub mod cmds {
                 .or(tx_update_account)
                 .or(tx_init_account)
                 .or(tx_reveal_pk)
+                .or(tx_sweep_disposable)
                 .or(tx_init_proposal)
                 .or(tx_vote_proposal)
                 .or(tx_become_validator)
@@ -432,6 +444,7 @@ pub mod cmds {
                 .or(query_block)
                 .or(query_balance)
                 .or(query_rewards_estimate)
+                .or(query_rewards_projection)
                 .or(query_bonds)
                 .or(query_bonded_stake)
                 .or(query_slashes)
@@ -453,6 +466,7 @@ pub mod cmds {
                 .or(query_staking_rewards_rate)
                 .or(query_account)
                 .or(shielded_sync)
+                .or(export_masp_snapshot)
                 .or(gen_ibc_shielding)
                 .or(utils)
         }
@@ -511,6 +525,7 @@ pub mod cmds {
         TxInitProposal(TxInitProposal),
         TxVoteProposal(TxVoteProposal),
         TxRevealPk(TxRevealPk),
+        TxSweepDisposable(TxSweepDisposable),
         Bond(Bond),
         Unbond(Unbond),
         Withdraw(Withdraw),
@@ -528,6 +543,7 @@ pub mod cmds {
         QueryBlock(QueryBlock),
         QueryBalance(QueryBalance),
         QueryShieldingRewardsEstimate(QueryShieldingRewardsEstimate),
+        QueryShieldingRewardsProjection(QueryShieldingRewardsProjection),
         QueryBonds(QueryBonds),
         QueryBondedStake(QueryBondedStake),
         QueryCommissionRate(QueryCommissionRate),
@@ -547,6 +563,7 @@ pub mod cmds {
         QueryValidatorState(QueryValidatorState),
         QueryRewards(QueryRewards),
         ShieldedSync(ShieldedSync),
+        ExportMaspSnapshot(ExportMaspSnapshot),
         GenIbcShieldingTransfer(GenIbcShieldingTransfer),
     }
 
@@ -573,6 +590,28 @@ pub mod cmds {
         KeyAddrAdd(WalletAddKeyAddress),
         /// Key / address remove
         KeyAddrRemove(WalletRemoveKeyAddress),
+        /// Whole store encryption
+        StoreEncrypt(WalletEncryptStore),
+        /// Whole store decryption
+        StoreDecrypt(WalletDecryptStore),
+        /// Watch-only account add
+        WatchOnlyAdd(WalletAddWatchOnly),
+        /// Address book entry add
+        ContactAdd(WalletAddContact),
+        /// Address book list
+        ContactList(WalletListContacts),
+        /// Address book export
+        BookExport(WalletExportBook),
+        /// Address book import
+        BookImport(WalletImportBook),
+        /// Key policy setting
+        KeyPolicySet(WalletSetKeyPolicy),
+        /// Key usage log
+        KeyUsage(WalletKeyUsage),
+        /// Disposable keys pruning
+        DisposablePrune(WalletPruneDisposable),
+        /// Disposable keys restoration
+        DisposableRestore(WalletRestoreDisposable),
     }
 
     impl Cmd for NamadaWallet {
@@ -587,6 +626,17 @@ pub mod cmds {
                 .subcommand(WalletImportKey::def())
                 .subcommand(WalletAddKeyAddress::def())
                 .subcommand(WalletRemoveKeyAddress::def())
+                .subcommand(WalletEncryptStore::def())
+                .subcommand(WalletDecryptStore::def())
+                .subcommand(WalletAddWatchOnly::def())
+                .subcommand(WalletAddContact::def())
+                .subcommand(WalletListContacts::def())
+                .subcommand(WalletExportBook::def())
+                .subcommand(WalletImportBook::def())
+                .subcommand(WalletSetKeyPolicy::def())
+                .subcommand(WalletKeyUsage::def())
+                .subcommand(WalletPruneDisposable::def())
+                .subcommand(WalletRestoreDisposable::def())
         }
 
         fn parse(matches: &ArgMatches) -> Option<Self> {
@@ -601,6 +651,19 @@ pub mod cmds {
             let key_addr_add = SubCmd::parse(matches).map(Self::KeyAddrAdd);
             let key_addr_remove =
                 SubCmd::parse(matches).map(Self::KeyAddrRemove);
+            let store_encrypt = SubCmd::parse(matches).map(Self::StoreEncrypt);
+            let store_decrypt = SubCmd::parse(matches).map(Self::StoreDecrypt);
+            let watch_only_add = SubCmd::parse(matches).map(Self::WatchOnlyAdd);
+            let contact_add = SubCmd::parse(matches).map(Self::ContactAdd);
+            let contact_list = SubCmd::parse(matches).map(Self::ContactList);
+            let book_export = SubCmd::parse(matches).map(Self::BookExport);
+            let book_import = SubCmd::parse(matches).map(Self::BookImport);
+            let key_policy_set = SubCmd::parse(matches).map(Self::KeyPolicySet);
+            let key_usage = SubCmd::parse(matches).map(Self::KeyUsage);
+            let disposable_prune =
+                SubCmd::parse(matches).map(Self::DisposablePrune);
+            let disposable_restore =
+                SubCmd::parse(matches).map(Self::DisposableRestore);
             gen.or(derive)
                 .or(pay_addr_gen)
                 .or(key_addr_list)
@@ -610,6 +673,17 @@ pub mod cmds {
                 .or(import)
                 .or(key_addr_add)
                 .or(key_addr_remove)
+                .or(store_encrypt)
+                .or(store_decrypt)
+                .or(watch_only_add)
+                .or(contact_add)
+                .or(contact_list)
+                .or(book_export)
+                .or(book_import)
+                .or(key_policy_set)
+                .or(key_usage)
+                .or(disposable_prune)
+                .or(disposable_restore)
         }
     }
 
@@ -864,6 +938,280 @@ pub mod cmds {
         }
     }
 
+    /// Encrypt the whole wallet store
+    #[derive(Clone, Debug)]
+    pub struct WalletEncryptStore;
+
+    impl SubCmd for WalletEncryptStore {
+        const CMD: &'static str = "encrypt-store";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches.subcommand_matches(Self::CMD).map(|_matches| Self)
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD).about(wrap!(
+                "Encrypt the whole wallet store with a password, including \
+                 its aliases, addresses, viewing keys and payment addresses. \
+                 The password is then asked once whenever the wallet is \
+                 loaded. Run it again to change the password."
+            ))
+        }
+    }
+
+    /// Save the whole wallet store in plaintext
+    #[derive(Clone, Debug)]
+    pub struct WalletDecryptStore(pub args::StoreDecrypt);
+
+    impl SubCmd for WalletDecryptStore {
+        const CMD: &'static str = "decrypt-store";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| Self(args::StoreDecrypt::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(wrap!(
+                    "Save an encrypted wallet store in plaintext again. The \
+                     secret keys that were encrypted individually remain \
+                     encrypted."
+                ))
+                .add_args::<args::StoreDecrypt>()
+        }
+    }
+
+    /// Add a watch-only account to the wallet
+    #[derive(Clone, Debug)]
+    pub struct WalletAddWatchOnly(pub args::WatchOnlyAdd);
+
+    impl SubCmd for WalletAddWatchOnly {
+        const CMD: &'static str = "watch";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| Self(args::WatchOnlyAdd::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(wrap!(
+                    "Adds an account of which the wallet tracks the address, \
+                     public keys and viewing key without holding any of its \
+                     secrets."
+                ))
+                .add_args::<args::WatchOnlyAdd>()
+        }
+    }
+
+    /// Add a contact to the address book
+    #[derive(Clone, Debug)]
+    pub struct WalletAddContact(pub args::AddressBookAdd);
+
+    impl SubCmd for WalletAddContact {
+        const CMD: &'static str = "add-contact";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| Self(args::AddressBookAdd::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(wrap!(
+                    "Adds a transparent or shielded address of another party \
+                     to the address book."
+                ))
+                .add_args::<args::AddressBookAdd>()
+        }
+    }
+
+    /// List the address book
+    #[derive(Clone, Debug)]
+    pub struct WalletListContacts(pub args::AddressBookList);
+
+    impl SubCmd for WalletListContacts {
+        const CMD: &'static str = "contacts";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| Self(args::AddressBookList::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(wrap!(
+                    "Lists the address book entries and the watch-only \
+                     accounts."
+                ))
+                .add_args::<args::AddressBookList>()
+        }
+    }
+
+    /// Export the address book or the watch-only accounts to a file
+    #[derive(Clone, Debug)]
+    pub struct WalletExportBook(pub args::BookExport);
+
+    impl SubCmd for WalletExportBook {
+        const CMD: &'static str = "export-book";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| Self(args::BookExport::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(wrap!(
+                    "Exports the address book or the watch-only accounts to a \
+                     JSON or CSV file."
+                ))
+                .add_args::<args::BookExport>()
+        }
+    }
+
+    /// Import address book entries or watch-only accounts from a file
+    #[derive(Clone, Debug)]
+    pub struct WalletImportBook(pub args::BookImport);
+
+    impl SubCmd for WalletImportBook {
+        const CMD: &'static str = "import-book";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| Self(args::BookImport::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(wrap!(
+                    "Imports address book entries or watch-only accounts from \
+                     a JSON or CSV file."
+                ))
+                .add_args::<args::BookImport>()
+        }
+    }
+
+    /// Set or remove the local policy of a key
+    #[derive(Clone, Debug)]
+    pub struct WalletSetKeyPolicy(pub args::KeyPolicySet);
+
+    impl SubCmd for WalletSetKeyPolicy {
+        const CMD: &'static str = "set-policy";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| Self(args::KeyPolicySet::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(wrap!(
+                    "Sets the local policy of a key, replacing its previous \
+                     policy, or removes it."
+                ))
+                .long_about(wrap!(
+                    "Sets the local policy of a key, replacing its previous \
+                     policy, or removes it. The policy is checked before the \
+                     key signs a transaction and may limit the amounts spent \
+                     per day, restrict the targets of the transactions or \
+                     require confirming every use of the key."
+                ))
+                .add_args::<args::KeyPolicySet>()
+        }
+    }
+
+    /// Show and verify the key usage log
+    #[derive(Clone, Debug)]
+    pub struct WalletKeyUsage(pub args::KeyUsageList);
+
+    impl SubCmd for WalletKeyUsage {
+        const CMD: &'static str = "key-usage";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| Self(args::KeyUsageList::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(wrap!(
+                    "Lists the signatures recorded in the key usage log and \
+                     verifies its integrity."
+                ))
+                .add_args::<args::KeyUsageList>()
+        }
+    }
+
+    /// Prune the expired disposable keys
+    #[derive(Clone, Debug)]
+    pub struct WalletPruneDisposable(pub args::DisposableKeysPrune);
+
+    impl SubCmd for WalletPruneDisposable {
+        const CMD: &'static str = "prune-disposable";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| Self(args::DisposableKeysPrune::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(wrap!(
+                    "Removes the disposable gas payer keys that have outlived \
+                     their lifetime from the wallet."
+                ))
+                .long_about(wrap!(
+                    "Removes the disposable gas payer keys that have outlived \
+                     their lifetime from the wallet, optionally setting a new \
+                     lifetime first. Their leftover balances can be moved \
+                     beforehand with `namadac sweep-disposable`."
+                ))
+                .add_args::<args::DisposableKeysPrune>()
+        }
+    }
+
+    /// Restore the disposable keys from an HD seed
+    #[derive(Clone, Debug)]
+    pub struct WalletRestoreDisposable(pub args::DisposableKeysRestore);
+
+    impl SubCmd for WalletRestoreDisposable {
+        const CMD: &'static str = "restore-disposable";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                Self(args::DisposableKeysRestore::parse(matches))
+            })
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(wrap!(
+                    "Derives the disposable gas payer keys from the HD seed \
+                     of a mnemonic code or SLIP-39 shares and adds the \
+                     missing ones to the wallet."
+                ))
+                .long_about(wrap!(
+                    "Derives the disposable gas payer keys from the HD seed \
+                     of a mnemonic code or SLIP-39 shares and adds the \
+                     missing ones to the wallet. The restored keys are \
+                     considered expired, so that their balances can be moved \
+                     with `namadac sweep-disposable`."
+                ))
+                .add_args::<args::DisposableKeysRestore>()
+        }
+    }
+
     /// Generate a payment address from a viewing key or payment address
     #[derive(Clone, Debug)]
     pub struct WalletGenPaymentAddress(pub args::PayAddressGen<args::CliTypes>);
@@ -892,8 +1240,12 @@ pub mod cmds {
         RunUntil(LedgerRunUntil),
         Reset(LedgerReset),
         DumpDb(LedgerDumpDb),
+        ExportDiffs(LedgerExportDiffs),
+        InspectDb(LedgerInspectDb),
+        MigrateDb(LedgerMigrateDb),
         UpdateDB(LedgerUpdateDB),
         QueryDB(LedgerQueryDB),
+        ReplayProtectionMigration(LedgerReplayProtectionMigration),
         RollBack(LedgerRollBack),
     }
 
@@ -905,14 +1257,24 @@ pub mod cmds {
                 let run = SubCmd::parse(matches).map(Self::Run);
                 let reset = SubCmd::parse(matches).map(Self::Reset);
                 let dump_db = SubCmd::parse(matches).map(Self::DumpDb);
+                let export_diffs =
+                    SubCmd::parse(matches).map(Self::ExportDiffs);
+                let inspect_db = SubCmd::parse(matches).map(Self::InspectDb);
+                let migrate_db = SubCmd::parse(matches).map(Self::MigrateDb);
                 let update_db = SubCmd::parse(matches).map(Self::UpdateDB);
                 let query_db = SubCmd::parse(matches).map(Self::QueryDB);
+                let replay_protection_migration =
+                    SubCmd::parse(matches).map(Self::ReplayProtectionMigration);
                 let rollback = SubCmd::parse(matches).map(Self::RollBack);
                 let run_until = SubCmd::parse(matches).map(Self::RunUntil);
                 run.or(reset)
                     .or(dump_db)
+                    .or(export_diffs)
+                    .or(inspect_db)
+                    .or(migrate_db)
                     .or(update_db)
                     .or(query_db)
+                    .or(replay_protection_migration)
                     .or(rollback)
                     .or(run_until)
                     // The `run` command is the default if no sub-command given
@@ -935,8 +1297,12 @@ pub mod cmds {
                 .subcommand(LedgerRunUntil::def())
                 .subcommand(LedgerReset::def())
                 .subcommand(LedgerDumpDb::def())
+                .subcommand(LedgerExportDiffs::def())
+                .subcommand(LedgerInspectDb::def())
+                .subcommand(LedgerMigrateDb::def())
                 .subcommand(LedgerUpdateDB::def())
                 .subcommand(LedgerQueryDB::def())
+                .subcommand(LedgerReplayProtectionMigration::def())
                 .subcommand(LedgerRollBack::def())
         }
     }
@@ -1022,142 +1388,418 @@ pub mod cmds {
     }
 
     #[derive(Clone, Debug)]
-    pub struct LedgerUpdateDB(pub args::LedgerUpdateDb);
+    pub struct LedgerExportDiffs(pub args::LedgerExportDiffs);
 
-    impl SubCmd for LedgerUpdateDB {
-        const CMD: &'static str = "update-db";
+    impl SubCmd for LedgerExportDiffs {
+        const CMD: &'static str = "export-diffs";
 
         fn parse(matches: &ArgMatches) -> Option<Self> {
             matches
                 .subcommand_matches(Self::CMD)
-                .map(|matches| Self(args::LedgerUpdateDb::parse(matches)))
+                .map(|matches| Self(args::LedgerExportDiffs::parse(matches)))
         }
 
         fn def() -> App {
             App::new(Self::CMD)
                 .about(wrap!(
-                    "Applies a set of updates to the DB for hard forking. The \
-                     input should be a path to a file dictating a set of keys \
-                     and their new values. Can be dry-run for testing."
+                    "Export the state diffs of a range of committed blocks \
+                     into a file of newline-delimited JSON records. With the \
+                     RocksDB storage backend, can be used while the ledger is \
+                     running."
                 ))
-                .add_args::<args::LedgerUpdateDb>()
+                .add_args::<args::LedgerExportDiffs>()
         }
     }
 
     #[derive(Clone, Debug)]
-    pub struct LedgerQueryDB(pub args::LedgerQueryDb);
+    pub enum LedgerInspectDb {
+        Iter(InspectDbIter),
+        DiffHeights(InspectDbDiffHeights),
+        DiffDbs(InspectDbDiffDbs),
+        CheckMerkle(InspectDbCheckMerkle),
+        Stats(InspectDbStats),
+        Shell(InspectDbShell),
+    }
 
-    impl SubCmd for LedgerQueryDB {
-        const CMD: &'static str = "query-db";
+    impl LedgerInspectDb {
+        /// Parse a query of the interactive shell, which is given as the
+        /// arguments of this command
+        pub fn parse_query(
+            query: &str,
+        ) -> std::result::Result<Self, clap::Error> {
+            let matches = App::new("inspect-db-shell")
+                .no_binary_name(true)
+                .subcommand_required(true)
+                .subcommand(Self::def())
+                .try_get_matches_from(
+                    std::iter::once(Self::CMD).chain(query.split_whitespace()),
+                )?;
+            Ok(Self::parse(&matches)
+                .expect("A sub-command is required to match the arguments"))
+        }
+    }
+
+    impl SubCmd for LedgerInspectDb {
+        const CMD: &'static str = "inspect-db";
 
         fn parse(matches: &ArgMatches) -> Option<Self> {
-            matches
-                .subcommand_matches(Self::CMD)
-                .map(|matches| Self(args::LedgerQueryDb::parse(matches)))
+            matches.subcommand_matches(Self::CMD).and_then(|matches| {
+                let iter = SubCmd::parse(matches).map(Self::Iter);
+                let diff_heights =
+                    SubCmd::parse(matches).map(Self::DiffHeights);
+                let diff_dbs = SubCmd::parse(matches).map(Self::DiffDbs);
+                let check_merkle =
+                    SubCmd::parse(matches).map(Self::CheckMerkle);
+                let stats = SubCmd::parse(matches).map(Self::Stats);
+                let shell = SubCmd::parse(matches).map(Self::Shell);
+                iter.or(diff_heights)
+                    .or(diff_dbs)
+                    .or(check_merkle)
+                    .or(stats)
+                    .or(shell)
+            })
         }
 
         fn def() -> App {
             App::new(Self::CMD)
+                .subcommand_required(true)
+                .arg_required_else_help(true)
                 .about(wrap!(
-                    "Query the value of keys from the DB while the ledger is \
-                     not running."
+                    "Inspect Namada ledger node's DB while the ledger is not \
+                     running."
                 ))
-                .add_args::<args::LedgerQueryDb>()
+                .subcommand(InspectDbIter::def())
+                .subcommand(InspectDbDiffHeights::def())
+                .subcommand(InspectDbDiffDbs::def())
+                .subcommand(InspectDbCheckMerkle::def())
+                .subcommand(InspectDbStats::def())
+                .subcommand(InspectDbShell::def())
         }
     }
 
     #[derive(Clone, Debug)]
-    pub struct LedgerRollBack;
+    pub struct InspectDbIter(pub args::InspectDbIter);
 
-    impl SubCmd for LedgerRollBack {
-        const CMD: &'static str = "rollback";
+    impl SubCmd for InspectDbIter {
+        const CMD: &'static str = "iter";
 
         fn parse(matches: &ArgMatches) -> Option<Self> {
-            matches.subcommand_matches(Self::CMD).map(|_matches| Self)
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| Self(args::InspectDbIter::parse(matches)))
         }
 
         fn def() -> App {
-            App::new(Self::CMD).about(wrap!(
-                "Roll Namada state back to the previous height. This command \
-                 does not create a backup of neither the Namada nor the \
-                 Tendermint state before execution: for extra safety, it is \
-                 recommended to make a backup in advance."
-            ))
+            App::new(Self::CMD)
+                .about(wrap!(
+                    "Print the entries of a column family under a key prefix \
+                     or whose keys match a pattern."
+                ))
+                .add_args::<args::InspectDbIter>()
         }
     }
 
     #[derive(Clone, Debug)]
-    pub enum Config {
-        Gen(ConfigGen),
-        UpdateValidatorLocalConfig(ValidatorLocalConfig),
-        UpdateLocalConfig(LocalConfig),
-    }
+    pub struct InspectDbDiffHeights(pub args::InspectDbDiffHeights);
 
-    impl SubCmd for Config {
-        const CMD: &'static str = "config";
+    impl SubCmd for InspectDbDiffHeights {
+        const CMD: &'static str = "diff-heights";
 
         fn parse(matches: &ArgMatches) -> Option<Self> {
-            matches.subcommand_matches(Self::CMD).and_then(|matches| {
-                let gen = SubCmd::parse(matches).map(Self::Gen);
-                let validator_cfg = SubCmd::parse(matches)
-                    .map(Self::UpdateValidatorLocalConfig);
-                let local_cfg =
-                    SubCmd::parse(matches).map(Self::UpdateLocalConfig);
-                gen.or(validator_cfg).or(local_cfg)
-            })
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| Self(args::InspectDbDiffHeights::parse(matches)))
         }
 
         fn def() -> App {
             App::new(Self::CMD)
-                .subcommand_required(true)
-                .arg_required_else_help(true)
-                .about(wrap!("Configuration sub-commands."))
-                .subcommand(ConfigGen::def())
-                .subcommand(ValidatorLocalConfig::def())
-                .subcommand(LocalConfig::def())
+                .about(wrap!(
+                    "Print the keys whose values differ between two committed \
+                     block heights. Only the keys whose diffs are persisted \
+                     are compared."
+                ))
+                .add_args::<args::InspectDbDiffHeights>()
         }
     }
 
     #[derive(Clone, Debug)]
-    pub struct ConfigGen;
+    pub struct InspectDbDiffDbs(pub args::InspectDbDiffDbs);
 
-    impl SubCmd for ConfigGen {
-        const CMD: &'static str = "gen";
+    impl SubCmd for InspectDbDiffDbs {
+        const CMD: &'static str = "diff-dbs";
 
         fn parse(matches: &ArgMatches) -> Option<Self> {
-            matches.subcommand_matches(Self::CMD).map(|_matches| Self)
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| Self(args::InspectDbDiffDbs::parse(matches)))
         }
 
         fn def() -> App {
             App::new(Self::CMD)
-                .about(wrap!("Generate the default configuration file."))
+                .about(wrap!(
+                    "Print the entries that differ between the DB and another \
+                     DB."
+                ))
+                .add_args::<args::InspectDbDiffDbs>()
         }
     }
 
     #[derive(Clone, Debug)]
-    pub struct ValidatorLocalConfig(pub args::UpdateValidatorLocalConfig);
+    pub struct InspectDbCheckMerkle(pub args::InspectDbCheckMerkle);
 
-    impl SubCmd for ValidatorLocalConfig {
-        const CMD: &'static str = "update-validator-local-config";
+    impl SubCmd for InspectDbCheckMerkle {
+        const CMD: &'static str = "check-merkle";
 
         fn parse(matches: &ArgMatches) -> Option<Self> {
-            matches.subcommand_matches(Self::CMD).map(|matches| {
-                Self(args::UpdateValidatorLocalConfig::parse(matches))
-            })
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| Self(args::InspectDbCheckMerkle::parse(matches)))
         }
 
         fn def() -> App {
             App::new(Self::CMD)
-                .about(wrap!("Update the validator's local configuration."))
-                .add_args::<args::UpdateValidatorLocalConfig>()
+                .about(wrap!(
+                    "Restore the Merkle tree of a committed block and check \
+                     the consistency of each of its stores."
+                ))
+                .add_args::<args::InspectDbCheckMerkle>()
         }
     }
 
     #[derive(Clone, Debug)]
-    pub struct LocalConfig(pub args::UpdateLocalConfig);
+    pub struct InspectDbStats(pub args::InspectDbStats);
 
-    impl SubCmd for LocalConfig {
-        const CMD: &'static str = "update-local-config";
+    impl SubCmd for InspectDbStats {
+        const CMD: &'static str = "stats";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| Self(args::InspectDbStats::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(wrap!(
+                    "Print the number of entries and their size in bytes per \
+                     column family and key prefix."
+                ))
+                .add_args::<args::InspectDbStats>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct InspectDbShell;
+
+    impl SubCmd for InspectDbShell {
+        const CMD: &'static str = "shell";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches.subcommand_matches(Self::CMD).map(|_matches| Self)
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD).about(wrap!(
+                "Start an interactive shell that reads the sub-commands of \
+                 `inspect-db` from the standard input, one per line, and \
+                 keeps the DB open between them. Exit with `exit` or an end \
+                 of file."
+            ))
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct LedgerMigrateDb(pub args::LedgerMigrateDb);
+
+    impl SubCmd for LedgerMigrateDb {
+        const CMD: &'static str = "migrate-db";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| Self(args::LedgerMigrateDb::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(wrap!(
+                    "Copy Namada ledger node's DB into a new DB with the \
+                     given storage backend. The ledger must not be running."
+                ))
+                .add_args::<args::LedgerMigrateDb>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct LedgerUpdateDB(pub args::LedgerUpdateDb);
+
+    impl SubCmd for LedgerUpdateDB {
+        const CMD: &'static str = "update-db";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| Self(args::LedgerUpdateDb::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(wrap!(
+                    "Applies a set of updates to the DB for hard forking. The \
+                     input should be a path to a file dictating a set of keys \
+                     and their new values. Can be dry-run for testing."
+                ))
+                .add_args::<args::LedgerUpdateDb>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct LedgerQueryDB(pub args::LedgerQueryDb);
+
+    impl SubCmd for LedgerQueryDB {
+        const CMD: &'static str = "query-db";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| Self(args::LedgerQueryDb::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(wrap!(
+                    "Query the value of keys from the DB while the ledger is \
+                     not running."
+                ))
+                .add_args::<args::LedgerQueryDb>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct LedgerReplayProtectionMigration(
+        pub args::LedgerReplayProtectionMigration,
+    );
+
+    impl SubCmd for LedgerReplayProtectionMigration {
+        const CMD: &'static str = "replay-protection-migration";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                Self(args::LedgerReplayProtectionMigration::parse(matches))
+            })
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(wrap!(
+                    "Precompute the commitment to the replay protection \
+                     entries and write the DB changes that initialize it, to \
+                     be applied with `update-db`. Once initialized, the \
+                     replay protection entries and the block state are \
+                     committed to the app hash. The ledger must not be \
+                     running."
+                ))
+                .add_args::<args::LedgerReplayProtectionMigration>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct LedgerRollBack(pub args::LedgerRollBack);
+
+    impl SubCmd for LedgerRollBack {
+        const CMD: &'static str = "rollback";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| Self(args::LedgerRollBack::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(wrap!(
+                    "Roll Namada state back to the previous height, or to a \
+                     given height in the rollback window of the node. This \
+                     command does not create a backup of neither the Namada \
+                     nor the Tendermint state before execution: for extra \
+                     safety, it is recommended to make a backup in advance."
+                ))
+                .add_args::<args::LedgerRollBack>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub enum Config {
+        Gen(ConfigGen),
+        UpdateValidatorLocalConfig(ValidatorLocalConfig),
+        UpdateLocalConfig(LocalConfig),
+    }
+
+    impl SubCmd for Config {
+        const CMD: &'static str = "config";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches.subcommand_matches(Self::CMD).and_then(|matches| {
+                let gen = SubCmd::parse(matches).map(Self::Gen);
+                let validator_cfg = SubCmd::parse(matches)
+                    .map(Self::UpdateValidatorLocalConfig);
+                let local_cfg =
+                    SubCmd::parse(matches).map(Self::UpdateLocalConfig);
+                gen.or(validator_cfg).or(local_cfg)
+            })
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .subcommand_required(true)
+                .arg_required_else_help(true)
+                .about(wrap!("Configuration sub-commands."))
+                .subcommand(ConfigGen::def())
+                .subcommand(ValidatorLocalConfig::def())
+                .subcommand(LocalConfig::def())
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct ConfigGen;
+
+    impl SubCmd for ConfigGen {
+        const CMD: &'static str = "gen";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches.subcommand_matches(Self::CMD).map(|_matches| Self)
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(wrap!("Generate the default configuration file."))
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct ValidatorLocalConfig(pub args::UpdateValidatorLocalConfig);
+
+    impl SubCmd for ValidatorLocalConfig {
+        const CMD: &'static str = "update-validator-local-config";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                Self(args::UpdateValidatorLocalConfig::parse(matches))
+            })
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(wrap!("Update the validator's local configuration."))
+                .add_args::<args::UpdateValidatorLocalConfig>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct LocalConfig(pub args::UpdateLocalConfig);
+
+    impl SubCmd for LocalConfig {
+        const CMD: &'static str = "update-local-config";
 
         fn parse(matches: &ArgMatches) -> Option<Self> {
             matches
@@ -1618,6 +2260,30 @@ pub mod cmds {
         }
     }
 
+    #[derive(Clone, Debug)]
+    pub struct ExportMaspSnapshot(pub args::ExportMaspSnapshot<args::CliTypes>);
+
+    impl SubCmd for ExportMaspSnapshot {
+        const CMD: &'static str = "export-masp-snapshot";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                ExportMaspSnapshot(args::ExportMaspSnapshot::parse(matches))
+            })
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(wrap!(
+                    "Export a snapshot of the shielded transfers, commitment \
+                     trees, note indices and witness maps in a range of block \
+                     heights to a file, which can later be used to perform a \
+                     shielded sync without connecting to a node."
+                ))
+                .add_args::<args::ExportMaspSnapshot<args::CliTypes>>()
+        }
+    }
+
     #[derive(Clone, Debug)]
     pub struct Bond(pub args::Bond<args::CliTypes>);
 
@@ -1908,6 +2574,33 @@ pub mod cmds {
         }
     }
 
+    #[derive(Clone, Debug)]
+    pub struct QueryShieldingRewardsProjection(
+        pub args::QueryShieldingRewardsProjection<args::CliTypes>,
+    );
+
+    impl SubCmd for QueryShieldingRewardsProjection {
+        const CMD: &'static str = "project-shielding-rewards";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                QueryShieldingRewardsProjection(
+                    args::QueryShieldingRewardsProjection::parse(matches),
+                )
+            })
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(wrap!(
+                    "Project the MASP rewards of a token, and optionally the \
+                     shielded balance of a viewing key, over future MASP \
+                     epochs. Please run shielded-sync first for best results."
+                ))
+                .add_args::<args::QueryShieldingRewardsProjection<args::CliTypes>>()
+        }
+    }
+
     #[derive(Clone, Debug)]
     pub struct QueryBonds(pub args::QueryBonds<args::CliTypes>);
 
@@ -2421,6 +3114,39 @@ pub mod cmds {
         }
     }
 
+    #[derive(Clone, Debug)]
+    pub struct TxSweepDisposable(pub args::SweepDisposable<args::CliTypes>);
+
+    impl SubCmd for TxSweepDisposable {
+        const CMD: &'static str = "sweep-disposable";
+
+        fn parse(matches: &ArgMatches) -> Option<Self>
+        where
+            Self: Sized,
+        {
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                TxSweepDisposable(args::SweepDisposable::parse(matches))
+            })
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(wrap!(
+                    "Transfer the leftover balances of the expired disposable \
+                     gas payer keys of the wallet to the given target."
+                ))
+                .long_about(wrap!(
+                    "Transfer the leftover balances of the expired disposable \
+                     gas payer keys of the wallet to the given target. The \
+                     balances are moved in full, so the fees must be paid by \
+                     another key given with --gas-payer. Disposable keys that \
+                     have been pruned can be brought back for sweeping with \
+                     `namadaw restore-disposable`."
+                ))
+                .add_args::<args::SweepDisposable<args::CliTypes>>()
+        }
+    }
+
     #[derive(Clone, Debug)]
     pub struct Complete(pub args::Complete);
 
@@ -2522,6 +3248,15 @@ pub mod cmds {
         InitGenesisValidator(InitGenesisValidator),
         PkToTmAddress(PkToTmAddress),
         SignOffline(SignOffline),
+        InitShieldedMultisig(InitShieldedMultisig),
+        CommitShieldedSpends(CommitShieldedSpends),
+        SignShieldedSpends(SignShieldedSpends),
+        AggregateShieldedSpends(AggregateShieldedSpends),
+        CreatePartialTx(CreatePartialTx),
+        InspectPartialTx(InspectPartialTx),
+        AddPartialSignatures(AddPartialSignatures),
+        VerifyPartialTx(VerifyPartialTx),
+        SubmitPartialTx(SubmitPartialTx),
         DefaultBaseDir(DefaultBaseDir),
         EpochSleep(EpochSleep),
         ValidateGenesisTemplates(ValidateGenesisTemplates),
@@ -2554,6 +3289,24 @@ pub mod cmds {
                     SubCmd::parse(matches).map(Self::PkToTmAddress);
                 let sign_offline =
                     SubCmd::parse(matches).map(Self::SignOffline);
+                let init_shielded_multisig =
+                    SubCmd::parse(matches).map(Self::InitShieldedMultisig);
+                let commit_shielded_spends =
+                    SubCmd::parse(matches).map(Self::CommitShieldedSpends);
+                let sign_shielded_spends =
+                    SubCmd::parse(matches).map(Self::SignShieldedSpends);
+                let aggregate_shielded_spends =
+                    SubCmd::parse(matches).map(Self::AggregateShieldedSpends);
+                let create_partial_tx =
+                    SubCmd::parse(matches).map(Self::CreatePartialTx);
+                let inspect_partial_tx =
+                    SubCmd::parse(matches).map(Self::InspectPartialTx);
+                let add_partial_signatures =
+                    SubCmd::parse(matches).map(Self::AddPartialSignatures);
+                let verify_partial_tx =
+                    SubCmd::parse(matches).map(Self::VerifyPartialTx);
+                let submit_partial_tx =
+                    SubCmd::parse(matches).map(Self::SubmitPartialTx);
                 let default_base_dir =
                     SubCmd::parse(matches).map(Self::DefaultBaseDir);
                 let epoch_sleep = SubCmd::parse(matches).map(Self::EpochSleep);
@@ -2581,6 +3334,15 @@ pub mod cmds {
                     .or(genesis_tx)
                     .or(parse_migrations_json)
                     .or(sign_offline)
+                    .or(init_shielded_multisig)
+                    .or(commit_shielded_spends)
+                    .or(sign_shielded_spends)
+                    .or(aggregate_shielded_spends)
+                    .or(create_partial_tx)
+                    .or(inspect_partial_tx)
+                    .or(add_partial_signatures)
+                    .or(verify_partial_tx)
+                    .or(submit_partial_tx)
                     .or(derive_ibc_token)
                     .or(pubkey_to_addr)
             })
@@ -2598,6 +3360,15 @@ pub mod cmds {
                 .subcommand(InitGenesisValidator::def())
                 .subcommand(PkToTmAddress::def())
                 .subcommand(SignOffline::def())
+                .subcommand(InitShieldedMultisig::def())
+                .subcommand(CommitShieldedSpends::def())
+                .subcommand(SignShieldedSpends::def())
+                .subcommand(AggregateShieldedSpends::def())
+                .subcommand(CreatePartialTx::def())
+                .subcommand(InspectPartialTx::def())
+                .subcommand(AddPartialSignatures::def())
+                .subcommand(VerifyPartialTx::def())
+                .subcommand(SubmitPartialTx::def())
                 .subcommand(DefaultBaseDir::def())
                 .subcommand(EpochSleep::def())
                 .subcommand(ValidateGenesisTemplates::def())
@@ -3332,38 +4103,237 @@ pub mod cmds {
     }
 
     #[derive(Clone, Debug)]
-    pub struct DefaultBaseDir(pub args::DefaultBaseDir);
+    pub struct InitShieldedMultisig(pub args::InitShieldedMultisig);
 
-    impl SubCmd for DefaultBaseDir {
-        const CMD: &'static str = "default-base-dir";
+    impl SubCmd for InitShieldedMultisig {
+        const CMD: &'static str = "init-shielded-multisig";
 
         fn parse(matches: &ArgMatches) -> Option<Self> {
             matches
                 .subcommand_matches(Self::CMD)
-                .map(|matches| Self(args::DefaultBaseDir::parse(matches)))
+                .map(|matches| Self(args::InitShieldedMultisig::parse(matches)))
         }
 
         fn def() -> App {
             App::new(Self::CMD)
                 .about(wrap!(
-                    "Print the default base directory that would be used if \
-                     --base-dir or NAMADA_BASE_DIR were not used to set the \
-                     base directory."
+                    "Generate a shielded multisig key and split it into \
+                     shares for its participants."
                 ))
-                .add_args::<args::DefaultBaseDir>()
+                .add_args::<args::InitShieldedMultisig>()
         }
     }
-}
 
-pub mod args {
-    use std::env;
-    use std::net::SocketAddr;
-    use std::path::PathBuf;
+    #[derive(Clone, Debug)]
+    pub struct CommitShieldedSpends(pub args::CommitShieldedSpends);
+
+    impl SubCmd for CommitShieldedSpends {
+        const CMD: &'static str = "commit-shielded-spends";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| Self(args::CommitShieldedSpends::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(wrap!(
+                    "Generate the signing commitments of a shielded multisig \
+                     participant for a spend authorization request."
+                ))
+                .add_args::<args::CommitShieldedSpends>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct SignShieldedSpends(pub args::SignShieldedSpends);
+
+    impl SubCmd for SignShieldedSpends {
+        const CMD: &'static str = "sign-shielded-spends";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| Self(args::SignShieldedSpends::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(wrap!(
+                    "Produce the signature shares of a shielded multisig \
+                     participant for a spend authorization request."
+                ))
+                .add_args::<args::SignShieldedSpends>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct AggregateShieldedSpends(pub args::AggregateShieldedSpends);
+
+    impl SubCmd for AggregateShieldedSpends {
+        const CMD: &'static str = "aggregate-shielded-spends";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                Self(args::AggregateShieldedSpends::parse(matches))
+            })
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(wrap!(
+                    "Combine the signature shares of the participants of a \
+                     shielded multisig into an authorized transaction."
+                ))
+                .add_args::<args::AggregateShieldedSpends>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct CreatePartialTx(pub args::CreatePartialTx<CliTypes>);
+
+    impl SubCmd for CreatePartialTx {
+        const CMD: &'static str = "create-partial-tx";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                Self(args::CreatePartialTx::<CliTypes>::parse(matches))
+            })
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(wrap!(
+                    "Bundle a transaction with the public keys and the \
+                     threshold of its owner, to collect the signatures of the \
+                     owner's members."
+                ))
+                .add_args::<args::CreatePartialTx<CliTypes>>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct InspectPartialTx(pub args::PartialTx);
+
+    impl SubCmd for InspectPartialTx {
+        const CMD: &'static str = "inspect-partial-tx";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| Self(args::PartialTx::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(wrap!(
+                    "Show the signatures collected in a partially signed \
+                     transaction and the ones that are still missing."
+                ))
+                .add_args::<args::PartialTx>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct AddPartialSignatures(pub args::AddPartialSignatures);
+
+    impl SubCmd for AddPartialSignatures {
+        const CMD: &'static str = "add-partial-signatures";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| Self(args::AddPartialSignatures::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(wrap!(
+                    "Verify offline signatures and add them to a partially \
+                     signed transaction."
+                ))
+                .add_args::<args::AddPartialSignatures>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct VerifyPartialTx(pub args::PartialTx);
+
+    impl SubCmd for VerifyPartialTx {
+        const CMD: &'static str = "verify-partial-tx";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| Self(args::PartialTx::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(wrap!(
+                    "Verify the signatures of a partially signed transaction \
+                     and check that they meet the threshold of its owner."
+                ))
+                .add_args::<args::PartialTx>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct SubmitPartialTx(pub args::SubmitPartialTx<CliTypes>);
+
+    impl SubCmd for SubmitPartialTx {
+        const CMD: &'static str = "submit-partial-tx";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches.subcommand_matches(Self::CMD).map(|matches| {
+                Self(args::SubmitPartialTx::<CliTypes>::parse(matches))
+            })
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(wrap!(
+                    "Finalize a partially signed transaction whose threshold \
+                     is met and submit it to the ledger."
+                ))
+                .add_args::<args::SubmitPartialTx<CliTypes>>()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct DefaultBaseDir(pub args::DefaultBaseDir);
+
+    impl SubCmd for DefaultBaseDir {
+        const CMD: &'static str = "default-base-dir";
+
+        fn parse(matches: &ArgMatches) -> Option<Self> {
+            matches
+                .subcommand_matches(Self::CMD)
+                .map(|matches| Self(args::DefaultBaseDir::parse(matches)))
+        }
+
+        fn def() -> App {
+            App::new(Self::CMD)
+                .about(wrap!(
+                    "Print the default base directory that would be used if \
+                     --base-dir or NAMADA_BASE_DIR were not used to set the \
+                     base directory."
+                ))
+                .add_args::<args::DefaultBaseDir>()
+        }
+    }
+}
+
+pub mod args {
+    use std::env;
+    use std::net::SocketAddr;
+    use std::path::PathBuf;
     use std::str::FromStr;
 
     use data_encoding::HEXUPPER;
     use either::Either;
-    use namada_core::masp::{MaspEpoch, PaymentAddress};
+    use namada_core::masp::{ExtendedViewingKey, MaspEpoch, PaymentAddress};
     use namada_sdk::address::{Address, EstablishedAddress};
     pub use namada_sdk::args::*;
     use namada_sdk::chain::{ChainId, ChainIdPrefix};
@@ -3375,6 +4345,7 @@ pub mod args {
     use namada_sdk::keccak::KeccakHash;
     use namada_sdk::key::*;
     use namada_sdk::masp::utils::RetryStrategy;
+    use namada_sdk::state::StoreType;
     use namada_sdk::storage::{self, BlockHeight, Epoch};
     use namada_sdk::time::DateTimeUtc;
     use namada_sdk::token::NATIVE_MAX_DECIMAL_PLACES;
@@ -3390,6 +4361,8 @@ pub mod args {
         TX_UPDATE_STEWARD_COMMISSION, TX_VOTE_PROPOSAL, TX_WITHDRAW_WASM,
         VP_USER_WASM,
     };
+    use namada_sdk::wallet::address_book::{BookFormat, ContactAddress};
+    use namada_sdk::wallet::slip39::ShareGroups;
     use namada_sdk::{token, DEFAULT_GAS_LIMIT};
 
     use super::context::*;
@@ -3409,6 +4382,8 @@ pub mod args {
     pub const ALIAS_FORCE: ArgFlag = flag("alias-force");
     pub const ALIAS_MANY: ArgMulti<String, GlobPlus> = arg_multi("aliases");
     pub const ALLOW_DUPLICATE_IP: ArgFlag = flag("allow-duplicate-ip");
+    pub const ALLOWED_TARGETS: ArgMulti<Address, GlobStar> =
+        arg_multi("allowed-targets");
     pub const AMOUNT: Arg<token::DenominatedAmount> = arg("amount");
     pub const ARCHIVE_DIR: ArgOpt<PathBuf> = arg_opt("archive-dir");
     pub const AVATAR_OPT: ArgOpt<String> = arg_opt("avatar");
@@ -3424,8 +4399,12 @@ pub mod args {
     pub const BLOCK_BATCH: ArgDefault<usize> =
         arg_default("block-batch", DefaultFn(|| 10));
     pub const BLOCK_HEIGHT: Arg<BlockHeight> = arg("block-height");
+    pub const BLOCK_HEIGHT_FROM_OPT: ArgOpt<BlockHeight> =
+        arg_opt("from-height");
     pub const BLOCK_HEIGHT_OPT: ArgOpt<BlockHeight> = arg_opt("height");
     pub const BLOCK_HEIGHT_TO_OPT: ArgOpt<BlockHeight> = arg_opt("to-height");
+    pub const BOOK_FORMAT: ArgDefault<BookFormat> =
+        arg_default("format", DefaultFn(|| BookFormat::Json));
     pub const BRIDGE_POOL_GAS_AMOUNT: ArgDefault<token::DenominatedAmount> =
         arg_default(
             "pool-gas-amount",
@@ -3458,24 +4437,39 @@ pub mod args {
         "consensus-timeout-commit",
         DefaultFn(|| Timeout::from_str("1s").unwrap()),
     );
+    pub const CONTACT_ADDRESS: Arg<ContactAddress> = arg("address");
+    pub const CONTACT_FEE_TOKEN: ArgOpt<Address> = arg_opt("fee-token");
+    pub const CONTACT_LABEL: ArgOpt<String> = arg_opt("label");
+    pub const CONTACT_NOTE: ArgOpt<String> = arg_opt("note");
+    pub const CONTACT_TAG: ArgOpt<String> = arg_opt("tag");
+    pub const CONTACT_TAGS: ArgMulti<String, GlobStar> = arg_multi("tags");
     pub const CONVERSION_TABLE: Arg<PathBuf> = arg("conversion-table");
     pub const DAEMON_MODE: ArgFlag = flag("daemon");
     pub const DAEMON_MODE_RETRY_DUR: ArgOpt<Duration> = arg_opt("retry-sleep");
     pub const DAEMON_MODE_SUCCESS_DUR: ArgOpt<Duration> =
         arg_opt("success-sleep");
+    pub const DAILY_LIMITS: ArgMulti<DailyLimit, GlobStar> =
+        arg_multi("daily-limits");
     pub const DATA_PATH_OPT: ArgOpt<PathBuf> = arg_opt("data-path");
     pub const DATA_PATH: Arg<PathBuf> = arg("data-path");
     pub const DATED_SPENDING_KEYS: ArgMulti<WalletDatedSpendingKey, GlobStar> =
         arg_multi("spending-keys");
     pub const DATED_VIEWING_KEYS: ArgMulti<WalletDatedViewingKey, GlobStar> =
         arg_multi("viewing-keys");
+    pub const DB_BACKEND: Arg<config::DbBackend> = arg("backend");
     pub const DB_KEY: Arg<String> = arg("db-key");
+    pub const DB_KEY_PATTERN: ArgOpt<String> = arg_opt("pattern");
+    pub const DB_KEY_PREFIX: ArgOpt<storage::Key> = arg_opt("prefix");
     pub const DB_COLUMN_FAMILY: ArgDefault<String> = arg_default(
         "db-column-family",
         DefaultFn(|| storage::SUBSPACE_CF.to_string()),
     );
+    pub const DB_COLUMN_FAMILY_OPT: ArgOpt<String> =
+        arg_opt("db-column-family");
     pub const DECRYPT: ArgFlag = flag("decrypt");
     pub const DESCRIPTION_OPT: ArgOpt<String> = arg_opt("description");
+    pub const DISPOSABLE_KEY_COUNT: Arg<u32> = arg("count");
+    pub const DISPOSABLE_KEY_LIFETIME: ArgOpt<u64> = arg_opt("lifetime");
     pub const DISPOSABLE_SIGNING_KEY: ArgFlag = flag("disposable-gas-payer");
     pub const DESTINATION_VALIDATOR: Arg<WalletAddress> =
         arg("destination-validator");
@@ -3483,6 +4477,7 @@ pub mod args {
     pub const DO_IT: ArgFlag = flag("do-it");
     pub const DRY_RUN_TX: ArgFlag = flag("dry-run");
     pub const DRY_RUN_WRAPPER_TX: ArgFlag = flag("dry-run-wrapper");
+    pub const DRY_RUN_TRACE: ArgFlag = flag("trace");
     pub const DUMP_TX: ArgFlag = flag("dump-tx");
     pub const DUMP_WRAPPER_TX: ArgFlag = flag("dump-wrapper-tx");
     pub const DUMP_CONVERSION_TREE: ArgFlag = flag("dump-conversion-tree");
@@ -3506,6 +4501,8 @@ pub mod args {
     pub const FEE_PAYER_OPT: ArgOpt<WalletPublicKey> = arg_opt("gas-payer");
     pub const FILE_PATH: Arg<String> = arg("file");
     pub const FORCE: ArgFlag = flag("force");
+    pub const FROM_HEIGHT: Arg<BlockHeight> = arg("from");
+    pub const FROM_SHARES: ArgFlag = flag("from-shares");
     pub const GAS_LIMIT: ArgDefault<GasLimit> = arg_default(
         "gas-limit",
         DefaultFn(|| GasLimit::from(DEFAULT_GAS_LIMIT)),
@@ -3551,11 +4548,25 @@ pub mod args {
             let raw = "http://127.0.0.1:26657";
             Url::from_str(raw).unwrap()
         }));
+    pub const JSON_OUTPUT: ArgFlag = flag("json");
+    pub const KEY_PREFIXES: ArgMulti<storage::Key, GlobStar> =
+        arg_multi("prefixes");
     pub const LEDGER_ZIP32: ArgFlag = flag("ledger-zip32");
     pub const LIST_FIND_ADDRESSES_ONLY: ArgFlag = flag("addr");
+    pub const LIMIT: ArgOpt<usize> = arg_opt("limit");
     pub const LIST_FIND_KEYS_ONLY: ArgFlag = flag("keys");
     pub const LOCALHOST: ArgFlag = flag("localhost");
+    pub const LOCKED_AMOUNT_CHANGE: ArgOpt<Dec> =
+        arg_opt("locked-amount-change");
+    pub const LOCKED_AMOUNT_RATE: ArgOpt<Dec> = arg_opt("locked-amount-rate");
     pub const MASP_EPOCH: ArgOpt<MaspEpoch> = arg_opt("masp-epoch");
+    pub const MASP_EPOCHS: ArgDefault<u64> =
+        arg_default("epochs", DefaultFn(|| 10));
+    pub const MASP_PROVER: ArgOpt<String> = arg_opt("masp-prover");
+    pub const MASP_PROVER_LISTEN_ADDR: ArgDefault<SocketAddr> = arg_default(
+        "listen-addr",
+        DefaultFn(|| SocketAddr::from(([127, 0, 0, 1], 8551))),
+    );
     pub const MAX_COMMISSION_RATE_CHANGE: Arg<Dec> =
         arg("max-commission-rate-change");
     pub const MAX_CONCURRENT_FETCHES: ArgDefault<usize> =
@@ -3569,12 +4580,15 @@ pub mod args {
     pub const NO_CONVERSIONS: ArgFlag = flag("no-conversions");
     pub const NO_EXPIRATION: ArgFlag = flag("no-expiration");
     pub const NUT: ArgFlag = flag("nut");
+    pub const OTHER_DB_PATH: Arg<PathBuf> = arg("other-db");
     pub const OUT_FILE_PATH_OPT: ArgOpt<PathBuf> = arg_opt("out-file-path");
     pub const OUTPUT: ArgOpt<PathBuf> = arg_opt("output");
     pub const OUTPUT_FOLDER_PATH: ArgOpt<PathBuf> =
         arg_opt("output-folder-path");
     pub const OWNER: Arg<WalletAddress> = arg("owner");
     pub const OWNER_OPT: ArgOpt<WalletAddress> = OWNER.opt();
+    pub const PARTIAL_TX: Arg<PathBuf> = arg("partial-tx");
+    pub const PARTICIPANTS: Arg<u16> = arg("participants");
     pub const PATH: Arg<PathBuf> = arg("path");
     pub const PATH_OPT: ArgOpt<PathBuf> = arg_opt("path");
     pub const PAYMENT_ADDRESS_TARGET: Arg<WalletPaymentAddr> = arg("target");
@@ -3590,6 +4604,8 @@ pub mod args {
     pub const PROPOSAL_PGF_FUNDING: ArgFlag = flag("pgf-funding");
     pub const PROTOCOL_KEY: ArgOpt<WalletPublicKey> = arg_opt("protocol-key");
     pub const PRE_GENESIS_PATH: ArgOpt<PathBuf> = arg_opt("pre-genesis-path");
+    pub const PREFIX_DEPTH: ArgDefault<usize> =
+        arg_default("depth", DefaultFn(|| 1));
     pub const PUBLIC_KEY: Arg<WalletPublicKey> = arg("public-key");
     pub const PUBLIC_KEYS: ArgMulti<WalletPublicKey, GlobStar> =
         arg_multi("public-keys");
@@ -3611,10 +4627,27 @@ pub mod args {
     pub const RAW_PUBLIC_KEY_HASH: Arg<String> = arg("public-key-hash");
     pub const RAW_PUBLIC_KEY_HASH_OPT: ArgOpt<String> =
         RAW_PUBLIC_KEY_HASH.opt();
+    pub const RAW_PUBLIC_KEYS: ArgMulti<common::PublicKey, GlobStar> =
+        arg_multi("public-keys");
+    pub const RAW_VIEWING_KEY_OPT: ArgOpt<ExtendedViewingKey> =
+        arg_opt("viewing-key");
     pub const RECEIVER: Arg<String> = arg("receiver");
     pub const REFUND_TARGET: ArgOpt<WalletTransferTarget> =
         arg_opt("refund-target");
     pub const RELAYER: Arg<Address> = arg("relayer");
+    pub const REMOTE_SIGNER: ArgOpt<String> = arg_opt("signer");
+    pub const REMOTE_SIGNER_ALLOWED_TXS: ArgMulti<String, GlobStar> =
+        arg_multi("allowed-txs");
+    pub const REMOTE_SIGNER_KEYS: ArgMulti<String, GlobPlus> =
+        arg_multi("keys");
+    pub const REMOTE_SIGNER_LISTEN_ADDR: ArgDefault<String> = arg_default(
+        "listen-addr",
+        DefaultFn(|| "tcp://127.0.0.1:26659".to_string()),
+    );
+    pub const REMOTE_SIGNER_MAX_GAS_LIMIT: ArgOpt<u64> =
+        arg_opt("max-gas-limit");
+    pub const REMOVE_POLICY: ArgFlag = flag("remove");
+    pub const REQUIRE_CONFIRMATION: ArgFlag = flag("require-confirmation");
     pub const RETRIES: ArgOpt<u64> = arg_opt("retries");
     pub const SCHEME: ArgDefault<SchemeType> =
         arg_default("scheme", DefaultFn(|| SchemeType::Ed25519));
@@ -3622,7 +4655,13 @@ pub mod args {
     pub const SELF_BOND_AMOUNT: Arg<token::DenominatedAmount> =
         arg("self-bond-amount");
     pub const SENDER: Arg<String> = arg("sender");
+    pub const SHARES: ArgOpt<ShareGroups> = arg_opt("shares");
+    pub const SHARE_GROUP_THRESHOLD: ArgOpt<u8> =
+        arg_opt("share-group-threshold");
     pub const SHIELDED: ArgFlag = flag("shielded");
+    pub const SHIELDED_KEY_SHARE: Arg<PathBuf> = arg("shielded-key-share");
+    pub const SHIELDED_KEY_SHARE_OPT: ArgOpt<PathBuf> =
+        SHIELDED_KEY_SHARE.opt();
     pub const SHOW_IBC_TOKENS: ArgFlag = flag("show-ibc-tokens");
     pub const SIGNER: ArgOpt<WalletAddress> = arg_opt("signer");
     pub const SIGNING_KEYS: ArgMulti<WalletPublicKey, GlobStar> =
@@ -3634,14 +4673,24 @@ pub mod args {
     pub const SPENDING_KEY_SOURCE: Arg<WalletSpendingKey> = arg("source");
     pub const SPENDING_KEYS: ArgMulti<WalletSpendingKey, GlobStar> =
         arg_multi("spending-keys");
+    pub const SPEND_AUTH_COMMITMENTS: ArgMulti<PathBuf, GlobPlus> =
+        arg_multi("commitments");
+    pub const SPEND_AUTH_NONCES: Arg<PathBuf> = arg("nonces");
+    pub const SPEND_AUTH_REQUEST: Arg<PathBuf> = arg("request");
+    pub const SPEND_AUTH_SIGNATURE_SHARES: ArgMulti<PathBuf, GlobPlus> =
+        arg_multi("signature-shares");
+    pub const SPEND_AUTH_THRESHOLD: Arg<u16> = arg("threshold");
     pub const STEWARD: Arg<WalletAddress> = arg("steward");
     pub const STORAGE_KEY: Arg<storage::Key> = arg("storage-key");
+    pub const STORE_TYPES: ArgMulti<StoreType, GlobStar> =
+        arg_multi("store-types");
     pub const SUSPEND_ACTION: ArgFlag = flag("suspend");
     pub const TARGET: Arg<WalletAddress> = arg("target");
     pub const TEMPLATES_PATH: Arg<PathBuf> = arg("templates-path");
     pub const TIMEOUT_HEIGHT: ArgOpt<u64> = arg_opt("timeout-height");
     pub const TIMEOUT_SEC_OFFSET: ArgOpt<u64> = arg_opt("timeout-sec-offset");
     pub const TM_ADDRESS_OPT: ArgOpt<String> = arg_opt("tm-address");
+    pub const TO_HEIGHT: Arg<BlockHeight> = arg("to");
     pub const TOKEN_OPT: ArgOpt<WalletAddress> = TOKEN.opt();
     pub const TOKEN_STR_OPT: ArgOpt<String> = TOKEN_STR.opt();
     pub const TOKEN: Arg<WalletAddress> = arg("token");
@@ -3672,16 +4721,23 @@ pub mod args {
     pub const VALUE: Arg<String> = arg("value");
     pub const VOTER_OPT: ArgOpt<WalletAddress> = arg_opt("voter");
     pub const VIEWING_KEY: Arg<WalletViewingKey> = arg("key");
+    pub const VIEWING_KEY_OPT: ArgOpt<WalletViewingKey> = VIEWING_KEY.opt();
     pub const VIEWING_KEYS: ArgMulti<WalletViewingKey, GlobStar> =
         arg_multi("viewing-keys");
     pub const VP: ArgOpt<String> = arg_opt("vp");
     pub const WAIT_FOR_LAST_QUERY_HEIGHT: ArgFlag =
         flag("wait-for-last-query-height");
     pub const WALLET_ALIAS_FORCE: ArgFlag = flag("wallet-alias-force");
+    pub const WALLET_DIR: Arg<PathBuf> = arg("wallet-dir");
     pub const WASM_CHECKSUMS_PATH: Arg<PathBuf> = arg("wasm-checksums-path");
+    pub const WASM_CHECKSUMS_PATH_OPT: ArgOpt<PathBuf> =
+        WASM_CHECKSUMS_PATH.opt();
     pub const WASM_DIR: ArgOpt<PathBuf> = arg_opt("wasm-dir");
+    pub const WATCH: ArgOpt<Duration> = arg_opt("watch");
+    pub const WATCH_ONLY: ArgFlag = flag("watch-only");
     pub const WEBSITE_OPT: ArgOpt<String> = arg_opt("website");
     pub const WITH_INDEXER: ArgOpt<String> = arg_opt("with-indexer");
+    pub const WITH_SNAPSHOT: ArgOpt<PathBuf> = arg_opt("with-snapshot");
     pub const WRAPPER_SIGNATURE_OPT: ArgOpt<PathBuf> = arg_opt("gas-signature");
     pub const TX_PATH: Arg<PathBuf> = arg("tx-path");
     pub const TX_PATH_OPT: ArgOpt<PathBuf> = TX_PATH.opt();
@@ -3696,6 +4752,104 @@ pub mod args {
     );
     pub const DEVICE_TRANSPORT_ENV_VAR: &str = "NAMADA_DEVICE_TRANSPORT";
 
+    /// MASP prover server arguments
+    #[derive(Clone, Debug)]
+    pub struct MaspProverServer {
+        /// Address to listen for proof requests on
+        pub listen_addr: SocketAddr,
+    }
+
+    impl Args for MaspProverServer {
+        fn parse(matches: &ArgMatches) -> Self {
+            let listen_addr = MASP_PROVER_LISTEN_ADDR.parse(matches);
+            Self { listen_addr }
+        }
+
+        fn def(app: App) -> App {
+            app.arg(MASP_PROVER_LISTEN_ADDR.def().help(wrap!(
+                "Loopback address to listen for MASP proof requests on. \
+                 Defaults to 127.0.0.1:8551."
+            )))
+        }
+    }
+
+    /// Remote signer arguments
+    #[derive(Clone, Debug)]
+    pub struct RemoteSignerServer {
+        /// Address to listen for signature requests on
+        pub listen_addr: String,
+        /// Directory of the wallet holding the keys
+        pub wallet_dir: PathBuf,
+        /// Aliases of the keys to sign with
+        pub keys: Vec<String>,
+        /// The only chain for which transactions may be signed
+        pub chain_id: Option<ChainId>,
+        /// The code hashes or WASM names of the only transactions that may
+        /// be signed
+        pub allowed_txs: Vec<String>,
+        /// The WASM checksums used to resolve the code hashes of the allowed
+        /// transactions
+        pub wasm_checksums_path: Option<PathBuf>,
+        /// The maximum gas limit of the wrappers that may be signed
+        pub max_gas_limit: Option<u64>,
+    }
+
+    impl Args for RemoteSignerServer {
+        fn parse(matches: &ArgMatches) -> Self {
+            let listen_addr = REMOTE_SIGNER_LISTEN_ADDR.parse(matches);
+            let wallet_dir = WALLET_DIR.parse(matches);
+            let keys = REMOTE_SIGNER_KEYS.parse(matches);
+            let chain_id = CHAIN_ID_OPT.parse(matches);
+            let allowed_txs = REMOTE_SIGNER_ALLOWED_TXS.parse(matches);
+            let wasm_checksums_path = WASM_CHECKSUMS_PATH_OPT.parse(matches);
+            let max_gas_limit = REMOTE_SIGNER_MAX_GAS_LIMIT.parse(matches);
+            Self {
+                listen_addr,
+                wallet_dir,
+                keys,
+                chain_id,
+                allowed_txs,
+                wasm_checksums_path,
+                max_gas_limit,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.arg(REMOTE_SIGNER_LISTEN_ADDR.def().help(wrap!(
+                "Address to listen for signature requests on, either as \
+                 \"tcp://{host}:{port}\" or as \"unix://{socket path}\". \
+                 Defaults to tcp://127.0.0.1:26659. The requests are not \
+                 authenticated, hence TCP addresses must be loopback ones. \
+                 Use an SSH tunnel to sign for clients on other hosts."
+            )))
+            .arg(WALLET_DIR.def().help(wrap!(
+                "The directory of the wallet holding the keys to sign with."
+            )))
+            .arg(REMOTE_SIGNER_KEYS.def().help(wrap!(
+                "Comma separated list of the aliases of the keys to sign with."
+            )))
+            .arg(
+                CHAIN_ID_OPT.def().help(wrap!(
+                    "Only sign the transactions of the given chain."
+                )),
+            )
+            .arg(REMOTE_SIGNER_ALLOWED_TXS.def().help(wrap!(
+                "Comma separated list of the only transactions that may be \
+                 signed, given either as code hashes or as WASM names, e.g. \
+                 tx_transfer.wasm, whose code hashes are read from the WASM \
+                 checksums file. Transactions are matched by code hash. Any \
+                 transaction may be signed if absent."
+            )))
+            .arg(WASM_CHECKSUMS_PATH_OPT.def().help(wrap!(
+                "The WASM checksums file used to resolve the code hashes of \
+                 the allowed transactions given by name."
+            )))
+            .arg(REMOTE_SIGNER_MAX_GAS_LIMIT.def().help(wrap!(
+                "The maximum gas limit of the fees that may be signed."
+            )))
+        }
+    }
+
     /// Global command arguments
     #[derive(Clone, Debug)]
     pub struct Global {
@@ -3816,89 +4970,414 @@ pub mod args {
     }
 
     #[derive(Clone, Debug)]
-    pub struct LedgerRunUntil {
-        pub time: Option<DateTimeUtc>,
-        pub action_at_height: ActionAtHeight,
+    pub struct LedgerRunUntil {
+        pub time: Option<DateTimeUtc>,
+        pub action_at_height: ActionAtHeight,
+    }
+
+    impl Args for LedgerRunUntil {
+        fn parse(matches: &ArgMatches) -> Self {
+            Self {
+                time: NAMADA_START_TIME.parse(matches),
+                action_at_height: ActionAtHeight {
+                    height: BLOCK_HEIGHT.parse(matches),
+                    action: if HALT_ACTION.parse(matches) {
+                        Action::Halt
+                    } else {
+                        Action::Suspend
+                    },
+                },
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.arg(
+                NAMADA_START_TIME
+                    .def()
+                    .help(wrap!("The start time of the ledger.")),
+            )
+            .arg(
+                BLOCK_HEIGHT
+                    .def()
+                    .help(wrap!("The block height to run until.")),
+            )
+            .arg(
+                HALT_ACTION
+                    .def()
+                    .help(wrap!("Halt at the given block height")),
+            )
+            .arg(
+                SUSPEND_ACTION
+                    .def()
+                    .help(wrap!("Suspend consensus at the given block height")),
+            )
+            .group(
+                ArgGroup::new("find_flags")
+                    .args([HALT_ACTION.name, SUSPEND_ACTION.name])
+                    .required(true),
+            )
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct LedgerDumpDb {
+        pub block_height: Option<BlockHeight>,
+        pub out_file_path: PathBuf,
+        pub historic: bool,
+    }
+
+    impl Args for LedgerDumpDb {
+        fn parse(matches: &ArgMatches) -> Self {
+            let block_height = BLOCK_HEIGHT_OPT.parse(matches);
+            let out_file_path = OUT_FILE_PATH_OPT
+                .parse(matches)
+                .unwrap_or_else(|| PathBuf::from("db_dump".to_string()));
+            let historic = HISTORIC.parse(matches);
+
+            Self {
+                block_height,
+                out_file_path,
+                historic,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.arg(BLOCK_HEIGHT_OPT.def().help(wrap!(
+                "The block height to dump. Defaults to latest committed
+                block."
+            )))
+            .arg(OUT_FILE_PATH_OPT.def().help(wrap!(
+                "Path for the output file (omitting file extension). Defaults \
+                 to \"db_dump.{block_height}.toml\" in the current working \
+                 directory."
+            )))
+            .arg(HISTORIC.def().help(wrap!(
+                "If provided, dump also the diff of the last height"
+            )))
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct LedgerExportDiffs {
+        pub from: BlockHeight,
+        pub to: BlockHeight,
+        pub prefixes: Vec<storage::Key>,
+        pub store_types: Vec<StoreType>,
+        pub out_file_path: PathBuf,
+    }
+
+    impl Args for LedgerExportDiffs {
+        fn parse(matches: &ArgMatches) -> Self {
+            let from = FROM_HEIGHT.parse(matches);
+            let to = TO_HEIGHT.parse(matches);
+            let prefixes = KEY_PREFIXES.parse(matches);
+            let store_types = STORE_TYPES.parse(matches);
+            let out_file_path =
+                OUT_FILE_PATH_OPT.parse(matches).unwrap_or_else(|| {
+                    PathBuf::from(format!("state_diffs.{from}-{to}.jsonl"))
+                });
+
+            Self {
+                from,
+                to,
+                prefixes,
+                store_types,
+                out_file_path,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.arg(
+                FROM_HEIGHT
+                    .def()
+                    .help(wrap!("The first block height to export.")),
+            )
+            .arg(
+                TO_HEIGHT
+                    .def()
+                    .help(wrap!("The last block height to export.")),
+            )
+            .arg(KEY_PREFIXES.def().help(wrap!(
+                "Only export the keys that start with any of these \
+                 comma-separated key prefixes."
+            )))
+            .arg(STORE_TYPES.def().help(wrap!(
+                "Only export the keys of any of these comma-separated merkle \
+                 stores: base, account, ibc, pos, eth_bridge_pool, no_diff or \
+                 commit_data."
+            )))
+            .arg(OUT_FILE_PATH_OPT.def().help(wrap!(
+                "Path for the output file. Defaults to \
+                 \"state_diffs.{from}-{to}.jsonl\" in the current working \
+                 directory."
+            )))
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct LedgerRollBack {
+        pub height: Option<BlockHeight>,
+        pub dry_run: bool,
+    }
+
+    impl Args for LedgerRollBack {
+        fn parse(matches: &ArgMatches) -> Self {
+            let height = BLOCK_HEIGHT_OPT.parse(matches);
+            let dry_run = DRY_RUN_TX.parse(matches);
+
+            Self { height, dry_run }
+        }
+
+        fn def(app: App) -> App {
+            app.arg(BLOCK_HEIGHT_OPT.def().help(wrap!(
+                "The height to roll Namada state back to, which must be in \
+                 the rollback window of the node (see the `rollback_window` \
+                 option of the config). Only Namada state is rolled back: \
+                 CometBFT replays its stored blocks above the height when the \
+                 node is started again. Defaults to the previous height, \
+                 together with a rollback of the CometBFT state."
+            )))
+            .arg(DRY_RUN_TX.def().help(wrap!(
+                "Print the changes of the DB without writing them."
+            )))
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct LedgerMigrateDb {
+        pub backend: config::DbBackend,
+        pub output_folder: Option<PathBuf>,
+    }
+
+    impl Args for LedgerMigrateDb {
+        fn parse(matches: &ArgMatches) -> Self {
+            let backend = DB_BACKEND.parse(matches);
+            let output_folder = OUTPUT_FOLDER_PATH.parse(matches);
+
+            Self {
+                backend,
+                output_folder,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.arg(DB_BACKEND.def().help(wrap!(
+                "The storage backend of the new DB: rocksdb or redb."
+            )))
+            .arg(OUTPUT_FOLDER_PATH.def().help(wrap!(
+                "The directory of the new DB, which must not exist yet. \
+                 Defaults to a sibling of the DB directory suffixed with the \
+                 name of the backend."
+            )))
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct InspectDbIter {
+        pub cf: storage::DbColFam,
+        pub prefix: Option<storage::Key>,
+        pub pattern: Option<String>,
+        pub type_hash: Option<[u8; 32]>,
+        pub limit: Option<usize>,
+        pub json: bool,
+    }
+
+    impl Args for InspectDbIter {
+        fn parse(matches: &ArgMatches) -> Self {
+            let cf =
+                storage::DbColFam::from_str(&DB_COLUMN_FAMILY.parse(matches))
+                    .unwrap();
+            let prefix = DB_KEY_PREFIX.parse(matches);
+            let pattern = DB_KEY_PATTERN.parse(matches);
+            let type_hash = HASH_OPT.parse(matches).map(|hex_hash| {
+                HEXUPPER
+                    .decode(hex_hash.to_uppercase().as_bytes())
+                    .unwrap()
+                    .try_into()
+                    .unwrap()
+            });
+            let limit = LIMIT.parse(matches);
+            let json = JSON_OUTPUT.parse(matches);
+            Self {
+                cf,
+                prefix,
+                pattern,
+                type_hash,
+                limit,
+                json,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.arg(DB_COLUMN_FAMILY.def().help(wrap!(
+                "The column family to iterate. Defaults to the subspace \
+                 column family if none is provided."
+            )))
+            .arg(
+                DB_KEY_PREFIX.def().help(wrap!(
+                    "Only print the entries under this key prefix."
+                )),
+            )
+            .arg(DB_KEY_PATTERN.def().help(wrap!(
+                "Only print the entries whose keys match this regular \
+                 expression."
+            )))
+            .arg(HASH_OPT.def().help(wrap!(
+                "The hex encoded type hash of the values, to print them \
+                 decoded. The values that can't be decoded are printed in hex."
+            )))
+            .arg(
+                LIMIT
+                    .def()
+                    .help(wrap!("The maximum number of entries to print.")),
+            )
+            .arg(JSON_OUTPUT.def().help(wrap!(
+                "Print the entries as newline-delimited JSON records."
+            )))
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct InspectDbDiffHeights {
+        pub from: BlockHeight,
+        pub to: BlockHeight,
+        pub prefixes: Vec<storage::Key>,
+        pub store_types: Vec<StoreType>,
+        pub json: bool,
+    }
+
+    impl Args for InspectDbDiffHeights {
+        fn parse(matches: &ArgMatches) -> Self {
+            let from = FROM_HEIGHT.parse(matches);
+            let to = TO_HEIGHT.parse(matches);
+            let prefixes = KEY_PREFIXES.parse(matches);
+            let store_types = STORE_TYPES.parse(matches);
+            let json = JSON_OUTPUT.parse(matches);
+            Self {
+                from,
+                to,
+                prefixes,
+                store_types,
+                json,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.arg(
+                FROM_HEIGHT
+                    .def()
+                    .help(wrap!("The block height to compare from.")),
+            )
+            .arg(
+                TO_HEIGHT
+                    .def()
+                    .help(wrap!("The block height to compare to.")),
+            )
+            .arg(KEY_PREFIXES.def().help(wrap!(
+                "Only compare the keys that start with any of these \
+                 comma-separated key prefixes."
+            )))
+            .arg(STORE_TYPES.def().help(wrap!(
+                "Only compare the keys of any of these comma-separated merkle \
+                 stores: base, account, ibc, pos, eth_bridge_pool, no_diff or \
+                 commit_data."
+            )))
+            .arg(JSON_OUTPUT.def().help(wrap!(
+                "Print the diffs as newline-delimited JSON records."
+            )))
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct InspectDbDiffDbs {
+        pub other_db: PathBuf,
+        pub cf: Option<storage::DbColFam>,
+        pub prefix: Option<storage::Key>,
+        pub json: bool,
     }
 
-    impl Args for LedgerRunUntil {
+    impl Args for InspectDbDiffDbs {
         fn parse(matches: &ArgMatches) -> Self {
+            let other_db = OTHER_DB_PATH.parse(matches);
+            let cf = DB_COLUMN_FAMILY_OPT
+                .parse(matches)
+                .map(|cf| storage::DbColFam::from_str(&cf).unwrap());
+            let prefix = DB_KEY_PREFIX.parse(matches);
+            let json = JSON_OUTPUT.parse(matches);
             Self {
-                time: NAMADA_START_TIME.parse(matches),
-                action_at_height: ActionAtHeight {
-                    height: BLOCK_HEIGHT.parse(matches),
-                    action: if HALT_ACTION.parse(matches) {
-                        Action::Halt
-                    } else {
-                        Action::Suspend
-                    },
-                },
+                other_db,
+                cf,
+                prefix,
+                json,
             }
         }
 
         fn def(app: App) -> App {
-            app.arg(
-                NAMADA_START_TIME
-                    .def()
-                    .help(wrap!("The start time of the ledger.")),
-            )
-            .arg(
-                BLOCK_HEIGHT
-                    .def()
-                    .help(wrap!("The block height to run until.")),
-            )
-            .arg(
-                HALT_ACTION
-                    .def()
-                    .help(wrap!("Halt at the given block height")),
-            )
+            app.arg(OTHER_DB_PATH.def().help(wrap!(
+                "The directory of the DB to compare with. It can use either \
+                 storage backend."
+            )))
+            .arg(DB_COLUMN_FAMILY_OPT.def().help(wrap!(
+                "Only compare this column family. Defaults to all the column \
+                 families."
+            )))
             .arg(
-                SUSPEND_ACTION
-                    .def()
-                    .help(wrap!("Suspend consensus at the given block height")),
-            )
-            .group(
-                ArgGroup::new("find_flags")
-                    .args([HALT_ACTION.name, SUSPEND_ACTION.name])
-                    .required(true),
+                DB_KEY_PREFIX.def().help(wrap!(
+                    "Only compare the entries under this key prefix."
+                )),
             )
+            .arg(JSON_OUTPUT.def().help(wrap!(
+                "Print the diffs as newline-delimited JSON records."
+            )))
         }
     }
 
     #[derive(Clone, Debug)]
-    pub struct LedgerDumpDb {
-        pub block_height: Option<BlockHeight>,
-        pub out_file_path: PathBuf,
-        pub historic: bool,
+    pub struct InspectDbCheckMerkle {
+        pub height: Option<BlockHeight>,
+        pub json: bool,
     }
 
-    impl Args for LedgerDumpDb {
+    impl Args for InspectDbCheckMerkle {
         fn parse(matches: &ArgMatches) -> Self {
-            let block_height = BLOCK_HEIGHT_OPT.parse(matches);
-            let out_file_path = OUT_FILE_PATH_OPT
-                .parse(matches)
-                .unwrap_or_else(|| PathBuf::from("db_dump".to_string()));
-            let historic = HISTORIC.parse(matches);
-
-            Self {
-                block_height,
-                out_file_path,
-                historic,
-            }
+            let height = BLOCK_HEIGHT_OPT.parse(matches);
+            let json = JSON_OUTPUT.parse(matches);
+            Self { height, json }
         }
 
         fn def(app: App) -> App {
             app.arg(BLOCK_HEIGHT_OPT.def().help(wrap!(
-                "The block height to dump. Defaults to latest committed
-                block."
+                "The block height of the Merkle tree to check. Defaults to \
+                 the last committed block."
             )))
-            .arg(OUT_FILE_PATH_OPT.def().help(wrap!(
-                "Path for the output file (omitting file extension). Defaults \
-                 to \"db_dump.{block_height}.toml\" in the current working \
-                 directory."
+            .arg(JSON_OUTPUT.def().help(wrap!(
+                "Print the result of each store as a newline-delimited JSON \
+                 record."
             )))
-            .arg(HISTORIC.def().help(wrap!(
-                "If provided, dump also the diff of the last height"
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct InspectDbStats {
+        pub depth: usize,
+        pub json: bool,
+    }
+
+    impl Args for InspectDbStats {
+        fn parse(matches: &ArgMatches) -> Self {
+            let depth = PREFIX_DEPTH.parse(matches);
+            let json = JSON_OUTPUT.parse(matches);
+            Self { depth, json }
+        }
+
+        fn def(app: App) -> App {
+            app.arg(PREFIX_DEPTH.def().help(wrap!(
+                "The number of key segments of the prefixes to group the \
+                 entries by. With 0, only the totals of the column families \
+                 are printed. Defaults to 1."
+            )))
+            .arg(JSON_OUTPUT.def().help(wrap!(
+                "Print the statistics as newline-delimited JSON records."
             )))
         }
     }
@@ -3938,6 +5417,24 @@ pub mod args {
         }
     }
 
+    #[derive(Clone, Debug)]
+    pub struct LedgerReplayProtectionMigration {
+        pub path: PathBuf,
+    }
+
+    impl Args for LedgerReplayProtectionMigration {
+        fn parse(matches: &ArgMatches) -> Self {
+            let path = PATH.parse(matches);
+            Self { path }
+        }
+
+        fn def(app: App) -> App {
+            app.arg(PATH.def().help(wrap!(
+                "The path to write the json of the DB changes to."
+            )))
+        }
+    }
+
     #[derive(Clone, Debug)]
     pub struct LedgerQueryDb {
         pub key: storage::Key,
@@ -5953,6 +7450,53 @@ pub mod args {
         }
     }
 
+    impl CliToSdk<SweepDisposable<SdkTypes>> for SweepDisposable<CliTypes> {
+        type Error = std::io::Error;
+
+        fn to_sdk(
+            self,
+            ctx: &mut Context,
+        ) -> Result<SweepDisposable<SdkTypes>, Self::Error> {
+            let tx = self.tx.to_sdk(ctx)?;
+            let chain_ctx = ctx.borrow_mut_chain_or_exit();
+
+            Ok(SweepDisposable::<SdkTypes> {
+                tx,
+                target: chain_ctx.get(&self.target),
+                token: self.token.map(|token| chain_ctx.get(&token)),
+                tx_code_path: self.tx_code_path.to_path_buf(),
+            })
+        }
+    }
+
+    impl Args for SweepDisposable<CliTypes> {
+        fn parse(matches: &ArgMatches) -> Self {
+            let tx = Tx::parse(matches);
+            let target = TARGET.parse(matches);
+            let token = TOKEN_OPT.parse(matches);
+            let tx_code_path = PathBuf::from(TX_TRANSFER_WASM);
+
+            Self {
+                tx,
+                target,
+                token,
+                tx_code_path,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Tx<CliTypes>>()
+                .arg(
+                    TARGET.def().help(wrap!(
+                        "The address receiving the swept balances."
+                    )),
+                )
+                .arg(TOKEN_OPT.def().help(wrap!(
+                    "The token to sweep. Defaults to the native token."
+                )))
+        }
+    }
+
     impl CliToSdk<Complete> for Complete {
         type Error = std::io::Error;
 
@@ -6395,6 +7939,85 @@ pub mod args {
         }
     }
 
+    impl CliToSdk<QueryShieldingRewardsProjection<SdkTypes>>
+        for QueryShieldingRewardsProjection<CliTypes>
+    {
+        type Error = std::convert::Infallible;
+
+        fn to_sdk(
+            self,
+            ctx: &mut Context,
+        ) -> Result<QueryShieldingRewardsProjection<SdkTypes>, Self::Error>
+        {
+            let query = self.query.to_sdk(ctx)?;
+            let chain_ctx = ctx.borrow_mut_chain_or_exit();
+
+            Ok(QueryShieldingRewardsProjection::<SdkTypes> {
+                query,
+                token: chain_ctx.get(&self.token),
+                owner: self.owner.map(|owner| chain_ctx.get_cached(&owner)),
+                epochs: self.epochs,
+                locked_amount_change: self.locked_amount_change,
+                locked_amount_rate: self.locked_amount_rate,
+            })
+        }
+    }
+
+    impl Args for QueryShieldingRewardsProjection<CliTypes> {
+        fn parse(matches: &ArgMatches) -> Self {
+            let query = Query::parse(matches);
+            let token = TOKEN.parse(matches);
+            let owner = VIEWING_KEY_OPT.parse(matches);
+            let epochs = MASP_EPOCHS.parse(matches);
+            let locked_amount_change = LOCKED_AMOUNT_CHANGE.parse(matches);
+            let locked_amount_rate = LOCKED_AMOUNT_RATE.parse(matches);
+            Self {
+                query,
+                token,
+                owner,
+                epochs,
+                locked_amount_change,
+                locked_amount_rate,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Query<CliTypes>>()
+                .arg(
+                    TOKEN
+                        .def()
+                        .help(wrap!("The token whose rewards to project.")),
+                )
+                .arg(VIEWING_KEY_OPT.def().help(wrap!(
+                    "The viewing key whose shielded balance of the token to \
+                     project."
+                )))
+                .arg(MASP_EPOCHS.def().help(wrap!(
+                    "The number of future MASP epochs to project. Defaults to \
+                     10."
+                )))
+                .arg(LOCKED_AMOUNT_CHANGE.def().allow_hyphen_values(true).help(
+                    wrap!(
+                        "The assumed change of the amount of the token locked \
+                         in the shielded pool every MASP epoch, in whole \
+                         units of the token. Can be negative."
+                    ),
+                ))
+                .arg(
+                    LOCKED_AMOUNT_RATE
+                        .def()
+                        .allow_hyphen_values(true)
+                        .help(wrap!(
+                            "The assumed rate of change of the amount of the \
+                             token locked in the shielded pool every MASP \
+                             epoch, e.g. 0.01 for a 1% growth. Can be \
+                             negative."
+                        ))
+                        .conflicts_with(LOCKED_AMOUNT_CHANGE.name),
+                )
+        }
+    }
+
     impl CliToSdk<QueryBonds<SdkTypes>> for QueryBonds<CliTypes> {
         type Error = std::convert::Infallible;
 
@@ -6871,8 +8494,10 @@ pub mod args {
             let spending_keys = DATED_SPENDING_KEYS.parse(matches);
             let viewing_keys = DATED_VIEWING_KEYS.parse(matches);
             let with_indexer = WITH_INDEXER.parse(matches);
+            let with_snapshot = WITH_SNAPSHOT.parse(matches);
             let wait_for_last_query_height =
                 WAIT_FOR_LAST_QUERY_HEIGHT.parse(matches);
+            let watch = WATCH.parse(matches).map(|dur| dur.0);
             let max_concurrent_fetches = MAX_CONCURRENT_FETCHES.parse(matches);
             let retry_strategy = match RETRIES.parse(matches) {
                 Some(times) => RetryStrategy::Times(times),
@@ -6885,7 +8510,9 @@ pub mod args {
                 spending_keys,
                 viewing_keys,
                 with_indexer,
+                with_snapshot,
                 wait_for_last_query_height,
+                watch,
                 max_concurrent_fetches,
                 retry_strategy,
                 block_batch_size,
@@ -6911,20 +8538,130 @@ pub mod args {
                 )))
                 .arg(WITH_INDEXER.def().help(wrap!(
                     "Address of a `namada-masp-indexer` live instance. If \
-                     present, the shielded sync will be performed using data \
-                     retrieved from the given indexer."
+                     present, the shielded sync will be performed using data \
+                     retrieved from the given indexer."
+                )))
+                .arg(
+                    WITH_SNAPSHOT
+                        .def()
+                        .help(wrap!(
+                            "Path to a MASP snapshot file produced by \
+                             `export-masp-snapshot`. If present, the shielded \
+                             sync will be performed using data read from the \
+                             given snapshot, without connecting to a node."
+                        ))
+                        .conflicts_with(WITH_INDEXER.name),
+                )
+                .arg(WAIT_FOR_LAST_QUERY_HEIGHT.def().help(wrap!(
+                    "Wait until the last height to sync is available instead \
+                     of returning early from the shielded sync."
+                )))
+                .arg(
+                    WATCH
+                        .def()
+                        .help(wrap!(
+                            "Keep syncing new blocks at the given interval \
+                             (e.g. `30s`) until interrupted, reporting the \
+                             sync height and new notes of each key after \
+                             every round. Cannot be used with a MASP \
+                             snapshot, as it holds no new blocks."
+                        ))
+                        .conflicts_with_all([
+                            BLOCK_HEIGHT_TO_OPT.name,
+                            WITH_SNAPSHOT.name,
+                        ]),
+                )
+                .arg(MAX_CONCURRENT_FETCHES.def().help(wrap!(
+                    "Maximum number of fetch jobs that will ever execute \
+                     concurrently during the shielded sync."
+                )))
+                .arg(RETRIES.def().help(wrap!(
+                    "Maximum number of times to retry fetching. If no \
+                     argument is provided, defaults to retrying forever."
+                )))
+                .arg(BLOCK_BATCH.def().help(wrap!(
+                    "Number of blocks fetched per concurrent fetch job. The \
+                     default is 10."
+                )))
+        }
+    }
+
+    impl CliToSdk<ShieldedSync<SdkTypes>> for ShieldedSync<CliTypes> {
+        type Error = std::convert::Infallible;
+
+        fn to_sdk(
+            self,
+            ctx: &mut Context,
+        ) -> Result<ShieldedSync<SdkTypes>, Self::Error> {
+            let chain_ctx = ctx.borrow_mut_chain_or_exit();
+
+            Ok(ShieldedSync {
+                block_batch_size: self.block_batch_size,
+                max_concurrent_fetches: self.max_concurrent_fetches,
+                wait_for_last_query_height: self.wait_for_last_query_height,
+                watch: self.watch,
+                ledger_address: chain_ctx.get(&self.ledger_address),
+                last_query_height: self.last_query_height,
+                spending_keys: self
+                    .spending_keys
+                    .iter()
+                    .map(|sk| chain_ctx.get_cached(sk))
+                    .collect(),
+                viewing_keys: self
+                    .viewing_keys
+                    .iter()
+                    .map(|vk| chain_ctx.get_cached(vk))
+                    .collect(),
+                with_indexer: self.with_indexer,
+                with_snapshot: self.with_snapshot,
+                retry_strategy: self.retry_strategy,
+            })
+        }
+    }
+
+    impl Args for ExportMaspSnapshot<CliTypes> {
+        fn parse(matches: &ArgMatches) -> Self {
+            let ledger_address = CONFIG_RPC_LEDGER_ADDRESS.parse(matches);
+            let from_height = BLOCK_HEIGHT_FROM_OPT.parse(matches);
+            let to_height = BLOCK_HEIGHT_TO_OPT.parse(matches);
+            let with_indexer = WITH_INDEXER.parse(matches);
+            let output = OUTPUT.parse(matches);
+            let max_concurrent_fetches = MAX_CONCURRENT_FETCHES.parse(matches);
+            let block_batch_size = BLOCK_BATCH.parse(matches);
+            Self {
+                ledger_address,
+                from_height,
+                to_height,
+                with_indexer,
+                output,
+                max_concurrent_fetches,
+                block_batch_size,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.arg(CONFIG_RPC_LEDGER_ADDRESS.def().help(LEDGER_ADDRESS_ABOUT))
+                .arg(BLOCK_HEIGHT_FROM_OPT.def().help(wrap!(
+                    "Optional first block height covered by the snapshot. \
+                     Default is the first block."
+                )))
+                .arg(BLOCK_HEIGHT_TO_OPT.def().help(wrap!(
+                    "Optional last block height covered by the snapshot. \
+                     Default is latest."
+                )))
+                .arg(WITH_INDEXER.def().help(wrap!(
+                    "Address of a `namada-masp-indexer` live instance. If \
+                     present, the snapshot will be built from data retrieved \
+                     from the given indexer."
                 )))
-                .arg(WAIT_FOR_LAST_QUERY_HEIGHT.def().help(wrap!(
-                    "Wait until the last height to sync is available instead \
-                     of returning early from the shielded sync."
+                .arg(OUTPUT.def().help(wrap!(
+                    "Path of the snapshot file to write. Defaults to a file \
+                     named after the chain ID and the block heights in the \
+                     current directory."
                 )))
                 .arg(MAX_CONCURRENT_FETCHES.def().help(wrap!(
                     "Maximum number of fetch jobs that will ever execute \
-                     concurrently during the shielded sync."
-                )))
-                .arg(RETRIES.def().help(wrap!(
-                    "Maximum number of times to retry fetching. If no \
-                     argument is provided, defaults to retrying forever."
+                     concurrently while building the snapshot."
                 )))
                 .arg(BLOCK_BATCH.def().help(wrap!(
                     "Number of blocks fetched per concurrent fetch job. The \
@@ -6933,33 +8670,23 @@ pub mod args {
         }
     }
 
-    impl CliToSdk<ShieldedSync<SdkTypes>> for ShieldedSync<CliTypes> {
+    impl CliToSdk<ExportMaspSnapshot<SdkTypes>> for ExportMaspSnapshot<CliTypes> {
         type Error = std::convert::Infallible;
 
         fn to_sdk(
             self,
             ctx: &mut Context,
-        ) -> Result<ShieldedSync<SdkTypes>, Self::Error> {
+        ) -> Result<ExportMaspSnapshot<SdkTypes>, Self::Error> {
             let chain_ctx = ctx.borrow_mut_chain_or_exit();
 
-            Ok(ShieldedSync {
-                block_batch_size: self.block_batch_size,
-                max_concurrent_fetches: self.max_concurrent_fetches,
-                wait_for_last_query_height: self.wait_for_last_query_height,
+            Ok(ExportMaspSnapshot {
                 ledger_address: chain_ctx.get(&self.ledger_address),
-                last_query_height: self.last_query_height,
-                spending_keys: self
-                    .spending_keys
-                    .iter()
-                    .map(|sk| chain_ctx.get_cached(sk))
-                    .collect(),
-                viewing_keys: self
-                    .viewing_keys
-                    .iter()
-                    .map(|vk| chain_ctx.get_cached(vk))
-                    .collect(),
+                from_height: self.from_height,
+                to_height: self.to_height,
                 with_indexer: self.with_indexer,
-                retry_strategy: self.retry_strategy,
+                output: self.output,
+                max_concurrent_fetches: self.max_concurrent_fetches,
+                block_batch_size: self.block_batch_size,
             })
         }
     }
@@ -7427,6 +9154,7 @@ pub mod args {
             Ok(Tx::<SdkTypes> {
                 dry_run: self.dry_run,
                 dry_run_wrapper: self.dry_run_wrapper,
+                dry_run_trace: self.dry_run_trace,
                 dump_tx: self.dump_tx,
                 dump_wrapper_tx: self.dump_wrapper_tx,
                 output_folder: self.output_folder,
@@ -7476,6 +9204,19 @@ pub mod args {
                 memo: self.memo,
                 use_device: self.use_device,
                 device_transport: self.device_transport,
+                masp_prover: self.masp_prover,
+                shielded_key_share: self
+                    .shielded_key_share
+                    .map(|path| {
+                        std::fs::read(path).map_err(|e| {
+                            std::io::Error::new(
+                                std::io::ErrorKind::InvalidInput,
+                                format!("Error reading key share file: {}", e),
+                            )
+                        })
+                    })
+                    .transpose()?,
+                remote_signer: self.remote_signer,
             })
         }
     }
@@ -7498,6 +9239,13 @@ pub mod args {
                     ))
                     .conflicts_with(DRY_RUN_TX.name),
             )
+            .arg(DRY_RUN_TRACE.def().help(wrap!(
+                "Trace the execution of the inner transactions when \
+                 simulating the transaction application with `--dry-run` or \
+                 `--dry-run-wrapper`. The trace shows the host functions \
+                 called, the storage keys accessed, the gas consumed and the \
+                 verdicts of the validity predicates."
+            )))
             .arg(
                 DUMP_TX
                     .def()
@@ -7617,11 +9365,40 @@ pub mod args {
                     .def()
                     .help(wrap!("Attach a plaintext memo to the transaction.")),
             )
+            .arg(MASP_PROVER.def().help(wrap!(
+                "Address of a `namada-masp-prover` server used to generate \
+                 the proofs of shielded transactions. Spending keys never \
+                 leave the client, which still signs the transaction. If \
+                 absent, the proofs are generated locally."
+            )))
+            .arg(
+                SHIELDED_KEY_SHARE_OPT
+                    .def()
+                    .help(wrap!(
+                        "The file path containing a shielded multisig key \
+                         share. The spends of the multisig are left \
+                         unauthorized, and a request for its participants to \
+                         authorize them is dumped along with the transaction."
+                    ))
+                    .requires(DUMP_TX.name),
+            )
+            .arg(
+                REMOTE_SIGNER
+                    .def()
+                    .help(wrap!(
+                        "Address of a remote signer to sign the transaction \
+                         with the keys that are not in the wallet, either as \
+                         \"tcp://{host}:{port}\" or as \"unix://{socket \
+                         path}\"."
+                    ))
+                    .conflicts_with(USE_DEVICE.name),
+            )
         }
 
         fn parse(matches: &ArgMatches) -> Self {
             let dry_run = DRY_RUN_TX.parse(matches);
             let dry_run_wrapper = DRY_RUN_WRAPPER_TX.parse(matches);
+            let dry_run_trace = DRY_RUN_TRACE.parse(matches);
             let dump_tx = DUMP_TX.parse(matches);
             let dump_wrapper_tx = DUMP_WRAPPER_TX.parse(matches);
             let force = FORCE.parse(matches);
@@ -7654,9 +9431,13 @@ pub mod args {
                 }
             };
             let device_transport = DEVICE_TRANSPORT.parse(matches);
+            let masp_prover = MASP_PROVER.parse(matches);
+            let shielded_key_share = SHIELDED_KEY_SHARE_OPT.parse(matches);
+            let remote_signer = REMOTE_SIGNER.parse(matches);
             Self {
                 dry_run,
                 dry_run_wrapper,
+                dry_run_trace,
                 dump_tx,
                 dump_wrapper_tx,
                 force,
@@ -7679,6 +9460,9 @@ pub mod args {
                 memo,
                 use_device,
                 device_transport,
+                masp_prover,
+                shielded_key_share,
+                remote_signer,
             }
         }
     }
@@ -7817,6 +9601,7 @@ pub mod args {
             let use_device = USE_DEVICE.parse(matches);
             let device_transport = DEVICE_TRANSPORT.parse(matches);
             let ledger_zip32 = LEDGER_ZIP32.parse(matches);
+            let from_shares = FROM_SHARES.parse(matches);
             Self {
                 scheme,
                 shielded,
@@ -7830,6 +9615,7 @@ pub mod args {
                 use_device,
                 device_transport,
                 birthday,
+                from_shares,
             }
         }
 
@@ -7894,13 +9680,21 @@ pub mod args {
             .arg(HD_PROMPT_BIP39_PASSPHRASE.def().help(wrap!(
                 "Use an additional passphrase for HD-key generation."
             )))
+            .arg(LEDGER_ZIP32.def().requires(SHIELDED.name).help(wrap!(
+                "Use the modified ZIP 32 algorithm supported by Ledger \
+                 devices. This flag is necessary if importing the generated \
+                 mnemonic code onto the Ledger device at some future time is \
+                 a requirement."
+            )))
             .arg(
-                LEDGER_ZIP32.def().requires(SHIELDED.name).help(wrap!(
-                    "Use the modified ZIP 32 algorithm supported by Ledger \
-                     devices. This flag is necessary if importing the \
-                     generated mnemonic code onto the Ledger device at some \
-                     future time is a requirement."
-                )),
+                FROM_SHARES
+                    .def()
+                    .conflicts_with_all([USE_DEVICE.name, LEDGER_ZIP32.name])
+                    .help(wrap!(
+                        "Recover the HD seed from SLIP-39 share mnemonics \
+                         instead of a BIP39 mnemonic code. The shares are \
+                         read one per line until an empty line."
+                    )),
             )
         }
     }
@@ -7919,6 +9713,8 @@ pub mod args {
                 HD_ALLOW_NON_COMPLIANT_DERIVATION_PATH.parse(matches);
             let prompt_bip39_passphrase =
                 HD_PROMPT_BIP39_PASSPHRASE.parse(matches);
+            let shares = SHARES.parse(matches);
+            let share_group_threshold = SHARE_GROUP_THRESHOLD.parse(matches);
             Self {
                 scheme,
                 shielded,
@@ -7930,6 +9726,8 @@ pub mod args {
                 derivation_path,
                 allow_non_compliant,
                 prompt_bip39_passphrase,
+                shares,
+                share_group_threshold,
             }
         }
 
@@ -7993,6 +9791,22 @@ pub mod args {
             .arg(HD_PROMPT_BIP39_PASSPHRASE.def().help(wrap!(
                 "Use an additional passphrase for HD-key generation."
             )))
+            .arg(SHARES.def().conflicts_with(RAW_KEY_GEN.name).help(wrap!(
+                "Split the HD seed into SLIP-39 share mnemonics instead of \
+                 generating a BIP39 mnemonic code. Takes comma separated \
+                 groups of shares, each written as <threshold>-of-<count>, \
+                 e.g. `2-of-3,3-of-5`."
+            )))
+            .arg(
+                SHARE_GROUP_THRESHOLD
+                    .def()
+                    .requires(SHARES.name)
+                    .help(wrap!(
+                        "The number of groups of shares required to recover \
+                         the HD seed. If none is provided, all the groups are \
+                         required."
+                    )),
+            )
         }
     }
 
@@ -8191,6 +10005,24 @@ pub mod args {
         }
     }
 
+    impl Args for StoreDecrypt {
+        fn parse(matches: &ArgMatches) -> Self {
+            let do_it = DO_IT.parse(matches);
+            Self { do_it }
+        }
+
+        fn def(app: App) -> App {
+            app.arg(
+                DO_IT
+                    .def()
+                    .help(wrap!(
+                        "Confirm saving the wallet store in plaintext."
+                    ))
+                    .required(true),
+            )
+        }
+    }
+
     impl Args for KeyExport {
         fn parse(matches: &ArgMatches) -> Self {
             let alias = ALIAS.parse(matches);
@@ -8206,46 +10038,312 @@ pub mod args {
         }
     }
 
-    impl Args for KeyConvert {
+    impl Args for KeyConvert {
+        fn parse(matches: &ArgMatches) -> Self {
+            let alias = ALIAS.parse(matches);
+            Self { alias }
+        }
+
+        fn def(app: App) -> App {
+            app.arg(
+                ALIAS
+                    .def()
+                    .help(wrap!("The alias of the key you wish to export.")),
+            )
+        }
+    }
+
+    impl Args for KeyImport {
+        fn parse(matches: &ArgMatches) -> Self {
+            let file_path = FILE_PATH.parse(matches);
+            let alias = ALIAS.parse(matches);
+            let alias_force = ALIAS_FORCE.parse(matches);
+            let unsafe_dont_encrypt = UNSAFE_DONT_ENCRYPT.parse(matches);
+            Self {
+                alias,
+                alias_force,
+                file_path,
+                unsafe_dont_encrypt,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.arg(FILE_PATH.def().help(wrap!(
+                "Path to the file containing the key you wish to import."
+            )))
+            .arg(ALIAS.def().help(wrap!("The alias assigned to the.")))
+            .arg(ALIAS_FORCE.def().help(wrap!(
+                "An alias to be associated with the imported entry."
+            )))
+            .arg(UNSAFE_DONT_ENCRYPT.def().help(wrap!(
+                "UNSAFE: Do not encrypt the imported keys. Do not use this \
+                 for keys used in a live network."
+            )))
+        }
+    }
+
+    impl Args for WatchOnlyAdd {
+        fn parse(matches: &ArgMatches) -> Self {
+            let alias = ALIAS.parse(matches);
+            let alias_force = ALIAS_FORCE.parse(matches);
+            let address = RAW_ADDRESS.parse(matches);
+            let public_keys = RAW_PUBLIC_KEYS.parse(matches);
+            let viewing_key = RAW_VIEWING_KEY_OPT.parse(matches);
+            let birthday = BIRTHDAY.parse(matches);
+            Self {
+                alias,
+                alias_force,
+                address,
+                public_keys,
+                viewing_key,
+                birthday,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.arg(
+                ALIAS
+                    .def()
+                    .help(wrap!("An alias to be associated with the account.")),
+            )
+            .arg(ALIAS_FORCE.def().help(wrap!(
+                "Override the alias without confirmation if it already exists."
+            )))
+            .arg(
+                RAW_ADDRESS
+                    .def()
+                    .help(wrap!("The address of the account to watch.")),
+            )
+            .arg(RAW_PUBLIC_KEYS.def().help(wrap!(
+                "A comma separated list of the public keys of the account."
+            )))
+            .arg(RAW_VIEWING_KEY_OPT.def().help(wrap!(
+                "The viewing key used to track the shielded balance of the \
+                 account."
+            )))
+            .arg(BIRTHDAY.def().help(wrap!(
+                "A block height after which the viewing key was created."
+            )))
+        }
+    }
+
+    impl Args for AddressBookAdd {
+        fn parse(matches: &ArgMatches) -> Self {
+            let alias = ALIAS.parse(matches);
+            let alias_force = ALIAS_FORCE.parse(matches);
+            let address = CONTACT_ADDRESS.parse(matches);
+            let label = CONTACT_LABEL.parse(matches);
+            let tags = CONTACT_TAGS.parse(matches);
+            let note = CONTACT_NOTE.parse(matches);
+            let fee_token = CONTACT_FEE_TOKEN.parse(matches);
+            Self {
+                alias,
+                alias_force,
+                address,
+                label,
+                tags,
+                note,
+                fee_token,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.arg(
+                ALIAS
+                    .def()
+                    .help(wrap!("An alias to be associated with the contact.")),
+            )
+            .arg(ALIAS_FORCE.def().help(wrap!(
+                "Override the alias without confirmation if it already exists."
+            )))
+            .arg(CONTACT_ADDRESS.def().help(wrap!(
+                "The transparent address or shielded payment address of the \
+                 contact."
+            )))
+            .arg(
+                CONTACT_LABEL
+                    .def()
+                    .help(wrap!("A human readable label of the contact.")),
+            )
+            .arg(CONTACT_TAGS.def().help(wrap!(
+                "A comma separated list of tags to group the contact by."
+            )))
+            .arg(CONTACT_NOTE.def().help(wrap!("A note about the contact.")))
+            .arg(CONTACT_FEE_TOKEN.def().help(wrap!(
+                "The token that the contact prefers to pay fees with."
+            )))
+        }
+    }
+
+    impl Args for AddressBookList {
+        fn parse(matches: &ArgMatches) -> Self {
+            let tag = CONTACT_TAG.parse(matches);
+            Self { tag }
+        }
+
+        fn def(app: App) -> App {
+            app.arg(
+                CONTACT_TAG
+                    .def()
+                    .help(wrap!("Only list the contacts with the given tag.")),
+            )
+        }
+    }
+
+    impl Args for BookExport {
+        fn parse(matches: &ArgMatches) -> Self {
+            let watch_only = WATCH_ONLY.parse(matches);
+            let format = BOOK_FORMAT.parse(matches);
+            let file_path = FILE_PATH.parse(matches);
+            Self {
+                watch_only,
+                format,
+                file_path,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.arg(WATCH_ONLY.def().help(wrap!(
+                "Export the watch-only accounts instead of the address book."
+            )))
+            .arg(BOOK_FORMAT.def().help(wrap!(
+                "The format of the file, either `json` or `csv`. Defaults to \
+                 `json`."
+            )))
+            .arg(FILE_PATH.def().help(wrap!("Path of the file to write.")))
+        }
+    }
+
+    impl Args for BookImport {
+        fn parse(matches: &ArgMatches) -> Self {
+            let watch_only = WATCH_ONLY.parse(matches);
+            let format = BOOK_FORMAT.parse(matches);
+            let file_path = FILE_PATH.parse(matches);
+            let alias_force = ALIAS_FORCE.parse(matches);
+            Self {
+                watch_only,
+                format,
+                file_path,
+                alias_force,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.arg(WATCH_ONLY.def().help(wrap!(
+                "Import watch-only accounts instead of address book entries."
+            )))
+            .arg(BOOK_FORMAT.def().help(wrap!(
+                "The format of the file, either `json` or `csv`. Defaults to \
+                 `json`."
+            )))
+            .arg(FILE_PATH.def().help(wrap!("Path of the file to read.")))
+            .arg(
+                ALIAS_FORCE.def().help(wrap!(
+                    "Override existing aliases without confirmation."
+                )),
+            )
+        }
+    }
+
+    impl Args for KeyPolicySet {
+        fn parse(matches: &ArgMatches) -> Self {
+            let alias = ALIAS.parse(matches);
+            let daily_limits = DAILY_LIMITS.parse(matches);
+            let allowed_targets = ALLOWED_TARGETS.parse(matches);
+            let require_confirmation = REQUIRE_CONFIRMATION.parse(matches);
+            let remove = REMOVE_POLICY.parse(matches);
+            Self {
+                alias,
+                daily_limits,
+                allowed_targets,
+                require_confirmation,
+                remove,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.arg(
+                ALIAS
+                    .def()
+                    .help(wrap!("The alias of the key to set the policy of.")),
+            )
+            .arg(DAILY_LIMITS.def().help(wrap!(
+                "A comma separated list of daily spending limits of the form \
+                 <token>=<amount>. The limits apply per UTC day."
+            )))
+            .arg(ALLOWED_TARGETS.def().help(wrap!(
+                "A comma separated list of the only addresses that the key \
+                 may send tokens or delegations to."
+            )))
+            .arg(
+                REQUIRE_CONFIRMATION
+                    .def()
+                    .help(wrap!("Require confirming every use of the key.")),
+            )
+            .arg(
+                REMOVE_POLICY
+                    .def()
+                    .help(wrap!("Remove the policy of the key."))
+                    .conflicts_with_all([
+                        DAILY_LIMITS.name,
+                        ALLOWED_TARGETS.name,
+                        REQUIRE_CONFIRMATION.name,
+                    ]),
+            )
+        }
+    }
+
+    impl Args for KeyUsageList {
+        fn parse(matches: &ArgMatches) -> Self {
+            let alias = ALIAS_OPT.parse(matches);
+            Self { alias }
+        }
+
+        fn def(app: App) -> App {
+            app.arg(ALIAS_OPT.def().help(wrap!(
+                "Only list the usages of the key with the given alias."
+            )))
+        }
+    }
+
+    impl Args for DisposableKeysPrune {
         fn parse(matches: &ArgMatches) -> Self {
-            let alias = ALIAS.parse(matches);
-            Self { alias }
+            let lifetime = DISPOSABLE_KEY_LIFETIME.parse(matches);
+            Self { lifetime }
         }
 
         fn def(app: App) -> App {
-            app.arg(
-                ALIAS
-                    .def()
-                    .help(wrap!("The alias of the key you wish to export.")),
-            )
+            app.arg(DISPOSABLE_KEY_LIFETIME.def().help(wrap!(
+                "Set the lifetime of the disposable keys of the wallet, in \
+                 seconds, before pruning. The lifetime defaults to one week."
+            )))
         }
     }
 
-    impl Args for KeyImport {
+    impl Args for DisposableKeysRestore {
         fn parse(matches: &ArgMatches) -> Self {
-            let file_path = FILE_PATH.parse(matches);
-            let alias = ALIAS.parse(matches);
-            let alias_force = ALIAS_FORCE.parse(matches);
-            let unsafe_dont_encrypt = UNSAFE_DONT_ENCRYPT.parse(matches);
+            let count = DISPOSABLE_KEY_COUNT.parse(matches);
+            let from_shares = FROM_SHARES.parse(matches);
+            let prompt_bip39_passphrase =
+                HD_PROMPT_BIP39_PASSPHRASE.parse(matches);
             Self {
-                alias,
-                alias_force,
-                file_path,
-                unsafe_dont_encrypt,
+                count,
+                from_shares,
+                prompt_bip39_passphrase,
             }
         }
 
         fn def(app: App) -> App {
-            app.arg(FILE_PATH.def().help(wrap!(
-                "Path to the file containing the key you wish to import."
+            app.arg(DISPOSABLE_KEY_COUNT.def().help(wrap!(
+                "The number of disposable keys to derive, starting from the \
+                 first one."
             )))
-            .arg(ALIAS.def().help(wrap!("The alias assigned to the.")))
-            .arg(ALIAS_FORCE.def().help(wrap!(
-                "An alias to be associated with the imported entry."
+            .arg(FROM_SHARES.def().help(wrap!(
+                "Recover the HD seed from SLIP-39 shares instead of a BIP39 \
+                 mnemonic code."
             )))
-            .arg(UNSAFE_DONT_ENCRYPT.def().help(wrap!(
-                "UNSAFE: Do not encrypt the imported keys. Do not use this \
-                 for keys used in a live network."
+            .arg(HD_PROMPT_BIP39_PASSPHRASE.def().help(wrap!(
+                "Use an additional passphrase for HD-key generation."
             )))
         }
     }
@@ -8384,6 +10482,335 @@ pub mod args {
         }
     }
 
+    #[derive(Clone, Debug)]
+    pub struct InitShieldedMultisig {
+        pub threshold: u16,
+        pub participants: u16,
+        pub output_folder_path: Option<PathBuf>,
+    }
+
+    impl Args for InitShieldedMultisig {
+        fn parse(matches: &ArgMatches) -> Self {
+            let threshold = SPEND_AUTH_THRESHOLD.parse(matches);
+            let participants = PARTICIPANTS.parse(matches);
+            let output_folder_path = OUTPUT_FOLDER_PATH.parse(matches);
+
+            Self {
+                threshold,
+                participants,
+                output_folder_path,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.arg(SPEND_AUTH_THRESHOLD.def().help(wrap!(
+                "The minimum number of participants required to authorize a \
+                 spend."
+            )))
+            .arg(
+                PARTICIPANTS
+                    .def()
+                    .help(wrap!("The number of participants of the multisig.")),
+            )
+            .arg(
+                OUTPUT_FOLDER_PATH
+                    .def()
+                    .help(wrap!("Folder to where serialize the key shares.")),
+            )
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct CommitShieldedSpends {
+        pub request: PathBuf,
+        pub key_share: PathBuf,
+        pub output_folder_path: Option<PathBuf>,
+    }
+
+    impl Args for CommitShieldedSpends {
+        fn parse(matches: &ArgMatches) -> Self {
+            let request = SPEND_AUTH_REQUEST.parse(matches);
+            let key_share = SHIELDED_KEY_SHARE.parse(matches);
+            let output_folder_path = OUTPUT_FOLDER_PATH.parse(matches);
+
+            Self {
+                request,
+                key_share,
+                output_folder_path,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.arg(
+                SPEND_AUTH_REQUEST.def().help(wrap!(
+                    "The path to the spend authorization request."
+                )),
+            )
+            .arg(
+                SHIELDED_KEY_SHARE.def().help(wrap!(
+                    "The path to the key share of the participant."
+                )),
+            )
+            .arg(OUTPUT_FOLDER_PATH.def().help(wrap!(
+                "Folder to where serialize the nonces and commitments."
+            )))
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct SignShieldedSpends {
+        pub request: PathBuf,
+        pub key_share: PathBuf,
+        pub nonces: PathBuf,
+        pub commitments: Vec<PathBuf>,
+        pub output_folder_path: Option<PathBuf>,
+    }
+
+    impl Args for SignShieldedSpends {
+        fn parse(matches: &ArgMatches) -> Self {
+            let request = SPEND_AUTH_REQUEST.parse(matches);
+            let key_share = SHIELDED_KEY_SHARE.parse(matches);
+            let nonces = SPEND_AUTH_NONCES.parse(matches);
+            let commitments = SPEND_AUTH_COMMITMENTS.parse(matches);
+            let output_folder_path = OUTPUT_FOLDER_PATH.parse(matches);
+
+            Self {
+                request,
+                key_share,
+                nonces,
+                commitments,
+                output_folder_path,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.arg(
+                SPEND_AUTH_REQUEST.def().help(wrap!(
+                    "The path to the spend authorization request."
+                )),
+            )
+            .arg(
+                SHIELDED_KEY_SHARE.def().help(wrap!(
+                    "The path to the key share of the participant."
+                )),
+            )
+            .arg(SPEND_AUTH_NONCES.def().help(wrap!(
+                "The path to the nonces of the participant. These must not be \
+                 used more than once."
+            )))
+            .arg(SPEND_AUTH_COMMITMENTS.def().help(wrap!(
+                "The paths to the commitments of all the signing \
+                 participants, including this one."
+            )))
+            .arg(
+                OUTPUT_FOLDER_PATH.def().help(wrap!(
+                    "Folder to where serialize the signature shares."
+                )),
+            )
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct AggregateShieldedSpends {
+        pub request: PathBuf,
+        pub key_share: PathBuf,
+        pub commitments: Vec<PathBuf>,
+        pub signature_shares: Vec<PathBuf>,
+        pub output_folder_path: Option<PathBuf>,
+    }
+
+    impl Args for AggregateShieldedSpends {
+        fn parse(matches: &ArgMatches) -> Self {
+            let request = SPEND_AUTH_REQUEST.parse(matches);
+            let key_share = SHIELDED_KEY_SHARE.parse(matches);
+            let commitments = SPEND_AUTH_COMMITMENTS.parse(matches);
+            let signature_shares = SPEND_AUTH_SIGNATURE_SHARES.parse(matches);
+            let output_folder_path = OUTPUT_FOLDER_PATH.parse(matches);
+
+            Self {
+                request,
+                key_share,
+                commitments,
+                signature_shares,
+                output_folder_path,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.arg(
+                SPEND_AUTH_REQUEST.def().help(wrap!(
+                    "The path to the spend authorization request."
+                )),
+            )
+            .arg(
+                SHIELDED_KEY_SHARE.def().help(wrap!(
+                    "The path to the key share of any participant."
+                )),
+            )
+            .arg(SPEND_AUTH_COMMITMENTS.def().help(wrap!(
+                "The paths to the commitments of all the signing participants."
+            )))
+            .arg(SPEND_AUTH_SIGNATURE_SHARES.def().help(wrap!(
+                "The paths to the signature shares of all the signing \
+                 participants."
+            )))
+            .arg(OUTPUT_FOLDER_PATH.def().help(wrap!(
+                "Folder to where serialize the authorized transaction."
+            )))
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct CreatePartialTx<C: NamadaTypes = SdkTypes> {
+        pub query: Query<C>,
+        pub tx_path: PathBuf,
+        pub owner: C::Address,
+        pub output_folder_path: Option<PathBuf>,
+    }
+
+    impl Args for CreatePartialTx<CliTypes> {
+        fn parse(matches: &ArgMatches) -> Self {
+            let query = Query::parse(matches);
+            let tx_path = TX_PATH.parse(matches);
+            let owner = OWNER.parse(matches);
+            let output_folder_path = OUTPUT_FOLDER_PATH.parse(matches);
+
+            Self {
+                query,
+                tx_path,
+                owner,
+                output_folder_path,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Query<CliTypes>>()
+                .arg(
+                    TX_PATH
+                        .def()
+                        .help(wrap!("The path to the serialized transaction.")),
+                )
+                .arg(OWNER.def().help(wrap!(
+                    "The address of the account on whose behalf the \
+                     transaction is signed."
+                )))
+                .arg(OUTPUT_FOLDER_PATH.def().help(wrap!(
+                    "Folder to where serialize the partially signed \
+                     transaction."
+                )))
+        }
+    }
+
+    impl CliToSdk<CreatePartialTx<SdkTypes>> for CreatePartialTx<CliTypes> {
+        type Error = std::convert::Infallible;
+
+        fn to_sdk(
+            self,
+            ctx: &mut Context,
+        ) -> Result<CreatePartialTx<SdkTypes>, Self::Error> {
+            Ok(CreatePartialTx::<SdkTypes> {
+                query: self.query.to_sdk(ctx)?,
+                tx_path: self.tx_path,
+                owner: ctx.borrow_chain_or_exit().get(&self.owner),
+                output_folder_path: self.output_folder_path,
+            })
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct PartialTx {
+        pub path: PathBuf,
+    }
+
+    impl Args for PartialTx {
+        fn parse(matches: &ArgMatches) -> Self {
+            let path = PARTIAL_TX.parse(matches);
+            Self { path }
+        }
+
+        fn def(app: App) -> App {
+            app.arg(
+                PARTIAL_TX.def().help(wrap!(
+                    "The path to the partially signed transaction."
+                )),
+            )
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct AddPartialSignatures {
+        pub path: PathBuf,
+        pub signatures: Vec<PathBuf>,
+        pub wrapper_signature: Option<PathBuf>,
+    }
+
+    impl Args for AddPartialSignatures {
+        fn parse(matches: &ArgMatches) -> Self {
+            let path = PARTIAL_TX.parse(matches);
+            let signatures = SIGNATURES.parse(matches);
+            let wrapper_signature = WRAPPER_SIGNATURE_OPT.parse(matches);
+
+            Self {
+                path,
+                signatures,
+                wrapper_signature,
+            }
+        }
+
+        fn def(app: App) -> App {
+            app.arg(PARTIAL_TX.def().help(wrap!(
+                "The path to the partially signed transaction. The collected \
+                 signatures are written back to it."
+            )))
+            .arg(SIGNATURES.def().help(wrap!(
+                "List of file paths containing the serialized signatures \
+                 produced by sign-offline."
+            )))
+            .arg(WRAPPER_SIGNATURE_OPT.def().help(wrap!(
+                "The file path containing the serialized signature of the fee \
+                 payer over the wrapper. It can only be added once the \
+                 threshold is met, after which the other signatures can no \
+                 longer change."
+            )))
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct SubmitPartialTx<C: NamadaTypes = SdkTypes> {
+        pub query: Query<C>,
+        pub path: PathBuf,
+    }
+
+    impl Args for SubmitPartialTx<CliTypes> {
+        fn parse(matches: &ArgMatches) -> Self {
+            let query = Query::parse(matches);
+            let path = PARTIAL_TX.parse(matches);
+            Self { query, path }
+        }
+
+        fn def(app: App) -> App {
+            app.add_args::<Query<CliTypes>>().arg(
+                PARTIAL_TX.def().help(wrap!(
+                    "The path to the partially signed transaction."
+                )),
+            )
+        }
+    }
+
+    impl CliToSdk<SubmitPartialTx<SdkTypes>> for SubmitPartialTx<CliTypes> {
+        type Error = std::convert::Infallible;
+
+        fn to_sdk(
+            self,
+            ctx: &mut Context,
+        ) -> Result<SubmitPartialTx<SdkTypes>, Self::Error> {
+            Ok(SubmitPartialTx::<SdkTypes> {
+                query: self.query.to_sdk(ctx)?,
+                path: self.path,
+            })
+        }
+    }
+
     #[derive(Clone, Debug)]
     pub struct DefaultBaseDir {}
 
@@ -9031,6 +11458,32 @@ pub fn namada_wallet_app() -> App {
     cmds::NamadaWallet::add_sub(args::Global::def(app))
 }
 
+pub fn namada_masp_prover_cli() -> args::MaspProverServer {
+    let matches = namada_masp_prover_app().get_matches();
+    args::MaspProverServer::parse(&matches)
+}
+
+pub fn namada_masp_prover_app() -> App {
+    let app = App::new(APP_NAME)
+        .version(namada_version())
+        .about("Namada MASP prover server.")
+        .color(ColorChoice::Auto);
+    args::MaspProverServer::def(app)
+}
+
+pub fn namada_signer_cli() -> args::RemoteSignerServer {
+    let matches = namada_signer_app().get_matches();
+    args::RemoteSignerServer::parse(&matches)
+}
+
+pub fn namada_signer_app() -> App {
+    let app = App::new(APP_NAME)
+        .version(namada_version())
+        .about("Namada reference remote signer.")
+        .color(ColorChoice::Auto);
+    args::RemoteSignerServer::def(app)
+}
+
 pub fn namada_relayer_app() -> App {
     let app = App::new(APP_NAME)
         .version(namada_version())
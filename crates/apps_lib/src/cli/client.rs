@@ -237,6 +237,18 @@ impl CliApi {
                         let namada = ctx.to_sdk(client, io);
                         tx::submit_reveal_pk(&namada, args).await?;
                     }
+                    Sub::TxSweepDisposable(TxSweepDisposable(args)) => {
+                        let chain_ctx = ctx.borrow_mut_chain_or_exit();
+                        let ledger_address =
+                            chain_ctx.get(&args.tx.ledger_address);
+                        let client = client.unwrap_or_else(|| {
+                            C::from_tendermint_address(&ledger_address)
+                        });
+                        client.wait_until_node_is_synced(&io).await?;
+                        let args = args.to_sdk(&mut ctx)?;
+                        let namada = ctx.to_sdk(client, io);
+                        tx::submit_sweep_disposable(&namada, args).await?;
+                    }
                     Sub::Bond(Bond(args)) => {
                         let chain_ctx = ctx.borrow_mut_chain_or_exit();
                         let ledger_address =
@@ -343,7 +355,12 @@ impl CliApi {
                         let client = client.unwrap_or_else(|| {
                             C::from_tendermint_address(&args.ledger_address)
                         });
-                        if args.with_indexer.is_none() {
+                        if let Some(path) = &args.with_snapshot {
+                            crate::client::masp::check_snapshot_chain_id(
+                                path,
+                                &chain_ctx.config.ledger.chain_id,
+                            )?;
+                        } else if args.with_indexer.is_none() {
                             client.wait_until_node_is_synced(&io).await?;
                         }
                         args.viewing_keys.extend(
@@ -370,6 +387,23 @@ impl CliApi {
                         )
                         .await?;
                     }
+                    Sub::ExportMaspSnapshot(ExportMaspSnapshot(args)) => {
+                        let args = args.to_sdk(&mut ctx)?;
+                        let chain_ctx = ctx.take_chain_or_exit();
+                        let client = client.unwrap_or_else(|| {
+                            C::from_tendermint_address(&args.ledger_address)
+                        });
+                        if args.with_indexer.is_none() {
+                            client.wait_until_node_is_synced(&io).await?;
+                        }
+                        crate::client::masp::export_snapshot(
+                            client,
+                            chain_ctx.config.ledger.chain_id,
+                            args,
+                            &io,
+                        )
+                        .await?;
+                    }
                     Sub::GenIbcShieldingTransfer(GenIbcShieldingTransfer(
                         args,
                     )) => {
@@ -574,6 +608,20 @@ impl CliApi {
                         let namada = ctx.to_sdk(client, io);
                         rpc::query_rewards_estimate(&namada, args).await;
                     }
+                    Sub::QueryShieldingRewardsProjection(
+                        QueryShieldingRewardsProjection(args),
+                    ) => {
+                        let chain_ctx = ctx.borrow_mut_chain_or_exit();
+                        let ledger_address =
+                            chain_ctx.get(&args.query.ledger_address);
+                        let client = client.unwrap_or_else(|| {
+                            C::from_tendermint_address(&ledger_address)
+                        });
+                        client.wait_until_node_is_synced(&io).await?;
+                        let args = args.to_sdk(&mut ctx)?;
+                        let namada = ctx.to_sdk(client, io);
+                        rpc::query_rewards_projection(&namada, args).await;
+                    }
                     Sub::QueryBonds(QueryBonds(args)) => {
                         let chain_ctx = ctx.borrow_mut_chain_or_exit();
                         let ledger_address =
@@ -846,6 +894,53 @@ impl CliApi {
                         let args = args.to_sdk(&mut ctx)?;
                         utils::sign_offline(args).await
                     }
+                    ClientUtils::InitShieldedMultisig(
+                        InitShieldedMultisig(args),
+                    ) => utils::init_shielded_multisig(args),
+                    ClientUtils::CommitShieldedSpends(
+                        CommitShieldedSpends(args),
+                    ) => utils::commit_shielded_spends(args),
+                    ClientUtils::SignShieldedSpends(SignShieldedSpends(
+                        args,
+                    )) => utils::sign_shielded_spends(args),
+                    ClientUtils::AggregateShieldedSpends(
+                        AggregateShieldedSpends(args),
+                    ) => utils::aggregate_shielded_spends(args),
+                    ClientUtils::CreatePartialTx(CreatePartialTx(args)) => {
+                        let mut ctx = cli::Context::new::<IO>(global_args)
+                            .expect("expected to construct a context");
+                        let chain_ctx = ctx.borrow_mut_chain_or_exit();
+                        let ledger_address =
+                            chain_ctx.get(&args.query.ledger_address);
+                        let client =
+                            C::from_tendermint_address(&ledger_address);
+                        client.wait_until_node_is_synced(&io).await?;
+                        let args = args.to_sdk(&mut ctx)?;
+                        let namada = ctx.to_sdk(client, io);
+                        utils::create_partial_tx(&namada, args).await
+                    }
+                    ClientUtils::InspectPartialTx(InspectPartialTx(args)) => {
+                        utils::inspect_partial_tx(args)
+                    }
+                    ClientUtils::AddPartialSignatures(
+                        AddPartialSignatures(args),
+                    ) => utils::add_partial_signatures(args),
+                    ClientUtils::VerifyPartialTx(VerifyPartialTx(args)) => {
+                        utils::verify_partial_tx(args)
+                    }
+                    ClientUtils::SubmitPartialTx(SubmitPartialTx(args)) => {
+                        let mut ctx = cli::Context::new::<IO>(global_args)
+                            .expect("expected to construct a context");
+                        let chain_ctx = ctx.borrow_mut_chain_or_exit();
+                        let ledger_address =
+                            chain_ctx.get(&args.query.ledger_address);
+                        let client =
+                            C::from_tendermint_address(&ledger_address);
+                        client.wait_until_node_is_synced(&io).await?;
+                        let args = args.to_sdk(&mut ctx)?;
+                        let namada = ctx.to_sdk(client, io);
+                        utils::submit_partial_tx(&namada, args).await
+                    }
                     ClientUtils::DefaultBaseDir(DefaultBaseDir(args)) => {
                         utils::default_base_dir(global_args, args)
                     }
@@ -5,7 +5,7 @@ use std::io::{self, Write};
 use std::str::FromStr;
 
 use color_eyre::eyre::Result;
-use itertools::sorted;
+use itertools::{sorted, Itertools};
 use ledger_namada_rs::{BIP44Path, NamadaApp};
 use namada_core::chain::BlockHeight;
 use namada_core::masp::{ExtendedSpendingKey, MaspValue, PaymentAddress};
@@ -14,10 +14,18 @@ use namada_sdk::borsh::{BorshDeserialize, BorshSerializeExt};
 use namada_sdk::io::{display_line, edisplay_line, Io};
 use namada_sdk::key::*;
 use namada_sdk::masp::find_valid_diversifier;
+use namada_sdk::time::DateTimeUtc;
+use namada_sdk::wallet::address_book::{
+    export_book, import_book, AddressBookEntry, WatchOnlyAccount,
+};
+use namada_sdk::wallet::audit::{self, KeyPolicy};
+use namada_sdk::wallet::slip39::ShareGroups;
 use namada_sdk::wallet::{
     DecryptionError, DerivationPath, DerivationPathError, FindKeyError, Wallet,
+    WalletIo,
 };
 use rand_core::OsRng;
+use zeroize::Zeroizing;
 
 use crate::cli;
 use crate::cli::api::CliApi;
@@ -63,6 +71,39 @@ impl CliApi {
             cmds::NamadaWallet::KeyAddrRemove(
                 cmds::WalletRemoveKeyAddress(args),
             ) => key_address_remove(ctx, io, args),
+            cmds::NamadaWallet::StoreEncrypt(cmds::WalletEncryptStore) => {
+                store_encrypt(ctx, io)
+            }
+            cmds::NamadaWallet::StoreDecrypt(cmds::WalletDecryptStore(
+                args,
+            )) => store_decrypt(ctx, io, args),
+            cmds::NamadaWallet::WatchOnlyAdd(cmds::WalletAddWatchOnly(
+                args,
+            )) => watch_only_add(ctx, io, args),
+            cmds::NamadaWallet::ContactAdd(cmds::WalletAddContact(args)) => {
+                contact_add(ctx, io, args)
+            }
+            cmds::NamadaWallet::ContactList(cmds::WalletListContacts(args)) => {
+                contact_list(ctx, io, args)
+            }
+            cmds::NamadaWallet::BookExport(cmds::WalletExportBook(args)) => {
+                book_export(ctx, io, args)
+            }
+            cmds::NamadaWallet::BookImport(cmds::WalletImportBook(args)) => {
+                book_import(ctx, io, args)
+            }
+            cmds::NamadaWallet::KeyPolicySet(cmds::WalletSetKeyPolicy(
+                args,
+            )) => key_policy_set(ctx, io, args),
+            cmds::NamadaWallet::KeyUsage(cmds::WalletKeyUsage(args)) => {
+                key_usage_list(ctx, io, args)
+            }
+            cmds::NamadaWallet::DisposablePrune(
+                cmds::WalletPruneDisposable(args),
+            ) => disposable_prune(ctx, io, args),
+            cmds::NamadaWallet::DisposableRestore(
+                cmds::WalletRestoreDisposable(args),
+            ) => disposable_restore(ctx, io, args),
             cmds::NamadaWallet::PayAddrGen(cmds::WalletGenPaymentAddress(
                 args,
             )) => {
@@ -196,6 +237,7 @@ async fn shielded_key_derive(
         prompt_bip39_passphrase,
         use_device,
         birthday,
+        from_shares,
         ..
     }: args::KeyDerive,
 ) {
@@ -212,7 +254,26 @@ async fn shielded_key_derive(
         cli::safe_exit(1)
     }
     let alias = alias.to_lowercase();
-    let alias = if !use_device {
+    let alias = if from_shares {
+        let seed = recover_hd_seed_from_shares(io, prompt_bip39_passphrase);
+        let encryption_password =
+            read_and_confirm_encryption_password(unsafe_dont_encrypt);
+        wallet
+            .derive_store_hd_spending_key(
+                alias,
+                alias_force,
+                birthday,
+                &seed,
+                derivation_path,
+                encryption_password,
+            )
+            .unwrap_or_else(|| {
+                edisplay_line!(io, "Failed to derive a key.");
+                display_line!(io, "No changes are persisted. Exiting.");
+                cli::safe_exit(1)
+            })
+            .0
+    } else if !use_device {
         let encryption_password =
             read_and_confirm_encryption_password(unsafe_dont_encrypt);
         wallet
@@ -263,6 +324,8 @@ fn shielded_key_gen(
         allow_non_compliant,
         prompt_bip39_passphrase,
         birthday,
+        shares,
+        share_group_threshold,
         ..
     }: args::KeyGen,
 ) {
@@ -291,16 +354,17 @@ fn shielded_key_gen(
             display_line!(io, "No changes are persisted. Exiting.");
             cli::safe_exit(1)
         }
-        let (_mnemonic, seed) = Wallet::<CliWalletUtils>::gen_hd_seed(
-            None,
-            &mut OsRng,
+        let seed = gen_hd_seed(
+            io,
+            shares,
+            share_group_threshold,
             prompt_bip39_passphrase,
         );
         wallet.derive_store_hd_spending_key(
             alias,
             alias_force,
             birthday,
-            seed,
+            &seed,
             derivation_path,
             password,
         )
@@ -469,6 +533,7 @@ async fn transparent_key_and_address_derive(
         prompt_bip39_passphrase,
         use_device,
         device_transport,
+        from_shares,
         ..
     }: args::KeyDerive,
 ) {
@@ -488,7 +553,26 @@ async fn transparent_key_and_address_derive(
         cli::safe_exit(1)
     }
     let alias = alias.to_lowercase();
-    let alias = if !use_device {
+    let alias = if from_shares {
+        let seed = recover_hd_seed_from_shares(io, prompt_bip39_passphrase);
+        let encryption_password =
+            read_and_confirm_encryption_password(unsafe_dont_encrypt);
+        wallet
+            .derive_store_hd_secret_key(
+                scheme,
+                Some(alias),
+                alias_force,
+                &seed,
+                derivation_path,
+                encryption_password,
+            )
+            .unwrap_or_else(|| {
+                edisplay_line!(io, "Failed to derive a keypair.");
+                display_line!(io, "No changes are persisted. Exiting.");
+                cli::safe_exit(1)
+            })
+            .0
+    } else if !use_device {
         let encryption_password =
             read_and_confirm_encryption_password(unsafe_dont_encrypt);
         wallet
@@ -570,6 +654,8 @@ fn transparent_key_and_address_gen(
         derivation_path,
         allow_non_compliant,
         prompt_bip39_passphrase,
+        shares,
+        share_group_threshold,
         ..
     }: args::KeyGen,
 ) {
@@ -600,16 +686,17 @@ fn transparent_key_and_address_gen(
             display_line!(io, "No changes are persisted. Exiting.");
             cli::safe_exit(1)
         }
-        let (_mnemonic, seed) = Wallet::<CliWalletUtils>::gen_hd_seed(
-            None,
-            &mut OsRng,
+        let seed = gen_hd_seed(
+            io,
+            shares,
+            share_group_threshold,
             prompt_bip39_passphrase,
         );
         wallet.derive_store_hd_secret_key(
             scheme,
             Some(alias),
             alias_force,
-            seed,
+            &seed,
             derivation_path,
             encryption_password,
         )
@@ -630,6 +717,60 @@ fn transparent_key_and_address_gen(
     );
 }
 
+/// Generate a new HD seed, either from a BIP39 mnemonic code or split into
+/// SLIP-39 shares of the given groups
+fn gen_hd_seed(
+    io: &impl Io,
+    shares: Option<ShareGroups>,
+    share_group_threshold: Option<u8>,
+    prompt_bip39_passphrase: bool,
+) -> Zeroizing<Vec<u8>> {
+    match shares {
+        Some(ShareGroups(groups)) => {
+            // By default, all the groups are required
+            let group_threshold = share_group_threshold.unwrap_or_else(|| {
+                u8::try_from(groups.len()).unwrap_or(u8::MAX)
+            });
+            Wallet::<CliWalletUtils>::gen_hd_seed_shares(
+                group_threshold,
+                &groups,
+                None,
+                &mut OsRng,
+                prompt_bip39_passphrase,
+            )
+            .unwrap_or_else(|err| {
+                edisplay_line!(io, "{}", err);
+                display_line!(io, "No changes are persisted. Exiting.");
+                cli::safe_exit(1)
+            })
+        }
+        None => {
+            let (_mnemonic, seed) = Wallet::<CliWalletUtils>::gen_hd_seed(
+                None,
+                &mut OsRng,
+                prompt_bip39_passphrase,
+            );
+            Zeroizing::new(seed.as_bytes().to_vec())
+        }
+    }
+}
+
+/// Recover an HD seed from SLIP-39 shares read from stdin
+fn recover_hd_seed_from_shares(
+    io: &impl Io,
+    prompt_bip39_passphrase: bool,
+) -> Zeroizing<Vec<u8>> {
+    Wallet::<CliWalletUtils>::recover_hd_seed_from_shares(
+        None,
+        prompt_bip39_passphrase,
+    )
+    .unwrap_or_else(|err| {
+        edisplay_line!(io, "{}", err);
+        display_line!(io, "No changes are persisted. Exiting.");
+        cli::safe_exit(1)
+    })
+}
+
 /// Key generation
 fn key_gen(ctx: Context, io: &impl Io, args_key_gen: args::KeyGen) {
     if !args_key_gen.shielded {
@@ -888,6 +1029,449 @@ fn key_address_remove(
     display_line!(io, "Successfully removed alias: \"{}\"", alias);
 }
 
+/// Encrypt the whole wallet store, or change the password of an already
+/// encrypted store.
+fn store_encrypt(ctx: Context, io: &impl Io) {
+    let mut wallet = load_wallet(ctx);
+    let password = CliWalletUtils::read_password(true, None);
+    wallet.encrypt_store(password);
+    wallet.save().unwrap_or_else(|err| {
+        edisplay_line!(io, "{}", err);
+        cli::safe_exit(1)
+    });
+    display_line!(io, "Successfully encrypted the wallet store.");
+}
+
+/// Save an encrypted wallet store in plaintext.
+fn store_decrypt(
+    ctx: Context,
+    io: &impl Io,
+    args::StoreDecrypt { .. }: args::StoreDecrypt,
+) {
+    let mut wallet = load_wallet(ctx);
+    if !wallet.is_store_encrypted() {
+        display_line!(io, "The wallet store is not encrypted.");
+        return;
+    }
+    wallet.decrypt_store();
+    wallet.save().unwrap_or_else(|err| {
+        edisplay_line!(io, "{}", err);
+        cli::safe_exit(1)
+    });
+    display_line!(io, "Successfully decrypted the wallet store.");
+}
+
+/// Add a watch-only account
+fn watch_only_add(
+    ctx: Context,
+    io: &impl Io,
+    args::WatchOnlyAdd {
+        alias,
+        alias_force,
+        address,
+        public_keys,
+        viewing_key,
+        birthday,
+    }: args::WatchOnlyAdd,
+) {
+    let alias = alias.to_lowercase();
+    let mut wallet = load_wallet(ctx);
+    let account = WatchOnlyAccount {
+        address,
+        public_keys,
+        viewing_key,
+        birthday,
+    };
+    if wallet
+        .insert_watch_only(&alias, account, alias_force)
+        .is_none()
+    {
+        edisplay_line!(io, "Watch-only account not added");
+        cli::safe_exit(1);
+    }
+    wallet
+        .save()
+        .unwrap_or_else(|err| edisplay_line!(io, "{}", err));
+    display_line!(
+        io,
+        "Successfully added a watch-only account with alias: \"{}\"",
+        alias
+    );
+}
+
+/// Add an address book entry
+fn contact_add(
+    ctx: Context,
+    io: &impl Io,
+    args::AddressBookAdd {
+        alias,
+        alias_force,
+        address,
+        label,
+        tags,
+        note,
+        fee_token,
+    }: args::AddressBookAdd,
+) {
+    let alias = alias.to_lowercase();
+    let mut wallet = load_wallet(ctx);
+    let entry = AddressBookEntry {
+        address,
+        label,
+        tags: tags.into_iter().collect(),
+        note,
+        fee_token,
+    };
+    if wallet
+        .insert_address_book_entry(&alias, entry, alias_force)
+        .is_none()
+    {
+        edisplay_line!(io, "Contact not added");
+        cli::safe_exit(1);
+    }
+    wallet
+        .save()
+        .unwrap_or_else(|err| edisplay_line!(io, "{}", err));
+    display_line!(io, "Successfully added a contact with alias: \"{}\"", alias);
+}
+
+/// List the address book and the watch-only accounts
+fn contact_list(
+    ctx: Context,
+    io: &impl Io,
+    args::AddressBookList { tag }: args::AddressBookList,
+) {
+    let wallet = load_wallet(ctx);
+    let mut w_lock = io::stdout().lock();
+    let contacts: Vec<_> = wallet
+        .get_address_book()
+        .iter()
+        .filter(|(_, entry)| {
+            tag.as_ref().map_or(true, |tag| entry.tags.contains(tag))
+        })
+        .collect();
+    if contacts.is_empty() {
+        display_line!(
+            io,
+            "No known contacts. Try `add-contact --alias my-contact --address \
+             ...` to add a new contact to the address book.",
+        );
+    } else {
+        display_line!(io, &mut w_lock; "Known contacts:").unwrap();
+        for (alias, entry) in contacts {
+            display_line!(io, &mut w_lock; "  \"{}\": {}", alias, entry.address)
+                .unwrap();
+            if let Some(label) = &entry.label {
+                display_line!(io, &mut w_lock; "    Label: {}", label).unwrap();
+            }
+            if !entry.tags.is_empty() {
+                display_line!(
+                    io,
+                    &mut w_lock;
+                    "    Tags: {}",
+                    entry.tags.iter().join(", ")
+                )
+                .unwrap();
+            }
+            if let Some(note) = &entry.note {
+                display_line!(io, &mut w_lock; "    Note: {}", note).unwrap();
+            }
+            if let Some(fee_token) = &entry.fee_token {
+                display_line!(io, &mut w_lock; "    Fee token: {}", fee_token)
+                    .unwrap();
+            }
+        }
+    }
+    // Watch-only accounts carry no tags
+    if tag.is_some() {
+        return;
+    }
+    let accounts = wallet.get_watch_only_accounts();
+    if accounts.is_empty() {
+        return;
+    }
+    display_line!(io, &mut w_lock; "Watch-only accounts:").unwrap();
+    for (alias, account) in accounts {
+        display_line!(
+            io,
+            &mut w_lock;
+            "  \"{}\": {}",
+            alias,
+            account.address.to_pretty_string()
+        )
+        .unwrap();
+        for public_key in &account.public_keys {
+            display_line!(io, &mut w_lock; "    Public key: {}", public_key)
+                .unwrap();
+        }
+        if let Some(viewing_key) = &account.viewing_key {
+            display_line!(io, &mut w_lock; "    Viewing key: {}", viewing_key)
+                .unwrap();
+        }
+    }
+}
+
+/// Export the address book or the watch-only accounts to a file
+fn book_export(
+    ctx: Context,
+    io: &impl Io,
+    args::BookExport {
+        watch_only,
+        format,
+        file_path,
+    }: args::BookExport,
+) {
+    let wallet = load_wallet(ctx);
+    let data = if watch_only {
+        export_book(wallet.get_watch_only_accounts(), format)
+    } else {
+        export_book(wallet.get_address_book(), format)
+    };
+    std::fs::write(&file_path, data).unwrap_or_else(|err| {
+        edisplay_line!(io, "{}", err);
+        cli::safe_exit(1)
+    });
+    display_line!(io, "Exported to file {}", file_path);
+}
+
+/// Import address book entries or watch-only accounts from a file
+fn book_import(
+    ctx: Context,
+    io: &impl Io,
+    args::BookImport {
+        watch_only,
+        format,
+        file_path,
+        alias_force,
+    }: args::BookImport,
+) {
+    let data = std::fs::read_to_string(file_path).unwrap_or_else(|err| {
+        edisplay_line!(io, "{}", err);
+        display_line!(io, "No changes are persisted. Exiting.");
+        cli::safe_exit(1)
+    });
+    let mut wallet = load_wallet(ctx);
+    let (imported, skipped) = if watch_only {
+        let accounts = import_book::<WatchOnlyAccount>(&data, format)
+            .unwrap_or_else(|err| {
+                edisplay_line!(io, "{}", err);
+                display_line!(io, "No changes are persisted. Exiting.");
+                cli::safe_exit(1)
+            });
+        let total = accounts.len();
+        let imported = accounts
+            .into_iter()
+            .filter_map(|(alias, account)| {
+                wallet.insert_watch_only(
+                    alias.to_string(),
+                    account,
+                    alias_force,
+                )
+            })
+            .count();
+        (imported, total - imported)
+    } else {
+        let entries = import_book::<AddressBookEntry>(&data, format)
+            .unwrap_or_else(|err| {
+                edisplay_line!(io, "{}", err);
+                display_line!(io, "No changes are persisted. Exiting.");
+                cli::safe_exit(1)
+            });
+        let total = entries.len();
+        let imported = entries
+            .into_iter()
+            .filter_map(|(alias, entry)| {
+                wallet.insert_address_book_entry(
+                    alias.to_string(),
+                    entry,
+                    alias_force,
+                )
+            })
+            .count();
+        (imported, total - imported)
+    };
+    wallet
+        .save()
+        .unwrap_or_else(|err| edisplay_line!(io, "{}", err));
+    display_line!(
+        io,
+        "Successfully imported {} entries, skipped {}.",
+        imported,
+        skipped
+    );
+}
+
+/// Set or remove the policy of a key
+fn key_policy_set(
+    ctx: Context,
+    io: &impl Io,
+    args::KeyPolicySet {
+        alias,
+        daily_limits,
+        allowed_targets,
+        require_confirmation,
+        remove,
+    }: args::KeyPolicySet,
+) {
+    let alias = alias.to_lowercase();
+    let mut wallet = load_wallet(ctx);
+    if remove {
+        if wallet.remove_key_policy(&alias).is_none() {
+            display_line!(io, "Key \"{}\" has no policy.", alias);
+            return;
+        }
+    } else {
+        let policy = KeyPolicy {
+            daily_limits: daily_limits
+                .into_iter()
+                .map(|limit| (limit.token, limit.amount))
+                .collect(),
+            allowed_targets: allowed_targets.into_iter().collect(),
+            require_confirmation,
+        };
+        wallet
+            .insert_key_policy(&alias, policy)
+            .unwrap_or_else(|err| {
+                edisplay_line!(io, "{}", err);
+                display_line!(io, "No changes are persisted. Exiting.");
+                cli::safe_exit(1)
+            });
+    }
+    wallet
+        .save()
+        .unwrap_or_else(|err| edisplay_line!(io, "{}", err));
+    display_line!(io, "Successfully updated the policy of key \"{}\"", alias);
+}
+
+/// List the key usage log and verify its hash chain
+fn key_usage_list(
+    ctx: Context,
+    io: &impl Io,
+    args::KeyUsageList { alias }: args::KeyUsageList,
+) {
+    let wallet = load_wallet(ctx);
+    let log = wallet.load_key_usage().unwrap_or_else(|err| {
+        edisplay_line!(io, "{}", err);
+        cli::safe_exit(1)
+    });
+    let signer = alias.map(|alias| {
+        wallet.find_public_key(&alias).unwrap_or_else(|err| {
+            edisplay_line!(io, "{}", err);
+            cli::safe_exit(1)
+        })
+    });
+    let mut w_lock = io::stdout().lock();
+    for record in &log {
+        let usage = &record.usage;
+        if signer
+            .as_ref()
+            .is_some_and(|signer| *signer != usage.signer)
+        {
+            continue;
+        }
+        display_line!(
+            io,
+            &mut w_lock;
+            "{} tx {} signed by {}",
+            usage.timestamp,
+            usage.tx_hash,
+            usage.signer
+        )
+        .unwrap();
+        for action in &usage.actions {
+            display_line!(io, &mut w_lock; "  {}", action).unwrap();
+        }
+        for (token, amount) in &usage.amounts {
+            display_line!(io, &mut w_lock; "  Spent {} of {}", amount, token)
+                .unwrap();
+        }
+    }
+    match audit::verify_log(&log) {
+        Ok(()) => display_line!(
+            io,
+            &mut w_lock;
+            "The key usage log is intact ({} records).",
+            log.len()
+        )
+        .unwrap(),
+        Err(err) => {
+            edisplay_line!(io, "{}", err);
+            cli::safe_exit(1)
+        }
+    }
+}
+
+/// Remove the expired disposable keys from the wallet, optionally setting
+/// their lifetime first
+fn disposable_prune(
+    ctx: Context,
+    io: &impl Io,
+    args::DisposableKeysPrune { lifetime }: args::DisposableKeysPrune,
+) {
+    let mut wallet = load_wallet(ctx);
+    if let Some(lifetime) = lifetime {
+        let lifetime = i64::try_from(lifetime).unwrap_or_else(|_| {
+            edisplay_line!(io, "The lifetime {} is too large.", lifetime);
+            display_line!(io, "No changes are persisted. Exiting.");
+            cli::safe_exit(1)
+        });
+        wallet.set_disposable_key_lifetime(lifetime);
+    }
+    #[allow(clippy::disallowed_methods)]
+    let current_unix_timestamp = DateTimeUtc::now().to_unix_timestamp();
+    let pruned = wallet.prune_disposable_keys(current_unix_timestamp);
+    wallet
+        .save()
+        .unwrap_or_else(|err| edisplay_line!(io, "{}", err));
+    for alias in &pruned {
+        display_line!(io, "Removed the disposable key \"{}\".", alias);
+    }
+    display_line!(
+        io,
+        "Pruned {} disposable keys older than {} seconds.",
+        pruned.len(),
+        wallet.disposable_key_lifetime()
+    );
+}
+
+/// Restore the disposable keys derived from an HD seed
+fn disposable_restore(
+    ctx: Context,
+    io: &impl Io,
+    args::DisposableKeysRestore {
+        count,
+        from_shares,
+        prompt_bip39_passphrase,
+    }: args::DisposableKeysRestore,
+) {
+    let mut wallet = load_wallet(ctx);
+    let seed = if from_shares {
+        recover_hd_seed_from_shares(io, prompt_bip39_passphrase)
+    } else {
+        let seed =
+            Wallet::<CliWalletUtils>::read_hd_seed(prompt_bip39_passphrase)
+                .unwrap_or_else(|| {
+                    edisplay_line!(io, "Failed to read the mnemonic code.");
+                    display_line!(io, "No changes are persisted. Exiting.");
+                    cli::safe_exit(1)
+                });
+        Zeroizing::new(seed.as_bytes().to_vec())
+    };
+    let restored = wallet.restore_disposable_keys(&seed, count);
+    wallet
+        .save()
+        .unwrap_or_else(|err| edisplay_line!(io, "{}", err));
+    for alias in &restored {
+        display_line!(io, "Restored the disposable key \"{}\".", alias);
+    }
+    display_line!(
+        io,
+        "Restored {} disposable keys. Their balances can be moved with \
+         `namadac sweep-disposable`.",
+        restored.len()
+    );
+}
+
 /// Find a keypair in the wallet store.
 fn transparent_key_find(
     ctx: Context,
@@ -1,16 +1,22 @@
+use std::fs::File;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
 use color_eyre::owo_colors::OwoColorize;
-use namada_sdk::args::ShieldedSync;
-use namada_sdk::control_flow::install_shutdown_signal;
+use namada_sdk::args::{ExportMaspSnapshot, ShieldedSync};
+use namada_sdk::chain::{BlockHeight, ChainId};
+use namada_sdk::control_flow::{install_shutdown_signal, ShutdownSignal};
 use namada_sdk::error::Error;
-#[cfg(any(test, feature = "testing"))]
-use namada_sdk::io::DevNullProgressBar;
-use namada_sdk::io::{display, display_line, Client, Io, MaybeSend, MaybeSync};
+use namada_sdk::io::{
+    display, display_line, Client, DevNullProgressBar, Io, MaybeSend, MaybeSync,
+};
+use namada_sdk::masp::snapshot::MaspSnapshot;
+use namada_sdk::masp::utils::MaspClient;
 use namada_sdk::masp::{
-    IndexerMaspClient, LedgerMaspClient, MaspLocalTaskEnv, ShieldedContext,
-    ShieldedSyncConfig, ShieldedUtils,
+    FileMaspClient, IndexerMaspClient, LedgerMaspClient, MaspLocalTaskEnv,
+    ShieldedContext, ShieldedSyncConfig, ShieldedUtils,
 };
+use namada_sdk::ExtendedViewingKey;
 
 #[allow(clippy::too_many_arguments)]
 pub async fn syncing<
@@ -18,70 +24,162 @@ pub async fn syncing<
     C: Client + Send + Sync + 'static,
     IO: Io + Send + Sync,
 >(
-    mut shielded: ShieldedContext<U>,
+    shielded: ShieldedContext<U>,
     client: C,
     args: ShieldedSync,
     io: &IO,
 ) -> Result<ShieldedContext<U>, Error> {
-    let (fetched_bar, scanned_bar, applied_bar) = {
-        #[cfg(any(test, feature = "testing"))]
-        {
-            (DevNullProgressBar, DevNullProgressBar, DevNullProgressBar)
+    let shielded = if let Some(path) = args.with_snapshot.as_ref() {
+        if args.watch.is_some() {
+            return Err(Error::Other(
+                "Cannot watch for new blocks when syncing from a MASP snapshot"
+                    .to_string(),
+            ));
         }
+        display_line!(
+            io,
+            "{}\n",
+            "==== Shielded sync started using snapshot client ====".bold()
+        );
 
-        #[cfg(not(any(test, feature = "testing")))]
-        {
-            let fetched = kdam::tqdm!(
-                total = 0,
-                desc = "fetched ",
-                animation = "fillup",
-                position = 0,
-                force_refresh = true,
-                dynamic_ncols = true,
-                miniters = 0,
-                mininterval = 0.05
-            );
+        let client = FileMaspClient::open(path)?;
+        let header = client.snapshot().header();
+        display_line!(
+            io,
+            "Using MASP snapshot {} of the block heights {}-{}\n",
+            client.snapshot().id(),
+            header.base_height.next_height(),
+            header.last_height,
+        );
 
-            let scanned = kdam::tqdm!(
-                total = 0,
-                desc = "scanned ",
-                animation = "fillup",
-                position = 1,
-                force_refresh = true,
-                dynamic_ncols = true,
-                miniters = 0,
-                mininterval = 0.05
-            );
+        sync_with_client(shielded, client, &args, io).await?
+    } else if let Some(endpoint) = args.with_indexer.as_ref() {
+        display_line!(
+            io,
+            "{}\n",
+            "==== Shielded sync started using indexer client ====".bold()
+        );
 
-            let applied = kdam::tqdm!(
-                total = 0,
-                desc = "applied ",
-                animation = "fillup",
-                position = 2,
-                force_refresh = true,
-                dynamic_ncols = true,
-                miniters = 0,
-                mininterval = 0.05
-            );
+        let http_client = reqwest::Client::builder()
+            .connect_timeout(Duration::from_secs(60))
+            .build()
+            .map_err(|err| {
+                Error::Other(format!("Failed to build http client: {err}"))
+            })?;
+        let url = endpoint.as_str().try_into().map_err(|err| {
+            Error::Other(format!(
+                "Failed to parse API endpoint {endpoint:?}: {err}"
+            ))
+        })?;
 
-            (fetched, scanned, applied)
-        }
+        sync_with_client(
+            shielded,
+            IndexerMaspClient::new(
+                http_client,
+                url,
+                true,
+                args.max_concurrent_fetches,
+            ),
+            &args,
+            io,
+        )
+        .await?
+    } else {
+        display_line!(
+            io,
+            "{}\n",
+            "==== Shielded sync started using ledger client ====".bold()
+        );
+
+        sync_with_client(
+            shielded,
+            LedgerMaspClient::new(
+                client,
+                args.max_concurrent_fetches,
+                Duration::from_millis(5),
+            ),
+            &args,
+            io,
+        )
+        .await?
+    };
+
+    Ok(shielded)
+}
+
+/// Sync the shielded context with the given MASP client. In watch mode,
+/// keep syncing new blocks until an interrupt is received, reporting the
+/// progress of each key after every round.
+async fn sync_with_client<U, M, IO>(
+    mut shielded: ShieldedContext<U>,
+    client: M,
+    args: &ShieldedSync,
+    io: &IO,
+) -> Result<ShieldedContext<U>, Error>
+where
+    U: ShieldedUtils + MaybeSend + MaybeSync,
+    M: MaspClient + Send + Sync + Unpin + 'static,
+    IO: Io + Send + Sync,
+{
+    #[cfg(any(test, feature = "testing"))]
+    let progress_bars =
+        || (DevNullProgressBar, DevNullProgressBar, DevNullProgressBar);
+
+    #[cfg(not(any(test, feature = "testing")))]
+    let progress_bars = || {
+        let fetched = kdam::tqdm!(
+            total = 0,
+            desc = "fetched ",
+            animation = "fillup",
+            position = 0,
+            force_refresh = true,
+            dynamic_ncols = true,
+            miniters = 0,
+            mininterval = 0.05
+        );
+
+        let scanned = kdam::tqdm!(
+            total = 0,
+            desc = "scanned ",
+            animation = "fillup",
+            position = 1,
+            force_refresh = true,
+            dynamic_ncols = true,
+            miniters = 0,
+            mininterval = 0.05
+        );
+
+        let applied = kdam::tqdm!(
+            total = 0,
+            desc = "applied ",
+            animation = "fillup",
+            position = 2,
+            force_refresh = true,
+            dynamic_ncols = true,
+            miniters = 0,
+            mininterval = 0.05
+        );
+
+        (fetched, scanned, applied)
     };
 
     let vks = args
         .viewing_keys
-        .into_iter()
-        .map(|vk| vk.map(|vk| vk.as_viewing_key()))
+        .iter()
+        .map(|vk| vk.clone().map(|vk| vk.as_viewing_key()))
         .collect::<Vec<_>>();
 
-    macro_rules! dispatch_client {
-        ($client:expr) => {{
+    let shutdown_signal = install_shutdown_signal(false);
+
+    macro_rules! sync_pass {
+        ($trackers:expr, $start_height:expr, $last_height:expr) => {{
+            let (fetched, scanned, applied) = $trackers;
             let config = ShieldedSyncConfig::builder()
-                .client($client)
-                .fetched_tracker(fetched_bar)
-                .scanned_tracker(scanned_bar)
-                .applied_tracker(applied_bar)
-                .shutdown_signal(install_shutdown_signal(false))
+                .client(client.clone())
+                .fetched_tracker(fetched)
+                .scanned_tracker(scanned)
+                .applied_tracker(applied)
+                .shutdown_signal(shutdown_signal.clone())
                 .wait_for_last_query_height(args.wait_for_last_query_height)
                 .retry_strategy(args.retry_strategy)
                 .block_batch_size(args.block_batch_size)
@@ -89,31 +187,198 @@ pub async fn syncing<
 
             let env = MaspLocalTaskEnv::new(500)
                 .map_err(|e| Error::Other(e.to_string()))?;
-            let ctx = shielded
+            shielded
                 .sync(
                     env,
                     config,
-                    args.last_query_height,
+                    $start_height,
+                    $last_height,
                     &args.spending_keys,
                     &vks,
                 )
                 .await
-                .map(|_| shielded)
-                .map_err(|e| Error::Other(e.to_string()));
-
-            display!(io, "\nSyncing finished\n");
+                .map_err(|e| Error::Other(e.to_string()))?;
+        }};
+    }
 
-            ctx
+    // NB: every key advances from its own sync cursor. The keys that are
+    // the most synced are brought up to date first, so that the keys lagging
+    // behind them, e.g. keys added with an old birthday, don't hold them back
+    // while catching up in a second pass.
+    macro_rules! sync_round {
+        ($trackers:expr, $last_query_height:expr) => {{
+            let leading_height = shielded
+                .leading_height_to_sync_from(&args.spending_keys, &vks)
+                .filter(|height| {
+                    $last_query_height.map_or(true, |last| *height <= last)
+                });
+            if let Some(height) = leading_height {
+                sync_pass!($trackers, Some(height), $last_query_height);
+            }
+            if !shutdown_signal.clone().received() {
+                sync_pass!($trackers, None, $last_query_height);
+            }
         }};
     }
 
-    let shielded = if let Some(endpoint) = args.with_indexer {
-        display_line!(
-            io,
-            "{}\n",
-            "==== Shielded sync started using indexer client ====".bold()
+    sync_round!(progress_bars(), args.last_query_height);
+    display!(io, "\nSyncing finished\n");
+
+    let Some(interval) = args.watch else {
+        return Ok(shielded);
+    };
+
+    display_line!(
+        io,
+        "Watching for new blocks every {}s. Press Ctrl-C to stop.",
+        interval.as_secs()
+    );
+    let mut watch_signal = shutdown_signal.clone();
+    let mut notes_per_key = count_notes_per_key(&shielded, args);
+
+    loop {
+        tokio::select! {
+            _ = tokio::time::sleep(interval) => {}
+            _ = watch_signal.wait_for_shutdown() => break,
+        }
+
+        sync_round!(
+            (DevNullProgressBar, DevNullProgressBar, DevNullProgressBar),
+            None::<BlockHeight>
         );
+        if watch_signal.received() {
+            break;
+        }
 
+        let synced_notes = count_notes_per_key(&shielded, args);
+        let synced_height = synced_notes
+            .iter()
+            .filter_map(|(_, height, _)| *height)
+            .max();
+        if let Some(height) = synced_height {
+            display_line!(io, "Synced up to block height {height}");
+        }
+        // NB: only report the keys that found new notes or that are
+        // still catching up with the rest
+        for ((vk, height, notes), (_, _, prev_notes)) in
+            synced_notes.iter().zip(&notes_per_key)
+        {
+            let new_notes = notes.saturating_sub(*prev_notes);
+            if *height == synced_height && new_notes == 0 {
+                continue;
+            }
+            let height = height.map_or_else(
+                || "none".to_string(),
+                |height| height.to_string(),
+            );
+            display_line!(
+                io,
+                "{vk}: synced up to block height {height}, {new_notes} new \
+                 note(s)"
+            );
+        }
+        notes_per_key = synced_notes;
+    }
+
+    display_line!(io, "Stopped watching for new blocks");
+    Ok(shielded)
+}
+
+/// Get the sync cursor and the number of owned notes of each key passed to
+/// the shielded sync. The spending keys are reported by their viewing keys.
+fn count_notes_per_key<U: ShieldedUtils>(
+    shielded: &ShieldedContext<U>,
+    args: &ShieldedSync,
+) -> Vec<(ExtendedViewingKey, Option<BlockHeight>, usize)> {
+    args.spending_keys
+        .iter()
+        .map(|dated_sk| dated_sk.key.to_viewing_key())
+        .chain(args.viewing_keys.iter().map(|dated_vk| dated_vk.key))
+        .map(|key| {
+            let vk = key.as_viewing_key();
+            let height = shielded
+                .vk_heights
+                .get(&vk)
+                .copied()
+                .flatten()
+                .map(|itx| itx.height);
+            let notes = shielded.pos_map.get(&vk).map_or(0, |pos| pos.len());
+            (key, height, notes)
+        })
+        .collect()
+}
+
+/// Check that the MASP snapshot at the given path was taken from the chain
+/// with the given id.
+pub fn check_snapshot_chain_id(
+    path: &Path,
+    chain_id: &ChainId,
+) -> Result<(), Error> {
+    let mut file = File::open(path).map_err(|err| {
+        Error::Other(format!(
+            "Failed to open MASP snapshot file {}: {err}",
+            path.to_string_lossy()
+        ))
+    })?;
+    let header = MaspSnapshot::read_header(&mut file).map_err(|err| {
+        Error::Other(format!(
+            "Invalid MASP snapshot file {}: {err}",
+            path.to_string_lossy()
+        ))
+    })?;
+    if &header.chain_id != chain_id {
+        return Err(Error::Other(format!(
+            "The MASP snapshot file {} was taken from chain {}, but the \
+             current chain is {chain_id}",
+            path.to_string_lossy(),
+            header.chain_id
+        )));
+    }
+    Ok(())
+}
+
+/// Export a snapshot of the shielded sync data in the requested range of
+/// block heights to a file.
+pub async fn export_snapshot<
+    C: Client + Send + Sync + 'static,
+    IO: Io + Send + Sync,
+>(
+    client: C,
+    chain_id: ChainId,
+    args: ExportMaspSnapshot,
+    io: &IO,
+) -> Result<(), Error> {
+    macro_rules! export_with_client {
+        ($client:expr) => {{
+            let client = $client;
+            let last_block_height =
+                client.last_block_height().await?.ok_or_else(|| {
+                    Error::Other("No block has been committed yet".to_string())
+                })?;
+            let from = args.from_height.unwrap_or_else(BlockHeight::first);
+            let to = args
+                .to_height
+                .unwrap_or(last_block_height)
+                .min(last_block_height);
+
+            display_line!(
+                io,
+                "Fetching the MASP data of the block heights {from}-{to}..."
+            );
+            MaspSnapshot::fetch(
+                &client,
+                chain_id.clone(),
+                from,
+                to,
+                args.block_batch_size,
+                args.max_concurrent_fetches,
+            )
+            .await
+            .map_err(|err| Error::Other(err.to_string()))?
+        }};
+    }
+
+    let snapshot = if let Some(endpoint) = args.with_indexer.as_ref() {
         let client = reqwest::Client::builder()
             .connect_timeout(Duration::from_secs(60))
             .build()
@@ -126,25 +391,43 @@ pub async fn syncing<
             ))
         })?;
 
-        dispatch_client!(IndexerMaspClient::new(
+        export_with_client!(IndexerMaspClient::new(
             client,
             url,
             true,
             args.max_concurrent_fetches,
-        ))?
+        ))
     } else {
-        display_line!(
-            io,
-            "{}\n",
-            "==== Shielded sync started using ledger client ====".bold()
-        );
-
-        dispatch_client!(LedgerMaspClient::new(
+        export_with_client!(LedgerMaspClient::new(
             client,
             args.max_concurrent_fetches,
             Duration::from_millis(5),
-        ))?
+        ))
     };
 
-    Ok(shielded)
+    let header = snapshot.header();
+    let output = args.output.clone().unwrap_or_else(|| {
+        PathBuf::from(format!(
+            "masp-snapshot-{}-{}-{}.bin",
+            header.chain_id,
+            header.base_height.next_height(),
+            header.last_height
+        ))
+    });
+    std::fs::write(&output, snapshot.to_bytes()).map_err(|err| {
+        Error::Other(format!(
+            "Failed to write MASP snapshot file {}: {err}",
+            output.to_string_lossy()
+        ))
+    })?;
+
+    display_line!(
+        io,
+        "Wrote MASP snapshot {} with {} shielded transfers to {}",
+        snapshot.id(),
+        snapshot.len(),
+        output.to_string_lossy()
+    );
+
+    Ok(())
 }
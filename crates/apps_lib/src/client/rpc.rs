@@ -18,6 +18,7 @@ use namada_sdk::borsh::BorshDeserialize;
 use namada_sdk::chain::{BlockHeight, Epoch};
 use namada_sdk::collections::{HashMap, HashSet};
 use namada_sdk::control_flow::time::{Duration, Instant};
+use namada_sdk::dec::Dec;
 use namada_sdk::events::Event;
 use namada_sdk::governance::parameters::GovernanceParameters;
 use namada_sdk::governance::pgf::parameters::PgfParameters;
@@ -50,8 +51,10 @@ use namada_sdk::rpc::{
 use namada_sdk::storage::BlockResults;
 use namada_sdk::tendermint_rpc::endpoint::status;
 use namada_sdk::time::DateTimeUtc;
+use namada_sdk::token::conversion::LockedAmountGrowth;
 use namada_sdk::token::{DenominatedAmount, MaspDigitPos};
 use namada_sdk::tx::display_batch_resp;
+use namada_sdk::uint::Uint;
 use namada_sdk::wallet::AddressVpType;
 use namada_sdk::{error, state as storage, token, Namada};
 
@@ -448,6 +451,157 @@ pub async fn query_rewards_estimate(
     );
 }
 
+/// Project MASP rewards over future MASP epochs
+pub async fn query_rewards_projection(
+    context: &impl Namada,
+    args: args::QueryShieldingRewardsProjection,
+) {
+    let token = args.token;
+    let Some(denom) = rpc::query_denom(context.client(), &token).await else {
+        edisplay_line!(
+            context.io(),
+            "Failed to query the denomination of token {token}"
+        );
+        cli::safe_exit(1);
+    };
+    let growth = match (args.locked_amount_change, args.locked_amount_rate) {
+        (Some(change), _) => {
+            let raw_change = Dec::try_from(Uint::exp10(usize::from(denom.0)))
+                .ok()
+                .and_then(|scale| change.checked_mul(scale));
+            match raw_change {
+                Some(raw_change) => {
+                    LockedAmountGrowth::Linear(raw_change.to_i256())
+                }
+                None => {
+                    edisplay_line!(
+                        context.io(),
+                        "The locked amount change {change} is too large"
+                    );
+                    cli::safe_exit(1);
+                }
+            }
+        }
+        (None, Some(rate)) => LockedAmountGrowth::Geometric(rate),
+        (None, None) => LockedAmountGrowth::Constant,
+    };
+
+    let masp_epoch = query_and_print_masp_epoch(context).await;
+    let balance = match &args.owner {
+        Some(owner) => {
+            let mut shielded = context.shielded_mut().await;
+            let _ = shielded.load().await;
+            let _ = shielded
+                .precompute_asset_types(context.client(), vec![&token])
+                .await;
+            let balance = match shielded
+                .compute_exchanged_balance(
+                    context.client(),
+                    context.io(),
+                    &owner.as_viewing_key(),
+                    masp_epoch,
+                )
+                .await
+            {
+                Ok(balance) => balance,
+                Err(e) => {
+                    edisplay_line!(
+                        context.io(),
+                        "Failed to query shielded balance: {}",
+                        e
+                    );
+                    cli::safe_exit(1);
+                }
+            };
+            match balance {
+                Some(balance) => shielded
+                    .decode_combine_sum_to_epoch(
+                        context.client(),
+                        balance,
+                        masp_epoch,
+                    )
+                    .await
+                    .0
+                    .get(&token)
+                    .into(),
+                None => Amount::zero(),
+            }
+        }
+        None => Amount::zero(),
+    };
+
+    let projections = match rpc::query_masp_rewards_projection(
+        context.client(),
+        &token,
+        growth,
+        balance,
+        args.epochs,
+    )
+    .await
+    {
+        Ok(projections) => projections,
+        Err(e) => {
+            edisplay_line!(
+                context.io(),
+                "Failed to project the MASP rewards: {}",
+                e
+            );
+            cli::safe_exit(1);
+        }
+    };
+
+    let native_token = context.native_token();
+    let token_alias = lookup_token_alias(context, &token, &MASP).await;
+    // The rewards in native tokens for a whole unit of the token
+    let whole_token = Amount::from_uint(1, denom).unwrap_or_default();
+    display_line!(
+        context.io(),
+        "Projected MASP rewards for {token_alias} (rewards are in native \
+         tokens):"
+    );
+    let mut header = format!(
+        "{:>8} {:>24} {:>24} {:>24}",
+        "Epoch", "Locked amount", "Inflation", "Reward per token"
+    );
+    if args.owner.is_some() {
+        header.push_str(&format!(
+            " {:>24} {:>24} {:>24}",
+            "Balance", "Reward", "Cumulative reward"
+        ));
+    }
+    display_line!(context.io(), "{header}");
+    for projection in projections {
+        let reward_per_token = whole_token
+            .u128_eucl_div_rem(projection.reward)
+            .map(|(reward, _)| reward)
+            .unwrap_or_default();
+        let mut row = format!(
+            "{:>8} {:>24} {:>24} {:>24}",
+            format!("+{}", projection.epoch_offset),
+            context
+                .format_amount(&token, projection.locked_amount)
+                .await,
+            context
+                .format_amount(&native_token, projection.inflation)
+                .await,
+            context.format_amount(&native_token, reward_per_token).await,
+        );
+        if args.owner.is_some() {
+            row.push_str(&format!(
+                " {:>24} {:>24} {:>24}",
+                context.format_amount(&token, projection.balance).await,
+                context
+                    .format_amount(&native_token, projection.balance_reward)
+                    .await,
+                context
+                    .format_amount(&native_token, projection.cumulative_reward)
+                    .await,
+            ));
+        }
+        display_line!(context.io(), "{row}");
+    }
+}
+
 /// Query token shielded balance(s)
 async fn query_shielded_balance(
     context: &impl Namada,
@@ -21,9 +21,12 @@ use namada_sdk::governance::cli::onchain::{
 use namada_sdk::ibc::convert_masp_tx_to_ibc_memo;
 use namada_sdk::io::{display_line, edisplay_line, Io};
 use namada_sdk::key::*;
+use namada_sdk::masp::multisig::{ShieldedKeyShare, SpendAuthRequest};
 use namada_sdk::rpc::{InnerTxResult, TxBroadcastData, TxResponse};
+use namada_sdk::signing::remote::RemoteSigner;
 use namada_sdk::state::EPOCH_SWITCH_BLOCKS_DELAY;
-use namada_sdk::tx::data::compute_inner_tx_hash;
+use namada_sdk::time::DateTimeUtc;
+use namada_sdk::tx::data::{compute_inner_tx_hash, TxType};
 use namada_sdk::tx::{CompressedAuthorization, Section, Signer, Tx};
 use namada_sdk::wallet::alias::{validator_address, validator_consensus_key};
 use namada_sdk::wallet::{Wallet, WalletIo};
@@ -187,33 +190,75 @@ where
     Ok(tx)
 }
 
-// Sign the given transaction using a hardware wallet as a backup
+pub async fn with_remote_signer<U>(
+    tx: Tx,
+    pubkey: common::PublicKey,
+    parts: signing::Signable,
+    (wallet, signer): (&RwLock<Wallet<U>>, &RemoteSigner),
+) -> Result<Tx, error::Error>
+where
+    U: WalletIo + Clone,
+{
+    // Give the remote signer a human-readable summary of what it is signing
+    let summary = signing::to_ledger_vector(&*wallet.read().await, &tx)
+        .await?
+        .output;
+    signer.sign(tx, pubkey, parts, summary).await
+}
+
+// Sign the given transaction using a hardware wallet as a backup, subject to
+// the local policies of the keys in the wallet
 pub async fn sign<N: Namada>(
     context: &N,
     tx: &mut Tx,
     args: &args::Tx,
     signing_data: SigningTxData,
 ) -> Result<(), error::Error> {
+    // Identify the inner transactions for the local policies of the keys
+    let summary_context =
+        signing::tx_summary_context(context, tx, &signing_data).await?;
     // Setup a reusable context for signing transactions using the Ledger
     if args.use_device {
         let transport = WalletTransport::from_arg(args.device_transport);
         let app = NamadaApp::new(transport);
         let with_hw_data = (context.wallet_lock(), &app);
         // Finally, begin the signing with the Ledger as backup
-        context
-            .sign(
-                tx,
-                args,
-                signing_data,
-                with_hardware_wallet::<N::WalletUtils, _>,
-                with_hw_data,
-            )
-            .await?;
+        signing::sign_tx(
+            context.wallet_lock(),
+            args,
+            tx,
+            signing_data,
+            &summary_context,
+            with_hardware_wallet::<N::WalletUtils, _>,
+            with_hw_data,
+        )
+        .await?;
+    } else if let Some(addr) = &args.remote_signer {
+        // Sign with the keys held by the remote signer as backup
+        let signer = RemoteSigner::new(addr)?;
+        let with_signer_data = (context.wallet_lock(), &signer);
+        signing::sign_tx(
+            context.wallet_lock(),
+            args,
+            tx,
+            signing_data,
+            &summary_context,
+            with_remote_signer::<N::WalletUtils>,
+            with_signer_data,
+        )
+        .await?;
     } else {
         // Otherwise sign without a backup procedure
-        context
-            .sign(tx, args, signing_data, default_sign, ())
-            .await?;
+        signing::sign_tx(
+            context.wallet_lock(),
+            args,
+            tx,
+            signing_data,
+            &summary_context,
+            default_sign,
+            (),
+        )
+        .await?;
     }
     Ok(())
 }
@@ -868,6 +913,104 @@ pub async fn submit_transparent_transfer(
     Ok(())
 }
 
+/// Transfer the leftover balances of the expired disposable keys of the wallet
+/// to the target, in a single transaction signed by all of the swept keys
+pub async fn submit_sweep_disposable<N: Namada>(
+    namada: &N,
+    args: args::SweepDisposable,
+) -> Result<(), error::Error>
+where
+    <N::Client as namada_sdk::io::Client>::Error: std::fmt::Display,
+{
+    if args.tx.wrapper_fee_payer.is_none() {
+        // Otherwise the fees would be paid by one of the swept keys, whose
+        // balance is moved in full
+        return Err(error::Error::Other(
+            "A gas payer must be provided with --gas-payer to sweep the \
+             disposable keys"
+                .to_string(),
+        ));
+    }
+    let token = args.token.clone().unwrap_or_else(|| namada.native_token());
+
+    #[allow(clippy::disallowed_methods)]
+    let current_unix_timestamp = DateTimeUtc::now().to_unix_timestamp();
+    let expired_keys = namada
+        .wallet()
+        .await
+        .find_expired_disposable_keys(current_unix_timestamp);
+
+    let mut data = vec![];
+    let mut signing_keys = vec![];
+    for (alias, public_key) in expired_keys {
+        let source = Address::from(&public_key);
+        let balance = namada_sdk::rpc::get_token_balance(
+            namada.client(),
+            &token,
+            &source,
+            None,
+        )
+        .await?;
+        if balance.is_zero() {
+            continue;
+        }
+        let amount = namada_sdk::rpc::denominate_amount(
+            namada.client(),
+            namada.io(),
+            &token,
+            balance,
+        )
+        .await;
+        display_line!(
+            namada.io(),
+            "Sweeping {} from the disposable key {}.",
+            amount,
+            alias
+        );
+        data.push(args::TxTransparentTransferData {
+            source,
+            target: args.target.clone(),
+            token: token.clone(),
+            amount: args::InputAmount::Validated(amount),
+        });
+        signing_keys.push(public_key);
+    }
+    if data.is_empty() {
+        display_line!(
+            namada.io(),
+            "No expired disposable key holds a balance of token {}.",
+            token
+        );
+        return Ok(());
+    }
+
+    let transfer = args::TxTransparentTransfer {
+        tx: args::Tx {
+            signing_keys,
+            ..args.tx
+        },
+        data,
+        tx_code_path: args.tx_code_path,
+    };
+    let transfer_data = transfer.clone().build(namada).await?;
+
+    if transfer.tx.dump_tx || transfer.tx.dump_wrapper_tx {
+        tx::dump_tx(namada.io(), &transfer.tx, transfer_data.0)?;
+    } else {
+        let reveal_pks: Vec<_> =
+            transfer.data.iter().map(|datum| &datum.source).collect();
+        batch_opt_reveal_pk_and_submit(
+            namada,
+            &transfer.tx,
+            &reveal_pks,
+            transfer_data,
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
 // A mapper that replaces authorization signatures with those in a built-in map
 struct MapSaplingSigAuth(
     HashMap<usize, <sapling::Authorized as sapling::Authorization>::AuthSig>,
@@ -1046,6 +1189,87 @@ async fn masp_sign(
     Ok(())
 }
 
+// Load the shielded multisig key share given to the transaction, if any, and
+// augment the sources that the multisig controls with its proof generation
+// key. The spends of these sources are left unauthorized.
+fn augment_masp_multisig_keys<'a>(
+    args: &args::Tx,
+    sources: impl Iterator<Item = &'a mut PseudoExtendedKey>,
+) -> Result<Option<ShieldedKeyShare>, error::Error> {
+    let Some(share) = &args.shielded_key_share else {
+        return Ok(None);
+    };
+    if !args.dump_tx {
+        return Err(error::Error::Other(
+            "Spending from a shielded multisig requires dumping the \
+             transaction with --dump-tx, for its participants to authorize \
+             the spends."
+                .to_string(),
+        ));
+    }
+    let share: ShieldedKeyShare =
+        serde_json::from_slice(share).map_err(|err| {
+            error::Error::Other(format!(
+                "Invalid shielded multisig key share: {err}"
+            ))
+        })?;
+    let multisig_key = share.pseudo_extended_key()?;
+    for source in sources {
+        if source.to_viewing_key() == multisig_key.to_viewing_key() {
+            *source = multisig_key;
+        }
+    }
+    Ok(Some(share))
+}
+
+// Serialize the given transaction within a request for the participants of a
+// shielded multisig to authorize its spends.
+fn dump_spend_auth_request(
+    io: &impl Io,
+    args: &args::Tx,
+    mut tx: Tx,
+    signing_data: &SigningTxData,
+    bparams: RngBuildParams<OsRng>,
+    share: &ShieldedKeyShare,
+) -> Result<(), error::Error> {
+    let shielded_hash = signing_data.shielded_hash.ok_or_else(|| {
+        error::Error::Other(
+            "Missing MASP section in shielded transaction".to_string(),
+        )
+    })?;
+    let bparams = bparams.to_stored().ok_or_else(|| {
+        error::Error::Other(
+            "Unable to recover the MASP build parameters".to_string(),
+        )
+    })?;
+    tx.update_header(TxType::Raw);
+    let request =
+        SpendAuthRequest::new(tx, shielded_hash, share.viewing_key, &bparams)?;
+
+    let filename = format!(
+        "spend_auth_request_{}.json",
+        shielded_hash.to_string().to_lowercase()
+    );
+    let request_path = match &args.output_folder {
+        Some(path) => path.join(filename),
+        None => filename.into(),
+    };
+    let out = File::create(&request_path).map_err(|err| {
+        error::Error::Other(format!(
+            "Unable to create {}: {err}",
+            request_path.to_string_lossy()
+        ))
+    })?;
+    serde_json::to_writer_pretty(out, &request)
+        .expect("Should be able to write to file.");
+    display_line!(
+        io,
+        "Spend authorization request serialized to {}.",
+        request_path.to_string_lossy()
+    );
+    Ok(())
+}
+
 pub async fn submit_shielded_transfer(
     namada: &impl Namada,
     mut args: args::TxShieldedTransfer,
@@ -1059,6 +1283,25 @@ pub async fn submit_shielded_transfer(
          this command.",
     );
 
+    let sources = args
+        .data
+        .iter_mut()
+        .map(|x| &mut x.source)
+        .chain(args.gas_spending_key.iter_mut());
+    if let Some(share) = augment_masp_multisig_keys(&args.tx, sources)? {
+        let mut bparams = RngBuildParams::new(OsRng);
+        let (tx, signing_data) =
+            args.clone().build(namada, &mut bparams).await?;
+        return dump_spend_auth_request(
+            namada.io(),
+            &args.tx,
+            tx,
+            &signing_data,
+            bparams,
+            &share,
+        );
+    }
+
     let sources = args
         .data
         .iter_mut()
@@ -1195,6 +1438,22 @@ pub async fn submit_unshielding_transfer(
          this command.",
     );
 
+    let sources = std::iter::once(&mut args.source)
+        .chain(args.gas_spending_key.iter_mut());
+    if let Some(share) = augment_masp_multisig_keys(&args.tx, sources)? {
+        let mut bparams = RngBuildParams::new(OsRng);
+        let (tx, signing_data) =
+            args.clone().build(namada, &mut bparams).await?;
+        return dump_spend_auth_request(
+            namada.io(),
+            &args.tx,
+            tx,
+            &signing_data,
+            bparams,
+            &share,
+        );
+    }
+
     let sources = std::iter::once(&mut args.source)
         .chain(args.gas_spending_key.iter_mut());
     let shielded_hw_keys =
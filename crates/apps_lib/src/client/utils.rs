@@ -16,13 +16,19 @@ use namada_sdk::chain::ChainId;
 use namada_sdk::dec::Dec;
 use namada_sdk::ibc::trace::ibc_token;
 use namada_sdk::key::*;
+use namada_sdk::masp::multisig;
+use namada_sdk::rpc::TxBroadcastData;
+use namada_sdk::signing::bundle::PartiallySignedTx;
 use namada_sdk::string_encoding::StringEncoded;
-use namada_sdk::token;
-use namada_sdk::tx::{Authorization, Tx};
+use namada_sdk::tx::{Authorization, SignatureIndex, Tx};
 use namada_sdk::uint::Uint;
 use namada_sdk::wallet::{alias, LoadStoreError, Wallet};
+use namada_sdk::{rpc, token, Namada};
 use namada_vm::validate_untrusted_wasm;
 use prost::bytes::Bytes;
+use rand::rngs::OsRng;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
 use serde_json::json;
 use sha2::{Digest, Sha256};
 use tokio::sync::RwLock;
@@ -1133,6 +1139,364 @@ pub async fn sign_offline(
     }
 }
 
+/// Generate a shielded multisig key and serialize the key shares of its
+/// participants.
+pub fn init_shielded_multisig(
+    args::InitShieldedMultisig {
+        threshold,
+        participants,
+        output_folder_path,
+    }: args::InitShieldedMultisig,
+) {
+    let shares =
+        multisig::generate_key_shares(threshold, participants, &mut OsRng)
+            .unwrap_or_else(|err| {
+                eprintln!(
+                    "Failed to generate the shielded multisig key: {err}"
+                );
+                safe_exit(1)
+            });
+
+    for share in &shares {
+        let share_path = write_json_or_exit(
+            &output_folder_path,
+            format!("shielded_key_share_{}.json", share.identifier),
+            share,
+        );
+        println!(
+            "Key share of participant {} serialized at {}",
+            share.identifier, share_path
+        );
+    }
+    if let Some(share) = shares.first() {
+        println!(
+            "Shielded multisig viewing key: {}. Add it to your wallet to \
+             derive payment addresses and track its balance.",
+            share.viewing_key
+        );
+    }
+}
+
+/// Generate the signing nonces and commitments of a shielded multisig
+/// participant for a spend authorization request.
+pub fn commit_shielded_spends(
+    args::CommitShieldedSpends {
+        request,
+        key_share,
+        output_folder_path,
+    }: args::CommitShieldedSpends,
+) {
+    let request: multisig::SpendAuthRequest = read_json_or_exit(&request);
+    let share: multisig::ShieldedKeyShare = read_json_or_exit(&key_share);
+    let (nonces, commitments) = multisig::commit(&share, &request, &mut OsRng)
+        .unwrap_or_else(|err| {
+            eprintln!("Failed to commit to the shielded spends: {err}");
+            safe_exit(1)
+        });
+
+    let shielded_hash = request.shielded_hash.to_string().to_lowercase();
+    let nonces_path = write_json_or_exit(
+        &output_folder_path,
+        format!(
+            "spend_auth_nonces_{}_{}.json",
+            shielded_hash, share.identifier
+        ),
+        &nonces,
+    );
+    println!(
+        "Nonces serialized at {}. Keep them private and use them to sign only \
+         once.",
+        nonces_path
+    );
+    let commitments_path = write_json_or_exit(
+        &output_folder_path,
+        format!(
+            "spend_auth_commitments_{}_{}.json",
+            shielded_hash, share.identifier
+        ),
+        &commitments,
+    );
+    println!("Commitments serialized at {}", commitments_path);
+}
+
+/// Produce the signature shares of a shielded multisig participant for a
+/// spend authorization request.
+pub fn sign_shielded_spends(
+    args::SignShieldedSpends {
+        request,
+        key_share,
+        nonces,
+        commitments,
+        output_folder_path,
+    }: args::SignShieldedSpends,
+) {
+    let request: multisig::SpendAuthRequest = read_json_or_exit(&request);
+    let share: multisig::ShieldedKeyShare = read_json_or_exit(&key_share);
+    let nonces: multisig::SpendAuthNonces = read_json_or_exit(&nonces);
+    let commitments: Vec<multisig::SpendAuthCommitments> = commitments
+        .iter()
+        .map(|path| read_json_or_exit(path))
+        .collect();
+    let signature_shares =
+        multisig::sign(&share, &request, &nonces, &commitments).unwrap_or_else(
+            |err| {
+                eprintln!("Failed to sign the shielded spends: {err}");
+                safe_exit(1)
+            },
+        );
+
+    let shares_path = write_json_or_exit(
+        &output_folder_path,
+        format!(
+            "spend_auth_signature_shares_{}_{}.json",
+            request.shielded_hash.to_string().to_lowercase(),
+            share.identifier
+        ),
+        &signature_shares,
+    );
+    println!("Signature shares serialized at {}", shares_path);
+}
+
+/// Combine the signature shares of the participants of a shielded multisig
+/// into the authorized transaction.
+pub fn aggregate_shielded_spends(
+    args::AggregateShieldedSpends {
+        request,
+        key_share,
+        commitments,
+        signature_shares,
+        output_folder_path,
+    }: args::AggregateShieldedSpends,
+) {
+    let request: multisig::SpendAuthRequest = read_json_or_exit(&request);
+    let share: multisig::ShieldedKeyShare = read_json_or_exit(&key_share);
+    let commitments: Vec<multisig::SpendAuthCommitments> = commitments
+        .iter()
+        .map(|path| read_json_or_exit(path))
+        .collect();
+    let signature_shares: Vec<multisig::SpendAuthSignatureShares> =
+        signature_shares
+            .iter()
+            .map(|path| read_json_or_exit(path))
+            .collect();
+    let tx =
+        multisig::aggregate(&share, &request, &commitments, &signature_shares)
+            .unwrap_or_else(|err| {
+                eprintln!(
+                    "Failed to aggregate the shielded spends signatures: {err}"
+                );
+                safe_exit(1)
+            });
+
+    let filename =
+        format!("{}.tx", tx.header_hash().to_string().to_lowercase());
+    let tx_path = match output_folder_path {
+        Some(ref path) => path.join(filename).to_string_lossy().to_string(),
+        None => filename,
+    };
+    let tx_file =
+        File::create(&tx_path).expect("Should be able to create a tx file.");
+    tx.to_writer_json(tx_file)
+        .expect("Should be able to write to file.");
+    println!("Authorized transaction serialized at {}", tx_path);
+}
+
+/// Bundle a transaction with the public keys and the threshold of its owner,
+/// to collect the signatures of the owner's members.
+pub async fn create_partial_tx(
+    namada: &impl Namada,
+    args::CreatePartialTx {
+        query: _,
+        tx_path,
+        owner,
+        output_folder_path,
+    }: args::CreatePartialTx,
+) {
+    let tx: Tx = read_json_or_exit(&tx_path);
+    let account = rpc::get_account_info(namada.client(), &owner)
+        .await
+        .unwrap_or_else(|err| {
+            eprintln!("Failed to query the account {owner}: {err}");
+            safe_exit(1)
+        })
+        .unwrap_or_else(|| {
+            eprintln!(
+                "No account found at {owner}. The public key of an implicit \
+                 account must be revealed first."
+            );
+            safe_exit(1)
+        });
+    let bundle = PartiallySignedTx::new(
+        tx,
+        Some(owner),
+        &account.public_keys_map,
+        account.threshold,
+    );
+
+    let filename = format!(
+        "partial_tx_{}.json",
+        bundle.tx.raw_header_hash().to_string().to_lowercase()
+    );
+    let path = write_json_or_exit(&output_folder_path, filename, &bundle);
+    println!("Partially signed transaction serialized at {path}");
+    print_partial_tx(&bundle);
+}
+
+/// Show the signatures collected in a partially signed transaction.
+pub fn inspect_partial_tx(args::PartialTx { path }: args::PartialTx) {
+    let bundle: PartiallySignedTx = read_json_or_exit(&path);
+    print_partial_tx(&bundle);
+}
+
+/// Verify offline signatures and add them to a partially signed transaction.
+pub fn add_partial_signatures(
+    args::AddPartialSignatures {
+        path,
+        signatures,
+        wrapper_signature,
+    }: args::AddPartialSignatures,
+) {
+    let mut bundle: PartiallySignedTx = read_json_or_exit(&path);
+    for signature_path in signatures {
+        let signature: SignatureIndex = read_json_or_exit(&signature_path);
+        let pubkey = signature.pubkey.clone();
+        match bundle.add_signature(signature) {
+            Ok(true) => println!("Added the signature of {pubkey}."),
+            Ok(false) => println!("Replaced the signature of {pubkey}."),
+            Err(err) => {
+                eprintln!(
+                    "Couldn't add the signature at {}: {err}",
+                    signature_path.display()
+                );
+                safe_exit(1)
+            }
+        }
+    }
+    if let Some(wrapper_signature) = wrapper_signature {
+        let signature: Authorization = read_json_or_exit(&wrapper_signature);
+        bundle
+            .add_wrapper_signature(signature)
+            .unwrap_or_else(|err| {
+                eprintln!(
+                    "Couldn't add the wrapper signature at {}: {err}",
+                    wrapper_signature.display()
+                );
+                safe_exit(1)
+            });
+        println!("Added the wrapper signature.");
+    }
+
+    let file = File::create(&path).unwrap_or_else(|err| {
+        eprintln!("Couldn't create file at {}: {err}", path.display());
+        safe_exit(1)
+    });
+    bundle
+        .to_writer_json(file)
+        .expect("Partially signed transaction should be serializable.");
+    print_partial_tx(&bundle);
+}
+
+/// Verify the signatures of a partially signed transaction against the
+/// threshold of its owner.
+pub fn verify_partial_tx(args::PartialTx { path }: args::PartialTx) {
+    let bundle: PartiallySignedTx = read_json_or_exit(&path);
+    if let Err(err) = bundle.verify() {
+        eprintln!("{err}");
+        safe_exit(1)
+    }
+    println!(
+        "The {} collected signatures meet the threshold of {}.",
+        bundle.signatures.len(),
+        bundle.threshold
+    );
+    if bundle.tx.header.wrapper().is_some()
+        && bundle.wrapper_signature.is_none()
+    {
+        println!("The wrapper must still be signed by the fee payer.");
+    }
+}
+
+/// Finalize a partially signed transaction and submit it to the ledger.
+pub async fn submit_partial_tx(
+    namada: &impl Namada,
+    args::SubmitPartialTx { query: _, path }: args::SubmitPartialTx,
+) {
+    let bundle: PartiallySignedTx = read_json_or_exit(&path);
+    let tx = bundle.finalize().unwrap_or_else(|err| {
+        eprintln!("Couldn't finalize the transaction: {err}");
+        safe_exit(1)
+    });
+    let to_broadcast = TxBroadcastData::Live {
+        tx_hash: tx.header_hash().to_string(),
+        tx,
+    };
+    match namada_sdk::tx::submit_tx(namada, to_broadcast).await {
+        Ok(resp) => namada_sdk::tx::display_batch_resp(namada, &resp),
+        Err(err) => {
+            eprintln!("Couldn't submit the transaction: {err}");
+            safe_exit(1)
+        }
+    }
+}
+
+fn print_partial_tx(bundle: &PartiallySignedTx) {
+    println!(
+        "Transaction: {}",
+        bundle.tx.raw_header_hash().to_string().to_lowercase()
+    );
+    if let Some(owner) = &bundle.owner {
+        println!("Owner: {owner}");
+    }
+    println!(
+        "Signatures: {} of {} required",
+        bundle.signatures.len(),
+        bundle.threshold
+    );
+    for pubkey in bundle.signers() {
+        println!("  signed: {pubkey}");
+    }
+    for pubkey in bundle.missing_signers() {
+        println!("  missing: {pubkey}");
+    }
+    if let Some(wrapper) = bundle.tx.header.wrapper() {
+        let status = if bundle.wrapper_signature.is_some() {
+            "signed"
+        } else {
+            "missing"
+        };
+        println!("Fee payer: {} ({status})", wrapper.pk);
+    }
+}
+
+fn read_json_or_exit<T: DeserializeOwned>(path: &Path) -> T {
+    let data = fs::read(path).unwrap_or_else(|err| {
+        eprintln!("Couldn't open file at {}: {err}", path.display());
+        safe_exit(1)
+    });
+    serde_json::from_slice(&data).unwrap_or_else(|err| {
+        eprintln!("Couldn't decode the file at {}: {err}", path.display());
+        safe_exit(1)
+    })
+}
+
+fn write_json_or_exit(
+    output_folder_path: &Option<PathBuf>,
+    filename: String,
+    value: &impl Serialize,
+) -> String {
+    let path = match output_folder_path {
+        Some(path) => path.join(filename).to_string_lossy().to_string(),
+        None => filename,
+    };
+    let file = File::create(&path).unwrap_or_else(|err| {
+        eprintln!("Couldn't create file at {path}: {err}");
+        safe_exit(1)
+    });
+    serde_json::to_writer_pretty(file, value)
+        .expect("Value should be serializable.");
+    path
+}
+
 /// Add a spinning wheel to a message for long running commands.
 /// Can be turned off for E2E tests by setting the `REDUCED_CLI_PRINTING`
 /// environment variable.
@@ -20,7 +20,7 @@ use namada_sdk::dec::Dec;
 use namada_sdk::key::common::PublicKey;
 use namada_sdk::key::{common, ed25519, RefTo, SerializeWithBorsh, SigScheme};
 use namada_sdk::proof_of_stake::types::ValidatorMetaData;
-use namada_sdk::signing::{sign_tx, SigningTxData};
+use namada_sdk::signing::{sign_tx, SigningTxData, TxSummaryContext};
 use namada_sdk::string_encoding::StringEncoded;
 use namada_sdk::time::DateTimeUtc;
 use namada_sdk::token;
@@ -79,6 +79,7 @@ fn get_tx_args(use_device: bool) -> TxArgs {
     TxArgs {
         dry_run: false,
         dry_run_wrapper: false,
+        dry_run_trace: false,
         dump_tx: false,
         dump_wrapper_tx: false,
         output_folder: None,
@@ -102,6 +103,9 @@ fn get_tx_args(use_device: bool) -> TxArgs {
         memo: None,
         use_device,
         device_transport: DeviceTransport::default(),
+        masp_prover: None,
+        shielded_key_share: None,
+        remote_signer: None,
     }
 }
 
@@ -785,6 +789,7 @@ impl<T> Signed<T> {
                 &get_tx_args(use_device),
                 &mut tx,
                 signing_data,
+                &TxSummaryContext::default(),
                 utils::with_hardware_wallet,
                 (wallet_lock, &app),
             )
@@ -811,6 +816,7 @@ impl<T> Signed<T> {
                 &get_tx_args(use_device),
                 &mut tx,
                 signing_data,
+                &TxSummaryContext::default(),
                 software_wallet_sign,
                 (),
             )
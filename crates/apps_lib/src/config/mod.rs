@@ -7,12 +7,16 @@ pub mod utils;
 
 use std::fs::{create_dir_all, File};
 use std::io::Write;
+use std::net::SocketAddr;
 use std::num::NonZeroU64;
 use std::path::{Path, PathBuf};
 
 use directories::ProjectDirs;
 use namada_sdk::chain::{BlockHeight, ChainId};
 use namada_sdk::collections::HashMap;
+use namada_sdk::state::{
+    PruningMode, TxBatchExecution, VpEvaluation, DEFAULT_ROLLBACK_WINDOW,
+};
 use namada_sdk::time::Rfc3339String;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
@@ -44,6 +48,46 @@ pub struct Config {
 pub struct ValidatorLocalConfig {
     pub accepted_gas_tokens:
         HashMap<namada_sdk::address::Address, namada_sdk::token::Amount>,
+    /// The HSM holding the protocol key and possibly the Ethereum bridge hot
+    /// key and the consensus key of the validator, which are otherwise loaded
+    /// from the wallet and CometBFT's key file
+    #[serde(default)]
+    pub hsm: Option<Pkcs11Config>,
+}
+
+/// The default env var holding the user PIN of a validator's HSM token
+pub const DEFAULT_HSM_PIN_ENV: &str = "NAMADA_HSM_PIN";
+
+/// The configuration of a PKCS#11 HSM token holding validator keys
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Pkcs11Config {
+    /// The path of the PKCS#11 module of the HSM, e.g.
+    /// `/usr/lib/softhsm/libsofthsm2.so`
+    pub module: PathBuf,
+    /// The label of the token holding the keys
+    pub token_label: String,
+    /// The env var holding the user PIN of the token
+    #[serde(default = "default_hsm_pin_env")]
+    pub pin_env: String,
+    /// The label of the protocol key pair
+    pub protocol_key_label: String,
+    /// The label of the Ethereum bridge hot key pair, if it is held in the
+    /// HSM too
+    #[serde(default)]
+    pub eth_bridge_key_label: Option<String>,
+    /// The label of the consensus key pair, if it is held in the HSM too. It
+    /// requires CometBFT's `priv_validator_laddr` to be set to a Unix socket,
+    /// to which the node connects to sign votes and proposals.
+    #[serde(default)]
+    pub consensus_key_label: Option<String>,
+}
+
+fn default_hsm_pin_env() -> String {
+    DEFAULT_HSM_PIN_ENV.to_string()
+}
+
+fn default_rollback_window() -> u64 {
+    DEFAULT_ROLLBACK_WINDOW
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -89,6 +133,48 @@ pub struct ActionAtHeight {
     pub action: Action,
 }
 
+/// The storage backend of the DB
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum DbBackend {
+    /// RocksDB, the only backend that supports state sync snapshots
+    #[default]
+    RocksDb,
+    /// redb, a pure-Rust embedded key-value store
+    Redb,
+}
+
+impl DbBackend {
+    pub fn to_str(&self) -> &str {
+        match self {
+            DbBackend::RocksDb => "rocksdb",
+            DbBackend::Redb => "redb",
+        }
+    }
+}
+
+impl std::fmt::Display for DbBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.to_str())
+    }
+}
+
+impl std::str::FromStr for DbBackend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "rocksdb" => Ok(DbBackend::RocksDb),
+            "redb" => Ok(DbBackend::Redb),
+            _ => Err(format!(
+                "Unknown DB backend {s}, expected one of rocksdb, redb"
+            )),
+        }
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Ledger {
     pub genesis_time: Rfc3339String,
@@ -114,6 +200,39 @@ pub struct Shell {
     /// When set, will limit the how many block heights in the past can the
     /// storage be queried for reading values.
     pub storage_read_past_height_limit: Option<u64>,
+    /// How much of the block history is kept in the DB. The history is
+    /// pruned in the background while the node is idle, and the blocks in
+    /// the `rollback_window` are always kept. Queries at heights whose
+    /// history has been pruned fail, even within the
+    /// `storage_read_past_height_limit`. Unlike the rest of the history, the
+    /// block headers are kept by default, as the IBC consensus states of past
+    /// heights are looked up from them. They are only pruned in the `pruned`
+    /// mode with `prune_headers = true`, which may make the node reject IBC
+    /// transactions that the rest of the network accepts.
+    #[serde(default)]
+    pub pruning_mode: PruningMode,
+    /// How many of the most recent blocks can be rolled back with the
+    /// `ledger rollback` command. The data needed to roll back a block that
+    /// is not kept with the block history is kept for these blocks only.
+    #[serde(default = "default_rollback_window")]
+    pub rollback_window: u64,
+    /// The storage backend of the DB. An existing DB can be converted to
+    /// another backend with the `ledger migrate-db` command.
+    #[serde(default)]
+    pub db_backend: DbBackend,
+    /// Whether the validity predicates of a transaction are evaluated
+    /// sequentially or in parallel. This doesn't affect the results, but in
+    /// parallel all the VPs are evaluated even if one of them rejects the tx.
+    /// Defaults to sequential, parallel evaluation is opt-in with
+    /// `vp_evaluation = "parallel"`.
+    #[serde(default)]
+    pub vp_evaluation: VpEvaluation,
+    /// Whether the transaction batches of a block are executed sequentially
+    /// or in parallel. This doesn't affect the results. Defaults to
+    /// sequential, parallel execution is opt-in with
+    /// `tx_batch_execution = "parallel"`.
+    #[serde(default)]
+    pub tx_batch_execution: TxBatchExecution,
     /// Use the [`Ledger::db_dir()`] method to read the value.
     db_dir: PathBuf,
     /// Use the [`Ledger::cometbft_dir()`] method to read the value.
@@ -127,6 +246,10 @@ pub struct Shell {
     pub blocks_between_snapshots: Option<NonZeroU64>,
     /// Number of snapshots to keep
     pub snapshots_to_keep: Option<NonZeroU64>,
+    /// When set, the state diffs of every committed block are streamed to the
+    /// WebSocket clients connected to this local address
+    #[serde(default)]
+    pub change_feed_addr: Option<SocketAddr>,
 }
 
 impl Ledger {
@@ -151,12 +274,18 @@ impl Ledger {
                 tx_wasm_compilation_cache_bytes: None,
                 // Default corresponds to 1 hour of past blocks at 1 block/sec
                 storage_read_past_height_limit: Some(3600),
+                pruning_mode: PruningMode::default(),
+                rollback_window: DEFAULT_ROLLBACK_WINDOW,
+                db_backend: DbBackend::default(),
+                vp_evaluation: VpEvaluation::default(),
+                tx_batch_execution: TxBatchExecution::default(),
                 db_dir: DB_DIR.into(),
                 cometbft_dir: COMETBFT_DIR.into(),
                 action_at_height: None,
                 tendermint_mode: mode,
                 blocks_between_snapshots: None,
                 snapshots_to_keep: None,
+                change_feed_addr: None,
             },
             cometbft: tendermint_config,
             ethereum_bridge: ethereum_bridge::ledger::Config::default(),
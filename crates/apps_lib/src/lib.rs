@@ -18,6 +18,8 @@ pub mod cli;
 pub mod client;
 pub mod config;
 pub mod logging;
+pub mod masp_prover;
+pub mod remote_signer;
 pub mod tendermint_node;
 pub mod wallet;
 pub mod wasm_loader;
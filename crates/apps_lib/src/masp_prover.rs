@@ -0,0 +1,287 @@
+//! A server generating the proofs of MASP transactions on behalf of clients
+//! that cannot afford to prove them locally.
+//!
+//! Clients `POST` a borsh encoded list of
+//! [`ProofRequest`](namada_sdk::masp::prover::ProofRequest)s to the `/prove`
+//! endpoint, and receive back a borsh encoded
+//! [`ProofResponse`](namada_sdk::masp::prover::ProofResponse).
+//!
+//! The requests carry the private inputs of the MASP circuits. Spend requests
+//! include the proof generation keys of the spent notes, that is both their
+//! spend validating keys and their nullifier deriving keys. The server cannot
+//! authorize spends, as it never sees the spend authorizing keys, but it can
+//! compute the nullifiers of the notes it proves and hence link them to the
+//! later transactions of the client, on top of learning the values, asset
+//! types and recipients of the proven notes. The server must therefore be
+//! trusted with the privacy of its clients, and should only be reached over
+//! a trusted channel. As the server speaks plain HTTP, it only listens on
+//! loopback addresses. Clients on other hosts must reach it through an
+//! authenticated and encrypted tunnel, e.g. an SSH port forwarding.
+//!
+//! Proving is CPU bound, so at most [`MAX_CONCURRENT_PROVING`] batches are
+//! proven at once, and further requests are answered with a `503 Service
+//! Unavailable` status until a batch completes.
+
+use std::convert::Infallible;
+use std::future::Future;
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use borsh::BorshDeserialize;
+use hyper::body::HttpBody;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use namada_sdk::borsh::BorshSerializeExt;
+use namada_sdk::control_flow::{install_shutdown_signal, ShutdownSignal};
+use namada_sdk::masp::fs::FsShieldedUtils;
+use namada_sdk::masp::prover::{
+    GrothProof, LocalMaspProver, ProofRequest, ProofResponse,
+};
+use namada_sdk::masp::ShieldedUtils;
+use tokio::sync::Semaphore;
+
+use crate::cli::args;
+
+/// The maximum size in bytes of the body of a proof request
+pub const MAX_REQUEST_BODY_SIZE: usize = 16 * 1024 * 1024;
+
+/// The maximum number of circuits that can be proven in a single request
+pub const MAX_PROOF_REQUESTS: usize = 1024;
+
+/// The maximum number of requests that are proven concurrently. The circuits
+/// of a request are already proven in parallel.
+pub const MAX_CONCURRENT_PROVING: usize = 2;
+
+/// Run the MASP prover server until an interrupt signal is received.
+pub async fn run(args: args::MaspProverServer) -> eyre::Result<()> {
+    if !args.listen_addr.ip().is_loopback() {
+        return Err(eyre::eyre!(
+            "Refusing to listen on the non-loopback address {}, as MASP proof \
+             requests are neither authenticated nor encrypted",
+            args.listen_addr
+        ));
+    }
+
+    // Make sure that the MASP parameters are available
+    let utils = FsShieldedUtils::new(PathBuf::new()).utils;
+    let prover = LocalMaspProver(utils.local_tx_prover());
+
+    let listener = TcpListener::bind(args.listen_addr)?;
+    tracing::info!("MASP prover listening on {}", args.listen_addr);
+
+    let mut shutdown_signal = install_shutdown_signal(true);
+    serve(
+        listener,
+        move |requests| prover.prove_requests(requests),
+        async move { shutdown_signal.wait_for_shutdown().await },
+    )
+    .await
+}
+
+/// Serve the proof requests received on the given listener with the
+/// provided proving function, until the `shutdown` future resolves.
+pub async fn serve<F>(
+    listener: TcpListener,
+    prove: F,
+    shutdown: impl Future<Output = ()>,
+) -> eyre::Result<()>
+where
+    F: Fn(Vec<ProofRequest>) -> eyre::Result<Vec<GrothProof>>
+        + Send
+        + Sync
+        + 'static,
+{
+    listener.set_nonblocking(true)?;
+    let prove = Arc::new(prove);
+    let permits = Arc::new(Semaphore::new(MAX_CONCURRENT_PROVING));
+    let make_service = make_service_fn(move |_conn| {
+        let prove = prove.clone();
+        let permits = permits.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request| {
+                handle_request(prove.clone(), permits.clone(), request)
+            }))
+        }
+    });
+
+    Server::from_tcp(listener)?
+        .serve(make_service)
+        .with_graceful_shutdown(shutdown)
+        .await?;
+
+    Ok(())
+}
+
+async fn handle_request<F>(
+    prove: Arc<F>,
+    permits: Arc<Semaphore>,
+    request: Request<Body>,
+) -> Result<Response<Body>, Infallible>
+where
+    F: Fn(Vec<ProofRequest>) -> eyre::Result<Vec<GrothProof>>
+        + Send
+        + Sync
+        + 'static,
+{
+    if request.method() != Method::POST || request.uri().path() != "/prove" {
+        let mut response = Response::new(Body::empty());
+        *response.status_mut() = StatusCode::NOT_FOUND;
+        return Ok(response);
+    }
+
+    // Turn the request away before reading its body if the prover is busy
+    let Ok(permit) = permits.try_acquire_owned() else {
+        tracing::warn!("Rejecting MASP proof requests, the prover is busy");
+        let mut response = Response::new(Body::empty());
+        *response.status_mut() = StatusCode::SERVICE_UNAVAILABLE;
+        return Ok(response);
+    };
+
+    let response: ProofResponse = match read_body(request.into_body()).await {
+        Ok(payload) => match decode_requests(&payload) {
+            Ok(requests) => {
+                tracing::debug!("Proving {} MASP circuits", requests.len());
+                tokio::task::spawn_blocking(move || {
+                    let _permit = permit;
+                    prove(requests)
+                })
+                .await
+                .map_err(|err| err.to_string())
+                .and_then(|proofs| proofs.map_err(|err| err.to_string()))
+            }
+            Err(err) => Err(err),
+        },
+        Err(err) => Err(err),
+    };
+
+    if let Err(err) = &response {
+        tracing::warn!("Failed to serve MASP proof requests: {err}");
+    }
+
+    Ok(Response::new(Body::from(response.serialize_to_vec())))
+}
+
+/// Read the body of a request, failing if it exceeds
+/// [`MAX_REQUEST_BODY_SIZE`].
+async fn read_body(mut body: Body) -> Result<Vec<u8>, String> {
+    let too_large = || {
+        format!(
+            "The request body exceeds the maximum size of \
+             {MAX_REQUEST_BODY_SIZE} bytes"
+        )
+    };
+    let size_hint =
+        usize::try_from(body.size_hint().lower()).map_err(|_| too_large())?;
+    if size_hint > MAX_REQUEST_BODY_SIZE {
+        return Err(too_large());
+    }
+
+    let mut payload = Vec::with_capacity(size_hint);
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk
+            .map_err(|err| format!("Failed to read the request body: {err}"))?;
+        if chunk.len() > MAX_REQUEST_BODY_SIZE.saturating_sub(payload.len()) {
+            return Err(too_large());
+        }
+        payload.extend_from_slice(&chunk);
+    }
+    Ok(payload)
+}
+
+/// Decode a batch of proof requests, failing if it holds more than
+/// [`MAX_PROOF_REQUESTS`] requests.
+fn decode_requests(payload: &[u8]) -> Result<Vec<ProofRequest>, String> {
+    // Check the length prefix of the batch before decoding its elements
+    let mut reader = payload;
+    let len = u32::deserialize(&mut reader)
+        .map_err(|err| format!("Invalid MASP proof requests: {err}"))?;
+    if usize::try_from(len).map_or(true, |len| len > MAX_PROOF_REQUESTS) {
+        return Err(format!(
+            "The request holds {len} MASP circuits, more than the maximum of \
+             {MAX_PROOF_REQUESTS}"
+        ));
+    }
+    Vec::<ProofRequest>::try_from_slice(payload)
+        .map_err(|err| format!("Invalid MASP proof requests: {err}"))
+}
+
+#[cfg(test)]
+#[allow(clippy::arithmetic_side_effects)]
+mod test {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_read_body_size_limit() {
+        let payload = vec![0u8; MAX_REQUEST_BODY_SIZE];
+        assert_eq!(read_body(Body::from(payload.clone())).await, Ok(payload));
+
+        let oversized = vec![0u8; MAX_REQUEST_BODY_SIZE + 1];
+        assert!(read_body(Body::from(oversized)).await.is_err());
+
+        // The limit must also hold for streamed bodies without a size hint
+        let (mut sender, body) = Body::channel();
+        tokio::spawn(async move {
+            for _ in 0..=MAX_REQUEST_BODY_SIZE / 1024 {
+                if sender.send_data(vec![0u8; 1024].into()).await.is_err() {
+                    break;
+                }
+            }
+        });
+        assert!(read_body(body).await.is_err());
+    }
+
+    #[test]
+    fn test_decode_requests_batch_limit() {
+        let empty = Vec::<ProofRequest>::new().serialize_to_vec();
+        assert!(decode_requests(&empty).unwrap().is_empty());
+
+        // Only the length prefix is needed to reject the batch
+        let too_many = u32::try_from(MAX_PROOF_REQUESTS + 1)
+            .unwrap()
+            .serialize_to_vec();
+        let err = decode_requests(&too_many).unwrap_err();
+        assert!(err.contains("more than the maximum"));
+
+        assert!(decode_requests(&[]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_proving_limit() {
+        let prove = Arc::new(
+            |_requests: Vec<ProofRequest>| -> eyre::Result<Vec<GrothProof>> {
+                Ok(vec![])
+            },
+        );
+        let permits = Arc::new(Semaphore::new(1));
+        let request = || {
+            Request::post("/prove")
+                .body(Body::from(Vec::<ProofRequest>::new().serialize_to_vec()))
+                .unwrap()
+        };
+
+        // A busy prover turns requests away
+        let busy = permits.clone().try_acquire_owned().unwrap();
+        let response =
+            handle_request(prove.clone(), permits.clone(), request())
+                .await
+                .unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        // The permit of a request is released once it is proven
+        drop(busy);
+        for _ in 0..2 {
+            let response =
+                handle_request(prove.clone(), permits.clone(), request())
+                    .await
+                    .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let body =
+                hyper::body::to_bytes(response.into_body()).await.unwrap();
+            assert_eq!(
+                ProofResponse::try_from_slice(&body).unwrap(),
+                Ok(vec![])
+            );
+        }
+    }
+}
@@ -0,0 +1,450 @@
+//! A reference implementation of a remote signer, serving signature requests
+//! with keys loaded from a wallet.
+//!
+//! The signer speaks the protocol described in
+//! [`namada_sdk::signing::remote`], and only signs the transactions that
+//! satisfy its [`SignerPolicy`]. The human-readable summaries of the signed
+//! transactions are logged.
+//!
+//! The protocol is neither authenticated nor encrypted, hence the signer only
+//! listens on loopback addresses or on Unix domain sockets that only its user
+//! can connect to. Clients on other hosts must reach it through an
+//! authenticated tunnel, e.g. an SSH port forwarding.
+
+use std::future::Future;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use namada_sdk::chain::ChainId;
+use namada_sdk::collections::{HashMap, HashSet};
+use namada_sdk::control_flow::install_shutdown_signal;
+use namada_sdk::hash::Hash;
+use namada_sdk::key::{common, RefTo};
+use namada_sdk::signing::remote::{
+    read_message, sign_request, write_message, RemoteSignerAddr, SignRequest,
+    SignerRequest, SignerResponse,
+};
+use namada_sdk::signing::Signable;
+use namada_sdk::tx::Section;
+use tokio::io::{AsyncRead, AsyncWrite};
+
+use crate::cli::args;
+use crate::wallet;
+use crate::wasm_loader::Checksums;
+
+/// The local policy that the signature requests must satisfy
+#[derive(Clone, Debug, Default)]
+pub struct SignerPolicy {
+    /// The only chain for which transactions may be signed
+    pub chain_id: Option<ChainId>,
+    /// The hashes of the only transaction codes that may be signed. Any code
+    /// may be signed if empty.
+    pub allowed_code_hashes: HashSet<Hash>,
+    /// The maximum gas limit of the wrappers that may be signed
+    pub max_gas_limit: Option<u64>,
+}
+
+impl SignerPolicy {
+    /// Check that the given signature request satisfies this policy
+    pub fn check(&self, request: &SignRequest) -> Result<(), String> {
+        let header = request.tx.header();
+        if let Some(chain_id) = &self.chain_id {
+            if header.chain_id != *chain_id {
+                return Err(format!(
+                    "Transaction is for chain {}, only {chain_id} is allowed",
+                    header.chain_id
+                ));
+            }
+        }
+        if !self.allowed_code_hashes.is_empty() {
+            for cmt in request.tx.commitments() {
+                // The tags of the codes are chosen by the client, so only
+                // their hashes can be trusted
+                let code_hash = match request
+                    .tx
+                    .get_section(cmt.code_sechash())
+                    .as_deref()
+                {
+                    Some(Section::Code(code)) => code.code.hash(),
+                    _ => {
+                        return Err("Transaction code is missing".to_string());
+                    }
+                };
+                if !self.allowed_code_hashes.contains(&code_hash) {
+                    return Err(format!(
+                        "Transaction code {code_hash} is not allowed"
+                    ));
+                }
+            }
+        }
+        if let (Some(max_gas_limit), Signable::FeeRawHeader) =
+            (self.max_gas_limit, request.parts)
+        {
+            let wrapper = header
+                .wrapper()
+                .ok_or_else(|| "Fee header is missing".to_string())?;
+            let gas_limit = u64::from(wrapper.gas_limit);
+            if gas_limit > max_gas_limit {
+                return Err(format!(
+                    "Gas limit {gas_limit} exceeds the maximum of \
+                     {max_gas_limit}"
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Resolve the allowed transactions, given either as code hashes or as the
+/// names of the WASMs listed in the given checksums, to their code hashes.
+pub fn resolve_allowed_txs(
+    allowed_txs: &[String],
+    checksums: Option<&Checksums>,
+) -> eyre::Result<HashSet<Hash>> {
+    allowed_txs
+        .iter()
+        .map(|tx| {
+            if let Ok(code_hash) = Hash::from_str(tx) {
+                return Ok(code_hash);
+            }
+            let checksums = checksums.ok_or_else(|| {
+                eyre::eyre!(
+                    "The WASM checksums are required to resolve the code hash \
+                     of {tx}"
+                )
+            })?;
+            // The full names of the WASMs embed their code hash, e.g.
+            // `tx_transfer.{code hash}.wasm`
+            checksums
+                .0
+                .get(tx)
+                .and_then(|full_name| full_name.split('.').nth(1))
+                .and_then(|code_hash| Hash::from_str(code_hash).ok())
+                .ok_or_else(|| {
+                    eyre::eyre!("No valid WASM checksum found for {tx}")
+                })
+        })
+        .collect()
+}
+
+/// Answer a request with the given keys, subject to the given policy
+pub fn handle_request(
+    keys: &HashMap<common::PublicKey, common::SecretKey>,
+    policy: &SignerPolicy,
+    request: SignerRequest,
+) -> SignerResponse {
+    match request {
+        SignerRequest::PublicKeys => {
+            SignerResponse::PublicKeys(keys.keys().cloned().collect())
+        }
+        SignerRequest::Sign(request) => {
+            let Some(secret_key) = keys.get(&request.public_key) else {
+                return SignerResponse::Rejected(format!(
+                    "Unknown key {}",
+                    request.public_key
+                ));
+            };
+            if let Err(reason) = policy.check(&request) {
+                tracing::warn!(
+                    "Refused to sign with {}: {reason}",
+                    request.public_key
+                );
+                return SignerResponse::Rejected(reason);
+            }
+            tracing::info!(
+                "Signing {:?} of transaction with {}:\n{}",
+                request.parts,
+                request.public_key,
+                request.summary.join("\n")
+            );
+            SignerResponse::Signatures(sign_request(&request, secret_key))
+        }
+    }
+}
+
+/// Run the remote signer until an interrupt signal is received.
+pub async fn run(args: args::RemoteSignerServer) -> eyre::Result<()> {
+    let mut wallet = wallet::load(&args.wallet_dir).map_err(|err| {
+        eyre::eyre!(
+            "Failed to load the wallet from {}: {err}",
+            args.wallet_dir.to_string_lossy()
+        )
+    })?;
+    let mut keys = HashMap::new();
+    for alias in &args.keys {
+        let secret_key =
+            wallet.find_secret_key(alias, None).map_err(|err| {
+                eyre::eyre!("Failed to load the key {alias}: {err}")
+            })?;
+        keys.insert(secret_key.ref_to(), secret_key);
+    }
+    let checksums = args
+        .wasm_checksums_path
+        .as_ref()
+        .map(Checksums::read_checksums_file)
+        .transpose()?;
+    let policy = SignerPolicy {
+        chain_id: args.chain_id,
+        allowed_code_hashes: resolve_allowed_txs(
+            &args.allowed_txs,
+            checksums.as_ref(),
+        )?,
+        max_gas_limit: args.max_gas_limit,
+    };
+
+    let listener = Listener::bind(&args.listen_addr.parse()?).await?;
+    tracing::info!(
+        "Remote signer listening on {} with {} keys",
+        args.listen_addr,
+        keys.len()
+    );
+
+    let mut shutdown_signal = install_shutdown_signal(true);
+    serve(
+        listener,
+        move |request| handle_request(&keys, &policy, request),
+        async move { shutdown_signal.wait_for_shutdown().await },
+    )
+    .await
+}
+
+/// A listener for the connections of remote signer clients
+pub enum Listener {
+    /// A TCP listener
+    Tcp(tokio::net::TcpListener),
+    /// A Unix domain socket listener
+    #[cfg(unix)]
+    Unix(tokio::net::UnixListener),
+}
+
+impl Listener {
+    /// Listen on the given address. As the protocol is not authenticated,
+    /// only loopback TCP addresses are allowed, and Unix domain sockets are
+    /// only made accessible to the current user.
+    pub async fn bind(addr: &RemoteSignerAddr) -> std::io::Result<Self> {
+        match addr {
+            RemoteSignerAddr::Tcp(addr) => {
+                let addrs: Vec<_> =
+                    tokio::net::lookup_host(addr.as_str()).await?.collect();
+                if let Some(addr) =
+                    addrs.iter().find(|addr| !addr.ip().is_loopback())
+                {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::PermissionDenied,
+                        format!(
+                            "Refusing to listen on the non-loopback address \
+                             {addr}, as remote signer requests are not \
+                             authenticated"
+                        ),
+                    ));
+                }
+                Ok(Self::Tcp(tokio::net::TcpListener::bind(&addrs[..]).await?))
+            }
+            #[cfg(unix)]
+            RemoteSignerAddr::Unix(path) => {
+                use std::os::unix::fs::PermissionsExt;
+
+                let listener = tokio::net::UnixListener::bind(path)?;
+                std::fs::set_permissions(
+                    path,
+                    std::fs::Permissions::from_mode(0o600),
+                )?;
+                Ok(Self::Unix(listener))
+            }
+        }
+    }
+}
+
+/// Serve the requests received on the given listener with the provided
+/// handler, until the `shutdown` future resolves.
+pub async fn serve<F>(
+    listener: Listener,
+    handler: F,
+    shutdown: impl Future<Output = ()>,
+) -> eyre::Result<()>
+where
+    F: Fn(SignerRequest) -> SignerResponse + Send + Sync + 'static,
+{
+    let handler = Arc::new(handler);
+    tokio::pin!(shutdown);
+    loop {
+        match &listener {
+            Listener::Tcp(listener) => tokio::select! {
+                _ = &mut shutdown => break,
+                conn = listener.accept() => {
+                    let (stream, _) = conn?;
+                    tokio::spawn(handle_connection(stream, handler.clone()));
+                }
+            },
+            #[cfg(unix)]
+            Listener::Unix(listener) => tokio::select! {
+                _ = &mut shutdown => break,
+                conn = listener.accept() => {
+                    let (stream, _) = conn?;
+                    tokio::spawn(handle_connection(stream, handler.clone()));
+                }
+            },
+        }
+    }
+    Ok(())
+}
+
+async fn handle_connection<S, F>(mut stream: S, handler: Arc<F>)
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+    F: Fn(SignerRequest) -> SignerResponse + Send + Sync + 'static,
+{
+    let response = match read_message(&mut stream).await {
+        Ok(request) => handler(request),
+        Err(err) => {
+            tracing::warn!("Failed to read a remote signer request: {err}");
+            return;
+        }
+    };
+    if let Err(err) = write_message(&mut stream, &response).await {
+        tracing::warn!("Failed to write a remote signer response: {err}");
+    }
+}
+
+#[cfg(test)]
+mod test_remote_signer {
+    use namada_sdk::address::testing::established_address_1;
+    use namada_sdk::key::testing::keypair_1;
+    use namada_sdk::token::{Amount, DenominatedAmount};
+    use namada_sdk::tx::data::{Fee, TxType, WrapperTx};
+    use namada_sdk::tx::Tx;
+
+    use super::*;
+
+    const TRANSFER_CODE: &[u8] = b"transfer";
+    const BOND_CODE: &[u8] = b"bond";
+
+    fn sign_request(parts: Signable, gas_limit: u64) -> SignRequest {
+        let keypair = keypair_1();
+        let mut tx = Tx::from_type(TxType::Wrapper(Box::new(WrapperTx::new(
+            Fee {
+                amount_per_gas_unit: DenominatedAmount::native(Amount::zero()),
+                token: established_address_1(),
+            },
+            keypair.ref_to(),
+            gas_limit.into(),
+        ))));
+        tx.add_code(
+            TRANSFER_CODE.to_vec(),
+            Some("tx_transfer.wasm".to_string()),
+        );
+        SignRequest {
+            tx,
+            public_key: keypair.ref_to(),
+            parts,
+            summary: vec![],
+        }
+    }
+
+    #[test]
+    fn test_signer_policy() {
+        let request = sign_request(Signable::FeeRawHeader, 100_000);
+        SignerPolicy::default().check(&request).unwrap();
+
+        let policy = SignerPolicy {
+            chain_id: Some(request.tx.header.chain_id.clone()),
+            allowed_code_hashes: [Hash::sha256(TRANSFER_CODE)].into(),
+            max_gas_limit: Some(100_000),
+        };
+        policy.check(&request).unwrap();
+
+        let policy = SignerPolicy {
+            chain_id: Some(ChainId("other-chain".to_string())),
+            ..Default::default()
+        };
+        assert!(policy.check(&request).is_err());
+
+        let policy = SignerPolicy {
+            allowed_code_hashes: [Hash::sha256(BOND_CODE)].into(),
+            ..Default::default()
+        };
+        assert!(policy.check(&request).is_err());
+        // The tag of the code doesn't matter, only its hash does
+        let mut mislabeled = sign_request(Signable::FeeRawHeader, 100_000);
+        mislabeled
+            .tx
+            .add_code(TRANSFER_CODE.to_vec(), Some("tx_bond.wasm".to_string()));
+        assert!(policy.check(&mislabeled).is_err());
+
+        let policy = SignerPolicy {
+            max_gas_limit: Some(50_000),
+            ..Default::default()
+        };
+        assert!(policy.check(&request).is_err());
+        // The gas limit only matters when signing the fee header
+        let request = sign_request(Signable::RawHeader, 100_000);
+        policy.check(&request).unwrap();
+    }
+
+    #[test]
+    fn test_handle_request() {
+        let keypair = keypair_1();
+        let keys: HashMap<_, _> = [(keypair.ref_to(), keypair)].into();
+        let policy = SignerPolicy::default();
+
+        match handle_request(&keys, &policy, SignerRequest::PublicKeys) {
+            SignerResponse::PublicKeys(pks) => {
+                assert_eq!(pks, keys.keys().cloned().collect::<Vec<_>>())
+            }
+            _ => panic!("Unexpected response"),
+        }
+
+        let request = sign_request(Signable::FeeRawHeader, 100_000);
+        match handle_request(&keys, &policy, SignerRequest::Sign(request)) {
+            SignerResponse::Signatures(authorizations) => {
+                assert_eq!(authorizations.len(), 2)
+            }
+            _ => panic!("Unexpected response"),
+        }
+
+        let mut request = sign_request(Signable::RawHeader, 100_000);
+        request.public_key = namada_sdk::key::testing::keypair_2().ref_to();
+        assert!(matches!(
+            handle_request(&keys, &policy, SignerRequest::Sign(request)),
+            SignerResponse::Rejected(_)
+        ));
+    }
+
+    #[test]
+    fn test_resolve_allowed_txs() {
+        let transfer_hash = Hash::sha256(TRANSFER_CODE);
+        let checksums = Checksums(
+            [(
+                "tx_transfer.wasm".to_string(),
+                format!(
+                    "tx_transfer.{}.wasm",
+                    transfer_hash.to_string().to_lowercase()
+                ),
+            )]
+            .into(),
+        );
+        let bond_hash = Hash::sha256(BOND_CODE);
+        let allowed_txs =
+            vec!["tx_transfer.wasm".to_string(), bond_hash.to_string()];
+
+        assert_eq!(
+            resolve_allowed_txs(&allowed_txs, Some(&checksums)).unwrap(),
+            [transfer_hash, bond_hash].into()
+        );
+        // The names can't be resolved without the checksums
+        assert!(resolve_allowed_txs(&allowed_txs, None).is_err());
+        assert!(resolve_allowed_txs(
+            &["tx_bond.wasm".to_string()],
+            Some(&checksums)
+        )
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_listen_on_loopback_only() {
+        let addr = "tcp://0.0.0.0:0".parse().unwrap();
+        assert!(Listener::bind(&addr).await.is_err());
+        let addr = "tcp://127.0.0.1:0".parse().unwrap();
+        assert!(Listener::bind(&addr).await.is_ok());
+    }
+}
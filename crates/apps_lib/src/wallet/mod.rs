@@ -11,11 +11,14 @@ use std::{env, fs};
 use namada_sdk::bip39::{Language, Mnemonic};
 use namada_sdk::key::*;
 pub use namada_sdk::wallet::alias::Alias;
+use namada_sdk::wallet::audit::TxSummary;
 use namada_sdk::wallet::store::Store;
 use namada_sdk::wallet::{
     ConfirmationResponse, FindKeyError, LoadStoreError, Wallet, WalletIo,
 };
-pub use namada_sdk::wallet::{ValidatorData, ValidatorKeys};
+pub use namada_sdk::wallet::{
+    StoredValidatorKeys, ValidatorData, ValidatorKeys,
+};
 use namada_wallet::fs::FsWalletStorage;
 use rand_core::OsRng;
 pub use store::wallet_file;
@@ -122,6 +125,57 @@ impl WalletIo for CliWalletUtils {
         })
     }
 
+    fn read_slip39_shares() -> Vec<Zeroizing<String>> {
+        println!(
+            "Input the SLIP-39 share mnemonics, one per line, followed by an \
+             empty line."
+        );
+        let mut shares = vec![];
+        loop {
+            let share =
+                get_secure_user_input(format!("Share {}:", shares.len() + 1))
+                    .unwrap_or_else(|e| {
+                        eprintln!("{}", e);
+                        eprintln!("Action cancelled, no changes persisted.");
+                        cli::safe_exit(1)
+                    });
+            if share.trim().is_empty() {
+                return shares;
+            }
+            shares.push(Zeroizing::new(share.trim().to_owned()));
+        }
+    }
+
+    fn confirm_key_usage(alias: &Alias, summary: &TxSummary) -> bool {
+        println!(
+            "The policy of key \"{}\" requires confirming its use.",
+            alias
+        );
+        for action in &summary.actions {
+            println!("  {}", action);
+        }
+        for (token, amount) in &summary.amounts {
+            println!("  Spending {} of {}", amount, token);
+        }
+        print!("Sign with key \"{}\"? (y/n): ", alias);
+        io::stdout().flush().unwrap();
+
+        let mut buffer = String::new();
+        match io::stdin().read_line(&mut buffer) {
+            Ok(size) if size > 0 => match buffer.trim() {
+                "y" | "Y" => true,
+                "n" | "N" => false,
+                // Input is senseless fall through to repeat prompt
+                _ => {
+                    println!("Invalid option, try again.");
+                    Self::confirm_key_usage(alias, summary)
+                }
+            },
+            // Refuse if the input is closed
+            _ => false,
+        }
+    }
+
     // The given alias has been selected but conflicts with another alias in
     // the store. Offer the user to either replace existing mapping, alter the
     // chosen alias to a name of their choosing, or cancel the aliasing.
@@ -235,7 +289,7 @@ fn find_secret_key<F, U>(
     extract_key: F,
 ) -> Result<Option<common::SecretKey>, FindKeyError>
 where
-    F: Fn(&ValidatorData) -> common::SecretKey,
+    F: Fn(&ValidatorData) -> Option<common::SecretKey>,
     U: WalletIo,
 {
     maybe_pk
@@ -245,7 +299,7 @@ where
                 // TODO(namada#3251): optionally encrypt validator keys
                 .find_key_by_pkh(&pkh, None)
                 .ok()
-                .or_else(|| wallet.get_validator_data().map(extract_key))
+                .or_else(|| wallet.get_validator_data().and_then(extract_key))
                 .ok_or_else(|| FindKeyError::KeyNotFound(pkh.to_string()))
         })
         .transpose()
@@ -258,25 +312,23 @@ pub fn save(wallet: &Wallet<CliWalletUtils>) -> std::io::Result<()> {
         .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err))
 }
 
-/// Load a wallet from the store file.
+/// Load a wallet from the store file, prompting for its password if it is
+/// encrypted as a whole.
 pub fn load(
     store_dir: &Path,
 ) -> Result<Wallet<CliWalletUtils>, LoadStoreError> {
-    let mut wallet = CliWalletUtils::new(store_dir.to_path_buf());
-    wallet.load()?;
-    Ok(wallet)
+    self::store::load(store_dir)
 }
 
 /// Load a wallet from the store file or create a new wallet without any
 /// keys or addresses.
 pub fn load_or_new(store_dir: &Path) -> Wallet<CliWalletUtils> {
-    let store = self::store::load_or_new(store_dir).unwrap_or_else(|err| {
+    // Go through the wallet rather than the bare store to keep the password
+    // of an encrypted store
+    self::store::load_or_new(store_dir).unwrap_or_else(|err| {
         eprintln!("Unable to load the wallet: {}", err);
         cli::safe_exit(1)
-    });
-    let mut wallet = CliWalletUtils::new(store_dir.to_path_buf());
-    *wallet.store_mut() = store;
-    wallet
+    })
 }
 
 /// Check if a wallet exists in the given store dir.
@@ -2,7 +2,7 @@ use std::path::{Path, PathBuf};
 
 use namada_sdk::key::*;
 use namada_sdk::wallet::{
-    gen_secret_key, LoadStoreError, Store, ValidatorKeys,
+    gen_secret_key, LoadStoreError, ValidatorKeys, Wallet, WalletIo,
 };
 use rand::rngs::OsRng;
 
@@ -17,24 +17,36 @@ pub fn wallet_file(store_dir: impl AsRef<Path>) -> PathBuf {
 }
 
 /// Load the store file or create a new one without any keys or addresses.
-pub fn load_or_new(store_dir: &Path) -> Result<Store, LoadStoreError> {
+pub fn load_or_new(
+    store_dir: &Path,
+) -> Result<Wallet<CliWalletUtils>, LoadStoreError> {
     load(store_dir).or_else(|err| {
         // Only create a new file if not found, otherwise propagate the err
         if let LoadStoreError::NotFound { .. } = &err {
             let wallet = CliWalletUtils::new(store_dir.to_path_buf());
             wallet.save()?;
-            Ok(wallet.into())
+            Ok(wallet)
         } else {
             Err(err)
         }
     })
 }
 
-/// Attempt to load the store file.
-pub fn load(store_dir: &Path) -> Result<Store, LoadStoreError> {
+/// Attempt to load the store file, prompting for its password if it is
+/// encrypted as a whole.
+pub fn load(
+    store_dir: &Path,
+) -> Result<Wallet<CliWalletUtils>, LoadStoreError> {
     let mut wallet = CliWalletUtils::new(store_dir.to_path_buf());
-    wallet.load()?;
-    Ok(wallet.into())
+    match wallet.load() {
+        Err(LoadStoreError::PasswordRequired { .. }) => {
+            let password =
+                CliWalletUtils::read_password(false, Some("the wallet store"));
+            wallet.load_with_password(password)?;
+        }
+        result => result?,
+    }
+    Ok(wallet)
 }
 
 /// Generate keypair for signing protocol txs and for the DKG
@@ -64,6 +76,7 @@ pub fn gen_validator_keys(
 #[cfg(test)]
 mod test_wallet {
     use namada_sdk::address::Address;
+    use namada_sdk::wallet::Store;
 
     use super::*;
 
@@ -1,6 +1,7 @@
 use criterion::{criterion_group, criterion_main, Criterion};
 use namada_apps_lib::account::AccountPublicKeysMap;
 use namada_apps_lib::collections::{HashMap, HashSet};
+use namada_apps_lib::key::{common, ed25519, secp256k1, RefTo, SigScheme};
 use namada_apps_lib::storage::DB;
 use namada_apps_lib::token::{Amount, Transfer};
 use namada_apps_lib::tx::Authorization;
@@ -11,6 +12,8 @@ use namada_node::bench_utils::{
     TX_UPDATE_ACCOUNT_WASM, VP_USER_WASM, WASM_DIR,
 };
 use namada_vm::wasm::TxCache;
+use namada_vp::vp_host_fns::HashFunction;
+use rand_core::OsRng;
 
 // Benchmarks the validation of a single signature on a single `Section` of a
 // transaction
@@ -330,6 +333,65 @@ fn storage_write(c: &mut Criterion) {
     group.finish();
 }
 
+// Benchmarks the hash functions exposed to the wasm guests. The fixed cost of
+// hashing is the intercept of the costs of the different input sizes
+fn hash(c: &mut Criterion) {
+    let mut group = c.benchmark_group("hash");
+
+    for (function, name) in [
+        (HashFunction::Sha256, "sha256"),
+        (HashFunction::Keccak256, "keccak256"),
+        (HashFunction::Blake2b, "blake2b"),
+    ] {
+        for len in [32_u64, 1_024, 16_384, 1_048_576] {
+            let data = vec![1_u8; len as usize];
+            group.throughput(criterion::Throughput::Bytes(len));
+            group.bench_function(format!("{name}, size: {len}"), |b| {
+                b.iter(|| function.digest(&data))
+            });
+        }
+    }
+
+    group.finish();
+}
+
+// Benchmarks the verification of a signature produced outside of Namada, for
+// the signature schemes exposed to the wasm guests: ed25519 signatures over raw
+// messages of different sizes, whose fixed cost is the intercept of the costs
+// and whose cost per byte is their slope, and secp256k1 signatures over a
+// prehash
+fn signature_verification(c: &mut Criterion) {
+    let mut group = c.benchmark_group("signature_verification");
+
+    let keypair = ed25519::SigScheme::generate(&mut OsRng);
+    let pk = common::PublicKey::Ed25519(keypair.ref_to());
+    for len in [32_u64, 1_024, 16_384] {
+        let msg = vec![1_u8; len as usize];
+        let sig = common::Signature::Ed25519(ed25519::Signature(
+            keypair.0.sign(&msg),
+        ));
+        group.throughput(criterion::Throughput::Bytes(len));
+        group.bench_function(format!("ed25519, size: {len}"), |b| {
+            b.iter(|| common::verify_raw_signature(&pk, &msg, &sig).unwrap())
+        });
+    }
+
+    let keypair = secp256k1::SigScheme::generate(&mut OsRng);
+    let pk = common::PublicKey::Secp256k1(keypair.ref_to());
+    let prehash = HashFunction::Keccak256.digest(b"attestation");
+    let (sig, recovery_id) = k256::ecdsa::SigningKey::from(keypair.0.as_ref())
+        .sign_prehash_recoverable(&prehash)
+        .unwrap();
+    let sig =
+        common::Signature::Secp256k1(secp256k1::Signature(sig, recovery_id));
+    group.throughput(criterion::Throughput::Elements(1));
+    group.bench_function("secp256k1", |b| {
+        b.iter(|| common::verify_raw_signature(&pk, &prehash, &sig).unwrap())
+    });
+
+    group.finish();
+}
+
 criterion_group!(
     host_env,
     tx_section_signature_validation,
@@ -339,5 +401,7 @@ criterion_group!(
     storage_read,
     write_log_write,
     storage_write,
+    hash,
+    signature_verification,
 );
 criterion_main!(host_env);
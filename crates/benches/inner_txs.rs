@@ -0,0 +1,91 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use namada_apps_lib::address::{self, Address};
+use namada_apps_lib::key::{common, RefTo};
+use namada_apps_lib::token::{Amount, Transfer};
+use namada_apps_lib::tx::Tx;
+use namada_apps_lib::wallet::defaults;
+use namada_node::bench_utils::{BenchShell, BenchShellInner, TX_TRANSFER_WASM};
+
+fn transfer_batches(
+    shell: &BenchShellInner,
+    transfers: Vec<(Address, Address, common::SecretKey)>,
+) -> Vec<Tx> {
+    transfers
+        .into_iter()
+        .map(|(source, target, signer)| {
+            shell
+                .generate_tx(
+                    TX_TRANSFER_WASM,
+                    Transfer::default()
+                        .transfer(
+                            source,
+                            target,
+                            address::testing::nam(),
+                            Amount::native_whole(1).native_denominated(),
+                        )
+                        .unwrap(),
+                    None,
+                    None,
+                    vec![&signer],
+                )
+                .tx
+        })
+        .collect()
+}
+
+fn inner_txs(c: &mut Criterion) {
+    let mut group = c.benchmark_group("inner_txs");
+    let bench_shell = BenchShell::default();
+    let mut shell = bench_shell.write();
+
+    // Transfers between distinct accounts, whose speculative execution is
+    // never invalidated
+    let independent = transfer_batches(
+        &shell,
+        vec![
+            (
+                defaults::albert_address(),
+                defaults::daewon_address(),
+                defaults::albert_keypair(),
+            ),
+            (
+                defaults::bertha_address(),
+                defaults::ester_address(),
+                defaults::bertha_keypair(),
+            ),
+            (
+                defaults::christel_address(),
+                (&defaults::frank_keypair().ref_to()).into(),
+                defaults::christel_keypair(),
+            ),
+        ],
+    );
+    // Transfers from the same account, which have to be re-executed in order
+    let conflicting = transfer_batches(
+        &shell,
+        vec![
+            (
+                defaults::albert_address(),
+                defaults::bertha_address(),
+                defaults::albert_keypair(),
+            );
+            3
+        ],
+    );
+
+    for (batches, bench_name) in [
+        (&independent, "independent_transfers"),
+        (&conflicting, "conflicting_transfers"),
+    ] {
+        for (parallel, mode) in [(false, "sequential"), (true, "parallel")] {
+            group.bench_function(format!("{bench_name}_{mode}"), |b| {
+                b.iter(|| shell.execute_inner_tx_batches(batches, parallel))
+            });
+        }
+    }
+
+    group.finish();
+}
+
+criterion_group!(parallel_inner_txs, inner_txs);
+criterion_main!(parallel_inner_txs);
@@ -42,7 +42,9 @@ use namada_apps_lib::masp_primitives::merkle_tree::CommitmentTree;
 use namada_apps_lib::masp_primitives::transaction::Transaction;
 use namada_apps_lib::masp_proofs::sapling::SaplingVerificationContextInner;
 use namada_apps_lib::proof_of_stake::KeySeg;
-use namada_apps_lib::state::{Epoch, StorageRead, StorageWrite, TxIndex};
+use namada_apps_lib::state::{
+    Epoch, StorageRead, StorageWrite, TxIndex, VpEvaluation,
+};
 use namada_apps_lib::token::masp::{
     partial_deauthorize, preload_verifying_keys, PVKs,
 };
@@ -1799,6 +1801,47 @@ fn ibc_vp_execute_action(c: &mut Criterion) {
     group.finish();
 }
 
+fn vp_evaluation(c: &mut Criterion) {
+    let mut group = c.benchmark_group("vp_evaluation");
+
+    // Txs triggering the VPs of several verifiers
+    for bench_name in
+        ["outgoing_transfer", "outgoing_shielded_action", "shielding"]
+    {
+        let (shielded_ctx, verifiers_from_tx, signed_tx) = match bench_name {
+            "shielding" => setup_storage_for_masp_verification(bench_name),
+            _ => {
+                let (shielded_ctx, signed_tx) =
+                    prepare_ibc_tx_and_ctx(bench_name);
+                let verifiers_from_tx =
+                    shielded_ctx.shell.write().execute_tx(&signed_tx.to_ref());
+                (shielded_ctx, verifiers_from_tx, signed_tx)
+            }
+        };
+
+        for (vp_evaluation, mode) in [
+            (VpEvaluation::Sequential, "sequential"),
+            (VpEvaluation::Parallel, "parallel"),
+        ] {
+            shielded_ctx.shell.write().state.in_mem_mut().vp_evaluation =
+                vp_evaluation;
+            let shell = shielded_ctx.shell.read();
+            group.bench_function(format!("{bench_name}_{mode}"), |b| {
+                b.iter(|| {
+                    assert!(
+                        shell
+                            .check_vps(&signed_tx.to_ref(), &verifiers_from_tx)
+                            .rejected_vps
+                            .is_empty()
+                    )
+                })
+            });
+        }
+    }
+
+    group.finish();
+}
+
 criterion_group!(
     native_vps,
     governance,
@@ -1821,6 +1864,7 @@ criterion_group!(
     parameters,
     pos,
     ibc_vp_validate_action,
-    ibc_vp_execute_action
+    ibc_vp_execute_action,
+    vp_evaluation
 );
 criterion_main!(native_vps);
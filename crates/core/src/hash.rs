@@ -252,8 +252,10 @@ impl From<&H256> for Hash {
     }
 }
 
-/// The storage hasher used for the merkle tree.
-pub trait StorageHasher: Hasher + fmt::Debug + Default {
+/// The storage hasher used for the merkle tree. The in-memory state is shared
+/// with the forks of the write-log that execute transactions in parallel,
+/// hence it must be `Send`.
+pub trait StorageHasher: Hasher + fmt::Debug + Default + Send {
     /// Hash the value to store
     fn hash(value: impl AsRef<[u8]>) -> H256;
 }
@@ -20,6 +20,7 @@ use super::{
 };
 use crate::borsh::BorshSerializeExt;
 use crate::ethereum_events::EthAddress;
+use crate::hash::Sha256Hasher;
 use crate::key::{SignableBytes, StorageHasher};
 use crate::{impl_display_and_from_str_via_format, string_encoding};
 
@@ -242,6 +243,69 @@ impl SecretKey {
     }
 }
 
+/// A key that can produce signatures without necessarily exposing its secret
+/// material, e.g. a key that lives in a hardware security module, in which
+/// case signing may fail.
+pub trait SigningKey {
+    /// The error returned when the key fails to sign
+    type Error: std::fmt::Display;
+
+    /// Get the public key of this signing key
+    fn public_key(&self) -> PublicKey;
+
+    /// Sign the given data, using the given hasher to compress it first
+    fn try_sign_with_hasher<H>(
+        &self,
+        data: impl SignableBytes,
+    ) -> Result<Signature, Self::Error>
+    where
+        H: 'static + StorageHasher;
+
+    /// Sign the given data, using a SHA256 hasher to compress it first
+    fn try_sign(
+        &self,
+        data: impl SignableBytes,
+    ) -> Result<Signature, Self::Error> {
+        self.try_sign_with_hasher::<Sha256Hasher>(data)
+    }
+}
+
+impl SigningKey for SecretKey {
+    type Error = std::convert::Infallible;
+
+    fn public_key(&self) -> PublicKey {
+        self.ref_to()
+    }
+
+    fn try_sign_with_hasher<H>(
+        &self,
+        data: impl SignableBytes,
+    ) -> Result<Signature, Self::Error>
+    where
+        H: 'static + StorageHasher,
+    {
+        Ok(SigScheme::sign_with_hasher::<H>(self, data))
+    }
+}
+
+impl<K: SigningKey + ?Sized> SigningKey for &K {
+    type Error = K::Error;
+
+    fn public_key(&self) -> PublicKey {
+        (*self).public_key()
+    }
+
+    fn try_sign_with_hasher<H>(
+        &self,
+        data: impl SignableBytes,
+    ) -> Result<Signature, Self::Error>
+    where
+        H: 'static + StorageHasher,
+    {
+        (*self).try_sign_with_hasher::<H>(data)
+    }
+}
+
 impl super::SecretKey for SecretKey {
     type PublicKey = PublicKey;
 
@@ -516,6 +580,43 @@ impl super::SigScheme for SigScheme {
     }
 }
 
+/// Verify a signature that was produced outside of Namada over a raw message,
+/// rather than over the hash of some [`SignableBytes`]. Ed25519 signatures
+/// are verified over the message itself, as specified by RFC 8032. Secp256k1
+/// signatures are verified over a 32 bytes prehash that is given as the
+/// message, e.g. the Keccak-256 digest of an Ethereum `personal_sign` message.
+pub fn verify_raw_signature(
+    pk: &PublicKey,
+    msg: &[u8],
+    sig: &Signature,
+) -> Result<(), VerifySigError> {
+    match (pk, sig) {
+        (PublicKey::Ed25519(pk), Signature::Ed25519(sig)) => {
+            pk.0.verify(&sig.0, msg)
+                .map_err(|err| VerifySigError::SigVerifyError(err.to_string()))
+        }
+        (PublicKey::Secp256k1(pk), Signature::Secp256k1(sig)) => {
+            use k256::ecdsa::signature::hazmat::PrehashVerifier;
+
+            if msg.len() != 32 {
+                return Err(VerifySigError::SigVerifyError(format!(
+                    "A secp256k1 signature must be verified over a 32 bytes \
+                     prehash, got {} bytes",
+                    msg.len()
+                )));
+            }
+            k256::ecdsa::VerifyingKey::from(&pk.0)
+                .verify_prehash(msg, &sig.0)
+                .map_err(|err| {
+                    VerifySigError::SigVerifyError(format!(
+                        "Error verifying secp256k1 signature: {err}"
+                    ))
+                })
+        }
+        _ => Err(VerifySigError::MismatchedScheme),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -532,4 +633,73 @@ mod tests {
         println!("Public key: {}", public_key);
         println!("Secret key: {}", secret_key);
     }
+
+    /// An ed25519 signature from the test vectors of RFC 8032 (test 2)
+    #[test]
+    fn test_verify_raw_ed25519_signature() {
+        let pk = HEXLOWER
+            .decode(
+                b"3d4017c3e843895a92b70aa74d1b7ebc\
+                  9c982ccf2ec4968cc0cd55f12af4660c",
+            )
+            .unwrap();
+        let pk = PublicKey::Ed25519(ed25519::PublicKey(
+            ed25519_consensus::VerificationKey::try_from(pk.as_slice())
+                .unwrap(),
+        ));
+        let sig = HEXLOWER
+            .decode(
+                b"92a009a9f0d4cab8720e820b5f642540\
+                  a2b27b5416503f8fb3762223ebdb69da\
+                  085ac1e43e15996e458f3613d0f11d8c\
+                  387b2eaeb4302aeeb00d291612bb0c00",
+            )
+            .unwrap();
+        let sig = Signature::Ed25519(ed25519::Signature(
+            ed25519_consensus::Signature::try_from(sig.as_slice()).unwrap(),
+        ));
+        let msg = [0x72];
+
+        assert!(verify_raw_signature(&pk, &msg, &sig).is_ok());
+        assert!(verify_raw_signature(&pk, &[0x73], &sig).is_err());
+        // The signature is not over the hash of the message
+        assert!(SigScheme::verify_signature(&pk, &msg, &sig).is_err());
+    }
+
+    /// A secp256k1 signature of the message "Some data" produced by
+    /// `web3.eth.accounts.sign`, i.e. over the Keccak-256 digest of the
+    /// Ethereum `personal_sign` message
+    #[test]
+    fn test_verify_raw_secp256k1_signature() {
+        let pk = HEXLOWER
+            .decode(
+                b"024e3b81af9c2234cad09d679ce6035e\
+                  d1392347ce64ce405f5dcd36228a25de6e",
+            )
+            .unwrap();
+        let pk = PublicKey::Secp256k1(secp256k1::PublicKey(
+            k256::PublicKey::from_sec1_bytes(&pk).unwrap(),
+        ));
+        let sig = HEXLOWER
+            .decode(
+                b"b91467e570a6466aa9e9876cbcd013ba\
+                  ba02900b8979d43fe208a4a4f339f5fd\
+                  6007e74cd82e037b800186422fc2da16\
+                  7c747ef045e5d18a5f5d4300f8e1a029",
+            )
+            .unwrap();
+        let sig = Signature::Secp256k1(secp256k1::Signature(
+            k256::ecdsa::Signature::from_slice(&sig).unwrap(),
+            k256::ecdsa::RecoveryId::from_byte(1).unwrap(),
+        ));
+        let prehash = crate::keccak::keccak_hash(
+            b"\x19Ethereum Signed Message:\n9Some data",
+        );
+
+        assert!(verify_raw_signature(&pk, &prehash.0, &sig).is_ok());
+        let other_prehash = crate::keccak::keccak_hash(b"Some data");
+        assert!(verify_raw_signature(&pk, &other_prehash.0, &sig).is_err());
+        // Only a 32 bytes prehash is accepted
+        assert!(verify_raw_signature(&pk, b"Some data", &sig).is_err());
+    }
 }
@@ -89,7 +89,8 @@ pub enum DbColFam {
     STATE,
     /// Diffs
     DIFFS,
-    /// Diffs for rollback (only kept for 1 block)
+    /// Diffs and other data for rollback (only kept for the blocks that may
+    /// be rolled back)
     ROLLBACK,
     /// Replay protection
     REPLAYPROT,
@@ -99,7 +100,8 @@ pub enum DbColFam {
 pub const SUBSPACE_CF: &str = "subspace";
 /// Diffs column family name
 pub const DIFFS_CF: &str = "diffs";
-/// Diffs for rollback (only kept for 1 block) column family name
+/// Diffs and other data for rollback (only kept for the blocks that may be
+/// rolled back) column family name
 pub const ROLLBACK_CF: &str = "rollback";
 /// State column family name
 pub const STATE_CF: &str = "state";
@@ -24,12 +24,13 @@ use crate::storage::proof::BridgePoolRootProof;
 use crate::storage::vote_tallies::{self, BridgePoolRoot};
 
 /// Sign the latest Bridge pool root, and return the associated
-/// vote extension protocol transaction.
+/// vote extension protocol transaction. Failures to sign are logged, and no
+/// vote extension is returned.
 pub fn sign_bridge_pool_root<D, H>(
     state: &WlState<D, H>,
     validator_addr: &Address,
-    eth_hot_key: &common::SecretKey,
-    protocol_key: &common::SecretKey,
+    eth_hot_key: &impl common::SigningKey,
+    protocol_key: &impl common::SigningKey,
 ) -> Option<bridge_pool_roots::SignedVext>
 where
     D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
@@ -41,13 +42,26 @@ where
     let bp_root = state.ethbridge_queries().get_bridge_pool_root().0;
     let nonce = state.ethbridge_queries().get_bridge_pool_nonce().to_bytes();
     let to_sign = keccak_hash([bp_root.as_slice(), nonce.as_slice()].concat());
-    let signed = Signed::<_, SignableEthMessage>::new(eth_hot_key, to_sign);
+    let signed = Signed::<_, SignableEthMessage>::try_new(eth_hot_key, to_sign)
+        .inspect_err(|err| {
+            tracing::error!(
+                "Failed to sign the Bridge pool root with the Ethereum hot \
+                 key: {err}"
+            )
+        })
+        .ok()?;
     let ext = bridge_pool_roots::Vext {
         block_height: state.in_mem().get_last_block_height(),
         validator_addr: validator_addr.clone(),
         sig: signed.sig,
     };
-    Some(ext.sign(protocol_key))
+    ext.try_sign(protocol_key)
+        .inspect_err(|err| {
+            tracing::error!(
+                "Failed to sign the Bridge pool root vote extension: {err}"
+            )
+        })
+        .ok()
 }
 
 /// Applies a tally of signatures on over the Ethereum
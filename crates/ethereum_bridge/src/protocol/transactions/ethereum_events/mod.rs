@@ -41,14 +41,15 @@ impl utils::GetVoters for &HashSet<EthMsgUpdate> {
 }
 
 /// Sign the given Ethereum events, and return the associated
-/// vote extension protocol transaction.
+/// vote extension protocol transaction. Failures to sign are logged, and no
+/// vote extension is returned.
 ///
 /// __INVARIANT__: Assume `ethereum_events` are sorted in ascending
 /// order.
 pub fn sign_ethereum_events<D, H>(
     state: &WlState<D, H>,
     validator_addr: &Address,
-    protocol_key: &common::SecretKey,
+    protocol_key: &impl common::SigningKey,
     ethereum_events: Vec<EthereumEvent>,
 ) -> Option<SignedVext>
 where
@@ -73,7 +74,14 @@ where
         tracing::debug!("New Ethereum events - {:#?}", ext.ethereum_events);
     }
 
-    Some(ext.sign(protocol_key).into())
+    ext.try_sign(protocol_key)
+        .map(SignedVext::from)
+        .inspect_err(|err| {
+            tracing::error!(
+                "Failed to sign the Ethereum events vote extension: {err}"
+            )
+        })
+        .ok()
 }
 
 /// Applies derived state changes to storage, based on Ethereum `events` which
@@ -33,11 +33,12 @@ impl utils::GetVoters for (&validator_set_update::VextDigest, BlockHeight) {
 }
 
 /// Sign the next set of validators, and return the associated
-/// vote extension protocol transaction.
+/// vote extension protocol transaction. Failures to sign are logged, and no
+/// vote extension is returned.
 pub fn sign_validator_set_update<D, H, Gov>(
     state: &WlState<D, H>,
     validator_addr: &Address,
-    eth_hot_key: &common::SecretKey,
+    eth_hot_key: &impl common::SigningKey,
 ) -> Option<validator_set_update::SignedVext>
 where
     D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
@@ -64,7 +65,17 @@ where
                 signing_epoch: state.in_mem().get_current_epoch().0,
             };
 
-            ext.sign(eth_hot_key)
+            ext
+        })
+        .and_then(|ext| {
+            ext.try_sign(eth_hot_key)
+                .inspect_err(|err| {
+                    tracing::error!(
+                        "Failed to sign the validator set update vote \
+                         extension: {err}"
+                    )
+                })
+                .ok()
         })
 }
 
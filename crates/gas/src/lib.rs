@@ -119,6 +119,30 @@ const MASP_CONVERT_CHECK_GAS_RAW: u64 = 188_590;
 const MASP_OUTPUT_CHECK_GAS_RAW: u64 = 204_430;
 // The cost to run the final masp check in the bundle
 const MASP_FINAL_CHECK_GAS_RAW: u64 = 43;
+// The costs of the hash and signature verification host functions come from
+// the `hash` and `signature_verification` groups of the `host_env` benchmark.
+// Their mean times are converted to gas with the ratio between
+// `VERIFY_TX_SIG_GAS_RAW` and the mean time of the
+// `tx_section_signature_validation` group of the same benchmark. The fixed
+// cost of hashing is the intercept of the times over the different input
+// sizes, and the costs per byte are their slopes.
+//
+// The fixed cost of hashing data in the host environment
+const HASH_FIXED_GAS_RAW: u64 = 2_145;
+// The cost of hashing data with sha256, per byte
+const SHA256_GAS_PER_BYTE_RAW: u64 = 42;
+// The cost of hashing data with keccak256, per byte
+const KECCAK256_GAS_PER_BYTE_RAW: u64 = 57;
+// The cost of hashing data with blake2b, per byte
+const BLAKE2B_GAS_PER_BYTE_RAW: u64 = 26;
+// The fixed cost of verifying an ed25519 signature over a raw message
+const VERIFY_ED25519_SIG_GAS_RAW: u64 = 420_560;
+// The cost of verifying an ed25519 signature over a raw message, per byte.
+// The message is hashed with sha512 as part of the verification, which is
+// charged as hashing with sha256
+const VERIFY_ED25519_SIG_GAS_PER_BYTE_RAW: u64 = SHA256_GAS_PER_BYTE_RAW;
+// The cost of verifying a secp256k1 signature over a 32 bytes prehash
+const VERIFY_SECP256K1_SIG_GAS_RAW: u64 = 684_270;
 // =============================================================================
 
 // A correction factor for non-WASM-opcodes costs. We can see that the
@@ -207,6 +231,26 @@ pub const MASP_OUTPUT_CHECK_GAS: u64 =
 /// The cost to run the final masp check in the bundle
 pub const MASP_FINAL_CHECK_GAS: u64 =
     MASP_FINAL_CHECK_GAS_RAW * GAS_COST_CORRECTION;
+/// The fixed cost of hashing data in the host environment
+pub const HASH_FIXED_GAS: u64 = HASH_FIXED_GAS_RAW * GAS_COST_CORRECTION;
+/// The cost of hashing data with sha256, per byte
+pub const SHA256_GAS_PER_BYTE: u64 =
+    SHA256_GAS_PER_BYTE_RAW * GAS_COST_CORRECTION;
+/// The cost of hashing data with keccak256, per byte
+pub const KECCAK256_GAS_PER_BYTE: u64 =
+    KECCAK256_GAS_PER_BYTE_RAW * GAS_COST_CORRECTION;
+/// The cost of hashing data with blake2b, per byte
+pub const BLAKE2B_GAS_PER_BYTE: u64 =
+    BLAKE2B_GAS_PER_BYTE_RAW * GAS_COST_CORRECTION;
+/// The fixed cost of verifying an ed25519 signature over a raw message
+pub const VERIFY_ED25519_SIG_GAS: u64 =
+    VERIFY_ED25519_SIG_GAS_RAW * GAS_COST_CORRECTION;
+/// The cost of verifying an ed25519 signature over a raw message, per byte
+pub const VERIFY_ED25519_SIG_GAS_PER_BYTE: u64 =
+    VERIFY_ED25519_SIG_GAS_PER_BYTE_RAW * GAS_COST_CORRECTION;
+/// The cost of verifying a secp256k1 signature over a 32 bytes prehash
+pub const VERIFY_SECP256K1_SIG_GAS: u64 =
+    VERIFY_SECP256K1_SIG_GAS_RAW * GAS_COST_CORRECTION;
 // =============================================================================
 
 /// Gas module result for functions that may fail
@@ -396,7 +440,7 @@ pub trait GasMetering {
 }
 
 /// Gas metering in a transaction
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct TxGasMeter {
     /// Track gas overflow
     gas_overflow: bool,
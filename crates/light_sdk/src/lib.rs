@@ -8,12 +8,14 @@
 //!
 //! # Structure
 //!
-//! This SDK is divided into three modules:
+//! This SDK is divided into four modules:
 //!
 //! - [`transaction`]: contains functions to construct all the transactions
 //!   currently supported by the protocol
 //! - [`reading`]: exposes queries to retrieve data from a Namada node
 //! - [`writing`]: exposes functions to send data to a Namada node
+//! - [`verification`]: verifies the proofs of query responses against a block
+//!   header
 //!
 //! Both the [`reading`] and [`writing`] modules are further divided into a
 //! blocking and asynchronous submodules.
@@ -31,5 +33,6 @@
 
 pub mod reading;
 pub mod transaction;
+pub mod verification;
 pub mod writing;
 pub use namada_sdk;
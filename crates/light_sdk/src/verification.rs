@@ -0,0 +1,305 @@
+//! Verification of the proofs of query responses, to check the responses of
+//! an untrusted node against the app hash of a block header obtained from a
+//! CometBFT light client.
+//!
+//! A response to a query sent with `prove = true` carries the proofs of all
+//! the storage values the response was computed from. The state at a given
+//! height is committed to by the app hash in the header of the next block.
+//! The response data are verified by computing them again from the proven
+//! values. The queries that iterate a storage prefix don't support proofs.
+
+use std::collections::BTreeMap;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use namada_sdk::error::Error;
+use namada_sdk::hash::Sha256Hasher;
+use namada_sdk::queries::EncodedResponseQuery;
+use namada_sdk::state::merkle_tree::{
+    verify_proof, MerkleRoot, Proof, NO_DIFF_KEY_PREFIX,
+};
+use namada_sdk::storage::{DbKeySeg, Key};
+use namada_sdk::tendermint::block::Header;
+
+/// The storage values proven to be committed to the state at the queried
+/// height. The values of the keys that are proven to be absent are `None`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProvenValues(BTreeMap<Key, Option<Vec<u8>>>);
+
+impl ProvenValues {
+    /// Read the proven value of a key. Fails if the proof doesn't cover the
+    /// key, i.e. if a response computed from its value cannot be verified.
+    pub fn read_bytes(&self, key: &Key) -> Result<Option<&[u8]>, Error> {
+        self.0.get(key).map(Option::as_deref).ok_or_else(|| {
+            Error::Other(format!("The value of the key {key} is not proven"))
+        })
+    }
+
+    /// Read and decode the proven value of a key, like
+    /// [`ProvenValues::read_bytes`].
+    pub fn read<T: BorshDeserialize>(
+        &self,
+        key: &Key,
+    ) -> Result<Option<T>, Error> {
+        self.read_bytes(key)?
+            .map(|bytes| {
+                T::try_from_slice(bytes).map_err(|e| {
+                    Error::Other(format!(
+                        "Failed to decode the proven value of the key {key}: \
+                         {e}"
+                    ))
+                })
+            })
+            .transpose()
+    }
+}
+
+/// Verify a query response against the header of the block following the
+/// queried height. The header must have been verified by a light client.
+///
+/// The proof only commits to the storage values that the response was
+/// computed from, so the response data are bound to them by computing the
+/// expected data with `compute` from the proven values, like the query
+/// handler does from the state. Returns the response data, if they match the
+/// borsh encoding of the computed data.
+pub fn verify_response<T, F>(
+    response: &EncodedResponseQuery,
+    header: &Header,
+    compute: F,
+) -> Result<T, Error>
+where
+    T: BorshSerialize,
+    F: FnOnce(&ProvenValues) -> Result<T, Error>,
+{
+    let values = verify_proof_values(response, header)?;
+    let data = compute(&values)?;
+    let encoded = borsh::to_vec(&data).map_err(|e| {
+        Error::Other(format!("Failed to encode the response data: {e}"))
+    })?;
+    if encoded != response.data {
+        return Err(Error::Other(
+            "The response data don't match the data computed from the proven \
+             values"
+                .to_string(),
+        ));
+    }
+    Ok(data)
+}
+
+/// Verify the proof of a query response against the app hash of the header
+/// and get the proven values
+fn verify_proof_values(
+    response: &EncodedResponseQuery,
+    header: &Header,
+) -> Result<ProvenValues, Error> {
+    let commit_height = response.height.next_height();
+    if header.height.value() != commit_height.0 {
+        return Err(Error::Other(format!(
+            "The state at height {} is committed to the header at height {}, \
+             but the header is at height {}",
+            response.height, commit_height, header.height
+        )));
+    }
+    let root: [u8; 32] =
+        header.app_hash.as_bytes().try_into().map_err(|_| {
+            Error::Other(format!(
+                "Invalid app hash in the header at height {}",
+                header.height
+            ))
+        })?;
+    let root = MerkleRoot(root);
+    let proof_ops = response.proof.as_ref().ok_or_else(|| {
+        Error::Other("The query response has no proof".to_string())
+    })?;
+    let mut values = BTreeMap::new();
+    for proof in
+        Proof::decode_ops(proof_ops).map_err(|e| Error::Other(e.to_string()))?
+    {
+        let value = verify_proof::<Sha256Hasher>(&root, &proof)
+            .map_err(|e| Error::Other(e.to_string()))?;
+        values.insert(storage_key(proof.key), value);
+    }
+    Ok(ProvenValues(values))
+}
+
+/// Get the storage key of a key in the Merkle tree, i.e. strip the prefix of
+/// the keys whose values are merklized without diffs.
+fn storage_key(tree_key: Key) -> Key {
+    match tree_key.segments.split_first() {
+        Some((DbKeySeg::StringSeg(prefix), segments))
+            if prefix == NO_DIFF_KEY_PREFIX =>
+        {
+            Key {
+                segments: segments.to_vec(),
+            }
+        }
+        _ => tree_key,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use namada_sdk::address::{Address, InternalAddress};
+    use namada_sdk::chain::BlockHeight;
+    use namada_sdk::state::{MembershipProof, MerkleTree};
+    use namada_sdk::storage::KeySeg;
+    use namada_sdk::tendermint::block::header::Version;
+    use namada_sdk::tendermint::merkle::proof::ProofOps;
+    use namada_sdk::tendermint::{account, AppHash, Hash, Time};
+
+    use super::*;
+
+    fn test_key(seg: &str) -> Key {
+        Key::from(Address::Internal(InternalAddress::PoS).to_db_key())
+            .push(&seg.to_string())
+            .unwrap()
+    }
+
+    fn test_tree(values: &[(&Key, u64)]) -> MerkleTree<Sha256Hasher> {
+        let mut tree = MerkleTree::<Sha256Hasher>::default();
+        for (key, value) in values {
+            tree.update(key, borsh::to_vec(value).unwrap()).unwrap();
+        }
+        tree
+    }
+
+    /// Make a response at height 1 with the given data and the proofs of the
+    /// given values in the tree
+    fn test_response(
+        tree: &MerkleTree<Sha256Hasher>,
+        values: &[(&Key, u64)],
+        data: u64,
+    ) -> EncodedResponseQuery {
+        let mut proof_ops = ProofOps { ops: vec![] };
+        for (key, value) in values {
+            let value = borsh::to_vec(value).unwrap();
+            let MembershipProof::ICS23(sub_proof) = tree
+                .get_sub_tree_existence_proof(
+                    std::array::from_ref(*key),
+                    vec![&value],
+                )
+                .unwrap()
+            else {
+                panic!("Test failed")
+            };
+            let proof = tree.get_sub_tree_proof(key, sub_proof).unwrap();
+            proof_ops.ops.append(&mut ProofOps::from(proof).ops);
+        }
+        EncodedResponseQuery {
+            data: borsh::to_vec(&data).unwrap(),
+            info: Default::default(),
+            proof: Some(proof_ops),
+            height: BlockHeight(1),
+        }
+    }
+
+    fn test_header(height: u32, root: MerkleRoot) -> Header {
+        Header {
+            version: Version { block: 0, app: 0 },
+            chain_id: "test".to_string().try_into().unwrap(),
+            height: height.into(),
+            time: Time::unix_epoch(),
+            last_block_id: None,
+            last_commit_hash: None,
+            data_hash: None,
+            validators_hash: Hash::None,
+            next_validators_hash: Hash::None,
+            consensus_hash: Hash::None,
+            app_hash: AppHash::try_from(root.0.to_vec()).unwrap(),
+            last_results_hash: None,
+            evidence_hash: None,
+            proposer_address: account::Id::new([0u8; 20]),
+        }
+    }
+
+    /// Compute the response of a query of the sum of the values of the keys
+    fn sum(values: &ProvenValues, keys: [&Key; 2]) -> Result<u64, Error> {
+        keys.into_iter()
+            .map(|key| {
+                Ok::<_, Error>(values.read::<u64>(key)?.unwrap_or_default())
+            })
+            .sum()
+    }
+
+    /// Test the verification of a valid response.
+    #[test]
+    fn test_verify_response() {
+        let (key_a, key_b) = (test_key("a"), test_key("b"));
+        let values = [(&key_a, 1), (&key_b, 2)];
+        let tree = test_tree(&values);
+        let response = test_response(&tree, &values, 3);
+        let header = test_header(2, tree.root());
+
+        let data = verify_response(&response, &header, |values| {
+            sum(values, [&key_a, &key_b])
+        })
+        .unwrap();
+        assert_eq!(data, 3);
+    }
+
+    /// Test that a response with a value that is not committed to the app
+    /// hash is rejected.
+    #[test]
+    fn test_verify_response_tampered_value() {
+        let (key_a, key_b) = (test_key("a"), test_key("b"));
+        let values = [(&key_a, 1), (&key_b, 2)];
+        let tree = test_tree(&values);
+        let header = test_header(2, tree.root());
+
+        // The response data don't match the proven values
+        let response = test_response(&tree, &values, 4);
+        let result = verify_response(&response, &header, |values| {
+            sum(values, [&key_a, &key_b])
+        });
+        assert!(
+            matches!(result, Err(Error::Other(msg)) if msg.contains("don't match"))
+        );
+
+        // The proven values are taken from another state
+        let tampered_values = [(&key_a, 2), (&key_b, 2)];
+        let tampered_tree = test_tree(&tampered_values);
+        let response = test_response(&tampered_tree, &tampered_values, 4);
+        let result = verify_response(&response, &header, |values| {
+            sum(values, [&key_a, &key_b])
+        });
+        assert!(result.is_err());
+    }
+
+    /// Test that a response is rejected against a header at the wrong height
+    /// or with another app hash.
+    #[test]
+    fn test_verify_response_wrong_header() {
+        let (key_a, key_b) = (test_key("a"), test_key("b"));
+        let values = [(&key_a, 1), (&key_b, 2)];
+        let tree = test_tree(&values);
+        let response = test_response(&tree, &values, 3);
+
+        for header in [
+            test_header(1, tree.root()),
+            test_header(3, tree.root()),
+            test_header(2, MerkleRoot([0u8; 32])),
+        ] {
+            let result = verify_response(&response, &header, |values| {
+                sum(values, [&key_a, &key_b])
+            });
+            assert!(result.is_err());
+        }
+    }
+
+    /// Test that a response computed from a value whose proof is omitted is
+    /// rejected.
+    #[test]
+    fn test_verify_response_omitted_key() {
+        let (key_a, key_b) = (test_key("a"), test_key("b"));
+        let values = [(&key_a, 1), (&key_b, 2)];
+        let tree = test_tree(&values);
+        let response = test_response(&tree, &values[..1], 3);
+        let header = test_header(2, tree.root());
+
+        let result = verify_response(&response, &header, |values| {
+            sum(values, [&key_a, &key_b])
+        });
+        assert!(
+            matches!(result, Err(Error::Other(msg)) if msg.contains("not proven"))
+        );
+    }
+}
@@ -32,7 +32,9 @@ use arse_merkle_tree::{
 use eth_bridge_pool::{BridgePoolProof, BridgePoolTree};
 use ics23::commitment_proof::Proof as Ics23Proof;
 pub use ics23::CommitmentProof;
-use ics23::{ExistenceProof, NonExistenceProof};
+use ics23::{
+    ExistenceProof, HostFunctionsManager, NonExistenceProof, ProofSpec,
+};
 use ics23_specs::ibc_leaf_spec;
 use namada_core::address::{Address, InternalAddress};
 use namada_core::borsh::{BorshDeserialize, BorshSerialize, BorshSerializeExt};
@@ -128,6 +130,8 @@ pub enum Error {
     StoreType(String),
     #[error("Non-existence proofs not supported for store type: {0}")]
     NonExistenceProof(String),
+    #[error("Existence proofs not supported for store type: {0}")]
+    ExistenceProof(String),
     #[error("Invalid value given to sub-tree storage")]
     InvalidValue,
     #[error("ICS23 commitment proofs do not support multiple leaves")]
@@ -138,6 +142,8 @@ pub enum Error {
         "The merklized data did not produce that same hash as the stored root."
     )]
     RootValidationError,
+    #[error("Invalid proof: {0}")]
+    InvalidProof(String),
 }
 
 impl From<MtError> for Error {
@@ -646,42 +652,51 @@ impl<H: StorageHasher + Default> MerkleTree<H> {
     /// Recalculate the merkle tree root of storage and compare it against the
     /// old value.
     pub fn validate(&self) -> Result<()> {
-        if self.account.validate()
-            && self.ibc.validate()
-            && self.pos.validate()
-            && self.bridge_pool.validate()
-            && self.no_diff.validate()
-        {
-            let mut reconstructed = Smt::<H>::default();
-            reconstructed.update(
-                H::hash(StoreType::Account.to_string()).into(),
-                self.account.root().into(),
-            )?;
-            reconstructed.update(
-                H::hash(StoreType::PoS.to_string()).into(),
-                self.pos.root().into(),
-            )?;
-            reconstructed.update(
-                H::hash(StoreType::Ibc.to_string()).into(),
-                self.ibc.root().into(),
-            )?;
-            reconstructed.update(
-                H::hash(StoreType::BridgePool.to_string()).into(),
-                self.bridge_pool.root().into(),
-            )?;
-            reconstructed.update(
-                H::hash(StoreType::NoDiff.to_string()).into(),
-                self.no_diff.root().into(),
-            )?;
-            reconstructed.update(
-                H::hash(StoreType::CommitData.to_string()).into(),
-                self.commit_data.0,
-            )?;
-            if self.base.root() == reconstructed.root() {
+        for store_type in StoreType::iter_subtrees() {
+            self.validate_store(store_type)?;
+        }
+        self.validate_store(&StoreType::Base)
+    }
+
+    /// Recalculate the merkle tree root of the given store and compare it
+    /// against the old value. The root of the base tree is recalculated from
+    /// the roots of the subtrees.
+    pub fn validate_store(&self, store_type: &StoreType) -> Result<()> {
+        if *store_type != StoreType::Base {
+            return if self.tree(store_type).validate() {
                 Ok(())
             } else {
                 Err(Error::RootValidationError)
-            }
+            };
+        }
+
+        let mut reconstructed = Smt::<H>::default();
+        reconstructed.update(
+            H::hash(StoreType::Account.to_string()).into(),
+            self.account.root().into(),
+        )?;
+        reconstructed.update(
+            H::hash(StoreType::PoS.to_string()).into(),
+            self.pos.root().into(),
+        )?;
+        reconstructed.update(
+            H::hash(StoreType::Ibc.to_string()).into(),
+            self.ibc.root().into(),
+        )?;
+        reconstructed.update(
+            H::hash(StoreType::BridgePool.to_string()).into(),
+            self.bridge_pool.root().into(),
+        )?;
+        reconstructed.update(
+            H::hash(StoreType::NoDiff.to_string()).into(),
+            self.no_diff.root().into(),
+        )?;
+        reconstructed.update(
+            H::hash(StoreType::CommitData.to_string()).into(),
+            self.commit_data.0,
+        )?;
+        if self.base.root() == reconstructed.root() {
+            Ok(())
         } else {
             Err(Error::RootValidationError)
         }
@@ -777,29 +792,183 @@ impl<H: StorageHasher + Default> MerkleTree<H> {
         key: &Key,
         sub_proof: CommitmentProof,
     ) -> Result<Proof> {
+        let (store_type, _) = StoreType::sub_key(key)?;
+        let base_proof = self.get_sub_root_proof(&store_type)?;
+
+        Ok(Proof {
+            key: key.clone(),
+            sub_proof,
+            base_proof,
+        })
+    }
+
+    /// Get the membership proof of the root of a sub-tree in the base tree
+    pub fn get_sub_root_proof(
+        &self,
+        store_type: &StoreType,
+    ) -> Result<CommitmentProof> {
         // Get a membership proof of the base tree because the sub root should
         // exist
-        let (store_type, _) = StoreType::sub_key(key)?;
         let base_key = store_type.to_string();
         let cp = self.base.membership_proof(&H::hash(&base_key).into())?;
         // Replace the values and the leaf op for the verification
-        let base_proof = match cp.proof.expect("The proof should exist") {
-            Ics23Proof::Exist(ep) => CommitmentProof {
+        match cp.proof.expect("The proof should exist") {
+            Ics23Proof::Exist(ep) => Ok(CommitmentProof {
                 proof: Some(Ics23Proof::Exist(ExistenceProof {
                     key: base_key.as_bytes().to_vec(),
                     leaf: Some(ics23_specs::base_leaf_spec::<H>()),
                     ..ep
                 })),
-            },
+            }),
             // the proof should have an ExistenceProof
             _ => unreachable!(),
-        };
+        }
+    }
+}
 
-        Ok(Proof {
-            key: key.clone(),
-            sub_proof,
-            base_proof,
-        })
+/// Verify that the given root of a sub-tree is committed to the base tree
+/// with the given root
+pub fn verify_sub_root<H: StorageHasher>(
+    root: &MerkleRoot,
+    store_type: &StoreType,
+    sub_root: &MerkleRoot,
+    proof: &CommitmentProof,
+) -> bool {
+    let spec = ProofSpec {
+        leaf_spec: Some(ics23_specs::base_leaf_spec::<H>()),
+        ..arse_merkle_tree::proof_ics23::get_spec(H::hash_op())
+    };
+    ics23::verify_membership::<HostFunctionsManager>(
+        proof,
+        &spec,
+        &root.0.to_vec(),
+        store_type.to_string().as_bytes(),
+        &sub_root.0,
+    )
+}
+
+/// Verify that the given key-value pair is committed to the sub-tree with the
+/// given root. The key is the one in the Merkle tree, i.e. including the prefix
+/// of the data not stored to diffs if needed. The bridge pool and the commit
+/// data sub-trees don't have ICS23 proofs, so their values can't be verified.
+pub fn verify_sub_tree_existence<H: StorageHasher>(
+    sub_root: &MerkleRoot,
+    key: &Key,
+    value: &[u8],
+    proof: &CommitmentProof,
+) -> Result<bool> {
+    let (store_type, sub_key) = StoreType::sub_key(key)?;
+    let leaf_spec = match store_type {
+        StoreType::Account | StoreType::PoS | StoreType::NoDiff => {
+            ics23_specs::leaf_spec::<H>()
+        }
+        StoreType::Ibc => ibc_leaf_spec::<H>(),
+        StoreType::Base | StoreType::BridgePool | StoreType::CommitData => {
+            return Err(Error::ExistenceProof(store_type.to_string()));
+        }
+    };
+    let spec = ProofSpec {
+        leaf_spec: Some(leaf_spec),
+        ..arse_merkle_tree::proof_ics23::get_spec(H::hash_op())
+    };
+    Ok(ics23::verify_membership::<HostFunctionsManager>(
+        proof,
+        &spec,
+        &sub_root.0.to_vec(),
+        sub_key.to_string().as_bytes(),
+        value,
+    ))
+}
+
+/// Verify that the given key is not committed to the sub-tree with the given
+/// root. Only the IBC sub-tree has ICS23 non-existence proofs.
+pub fn verify_sub_tree_non_existence<H: StorageHasher>(
+    sub_root: &MerkleRoot,
+    key: &Key,
+    proof: &CommitmentProof,
+) -> Result<bool> {
+    let (store_type, sub_key) = StoreType::sub_key(key)?;
+    if store_type != StoreType::Ibc {
+        return Err(Error::NonExistenceProof(store_type.to_string()));
+    }
+    let spec = ProofSpec {
+        leaf_spec: Some(ibc_leaf_spec::<H>()),
+        ..arse_merkle_tree::proof_ics23::get_spec(H::hash_op())
+    };
+    Ok(ics23::verify_non_membership::<HostFunctionsManager>(
+        proof,
+        &spec,
+        &sub_root.0.to_vec(),
+        sub_key.to_string().as_bytes(),
+    ))
+}
+
+/// Verify a proof of a key in the Merkle tree with the given root, i.e. the
+/// app hash. Returns the value committed to the tree for the key, or `None`
+/// if the key is proven not to be in the tree.
+pub fn verify_proof<H: StorageHasher>(
+    root: &MerkleRoot,
+    proof: &Proof,
+) -> Result<Option<Vec<u8>>> {
+    let (store_type, _) = StoreType::sub_key(&proof.key)?;
+    let sub_root = match &proof.base_proof.proof {
+        Some(Ics23Proof::Exist(ep)) => {
+            let sub_root: [u8; 32] =
+                ep.value.as_slice().try_into().map_err(|_| {
+                    Error::InvalidProof(format!(
+                        "Invalid root of the {store_type} sub-tree"
+                    ))
+                })?;
+            MerkleRoot(sub_root)
+        }
+        _ => {
+            return Err(Error::InvalidProof(
+                "The base tree proof must be an existence proof".into(),
+            ));
+        }
+    };
+    if !verify_sub_root::<H>(root, &store_type, &sub_root, &proof.base_proof) {
+        return Err(Error::InvalidProof(format!(
+            "The root of the {store_type} sub-tree is not committed to the \
+             root {root}"
+        )));
+    }
+    match &proof.sub_proof.proof {
+        Some(Ics23Proof::Exist(ep)) => {
+            if verify_sub_tree_existence::<H>(
+                &sub_root,
+                &proof.key,
+                &ep.value,
+                &proof.sub_proof,
+            )? {
+                Ok(Some(ep.value.clone()))
+            } else {
+                Err(Error::InvalidProof(format!(
+                    "The value of the key {} is not committed to the {} \
+                     sub-tree",
+                    proof.key, store_type
+                )))
+            }
+        }
+        Some(Ics23Proof::Nonexist(_)) => {
+            if verify_sub_tree_non_existence::<H>(
+                &sub_root,
+                &proof.key,
+                &proof.sub_proof,
+            )? {
+                Ok(None)
+            } else {
+                Err(Error::InvalidProof(format!(
+                    "The absence of the key {} from the {} sub-tree is not \
+                     proven",
+                    proof.key, store_type
+                )))
+            }
+        }
+        _ => Err(Error::InvalidProof(
+            "The sub-tree proof must be an existence or a non-existence proof"
+                .into(),
+        )),
     }
 }
 
@@ -1004,6 +1173,49 @@ impl From<Proof> for namada_core::tendermint::merkle::proof::ProofOps {
     }
 }
 
+impl Proof {
+    /// Decode the proofs of a proof chain made of the concatenated
+    /// [`ProofOps`](namada_core::tendermint::merkle::proof::ProofOps) of one
+    /// or more proofs
+    pub fn decode_ops(
+        proof_ops: &namada_core::tendermint::merkle::proof::ProofOps,
+    ) -> Result<Vec<Self>> {
+        use prost::Message;
+
+        let decode = |data: &[u8]| {
+            CommitmentProof::decode(data).map_err(|e| {
+                Error::InvalidProof(format!("Failed to decode a proof: {e}"))
+            })
+        };
+        let chunks = proof_ops.ops.chunks_exact(2);
+        if !chunks.remainder().is_empty() {
+            return Err(Error::InvalidProof(
+                "A proof must have a sub-tree and a base tree proof op".into(),
+            ));
+        }
+        chunks
+            .map(|ops| {
+                let [sub_proof_op, base_proof_op] = ops else {
+                    unreachable!("The chunks have exactly 2 ops")
+                };
+                if sub_proof_op.key != base_proof_op.key {
+                    return Err(Error::InvalidProof(
+                        "The proof ops of a proof must have the same key"
+                            .into(),
+                    ));
+                }
+                let key = std::str::from_utf8(&sub_proof_op.key)
+                    .map_err(|e| Error::InvalidProof(e.to_string()))?;
+                Ok(Self {
+                    key: Key::parse(key)?,
+                    sub_proof: decode(&sub_proof_op.data)?,
+                    base_proof: decode(&base_proof_op.data)?,
+                })
+            })
+            .collect()
+    }
+}
+
 impl<'a, H: StorageHasher + Default> SubTreeRead for &'a Smt<H> {
     fn root(&self) -> MerkleRoot {
         Smt::<H>::root(self).into()
@@ -1255,7 +1467,6 @@ impl<'a> SubTreeWrite for &'a mut CommitDataRoot {
 #[cfg(test)]
 mod test {
     use assert_matches::assert_matches;
-    use ics23::HostFunctionsManager;
     use namada_core::hash::Sha256Hasher;
 
     use super::*;
@@ -1414,6 +1625,12 @@ mod test {
             tree.validate().unwrap_err(),
             Error::RootValidationError
         );
+        // Only the base tree doesn't match the updated subtree
+        assert!(tree.validate_store(&StoreType::Ibc).is_ok());
+        assert_matches!(
+            tree.validate_store(&StoreType::Base).unwrap_err(),
+            Error::RootValidationError
+        );
     }
 
     #[test]
@@ -1543,6 +1760,140 @@ mod test {
         assert_eq!(sub_root, tree.root().0);
     }
 
+    #[test]
+    fn test_verify_sub_tree_existence() {
+        let mut tree = MerkleTree::<Sha256Hasher>::default();
+
+        let key_prefix: Key =
+            Address::Internal(InternalAddress::Ibc).to_db_key().into();
+        let ibc_key = key_prefix.push(&"test".to_string()).unwrap();
+        let key_prefix: Key =
+            Address::Internal(InternalAddress::PoS).to_db_key().into();
+        let pos_key = key_prefix.push(&"test".to_string()).unwrap();
+
+        let ibc_val = [1u8; 8].to_vec();
+        tree.update(&ibc_key, ibc_val.clone()).unwrap();
+        let pos_val = [2u8; 8].to_vec();
+        tree.update(&pos_key, pos_val.clone()).unwrap();
+
+        for (key, val) in [(ibc_key, ibc_val), (pos_key, pos_val)] {
+            let proof = match tree
+                .get_sub_tree_existence_proof(
+                    std::array::from_ref(&key),
+                    vec![&val],
+                )
+                .unwrap()
+            {
+                MembershipProof::ICS23(proof) => proof,
+                _ => panic!("Test failed"),
+            };
+            let (store_type, _) = StoreType::sub_key(&key).unwrap();
+            let sub_root = tree.sub_root(&store_type);
+            assert!(
+                verify_sub_tree_existence::<Sha256Hasher>(
+                    &sub_root, &key, &val, &proof
+                )
+                .unwrap()
+            );
+            // A different value must not be verified
+            assert!(
+                !verify_sub_tree_existence::<Sha256Hasher>(
+                    &sub_root, &key, &[0u8; 8], &proof
+                )
+                .unwrap()
+            );
+
+            let sub_root_proof = tree.get_sub_root_proof(&store_type).unwrap();
+            assert!(verify_sub_root::<Sha256Hasher>(
+                &tree.root(),
+                &store_type,
+                &sub_root,
+                &sub_root_proof
+            ));
+            // The proof is only valid for the sub-tree's own root
+            assert!(!verify_sub_root::<Sha256Hasher>(
+                &tree.root(),
+                &store_type,
+                &MerkleRoot([0u8; 32]),
+                &sub_root_proof
+            ));
+        }
+    }
+
+    #[test]
+    fn test_verify_proof() {
+        use namada_core::tendermint::merkle::proof::ProofOps;
+
+        let mut tree = MerkleTree::<Sha256Hasher>::default();
+
+        let key_prefix: Key =
+            Address::Internal(InternalAddress::Ibc).to_db_key().into();
+        let ibc_key = key_prefix.push(&"test".to_string()).unwrap();
+        let ibc_non_key = key_prefix.push(&"test2".to_string()).unwrap();
+        let key_prefix: Key =
+            Address::Internal(InternalAddress::PoS).to_db_key().into();
+        let pos_key = key_prefix.push(&"test".to_string()).unwrap();
+
+        let ibc_val = [1u8; 8].to_vec();
+        tree.update(&ibc_key, ibc_val.clone()).unwrap();
+        let pos_val = [2u8; 8].to_vec();
+        tree.update(&pos_key, pos_val.clone()).unwrap();
+
+        // Concatenate the proof chains of several keys
+        let mut proof_ops = ProofOps { ops: vec![] };
+        for (key, val) in [(&ibc_key, &ibc_val), (&pos_key, &pos_val)] {
+            let MembershipProof::ICS23(sub_proof) = tree
+                .get_sub_tree_existence_proof(
+                    std::array::from_ref(key),
+                    vec![val],
+                )
+                .unwrap()
+            else {
+                panic!("Test failed")
+            };
+            let proof = tree.get_sub_tree_proof(key, sub_proof).unwrap();
+            proof_ops.ops.append(&mut ProofOps::from(proof).ops);
+        }
+        let non_existence_proof =
+            tree.get_non_existence_proof(&ibc_non_key).unwrap();
+        proof_ops
+            .ops
+            .append(&mut ProofOps::from(non_existence_proof).ops);
+
+        let proofs = Proof::decode_ops(&proof_ops).unwrap();
+        let verified: Vec<_> = proofs
+            .iter()
+            .map(|proof| {
+                let value =
+                    verify_proof::<Sha256Hasher>(&tree.root(), proof).unwrap();
+                (proof.key.clone(), value)
+            })
+            .collect();
+        assert_eq!(
+            verified,
+            vec![
+                (ibc_key, Some(ibc_val)),
+                (pos_key, Some(pos_val)),
+                (ibc_non_key, None)
+            ]
+        );
+
+        // The proofs are only valid for the root of the tree
+        for proof in &proofs {
+            assert_matches!(
+                verify_proof::<Sha256Hasher>(&MerkleRoot([0u8; 32]), proof),
+                Err(Error::InvalidProof(_))
+            );
+        }
+
+        // A proof chain must be made of pairs of proof ops
+        proof_ops.ops.pop();
+        assert_matches!(
+            Proof::decode_ops(&proof_ops),
+            Err(Error::InvalidProof(_))
+        );
+    }
+
     #[test]
     fn test_ibc_non_existence_proof() {
         let mut tree = MerkleTree::<Sha256Hasher>::default();
@@ -84,7 +84,7 @@ use namada_sdk::masp::{
 use namada_sdk::queries::{
     EncodedResponseQuery, RequestCtx, RequestQuery, Router, RPC,
 };
-use namada_sdk::state::StorageRead;
+use namada_sdk::state::{StorageRead, TxBatchExecution};
 use namada_sdk::storage::{Key, KeySeg, TxIndex};
 use namada_sdk::time::DateTimeUtc;
 use namada_sdk::token::{self, Amount, DenominatedAmount, Transfer};
@@ -122,7 +122,7 @@ use crate::config::TendermintMode;
 use crate::shell::Shell;
 use crate::tendermint::abci::request::InitChain;
 use crate::tendermint_proto::google::protobuf::Timestamp;
-use crate::{config, dry_run_tx, tendermint_rpc};
+use crate::{config, dry_run_tx, protocol, tendermint_rpc};
 
 pub const WASM_DIR: &str = "../../wasm";
 
@@ -282,6 +282,44 @@ impl BenchShellInner {
         .unwrap()
     }
 
+    /// Evaluate the VPs triggered by a tx that has been executed with
+    /// [`Self::execute_tx`], in the VP evaluation mode of the state.
+    pub fn check_vps(
+        &self,
+        batched_tx: &BatchedTxRef<'_>,
+        verifiers_from_tx: &BTreeSet<Address>,
+    ) -> VpsResult {
+        let (verifiers, keys_changed) = self
+            .state
+            .write_log()
+            .verifiers_and_changed_keys(verifiers_from_tx);
+        let (vps_result, _) = protocol::execute_vps(
+            verifiers,
+            keys_changed,
+            batched_tx,
+            &TxIndex(0),
+            &self.state,
+            &TxGasMeter::new(u64::MAX),
+            &mut self.vp_wasm_cache.clone(),
+        )
+        .unwrap();
+        vps_result
+    }
+
+    /// Execute the given batches on top of the current write log, either
+    /// sequentially or speculatively in parallel, like in `finalize_block`.
+    /// The changes are dropped.
+    pub fn execute_inner_tx_batches(&mut self, batches: &[Tx], parallel: bool) {
+        let write_log = self.state.write_log().clone();
+        self.state.in_mem_mut().tx_batch_execution = if parallel {
+            TxBatchExecution::Parallel
+        } else {
+            TxBatchExecution::Sequential
+        };
+        self.inner.execute_inner_tx_batches(batches.to_vec());
+        *self.state.write_log_mut() = write_log;
+    }
+
     pub fn advance_epoch(&mut self) {
         let params = proof_of_stake::storage::read_pos_params::<
             _,
@@ -883,7 +921,9 @@ impl Client for BenchShell {
 
         let shell = self.read();
 
-        if request.path == RPC.shell().dry_run_tx_path() {
+        if request.path == RPC.shell().dry_run_tx_path()
+            || request.path == RPC.shell().dry_run_tx_trace_path()
+        {
             dry_run_tx(
                 // This is safe because nothing else is using `self.state`
                 // concurrently and the `TempWlState` will be dropped right
@@ -900,6 +940,8 @@ impl Client for BenchShell {
                 vp_wasm_cache: shell.vp_wasm_cache.read_only(),
                 tx_wasm_cache: shell.tx_wasm_cache.read_only(),
                 storage_read_past_height_limit: None,
+                height: BlockHeight::default(),
+                read_log: None,
             };
             RPC.handle(ctx, &request)
         }
@@ -1194,7 +1236,9 @@ impl BenchShieldedCtx {
                     spending_keys: vec![spending_key],
                     viewing_keys: vec![],
                     with_indexer: None,
+                    with_snapshot: None,
                     wait_for_last_query_height: false,
+                    watch: None,
                     max_concurrent_fetches: 100,
                     retry_strategy: RetryStrategy::Forever,
                     block_batch_size: 10,
@@ -1229,6 +1273,7 @@ impl BenchShieldedCtx {
                         None,
                         expiration,
                         &mut RngBuildParams::new(OsRng),
+                        None,
                     )
                     .await
             })
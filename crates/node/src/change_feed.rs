@@ -0,0 +1,206 @@
+//! A local WebSocket endpoint that streams the state diffs of every committed
+//! block to its subscribers.
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use futures::{SinkExt, StreamExt};
+use namada_sdk::chain::BlockHeight;
+use namada_sdk::state::StoreType;
+use namada_sdk::storage::Key;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{broadcast, oneshot};
+use warp::ws::{Message, WebSocket, Ws};
+use warp::{Filter, Reply};
+
+use crate::storage::state_diffs::{DiffFilter, StateDiff};
+
+/// The path of the WebSocket endpoint. The diffs can be filtered with the
+/// comma-separated `prefix` and `store_type` query parameters, e.g.
+/// `ws://127.0.0.1:26661/diffs?prefix=a,b/c&store_type=account,pos`.
+const CHANGE_FEED_ENDPOINT: &str = "diffs";
+
+/// How many blocks can be buffered for a subscriber before it gets
+/// disconnected for lagging behind
+const CHANNEL_CAPACITY: usize = 64;
+
+/// The state diffs of a committed block
+#[derive(Debug)]
+pub struct BlockDiffs {
+    /// The height of the committed block
+    pub height: BlockHeight,
+    /// The diffs of the block, sorted by key
+    pub diffs: Vec<StateDiff>,
+}
+
+impl BlockDiffs {
+    /// Encode the diffs that pass the filter as a JSON object
+    fn to_json(&self, filter: &DiffFilter) -> serde_json::Value {
+        let diffs: Vec<serde_json::Value> = self
+            .diffs
+            .iter()
+            .filter(|diff| filter.matches(diff))
+            .map(StateDiff::to_json)
+            .collect();
+        serde_json::json!({
+            "height": self.height.0,
+            "diffs": diffs,
+        })
+    }
+}
+
+/// The sending half of the change feed, used to publish the diffs of each
+/// committed block
+pub type Sender = broadcast::Sender<Arc<BlockDiffs>>;
+
+/// Create the sending half of the change feed. Subscribers are added by the
+/// server for each WebSocket connection.
+pub fn channel() -> Sender {
+    let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+    sender
+}
+
+/// Starts a [`warp::Server`] that streams the diffs published on `sender` to
+/// the connected WebSocket clients, one JSON message per block.
+///
+/// It shuts down if a signal is sent on the `abort_recv` channel.
+pub async fn serve(
+    listen_addr: SocketAddr,
+    sender: Sender,
+    abort_recv: oneshot::Receiver<()>,
+) {
+    let change_feed =
+        warp::path(CHANGE_FEED_ENDPOINT)
+            .and(warp::ws())
+            .and(warp::query::<HashMap<String, String>>())
+            .map(move |ws: Ws, params: HashMap<String, String>| {
+                match parse_filter(&params) {
+                    Ok(filter) => {
+                        let receiver = sender.subscribe();
+                        ws.on_upgrade(move |socket| {
+                            stream_diffs(socket, receiver, filter)
+                        })
+                        .into_response()
+                    }
+                    Err(err) => warp::reply::with_status(
+                        err,
+                        warp::http::StatusCode::BAD_REQUEST,
+                    )
+                    .into_response(),
+                }
+            });
+
+    let (_, future) = warp::serve(change_feed).bind_with_graceful_shutdown(
+        listen_addr,
+        async move {
+            tracing::info!(?listen_addr, "State change feed is listening");
+            if abort_recv.await.is_err() {
+                tracing::warn!(
+                    "The state change feed abort sender has unexpectedly \
+                     dropped"
+                );
+            }
+            tracing::info!("Shutting down the state change feed");
+        },
+    );
+    future.await
+}
+
+/// Parse the diffs filter from the query parameters of a subscription
+fn parse_filter(
+    params: &HashMap<String, String>,
+) -> Result<DiffFilter, String> {
+    let values = |name: &str| {
+        params
+            .get(name)
+            .into_iter()
+            .flat_map(|values| values.split(','))
+            .filter(|value| !value.is_empty())
+    };
+    let prefixes = values("prefix")
+        .map(|prefix| {
+            Key::parse(prefix)
+                .map_err(|err| format!("Invalid key prefix {prefix}: {err}"))
+        })
+        .collect::<Result<_, _>>()?;
+    let store_types = values("store_type")
+        .map(|store_type| {
+            store_type.parse::<StoreType>().map_err(|err| {
+                format!("Invalid store type {store_type}: {err}")
+            })
+        })
+        .collect::<Result<_, _>>()?;
+    Ok(DiffFilter {
+        prefixes,
+        store_types,
+    })
+}
+
+/// Send the diffs of every new block to a subscriber until it disconnects or
+/// lags behind
+async fn stream_diffs(
+    socket: WebSocket,
+    mut receiver: broadcast::Receiver<Arc<BlockDiffs>>,
+    filter: DiffFilter,
+) {
+    let (mut socket_send, mut socket_recv) = socket.split();
+    loop {
+        let block = tokio::select! {
+            block = receiver.recv() => block,
+            // The client doesn't send anything but it may close the
+            // connection
+            message = socket_recv.next() => match message {
+                Some(Ok(message)) if !message.is_close() => continue,
+                _ => break,
+            },
+        };
+        let block = match block {
+            Ok(block) => block,
+            Err(RecvError::Lagged(skipped)) => {
+                tracing::warn!(
+                    skipped,
+                    "A state change feed subscriber lagged behind, closing \
+                     its connection"
+                );
+                break;
+            }
+            Err(RecvError::Closed) => break,
+        };
+        let message = Message::text(block.to_json(&filter).to_string());
+        if socket_send.send(message).await.is_err() {
+            break;
+        }
+    }
+    let _ = socket_send.close().await;
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_filter() {
+        let params = HashMap::from([
+            ("prefix".to_string(), "a,b/c".to_string()),
+            ("store_type".to_string(), "account,pos".to_string()),
+        ]);
+        let filter = parse_filter(&params).unwrap();
+        assert_eq!(
+            filter.prefixes,
+            vec![Key::parse("a").unwrap(), Key::parse("b/c").unwrap()]
+        );
+        assert_eq!(
+            filter.store_types,
+            vec![StoreType::Account, StoreType::PoS]
+        );
+
+        let filter = parse_filter(&HashMap::new()).unwrap();
+        assert!(filter.prefixes.is_empty());
+        assert!(filter.store_types.is_empty());
+
+        let params =
+            HashMap::from([("store_type".to_string(), "foo".to_string())]);
+        assert!(parse_filter(&params).is_err());
+    }
+}
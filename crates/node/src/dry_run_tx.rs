@@ -2,26 +2,33 @@
 
 use std::cell::RefCell;
 
+use either::Either;
 use namada_sdk::borsh::BorshSerializeExt;
 use namada_sdk::gas::{GasMetering, TxGasMeter};
+use namada_sdk::hash::Hash;
 use namada_sdk::parameters;
-use namada_sdk::queries::{EncodedResponseQuery, RequestQuery};
+use namada_sdk::queries::{EncodedResponseQuery, RequestQuery, RPC};
 use namada_sdk::state::{
-    DBIter, Result, ResultExt, StorageHasher, TxIndex, DB,
+    DBIter, Result, ResultExt, StorageHasher, TempWlState, TxIndex, DB,
+};
+use namada_sdk::tx::data::trace::DryRunTrace;
+use namada_sdk::tx::data::{
+    compute_inner_tx_hash, DryRunResult, GasLimit, TxResult, TxType,
 };
-use namada_sdk::tx::data::{DryRunResult, GasLimit, TxResult, TxType};
 use namada_sdk::tx::Tx;
 use namada_vm::wasm::{TxCache, VpCache};
 use namada_vm::WasmCacheAccess;
+use namada_vp::trace;
 
 use crate::protocol;
 use crate::protocol::ShellParams;
 
-/// Dry run a transaction
+/// Dry run a transaction. If requested with the path of the
+/// `dry_run_tx_trace` query, the execution of the inner txs is also traced.
 pub fn dry_run_tx<D, H, CA>(
-    mut state: namada_sdk::state::TempWlState<'static, D, H>,
-    mut vp_wasm_cache: VpCache<CA>,
-    mut tx_wasm_cache: TxCache<CA>,
+    state: TempWlState<'static, D, H>,
+    vp_wasm_cache: VpCache<CA>,
+    tx_wasm_cache: TxCache<CA>,
     request: &RequestQuery,
 ) -> Result<EncodedResponseQuery>
 where
@@ -32,6 +39,62 @@ where
     let tx = Tx::try_from_bytes(&request.data[..]).into_storage_result()?;
     tx.validate_tx().into_storage_result()?;
 
+    let height = state.in_mem().get_last_block_height();
+    let data = if request.path == RPC.shell().dry_run_tx_trace_path() {
+        let (dry_run_result, inner_tx_traces) = trace::record(|| {
+            execute_dry_run(
+                &tx,
+                state,
+                vp_wasm_cache,
+                tx_wasm_cache,
+                &request.data,
+            )
+        });
+        let (result, wrapper_hash) = dry_run_result?;
+        let traces = inner_tx_traces
+            .into_iter()
+            .map(|(cmt_hash, trace)| {
+                let inner_tx_hash = compute_inner_tx_hash(
+                    wrapper_hash.as_ref(),
+                    Either::Left(&cmt_hash),
+                );
+                (inner_tx_hash, trace)
+            })
+            .collect();
+        DryRunTrace { result, traces }.serialize_to_vec()
+    } else {
+        let (result, _wrapper_hash) = execute_dry_run(
+            &tx,
+            state,
+            vp_wasm_cache,
+            tx_wasm_cache,
+            &request.data,
+        )?;
+        result.serialize_to_vec()
+    };
+
+    Ok(EncodedResponseQuery {
+        data,
+        proof: None,
+        info: Default::default(),
+        height,
+    })
+}
+
+/// Execute the dry run of a transaction. Returns its result and the hash of
+/// its wrapper, if any.
+fn execute_dry_run<D, H, CA>(
+    tx: &Tx,
+    mut state: TempWlState<'static, D, H>,
+    mut vp_wasm_cache: VpCache<CA>,
+    mut tx_wasm_cache: TxCache<CA>,
+    tx_bytes: &[u8],
+) -> Result<(DryRunResult, Option<Hash>)>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+    CA: 'static + WasmCacheAccess + Sync,
+{
     let gas_scale = parameters::get_gas_scale(&state)?;
 
     // Wrapper dry run to allow estimating the entire gas cost of a transaction
@@ -50,9 +113,9 @@ where
                     &mut tx_wasm_cache,
                 );
                 let tx_result = protocol::apply_wrapper_tx(
-                    &tx,
+                    tx,
                     &wrapper,
-                    &request.data,
+                    tx_bytes,
                     &TxIndex::default(),
                     &tx_gas_meter,
                     &mut shell_params,
@@ -79,7 +142,7 @@ where
         };
 
     let extended_tx_result = protocol::dispatch_inner_txs(
-        &tx,
+        tx,
         wrapper_hash.as_ref(),
         extended_tx_result,
         TxIndex(0),
@@ -99,12 +162,7 @@ where
             .get_whole_gas_units(gas_scale),
     );
 
-    Ok(EncodedResponseQuery {
-        data: dry_run_result.serialize_to_vec(),
-        proof: None,
-        info: Default::default(),
-        height: state.in_mem().get_last_block_height(),
-    })
+    Ok((dry_run_result, wrapper_hash))
 }
 
 #[cfg(test)]
@@ -112,6 +170,7 @@ mod test {
     use namada_sdk::borsh::{BorshDeserialize, BorshSerializeExt};
     use namada_sdk::chain::BlockHeight;
     use namada_sdk::events::log::EventLog;
+    use namada_sdk::gas::Gas;
     use namada_sdk::hash::Hash;
     use namada_sdk::io::Client;
     use namada_sdk::queries::{
@@ -222,7 +281,9 @@ mod test {
             // TODO(namada#3240): this is a hack to propagate errors to the
             // caller, we should really permit error types other
             // than [`std::io::Error`]
-            if request.path == RPC.shell().dry_run_tx_path() {
+            if request.path == RPC.shell().dry_run_tx_path()
+                || request.path == RPC.shell().dry_run_tx_trace_path()
+            {
                 dry_run_tx(
                     // This is safe because nothing else is using `self.state`
                     // concurrently and the `TempWlState` will be dropped right
@@ -241,6 +302,8 @@ mod test {
                     vp_wasm_cache: self.vp_wasm_cache.clone(),
                     tx_wasm_cache: self.tx_wasm_cache.clone(),
                     storage_read_past_height_limit: None,
+                    height: BlockHeight::default(),
+                    read_log: None,
                 };
                 self.rpc.handle(ctx, &request)
             }
@@ -371,4 +434,61 @@ mod test {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_dry_run_tx_trace() {
+        let mut client = TestClient::new(RPC);
+        // store the wasm code
+        let tx_code = TestWasms::TxReadStorageKey.read_bytes();
+        let tx_hash = Hash::sha256(&tx_code);
+        let key = Key::wasm_code(&tx_hash);
+        let len_key = Key::wasm_code_len(&tx_hash);
+        client
+            .state
+            .db_write(&key, tx_code.serialize_to_vec())
+            .unwrap();
+        client
+            .state
+            .db_write(&len_key, (tx_code.len() as u64).serialize_to_vec())
+            .unwrap();
+        // write the value read by the tx
+        let read_key = Key::parse("read_key").unwrap();
+        let value = 1_u64.serialize_to_vec();
+        client.state.db_write(&read_key, &value).unwrap();
+
+        let mut outer_tx = Tx::from_type(TxType::Raw);
+        outer_tx.header.chain_id = client.state.in_mem().chain_id.clone();
+        outer_tx.set_code(Code::from_hash(tx_hash, None));
+        outer_tx.set_data(Data::new(read_key.serialize_to_vec()));
+        let cmt = outer_tx.first_commitments().unwrap();
+        let tx_bytes = outer_tx.to_bytes();
+        let DryRunTrace { result, traces } = RPC
+            .shell()
+            .dry_run_tx_trace(&client, Some(tx_bytes), None, false)
+            .await
+            .unwrap()
+            .data;
+        assert!(
+            result
+                .0
+                .get_inner_tx_result(None, either::Right(cmt))
+                .unwrap()
+                .as_ref()
+                .unwrap()
+                .is_accepted()
+        );
+
+        // The trace of the inner tx records the read of the key
+        let trace = &traces[&compute_inner_tx_hash(None, either::Right(cmt))];
+        let read = trace
+            .calls
+            .iter()
+            .find(|call| call.name == "namada_tx_read")
+            .unwrap();
+        assert_eq!(read.key, Some(read_key));
+        assert_eq!(read.value_len, Some(value.len() as u64));
+        assert!(read.gas > Gas::default());
+        // The tx doesn't trigger any VP
+        assert!(trace.vps.is_empty());
+    }
 }
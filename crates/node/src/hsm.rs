@@ -0,0 +1,488 @@
+//! PKCS#11 backend for the keys of a validator.
+//!
+//! The consensus key, the protocol key and the Ethereum bridge hot key of a
+//! validator can be held in a hardware security module (HSM) that is accessed
+//! through a PKCS#11 module, in which case they are used to sign votes,
+//! protocol txs and vote extensions without ever leaving the HSM. Only their
+//! public keys are loaded, and the secret keys of the validator's wallet are
+//! then optional. Both ed25519 (`CKK_EC_EDWARDS`) and secp256k1 (`CKK_EC`)
+//! keys are supported.
+//!
+//! The consensus key is used by CometBFT, which gets its signatures from the
+//! remote signer in [`privval`].
+//!
+//! For local testing, keys can be imported into a SoftHSM token with
+//! [`import_key`].
+
+use std::fmt;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use cryptoki::context::{CInitializeArgs, Pkcs11};
+use cryptoki::mechanism::Mechanism;
+use cryptoki::object::{
+    Attribute, AttributeType, KeyType, ObjectClass, ObjectHandle,
+};
+use cryptoki::session::{Session, UserType};
+use cryptoki::types::AuthPin;
+use k256::elliptic_curve::sec1::ToEncodedPoint;
+use namada_apps_lib::config::Pkcs11Config;
+use namada_sdk::hash::{Hash, StorageHasher};
+use namada_sdk::key::common::{self, SigningKey};
+use namada_sdk::key::{ed25519, secp256k1, SignableBytes};
+use thiserror::Error;
+
+pub mod privval;
+
+/// DER encoding of the OID of the ed25519 curve (1.3.101.112)
+const ED25519_PARAMS: &[u8] = &[0x06, 0x03, 0x2b, 0x65, 0x70];
+/// DER encoding of the OID of the secp256k1 curve (1.3.132.0.10)
+const SECP256K1_PARAMS: &[u8] = &[0x06, 0x05, 0x2b, 0x81, 0x04, 0x00, 0x0a];
+/// DER tag of an octet string
+const DER_OCTET_STRING: u8 = 0x04;
+
+/// Errors of the PKCS#11 backend
+#[derive(Error, Debug)]
+pub enum Error {
+    /// An error reported by the PKCS#11 module
+    #[error("PKCS#11 error: {0}")]
+    Pkcs11(#[from] cryptoki::error::Error),
+    /// The PIN environment variable is not set
+    #[error("The PIN of the HSM token must be given in the env var {0}")]
+    MissingPin(String),
+    /// No token has the given label
+    #[error("No HSM token with the label \"{0}\" was found")]
+    TokenNotFound(String),
+    /// No key pair has the given label
+    #[error("No HSM key pair with the label \"{0}\" was found")]
+    KeyNotFound(String),
+    /// The key pair with the given label has an unsupported type
+    #[error(
+        "The HSM key \"{0}\" must be either an ed25519 or a secp256k1 key"
+    )]
+    UnsupportedKey(String),
+    /// The public key with the given label could not be decoded
+    #[error("The HSM public key \"{0}\" is invalid")]
+    InvalidPublicKey(String),
+    /// The key with the given label failed to sign
+    #[error("The HSM failed to sign with the key \"{0}\": {1}")]
+    Signing(String, cryptoki::error::Error),
+    /// The key with the given label produced an invalid signature
+    #[error("The HSM key \"{0}\" produced an invalid signature")]
+    InvalidSignature(String),
+}
+
+/// The keys of a validator that are held in an HSM
+#[derive(Debug)]
+pub struct ValidatorHsmKeys {
+    /// The protocol key
+    pub protocol_key: Pkcs11Key,
+    /// The Ethereum bridge hot key, if it is held in the HSM too
+    pub eth_bridge_key: Option<Pkcs11Key>,
+    /// The consensus key, if it is held in the HSM too
+    pub consensus_key: Option<Pkcs11Key>,
+}
+
+impl ValidatorHsmKeys {
+    /// Log into the token of the given configuration and look up the keys of
+    /// the validator
+    pub fn load(config: &Pkcs11Config) -> Result<Self, Error> {
+        let pin = std::env::var(&config.pin_env)
+            .map_err(|_| Error::MissingPin(config.pin_env.clone()))?;
+        let session = Arc::new(Mutex::new(open_session(
+            &config.module,
+            &config.token_label,
+            &pin,
+        )?));
+        let protocol_key =
+            Pkcs11Key::find(session.clone(), &config.protocol_key_label)?;
+        let eth_bridge_key = config
+            .eth_bridge_key_label
+            .as_ref()
+            .map(|label| Pkcs11Key::find(session.clone(), label))
+            .transpose()?;
+        let consensus_key = config
+            .consensus_key_label
+            .as_ref()
+            .map(|label| Pkcs11Key::find(session.clone(), label))
+            .transpose()?;
+        Ok(Self {
+            protocol_key,
+            eth_bridge_key,
+            consensus_key,
+        })
+    }
+}
+
+/// Open a user session on the token with the given label
+pub fn open_session(
+    module: impl AsRef<Path>,
+    token_label: &str,
+    pin: &str,
+) -> Result<Session, Error> {
+    let pkcs11 = Pkcs11::new(module)?;
+    pkcs11.initialize(CInitializeArgs::OsThreads)?;
+    let slot = pkcs11
+        .get_slots_with_token()?
+        .into_iter()
+        .find(|slot| {
+            pkcs11
+                .get_token_info(*slot)
+                .map(|info| info.label().trim_end() == token_label)
+                .unwrap_or_default()
+        })
+        .ok_or_else(|| Error::TokenNotFound(token_label.to_string()))?;
+    let session = pkcs11.open_rw_session(slot)?;
+    session.login(UserType::User, Some(&AuthPin::new(pin.to_string())))?;
+    Ok(session)
+}
+
+/// A key pair held in an HSM, which signs with the same schemes as the
+/// corresponding [`common::SecretKey`]
+#[derive(Clone)]
+pub struct Pkcs11Key {
+    session: Arc<Mutex<Session>>,
+    label: String,
+    private_key: ObjectHandle,
+    public_key: common::PublicKey,
+}
+
+impl fmt::Debug for Pkcs11Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Pkcs11Key")
+            .field("label", &self.label)
+            .field("public_key", &self.public_key)
+            .finish()
+    }
+}
+
+impl Pkcs11Key {
+    /// Look up the key pair with the given label
+    pub fn find(
+        session: Arc<Mutex<Session>>,
+        label: &str,
+    ) -> Result<Self, Error> {
+        let (private_key, public_key) = {
+            let session = session.lock().unwrap();
+            let find = |class| {
+                session
+                    .find_objects(&[
+                        Attribute::Class(class),
+                        Attribute::Label(label.as_bytes().to_vec()),
+                    ])
+                    .map(|handles| handles.into_iter().next())
+            };
+            let (Some(private_key), Some(public_key)) = (
+                find(ObjectClass::PRIVATE_KEY)?,
+                find(ObjectClass::PUBLIC_KEY)?,
+            ) else {
+                return Err(Error::KeyNotFound(label.to_string()));
+            };
+            let attributes = session.get_attributes(
+                public_key,
+                &[AttributeType::KeyType, AttributeType::EcPoint],
+            )?;
+            let mut key_type = None;
+            let mut ec_point = None;
+            for attribute in attributes {
+                match attribute {
+                    Attribute::KeyType(kt) => key_type = Some(kt),
+                    Attribute::EcPoint(point) => ec_point = Some(point),
+                    _ => {}
+                }
+            }
+            let (Some(key_type), Some(ec_point)) = (key_type, ec_point) else {
+                return Err(Error::UnsupportedKey(label.to_string()));
+            };
+            let point = unwrap_octet_string(&ec_point);
+            let public_key = if key_type == KeyType::EC_EDWARDS {
+                ed25519_consensus::VerificationKey::try_from(point)
+                    .map(|pk| {
+                        common::PublicKey::Ed25519(ed25519::PublicKey(pk))
+                    })
+                    .map_err(|_| Error::InvalidPublicKey(label.to_string()))?
+            } else if key_type == KeyType::EC {
+                k256::PublicKey::from_sec1_bytes(point)
+                    .map(|pk| {
+                        common::PublicKey::Secp256k1(secp256k1::PublicKey(pk))
+                    })
+                    .map_err(|_| Error::InvalidPublicKey(label.to_string()))?
+            } else {
+                return Err(Error::UnsupportedKey(label.to_string()));
+            };
+            (private_key, public_key)
+        };
+        Ok(Self {
+            session,
+            label: label.to_string(),
+            private_key,
+            public_key,
+        })
+    }
+
+    /// Get the label of this key pair
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    fn raw_sign(
+        &self,
+        mechanism: &Mechanism,
+        data: &[u8],
+    ) -> Result<Vec<u8>, Error> {
+        self.session
+            .lock()
+            .unwrap()
+            .sign(mechanism, self.private_key, data)
+            .map_err(|err| Error::Signing(self.label.clone(), err))
+    }
+
+    /// Sign the given message as CometBFT does: ed25519 keys sign the raw
+    /// message, while secp256k1 keys sign its SHA256 digest with a low s
+    pub fn sign_raw(&self, msg: &[u8]) -> Result<Vec<u8>, Error> {
+        let invalid = || Error::InvalidSignature(self.label.clone());
+        match &self.public_key {
+            common::PublicKey::Ed25519(_) => {
+                let sig = self.raw_sign(&Mechanism::Eddsa, msg)?;
+                if sig.len() != 64 {
+                    return Err(invalid());
+                }
+                Ok(sig)
+            }
+            common::PublicKey::Secp256k1(_) => {
+                let digest = Hash::sha256(msg);
+                let sig = self.raw_sign(&Mechanism::Ecdsa, &digest.0)?;
+                let sig = k256::ecdsa::Signature::from_slice(&sig)
+                    .map_err(|_| invalid())?;
+                let sig = sig.normalize_s().unwrap_or(sig);
+                Ok(sig.to_bytes().to_vec())
+            }
+        }
+    }
+}
+
+impl SigningKey for Pkcs11Key {
+    type Error = Error;
+
+    fn public_key(&self) -> common::PublicKey {
+        self.public_key.clone()
+    }
+
+    fn try_sign_with_hasher<H>(
+        &self,
+        data: impl SignableBytes,
+    ) -> Result<common::Signature, Error>
+    where
+        H: 'static + StorageHasher,
+    {
+        let msg = data.signable_hash::<H>();
+        let invalid = || Error::InvalidSignature(self.label.clone());
+        match &self.public_key {
+            common::PublicKey::Ed25519(_) => {
+                let sig = self.raw_sign(&Mechanism::Eddsa, &msg)?;
+                let sig = <[u8; 64]>::try_from(sig.as_slice())
+                    .map_err(|_| invalid())?;
+                Ok(common::Signature::Ed25519(ed25519::Signature(
+                    ed25519_consensus::Signature::from(sig),
+                )))
+            }
+            common::PublicKey::Secp256k1(pk) => {
+                let sig = self.raw_sign(&Mechanism::Ecdsa, &msg)?;
+                let sig = k256::ecdsa::Signature::from_slice(&sig)
+                    .map_err(|_| invalid())?;
+                // Signatures are only valid with a low s
+                let sig = sig.normalize_s().unwrap_or(sig);
+                // Fails if the HSM didn't sign with the expected key
+                let recovery_id =
+                    k256::ecdsa::RecoveryId::trial_recovery_from_prehash(
+                        &k256::ecdsa::VerifyingKey::from(&pk.0),
+                        &msg,
+                        &sig,
+                    )
+                    .map_err(|_| invalid())?;
+                Ok(common::Signature::Secp256k1(secp256k1::Signature(
+                    sig,
+                    recovery_id,
+                )))
+            }
+        }
+    }
+}
+
+/// The signer of a validator's protocol txs or vote extensions, whose key
+/// is either loaded from the wallet or held in an HSM
+#[derive(Debug, Clone, Copy)]
+pub enum ValidatorSigner<'a> {
+    /// A key loaded from the wallet
+    Local(&'a common::SecretKey),
+    /// A key held in an HSM
+    Hsm(&'a Pkcs11Key),
+}
+
+impl SigningKey for ValidatorSigner<'_> {
+    type Error = Error;
+
+    fn public_key(&self) -> common::PublicKey {
+        match self {
+            Self::Local(sk) => sk.public_key(),
+            Self::Hsm(key) => key.public_key(),
+        }
+    }
+
+    fn try_sign_with_hasher<H>(
+        &self,
+        data: impl SignableBytes,
+    ) -> Result<common::Signature, Error>
+    where
+        H: 'static + StorageHasher,
+    {
+        match self {
+            Self::Local(sk) => match sk.try_sign_with_hasher::<H>(data) {
+                Ok(sig) => Ok(sig),
+                Err(infallible) => match infallible {},
+            },
+            Self::Hsm(key) => key.try_sign_with_hasher::<H>(data),
+        }
+    }
+}
+
+/// Import the given secret key into the token of the given session as a
+/// key pair with the given label, e.g. to set up a SoftHSM token
+pub fn import_key(
+    session: &Session,
+    label: &str,
+    secret_key: &common::SecretKey,
+) -> Result<(), Error> {
+    let (key_type, params, value, point) = match secret_key {
+        common::SecretKey::Ed25519(sk) => (
+            KeyType::EC_EDWARDS,
+            ED25519_PARAMS,
+            sk.0.to_bytes().to_vec(),
+            sk.0.verification_key().to_bytes().to_vec(),
+        ),
+        common::SecretKey::Secp256k1(sk) => (
+            KeyType::EC,
+            SECP256K1_PARAMS,
+            sk.0.to_bytes().to_vec(),
+            sk.0.public_key()
+                .to_encoded_point(false)
+                .as_bytes()
+                .to_vec(),
+        ),
+    };
+    let label = label.as_bytes().to_vec();
+    session.create_object(&[
+        Attribute::Class(ObjectClass::PRIVATE_KEY),
+        Attribute::KeyType(key_type),
+        Attribute::Token(true),
+        Attribute::Private(true),
+        Attribute::Sensitive(true),
+        Attribute::Sign(true),
+        Attribute::Label(label.clone()),
+        Attribute::EcParams(params.to_vec()),
+        Attribute::Value(value),
+    ])?;
+    session.create_object(&[
+        Attribute::Class(ObjectClass::PUBLIC_KEY),
+        Attribute::KeyType(key_type),
+        Attribute::Token(true),
+        Attribute::Verify(true),
+        Attribute::Label(label),
+        Attribute::EcParams(params.to_vec()),
+        Attribute::EcPoint(wrap_octet_string(&point)),
+    ])?;
+    Ok(())
+}
+
+/// Strip the DER octet string wrapping of an EC point, if there is one. Only
+/// the lengths of ed25519 points and of compressed or uncompressed secp256k1
+/// points are considered, so that raw points aren't mistaken for wrapped ones.
+fn unwrap_octet_string(point: &[u8]) -> &[u8] {
+    match point {
+        [DER_OCTET_STRING, len, rest @ ..]
+            if usize::from(*len) == rest.len()
+                && matches!(rest.len(), 32 | 33 | 65) =>
+        {
+            rest
+        }
+        _ => point,
+    }
+}
+
+/// Wrap an EC point in a DER octet string
+fn wrap_octet_string(point: &[u8]) -> Vec<u8> {
+    let len = u8::try_from(point.len()).expect("EC points are short");
+    [&[DER_OCTET_STRING, len], point].concat()
+}
+
+#[cfg(test)]
+mod test_hsm {
+    use namada_sdk::key::{self, SigScheme};
+
+    use super::*;
+
+    #[test]
+    fn test_octet_string_wrapping() {
+        let ed25519_point = [7; 32];
+        let wrapped = wrap_octet_string(&ed25519_point);
+        assert_eq!(wrapped.len(), 34);
+        assert_eq!(unwrap_octet_string(&wrapped), ed25519_point);
+
+        // A raw uncompressed secp256k1 point also starts with `0x04`, but it
+        // isn't mistaken for a wrapped one
+        let mut secp256k1_point = [9; 65];
+        secp256k1_point[0] = 0x04;
+        assert_eq!(unwrap_octet_string(&secp256k1_point), secp256k1_point);
+        let wrapped = wrap_octet_string(&secp256k1_point);
+        assert_eq!(unwrap_octet_string(&wrapped), secp256k1_point);
+    }
+
+    /// Sign with keys imported into a SoftHSM token. Only runs when the
+    /// `NAMADA_TEST_PKCS11_MODULE`, `NAMADA_TEST_PKCS11_TOKEN` and
+    /// `NAMADA_TEST_PKCS11_PIN` env vars are set, e.g. after:
+    ///
+    /// ```sh
+    /// softhsm2-util --init-token --free --label namada --pin 1234 --so-pin 1234
+    /// ```
+    #[test]
+    fn test_softhsm_signing() {
+        let (Ok(module), Ok(token), Ok(pin)) = (
+            std::env::var("NAMADA_TEST_PKCS11_MODULE"),
+            std::env::var("NAMADA_TEST_PKCS11_TOKEN"),
+            std::env::var("NAMADA_TEST_PKCS11_PIN"),
+        ) else {
+            return;
+        };
+        let session =
+            Arc::new(Mutex::new(open_session(module, &token, &pin).unwrap()));
+        let keys = [
+            (
+                "test-ed25519",
+                common::SecretKey::Ed25519(key::testing::gen_keypair::<
+                    ed25519::SigScheme,
+                >()),
+            ),
+            (
+                "test-secp256k1",
+                common::SecretKey::Secp256k1(key::testing::gen_keypair::<
+                    secp256k1::SigScheme,
+                >()),
+            ),
+        ];
+        for (label, secret_key) in keys {
+            let label = format!("{label}-{}", secret_key.public_key());
+            import_key(&session.lock().unwrap(), &label, &secret_key).unwrap();
+            let hsm_key = Pkcs11Key::find(session.clone(), &label).unwrap();
+            assert_eq!(hsm_key.public_key(), secret_key.public_key());
+
+            let data = b"signed by an HSM".to_vec();
+            let sig = ValidatorSigner::Hsm(&hsm_key).try_sign(&data).unwrap();
+            common::SigScheme::verify_signature(
+                &secret_key.public_key(),
+                &data,
+                &sig,
+            )
+            .unwrap();
+        }
+    }
+}
@@ -0,0 +1,726 @@
+//! CometBFT remote signer (privval) for a consensus key held in an HSM.
+//!
+//! CometBFT signs votes and proposals with the consensus key itself, unless
+//! its `priv_validator_laddr` is set, in which case it listens on that address
+//! for a remote signer to connect and sends it the messages to sign. Only Unix
+//! sockets (e.g. `unix:///path/to/privval.sock`) are supported, as TCP
+//! connections require CometBFT's authenticated encryption. The requests and
+//! responses are varint length-delimited protobuf `privval.Message`s.
+//!
+//! The signer never signs two different messages for the same height, round
+//! and step, nor a message for a lower height, round or step than the last one
+//! it signed. The last signed message is persisted to a file before its
+//! signature is released, so that this also holds across restarts.
+
+use std::cmp::Ordering;
+use std::io::{self, Read, Write};
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use namada_sdk::borsh::{BorshDeserialize, BorshSerialize, BorshSerializeExt};
+use namada_sdk::chain::ChainId;
+use namada_sdk::key::common;
+use prost::Message;
+use thiserror::Error;
+
+use super::Pkcs11Key;
+use crate::shell::key_to_tendermint;
+use crate::tendermint_proto::crypto::PublicKey;
+use crate::tendermint_proto::google::protobuf::Timestamp;
+use crate::tendermint_proto::privval::{
+    message, Message as PrivvalMessage, PingResponse, PubKeyResponse,
+    RemoteSignerError, SignedProposalResponse, SignedVoteResponse,
+};
+use crate::tendermint_proto::types::{
+    BlockId, CanonicalBlockId, CanonicalPartSetHeader, CanonicalProposal,
+    CanonicalVote, Proposal, SignedMsgType, Vote,
+};
+
+/// The maximum size of a request, which only holds a vote or a proposal
+const MAX_MESSAGE_SIZE: usize = 64 * 1024;
+/// The maximum length of a varint length prefix
+const MAX_VARINT_LENGTH: usize = 10;
+/// The delay between attempts to connect to CometBFT
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+/// The error code returned to CometBFT for failed requests
+const REMOTE_SIGNER_ERROR_CODE: i32 = 1;
+
+/// The step of a proposal, prevote or precommit in a round
+const STEP_PROPOSAL: u8 = 1;
+const STEP_PREVOTE: u8 = 2;
+const STEP_PRECOMMIT: u8 = 3;
+
+/// Errors of the remote signer
+#[derive(Error, Debug)]
+pub enum Error {
+    /// The HSM failed to sign
+    #[error("{0}")]
+    Hsm(#[from] super::Error),
+    /// The last sign state couldn't be read or written
+    #[error("Failed to access the last sign state: {0}")]
+    State(io::Error),
+    /// The request is for another chain
+    #[error("Expected a request for the chain {expected}, got {got}")]
+    ChainId { expected: String, got: String },
+    /// The request is malformed
+    #[error("Invalid request: {0}")]
+    InvalidRequest(String),
+    /// Signing the request could lead to a double-sign
+    #[error(
+        "Refusing to sign at height {height}, round {round}, step {step} \
+         after having signed at height {last_height}, round {last_round}, \
+         step {last_step}"
+    )]
+    DoubleSign {
+        height: i64,
+        round: i32,
+        step: u8,
+        last_height: i64,
+        last_round: i32,
+        last_step: u8,
+    },
+}
+
+/// A key that signs the sign bytes of CometBFT votes and proposals
+pub trait ConsensusKey {
+    /// Get the public key of this consensus key
+    fn public_key(&self) -> common::PublicKey;
+
+    /// Sign the given sign bytes
+    fn sign_consensus(
+        &self,
+        sign_bytes: &[u8],
+    ) -> Result<Vec<u8>, super::Error>;
+}
+
+impl ConsensusKey for Pkcs11Key {
+    fn public_key(&self) -> common::PublicKey {
+        self.public_key.clone()
+    }
+
+    fn sign_consensus(
+        &self,
+        sign_bytes: &[u8],
+    ) -> Result<Vec<u8>, super::Error> {
+        self.sign_raw(sign_bytes)
+    }
+}
+
+/// The last message signed by the signer
+#[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+struct LastSignState {
+    height: i64,
+    round: i32,
+    step: u8,
+    sign_bytes: Vec<u8>,
+    signature: Vec<u8>,
+}
+
+impl LastSignState {
+    fn read(path: &Path) -> Result<Option<Self>, Error> {
+        match std::fs::read(path) {
+            Ok(bytes) => {
+                Self::try_from_slice(&bytes).map(Some).map_err(Error::State)
+            }
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(Error::State(err)),
+        }
+    }
+
+    /// Atomically replace the state file
+    fn write(&self, path: &Path) -> Result<(), Error> {
+        let tmp_path = path.with_extension("tmp");
+        let mut file =
+            std::fs::File::create(&tmp_path).map_err(Error::State)?;
+        file.write_all(&self.serialize_to_vec())
+            .and_then(|()| file.sync_all())
+            .and_then(|()| std::fs::rename(&tmp_path, path))
+            .map_err(Error::State)
+    }
+}
+
+/// Signs CometBFT votes and proposals with a consensus key, with double-sign
+/// protection
+#[derive(Debug)]
+pub struct ConsensusSigner<K> {
+    key: K,
+    chain_id: ChainId,
+    state_path: PathBuf,
+    last: Option<LastSignState>,
+}
+
+impl<K: ConsensusKey> ConsensusSigner<K> {
+    /// Create a signer, resuming from the last sign state at the given path,
+    /// if any
+    pub fn new(
+        key: K,
+        chain_id: ChainId,
+        state_path: PathBuf,
+    ) -> Result<Self, Error> {
+        let last = LastSignState::read(&state_path)?;
+        Ok(Self {
+            key,
+            chain_id,
+            state_path,
+            last,
+        })
+    }
+
+    /// Handle a request from CometBFT and return the response
+    pub fn handle(&mut self, request: PrivvalMessage) -> PrivvalMessage {
+        let to_error = |err: Error| {
+            tracing::error!("Remote signer request failed: {err}");
+            Some(RemoteSignerError {
+                code: REMOTE_SIGNER_ERROR_CODE,
+                description: err.to_string(),
+            })
+        };
+        let response = match request.sum {
+            Some(message::Sum::PubKeyRequest(req)) => {
+                message::Sum::PubKeyResponse(
+                    match self.check_chain_id(&req.chain_id).and_then(|()| {
+                        key_to_tendermint(&self.key.public_key())
+                            .map(|sum| PublicKey { sum: Some(sum) })
+                            .map_err(|err| {
+                                Error::InvalidRequest(err.to_string())
+                            })
+                    }) {
+                        Ok(pub_key) => PubKeyResponse {
+                            pub_key: Some(pub_key),
+                            error: None,
+                        },
+                        Err(err) => PubKeyResponse {
+                            pub_key: None,
+                            error: to_error(err),
+                        },
+                    },
+                )
+            }
+            Some(message::Sum::SignVoteRequest(req)) => {
+                message::Sum::SignedVoteResponse(
+                    match self.check_chain_id(&req.chain_id).and_then(|()| {
+                        let mut vote = req.vote.ok_or_else(|| {
+                            Error::InvalidRequest("Missing vote".to_string())
+                        })?;
+                        self.sign_vote(&mut vote)?;
+                        Ok(vote)
+                    }) {
+                        Ok(vote) => SignedVoteResponse {
+                            vote: Some(vote),
+                            error: None,
+                        },
+                        Err(err) => SignedVoteResponse {
+                            vote: None,
+                            error: to_error(err),
+                        },
+                    },
+                )
+            }
+            Some(message::Sum::SignProposalRequest(req)) => {
+                message::Sum::SignedProposalResponse(
+                    match self.check_chain_id(&req.chain_id).and_then(|()| {
+                        let mut proposal = req.proposal.ok_or_else(|| {
+                            Error::InvalidRequest(
+                                "Missing proposal".to_string(),
+                            )
+                        })?;
+                        self.sign_proposal(&mut proposal)?;
+                        Ok(proposal)
+                    }) {
+                        Ok(proposal) => SignedProposalResponse {
+                            proposal: Some(proposal),
+                            error: None,
+                        },
+                        Err(err) => SignedProposalResponse {
+                            proposal: None,
+                            error: to_error(err),
+                        },
+                    },
+                )
+            }
+            Some(message::Sum::PingRequest(_)) => {
+                message::Sum::PingResponse(PingResponse {})
+            }
+            other => {
+                tracing::error!("Unexpected remote signer request: {other:?}");
+                message::Sum::PingResponse(PingResponse {})
+            }
+        };
+        PrivvalMessage {
+            sum: Some(response),
+        }
+    }
+
+    fn check_chain_id(&self, chain_id: &str) -> Result<(), Error> {
+        if chain_id == self.chain_id.as_str() {
+            Ok(())
+        } else {
+            Err(Error::ChainId {
+                expected: self.chain_id.to_string(),
+                got: chain_id.to_string(),
+            })
+        }
+    }
+
+    fn sign_vote(&mut self, vote: &mut Vote) -> Result<(), Error> {
+        let step = match SignedMsgType::try_from(vote.r#type) {
+            Ok(SignedMsgType::Prevote) => STEP_PREVOTE,
+            Ok(SignedMsgType::Precommit) => STEP_PRECOMMIT,
+            _ => {
+                return Err(Error::InvalidRequest(format!(
+                    "Unexpected vote type {}",
+                    vote.r#type
+                )));
+            }
+        };
+        let canonical = CanonicalVote {
+            r#type: vote.r#type,
+            height: vote.height,
+            round: vote.round.into(),
+            block_id: canonical_block_id(vote.block_id.clone()),
+            timestamp: vote.timestamp.clone(),
+            chain_id: self.chain_id.to_string(),
+        };
+        let (signature, timestamp) =
+            self.sign(vote.height, vote.round, step, canonical, |vote| {
+                &mut vote.timestamp
+            })?;
+        vote.signature = signature;
+        vote.timestamp = timestamp;
+        Ok(())
+    }
+
+    fn sign_proposal(&mut self, proposal: &mut Proposal) -> Result<(), Error> {
+        if !matches!(
+            SignedMsgType::try_from(proposal.r#type),
+            Ok(SignedMsgType::Proposal)
+        ) {
+            return Err(Error::InvalidRequest(format!(
+                "Unexpected proposal type {}",
+                proposal.r#type
+            )));
+        }
+        let canonical = CanonicalProposal {
+            r#type: proposal.r#type,
+            height: proposal.height,
+            round: proposal.round.into(),
+            pol_round: proposal.pol_round.into(),
+            block_id: canonical_block_id(proposal.block_id.clone()),
+            timestamp: proposal.timestamp.clone(),
+            chain_id: self.chain_id.to_string(),
+        };
+        let (signature, timestamp) = self.sign(
+            proposal.height,
+            proposal.round,
+            STEP_PROPOSAL,
+            canonical,
+            |proposal| &mut proposal.timestamp,
+        )?;
+        proposal.signature = signature;
+        proposal.timestamp = timestamp;
+        Ok(())
+    }
+
+    /// Sign the given canonical message, unless it conflicts with the last
+    /// signed message. A message that only differs from the last one by its
+    /// timestamp gets the last signature, along with the timestamp that it
+    /// covers.
+    fn sign<M>(
+        &mut self,
+        height: i64,
+        round: i32,
+        step: u8,
+        canonical: M,
+        timestamp: fn(&mut M) -> &mut Option<Timestamp>,
+    ) -> Result<(Vec<u8>, Option<Timestamp>), Error>
+    where
+        M: Message + Default + PartialEq,
+    {
+        let sign_bytes = canonical.encode_length_delimited_to_vec();
+        if let Some(last) = &self.last {
+            match (height, round, step).cmp(&(
+                last.height,
+                last.round,
+                last.step,
+            )) {
+                Ordering::Greater => {}
+                Ordering::Equal => {
+                    let mut new = canonical;
+                    let new_timestamp = timestamp(&mut new).take();
+                    let mut last_msg =
+                        M::decode_length_delimited(last.sign_bytes.as_slice())
+                            .map_err(|err| {
+                                Error::State(io::Error::new(
+                                    io::ErrorKind::InvalidData,
+                                    err,
+                                ))
+                            })?;
+                    let last_timestamp = timestamp(&mut last_msg).take();
+                    if new == last_msg {
+                        let timestamp = if last.sign_bytes == sign_bytes {
+                            new_timestamp
+                        } else {
+                            last_timestamp
+                        };
+                        return Ok((last.signature.clone(), timestamp));
+                    }
+                    return Err(self.double_sign(height, round, step, last));
+                }
+                Ordering::Less => {
+                    return Err(self.double_sign(height, round, step, last));
+                }
+            }
+        }
+
+        let mut canonical = canonical;
+        let new_timestamp = timestamp(&mut canonical).clone();
+        let signature = self.key.sign_consensus(&sign_bytes)?;
+        let state = LastSignState {
+            height,
+            round,
+            step,
+            sign_bytes,
+            signature: signature.clone(),
+        };
+        state.write(&self.state_path)?;
+        self.last = Some(state);
+        Ok((signature, new_timestamp))
+    }
+
+    fn double_sign(
+        &self,
+        height: i64,
+        round: i32,
+        step: u8,
+        last: &LastSignState,
+    ) -> Error {
+        Error::DoubleSign {
+            height,
+            round,
+            step,
+            last_height: last.height,
+            last_round: last.round,
+            last_step: last.step,
+        }
+    }
+}
+
+/// Canonicalize a block ID, which is left out if it is nil
+fn canonical_block_id(block_id: Option<BlockId>) -> Option<CanonicalBlockId> {
+    let block_id = block_id?;
+    let part_set_header = block_id.part_set_header.unwrap_or_default();
+    if block_id.hash.is_empty()
+        && part_set_header.total == 0
+        && part_set_header.hash.is_empty()
+    {
+        return None;
+    }
+    Some(CanonicalBlockId {
+        hash: block_id.hash,
+        part_set_header: Some(CanonicalPartSetHeader {
+            total: part_set_header.total,
+            hash: part_set_header.hash,
+        }),
+    })
+}
+
+/// Read a length-delimited message, or `None` if the connection was closed
+fn read_message(stream: &mut impl Read) -> io::Result<Option<PrivvalMessage>> {
+    let mut prefix = Vec::with_capacity(MAX_VARINT_LENGTH);
+    loop {
+        let mut byte = [0_u8];
+        if stream.read(&mut byte)? == 0 {
+            return if prefix.is_empty() {
+                Ok(None)
+            } else {
+                Err(io::ErrorKind::UnexpectedEof.into())
+            };
+        }
+        prefix.push(byte[0]);
+        if byte[0] & 0x80 == 0 {
+            break;
+        }
+        if prefix.len() == MAX_VARINT_LENGTH {
+            return Err(invalid_data("Invalid length prefix"));
+        }
+    }
+    let len = prost::encoding::decode_varint(&mut prefix.as_slice())
+        .map_err(invalid_data)?;
+    let len = usize::try_from(len)
+        .ok()
+        .filter(|len| *len <= MAX_MESSAGE_SIZE)
+        .ok_or_else(|| invalid_data("The message is too large"))?;
+    let mut buf = vec![0_u8; len];
+    stream.read_exact(&mut buf)?;
+    PrivvalMessage::decode(buf.as_slice())
+        .map(Some)
+        .map_err(invalid_data)
+}
+
+fn invalid_data(
+    err: impl Into<Box<dyn std::error::Error + Send + Sync>>,
+) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err)
+}
+
+/// Serve the requests of CometBFT on the given connection until it is closed
+fn serve<K: ConsensusKey>(
+    signer: &mut ConsensusSigner<K>,
+    stream: &mut (impl Read + Write),
+) -> io::Result<()> {
+    while let Some(request) = read_message(stream)? {
+        let response = signer.handle(request);
+        stream.write_all(&response.encode_length_delimited_to_vec())?;
+    }
+    Ok(())
+}
+
+/// Connect to the Unix socket of CometBFT and serve its requests, connecting
+/// again whenever the connection is lost. Never returns.
+pub fn run<K: ConsensusKey>(
+    mut signer: ConsensusSigner<K>,
+    socket_path: PathBuf,
+) -> ! {
+    loop {
+        match UnixStream::connect(&socket_path) {
+            Ok(mut stream) => {
+                tracing::info!(
+                    "Connected the HSM consensus key signer to CometBFT"
+                );
+                if let Err(err) = serve(&mut signer, &mut stream) {
+                    tracing::error!(
+                        "The connection of the HSM consensus key signer to \
+                         CometBFT failed: {err}"
+                    );
+                }
+            }
+            Err(err) => {
+                tracing::debug!(
+                    "Waiting for CometBFT to listen on {}: {err}",
+                    socket_path.to_string_lossy()
+                );
+            }
+        }
+        std::thread::sleep(RECONNECT_DELAY);
+    }
+}
+
+#[cfg(test)]
+mod test_privval {
+    use namada_sdk::key::{self, RefTo};
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::tendermint_proto::privval::{
+        PubKeyRequest, SignProposalRequest, SignVoteRequest,
+    };
+    use crate::tendermint_proto::types::PartSetHeader;
+
+    impl ConsensusKey for common::SecretKey {
+        fn public_key(&self) -> common::PublicKey {
+            self.ref_to()
+        }
+
+        fn sign_consensus(
+            &self,
+            sign_bytes: &[u8],
+        ) -> Result<Vec<u8>, super::super::Error> {
+            let common::SecretKey::Ed25519(sk) = self else {
+                unreachable!("Only ed25519 keys are used in tests")
+            };
+            Ok(sk.0.sign(sign_bytes).to_bytes().to_vec())
+        }
+    }
+
+    fn chain_id() -> ChainId {
+        ChainId("test-chain".to_string())
+    }
+
+    fn signer(path: &Path) -> ConsensusSigner<common::SecretKey> {
+        ConsensusSigner::new(
+            common::SecretKey::Ed25519(key::testing::keypair_1()),
+            chain_id(),
+            path.join("sign_state"),
+        )
+        .unwrap()
+    }
+
+    fn vote(height: i64, round: i32, r#type: SignedMsgType) -> Vote {
+        Vote {
+            r#type: r#type.into(),
+            height,
+            round,
+            block_id: Some(BlockId {
+                hash: vec![1; 32],
+                part_set_header: Some(PartSetHeader {
+                    total: 1,
+                    hash: vec![2; 32],
+                }),
+            }),
+            timestamp: Some(Timestamp {
+                seconds: 1,
+                nanos: 0,
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn sign_vote(
+        signer: &mut ConsensusSigner<common::SecretKey>,
+        vote: Vote,
+    ) -> Result<Vote, RemoteSignerError> {
+        let response = signer.handle(PrivvalMessage {
+            sum: Some(message::Sum::SignVoteRequest(SignVoteRequest {
+                vote: Some(vote),
+                chain_id: chain_id().to_string(),
+            })),
+        });
+        let Some(message::Sum::SignedVoteResponse(response)) = response.sum
+        else {
+            panic!("Expected a signed vote response");
+        };
+        match response.error {
+            Some(err) => Err(err),
+            None => Ok(response.vote.unwrap()),
+        }
+    }
+
+    /// Check the signature of a vote against its canonical sign bytes
+    fn verify_vote(vote: &Vote) {
+        let sign_bytes = CanonicalVote {
+            r#type: vote.r#type,
+            height: vote.height,
+            round: vote.round.into(),
+            block_id: canonical_block_id(vote.block_id.clone()),
+            timestamp: vote.timestamp.clone(),
+            chain_id: chain_id().to_string(),
+        }
+        .encode_length_delimited_to_vec();
+        let sig = <[u8; 64]>::try_from(vote.signature.as_slice()).unwrap();
+        let common::PublicKey::Ed25519(pk) =
+            common::SecretKey::Ed25519(key::testing::keypair_1()).ref_to()
+        else {
+            unreachable!()
+        };
+        pk.0.verify(&ed25519_consensus::Signature::from(sig), &sign_bytes)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_pub_key_and_chain_id() {
+        let dir = tempdir().unwrap();
+        let mut signer = signer(dir.path());
+        let response = signer.handle(PrivvalMessage {
+            sum: Some(message::Sum::PubKeyRequest(PubKeyRequest {
+                chain_id: chain_id().to_string(),
+            })),
+        });
+        let Some(message::Sum::PubKeyResponse(response)) = response.sum else {
+            panic!("Expected a public key response");
+        };
+        assert_eq!(
+            response.pub_key.unwrap().sum,
+            Some(key_to_tendermint(&signer.key.ref_to()).unwrap())
+        );
+
+        // a request for another chain is refused
+        let response = signer.handle(PrivvalMessage {
+            sum: Some(message::Sum::SignProposalRequest(SignProposalRequest {
+                proposal: Some(Proposal {
+                    r#type: SignedMsgType::Proposal.into(),
+                    height: 1,
+                    ..Default::default()
+                }),
+                chain_id: "other-chain".to_string(),
+            })),
+        });
+        let Some(message::Sum::SignedProposalResponse(response)) = response.sum
+        else {
+            panic!("Expected a signed proposal response");
+        };
+        assert!(response.error.is_some());
+    }
+
+    #[test]
+    fn test_double_sign_protection() {
+        let dir = tempdir().unwrap();
+        let mut signer = signer(dir.path());
+
+        let prevote =
+            sign_vote(&mut signer, vote(2, 0, SignedMsgType::Prevote)).unwrap();
+        verify_vote(&prevote);
+
+        // signing the same vote again returns the same signature
+        let again =
+            sign_vote(&mut signer, vote(2, 0, SignedMsgType::Prevote)).unwrap();
+        assert_eq!(again, prevote);
+
+        // a vote that only differs by its timestamp gets the signed timestamp
+        let mut later = vote(2, 0, SignedMsgType::Prevote);
+        later.timestamp = Some(Timestamp {
+            seconds: 2,
+            nanos: 0,
+        });
+        let later = sign_vote(&mut signer, later).unwrap();
+        assert_eq!(later, prevote);
+
+        // a conflicting vote at the same height, round and step is refused
+        let mut conflicting = vote(2, 0, SignedMsgType::Prevote);
+        conflicting.block_id = None;
+        assert!(sign_vote(&mut signer, conflicting).is_err());
+
+        // a vote at a later step is signed
+        let precommit =
+            sign_vote(&mut signer, vote(2, 0, SignedMsgType::Precommit))
+                .unwrap();
+        verify_vote(&precommit);
+
+        // a vote at an earlier step or height is refused, even after a
+        // restart
+        let mut signer = self::signer(dir.path());
+        assert!(
+            sign_vote(&mut signer, vote(2, 0, SignedMsgType::Prevote)).is_err()
+        );
+        assert!(sign_vote(&mut signer, vote(1, 5, SignedMsgType::Precommit))
+            .is_err());
+        let next =
+            sign_vote(&mut signer, vote(3, 0, SignedMsgType::Prevote)).unwrap();
+        verify_vote(&next);
+    }
+
+    #[test]
+    fn test_read_message() {
+        let request = PrivvalMessage {
+            sum: Some(message::Sum::PubKeyRequest(PubKeyRequest {
+                chain_id: chain_id().to_string(),
+            })),
+        };
+        let bytes = request.encode_length_delimited_to_vec();
+        let mut stream = bytes.as_slice();
+        assert_eq!(read_message(&mut stream).unwrap(), Some(request));
+        assert_eq!(read_message(&mut stream).unwrap(), None);
+
+        // a truncated message
+        let mut stream = &bytes[..bytes.len() - 1];
+        assert!(read_message(&mut stream).is_err());
+
+        // an oversized message
+        let mut prefix = vec![];
+        prost::encoding::encode_varint(
+            u64::try_from(MAX_MESSAGE_SIZE + 1).unwrap(),
+            &mut prefix,
+        );
+        assert!(read_message(&mut prefix.as_slice()).is_err());
+    }
+
+    #[test]
+    fn test_canonical_block_id() {
+        assert_eq!(canonical_block_id(None), None);
+        assert_eq!(canonical_block_id(Some(BlockId::default())), None);
+        assert!(canonical_block_id(
+            vote(1, 0, SignedMsgType::Prevote).block_id
+        )
+        .is_some());
+    }
+}
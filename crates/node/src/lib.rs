@@ -17,8 +17,10 @@ mod abortable;
 #[cfg(feature = "benches")]
 pub mod bench_utils;
 mod broadcaster;
+pub mod change_feed;
 mod dry_run_tx;
 pub mod ethereum_oracle;
+pub mod hsm;
 pub mod protocol;
 pub mod shell;
 pub mod shims;
@@ -34,7 +36,7 @@ use byte_unit::Byte;
 use data_encoding::HEXUPPER;
 pub use dry_run_tx::dry_run_tx;
 use futures::future::TryFutureExt;
-use namada_apps_lib::cli::args;
+use namada_apps_lib::cli::{args, cmds};
 use namada_apps_lib::config::utils::{
     convert_tm_addr_to_socket_addr, num_of_threads,
 };
@@ -45,7 +47,7 @@ pub use namada_apps_lib::{
 use namada_sdk::chain::BlockHeight;
 use namada_sdk::eth_bridge::ethers::providers::{Http, Provider};
 use namada_sdk::migrations::ScheduledMigration;
-use namada_sdk::state::{ProcessProposalCachedResult, StateRead, DB};
+use namada_sdk::state::{DbError, ProcessProposalCachedResult, StateRead, DB};
 use namada_sdk::storage::DbColFam;
 use namada_sdk::tendermint::abci::request::CheckTxKind;
 use namada_sdk::tendermint::abci::response::ProcessProposal;
@@ -347,6 +349,257 @@ pub fn dump_db(
     db.dump_block(out_file_path, historic, block_height);
 }
 
+/// Export the state diffs of a range of committed blocks into a file of
+/// newline-delimited JSON records. The DB is opened read-only, so with the
+/// RocksDB backend this can run while the ledger is running. Returns the number
+/// of exported records.
+pub fn export_diffs(
+    config: config::Ledger,
+    args::LedgerExportDiffs {
+        from,
+        to,
+        prefixes,
+        store_types,
+        out_file_path,
+    }: args::LedgerExportDiffs,
+) -> storage::state_diffs::Result<u64> {
+    let chain_id = config.chain_id;
+    let db_path = config.shell.db_dir(&chain_id);
+
+    let db = storage::open_read_only(db_path)?;
+    let out = std::io::BufWriter::new(std::fs::File::create(out_file_path)?);
+    let filter = storage::state_diffs::DiffFilter {
+        prefixes,
+        store_types,
+    };
+    storage::state_diffs::export_diffs(&db, from, to, &filter, out)
+}
+
+/// Copy Namada ledger node's DB into a new DB with the given storage backend.
+/// Returns the directory of the new DB and the number of copied entries.
+pub fn migrate_db(
+    config: config::Ledger,
+    args::LedgerMigrateDb {
+        backend,
+        output_folder,
+    }: args::LedgerMigrateDb,
+) -> Result<(PathBuf, u64), DbError> {
+    let db_path = config.db_dir();
+    let output_folder = output_folder
+        .unwrap_or_else(|| db_path.with_extension(backend.to_str()));
+    if output_folder.exists() {
+        return Err(DbError::DBError(format!(
+            "The output directory {} already exists",
+            output_folder.to_string_lossy()
+        )));
+    }
+
+    let from = storage::open_read_only(&db_path)?;
+    if from.backend() == backend {
+        return Err(DbError::DBError(format!(
+            "The DB already uses the {backend} backend"
+        )));
+    }
+    tracing::info!(
+        "Copying the DB from {} to {}...",
+        db_path.to_string_lossy(),
+        output_folder.to_string_lossy()
+    );
+    let to = storage::open_with_backend(&output_folder, backend)?;
+    let copied = storage::copy_db(&from, &to)?;
+    Ok((output_folder, copied))
+}
+
+/// Inspect Namada ledger node's DB while the ledger is not running. The
+/// interactive shell reads the queries from the standard input, one per line,
+/// until an `exit` or the end of the input.
+pub fn inspect_db(
+    config: config::Ledger,
+    query: cmds::LedgerInspectDb,
+) -> Result<(), storage::inspect::Error> {
+    use std::io::Write;
+
+    let db_path = config.db_dir();
+    if storage::detect_backend(&db_path).is_none() {
+        return Err(DbError::DBError(format!(
+            "No DB found in {}",
+            db_path.to_string_lossy()
+        ))
+        .into());
+    }
+    let native_token = config::genesis::chain::Finalized::read_native_token(
+        &config.chain_dir(),
+    )
+    .map_err(|err| storage::inspect::Error::Genesis(err.to_string()))?;
+    let state = storage::PersistentState::open(
+        db_path,
+        None,
+        config.chain_id,
+        native_token,
+        None,
+        shell::is_key_diff_storable,
+    );
+
+    let mut out = std::io::stdout().lock();
+    if !matches!(query, cmds::LedgerInspectDb::Shell(_)) {
+        return run_inspect_db_query(&state, query, &mut out);
+    }
+    let mut lines = std::io::stdin().lines();
+    loop {
+        write!(out, "inspect-db> ")?;
+        out.flush()?;
+        let Some(line) = lines.next() else {
+            writeln!(out)?;
+            return Ok(());
+        };
+        let line = line?;
+        let result = match line.trim() {
+            "" => continue,
+            "exit" | "quit" => return Ok(()),
+            line => match cmds::LedgerInspectDb::parse_query(line) {
+                Ok(cmds::LedgerInspectDb::Shell(_)) => {
+                    writeln!(out, "Already in the shell")?;
+                    continue;
+                }
+                Ok(query) => run_inspect_db_query(&state, query, &mut out),
+                Err(err) => {
+                    // Also the help requested with `--help`
+                    writeln!(out, "{}", err.render())?;
+                    continue;
+                }
+            },
+        };
+        if let Err(err) = result {
+            writeln!(out, "Error: {err}")?;
+        }
+    }
+}
+
+/// Run a query of `inspect_db` other than the interactive shell
+fn run_inspect_db_query(
+    state: &storage::PersistentState,
+    query: cmds::LedgerInspectDb,
+    out: &mut impl std::io::Write,
+) -> Result<(), storage::inspect::Error> {
+    use storage::inspect;
+
+    match query {
+        cmds::LedgerInspectDb::Iter(cmds::InspectDbIter(
+            args::InspectDbIter {
+                cf,
+                prefix,
+                pattern,
+                type_hash,
+                limit,
+                json,
+            },
+        )) => {
+            let pattern = pattern
+                .map(|pattern| regex::Regex::new(&pattern))
+                .transpose()?;
+            let decoder = type_hash.as_ref().map(value_decoder).transpose()?;
+            let entries = inspect::iter_entries(
+                state.db(),
+                &cf,
+                prefix.as_ref(),
+                pattern.as_ref(),
+                decoder,
+            )
+            .take(limit.unwrap_or(usize::MAX));
+            for entry in entries {
+                if json {
+                    writeln!(out, "{}", entry.to_json())?;
+                } else {
+                    writeln!(out, "{entry}")?;
+                }
+            }
+        }
+        cmds::LedgerInspectDb::DiffHeights(cmds::InspectDbDiffHeights(
+            args::InspectDbDiffHeights {
+                from,
+                to,
+                prefixes,
+                store_types,
+                json,
+            },
+        )) => {
+            let filter = storage::state_diffs::DiffFilter {
+                prefixes,
+                store_types,
+            };
+            for diff in inspect::diff_heights(state.db(), from, to, &filter)? {
+                if json {
+                    writeln!(out, "{}", diff.to_json())?;
+                } else {
+                    writeln!(out, "{diff}")?;
+                }
+            }
+        }
+        cmds::LedgerInspectDb::DiffDbs(cmds::InspectDbDiffDbs(
+            args::InspectDbDiffDbs {
+                other_db,
+                cf,
+                prefix,
+                json,
+            },
+        )) => {
+            let other = storage::open_read_only(other_db)?;
+            let cfs =
+                cf.map_or(storage::COLUMN_FAMILIES.to_vec(), |cf| vec![cf]);
+            for cf in cfs {
+                for diff in
+                    inspect::diff_dbs(state.db(), &other, cf, prefix.as_ref())
+                {
+                    if json {
+                        writeln!(out, "{}", diff.to_json())?;
+                    } else {
+                        writeln!(out, "{diff}")?;
+                    }
+                }
+            }
+        }
+        cmds::LedgerInspectDb::CheckMerkle(cmds::InspectDbCheckMerkle(
+            args::InspectDbCheckMerkle { height, json },
+        )) => {
+            for check in inspect::check_merkle_trees(state, height)? {
+                if json {
+                    writeln!(out, "{}", check.to_json())?;
+                } else {
+                    writeln!(out, "{check}")?;
+                }
+            }
+        }
+        cmds::LedgerInspectDb::Stats(cmds::InspectDbStats(
+            args::InspectDbStats { depth, json },
+        )) => {
+            for stats in inspect::size_stats(state.db(), depth) {
+                if json {
+                    writeln!(out, "{}", stats.to_json())?;
+                } else {
+                    writeln!(out, "{stats}")?;
+                }
+            }
+        }
+        cmds::LedgerInspectDb::Shell(_) => {}
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// Find the deserializer registered with the given type hash
+fn value_decoder(
+    type_hash: &[u8; 32],
+) -> Result<storage::inspect::ValueDecoder, storage::inspect::Error> {
+    #[cfg(feature = "migrations")]
+    let decoder = namada_migrations::get_deserializer(type_hash);
+    // The deserializers are only registered with the "migrations" feature
+    #[cfg(not(feature = "migrations"))]
+    let decoder = None;
+    decoder.ok_or_else(|| {
+        storage::inspect::Error::UnknownTypeHash(HEXUPPER.encode(type_hash))
+    })
+}
+
 #[cfg(feature = "migrations")]
 pub fn query_db(
     config: config::Ledger,
@@ -359,7 +612,7 @@ pub fn query_db(
     let db_path = config.shell.db_dir(&chain_id);
 
     let db = storage::PersistentDB::open(db_path, None);
-    let db_visitor = storage::RocksDBUpdateVisitor::new(&db);
+    let db_visitor = storage::PersistentDBUpdateVisitor::new(&db);
     let bytes = db_visitor.read(key, cf).unwrap();
 
     let deserializer = namada_migrations::get_deserializer(type_hash)
@@ -415,9 +668,57 @@ pub fn update_db_keys(config: config::Ledger, updates: PathBuf, dry_run: bool) {
     }
 }
 
-/// Roll Namada state back to the previous height
-pub fn rollback(config: config::Ledger) -> Result<(), shell::Error> {
-    shell::rollback(config)
+/// Precompute the commitment to the replay protection entries of a node that
+/// is not running and write the DB changes that initialize it to the given
+/// path, to be applied with `ledger update-db`. Once initialized, the replay
+/// protection entries and the block state are committed to the app hash.
+#[cfg(feature = "migrations")]
+pub fn replay_protection_migration(config: config::Ledger, path: PathBuf) {
+    use std::str::FromStr;
+
+    use namada_replay_protection::{self as replay_protection, SetCommitment};
+    use namada_sdk::hash::Hash;
+    use namada_sdk::migrations::{DbChanges, DbUpdateType, UpdateValue};
+    use namada_sdk::state::DBIter;
+
+    let chain_id = config.chain_id;
+    let db_path = config.shell.db_dir(&chain_id);
+
+    let db = storage::PersistentDB::open(db_path, None);
+    let mut set_commitment = SetCommitment::default();
+    let mut num_entries = 0_u64;
+    for (key, _value, _gas) in db.iter_replay_protection() {
+        // The entries of the last block are under the `current` prefix
+        let hash = key.rsplit_once('/').map_or(key.as_str(), |(_, h)| h);
+        set_commitment
+            .insert(&Hash::from_str(hash).expect("Failed hash conversion"));
+        num_entries += 1;
+    }
+    let changes = DbChanges {
+        changes: vec![DbUpdateType::Add {
+            key: replay_protection::set_commitment_key(),
+            cf: DbColFam::SUBSPACE,
+            value: UpdateValue::raw(set_commitment.to_bytes()),
+            force: false,
+        }],
+    };
+    let json = serde_json::to_string_pretty(&changes)
+        .expect("Failed to serialize the DB changes");
+    std::fs::write(&path, json).expect("Failed to write the DB changes");
+    tracing::info!(
+        "Wrote the commitment to {} replay protection entries to {}",
+        num_entries,
+        path.display()
+    );
+}
+
+/// Roll Namada state back to the given height, or to the previous height
+/// together with the CometBFT state if no height is given
+pub fn rollback(
+    config: config::Ledger,
+    args: args::LedgerRollBack,
+) -> Result<storage::rollback::Rollback, shell::Error> {
+    shell::rollback(config, args.height, args.dry_run)
 }
 
 /// Runs and monitors a few concurrent tasks.
@@ -488,6 +789,13 @@ async fn run_aux_setup(
 ) -> RunAuxSetup {
     wasm_loader::validate_wasm_artifacts(wasm_dir).await;
 
+    // Make sure that the DB uses the configured storage backend
+    if let Err(err) =
+        storage::prepare_db(config.db_dir(), config.shell.db_backend)
+    {
+        panic!("Failed to prepare the DB: {err}");
+    }
+
     // Find the system available memory
     let available_memory_bytes = Lazy::new(|| {
         let sys = System::new_with_specifics(RefreshKind::new().with_memory());
@@ -631,6 +939,29 @@ fn start_abci_broadcaster_shell(
             .expect("`db_block_cache_size_bytes` must not exceed `usize::MAX`"),
     );
 
+    // Start the state change feed, if enabled
+    let change_feed = config.shell.change_feed_addr.map(|listen_addr| {
+        let sender = change_feed::channel();
+        let (feed_abort_send, feed_abort_recv) =
+            tokio::sync::oneshot::channel::<()>();
+        let feed_sender = sender.clone();
+        spawner
+            .abortable("Change feed", move |aborter| async move {
+                change_feed::serve(listen_addr, feed_sender, feed_abort_recv)
+                    .await;
+                tracing::info!("State change feed is no longer running.");
+
+                drop(aborter);
+
+                Ok(())
+            })
+            .with_cleanup(async move {
+                let _ = feed_abort_send.send(());
+            })
+            .spawn();
+        sender
+    });
+
     // Construct our ABCI application.
     let tendermint_mode = config.shell.tendermint_mode.clone();
     let proxy_app_address =
@@ -645,6 +976,7 @@ fn start_abci_broadcaster_shell(
         scheduled_migration,
         vp_wasm_compilation_cache,
         tx_wasm_compilation_cache,
+        change_feed,
     );
 
     // Channel for signalling shut down to ABCI server
@@ -15,8 +15,10 @@ use namada_sdk::events::EventLevel;
 use namada_sdk::gas::{self, Gas, GasMetering, TxGasMeter, VpGasMeter};
 use namada_sdk::hash::Hash;
 use namada_sdk::parameters::get_gas_scale;
+use namada_sdk::state::write_log::{ReadSet, WriteLog};
 use namada_sdk::state::{
-    DBIter, State, StorageHasher, StorageRead, TxWrites, WlState, DB,
+    DBIter, State, StorageHasher, StorageRead, TxWrites, VpEvaluation, WlState,
+    DB,
 };
 use namada_sdk::storage::TxIndex;
 use namada_sdk::token::event::{TokenEvent, TokenOperation};
@@ -39,7 +41,10 @@ use namada_vm::{self, wasm, WasmCacheAccess};
 use namada_vote_ext::EthereumTxData;
 use namada_vp::native_vp::NativeVp;
 use namada_vp::state::ReadConversionState;
-use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use namada_vp::trace::{self, TraceCall, VpTrace, VpVerdict};
+use rayon::iter::{
+    IntoParallelIterator, IntoParallelRefIterator, ParallelIterator,
+};
 use smooth_operator::checked;
 use thiserror::Error;
 
@@ -421,6 +426,116 @@ where
     Ok(extended_tx_result)
 }
 
+/// A batch of inner transactions of a successful wrapper to be executed
+/// speculatively
+pub(crate) struct InnerTxBatch<'a> {
+    /// The batch transaction with a raw header
+    pub tx: &'a Tx,
+    /// Hash of the header of the wrapper tx
+    pub wrapper_hash: &'a Hash,
+    /// The tx index
+    pub tx_index: TxIndex,
+    /// The result of the wrapper tx
+    pub wrapper_tx_result: &'a ExtendedTxResult<Error>,
+    /// The gas meter of the wrapper tx
+    pub tx_gas_meter: &'a TxGasMeter,
+}
+
+/// The result of the speculative execution of a batch of inner transactions
+/// on a fork of the write log
+pub(crate) struct SpeculativeBatch {
+    /// The result of the batch
+    pub result: std::result::Result<ExtendedTxResult<Error>, DispatchError>,
+    /// The gas meter of the batch
+    pub tx_gas_meter: TxGasMeter,
+    /// The fork of the write log with the pending changes of the batch
+    pub write_log: WriteLog,
+    /// The keys read by the batch, including by the validity predicates
+    pub read_set: ReadSet,
+}
+
+/// Speculatively execute the given batches of inner transactions in parallel,
+/// each on its own fork of the write log. The result of a batch is only valid
+/// if none of the keys it read have been changed by the batches committed
+/// before it, otherwise it must be re-executed. The replay protection check
+/// is left to the caller.
+///
+/// The batches whose wrapper result cannot be copied are not executed.
+pub(crate) fn speculate_inner_txs<D, H, CA>(
+    batches: &[InnerTxBatch<'_>],
+    state: &WlState<D, H>,
+    vp_wasm_cache: &VpCache<CA>,
+    tx_wasm_cache: &TxCache<CA>,
+) -> Vec<Option<SpeculativeBatch>>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+    CA: 'static + WasmCacheAccess + Sync,
+{
+    // Copy the wrapper results and gas meters before moving them to the
+    // worker threads
+    let batches: Vec<_> = batches
+        .iter()
+        .map(|batch| {
+            let wrapper_tx_result = copy_tx_result(batch.wrapper_tx_result)?;
+            let tx_gas_meter = RefCell::new(batch.tx_gas_meter.clone());
+            Some((
+                batch.tx,
+                batch.wrapper_hash,
+                batch.tx_index,
+                wrapper_tx_result,
+                tx_gas_meter,
+            ))
+        })
+        .collect();
+    batches
+        .into_par_iter()
+        .map(|batch| {
+            let (tx, wrapper_hash, tx_index, wrapper_tx_result, tx_gas_meter) =
+                batch?;
+            let (result, write_log) = state.with_write_log_fork(|fork| {
+                dispatch_inner_txs(
+                    tx,
+                    Some(wrapper_hash),
+                    wrapper_tx_result,
+                    tx_index,
+                    &tx_gas_meter,
+                    fork,
+                    &mut vp_wasm_cache.clone(),
+                    &mut tx_wasm_cache.clone(),
+                )
+            });
+            let read_set = write_log
+                .get_read_set()
+                .expect("The reads of a fork must be tracked");
+            Some(SpeculativeBatch {
+                result,
+                tx_gas_meter: tx_gas_meter.into_inner(),
+                write_log,
+                read_set,
+            })
+        })
+        .collect()
+}
+
+/// Copy a transaction result that holds no errors, like the result of a
+/// successful wrapper tx.
+fn copy_tx_result(
+    result: &ExtendedTxResult<Error>,
+) -> Option<ExtendedTxResult<Error>> {
+    let tx_result = result
+        .tx_result
+        .iter()
+        .map(|(hash, inner_result)| {
+            Some((*hash, Ok(inner_result.as_ref().ok()?.clone())))
+        })
+        .collect::<Option<_>>()?;
+    Some(
+        TxResult(tx_result)
+            .to_extended_result(Some(result.masp_tx_refs.clone())),
+    )
+}
+
 /// Transaction result for masp transfer
 pub struct MaspTxResult {
     tx_result: BatchedTxResult,
@@ -938,33 +1053,35 @@ where
         tx_wasm_cache,
     } = shell_params;
 
-    let verifiers = execute_tx(
-        batched_tx,
-        tx_index,
-        state,
-        tx_gas_meter,
-        vp_wasm_cache,
-        tx_wasm_cache,
-    )?;
+    trace::inner_tx(batched_tx.cmt.get_hash(), || -> Result<_> {
+        let verifiers = execute_tx(
+            batched_tx,
+            tx_index,
+            state,
+            tx_gas_meter,
+            vp_wasm_cache,
+            tx_wasm_cache,
+        )?;
 
-    let vps_result = check_vps(CheckVps {
-        batched_tx,
-        tx_index,
-        state,
-        tx_gas_meter: &mut tx_gas_meter.borrow_mut(),
-        verifiers_from_tx: &verifiers,
-        vp_wasm_cache,
-    })?;
+        let vps_result = check_vps(CheckVps {
+            batched_tx,
+            tx_index,
+            state,
+            tx_gas_meter: &mut tx_gas_meter.borrow_mut(),
+            verifiers_from_tx: &verifiers,
+            vp_wasm_cache,
+        })?;
 
-    let initialized_accounts = state.write_log().get_initialized_accounts();
-    let changed_keys = state.write_log().get_keys();
-    let events = state.write_log_mut().take_events();
+        let initialized_accounts = state.write_log().get_initialized_accounts();
+        let changed_keys = state.write_log().get_keys();
+        let events = state.write_log_mut().take_events();
 
-    Ok(BatchedTxResult {
-        changed_keys,
-        vps_result,
-        initialized_accounts,
-        events,
+        Ok(BatchedTxResult {
+            changed_keys,
+            vps_result,
+            initialized_accounts,
+            events,
+        })
     })
 }
 
@@ -1137,7 +1254,7 @@ where
 
 /// Execute verifiers' validity predicates
 #[allow(clippy::too_many_arguments)]
-fn execute_vps<S, CA>(
+pub(crate) fn execute_vps<S, CA>(
     verifiers: BTreeSet<Address>,
     keys_changed: BTreeSet<storage::Key>,
     batched_tx: &BatchedTxRef<'_>,
@@ -1150,252 +1267,249 @@ where
     S: 'static + ReadConversionState + State + Sync,
     CA: 'static + WasmCacheAccess + Sync,
 {
-    let vps_result = verifiers
-        .par_iter()
-        .try_fold(
-            || (VpsResult::default(), Gas::from(0)),
-            |(mut result, mut vps_gas), addr| {
-                let gas_meter =
-                    RefCell::new(VpGasMeter::new_from_tx_meter(tx_gas_meter));
-                let tx_accepted = match &addr {
-                    Address::Implicit(_) | Address::Established(_) => {
-                        let (vp_hash, gas) = state
-                            .validity_predicate::<parameters::Store<()>>(addr)
-                            .map_err(Error::StateError)?;
-                        gas_meter
-                            .borrow_mut()
-                            .consume(gas)
-                            .map_err(|err| Error::GasError(err.to_string()))?;
-                        let Some(vp_code_hash) = vp_hash else {
-                            return Err(Error::MissingAddress(addr.clone()));
-                        };
+    // Run the VP of the given address, returning whether it accepted the
+    // transaction and the gas it consumed
+    let run_vp = |addr: &Address| -> Result<(Result<()>, Gas)> {
+        let gas_meter =
+            RefCell::new(VpGasMeter::new_from_tx_meter(tx_gas_meter));
+        let tx_accepted = match &addr {
+            Address::Implicit(_) | Address::Established(_) => {
+                let (vp_hash, gas) = state
+                    .validity_predicate::<parameters::Store<()>>(addr)
+                    .map_err(Error::StateError)?;
+                gas_meter
+                    .borrow_mut()
+                    .consume(gas)
+                    .map_err(|err| Error::GasError(err.to_string()))?;
+                let Some(vp_code_hash) = vp_hash else {
+                    return Err(Error::MissingAddress(addr.clone()));
+                };
 
-                        wasm::run::vp(
-                            vp_code_hash,
+                wasm::run::vp(
+                    vp_code_hash,
+                    batched_tx,
+                    tx_index,
+                    addr,
+                    state,
+                    &gas_meter,
+                    &keys_changed,
+                    &verifiers,
+                    vp_wasm_cache.clone(),
+                )
+                .map_err(|err| match err {
+                    wasm::run::Error::GasError(msg) => Error::GasError(msg),
+                    wasm::run::Error::InvalidSectionSignature(msg) => {
+                        Error::InvalidSectionSignature(msg)
+                    }
+                    _ => Error::VpRunnerError(err),
+                })
+            }
+            Address::Internal(internal_addr) => {
+                let ctx = NativeVpCtx::new(
+                    addr,
+                    state,
+                    batched_tx.tx,
+                    batched_tx.cmt,
+                    tx_index,
+                    &gas_meter,
+                    &keys_changed,
+                    &verifiers,
+                    vp_wasm_cache.clone(),
+                );
+
+                match internal_addr {
+                    InternalAddress::PoS => PosVp::validate_tx(
+                        &ctx,
+                        batched_tx,
+                        &keys_changed,
+                        &verifiers,
+                    )
+                    .map_err(Error::NativeVpError),
+                    InternalAddress::Ibc => {
+                        let ibc = IbcVp::new(ctx);
+                        ibc.validate_tx(batched_tx, &keys_changed, &verifiers)
+                            .map_err(Error::NativeVpError)
+                    }
+                    InternalAddress::Parameters => ParametersVp::validate_tx(
+                        &ctx,
+                        batched_tx,
+                        &keys_changed,
+                        &verifiers,
+                    )
+                    .map_err(Error::NativeVpError),
+                    InternalAddress::PosSlashPool => {
+                        Err(Error::AccessForbidden((*internal_addr).clone()))
+                    }
+                    InternalAddress::Governance => GovernanceVp::validate_tx(
+                        &ctx,
+                        batched_tx,
+                        &keys_changed,
+                        &verifiers,
+                    )
+                    .map_err(Error::NativeVpError),
+                    InternalAddress::Pgf => PgfVp::validate_tx(
+                        &ctx,
+                        batched_tx,
+                        &keys_changed,
+                        &verifiers,
+                    )
+                    .map_err(Error::NativeVpError),
+                    InternalAddress::Multitoken => MultitokenVp::validate_tx(
+                        &ctx,
+                        batched_tx,
+                        &keys_changed,
+                        &verifiers,
+                    )
+                    .map_err(Error::NativeVpError),
+                    InternalAddress::Masp => MaspVp::validate_tx(
+                        &ctx,
+                        batched_tx,
+                        &keys_changed,
+                        &verifiers,
+                    )
+                    .map_err(Error::NativeVpError),
+                    InternalAddress::EthBridge => EthBridgeVp::validate_tx(
+                        &ctx,
+                        batched_tx,
+                        &keys_changed,
+                        &verifiers,
+                    )
+                    .map_err(Error::NativeVpError),
+                    InternalAddress::EthBridgePool => {
+                        EthBridgePoolVp::validate_tx(
+                            &ctx,
                             batched_tx,
-                            tx_index,
-                            addr,
-                            state,
-                            &gas_meter,
                             &keys_changed,
                             &verifiers,
-                            vp_wasm_cache.clone(),
                         )
-                        .map_err(|err| match err {
-                            wasm::run::Error::GasError(msg) => {
-                                Error::GasError(msg)
-                            }
-                            wasm::run::Error::InvalidSectionSignature(msg) => {
-                                Error::InvalidSectionSignature(msg)
-                            }
-                            _ => Error::VpRunnerError(err),
-                        })
+                        .map_err(Error::NativeVpError)
                     }
-                    Address::Internal(internal_addr) => {
-                        let ctx = NativeVpCtx::new(
-                            addr,
-                            state,
-                            batched_tx.tx,
-                            batched_tx.cmt,
-                            tx_index,
-                            &gas_meter,
-                            &keys_changed,
-                            &verifiers,
-                            vp_wasm_cache.clone(),
-                        );
-
-                        match internal_addr {
-                            InternalAddress::PoS => PosVp::validate_tx(
-                                &ctx,
-                                batched_tx,
-                                &keys_changed,
-                                &verifiers,
-                            )
-                            .map_err(Error::NativeVpError),
-                            InternalAddress::Ibc => {
-                                let ibc = IbcVp::new(ctx);
-                                ibc.validate_tx(
-                                    batched_tx,
-                                    &keys_changed,
-                                    &verifiers,
-                                )
-                                .map_err(Error::NativeVpError)
-                            }
-                            InternalAddress::Parameters => {
-                                ParametersVp::validate_tx(
-                                    &ctx,
-                                    batched_tx,
-                                    &keys_changed,
-                                    &verifiers,
-                                )
-                                .map_err(Error::NativeVpError)
-                            }
-                            InternalAddress::PosSlashPool => {
-                                Err(Error::AccessForbidden(
-                                    (*internal_addr).clone(),
-                                ))
-                            }
-                            InternalAddress::Governance => {
-                                GovernanceVp::validate_tx(
-                                    &ctx,
-                                    batched_tx,
-                                    &keys_changed,
-                                    &verifiers,
-                                )
-                                .map_err(Error::NativeVpError)
-                            }
-                            InternalAddress::Pgf => PgfVp::validate_tx(
-                                &ctx,
-                                batched_tx,
-                                &keys_changed,
-                                &verifiers,
-                            )
-                            .map_err(Error::NativeVpError),
-                            InternalAddress::Multitoken => {
-                                MultitokenVp::validate_tx(
-                                    &ctx,
-                                    batched_tx,
-                                    &keys_changed,
-                                    &verifiers,
-                                )
-                                .map_err(Error::NativeVpError)
-                            }
-                            InternalAddress::Masp => MaspVp::validate_tx(
-                                &ctx,
-                                batched_tx,
-                                &keys_changed,
-                                &verifiers,
-                            )
-                            .map_err(Error::NativeVpError),
-                            InternalAddress::EthBridge => {
-                                EthBridgeVp::validate_tx(
-                                    &ctx,
-                                    batched_tx,
-                                    &keys_changed,
-                                    &verifiers,
-                                )
-                                .map_err(Error::NativeVpError)
-                            }
-                            InternalAddress::EthBridgePool => {
-                                EthBridgePoolVp::validate_tx(
-                                    &ctx,
-                                    batched_tx,
-                                    &keys_changed,
-                                    &verifiers,
-                                )
-                                .map_err(Error::NativeVpError)
-                            }
-                            InternalAddress::Nut(_) => {
-                                EthBridgeNutVp::validate_tx(
-                                    &ctx,
-                                    batched_tx,
-                                    &keys_changed,
-                                    &verifiers,
-                                )
-                                .map_err(Error::NativeVpError)
-                            }
-                            internal_addr @ (InternalAddress::IbcToken(_)
-                            | InternalAddress::Erc20(_)) => {
-                                // The address should be a part of a multitoken
-                                // key
-                                verifiers
-                                    .contains(&Address::Internal(
-                                        InternalAddress::Multitoken,
-                                    ))
-                                    .ok_or_else(|| {
-                                        Error::AccessForbidden(
-                                            internal_addr.clone(),
-                                        )
-                                    })
-                            }
-                            InternalAddress::TempStorage => Err(
-                                // Temp storage changes must never be committed
-                                Error::AccessForbidden(
-                                    (*internal_addr).clone(),
-                                ),
-                            ),
-                            InternalAddress::ReplayProtection => Err(
-                                // Replay protection entries should never be
-                                // written to via transactions
-                                Error::AccessForbidden(
-                                    (*internal_addr).clone(),
-                                ),
-                            ),
-                        }
+                    InternalAddress::Nut(_) => EthBridgeNutVp::validate_tx(
+                        &ctx,
+                        batched_tx,
+                        &keys_changed,
+                        &verifiers,
+                    )
+                    .map_err(Error::NativeVpError),
+                    internal_addr @ (InternalAddress::IbcToken(_)
+                    | InternalAddress::Erc20(_)) => {
+                        // The address should be a part of a multitoken
+                        // key
+                        verifiers
+                            .contains(&Address::Internal(
+                                InternalAddress::Multitoken,
+                            ))
+                            .ok_or_else(|| {
+                                Error::AccessForbidden(internal_addr.clone())
+                            })
                     }
-                };
-
-                tx_accepted.map_or_else(
-                    |err| {
-                        result
-                            .status_flags
-                            .insert(err.invalid_section_signature_flag());
-                        result.rejected_vps.insert(addr.clone());
-                        result.errors.push((addr.clone(), err.to_string()));
-                    },
-                    |()| {
-                        result.accepted_vps.insert(addr.clone());
-                    },
-                );
+                    InternalAddress::TempStorage => Err(
+                        // Temp storage changes must never be committed
+                        Error::AccessForbidden((*internal_addr).clone()),
+                    ),
+                    InternalAddress::ReplayProtection => Err(
+                        // Replay protection entries should never be
+                        // written to via transactions
+                        Error::AccessForbidden((*internal_addr).clone()),
+                    ),
+                }
+            }
+        };
 
-                // Execution of VPs can (and must) be short-circuited
-                // only in case of a gas overflow to prevent the
-                // transaction from consuming resources that have not
-                // been acquired in the corresponding wrapper tx. For
-                // all the other errors we keep evaluating the vps. This
-                // allows to display a consistent VpsResult across all
-                // nodes and find any invalid signatures
-                vps_gas = vps_gas
-                    .checked_add(gas_meter.borrow().get_vp_consumed_gas())
-                    .ok_or(Error::GasError(
-                        gas::Error::GasOverflow.to_string(),
-                    ))?;
-                gas_meter
-                    .borrow()
-                    .check_vps_limit(vps_gas.clone())
-                    .map_err(|err| Error::GasError(err.to_string()))?;
+        Ok((tx_accepted, gas_meter.into_inner().get_vp_consumed_gas()))
+    };
 
-                Ok((result, vps_gas))
-            },
-        )
-        .try_reduce(
-            || (VpsResult::default(), Gas::from(0)),
-            |a, b| merge_vp_results(a, b, tx_gas_meter),
-        )?;
+    // The VPs may run on other threads, which have to know if they are part of
+    // a trace being recorded
+    let tracing = trace::is_recording();
+    let trace_vp = |addr| {
+        let (vp_result, calls) = trace::record_vp(tracing, || run_vp(addr));
+        (addr, vp_result, calls)
+    };
 
-    Ok(vps_result)
+    // The VPs only read the same state, so they can be run concurrently. Their
+    // results are then collected in order of the verifiers, like in a
+    // sequential evaluation, so that the outcome doesn't depend on which VP
+    // finishes first.
+    match state.in_mem().vp_evaluation {
+        VpEvaluation::Sequential => {
+            collect_vp_results(verifiers.iter().map(trace_vp), tx_gas_meter)
+        }
+        VpEvaluation::Parallel => collect_vp_results(
+            verifiers.par_iter().map(trace_vp).collect::<Vec<_>>(),
+            tx_gas_meter,
+        ),
+    }
 }
 
-/// Merge VP results from parallel runs
-fn merge_vp_results(
-    (a, a_gas): (VpsResult, Gas),
-    (mut b, b_gas): (VpsResult, Gas),
+/// Collect the results of the VPs, in order of the verifiers, together with
+/// their recorded calls if a trace is being recorded
+fn collect_vp_results<'a>(
+    vp_results: impl IntoIterator<
+        Item = (
+            &'a Address,
+            Result<(Result<()>, Gas)>,
+            Option<Vec<TraceCall>>,
+        ),
+    >,
     tx_gas_meter: &TxGasMeter,
 ) -> Result<(VpsResult, Gas)> {
-    let mut accepted_vps = a.accepted_vps;
-    let mut rejected_vps = a.rejected_vps;
-    accepted_vps.extend(b.accepted_vps);
-    rejected_vps.extend(b.rejected_vps);
-    let mut errors = a.errors;
-    errors.append(&mut b.errors);
-    let status_flags = a.status_flags | b.status_flags;
-
-    let vps_gas = a_gas
-        .checked_add(b_gas)
-        .ok_or(Error::GasError(gas::Error::GasOverflow.to_string()))?;
-    tx_gas_meter
-        .check_vps_limit(vps_gas.clone())
-        .map_err(|err| Error::GasError(err.to_string()))?;
+    vp_results.into_iter().try_fold(
+        (VpsResult::default(), Gas::from(0)),
+        |(mut result, vps_gas), (addr, vp_result, calls)| {
+            if let Some(calls) = calls {
+                let (verdict, gas) = match &vp_result {
+                    Ok((Ok(()), gas)) => (VpVerdict::Accepted, gas.clone()),
+                    Ok((Err(err), gas)) => (
+                        VpVerdict::Rejected {
+                            reason: err.to_string(),
+                        },
+                        gas.clone(),
+                    ),
+                    Err(err) => (
+                        VpVerdict::Rejected {
+                            reason: err.to_string(),
+                        },
+                        Gas::default(),
+                    ),
+                };
+                trace::push_vp(VpTrace {
+                    addr: addr.clone(),
+                    verdict,
+                    gas,
+                    calls,
+                });
+            }
+            let (tx_accepted, vp_gas) = vp_result?;
+            tx_accepted.map_or_else(
+                |err| {
+                    result
+                        .status_flags
+                        .insert(err.invalid_section_signature_flag());
+                    result.rejected_vps.insert(addr.clone());
+                    result.errors.push((addr.clone(), err.to_string()));
+                },
+                |()| {
+                    result.accepted_vps.insert(addr.clone());
+                },
+            );
 
-    Ok((
-        VpsResult {
-            accepted_vps,
-            rejected_vps,
-            errors,
-            status_flags,
+            // Execution of VPs can (and must) be short-circuited only in case
+            // of a gas overflow to prevent the transaction from consuming
+            // resources that have not been acquired in the corresponding
+            // wrapper tx. For all the other errors we keep evaluating the vps.
+            // This allows to display a consistent VpsResult across all nodes
+            // and find any invalid signatures
+            let vps_gas = vps_gas
+                .checked_add(vp_gas)
+                .ok_or(Error::GasError(gas::Error::GasOverflow.to_string()))?;
+            tx_gas_meter
+                .check_vps_limit(vps_gas.clone())
+                .map_err(|err| Error::GasError(err.to_string()))?;
+
+            Ok((result, vps_gas))
         },
-        vps_gas,
-    ))
+    )
 }
 
 #[cfg(test)]
@@ -1639,6 +1753,88 @@ mod tests {
         assert!(matches!(result.unwrap_err(), Error::GasError(_)));
     }
 
+    /// Test that evaluating the VPs sequentially or in parallel yields the
+    /// same results and gas
+    #[test]
+    fn test_vp_evaluation_modes() {
+        let (mut state, _validators) = test_utils::setup_default_storage();
+
+        // Store the code of the test VPs and assign them to some accounts
+        let accepting = Address::Established([0xaa; 20].into());
+        let rejecting = Address::Established([0xbb; 20].into());
+        for (addr, vp) in [
+            (&accepting, TestWasms::VpAlwaysTrue),
+            (&rejecting, TestWasms::VpAlwaysFalse),
+        ] {
+            let vp_code = vp.read_bytes();
+            let code_hash = Hash::sha256(&vp_code);
+            let code_len = vp_code.len() as u64;
+            let key = namada_sdk::storage::Key::wasm_code(&code_hash);
+            let len_key = namada_sdk::storage::Key::wasm_code_len(&code_hash);
+            let vp_key = namada_sdk::storage::Key::validity_predicate(addr);
+            state.write(&key, vp_code).unwrap();
+            state.write(&len_key, code_len).unwrap();
+            state.write(&vp_key, code_hash).unwrap();
+        }
+        state.commit_tx_batch();
+        state.commit_block().unwrap();
+
+        let mut tx = Tx::from_type(namada_sdk::tx::data::TxType::Raw);
+        tx.set_code(namada_sdk::tx::Code::new(vec![], None));
+        tx.set_data(namada_sdk::tx::Data::new(vec![]));
+        let batched_tx = tx.batch_ref_first_tx().unwrap();
+        let slash_pool = Address::Internal(InternalAddress::PosSlashPool);
+        let mut verifiers = BTreeSet::from([
+            accepting.clone(),
+            rejecting.clone(),
+            slash_pool.clone(),
+        ]);
+        let (mut vp_cache, _) =
+            wasm::compilation_cache::common::testing::cache();
+        let gas_meter = TxGasMeter::new(u64::MAX);
+
+        let mut results = vec![];
+        for mode in [VpEvaluation::Sequential, VpEvaluation::Parallel] {
+            state.in_mem_mut().vp_evaluation = mode;
+            let (vps_result, vps_gas) = execute_vps(
+                verifiers.clone(),
+                BTreeSet::new(),
+                &batched_tx,
+                &TxIndex::default(),
+                &state,
+                &gas_meter,
+                &mut vp_cache,
+            )
+            .unwrap();
+            assert_eq!(
+                vps_result.accepted_vps,
+                BTreeSet::from([accepting.clone()])
+            );
+            assert_eq!(
+                vps_result.rejected_vps,
+                BTreeSet::from([rejecting.clone(), slash_pool.clone()])
+            );
+            results.push((vps_result.errors, vps_gas));
+        }
+        assert_eq!(results[0], results[1]);
+
+        // An account without a VP fails the evaluation in both modes
+        verifiers.insert(Address::Established([0xcc; 20].into()));
+        for mode in [VpEvaluation::Sequential, VpEvaluation::Parallel] {
+            state.in_mem_mut().vp_evaluation = mode;
+            let result = execute_vps(
+                verifiers.clone(),
+                BTreeSet::new(),
+                &batched_tx,
+                &TxIndex::default(),
+                &state,
+                &gas_meter,
+                &mut vp_cache,
+            );
+            assert!(matches!(result.unwrap_err(), Error::MissingAddress(_)));
+        }
+    }
+
     // Test that the host function for signature verification we expose allows
     // the vps to detect a tx that has been tampered with
     #[test]
@@ -17,7 +17,8 @@ use namada_sdk::proof_of_stake::storage::{
 };
 use namada_sdk::state::write_log::StorageModification;
 use namada_sdk::state::{
-    Result, ResultExt, StorageWrite, EPOCH_SWITCH_BLOCKS_DELAY,
+    Result, ResultExt, StorageWrite, TxBatchExecution,
+    EPOCH_SWITCH_BLOCKS_DELAY,
 };
 use namada_sdk::storage::{BlockHeader, BlockResults, Epoch};
 use namada_sdk::tx::data::protocol::ProtocolTxType;
@@ -30,7 +31,9 @@ use namada_vote_ext::ethereum_tx_data_variants;
 use tendermint::abci::types::Misbehavior;
 
 use super::*;
-use crate::protocol::{DispatchArgs, DispatchError};
+use crate::protocol::{
+    DispatchArgs, DispatchError, InnerTxBatch, SpeculativeBatch,
+};
 use crate::shell::stats::InternalStats;
 use crate::tendermint::abci::types::VoteInfo;
 use crate::tendermint_proto;
@@ -806,7 +809,12 @@ where
         successful_wrappers
     }
 
-    // Execute the transaction batches for successful wrapper transactions
+    // Execute the transaction batches for successful wrapper transactions.
+    // The batches are first executed speculatively in parallel, each on its
+    // own fork of the write log, and then committed in order. A batch that
+    // read any key changed by the batches committed before it, or that is a
+    // replay, is instead executed again in order, so that the outcome is
+    // identical to a sequential execution.
     fn execute_tx_batches(
         &mut self,
         successful_wrappers: Vec<WrapperCache>,
@@ -817,38 +825,94 @@ where
             height,
         }: ExecutionArgs<'_>,
     ) {
-        for WrapperCache {
-            mut tx,
-            tx_index,
-            gas_meter: tx_gas_meter,
-            event: tx_event,
-            extended_tx_result: wrapper_tx_result,
-        } in successful_wrappers
+        let batches: Vec<_> = successful_wrappers
+            .into_iter()
+            .map(|mut wrapper| {
+                let replay_protection_hashes = ReplayProtectionHashes {
+                    raw_header_hash: wrapper.tx.raw_header_hash(),
+                    header_hash: wrapper.tx.header_hash(),
+                };
+                // change tx type to raw for execution
+                wrapper.tx.update_header(TxType::Raw);
+                (wrapper, replay_protection_hashes)
+            })
+            .collect();
+
+        let speculative_batches = match self.state.in_mem().tx_batch_execution {
+            TxBatchExecution::Sequential => {
+                batches.iter().map(|_| None).collect()
+            }
+            TxBatchExecution::Parallel => protocol::speculate_inner_txs(
+                &batches
+                    .iter()
+                    .map(|(wrapper, hashes)| InnerTxBatch {
+                        tx: &wrapper.tx,
+                        wrapper_hash: &hashes.header_hash,
+                        tx_index: TxIndex::must_from_usize(wrapper.tx_index),
+                        wrapper_tx_result: &wrapper.extended_tx_result,
+                        tx_gas_meter: &wrapper.gas_meter,
+                    })
+                    .collect::<Vec<_>>(),
+                &self.state,
+                &self.vp_wasm_cache,
+                &self.tx_wasm_cache,
+            ),
+        };
+        // The keys changed by the batches committed after the forks
+        let mut committed_keys = BTreeSet::new();
+
+        for (
+            (
+                WrapperCache {
+                    tx,
+                    tx_index,
+                    gas_meter: tx_gas_meter,
+                    event: tx_event,
+                    extended_tx_result: wrapper_tx_result,
+                },
+                replay_protection_hashes,
+            ),
+            speculative_batch,
+        ) in batches.into_iter().zip(speculative_batches)
         {
-            let tx_hash = tx.header_hash();
+            let tx_hash = replay_protection_hashes.header_hash;
             let is_atomic_batch = tx.header.atomic;
             let commitments_len = tx.commitments().len() as u64;
-            let replay_protection_hashes = Some(ReplayProtectionHashes {
-                raw_header_hash: tx.raw_header_hash(),
-                header_hash: tx.header_hash(),
-            });
-
-            // change tx type to raw for execution
-            tx.update_header(TxType::Raw);
-            let tx_gas_meter = RefCell::new(tx_gas_meter);
-            let dispatch_result = protocol::dispatch_tx(
-                &tx,
-                DispatchArgs::Raw {
-                    wrapper_hash: Some(&tx_hash),
-                    tx_index: TxIndex::must_from_usize(tx_index),
-                    wrapper_tx_result: Some(wrapper_tx_result),
-                    vp_wasm_cache: &mut self.vp_wasm_cache,
-                    tx_wasm_cache: &mut self.tx_wasm_cache,
-                },
-                &tx_gas_meter,
-                &mut self.state,
+            let is_replay = self.state.write_log().has_replay_protection_entry(
+                &replay_protection_hashes.raw_header_hash,
             );
-            let tx_gas_meter = tx_gas_meter.into_inner();
+
+            let (dispatch_result, tx_gas_meter) = match speculative_batch {
+                Some(SpeculativeBatch {
+                    result,
+                    tx_gas_meter,
+                    write_log,
+                    read_set,
+                }) if !is_replay && !read_set.intersects(&committed_keys) => {
+                    self.state.write_log_mut().join_fork(write_log);
+                    (result, tx_gas_meter)
+                }
+                speculative_batch => {
+                    if speculative_batch.is_some() && !is_replay {
+                        stats.increment_reexecuted_batches();
+                    }
+                    let tx_gas_meter = RefCell::new(tx_gas_meter);
+                    let dispatch_result = protocol::dispatch_tx(
+                        &tx,
+                        DispatchArgs::Raw {
+                            wrapper_hash: Some(&tx_hash),
+                            tx_index: TxIndex::must_from_usize(tx_index),
+                            wrapper_tx_result: Some(wrapper_tx_result),
+                            vp_wasm_cache: &mut self.vp_wasm_cache,
+                            tx_wasm_cache: &mut self.tx_wasm_cache,
+                        },
+                        &tx_gas_meter,
+                        &mut self.state,
+                    );
+                    (dispatch_result, tx_gas_meter.into_inner())
+                }
+            };
+            committed_keys.extend(self.state.write_log().get_batch_keys());
             let consumed_gas = tx_gas_meter.get_tx_consumed_gas();
 
             // update the gas cost of the corresponding wrapper
@@ -862,7 +926,7 @@ where
                     tx: &tx,
                     commitments_len,
                     tx_index,
-                    replay_protection_hashes,
+                    replay_protection_hashes: Some(replay_protection_hashes),
                     tx_gas_meter,
                     height,
                 },
@@ -874,6 +938,40 @@ where
             );
         }
     }
+
+    /// Execute the given transactions as the batches of successful wrappers
+    /// at the current block height, with the configured
+    /// [`TxBatchExecution`]. The changes are committed to the block write log.
+    #[cfg(feature = "benches")]
+    pub fn execute_inner_tx_batches(
+        &mut self,
+        txs: Vec<Tx>,
+    ) -> shim::response::FinalizeBlock {
+        let height = self.state.in_mem().block.height;
+        let successful_wrappers = txs
+            .into_iter()
+            .enumerate()
+            .map(|(tx_index, tx)| WrapperCache {
+                event: new_tx_event(&tx, height.0),
+                tx,
+                tx_index,
+                gas_meter: TxGasMeter::new(u64::MAX),
+                extended_tx_result: namada_sdk::tx::data::TxResult::default()
+                    .to_extended_result(None),
+            })
+            .collect();
+        let mut response = shim::response::FinalizeBlock::default();
+        self.execute_tx_batches(
+            successful_wrappers,
+            ExecutionArgs {
+                response: &mut response,
+                changed_keys: &mut BTreeSet::new(),
+                stats: &mut InternalStats::default(),
+                height,
+            },
+        );
+        response
+    }
 }
 
 struct ExecutionArgs<'finalize> {
@@ -5802,6 +5900,93 @@ mod test_finalize_block {
         }
     }
 
+    /// Test that executing the transaction batches of a block in parallel
+    /// gives the same tx results, gas, events and storage root as executing
+    /// them sequentially, including when a batch reads a key written by a
+    /// previous batch of the block and must be re-executed.
+    #[test]
+    fn test_parallel_tx_batch_execution() {
+        let sk = wallet::defaults::bertha_keypair();
+        let key_a: Key = "parallel_key_a".parse().unwrap();
+        let key_b: Key = "parallel_key_b".parse().unwrap();
+
+        let shells = [TxBatchExecution::Sequential, TxBatchExecution::Parallel]
+            .map(|mode| {
+                let (mut shell, _, _, _) = setup();
+                shell.state.in_mem_mut().tx_batch_execution = mode;
+                shell
+            });
+
+        let mk_tx = |code: TestWasms, data: Vec<u8>| {
+            let mut tx =
+                Tx::from_type(TxType::Wrapper(Box::new(WrapperTx::new(
+                    Fee {
+                        amount_per_gas_unit: DenominatedAmount::native(
+                            1.into(),
+                        ),
+                        token: shells[0].state.in_mem().native_token.clone(),
+                    },
+                    sk.ref_to(),
+                    WRAPPER_GAS_LIMIT.into(),
+                ))));
+            tx.header.chain_id = shells[0].chain_id.clone();
+            tx.set_code(Code::new(code.read_bytes(), None));
+            tx.set_data(Data::new(data));
+            tx.sign_wrapper(sk.clone());
+            ProcessedTx {
+                tx: tx.to_bytes().into(),
+                result: TxResult {
+                    code: ResultCode::Ok.into(),
+                    info: "".into(),
+                },
+            }
+        };
+        let write = |key: &Key| TxWriteData {
+            key: key.clone(),
+            value: STORAGE_VALUE.serialize_to_vec(),
+        };
+        let req = FinalizeBlock {
+            txs: vec![
+                mk_tx(
+                    TestWasms::TxWriteStorageKey,
+                    write(&key_a).serialize_to_vec(),
+                ),
+                mk_tx(
+                    TestWasms::TxWriteStorageKey,
+                    write(&key_b).serialize_to_vec(),
+                ),
+                // Speculatively, this fails to read the key that is only
+                // written by the first batch, so it only succeeds if it's
+                // re-executed after the first batch is committed
+                mk_tx(TestWasms::TxReadStorageKey, key_a.serialize_to_vec()),
+            ],
+            ..Default::default()
+        };
+
+        let [sequential, parallel] = shells.map(|mut shell| {
+            let events =
+                shell.finalize_block(req.clone()).expect("Test failed");
+            shell.commit();
+            (events, shell.state.in_mem().merkle_root())
+        });
+
+        let (events, _) = &sequential;
+        let applied: Vec<_> = events
+            .iter()
+            .filter(|event| *event.kind() == APPLIED_TX)
+            .collect();
+        assert_eq!(applied.len(), 3);
+        for event in applied {
+            let code = event.read_attribute::<CodeAttr>().unwrap();
+            assert_eq!(code, ResultCode::Ok);
+            let results = event.read_attribute::<Batch<'_>>().unwrap();
+            assert!(results.are_results_successfull());
+            assert!(event.read_attribute::<GasUsed>().is_ok());
+        }
+        // The events carry the tx results and the gas used
+        assert_eq!(sequential, parallel);
+    }
+
     // Test a failing atomic batch with two successful txs and a failing one.
     // Verify that also the changes applied by the valid txs are dropped and
     // that the last transaction is never executed (batch short-circuit)
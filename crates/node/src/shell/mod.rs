@@ -31,7 +31,9 @@ use std::path::{Path, PathBuf};
 #[allow(unused_imports)]
 use std::rc::Rc;
 
-use namada_apps_lib::wallet::{self, ValidatorData, ValidatorKeys};
+use namada_apps_lib::wallet::{
+    self, StoredValidatorKeys, ValidatorData, ValidatorKeys,
+};
 use namada_sdk::address::Address;
 use namada_sdk::borsh::{BorshDeserialize, BorshSerializeExt};
 use namada_sdk::chain::{BlockHeight, ChainId};
@@ -52,7 +54,7 @@ use namada_sdk::proof_of_stake::types::{
 };
 use namada_sdk::state::tx_queue::ExpiredTx;
 use namada_sdk::state::{
-    DBIter, FullAccessState, Sha256Hasher, StorageHasher, StorageRead,
+    DBIter, DbError, FullAccessState, Sha256Hasher, StorageHasher, StorageRead,
     TempWlState, WlState, DB, EPOCH_SWITCH_BLOCKS_DELAY,
 };
 use namada_sdk::storage::{Key, TxIndex};
@@ -72,17 +74,26 @@ use thiserror::Error;
 use tokio::sync::mpsc::{Receiver, UnboundedSender};
 
 use super::ethereum_oracle::{self as oracle, last_processed_block};
-use crate::config::{self, genesis, TendermintMode, ValidatorLocalConfig};
+use crate::config::{
+    self, genesis, Pkcs11Config, TendermintMode, ValidatorLocalConfig,
+};
+use crate::hsm::{ValidatorHsmKeys, ValidatorSigner};
 use crate::protocol::ShellParams;
 use crate::shims::abcipp_shim_types::shim;
 use crate::shims::abcipp_shim_types::shim::response::TxResult;
 use crate::shims::abcipp_shim_types::shim::TakeSnapshot;
+use crate::storage::rollback::Rollback;
+use crate::storage::state_sync::{self, SnapshotRestore};
 use crate::tendermint::abci::{request, response};
 use crate::tendermint::{self, validator};
 use crate::tendermint_proto::crypto::public_key;
-use crate::{protocol, storage, tendermint_node};
+use crate::{protocol, storage, tendermint_config, tendermint_node};
+
+/// The maximum number of heights whose block history is pruned in a single
+/// batch, so that the requests to the shell don't wait long on the pruning
+const BLOCK_HISTORY_PRUNING_BATCH_SIZE: u64 = 100;
 
-fn key_to_tendermint(
+pub(crate) fn key_to_tendermint(
     pk: &common::PublicKey,
 ) -> std::result::Result<public_key::Sum, ParsePublicKeyError> {
     match pk {
@@ -157,20 +168,125 @@ pub fn reset(config: config::Ledger) -> ShellResult<()> {
     Ok(())
 }
 
-pub fn rollback(config: config::Ledger) -> ShellResult<()> {
-    // Rollback Tendermint state
-    tracing::info!("Rollback Tendermint state");
-    let tendermint_block_height =
-        tendermint_node::rollback(config.cometbft_dir())
-            .map_err(Error::Tendermint)?;
-
-    // Rollback Namada state
+/// Roll the Namada state back to the given height, or the CometBFT state and
+/// the Namada state back to the previous height if no height is given. With a
+/// dry run, only the changes that would be made in the DB are returned.
+pub fn rollback(
+    config: config::Ledger,
+    height: Option<BlockHeight>,
+    dry_run: bool,
+) -> ShellResult<Rollback> {
     let db_path = config.shell.db_dir(&config.chain_id);
-    let mut db = storage::PersistentDB::open(db_path, None);
-    tracing::info!("Rollback Namada state");
+    let db = storage::PersistentDB::open(db_path, None);
+    let storage_error =
+        |e: DbError| Error::Storage(namada_sdk::state::Error::new(e));
+
+    let height = match height {
+        Some(height) => height,
+        None if dry_run => {
+            let last_height = storage::rollback::read_last_height(&db)
+                .map_err(storage_error)?;
+            BlockHeight(last_height.0.saturating_sub(1))
+        }
+        None => {
+            // Rollback Tendermint state
+            tracing::info!("Rollback Tendermint state");
+            tendermint_node::rollback(config.cometbft_dir())
+                .map_err(Error::Tendermint)?
+        }
+    };
 
-    db.rollback(tendermint_block_height)
-        .map_err(|e| Error::Storage(namada_sdk::state::Error::new(e)))
+    let rollback = Rollback::plan(&db, height).map_err(storage_error)?;
+    tracing::info!(
+        "Namada last block height: {}, target height: {}",
+        rollback.last_height,
+        rollback.height
+    );
+    if dry_run {
+        return Ok(rollback);
+    }
+    if rollback.changes.is_empty() {
+        tracing::info!("Namada state is already at the target height");
+    } else {
+        // Rollback Namada state
+        tracing::info!("Rollback Namada state");
+        rollback.apply(&db).map_err(storage_error)?;
+    }
+    Ok(rollback)
+}
+
+/// Load the public keys of a validator from the given HSM. The secret keys of
+/// the validator's wallet are optional, but if they are present, they must
+/// match the keys in the HSM.
+#[cfg_attr(any(test, fuzzing), allow(dead_code))]
+fn load_hsm_keys(
+    config: &Pkcs11Config,
+    keys: &StoredValidatorKeys,
+) -> ValidatorHsmKeys {
+    use namada_sdk::key::common::SigningKey;
+
+    let hsm_keys = ValidatorHsmKeys::load(config).unwrap_or_else(|err| {
+        panic!("Failed to load the validator keys from the HSM: {err}")
+    });
+    if let Some(protocol_keypair) = &keys.protocol_keypair {
+        assert_eq!(
+            hsm_keys.protocol_key.public_key(),
+            protocol_keypair.to_public(),
+            "The HSM key \"{}\" is not the protocol key of this validator",
+            hsm_keys.protocol_key.label(),
+        );
+    }
+    match (&hsm_keys.eth_bridge_key, &keys.eth_bridge_keypair) {
+        (Some(eth_bridge_key), Some(eth_bridge_keypair)) => assert_eq!(
+            eth_bridge_key.public_key(),
+            eth_bridge_keypair.to_public(),
+            "The HSM key \"{}\" is not the Ethereum bridge key of this \
+             validator",
+            eth_bridge_key.label(),
+        ),
+        (None, None) => panic!(
+            "The Ethereum bridge key of this validator is neither in the HSM \
+             nor in its wallet"
+        ),
+        _ => {}
+    }
+    tracing::info!("Loaded the validator keys from the HSM");
+    hsm_keys
+}
+
+/// Run the remote signer of the consensus key held in the HSM on its own
+/// thread, serving CometBFT on its `priv_validator_laddr` Unix socket.
+#[cfg_attr(any(test, fuzzing), allow(dead_code))]
+fn spawn_hsm_consensus_signer(
+    consensus_key: &crate::hsm::Pkcs11Key,
+    chain_id: &ChainId,
+    cometbft_dir: &Path,
+    priv_validator_laddr: Option<&tendermint_config::net::Address>,
+) {
+    use crate::hsm::privval;
+
+    let socket_path = match priv_validator_laddr {
+        Some(tendermint_config::net::Address::Unix { path }) => {
+            PathBuf::from(path)
+        }
+        _ => panic!(
+            "CometBFT's `priv_validator_laddr` must be set to a Unix socket \
+             to use the consensus key in the HSM"
+        ),
+    };
+    let state_path = cometbft_dir.join("data").join("hsm_priv_validator_state");
+    let signer = privval::ConsensusSigner::new(
+        consensus_key.clone(),
+        chain_id.clone(),
+        state_path,
+    )
+    .unwrap_or_else(|err| {
+        panic!("Failed to set up the HSM consensus key signer: {err}")
+    });
+    std::thread::Builder::new()
+        .name("hsm-privval".to_string())
+        .spawn(move || privval::run(signer, socket_path))
+        .expect("Failed to spawn the HSM consensus key signer");
 }
 
 #[derive(Debug)]
@@ -182,6 +298,7 @@ pub(super) enum ShellMode {
         eth_oracle: Option<EthereumOracleChannels>,
         validator_local_config: Option<ValidatorLocalConfig>,
         local_config: Option<NodeLocalConfig>,
+        hsm_keys: Option<ValidatorHsmKeys>,
     },
     Full {
         local_config: Option<NodeLocalConfig>,
@@ -286,8 +403,9 @@ impl ShellMode {
                 data:
                     ValidatorData {
                         keys:
-                            ValidatorKeys {
-                                protocol_keypair, ..
+                            StoredValidatorKeys {
+                                protocol_keypair: Some(protocol_keypair),
+                                ..
                             },
                         ..
                     },
@@ -297,16 +415,44 @@ impl ShellMode {
         }
     }
 
+    /// Get the signer of the protocol txs of this validator, which uses the
+    /// HSM protocol key if one is configured.
+    pub fn get_protocol_signer(&self) -> Option<ValidatorSigner<'_>> {
+        match self {
+            ShellMode::Validator {
+                hsm_keys: Some(hsm_keys),
+                ..
+            } => Some(ValidatorSigner::Hsm(&hsm_keys.protocol_key)),
+            _ => self.get_protocol_key().map(ValidatorSigner::Local),
+        }
+    }
+
+    /// Get the signer of the Ethereum bridge vote extensions of this
+    /// validator, which uses the HSM Ethereum bridge key if one is configured.
+    pub fn get_eth_bridge_signer(&self) -> Option<ValidatorSigner<'_>> {
+        match self {
+            ShellMode::Validator {
+                hsm_keys:
+                    Some(ValidatorHsmKeys {
+                        eth_bridge_key: Some(eth_bridge_key),
+                        ..
+                    }),
+                ..
+            } => Some(ValidatorSigner::Hsm(eth_bridge_key)),
+            _ => self.get_eth_bridge_keypair().map(ValidatorSigner::Local),
+        }
+    }
+
     /// Get the Ethereum bridge keypair for this validator.
-    #[cfg_attr(not(test), allow(dead_code))]
     pub fn get_eth_bridge_keypair(&self) -> Option<&common::SecretKey> {
         match self {
             ShellMode::Validator {
                 data:
                     ValidatorData {
                         keys:
-                            ValidatorKeys {
-                                eth_bridge_keypair, ..
+                            StoredValidatorKeys {
+                                eth_bridge_keypair: Some(eth_bridge_keypair),
+                                ..
                             },
                         ..
                     },
@@ -346,7 +492,7 @@ pub struct SnapshotSync {
     pub height: BlockHeight,
     pub expected: Vec<Hash>,
     pub strikes: u64,
-    pub snapshot: std::fs::File,
+    pub restore: SnapshotRestore<Sha256Hasher>,
 }
 
 #[derive(Debug)]
@@ -395,7 +541,8 @@ pub fn is_key_diff_storable(key: &namada_sdk::storage::Key) -> bool {
         && *key != token::storage_key::masp_assets_hash_key()
         && !token::storage_key::is_masp_commitment_anchor_key(key)
         || ibc::storage::is_ibc_counter_key(key)
-        || proof_of_stake::storage_key::is_delegation_targets_key(key))
+        || proof_of_stake::storage_key::is_delegation_targets_key(key)
+        || namada_replay_protection::is_set_commitment_key(key))
 }
 
 /// Channels for communicating with an Ethereum oracle.
@@ -422,10 +569,13 @@ impl EthereumOracleChannels {
 
 impl Shell<crate::storage::PersistentDB, Sha256Hasher> {
     /// Restore the database with data fetched from the State Sync protocol.
-    pub fn restore_database_from_state_sync(&mut self) {
-        let Some(syncing) = self.syncing.as_mut() else {
-            return;
+    pub fn restore_database_from_state_sync(
+        &mut self,
+    ) -> Result<(), state_sync::Error> {
+        let Some(syncing) = self.syncing.take() else {
+            return Ok(());
         };
+        let restored_db = syncing.restore.finish()?;
 
         let db_block_cache_size_bytes = {
             let config = crate::config::Config::load(
@@ -454,11 +604,12 @@ impl Shell<crate::storage::PersistentDB, Sha256Hasher> {
 
         self.state
             .db_mut()
-            .restore_from((&db_cache, &mut syncing.snapshot))
+            .restore_from((&db_cache, restored_db.as_path()))
             .expect("Failed to restore state from snapshot");
 
         // rebuild the in-memory state
         self.state.load_last_state();
+        Ok(())
     }
 }
 
@@ -480,6 +631,8 @@ where
         vp_wasm_compilation_cache: u64,
         tx_wasm_compilation_cache: u64,
     ) -> Self {
+        #[cfg(not(any(test, fuzzing)))]
+        let cometbft_dir = config.cometbft_dir();
         let chain_id = config.chain_id;
         let db_path = config.shell.db_dir(&chain_id);
         let base_dir = config.shell.base_dir;
@@ -515,7 +668,7 @@ where
         };
 
         // load last state from storage
-        let state = FullAccessState::open(
+        let mut state = FullAccessState::open(
             db_path,
             db_cache,
             chain_id.clone(),
@@ -523,6 +676,10 @@ where
             config.shell.storage_read_past_height_limit,
             is_key_diff_storable,
         );
+        state.in_mem_mut().pruning_mode = config.shell.pruning_mode;
+        state.in_mem_mut().rollback_window = config.shell.rollback_window;
+        state.in_mem_mut().vp_evaluation = config.shell.vp_evaluation;
+        state.in_mem_mut().tx_batch_execution = config.shell.tx_batch_execution;
         let vp_wasm_cache_dir =
             base_dir.join(chain_id.as_str()).join("vp_wasm_cache");
         let tx_wasm_cache_dir =
@@ -569,19 +726,40 @@ where
                             None
                         };
 
-                    wallet
-                        .take_validator_data()
-                        .map(|data| ShellMode::Validator {
-                            data,
-                            broadcast_sender,
-                            eth_oracle,
-                            validator_local_config,
-                            local_config,
-                        })
-                        .expect(
-                            "Validator data should have been stored in the \
-                             wallet",
-                        )
+                    let data = wallet.take_validator_data().expect(
+                        "Validator data should have been stored in the wallet",
+                    );
+                    let hsm_keys = validator_local_config
+                        .as_ref()
+                        .and_then(|config| config.hsm.as_ref())
+                        .map(|hsm| load_hsm_keys(hsm, &data.keys));
+                    match &hsm_keys {
+                        Some(ValidatorHsmKeys {
+                            consensus_key: Some(consensus_key),
+                            ..
+                        }) => spawn_hsm_consensus_signer(
+                            consensus_key,
+                            &chain_id,
+                            &cometbft_dir,
+                            config.cometbft.priv_validator_laddr.as_ref(),
+                        ),
+                        Some(_) => {}
+                        None => assert!(
+                            data.keys.protocol_keypair.is_some()
+                                && data.keys.eth_bridge_keypair.is_some(),
+                            "The validator keys should have been stored in \
+                             the wallet, unless they are held in an HSM"
+                        ),
+                    }
+
+                    ShellMode::Validator {
+                        data,
+                        broadcast_sender,
+                        eth_oracle,
+                        validator_local_config,
+                        local_config,
+                        hsm_keys,
+                    }
                 }
                 #[cfg(any(test, fuzzing))]
                 {
@@ -593,12 +771,14 @@ where
                             keys: ValidatorKeys {
                                 protocol_keypair,
                                 eth_bridge_keypair,
-                            },
+                            }
+                            .into(),
                         },
                         broadcast_sender,
                         eth_oracle,
                         validator_local_config: None,
                         local_config: None,
+                        hsm_keys: None,
                     }
                 }
             }
@@ -830,6 +1010,33 @@ where
         }
     }
 
+    /// Prune a batch of the block history that is no longer kept according
+    /// to the pruning mode. Returns `true` if more of the block history is
+    /// left to prune.
+    pub fn prune_block_history_batch(&mut self) -> bool {
+        let (from, to) = match self.state.block_history_to_prune() {
+            Ok(Some(range)) => range,
+            Ok(None) => return false,
+            Err(err) => {
+                tracing::error!(
+                    "Failed to find the block history to prune: {err}"
+                );
+                return false;
+            }
+        };
+        let batch_end =
+            from.0.saturating_add(BLOCK_HISTORY_PRUNING_BATCH_SIZE - 1);
+        let batch_to = BlockHeight(to.0.min(batch_end));
+        tracing::debug!(
+            "Pruning the block history from height {from} to {batch_to}"
+        );
+        if let Err(err) = self.state.prune_block_history(from, batch_to) {
+            tracing::error!("Failed to prune the block history: {err}");
+            return false;
+        }
+        batch_to < to
+    }
+
     /// Updates the Ethereum oracle's last processed block.
     #[inline]
     fn bump_last_processed_eth_block(&mut self) {
@@ -873,13 +1080,17 @@ where
 
         let protocol_key = self
             .mode
-            .get_protocol_key()
+            .get_protocol_signer()
             .expect("Validators should have protocol keys");
 
-        let protocol_txs = iter_protocol_txs(ext).map(|protocol_tx| {
+        let protocol_txs = iter_protocol_txs(ext).filter_map(|protocol_tx| {
             protocol_tx
-                .sign(protocol_key, self.chain_id.clone())
-                .to_bytes()
+                .try_sign(&protocol_key, self.chain_id.clone())
+                .inspect_err(|err| {
+                    tracing::error!("Failed to sign a protocol tx: {err}")
+                })
+                .ok()
+                .map(|tx| tx.to_bytes())
         });
 
         for tx in protocol_txs {
@@ -910,16 +1121,19 @@ where
         if let Some(vote_extension) = self.sign_ethereum_events(eth_events) {
             let protocol_key = self
                 .mode
-                .get_protocol_key()
+                .get_protocol_signer()
                 .expect("Validators should have protocol keys");
 
-            let signed_tx = EthereumTxData::EthEventsVext(
+            match EthereumTxData::EthEventsVext(
                 namada_vote_ext::ethereum_events::SignedVext(vote_extension),
             )
-            .sign(protocol_key, self.chain_id.clone())
-            .to_bytes();
-
-            self.mode.broadcast(signed_tx);
+            .try_sign(&protocol_key, self.chain_id.clone())
+            {
+                Ok(signed_tx) => self.mode.broadcast(signed_tx.to_bytes()),
+                Err(err) => tracing::error!(
+                    "Failed to sign the expired Ethereum events: {err}"
+                ),
+            }
         }
     }
 
@@ -2054,7 +2268,6 @@ pub mod test_utils {
 #[cfg(test)]
 mod shell_tests {
     use std::collections::BTreeMap;
-    use std::fs::File;
 
     use eth_bridge::storage::eth_bridge_queries::is_bridge_comptime_enabled;
     use namada_apps_lib::state::StorageWrite;
@@ -2961,7 +3174,13 @@ mod shell_tests {
         shell.state.commit_block().expect("Test failed");
         shell.state.db_mut().flush(true).expect("Test failed");
         let original_root = shell.state.in_mem().merkle_root();
-        let snapshot = make_snapshot(config.db_dir(), base_dir);
+        let restore = make_snapshot(
+            config.db_dir(),
+            base_dir,
+            shell.chain_id.clone(),
+            shell.state.in_mem().native_token.clone(),
+            shell.state.in_mem().merkle_root().into(),
+        );
         shell
             .state
             .write(
@@ -2973,21 +3192,31 @@ mod shell_tests {
         let new_root = shell.state.in_mem().merkle_root();
         assert_ne!(new_root, original_root);
 
-        shell.restore_database_from_state_sync();
+        shell
+            .restore_database_from_state_sync()
+            .expect("Test failed");
         assert_eq!(shell.state.in_mem().merkle_root(), new_root,);
         shell.syncing = Some(SnapshotSync {
             next_chunk: 0,
             height: BlockHeight::first(),
             expected: vec![],
             strikes: 0,
-            snapshot,
+            restore,
         });
-        shell.restore_database_from_state_sync();
+        shell
+            .restore_database_from_state_sync()
+            .expect("Test failed");
         assert_eq!(shell.state.in_mem().merkle_root(), original_root,);
     }
 
     /// Helper function for the `test_restore_database_from_snapshot` test
-    fn make_snapshot(db_dir: PathBuf, base_dir: PathBuf) -> File {
+    fn make_snapshot(
+        db_dir: PathBuf,
+        base_dir: PathBuf,
+        chain_id: ChainId,
+        native_token: Address,
+        app_hash: Hash,
+    ) -> SnapshotRestore<Sha256Hasher> {
         let snapshot =
             DbSnapshot(SnapshotPath(base_dir.clone(), BlockHeight::first()));
         std::fs::create_dir_all(base_dir.join("snapshots"))
@@ -3004,7 +3233,26 @@ mod shell_tests {
                 .join(entry.file_name().to_string_lossy().to_string());
             std::fs::copy(entry.path(), dest_file).expect("Test failed");
         }
-        snapshot.clone().build_tarball().expect("Test failed");
-        File::open(snapshot.0.temp_tarball("zst")).expect("Test failed")
+        snapshot
+            .package(chain_id, native_token)
+            .expect("Test failed");
+
+        let mut restore = SnapshotRestore::new(
+            SnapshotPath::restore_rocksdb(base_dir.clone()),
+            BlockHeight::first(),
+            app_hash,
+        )
+        .expect("Test failed");
+        let meta = DbSnapshot::load_snapshot_metadata(&base_dir, [1])
+            .next()
+            .expect("Test failed")
+            .expect("Test failed");
+        for chunk in 0..meta.chunk_hashes.len() as u64 {
+            let chunk =
+                DbSnapshot::load_chunk(BlockHeight::first(), chunk, &base_dir)
+                    .expect("Test failed");
+            restore.apply_chunk(&chunk).expect("Test failed");
+        }
+        restore
     }
 }
@@ -1077,6 +1077,7 @@ mod test_prepare_proposal {
                 accepted_gas_tokens: namada_sdk::collections::HashMap::from([
                     (namada_sdk::address::testing::nam(), Amount::from(1)),
                 ]),
+                hsm: None,
             });
         }
 
@@ -1235,6 +1236,7 @@ mod test_prepare_proposal {
                 accepted_gas_tokens: namada_sdk::collections::HashMap::from([
                     (namada_sdk::address::testing::nam(), Amount::from(100)),
                 ]),
+                hsm: None,
             });
         }
 
@@ -1500,6 +1502,7 @@ mod test_prepare_proposal {
                 );
                 m
             },
+            hsm: None,
         };
         let computed_min_gas_price = compute_min_gas_price(
             &shell.state.in_mem().native_token,
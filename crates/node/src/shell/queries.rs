@@ -16,7 +16,9 @@ where
     /// INVARIANT: This method must be stateless.
     pub fn query(&self, query: request::Query) -> response::Query {
         // Invoke the root RPC handler - returns borsh-encoded data on success
-        let result = if query.path == RPC.shell().dry_run_tx_path() {
+        let result = if query.path == RPC.shell().dry_run_tx_path()
+            || query.path == RPC.shell().dry_run_tx_trace_path()
+        {
             dry_run_tx(
                 // This is safe as neither the inner `db` nor `in_mem` are
                 // actually mutable, only the `write_log` which is owned by
@@ -36,6 +38,8 @@ where
                 tx_wasm_cache: self.tx_wasm_cache.read_only(),
                 storage_read_past_height_limit: self
                     .storage_read_past_height_limit,
+                height: BlockHeight::default(),
+                read_log: None,
             };
             namada_sdk::queries::handle_path(ctx, &query)
         };
@@ -1,5 +1,3 @@
-use std::io::Write;
-
 use namada_sdk::arith::checked;
 use namada_sdk::borsh::{BorshDeserialize, BorshSerializeExt};
 use namada_sdk::hash::{Hash, Sha256Hasher};
@@ -8,7 +6,8 @@ use namada_sdk::state::{BlockHeight, StorageRead};
 use super::SnapshotSync;
 use crate::shell::Shell;
 use crate::storage;
-use crate::storage::{DbSnapshot, DbSnapshotMeta};
+use crate::storage::state_sync::SnapshotRestore;
+use crate::storage::{DbSnapshot, DbSnapshotMeta, SnapshotPath};
 use crate::tendermint::abci::types::Snapshot;
 use crate::tendermint::abci::{
     request as tm_request, response as tm_response, ApplySnapshotChunkResult,
@@ -115,21 +114,7 @@ impl Shell<storage::PersistentDB, Sha256Hasher> {
                 if self.state.get_block_height().unwrap_or_default().0
                     < u64::from(req.snapshot.height)
                 {
-                    let Ok(chunks) =
-                        Vec::<Hash>::try_from_slice(&req.snapshot.metadata)
-                    else {
-                        return tm_response::OfferSnapshot::Reject;
-                    };
-                    self.syncing = Some(SnapshotSync {
-                        next_chunk: 0,
-                        height: u64::from(req.snapshot.height).into(),
-                        expected: chunks,
-                        strikes: 0,
-                        snapshot: tempfile::tempfile()
-                            .expect("Failed to create snapshot temp file"),
-                    });
-                    tracing::info!("Accepting snapshot offer");
-                    tm_response::OfferSnapshot::Accept
+                    self.start_snapshot_sync(req)
                 } else {
                     tracing::info!("Rejecting snapshot offer");
                     tm_response::OfferSnapshot::Reject
@@ -137,22 +122,7 @@ impl Shell<storage::PersistentDB, Sha256Hasher> {
             }
             Some(snapshot_sync) => {
                 if snapshot_sync.height.0 < u64::from(req.snapshot.height) {
-                    let Ok(chunks) =
-                        Vec::<Hash>::try_from_slice(&req.snapshot.metadata)
-                    else {
-                        tracing::info!("Rejecting snapshot offer");
-                        return tm_response::OfferSnapshot::Reject;
-                    };
-                    self.syncing = Some(SnapshotSync {
-                        next_chunk: 0,
-                        height: u64::from(req.snapshot.height).into(),
-                        expected: chunks,
-                        strikes: 0,
-                        snapshot: tempfile::tempfile()
-                            .expect("Failed to create snapshot temp file"),
-                    });
-                    tracing::info!("Accepting snapshot offer");
-                    tm_response::OfferSnapshot::Accept
+                    self.start_snapshot_sync(req)
                 } else {
                     tracing::info!("Rejecting snapshot offer");
                     tm_response::OfferSnapshot::Reject
@@ -161,6 +131,53 @@ impl Shell<storage::PersistentDB, Sha256Hasher> {
         }
     }
 
+    /// Start restoring an accepted snapshot into a new DB. The chunks will be
+    /// verified against the app hash of the snapshot's height, which
+    /// CometBFT has checked with its light client.
+    fn start_snapshot_sync(
+        &mut self,
+        req: tm_request::OfferSnapshot,
+    ) -> tm_response::OfferSnapshot {
+        let Ok(chunks) = Vec::<Hash>::try_from_slice(&req.snapshot.metadata)
+        else {
+            tracing::info!("Rejecting snapshot offer");
+            return tm_response::OfferSnapshot::Reject;
+        };
+        let Ok(app_hash) =
+            Hash::try_from(Vec::<u8>::from(req.app_hash).as_slice())
+        else {
+            tracing::info!("Rejecting snapshot offer with an invalid app hash");
+            return tm_response::OfferSnapshot::Reject;
+        };
+        let height = BlockHeight(u64::from(req.snapshot.height));
+        // drop the restoration of a previous snapshot, if any, before
+        // reusing its DB path
+        self.syncing = None;
+        let restore = match SnapshotRestore::new(
+            SnapshotPath::restore_rocksdb(self.base_dir.clone()),
+            height,
+            app_hash,
+        ) {
+            Ok(restore) => restore,
+            Err(err) => {
+                tracing::error!(
+                    error = %err,
+                    "Failed to create the DB to restore the snapshot into"
+                );
+                return tm_response::OfferSnapshot::Abort;
+            }
+        };
+        self.syncing = Some(SnapshotSync {
+            next_chunk: 0,
+            height,
+            expected: chunks,
+            strikes: 0,
+            restore,
+        });
+        tracing::info!("Accepting snapshot offer");
+        tm_response::OfferSnapshot::Accept
+    }
+
     /// Write a snapshot chunk to the database
     pub fn apply_snapshot_chunk(
         &mut self,
@@ -245,21 +262,51 @@ impl Shell<storage::PersistentDB, Sha256Hasher> {
             snapshot_sync.strikes = 0;
         };
 
-        // write snapshot chunk
-        snapshot_sync
-            .snapshot
-            .write_all(&req.chunk)
-            .expect("Failed to save snapshot chunk");
+        // verify the chunk against the app hash and write its content
+        if let Err(err) = snapshot_sync.restore.apply_chunk(&req.chunk) {
+            self.syncing = None;
+            return if err.is_invalid_snapshot() {
+                tracing::error!(
+                    error = %err,
+                    "Received an invalid snapshot chunk; rejecting snapshot"
+                );
+                tm_response::ApplySnapshotChunk {
+                    result: ApplySnapshotChunkResult::RejectSnapshot,
+                    refetch_chunks: vec![],
+                    reject_senders: vec![req.sender],
+                }
+            } else {
+                tracing::error!(
+                    error = %err,
+                    "Failed to write a snapshot chunk"
+                );
+                tm_response::ApplySnapshotChunk {
+                    result: ApplySnapshotChunkResult::Abort,
+                    refetch_chunks: vec![],
+                    reject_senders: vec![],
+                }
+            };
+        }
 
         // increment the chunk counter
         snapshot_sync.next_chunk =
             checked!(snapshot_sync.next_chunk + 1).unwrap();
 
-        // check if all chunks have been saved, and restore the
-        // database from the fetched tar archive
+        // check if all chunks have been applied, and replace the
+        // database with the restored one
         if snapshot_sync.next_chunk == snapshot_sync.expected.len() as u64 {
-            self.restore_database_from_state_sync();
-            self.syncing = None;
+            if let Err(err) = self.restore_database_from_state_sync() {
+                tracing::error!(
+                    error = %err,
+                    "Failed to restore the state from the snapshot; \
+                     rejecting snapshot"
+                );
+                return tm_response::ApplySnapshotChunk {
+                    result: ApplySnapshotChunkResult::RejectSnapshot,
+                    refetch_chunks: vec![],
+                    reject_senders: vec![],
+                };
+            }
             tracing::info!("Snapshot completely applied");
         }
 
@@ -17,6 +17,8 @@ pub struct InternalStats {
     tx_cache_size: (usize, usize),
     tx_executed: HashMap<String, u64>,
     wrapper_txs: u64,
+    // Batches re-executed because of a conflict with a previous batch
+    reexecuted_batches: u64,
 }
 
 impl InternalStats {
@@ -77,6 +79,10 @@ impl InternalStats {
         self.wrapper_txs += 1;
     }
 
+    pub fn increment_reexecuted_batches(&mut self) {
+        self.reexecuted_batches += 1;
+    }
+
     /// Merges two intances of [`InternalStats`]. The caches stats are left
     /// untouched.
     pub fn merge(&mut self, other: Self) {
@@ -93,6 +99,7 @@ impl InternalStats {
                 .or_insert(cnt);
         }
         self.wrapper_txs += other.wrapper_txs;
+        self.reexecuted_batches += other.reexecuted_batches;
     }
 }
 
@@ -102,8 +109,8 @@ impl Display for InternalStats {
             f,
             "Applied {} transactions. Wrappers: {}, successful inner txs: {}, \
              rejected inner txs: {}, errored inner txs: {}, unrun txs: {}, \
-             valid txs discarded by failing atomic batch: {}, vp cache size: \
-             {} - {}, tx cache size {} - {}",
+             valid txs discarded by failing atomic batch: {}, re-executed \
+             batches: {}, vp cache size: {} - {}, tx cache size {} - {}",
             self.successful_tx + self.rejected_txs + self.errored_txs,
             self.wrapper_txs,
             self.successful_tx,
@@ -111,6 +118,7 @@ impl Display for InternalStats {
             self.errored_txs,
             self.unrun_txs,
             self.successful_tx_in_failed_batch,
+            self.reexecuted_batches,
             self.vp_cache_size.0,
             self.vp_cache_size.1,
             self.tx_cache_size.0,
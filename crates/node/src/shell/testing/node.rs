@@ -815,7 +815,9 @@ impl Client for MockNode {
             prove,
         };
         let borrowed = self.shell.lock().unwrap();
-        if request.path == RPC.shell().dry_run_tx_path() {
+        if request.path == RPC.shell().dry_run_tx_path()
+            || request.path == RPC.shell().dry_run_tx_trace_path()
+        {
             dry_run_tx(
                 // This is safe because nothing else is using `self.state`
                 // concurrently and the `TempWlState` will be dropped right
@@ -834,6 +836,8 @@ impl Client for MockNode {
                 vp_wasm_cache: borrowed.vp_wasm_cache.read_only(),
                 tx_wasm_cache: borrowed.tx_wasm_cache.read_only(),
                 storage_read_past_height_limit: None,
+                height: BlockHeight::default(),
+                read_log: None,
             };
             rpc.handle(ctx, &request)
         }
@@ -58,14 +58,12 @@ where
             .mode
             .get_validator_address()
             .expect(VALIDATOR_EXPECT_MSG);
-        let protocol_key = match &self.mode {
-            ShellMode::Validator { data, .. } => &data.keys.protocol_keypair,
-            _ => unreachable!("{VALIDATOR_EXPECT_MSG}"),
-        };
+        let protocol_key =
+            self.mode.get_protocol_signer().expect(VALIDATOR_EXPECT_MSG);
         sign_ethereum_events(
             &self.state,
             validator_addr,
-            protocol_key,
+            &protocol_key,
             ethereum_events,
         )
         .map(|ethereum_events::SignedVext(ext)| ext)
@@ -81,17 +79,15 @@ where
             .expect(VALIDATOR_EXPECT_MSG);
         let eth_hot_key = self
             .mode
-            .get_eth_bridge_keypair()
+            .get_eth_bridge_signer()
             .expect(VALIDATOR_EXPECT_MSG);
-        let protocol_key = match &self.mode {
-            ShellMode::Validator { data, .. } => &data.keys.protocol_keypair,
-            _ => unreachable!("{VALIDATOR_EXPECT_MSG}"),
-        };
+        let protocol_key =
+            self.mode.get_protocol_signer().expect(VALIDATOR_EXPECT_MSG);
         sign_bridge_pool_root(
             &self.state,
             validator_addr,
-            eth_hot_key,
-            protocol_key,
+            &eth_hot_key,
+            &protocol_key,
         )
         .map(|bridge_pool_roots::SignedVext(ext)| ext)
     }
@@ -107,12 +103,12 @@ where
             .expect(VALIDATOR_EXPECT_MSG);
         let eth_hot_key = self
             .mode
-            .get_eth_bridge_keypair()
-            .expect("{VALIDATOR_EXPECT_MSG}");
+            .get_eth_bridge_signer()
+            .expect(VALIDATOR_EXPECT_MSG);
         sign_validator_set_update::<_, _, governance::Store<_>>(
             &self.state,
             validator_addr,
-            eth_hot_key,
+            &eth_hot_key,
         )
     }
 
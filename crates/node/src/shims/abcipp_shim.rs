@@ -1,6 +1,8 @@
 use std::future::Future;
 use std::path::PathBuf;
 use std::pin::Pin;
+use std::sync::mpsc::TryRecvError;
+use std::sync::Arc;
 use std::task::{Context, Poll};
 
 use futures::future::FutureExt;
@@ -22,9 +24,11 @@ use super::abcipp_shim_types::shim::request::{
 use super::abcipp_shim_types::shim::{
     Error, Request, Response, TakeSnapshot, TxBytes,
 };
+use crate::change_feed::{self, BlockDiffs};
 use crate::config;
-use crate::config::{Action, ActionAtHeight};
+use crate::config::{Action, ActionAtHeight, DbBackend};
 use crate::shell::{EthereumOracleChannels, Shell};
+use crate::storage::state_diffs::read_block_diffs;
 use crate::storage::DbSnapshot;
 use crate::tendermint::abci::{request, Request as Req, Response as Resp};
 use crate::tower_abci::BoxError;
@@ -43,6 +47,9 @@ pub struct AbcippShim {
     )>,
     snapshot_task: Option<std::thread::JoinHandle<Result<(), DbError>>>,
     snapshots_to_keep: u64,
+    change_feed: Option<change_feed::Sender>,
+    /// Whether some of the block history may be left to prune
+    block_history_to_prune: bool,
 }
 
 impl AbcippShim {
@@ -58,6 +65,7 @@ impl AbcippShim {
         scheduled_migration: Option<ScheduledMigration>,
         vp_wasm_compilation_cache: u64,
         tx_wasm_compilation_cache: u64,
+        change_feed: Option<change_feed::Sender>,
     ) -> (Self, AbciService, broadcast::Sender<()>) {
         // We can use an unbounded channel here, because tower-abci limits the
         // the number of requests that can come in
@@ -84,6 +92,8 @@ impl AbcippShim {
                 shell_recv,
                 snapshot_task: None,
                 snapshots_to_keep,
+                change_feed,
+                block_history_to_prune: true,
             },
             AbciService {
                 shell_send,
@@ -105,7 +115,7 @@ impl AbcippShim {
     /// Run the shell's blocking loop that receives messages from the
     /// [`AbciService`].
     pub fn run(mut self) {
-        while let Ok((req, resp_sender)) = self.shell_recv.recv() {
+        while let Some((req, resp_sender)) = self.recv_request() {
             let resp = match req {
                 Req::ProcessProposal(proposal) => self
                     .service
@@ -165,6 +175,8 @@ impl AbcippShim {
                 Req::Commit => match self.service.call(Request::Commit) {
                     Ok(Response::Commit(res, take_snapshot)) => {
                         self.update_snapshot_task(take_snapshot);
+                        self.publish_state_diffs();
+                        self.block_history_to_prune = true;
                         Ok(Resp::Commit(res))
                     }
                     Ok(resp) => Err(Error::ConvertResp(resp)),
@@ -188,6 +200,27 @@ impl AbcippShim {
         }
     }
 
+    /// Receive the next request for the shell. The block history that is no
+    /// longer kept is pruned in the background while no request is pending,
+    /// in small batches, so that neither the commits nor the other requests
+    /// wait on it.
+    fn recv_request(
+        &mut self,
+    ) -> Option<(Req, tokio::sync::oneshot::Sender<Result<Resp, BoxError>>)>
+    {
+        while self.block_history_to_prune {
+            match self.shell_recv.try_recv() {
+                Ok(request) => return Some(request),
+                Err(TryRecvError::Empty) => {
+                    self.block_history_to_prune =
+                        self.service.prune_block_history_batch();
+                }
+                Err(TryRecvError::Disconnected) => return None,
+            }
+        }
+        self.shell_recv.recv().ok()
+    }
+
     fn update_snapshot_task(&mut self, take_snapshot: TakeSnapshot) {
         let snapshot_taken =
             self.snapshot_task.as_ref().map(|t| t.is_finished());
@@ -223,7 +256,17 @@ impl AbcippShim {
         let TakeSnapshot::Yes(db_path, height) = take_snapshot else {
             return;
         };
+        // The snapshots are RocksDB checkpoints
+        if self.service.state.db().backend() != DbBackend::RocksDb {
+            tracing::warn!(
+                "Skipping the snapshot at height {height}, snapshots are only \
+                 supported with the RocksDB storage backend"
+            );
+            return;
+        }
         let base_dir = self.service.base_dir.clone();
+        let chain_id = self.service.chain_id.clone();
+        let native_token = self.service.state.in_mem().native_token.clone();
 
         let (snap_send, snap_recv) = tokio::sync::oneshot::channel();
 
@@ -237,7 +280,7 @@ impl AbcippShim {
             DbSnapshot::cleanup(height, &base_dir, snapshots_to_keep)
                 .map_err(|e| DbError::DBError(e.to_string()))?;
             snapshot
-                .package()
+                .package(chain_id, native_token)
                 .map_err(|e| DbError::DBError(e.to_string()))
         });
 
@@ -252,6 +295,28 @@ impl AbcippShim {
         }
     }
 
+    /// Publish the state diffs of the last committed block to the change feed
+    /// subscribers, if any
+    fn publish_state_diffs(&self) {
+        let Some(sender) = self.change_feed.as_ref() else {
+            return;
+        };
+        if sender.receiver_count() == 0 {
+            return;
+        }
+        let height = self.service.state.in_mem().get_last_block_height();
+        match read_block_diffs(self.service.state.db(), height) {
+            Ok(diffs) => {
+                // Sending only fails if all the subscribers have disconnected
+                let _ = sender.send(Arc::new(BlockDiffs { height, diffs }));
+            }
+            Err(err) => tracing::error!(
+                %height,
+                "Failed to read the state diffs for the change feed: {err}"
+            ),
+        }
+    }
+
     // Retrieve the cached result of process proposal for the given block or
     // compute it if missing
     fn get_process_proposal_result(
@@ -0,0 +1,786 @@
+//! The persistent DB of the node, dispatching to one of the storage backends.
+//!
+//! The backend of an existing DB is detected from the files in its directory,
+//! so that the tools that open the DB don't need to be told which one it is.
+
+use std::path::Path;
+
+use itertools::Either;
+use namada_sdk::ethereum_events;
+use namada_sdk::hash::Hash;
+use namada_sdk::migrations::{DBUpdateVisitor, DbUpdateType};
+use namada_sdk::state::{
+    BlockStateRead, BlockStateWrite, DBIter, DBWriteBatch, DbError as Error,
+    DbResult as Result, MerkleTreeStoresRead, StoreType, DB,
+};
+use namada_sdk::storage::{BlockHeader, BlockHeight, DbColFam, Epoch, Key};
+use regex::Regex;
+
+use super::redb::{self, RedbDB, RedbUpdateVisitor, RedbWriteBatch};
+use super::rocksdb::{self, RocksDB, RocksDBUpdateVisitor, RocksDBWriteBatch};
+use super::rollback::Rollback;
+use crate::config::DbBackend;
+
+/// The number of entries copied in a single batch by [`copy_db`]
+const COPY_BATCH_SIZE: usize = 10_000;
+
+/// All the column families of the DB
+pub const COLUMN_FAMILIES: [DbColFam; 6] = [
+    DbColFam::SUBSPACE,
+    DbColFam::BLOCK,
+    DbColFam::STATE,
+    DbColFam::DIFFS,
+    DbColFam::ROLLBACK,
+    DbColFam::REPLAYPROT,
+];
+
+/// A DB handle of either backend
+#[derive(Debug)]
+pub enum PersistentDB {
+    RocksDB(RocksDB),
+    Redb(RedbDB),
+}
+
+/// DB Handle for batch writes. As a batch is created without a DB, it can
+/// hold the writes for either backend, but only the one of the DB that
+/// executes it gets written.
+#[derive(Default)]
+pub struct PersistentWriteBatch {
+    rocksdb: RocksDBWriteBatch,
+    redb: RedbWriteBatch,
+}
+
+impl DBWriteBatch for PersistentWriteBatch {}
+
+/// Find the backend of the DB in the given directory, if any
+pub fn detect_backend(db_path: impl AsRef<Path>) -> Option<DbBackend> {
+    let db_path = db_path.as_ref();
+    if redb::exists(db_path) {
+        Some(DbBackend::Redb)
+    } else if db_path.join("CURRENT").is_file() {
+        Some(DbBackend::RocksDb)
+    } else {
+        None
+    }
+}
+
+/// Check that the DB in the given directory uses the configured backend. If
+/// there is no DB yet, an empty one is created with the configured backend.
+pub fn prepare_db(db_path: impl AsRef<Path>, backend: DbBackend) -> Result<()> {
+    let db_path = db_path.as_ref();
+    match detect_backend(db_path) {
+        Some(found) if found != backend => Err(Error::DBError(format!(
+            "The DB in {} uses the {found} backend, but the {backend} backend \
+             is configured. Convert it with the `ledger migrate-db` command.",
+            db_path.to_string_lossy()
+        ))),
+        Some(_) => Ok(()),
+        None => match backend {
+            // A new RocksDB is created when the DB is opened
+            DbBackend::RocksDb => Ok(()),
+            DbBackend::Redb => redb::open(db_path, false).map(|_| ()),
+        },
+    }
+}
+
+/// Open an existing DB in read-only mode. Note that only a RocksDB can be
+/// opened while the node is running.
+pub fn open_read_only(db_path: impl AsRef<Path>) -> Result<PersistentDB> {
+    let db_path = db_path.as_ref();
+    match detect_backend(db_path) {
+        Some(DbBackend::RocksDb) => {
+            rocksdb::open(db_path, true, None).map(PersistentDB::RocksDB)
+        }
+        Some(DbBackend::Redb) => {
+            redb::open(db_path, true).map(PersistentDB::Redb)
+        }
+        None => Err(Error::DBError(format!(
+            "No DB found in {}",
+            db_path.to_string_lossy()
+        ))),
+    }
+}
+
+/// Open a DB with the given backend, creating it if it doesn't exist yet
+pub fn open_with_backend(
+    db_path: impl AsRef<Path>,
+    backend: DbBackend,
+) -> Result<PersistentDB> {
+    match backend {
+        DbBackend::RocksDb => {
+            rocksdb::open(db_path, false, None).map(PersistentDB::RocksDB)
+        }
+        DbBackend::Redb => redb::open(db_path, false).map(PersistentDB::Redb),
+    }
+}
+
+/// Copy all the entries of every column family from one DB to another.
+/// Returns the number of copied entries.
+pub fn copy_db(from: &PersistentDB, to: &PersistentDB) -> Result<u64> {
+    let mut copied = 0_u64;
+    for cf in COLUMN_FAMILIES {
+        let mut batch = PersistentDB::batch();
+        let mut batch_len = 0_usize;
+        for (key, value, _gas) in from.iter_column_family(&cf, None) {
+            to.add_raw_to_batch(&mut batch, &cf, &key, &value)?;
+            copied = copied.saturating_add(1);
+            batch_len = batch_len.saturating_add(1);
+            if batch_len == COPY_BATCH_SIZE {
+                to.exec_batch(std::mem::take(&mut batch))?;
+                batch_len = 0;
+            }
+        }
+        to.exec_batch(batch)?;
+    }
+    to.flush(true)?;
+    Ok(copied)
+}
+
+impl PersistentDB {
+    /// The storage backend of the DB
+    pub fn backend(&self) -> DbBackend {
+        match self {
+            PersistentDB::RocksDB(_) => DbBackend::RocksDb,
+            PersistentDB::Redb(_) => DbBackend::Redb,
+        }
+    }
+
+    /// Iterate over the entries of a column family, with their full keys,
+    /// optionally only the ones under a key prefix
+    pub fn iter_column_family(
+        &self,
+        cf: &DbColFam,
+        prefix: Option<&Key>,
+    ) -> impl Iterator<Item = (String, Vec<u8>, namada_sdk::gas::Gas)> + '_
+    {
+        match self {
+            PersistentDB::RocksDB(db) => {
+                Either::Left(db.iter_column_family(cf, prefix))
+            }
+            PersistentDB::Redb(db) => {
+                Either::Right(db.iter_column_family(cf, prefix))
+            }
+        }
+    }
+
+    /// Write an entry of a column family with its full key in a batch
+    pub fn add_raw_to_batch(
+        &self,
+        batch: &mut PersistentWriteBatch,
+        cf: &DbColFam,
+        key: &str,
+        value: &[u8],
+    ) -> Result<()> {
+        match self {
+            PersistentDB::RocksDB(db) => {
+                db.add_raw_to_batch(&mut batch.rocksdb, cf, key, value)
+            }
+            PersistentDB::Redb(db) => {
+                db.add_raw_to_batch(&mut batch.redb, cf, key, value);
+                Ok(())
+            }
+        }
+    }
+
+    /// Delete an entry of a column family with its full key in a batch
+    pub fn delete_raw_from_batch(
+        &self,
+        batch: &mut PersistentWriteBatch,
+        cf: &DbColFam,
+        key: &str,
+    ) -> Result<()> {
+        match self {
+            PersistentDB::RocksDB(db) => {
+                db.delete_raw_from_batch(&mut batch.rocksdb, cf, key)
+            }
+            PersistentDB::Redb(db) => {
+                db.delete_raw_from_batch(&mut batch.redb, cf, key);
+                Ok(())
+            }
+        }
+    }
+
+    /// Read an entry of a column family with its full key
+    pub fn read_column_family(
+        &self,
+        cf: &DbColFam,
+        key: &str,
+    ) -> Result<Option<Vec<u8>>> {
+        match self {
+            PersistentDB::RocksDB(db) => db.read_column_family(cf, key),
+            PersistentDB::Redb(db) => db.read_column_family(cf, key),
+        }
+    }
+
+    /// Dump last known block
+    pub fn dump_block(
+        &self,
+        out_file_path: std::path::PathBuf,
+        historic: bool,
+        height: Option<BlockHeight>,
+    ) {
+        match self {
+            PersistentDB::RocksDB(db) => {
+                db.dump_block(out_file_path, historic, height)
+            }
+            PersistentDB::Redb(db) => {
+                db.dump_block(out_file_path, historic, height)
+            }
+        }
+    }
+
+    /// Roll the DB back to the given height, which must be in the rollback
+    /// window
+    pub fn rollback(&self, height: BlockHeight) -> Result<Rollback> {
+        let rollback = Rollback::plan(self, height)?;
+        rollback.apply(self)?;
+        Ok(rollback)
+    }
+
+    /// Read diffs of non-persisted key-vals that are only kept for rollback of
+    /// the block heights in the rollback window.
+    #[cfg(test)]
+    pub fn read_rollback_val(
+        &self,
+        key: &Key,
+        height: BlockHeight,
+        is_old: bool,
+    ) -> Result<Option<Vec<u8>>> {
+        match self {
+            PersistentDB::RocksDB(db) => {
+                db.read_rollback_val(key, height, is_old)
+            }
+            PersistentDB::Redb(db) => db.read_rollback_val(key, height, is_old),
+        }
+    }
+
+    /// Read an entry of a column family with its full key
+    #[cfg(test)]
+    pub fn read_raw(&self, cf: &str, key: &str) -> Option<Vec<u8>> {
+        match self {
+            PersistentDB::RocksDB(db) => db.read_raw(cf, key),
+            PersistentDB::Redb(db) => db.read_raw(cf, key),
+        }
+    }
+}
+
+impl DB for PersistentDB {
+    type Cache = ::rocksdb::Cache;
+    type Migrator = DbUpdateType;
+    type RestoreSource<'a> = (&'a ::rocksdb::Cache, &'a Path);
+    type WriteBatch = PersistentWriteBatch;
+
+    fn open(
+        db_path: impl AsRef<std::path::Path>,
+        cache: Option<&Self::Cache>,
+    ) -> Self {
+        if redb::exists(&db_path) {
+            PersistentDB::Redb(RedbDB::open(db_path, None))
+        } else {
+            PersistentDB::RocksDB(RocksDB::open(db_path, cache))
+        }
+    }
+
+    /// The state sync restores a RocksDB, which is converted to redb first if
+    /// needed
+    fn restore_from(
+        &mut self,
+        (cache, restored_db): Self::RestoreSource<'_>,
+    ) -> Result<()> {
+        match self {
+            PersistentDB::RocksDB(db) => db.restore_from((cache, restored_db)),
+            PersistentDB::Redb(db) => {
+                let converted_db = restored_db.with_extension("redb");
+                {
+                    let from = PersistentDB::RocksDB(rocksdb::open(
+                        restored_db,
+                        true,
+                        Some(cache),
+                    )?);
+                    let to = open_with_backend(&converted_db, DbBackend::Redb)?;
+                    copy_db(&from, &to)?;
+                }
+                std::fs::remove_dir_all(restored_db)
+                    .map_err(|e| Error::DBError(e.to_string()))?;
+                db.restore_from(converted_db.as_path())
+            }
+        }
+    }
+
+    fn path(&self) -> Option<&Path> {
+        match self {
+            PersistentDB::RocksDB(db) => db.path(),
+            PersistentDB::Redb(db) => db.path(),
+        }
+    }
+
+    fn flush(&self, wait: bool) -> Result<()> {
+        match self {
+            PersistentDB::RocksDB(db) => db.flush(wait),
+            PersistentDB::Redb(db) => db.flush(wait),
+        }
+    }
+
+    fn read_last_block(&self) -> Result<Option<BlockStateRead>> {
+        match self {
+            PersistentDB::RocksDB(db) => db.read_last_block(),
+            PersistentDB::Redb(db) => db.read_last_block(),
+        }
+    }
+
+    fn add_block_to_batch(
+        &self,
+        state: BlockStateWrite<'_>,
+        batch: &mut Self::WriteBatch,
+        is_full_commit: bool,
+    ) -> Result<()> {
+        match self {
+            PersistentDB::RocksDB(db) => {
+                db.add_block_to_batch(state, &mut batch.rocksdb, is_full_commit)
+            }
+            PersistentDB::Redb(db) => {
+                db.add_block_to_batch(state, &mut batch.redb, is_full_commit)
+            }
+        }
+    }
+
+    fn read_block_header(
+        &self,
+        height: BlockHeight,
+    ) -> Result<Option<BlockHeader>> {
+        match self {
+            PersistentDB::RocksDB(db) => db.read_block_header(height),
+            PersistentDB::Redb(db) => db.read_block_header(height),
+        }
+    }
+
+    fn read_merkle_tree_stores(
+        &self,
+        epoch: Epoch,
+        base_height: BlockHeight,
+        store_type: Option<StoreType>,
+    ) -> Result<Option<MerkleTreeStoresRead>> {
+        match self {
+            PersistentDB::RocksDB(db) => {
+                db.read_merkle_tree_stores(epoch, base_height, store_type)
+            }
+            PersistentDB::Redb(db) => {
+                db.read_merkle_tree_stores(epoch, base_height, store_type)
+            }
+        }
+    }
+
+    fn has_replay_protection_entry(&self, hash: &Hash) -> Result<bool> {
+        match self {
+            PersistentDB::RocksDB(db) => db.has_replay_protection_entry(hash),
+            PersistentDB::Redb(db) => db.has_replay_protection_entry(hash),
+        }
+    }
+
+    fn read_subspace_val(&self, key: &Key) -> Result<Option<Vec<u8>>> {
+        match self {
+            PersistentDB::RocksDB(db) => db.read_subspace_val(key),
+            PersistentDB::Redb(db) => db.read_subspace_val(key),
+        }
+    }
+
+    fn read_subspace_val_with_height(
+        &self,
+        key: &Key,
+        height: BlockHeight,
+        last_height: BlockHeight,
+    ) -> Result<Option<Vec<u8>>> {
+        match self {
+            PersistentDB::RocksDB(db) => {
+                db.read_subspace_val_with_height(key, height, last_height)
+            }
+            PersistentDB::Redb(db) => {
+                db.read_subspace_val_with_height(key, height, last_height)
+            }
+        }
+    }
+
+    fn read_diffs_val(
+        &self,
+        key: &Key,
+        height: BlockHeight,
+        is_old: bool,
+    ) -> Result<Option<Vec<u8>>> {
+        match self {
+            PersistentDB::RocksDB(db) => db.read_diffs_val(key, height, is_old),
+            PersistentDB::Redb(db) => db.read_diffs_val(key, height, is_old),
+        }
+    }
+
+    fn write_subspace_val(
+        &mut self,
+        height: BlockHeight,
+        key: &Key,
+        value: impl AsRef<[u8]>,
+        persist_diffs: bool,
+    ) -> Result<i64> {
+        match self {
+            PersistentDB::RocksDB(db) => {
+                db.write_subspace_val(height, key, value, persist_diffs)
+            }
+            PersistentDB::Redb(db) => {
+                db.write_subspace_val(height, key, value, persist_diffs)
+            }
+        }
+    }
+
+    fn delete_subspace_val(
+        &mut self,
+        height: BlockHeight,
+        key: &Key,
+        persist_diffs: bool,
+    ) -> Result<i64> {
+        match self {
+            PersistentDB::RocksDB(db) => {
+                db.delete_subspace_val(height, key, persist_diffs)
+            }
+            PersistentDB::Redb(db) => {
+                db.delete_subspace_val(height, key, persist_diffs)
+            }
+        }
+    }
+
+    fn batch() -> Self::WriteBatch {
+        PersistentWriteBatch::default()
+    }
+
+    fn exec_batch(&self, batch: Self::WriteBatch) -> Result<()> {
+        match self {
+            PersistentDB::RocksDB(db) => db.exec_batch(batch.rocksdb),
+            PersistentDB::Redb(db) => db.exec_batch(batch.redb),
+        }
+    }
+
+    fn batch_write_subspace_val(
+        &self,
+        batch: &mut Self::WriteBatch,
+        height: BlockHeight,
+        key: &Key,
+        value: impl AsRef<[u8]>,
+        persist_diffs: bool,
+    ) -> Result<i64> {
+        match self {
+            PersistentDB::RocksDB(db) => db.batch_write_subspace_val(
+                &mut batch.rocksdb,
+                height,
+                key,
+                value,
+                persist_diffs,
+            ),
+            PersistentDB::Redb(db) => db.batch_write_subspace_val(
+                &mut batch.redb,
+                height,
+                key,
+                value,
+                persist_diffs,
+            ),
+        }
+    }
+
+    fn batch_delete_subspace_val(
+        &self,
+        batch: &mut Self::WriteBatch,
+        height: BlockHeight,
+        key: &Key,
+        persist_diffs: bool,
+    ) -> Result<i64> {
+        match self {
+            PersistentDB::RocksDB(db) => db.batch_delete_subspace_val(
+                &mut batch.rocksdb,
+                height,
+                key,
+                persist_diffs,
+            ),
+            PersistentDB::Redb(db) => db.batch_delete_subspace_val(
+                &mut batch.redb,
+                height,
+                key,
+                persist_diffs,
+            ),
+        }
+    }
+
+    fn prune_merkle_tree_store(
+        &mut self,
+        batch: &mut Self::WriteBatch,
+        store_type: &StoreType,
+        pruned_target: Either<BlockHeight, Epoch>,
+    ) -> Result<()> {
+        match self {
+            PersistentDB::RocksDB(db) => db.prune_merkle_tree_store(
+                &mut batch.rocksdb,
+                store_type,
+                pruned_target,
+            ),
+            PersistentDB::Redb(db) => db.prune_merkle_tree_store(
+                &mut batch.redb,
+                store_type,
+                pruned_target,
+            ),
+        }
+    }
+
+    fn read_bridge_pool_signed_nonce(
+        &self,
+        height: BlockHeight,
+        last_height: BlockHeight,
+    ) -> Result<Option<ethereum_events::Uint>> {
+        match self {
+            PersistentDB::RocksDB(db) => {
+                db.read_bridge_pool_signed_nonce(height, last_height)
+            }
+            PersistentDB::Redb(db) => {
+                db.read_bridge_pool_signed_nonce(height, last_height)
+            }
+        }
+    }
+
+    fn write_replay_protection_entry(
+        &mut self,
+        batch: &mut Self::WriteBatch,
+        key: &Key,
+    ) -> Result<()> {
+        match self {
+            PersistentDB::RocksDB(db) => {
+                db.write_replay_protection_entry(&mut batch.rocksdb, key)
+            }
+            PersistentDB::Redb(db) => {
+                db.write_replay_protection_entry(&mut batch.redb, key)
+            }
+        }
+    }
+
+    fn move_current_replay_protection_entries(
+        &mut self,
+        batch: &mut Self::WriteBatch,
+    ) -> Result<()> {
+        match self {
+            PersistentDB::RocksDB(db) => {
+                db.move_current_replay_protection_entries(&mut batch.rocksdb)
+            }
+            PersistentDB::Redb(db) => {
+                db.move_current_replay_protection_entries(&mut batch.redb)
+            }
+        }
+    }
+
+    fn prune_non_persisted_diffs(
+        &mut self,
+        batch: &mut Self::WriteBatch,
+        height: BlockHeight,
+    ) -> Result<()> {
+        match self {
+            PersistentDB::RocksDB(db) => {
+                db.prune_non_persisted_diffs(&mut batch.rocksdb, height)
+            }
+            PersistentDB::Redb(db) => {
+                db.prune_non_persisted_diffs(&mut batch.redb, height)
+            }
+        }
+    }
+
+    fn prune_block_history(
+        &mut self,
+        batch: &mut Self::WriteBatch,
+        from: BlockHeight,
+        to: BlockHeight,
+        prune_headers: bool,
+    ) -> Result<()> {
+        match self {
+            PersistentDB::RocksDB(db) => db.prune_block_history(
+                &mut batch.rocksdb,
+                from,
+                to,
+                prune_headers,
+            ),
+            PersistentDB::Redb(db) => {
+                db.prune_block_history(&mut batch.redb, from, to, prune_headers)
+            }
+        }
+    }
+
+    fn read_pruned_height(&self) -> Result<Option<BlockHeight>> {
+        match self {
+            PersistentDB::RocksDB(db) => db.read_pruned_height(),
+            PersistentDB::Redb(db) => db.read_pruned_height(),
+        }
+    }
+
+    fn overwrite_entry(
+        &self,
+        batch: &mut Self::WriteBatch,
+        cf: &DbColFam,
+        key: &Key,
+        new_value: impl AsRef<[u8]>,
+    ) -> Result<()> {
+        match self {
+            PersistentDB::RocksDB(db) => {
+                db.overwrite_entry(&mut batch.rocksdb, cf, key, new_value)
+            }
+            PersistentDB::Redb(db) => {
+                db.overwrite_entry(&mut batch.redb, cf, key, new_value)
+            }
+        }
+    }
+
+    fn apply_migration_to_batch(
+        &self,
+        updates: impl IntoIterator<Item = DbUpdateType>,
+    ) -> Result<Self::WriteBatch> {
+        let mut batch = PersistentDB::batch();
+        match self {
+            PersistentDB::RocksDB(db) => {
+                batch.rocksdb = db.apply_migration_to_batch(updates)?
+            }
+            PersistentDB::Redb(db) => {
+                batch.redb = db.apply_migration_to_batch(updates)?
+            }
+        }
+        Ok(batch)
+    }
+}
+
+/// A struct that can visit a set of updates of either backend, registering
+/// them all in the batch
+pub enum PersistentDBUpdateVisitor<'db> {
+    RocksDB(RocksDBUpdateVisitor<'db>),
+    Redb(RedbUpdateVisitor<'db>),
+}
+
+impl<'db> PersistentDBUpdateVisitor<'db> {
+    pub fn new(db: &'db PersistentDB) -> Self {
+        match db {
+            PersistentDB::RocksDB(db) => {
+                Self::RocksDB(RocksDBUpdateVisitor::new(db))
+            }
+            PersistentDB::Redb(db) => Self::Redb(RedbUpdateVisitor::new(db)),
+        }
+    }
+}
+
+impl<'db> DBUpdateVisitor for PersistentDBUpdateVisitor<'db> {
+    fn read(&self, key: &Key, cf: &DbColFam) -> Option<Vec<u8>> {
+        match self {
+            Self::RocksDB(visitor) => visitor.read(key, cf),
+            Self::Redb(visitor) => visitor.read(key, cf),
+        }
+    }
+
+    fn write(&mut self, key: &Key, cf: &DbColFam, value: impl AsRef<[u8]>) {
+        match self {
+            Self::RocksDB(visitor) => visitor.write(key, cf, value),
+            Self::Redb(visitor) => visitor.write(key, cf, value),
+        }
+    }
+
+    fn delete(&mut self, key: &Key, cf: &DbColFam) {
+        match self {
+            Self::RocksDB(visitor) => visitor.delete(key, cf),
+            Self::Redb(visitor) => visitor.delete(key, cf),
+        }
+    }
+
+    fn get_pattern(&self, pattern: Regex) -> Vec<(String, Vec<u8>)> {
+        match self {
+            Self::RocksDB(visitor) => visitor.get_pattern(pattern),
+            Self::Redb(visitor) => visitor.get_pattern(pattern),
+        }
+    }
+}
+
+impl<'iter> DBIter<'iter> for PersistentDB {
+    type PatternIter = Either<
+        <RocksDB as DBIter<'iter>>::PatternIter,
+        <RedbDB as DBIter<'iter>>::PatternIter,
+    >;
+    type PrefixIter = Either<
+        <RocksDB as DBIter<'iter>>::PrefixIter,
+        <RedbDB as DBIter<'iter>>::PrefixIter,
+    >;
+
+    fn iter_prefix(&'iter self, prefix: Option<&Key>) -> Self::PrefixIter {
+        match self {
+            PersistentDB::RocksDB(db) => Either::Left(db.iter_prefix(prefix)),
+            PersistentDB::Redb(db) => Either::Right(db.iter_prefix(prefix)),
+        }
+    }
+
+    fn iter_pattern(
+        &'iter self,
+        prefix: Option<&Key>,
+        pattern: Regex,
+    ) -> Self::PatternIter {
+        match self {
+            PersistentDB::RocksDB(db) => {
+                Either::Left(db.iter_pattern(prefix, pattern))
+            }
+            PersistentDB::Redb(db) => {
+                Either::Right(db.iter_pattern(prefix, pattern))
+            }
+        }
+    }
+
+    fn iter_results(&'iter self) -> Self::PrefixIter {
+        match self {
+            PersistentDB::RocksDB(db) => Either::Left(db.iter_results()),
+            PersistentDB::Redb(db) => Either::Right(db.iter_results()),
+        }
+    }
+
+    fn iter_old_diffs(
+        &'iter self,
+        height: BlockHeight,
+        prefix: Option<&'iter Key>,
+    ) -> Self::PrefixIter {
+        match self {
+            PersistentDB::RocksDB(db) => {
+                Either::Left(db.iter_old_diffs(height, prefix))
+            }
+            PersistentDB::Redb(db) => {
+                Either::Right(db.iter_old_diffs(height, prefix))
+            }
+        }
+    }
+
+    fn iter_new_diffs(
+        &'iter self,
+        height: BlockHeight,
+        prefix: Option<&'iter Key>,
+    ) -> Self::PrefixIter {
+        match self {
+            PersistentDB::RocksDB(db) => {
+                Either::Left(db.iter_new_diffs(height, prefix))
+            }
+            PersistentDB::Redb(db) => {
+                Either::Right(db.iter_new_diffs(height, prefix))
+            }
+        }
+    }
+
+    fn iter_current_replay_protection(&'iter self) -> Self::PrefixIter {
+        match self {
+            PersistentDB::RocksDB(db) => {
+                Either::Left(db.iter_current_replay_protection())
+            }
+            PersistentDB::Redb(db) => {
+                Either::Right(db.iter_current_replay_protection())
+            }
+        }
+    }
+
+    fn iter_replay_protection(&'iter self) -> Self::PrefixIter {
+        match self {
+            PersistentDB::RocksDB(db) => {
+                Either::Left(db.iter_replay_protection())
+            }
+            PersistentDB::Redb(db) => {
+                Either::Right(db.iter_replay_protection())
+            }
+        }
+    }
+}
@@ -0,0 +1,920 @@
+//! Tests of the [`DB`] and [`DBIter`] implementations, run against each of
+//! the storage backends.
+
+#![allow(clippy::arithmetic_side_effects)]
+
+use namada_replay_protection as replay_protection;
+use namada_sdk::address::EstablishedAddressGen;
+use namada_sdk::encode;
+use namada_sdk::hash::Hash;
+use namada_sdk::state::merkle_tree::{
+    tree_key_prefix_with_epoch, tree_key_prefix_with_height,
+};
+use namada_sdk::state::{
+    BlockStateWrite, DBIter, DbError as Error, DbResult as Result, MerkleTree,
+    Sha256Hasher, StoreType, DB,
+};
+use namada_sdk::storage::conversion_state::ConversionState;
+use namada_sdk::storage::types::CommitOnlyData;
+use namada_sdk::storage::{
+    BlockHeader, BlockHeight, BlockResults, DbColFam, Epoch, Epochs,
+    EthEventsQueue, Key, KeySeg, BLOCK_CF, DIFFS_CF, ROLLBACK_CF, STATE_CF,
+};
+use namada_sdk::time::DateTimeUtc;
+use tempfile::{tempdir, TempDir};
+
+use super::rocksdb::{
+    old_and_new_diff_key, BLOCK_HEADER_KEY_SEGMENT, BLOCK_TIME_KEY_SEGMENT,
+    EPOCH_KEY_SEGMENT, MERKLE_TREE_ROOT_KEY_SEGMENT, PRED_EPOCHS_KEY_SEGMENT,
+    RESULTS_KEY_PREFIX,
+};
+use super::rollback::{read_last_height, Rollback};
+use super::{
+    copy_db, open_with_backend, PersistentDB, PersistentWriteBatch,
+    COLUMN_FAMILIES,
+};
+use crate::config::DbBackend;
+
+/// Open an empty DB with each of the storage backends
+fn open_dbs() -> Vec<(TempDir, PersistentDB)> {
+    [DbBackend::RocksDb, DbBackend::Redb]
+        .into_iter()
+        .map(|backend| {
+            let dir = tempdir().unwrap();
+            let db = open_with_backend(dir.path(), backend).unwrap();
+            (dir, db)
+        })
+        .collect()
+}
+
+/// Test that a block written can be loaded back from DB.
+#[test]
+fn test_load_state() {
+    for (_dir, db) in open_dbs() {
+        let mut batch = PersistentDB::batch();
+        let last_height = BlockHeight::default();
+        db.batch_write_subspace_val(
+            &mut batch,
+            last_height,
+            &Key::parse("test").unwrap(),
+            vec![1_u8, 1, 1, 1],
+            true,
+        )
+        .unwrap();
+
+        add_block_to_batch(
+            &db,
+            &mut batch,
+            BlockHeight::default(),
+            Epoch::default(),
+            Epochs::default(),
+            &ConversionState::default(),
+        )
+        .unwrap();
+        db.exec_batch(batch).unwrap();
+
+        let _state = db
+            .read_last_block()
+            .expect("Should be able to read last block")
+            .expect("Block should have been written");
+    }
+}
+
+#[test]
+fn test_read() {
+    for (_dir, mut db) in open_dbs() {
+        let key = Key::parse("test").unwrap();
+        let batch_key = Key::parse("batch").unwrap();
+
+        let mut batch = PersistentDB::batch();
+        let last_height = BlockHeight(100);
+        db.batch_write_subspace_val(
+            &mut batch,
+            last_height,
+            &batch_key,
+            vec![1_u8, 1, 1, 1],
+            true,
+        )
+        .unwrap();
+        db.exec_batch(batch).unwrap();
+
+        db.write_subspace_val(last_height, &key, vec![1_u8, 1, 1, 0], true)
+            .unwrap();
+
+        let mut batch = PersistentDB::batch();
+        let last_height = BlockHeight(111);
+        db.batch_write_subspace_val(
+            &mut batch,
+            last_height,
+            &batch_key,
+            vec![2_u8, 2, 2, 2],
+            true,
+        )
+        .unwrap();
+        db.exec_batch(batch).unwrap();
+
+        db.write_subspace_val(last_height, &key, vec![2_u8, 2, 2, 0], true)
+            .unwrap();
+
+        let prev_value = db
+            .read_subspace_val_with_height(
+                &batch_key,
+                BlockHeight(100),
+                last_height,
+            )
+            .expect("read should succeed");
+        assert_eq!(prev_value, Some(vec![1_u8, 1, 1, 1]));
+        let prev_value = db
+            .read_subspace_val_with_height(&key, BlockHeight(100), last_height)
+            .expect("read should succeed");
+        assert_eq!(prev_value, Some(vec![1_u8, 1, 1, 0]));
+
+        let updated_value = db
+            .read_subspace_val_with_height(
+                &batch_key,
+                BlockHeight(111),
+                last_height,
+            )
+            .expect("read should succeed");
+        assert_eq!(updated_value, Some(vec![2_u8, 2, 2, 2]));
+        let updated_value = db
+            .read_subspace_val_with_height(&key, BlockHeight(111), last_height)
+            .expect("read should succeed");
+        assert_eq!(updated_value, Some(vec![2_u8, 2, 2, 0]));
+
+        let latest_value = db
+            .read_subspace_val(&batch_key)
+            .expect("read should succeed");
+        assert_eq!(latest_value, Some(vec![2_u8, 2, 2, 2]));
+        let latest_value =
+            db.read_subspace_val(&key).expect("read should succeed");
+        assert_eq!(latest_value, Some(vec![2_u8, 2, 2, 0]));
+
+        let mut batch = PersistentDB::batch();
+        let last_height = BlockHeight(222);
+        db.batch_delete_subspace_val(&mut batch, last_height, &batch_key, true)
+            .unwrap();
+        db.exec_batch(batch).unwrap();
+
+        db.delete_subspace_val(last_height, &key, true).unwrap();
+
+        let deleted_value = db
+            .read_subspace_val_with_height(
+                &batch_key,
+                BlockHeight(222),
+                last_height,
+            )
+            .expect("read should succeed");
+        assert_eq!(deleted_value, None);
+        let deleted_value = db
+            .read_subspace_val_with_height(&key, BlockHeight(222), last_height)
+            .expect("read should succeed");
+        assert_eq!(deleted_value, None);
+
+        let latest_value = db
+            .read_subspace_val(&batch_key)
+            .expect("read should succeed");
+        assert_eq!(latest_value, None);
+        let latest_value =
+            db.read_subspace_val(&key).expect("read should succeed");
+        assert_eq!(latest_value, None);
+    }
+}
+
+#[test]
+fn test_prefix_iter() {
+    for (_dir, db) in open_dbs() {
+        let prefix_0 = Key::parse("0").unwrap();
+        let key_0_a = prefix_0.push(&"a".to_string()).unwrap();
+        let key_0_b = prefix_0.push(&"b".to_string()).unwrap();
+        let key_0_c = prefix_0.push(&"c".to_string()).unwrap();
+        let prefix_1 = Key::parse("1").unwrap();
+        let key_1_a = prefix_1.push(&"a".to_string()).unwrap();
+        let key_1_b = prefix_1.push(&"b".to_string()).unwrap();
+        let key_1_c = prefix_1.push(&"c".to_string()).unwrap();
+        let prefix_01 = Key::parse("01").unwrap();
+        let key_01_a = prefix_01.push(&"a".to_string()).unwrap();
+
+        let keys_0 = vec![key_0_a, key_0_b, key_0_c];
+        let keys_1 = vec![key_1_a, key_1_b, key_1_c];
+        let keys_01 = vec![key_01_a];
+        let all_keys = [keys_0.clone(), keys_01, keys_1.clone()].concat();
+
+        // Write the keys
+        let mut batch = PersistentDB::batch();
+        let height = BlockHeight(1);
+        for key in &all_keys {
+            db.batch_write_subspace_val(&mut batch, height, key, [0_u8], true)
+                .unwrap();
+        }
+        db.exec_batch(batch).unwrap();
+
+        // Prefix "0" shouldn't match prefix "01"
+        let itered_keys: Vec<Key> = db
+            .iter_prefix(Some(&prefix_0))
+            .map(|(key, _val, _)| Key::parse(key).unwrap())
+            .collect();
+        itertools::assert_equal(keys_0, itered_keys);
+
+        let itered_keys: Vec<Key> = db
+            .iter_prefix(Some(&prefix_1))
+            .map(|(key, _val, _)| Key::parse(key).unwrap())
+            .collect();
+        itertools::assert_equal(keys_1, itered_keys);
+
+        let itered_keys: Vec<Key> = db
+            .iter_prefix(None)
+            .map(|(key, _val, _)| Key::parse(key).unwrap())
+            .collect();
+        itertools::assert_equal(all_keys, itered_keys);
+    }
+}
+
+#[test]
+fn test_rollback() {
+    for persist_diffs in [true, false] {
+        println!("Running with persist_diffs: {persist_diffs}");
+
+        for (_dir, mut db) in open_dbs() {
+            // A key that's gonna be added on a second block
+            let add_key = Key::parse("add").unwrap();
+            // A key that's gonna be deleted on a second block
+            let delete_key = Key::parse("delete").unwrap();
+            // A key that's gonna be overwritten on a second block
+            let overwrite_key = Key::parse("overwrite").unwrap();
+
+            // Write first block
+            let mut batch = PersistentDB::batch();
+            let height_0 = BlockHeight(100);
+            let mut pred_epochs = Epochs::default();
+            pred_epochs.new_epoch(height_0);
+            let conversion_state_0 = ConversionState::default();
+            let to_delete_val = vec![1_u8, 1, 0, 0];
+            let to_overwrite_val = vec![1_u8, 1, 1, 0];
+            db.batch_write_subspace_val(
+                &mut batch,
+                height_0,
+                &delete_key,
+                &to_delete_val,
+                persist_diffs,
+            )
+            .unwrap();
+            db.batch_write_subspace_val(
+                &mut batch,
+                height_0,
+                &overwrite_key,
+                &to_overwrite_val,
+                persist_diffs,
+            )
+            .unwrap();
+            for tx in [b"tx1", b"tx2"] {
+                db.write_replay_protection_entry(
+                    &mut batch,
+                    &replay_protection::key(&Hash::sha256(tx)),
+                )
+                .unwrap();
+            }
+
+            for tx in [b"tx3", b"tx4"] {
+                db.write_replay_protection_entry(
+                    &mut batch,
+                    &replay_protection::current_key(&Hash::sha256(tx)),
+                )
+                .unwrap();
+            }
+
+            add_block_to_batch(
+                &db,
+                &mut batch,
+                height_0,
+                Epoch(1),
+                pred_epochs.clone(),
+                &conversion_state_0,
+            )
+            .unwrap();
+            db.exec_batch(batch).unwrap();
+
+            // Write second block
+            let mut batch = PersistentDB::batch();
+            let height_1 = BlockHeight(101);
+            pred_epochs.new_epoch(height_1);
+            let conversion_state_1 = ConversionState::default();
+            let add_val = vec![1_u8, 0, 0, 0];
+            let overwrite_val = vec![1_u8, 1, 1, 1];
+            db.batch_write_subspace_val(
+                &mut batch,
+                height_1,
+                &add_key,
+                &add_val,
+                persist_diffs,
+            )
+            .unwrap();
+            db.batch_write_subspace_val(
+                &mut batch,
+                height_1,
+                &overwrite_key,
+                &overwrite_val,
+                persist_diffs,
+            )
+            .unwrap();
+            db.batch_delete_subspace_val(
+                &mut batch,
+                height_1,
+                &delete_key,
+                persist_diffs,
+            )
+            .unwrap();
+
+            db.move_current_replay_protection_entries(&mut batch)
+                .unwrap();
+
+            for tx in [b"tx5", b"tx6"] {
+                db.write_replay_protection_entry(
+                    &mut batch,
+                    &replay_protection::current_key(&Hash::sha256(tx)),
+                )
+                .unwrap();
+            }
+
+            add_block_to_batch(
+                &db,
+                &mut batch,
+                height_1,
+                Epoch(2),
+                pred_epochs,
+                &conversion_state_1,
+            )
+            .unwrap();
+            db.exec_batch(batch).unwrap();
+
+            // Check that the values are as expected from second block
+            let added = db.read_subspace_val(&add_key).unwrap();
+            assert_eq!(added, Some(add_val));
+            let overwritten = db.read_subspace_val(&overwrite_key).unwrap();
+            assert_eq!(overwritten, Some(overwrite_val));
+            let deleted = db.read_subspace_val(&delete_key).unwrap();
+            assert_eq!(deleted, None);
+
+            for tx in [b"tx1", b"tx2", b"tx3", b"tx4", b"tx5", b"tx6"] {
+                assert!(db
+                    .has_replay_protection_entry(&Hash::sha256(tx))
+                    .unwrap());
+            }
+
+            // Rollback to the first block height
+            db.rollback(height_0).unwrap();
+
+            // Check that the values are back to the state at the first block
+            let added = db.read_subspace_val(&add_key).unwrap();
+            assert_eq!(added, None);
+            let overwritten = db.read_subspace_val(&overwrite_key).unwrap();
+            assert_eq!(overwritten, Some(to_overwrite_val));
+            let deleted = db.read_subspace_val(&delete_key).unwrap();
+            assert_eq!(deleted, Some(to_delete_val));
+            // Check the conversion state
+            let conversion_state =
+                db.read_raw(STATE_CF, "conversion_state").unwrap();
+            assert_eq!(conversion_state, encode(&conversion_state_0));
+            for tx in [b"tx1", b"tx2", b"tx3", b"tx4"] {
+                assert!(db
+                    .has_replay_protection_entry(&Hash::sha256(tx))
+                    .unwrap());
+            }
+
+            for tx in [b"tx5", b"tx6"] {
+                assert!(!db
+                    .has_replay_protection_entry(&Hash::sha256(tx))
+                    .unwrap());
+            }
+        }
+    }
+}
+
+/// Test the rollback of several blocks in the rollback window, also with a
+/// corrupted last block
+#[test]
+fn test_rollback_multiple_blocks() {
+    for persist_diffs in [true, false] {
+        println!("Running with persist_diffs: {persist_diffs}");
+
+        for (_dir, mut db) in open_dbs() {
+            let overwrite_key = Key::parse("overwrite").unwrap();
+            let add_key = Key::parse("add").unwrap();
+            let delete_key = Key::parse("delete").unwrap();
+            let heights =
+                [BlockHeight(100), BlockHeight(101), BlockHeight(102)];
+            let vals = [vec![1_u8, 0, 0], vec![1_u8, 1, 0], vec![1_u8, 1, 1]];
+            let txs = [b"tx1", b"tx2", b"tx3"];
+            let mut pred_epochs = Epochs::default();
+            pred_epochs.new_epoch(heights[0]);
+
+            for (ix, (height, val)) in heights.iter().zip(&vals).enumerate() {
+                let mut batch = PersistentDB::batch();
+                db.batch_write_subspace_val(
+                    &mut batch,
+                    *height,
+                    &overwrite_key,
+                    val,
+                    persist_diffs,
+                )
+                .unwrap();
+                match ix {
+                    0 => db
+                        .batch_write_subspace_val(
+                            &mut batch,
+                            *height,
+                            &delete_key,
+                            val,
+                            persist_diffs,
+                        )
+                        .unwrap(),
+                    1 => db
+                        .batch_write_subspace_val(
+                            &mut batch,
+                            *height,
+                            &add_key,
+                            val,
+                            persist_diffs,
+                        )
+                        .unwrap(),
+                    _ => db
+                        .batch_delete_subspace_val(
+                            &mut batch,
+                            *height,
+                            &delete_key,
+                            persist_diffs,
+                        )
+                        .unwrap(),
+                };
+                if ix > 0 {
+                    db.move_current_replay_protection_entries(&mut batch)
+                        .unwrap();
+                }
+                db.write_replay_protection_entry(
+                    &mut batch,
+                    &replay_protection::current_key(&Hash::sha256(txs[ix])),
+                )
+                .unwrap();
+                // The second block starts a new epoch
+                if ix == 1 {
+                    pred_epochs.new_epoch(*height);
+                }
+                add_block_to_batch(
+                    &db,
+                    &mut batch,
+                    *height,
+                    pred_epochs.get_epoch(*height).unwrap(),
+                    pred_epochs.clone(),
+                    &ConversionState::default(),
+                )
+                .unwrap();
+                db.exec_batch(batch).unwrap();
+            }
+
+            // Heights out of the DB cannot be rolled back to
+            assert!(Rollback::plan(&db, BlockHeight(103)).is_err());
+            assert!(Rollback::plan(&db, BlockHeight(99)).is_err());
+
+            // A dry run doesn't change the DB
+            let plan = Rollback::plan(&db, heights[0]).unwrap();
+            assert!(plan
+                .to_string()
+                .starts_with("Rollback from height 102 to height 100"));
+            assert!(plan
+                .changes
+                .iter()
+                .any(|change| change.cf == DbColFam::SUBSPACE
+                    && change.key == add_key.to_string()
+                    && change.value.is_none()));
+            assert_eq!(
+                db.read_subspace_val(&overwrite_key).unwrap(),
+                Some(vals[2].clone())
+            );
+            assert_eq!(read_last_height(&db).unwrap(), heights[2]);
+
+            // Corrupt the data of the last block
+            let mut batch = PersistentDB::batch();
+            for segment in [EPOCH_KEY_SEGMENT, PRED_EPOCHS_KEY_SEGMENT] {
+                db.add_raw_to_batch(
+                    &mut batch,
+                    &DbColFam::BLOCK,
+                    &format!("{}/{segment}", heights[2].raw()),
+                    &[0xff; 3],
+                )
+                .unwrap();
+            }
+            db.exec_batch(batch).unwrap();
+
+            let rollback = db.rollback(heights[0]).unwrap();
+            assert_eq!(rollback.last_height, heights[2]);
+            assert_eq!(read_last_height(&db).unwrap(), heights[0]);
+
+            // Check that the values are back to the state at the first block
+            assert_eq!(
+                db.read_subspace_val(&overwrite_key).unwrap(),
+                Some(vals[0].clone())
+            );
+            assert_eq!(db.read_subspace_val(&add_key).unwrap(), None);
+            assert_eq!(
+                db.read_subspace_val(&delete_key).unwrap(),
+                Some(vals[0].clone())
+            );
+            assert!(db
+                .has_replay_protection_entry(&Hash::sha256(txs[0]))
+                .unwrap());
+            for tx in &txs[1..] {
+                assert!(!db
+                    .has_replay_protection_entry(&Hash::sha256(tx))
+                    .unwrap());
+            }
+
+            // Check that the data of the rolled back blocks is deleted
+            for height in &heights[1..] {
+                for segment in [EPOCH_KEY_SEGMENT, PRED_EPOCHS_KEY_SEGMENT] {
+                    let key = format!("{}/{segment}", height.raw());
+                    assert_eq!(db.read_raw(BLOCK_CF, &key), None);
+                }
+                let prefix = Key::from(height.to_db_key());
+                for cf in [DbColFam::DIFFS, DbColFam::ROLLBACK] {
+                    assert_eq!(
+                        db.iter_column_family(&cf, Some(&prefix)).count(),
+                        0
+                    );
+                }
+            }
+            let key = format!("{}/{EPOCH_KEY_SEGMENT}", heights[0].raw());
+            assert!(db.read_raw(BLOCK_CF, &key).is_some());
+
+            // The state can be loaded at the first block
+            let state = db.read_last_block().unwrap().unwrap();
+            assert_eq!(state.height, heights[0]);
+        }
+    }
+}
+
+#[test]
+fn test_diffs() {
+    for (_dir, mut db) in open_dbs() {
+        let key_with_diffs = Key::parse("with_diffs").unwrap();
+        let key_without_diffs = Key::parse("without_diffs").unwrap();
+
+        let initial_val = vec![1_u8, 1, 0, 0];
+        let overwrite_val = vec![1_u8, 1, 1, 0];
+
+        // Write first block
+        let mut batch = PersistentDB::batch();
+        let height_0 = BlockHeight::first();
+        db.batch_write_subspace_val(
+            &mut batch,
+            height_0,
+            &key_with_diffs,
+            &initial_val,
+            true,
+        )
+        .unwrap();
+        db.batch_write_subspace_val(
+            &mut batch,
+            height_0,
+            &key_without_diffs,
+            &initial_val,
+            false,
+        )
+        .unwrap();
+        db.exec_batch(batch).unwrap();
+
+        {
+            // Diffs new key for `key_with_diffs` at height_0 must be
+            // present
+            let (old_with_h0, new_with_h0) =
+                old_and_new_diff_key(&key_with_diffs, height_0).unwrap();
+            assert!(db.read_raw(DIFFS_CF, &old_with_h0).is_none());
+            assert!(db.read_raw(DIFFS_CF, &new_with_h0).is_some());
+
+            // Diffs new key for `key_without_diffs` at height_0 must be
+            // present
+            let (old_wo_h0, new_wo_h0) =
+                old_and_new_diff_key(&key_without_diffs, height_0).unwrap();
+            assert!(db.read_raw(ROLLBACK_CF, &old_wo_h0).is_none());
+            assert!(db.read_raw(ROLLBACK_CF, &new_wo_h0).is_some());
+        }
+
+        // Write second block
+        let mut batch = PersistentDB::batch();
+        let height_1 = height_0 + 10u64;
+        db.batch_write_subspace_val(
+            &mut batch,
+            height_1,
+            &key_with_diffs,
+            &overwrite_val,
+            true,
+        )
+        .unwrap();
+        db.batch_write_subspace_val(
+            &mut batch,
+            height_1,
+            &key_without_diffs,
+            &overwrite_val,
+            false,
+        )
+        .unwrap();
+        db.prune_non_persisted_diffs(&mut batch, height_0).unwrap();
+        db.exec_batch(batch).unwrap();
+
+        {
+            // Diffs keys for `key_with_diffs` at height_0 must be present
+            let (old_with_h0, new_with_h0) =
+                old_and_new_diff_key(&key_with_diffs, height_0).unwrap();
+            assert!(db.read_raw(DIFFS_CF, &old_with_h0).is_none());
+            assert!(db.read_raw(DIFFS_CF, &new_with_h0).is_some());
+
+            // Diffs keys for `key_without_diffs` at height_0 must be gone
+            let (old_wo_h0, new_wo_h0) =
+                old_and_new_diff_key(&key_without_diffs, height_0).unwrap();
+            assert!(db.read_raw(ROLLBACK_CF, &old_wo_h0).is_none());
+            assert!(db.read_raw(ROLLBACK_CF, &new_wo_h0).is_none());
+
+            // Diffs keys for `key_with_diffs` at height_1 must be present
+            let (old_with_h1, new_with_h1) =
+                old_and_new_diff_key(&key_with_diffs, height_1).unwrap();
+            assert!(db.read_raw(DIFFS_CF, &old_with_h1).is_some());
+            assert!(db.read_raw(DIFFS_CF, &new_with_h1).is_some());
+
+            // Diffs keys for `key_without_diffs` at height_1 must be
+            // present
+            let (old_wo_h1, new_wo_h1) =
+                old_and_new_diff_key(&key_without_diffs, height_1).unwrap();
+            assert!(db.read_raw(ROLLBACK_CF, &old_wo_h1).is_some());
+            assert!(db.read_raw(ROLLBACK_CF, &new_wo_h1).is_some());
+        }
+
+        // Write third block
+        let mut batch = PersistentDB::batch();
+        let height_2 = height_1 + 10;
+        db.batch_write_subspace_val(
+            &mut batch,
+            height_2,
+            &key_with_diffs,
+            &initial_val,
+            true,
+        )
+        .unwrap();
+        db.batch_write_subspace_val(
+            &mut batch,
+            height_2,
+            &key_without_diffs,
+            &initial_val,
+            false,
+        )
+        .unwrap();
+        db.prune_non_persisted_diffs(&mut batch, height_1).unwrap();
+        db.exec_batch(batch).unwrap();
+
+        {
+            // Diffs keys for `key_with_diffs` at height_1 must be present
+            let (old_with_h1, new_with_h1) =
+                old_and_new_diff_key(&key_with_diffs, height_1).unwrap();
+            assert!(db.read_raw(DIFFS_CF, &old_with_h1).is_some());
+            assert!(db.read_raw(DIFFS_CF, &new_with_h1).is_some());
+
+            // Diffs keys for `key_without_diffs` at height_1 must be gone
+            let (old_wo_h1, new_wo_h1) =
+                old_and_new_diff_key(&key_without_diffs, height_1).unwrap();
+            assert!(db.read_raw(ROLLBACK_CF, &old_wo_h1).is_none());
+            assert!(db.read_raw(ROLLBACK_CF, &new_wo_h1).is_none());
+
+            // Diffs keys for `key_with_diffs` at height_2 must be present
+            let (old_with_h2, new_with_h2) =
+                old_and_new_diff_key(&key_with_diffs, height_2).unwrap();
+            assert!(db.read_raw(DIFFS_CF, &old_with_h2).is_some());
+            assert!(db.read_raw(DIFFS_CF, &new_with_h2).is_some());
+
+            // Diffs keys for `key_without_diffs` at height_2 must be
+            // present
+            let (old_wo_h2, new_wo_h2) =
+                old_and_new_diff_key(&key_without_diffs, height_2).unwrap();
+            assert!(db.read_raw(ROLLBACK_CF, &old_wo_h2).is_some());
+            assert!(db.read_raw(ROLLBACK_CF, &new_wo_h2).is_some());
+        }
+    }
+}
+
+/// Test that the pruned block history can no longer be read, while the
+/// more recent history and the Merkle tree stores of the epochs are kept. The
+/// block headers are only pruned on demand.
+#[test]
+fn test_prune_block_history() {
+    for (_dir, mut db) in open_dbs() {
+        let key = Key::parse("key").unwrap();
+        let last_height = BlockHeight(5);
+        for height in 1..=last_height.0 {
+            let mut batch = PersistentDB::batch();
+            let height = BlockHeight(height);
+            db.batch_write_subspace_val(
+                &mut batch,
+                height,
+                &key,
+                vec![u8::try_from(height.0).unwrap()],
+                true,
+            )
+            .unwrap();
+            // The epochs have the same prefix as the heights in the DB
+            add_block_to_batch(
+                &db,
+                &mut batch,
+                height,
+                Epoch(height.0),
+                Epochs::default(),
+                &ConversionState::default(),
+            )
+            .unwrap();
+            db.add_raw_to_batch(
+                &mut batch,
+                &DbColFam::BLOCK,
+                &format!("{}/{BLOCK_HEADER_KEY_SEGMENT}", height.raw()),
+                &encode(&BlockHeader::default()),
+            )
+            .unwrap();
+            db.exec_batch(batch).unwrap();
+        }
+        assert!(db.read_pruned_height().unwrap().is_none());
+
+        let mut batch = PersistentDB::batch();
+        db.prune_block_history(
+            &mut batch,
+            BlockHeight(1),
+            BlockHeight(3),
+            false,
+        )
+        .unwrap();
+        db.exec_batch(batch).unwrap();
+
+        assert_eq!(db.read_pruned_height().unwrap(), Some(BlockHeight(3)));
+        match db.read_subspace_val_with_height(
+            &key,
+            BlockHeight(2),
+            last_height,
+        ) {
+            Err(Error::Pruned { height, oldest }) => {
+                assert_eq!(height, BlockHeight(2));
+                assert_eq!(oldest, BlockHeight(4));
+            }
+            res => panic!("Unexpected result {res:?}"),
+        }
+        let val = db
+            .read_subspace_val_with_height(&key, BlockHeight(4), last_height)
+            .unwrap();
+        assert_eq!(val, Some(vec![4]));
+
+        for height in 1..=last_height.0 {
+            let is_pruned = height <= 3;
+            let height = BlockHeight(height);
+            let (_, new_key) = old_and_new_diff_key(&key, height).unwrap();
+            assert_eq!(db.read_raw(DIFFS_CF, &new_key).is_none(), is_pruned);
+            let time_key = format!("{}/{BLOCK_TIME_KEY_SEGMENT}", height.raw());
+            assert_eq!(db.read_raw(BLOCK_CF, &time_key).is_none(), is_pruned);
+            let results_key = format!("{RESULTS_KEY_PREFIX}/{}", height.raw());
+            assert_eq!(
+                db.read_raw(BLOCK_CF, &results_key).is_none(),
+                is_pruned
+            );
+            let base_root_key = format!(
+                "{}/{MERKLE_TREE_ROOT_KEY_SEGMENT}",
+                tree_key_prefix_with_height(&StoreType::Base, height)
+            );
+            assert_eq!(
+                db.read_raw(BLOCK_CF, &base_root_key).is_none(),
+                is_pruned
+            );
+            // The stores written once per epoch are kept
+            let account_root_key = format!(
+                "{}/{MERKLE_TREE_ROOT_KEY_SEGMENT}",
+                tree_key_prefix_with_epoch(
+                    &StoreType::Account,
+                    Epoch(height.0)
+                )
+            );
+            assert!(db.read_raw(BLOCK_CF, &account_root_key).is_some());
+            // The block headers are kept for the IBC consensus states
+            assert!(db.read_block_header(height).unwrap().is_some());
+        }
+
+        let mut batch = PersistentDB::batch();
+        db.prune_block_history(
+            &mut batch,
+            BlockHeight(4),
+            BlockHeight(4),
+            true,
+        )
+        .unwrap();
+        db.exec_batch(batch).unwrap();
+        assert_eq!(db.read_pruned_height().unwrap(), Some(BlockHeight(4)));
+        assert!(db.read_block_header(BlockHeight(4)).unwrap().is_none());
+        assert!(db.read_block_header(BlockHeight(5)).unwrap().is_some());
+    }
+}
+
+/// Test that all the entries are copied from one backend to another.
+#[test]
+fn test_copy_db() {
+    let from_dir = tempdir().unwrap();
+    let from = open_with_backend(from_dir.path(), DbBackend::RocksDb).unwrap();
+    let to_dir = tempdir().unwrap();
+    let to = open_with_backend(to_dir.path(), DbBackend::Redb).unwrap();
+
+    let mut batch = PersistentDB::batch();
+    for height in 1..=3 {
+        let height = BlockHeight(height);
+        from.batch_write_subspace_val(
+            &mut batch,
+            height,
+            &Key::parse(format!("key{height}")).unwrap(),
+            vec![1_u8, 2, 3],
+            true,
+        )
+        .unwrap();
+        add_block_to_batch(
+            &from,
+            &mut batch,
+            height,
+            Epoch::default(),
+            Epochs::default(),
+            &ConversionState::default(),
+        )
+        .unwrap();
+    }
+    from.write_replay_protection_entry(
+        &mut batch,
+        &replay_protection::key(&Hash::sha256(b"tx")),
+    )
+    .unwrap();
+    from.exec_batch(batch).unwrap();
+
+    let copied = copy_db(&from, &to).unwrap();
+    assert!(copied > 0);
+
+    let mut total = 0_u64;
+    for cf in COLUMN_FAMILIES {
+        let from_entries: Vec<_> = from
+            .iter_column_family(&cf, None)
+            .map(|(key, val, _gas)| (key, val))
+            .collect();
+        let to_entries: Vec<_> = to
+            .iter_column_family(&cf, None)
+            .map(|(key, val, _gas)| (key, val))
+            .collect();
+        assert_eq!(from_entries, to_entries);
+        total += u64::try_from(from_entries.len()).unwrap();
+    }
+    assert_eq!(copied, total);
+
+    let from_block = from.read_last_block().unwrap().unwrap();
+    let to_block = to.read_last_block().unwrap().unwrap();
+    assert_eq!(from_block.height, to_block.height);
+    assert_eq!(to_block.height, BlockHeight(3));
+    assert_eq!(
+        to.read_subspace_val(&Key::parse("key2").unwrap()).unwrap(),
+        Some(vec![1_u8, 2, 3])
+    );
+}
+
+/// A test helper to write a block
+fn add_block_to_batch(
+    db: &PersistentDB,
+    batch: &mut PersistentWriteBatch,
+    height: BlockHeight,
+    epoch: Epoch,
+    pred_epochs: Epochs,
+    conversion_state: &ConversionState,
+) -> Result<()> {
+    let merkle_tree = MerkleTree::<Sha256Hasher>::default();
+    let merkle_tree_stores = merkle_tree.stores();
+    #[allow(clippy::disallowed_methods)]
+    let time = DateTimeUtc::now();
+    let next_epoch_min_start_height = BlockHeight::default();
+    #[allow(clippy::disallowed_methods)]
+    let next_epoch_min_start_time = DateTimeUtc::now();
+    let update_epoch_blocks_delay = None;
+    let address_gen = EstablishedAddressGen::new("whatever");
+    let results = BlockResults::default();
+    let eth_events_queue = EthEventsQueue::default();
+    let commit_only_data = CommitOnlyData::default();
+    let block = BlockStateWrite {
+        merkle_tree_stores,
+        header: None,
+        height,
+        time,
+        epoch,
+        results: &results,
+        conversion_state,
+        pred_epochs: &pred_epochs,
+        next_epoch_min_start_height,
+        next_epoch_min_start_time,
+        update_epoch_blocks_delay,
+        address_gen: &address_gen,
+        ethereum_height: None,
+        eth_events_queue: &eth_events_queue,
+        commit_only_data: &commit_only_data,
+    };
+
+    db.add_block_to_batch(block, batch, true)
+}
@@ -0,0 +1,589 @@
+//! Offline inspection of the DB: iteration over the entries of the column
+//! families, diffs between two heights or two DBs, consistency checks of the
+//! Merkle tree stores and size statistics.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use data_encoding::HEXLOWER;
+use itertools::{EitherOrBoth, Itertools};
+use namada_sdk::chain::BlockHeight;
+use namada_sdk::state::{
+    DBIter, DbError, FullAccessState, StorageHasher, StoreType, DB,
+};
+use namada_sdk::storage::{DbColFam, Key};
+use regex::Regex;
+use thiserror::Error;
+
+use super::state_diffs::{self, DiffFilter, StateDiff};
+use super::{PersistentDB, COLUMN_FAMILIES};
+
+/// DB inspection error
+#[allow(missing_docs)]
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("DB error: {0}")]
+    Db(#[from] DbError),
+    #[error("State error: {0}")]
+    State(#[from] namada_sdk::state::Error),
+    #[error("State diffs error: {0}")]
+    Diffs(#[from] state_diffs::Error),
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Genesis error: {0}")]
+    Genesis(String),
+    #[error("Invalid key pattern: {0}")]
+    InvalidPattern(#[from] regex::Error),
+    #[error("No deserializer is registered for the type hash {0}")]
+    UnknownTypeHash(String),
+}
+
+/// DB inspection result
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Decoder of a value into a human-readable string, such as the deserializers
+/// registered with their type hash in `namada_migrations`
+pub type ValueDecoder = fn(Vec<u8>) -> Option<String>;
+
+/// An entry of a column family
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DbEntry {
+    /// The full key of the entry
+    pub key: String,
+    /// The raw value
+    pub value: Vec<u8>,
+    /// The decoded value, if a decoder was given and it could decode it
+    pub decoded: Option<String>,
+}
+
+impl DbEntry {
+    /// Encode the entry as a JSON object with a hex-encoded value
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "key": self.key,
+            "value": HEXLOWER.encode(&self.value),
+            "decoded": self.decoded,
+        })
+    }
+}
+
+impl fmt::Display for DbEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.decoded {
+            Some(decoded) => write!(f, "{}: {}", self.key, decoded),
+            None => {
+                write!(f, "{}: 0x{}", self.key, HEXLOWER.encode(&self.value))
+            }
+        }
+    }
+}
+
+/// Iterate over the entries of a column family under the key prefix, if any,
+/// whose keys match the pattern, if any. The values are decoded with the
+/// given decoder.
+pub fn iter_entries<'a>(
+    db: &'a PersistentDB,
+    cf: &DbColFam,
+    prefix: Option<&Key>,
+    pattern: Option<&'a Regex>,
+    decoder: Option<ValueDecoder>,
+) -> impl Iterator<Item = DbEntry> + 'a {
+    db.iter_column_family(cf, prefix)
+        .filter(move |(key, _value, _gas)| {
+            pattern.map_or(true, |pattern| pattern.is_match(key))
+        })
+        .map(move |(key, value, _gas)| DbEntry {
+            decoded: decoder.and_then(|decode| decode(value.clone())),
+            key,
+            value,
+        })
+}
+
+/// Diff the state at two committed heights, from the persisted diffs of the
+/// blocks in between. The returned diffs have the `to` height and only the
+/// keys whose diffs are persisted in the DB are present. A key that was changed
+/// and then restored to its value at the `from` height is not present.
+pub fn diff_heights<D>(
+    db: &D,
+    from: BlockHeight,
+    to: BlockHeight,
+    filter: &DiffFilter,
+) -> Result<Vec<StateDiff>>
+where
+    D: DB + for<'iter> DBIter<'iter>,
+{
+    let last = db
+        .read_last_block()?
+        .ok_or(state_diffs::Error::NoBlock)?
+        .height;
+    if from >= to || to > last {
+        return Err(state_diffs::Error::InvalidRange { from, to, last }.into());
+    }
+    let mut height = from.next_height();
+    if let Some(pruned_height) = db.read_pruned_height()? {
+        if height <= pruned_height {
+            return Err(DbError::Pruned {
+                height,
+                oldest: pruned_height.next_height(),
+            }
+            .into());
+        }
+    }
+
+    let mut changes: BTreeMap<Key, StateDiff> = BTreeMap::new();
+    while height <= to {
+        for diff in state_diffs::read_block_diffs(db, height)? {
+            if !filter.matches(&diff) {
+                continue;
+            }
+            // Keep the value before the first change and after the last one
+            match changes.get_mut(&diff.key) {
+                Some(change) => change.new_value = diff.new_value,
+                None => {
+                    changes.insert(diff.key.clone(), diff);
+                }
+            }
+        }
+        height = height.next_height();
+    }
+
+    Ok(changes
+        .into_values()
+        .filter(|change| change.old_value != change.new_value)
+        .map(|change| StateDiff {
+            height: to,
+            ..change
+        })
+        .collect())
+}
+
+/// The difference of an entry between two DBs
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DbDiff {
+    /// The column family of the entry
+    pub cf: DbColFam,
+    /// The full key of the entry
+    pub key: String,
+    /// The value in the first DB, if present
+    pub left: Option<Vec<u8>>,
+    /// The value in the second DB, if present
+    pub right: Option<Vec<u8>>,
+}
+
+impl DbDiff {
+    /// Encode the diff as a JSON object with hex-encoded values
+    pub fn to_json(&self) -> serde_json::Value {
+        let hex = |value: &Option<Vec<u8>>| {
+            value.as_ref().map(|value| HEXLOWER.encode(value))
+        };
+        serde_json::json!({
+            "cf": self.cf.to_str(),
+            "key": self.key,
+            "left": hex(&self.left),
+            "right": hex(&self.right),
+        })
+    }
+}
+
+impl fmt::Display for DbDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let hex = |value: &Option<Vec<u8>>| match value {
+            Some(value) => format!("0x{}", HEXLOWER.encode(value)),
+            None => "<none>".to_string(),
+        };
+        write!(
+            f,
+            "[{}] {}: {} -> {}",
+            self.cf.to_str(),
+            self.key,
+            hex(&self.left),
+            hex(&self.right)
+        )
+    }
+}
+
+/// Diff the entries of a column family between two DBs, optionally only the
+/// ones under a key prefix. The diffs are sorted by key.
+pub fn diff_dbs<'a>(
+    left: &'a PersistentDB,
+    right: &'a PersistentDB,
+    cf: DbColFam,
+    prefix: Option<&Key>,
+) -> impl Iterator<Item = DbDiff> + 'a {
+    left.iter_column_family(&cf, prefix)
+        .merge_join_by(
+            right.iter_column_family(&cf, prefix),
+            |(left_key, ..), (right_key, ..)| left_key.cmp(right_key),
+        )
+        .filter_map(move |entries| {
+            let (key, left, right) = match entries {
+                EitherOrBoth::Both((key, left, _), (_, right, _)) => {
+                    if left == right {
+                        return None;
+                    }
+                    (key, Some(left), Some(right))
+                }
+                EitherOrBoth::Left((key, left, _)) => (key, Some(left), None),
+                EitherOrBoth::Right((key, right, _)) => {
+                    (key, None, Some(right))
+                }
+            };
+            Some(DbDiff {
+                cf,
+                key,
+                left,
+                right,
+            })
+        })
+}
+
+/// The result of the consistency check of a Merkle tree store
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MerkleCheck {
+    /// The height of the checked tree
+    pub height: BlockHeight,
+    /// The checked store
+    pub store_type: StoreType,
+    /// The root of the store
+    pub root: [u8; 32],
+    /// The validation error, if the store is inconsistent
+    pub error: Option<String>,
+}
+
+impl MerkleCheck {
+    /// Check if the store is consistent
+    pub fn is_valid(&self) -> bool {
+        self.error.is_none()
+    }
+
+    /// Encode the check as a JSON object with a hex-encoded root
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "height": self.height.0,
+            "store_type": self.store_type.to_string(),
+            "root": HEXLOWER.encode(&self.root),
+            "valid": self.is_valid(),
+            "error": self.error,
+        })
+    }
+}
+
+impl fmt::Display for MerkleCheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let root = HEXLOWER.encode(&self.root);
+        match &self.error {
+            None => write!(
+                f,
+                "[{}] {}: ok (root {root})",
+                self.height, self.store_type
+            ),
+            Some(error) => write!(
+                f,
+                "[{}] {}: INVALID (root {root}): {error}",
+                self.height, self.store_type
+            ),
+        }
+    }
+}
+
+/// Restore the Merkle tree at the given height, or at the last committed
+/// height if none, and check the consistency of each of its stores
+pub fn check_merkle_trees<D, H>(
+    state: &FullAccessState<D, H>,
+    height: Option<BlockHeight>,
+) -> Result<Vec<MerkleCheck>>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter>,
+    H: 'static + StorageHasher + Default,
+{
+    let last = state.in_mem().get_last_block_height();
+    if last.0 == 0 {
+        return Err(state_diffs::Error::NoBlock.into());
+    }
+    let height = height.unwrap_or(last);
+    if height.0 == 0 || height > last {
+        return Err(state_diffs::Error::InvalidRange {
+            from: height,
+            to: height,
+            last,
+        }
+        .into());
+    }
+
+    let tree = state.get_merkle_tree(height, None)?;
+    Ok(StoreType::iter()
+        .map(|store_type| MerkleCheck {
+            height,
+            store_type: *store_type,
+            root: tree.sub_root(store_type).0,
+            error: tree
+                .validate_store(store_type)
+                .err()
+                .map(|err| err.to_string()),
+        })
+        .collect())
+}
+
+/// Size statistics of the entries of a column family under a key prefix
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SizeStats {
+    /// The column family of the entries
+    pub cf: DbColFam,
+    /// The key prefix of the entries, empty for the whole column family
+    pub prefix: String,
+    /// The number of entries
+    pub entries: usize,
+    /// The total size of the keys in bytes
+    pub key_bytes: usize,
+    /// The total size of the values in bytes
+    pub value_bytes: usize,
+}
+
+impl SizeStats {
+    fn new(cf: DbColFam, prefix: String) -> Self {
+        Self {
+            cf,
+            prefix,
+            entries: 0,
+            key_bytes: 0,
+            value_bytes: 0,
+        }
+    }
+
+    fn add(&mut self, key: &str, value: &[u8]) {
+        self.entries = self.entries.saturating_add(1);
+        self.key_bytes = self.key_bytes.saturating_add(key.len());
+        self.value_bytes = self.value_bytes.saturating_add(value.len());
+    }
+
+    /// Encode the statistics as a JSON object
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "cf": self.cf.to_str(),
+            "prefix": self.prefix,
+            "entries": self.entries,
+            "key_bytes": self.key_bytes,
+            "value_bytes": self.value_bytes,
+        })
+    }
+}
+
+impl fmt::Display for SizeStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let prefix = if self.prefix.is_empty() {
+            "*"
+        } else {
+            &self.prefix
+        };
+        write!(
+            f,
+            "[{}] {prefix}: {} entries, {} key bytes, {} value bytes",
+            self.cf.to_str(),
+            self.entries,
+            self.key_bytes,
+            self.value_bytes
+        )
+    }
+}
+
+/// Collect the size statistics of every column family. For each of them, the
+/// totals come first, followed by the statistics of the key prefixes made of
+/// the first `depth` key segments, sorted by prefix.
+pub fn size_stats(db: &PersistentDB, depth: usize) -> Vec<SizeStats> {
+    let mut stats = vec![];
+    for cf in COLUMN_FAMILIES {
+        let mut total = SizeStats::new(cf, String::new());
+        let mut prefixes: BTreeMap<String, SizeStats> = BTreeMap::new();
+        for (key, value, _gas) in db.iter_column_family(&cf, None) {
+            total.add(&key, &value);
+            if depth > 0 {
+                let prefix = key.split('/').take(depth).join("/");
+                prefixes
+                    .entry(prefix)
+                    .or_insert_with_key(|prefix| {
+                        SizeStats::new(cf, prefix.clone())
+                    })
+                    .add(&key, &value);
+            }
+        }
+        stats.push(total);
+        stats.extend(prefixes.into_values());
+    }
+    stats
+}
+
+#[cfg(test)]
+mod test {
+    use namada_sdk::address;
+    use namada_sdk::borsh::BorshSerializeExt;
+    use namada_sdk::chain::ChainId;
+    use namada_sdk::state::StorageWrite;
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::config::DbBackend;
+    use crate::shell::is_key_diff_storable;
+    use crate::storage::{open_with_backend, PersistentState};
+
+    fn open_state(db_path: &TempDir) -> PersistentState {
+        PersistentState::open(
+            db_path.path(),
+            None,
+            ChainId::default(),
+            address::testing::nam(),
+            None,
+            is_key_diff_storable,
+        )
+    }
+
+    #[test]
+    fn test_inspect_db() {
+        let db_path =
+            TempDir::new().expect("Unable to create a temporary DB directory");
+        let mut state = open_state(&db_path);
+        let key_a = Key::parse("a").unwrap();
+        let key_b = Key::parse("b/c").unwrap();
+        let key_c = Key::parse("b/d").unwrap();
+
+        state.in_mem_mut().begin_block(BlockHeight(1)).unwrap();
+        state.write(&key_a, 1_u64).unwrap();
+        state.write(&key_b, 2_u64).unwrap();
+        state.commit_block().unwrap();
+
+        state.in_mem_mut().begin_block(BlockHeight(2)).unwrap();
+        state.write(&key_a, 3_u64).unwrap();
+        state.delete(&key_b).unwrap();
+        state.write(&key_c, 4_u64).unwrap();
+        state.commit_block().unwrap();
+
+        state.in_mem_mut().begin_block(BlockHeight(3)).unwrap();
+        state.write(&key_a, 1_u64).unwrap();
+        state.commit_block().unwrap();
+
+        // Iterate by prefix and by pattern
+        let db = state.db();
+        let decoder: ValueDecoder = |bytes| {
+            <u64 as namada_sdk::borsh::BorshDeserialize>::try_from_slice(&bytes)
+                .map(|val| format!("{val:?}"))
+                .ok()
+        };
+        let entries: Vec<_> = iter_entries(
+            db,
+            &DbColFam::SUBSPACE,
+            Some(&Key::parse("b").unwrap()),
+            None,
+            Some(decoder),
+        )
+        .collect();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].key, "b/d");
+        assert_eq!(entries[0].decoded.as_deref(), Some("4"));
+        let pattern = Regex::new("^a$").unwrap();
+        let entries: Vec<_> =
+            iter_entries(db, &DbColFam::SUBSPACE, None, Some(&pattern), None)
+                .collect();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].value, 1_u64.serialize_to_vec());
+        assert_eq!(entries[0].decoded, None);
+
+        // The key `a` is back to its value at height 1
+        let diffs = diff_heights(
+            db,
+            BlockHeight(1),
+            BlockHeight(3),
+            &DiffFilter::default(),
+        )
+        .unwrap();
+        assert_eq!(diffs.len(), 2);
+        assert_eq!(diffs[0].key, key_b);
+        assert_eq!(diffs[0].height, BlockHeight(3));
+        assert_eq!(diffs[0].old_value, Some(2_u64.serialize_to_vec()));
+        assert_eq!(diffs[0].new_value, None);
+        assert_eq!(diffs[1].key, key_c);
+        assert_eq!(diffs[1].old_value, None);
+        assert_eq!(diffs[1].new_value, Some(4_u64.serialize_to_vec()));
+        assert!(matches!(
+            diff_heights(
+                db,
+                BlockHeight(2),
+                BlockHeight(4),
+                &DiffFilter::default()
+            ),
+            Err(Error::Diffs(state_diffs::Error::InvalidRange { .. }))
+        ));
+
+        // All the stores of the committed trees are consistent
+        for height in [None, Some(BlockHeight(2))] {
+            let checks = check_merkle_trees(&state, height).unwrap();
+            assert_eq!(checks.len(), StoreType::iter().len());
+            assert_eq!(checks[0].height, height.unwrap_or(BlockHeight(3)));
+            assert!(checks.iter().all(MerkleCheck::is_valid));
+        }
+
+        // The totals come before the prefixes
+        let stats = size_stats(state.db(), 1);
+        let subspace: Vec<_> = stats
+            .iter()
+            .filter(|stats| stats.cf == DbColFam::SUBSPACE)
+            .collect();
+        assert_eq!(subspace[0].prefix, "");
+        assert_eq!(subspace[0].entries, 2);
+        assert_eq!(subspace[1].prefix, "a");
+        assert_eq!(subspace[1].entries, 1);
+        assert_eq!(subspace[2].prefix, "b");
+        assert_eq!(subspace[2].key_bytes, "b/d".len());
+        assert_eq!(subspace[2].value_bytes, 8);
+    }
+
+    #[test]
+    fn test_diff_dbs() {
+        let left_dir = TempDir::new().unwrap();
+        let left =
+            open_with_backend(left_dir.path(), DbBackend::RocksDb).unwrap();
+        let right_dir = TempDir::new().unwrap();
+        let right =
+            open_with_backend(right_dir.path(), DbBackend::Redb).unwrap();
+
+        let mut batch = PersistentDB::batch();
+        for (key, value) in [("a", 1_u8), ("b", 2), ("c", 3)] {
+            left.add_raw_to_batch(
+                &mut batch,
+                &DbColFam::SUBSPACE,
+                key,
+                &[value],
+            )
+            .unwrap();
+        }
+        left.exec_batch(batch).unwrap();
+        let mut batch = PersistentDB::batch();
+        for (key, value) in [("b", 2_u8), ("c", 4), ("d", 5)] {
+            right
+                .add_raw_to_batch(
+                    &mut batch,
+                    &DbColFam::SUBSPACE,
+                    key,
+                    &[value],
+                )
+                .unwrap();
+        }
+        right.exec_batch(batch).unwrap();
+
+        let diffs: Vec<_> =
+            diff_dbs(&left, &right, DbColFam::SUBSPACE, None).collect();
+        let summary: Vec<_> = diffs
+            .iter()
+            .map(|diff| {
+                (diff.key.as_str(), diff.left.clone(), diff.right.clone())
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("a", Some(vec![1]), None),
+                ("c", Some(vec![3]), Some(vec![4])),
+                ("d", None, Some(vec![5])),
+            ]
+        );
+        assert_eq!(diff_dbs(&left, &right, DbColFam::STATE, None).count(), 0);
+    }
+}
@@ -1,24 +1,35 @@
 //! The storage module handles both the current state in-memory and the stored
 //! state in DB.
 
+mod backend;
+#[cfg(test)]
+mod db_tests;
+pub mod inspect;
+mod redb;
 mod rocksdb;
+pub mod rollback;
+pub mod state_diffs;
+pub mod state_sync;
 
 use std::fmt;
 
 use arse_merkle_tree::blake2b::Blake2bHasher;
 use arse_merkle_tree::traits::Hasher;
 use arse_merkle_tree::H256;
+pub use backend::{
+    copy_db, detect_backend, open_read_only, open_with_backend, prepare_db,
+    PersistentDB, PersistentDBUpdateVisitor, PersistentWriteBatch,
+    COLUMN_FAMILIES,
+};
 use blake2b_rs::{Blake2b, Blake2bBuilder};
 use namada_sdk::state::{FullAccessState, StorageHasher};
-#[cfg(test)]
-pub use rocksdb::SnapshotPath;
-pub use rocksdb::{open, DbSnapshot, DbSnapshotMeta, RocksDBUpdateVisitor};
+pub use rocksdb::{
+    open, DbSnapshot, DbSnapshotMeta, RocksDBUpdateVisitor, SnapshotPath,
+};
 
 #[derive(Default)]
 pub struct PersistentStorageHasher(Blake2bHasher);
 
-pub type PersistentDB = rocksdb::RocksDB;
-
 pub type PersistentState =
     FullAccessState<PersistentDB, PersistentStorageHasher>;
 
@@ -861,6 +872,103 @@ mod tests {
         itertools::assert_equal(iter, expected);
     }
 
+    /// Test reads and prefix iteration of the state at past heights.
+    #[test]
+    fn test_historical_state_prefix_iter() {
+        let db_path =
+            TempDir::new().expect("Unable to create a temporary DB directory");
+        let mut state = PersistentState::open(
+            db_path.path(),
+            None,
+            ChainId::default(),
+            address::testing::nam(),
+            None,
+            is_key_diff_storable,
+        );
+        let prefix = storage::Key::parse("prefix").unwrap();
+        let sub_key = |i: i32| prefix.push(&i).unwrap();
+
+        // Height 1: write 1, 2 and 3
+        state.in_mem_mut().begin_block(BlockHeight(1)).unwrap();
+        state
+            .in_mem_mut()
+            .block
+            .pred_epochs
+            .new_epoch(BlockHeight(1));
+        for i in [1_i32, 2, 3] {
+            state.write(&sub_key(i), i).unwrap();
+        }
+        // The MASP note commitment tree is not stored to diffs
+        let no_diff_key = token::storage_key::masp_commitment_tree_key();
+        state.write(&no_diff_key, 1_i32).unwrap();
+        state.commit_block().unwrap();
+
+        // Height 2: update 2, delete 1 and write 4
+        state.in_mem_mut().begin_block(BlockHeight(2)).unwrap();
+        state.write(&sub_key(2), 20_i32).unwrap();
+        state.delete(&sub_key(1)).unwrap();
+        state.write(&sub_key(4), 4_i32).unwrap();
+        state.commit_block().unwrap();
+
+        // Height 3: delete 4 and write 5
+        state.in_mem_mut().begin_block(BlockHeight(3)).unwrap();
+        state.delete(&sub_key(4)).unwrap();
+        state.write(&sub_key(5), 5_i32).unwrap();
+        state.commit_block().unwrap();
+
+        let expected_at_heights = [
+            (1, vec![(1_i32, 1_i32), (2, 2), (3, 3)]),
+            (2, vec![(2, 20), (3, 3), (4, 4)]),
+            (3, vec![(2, 20), (3, 3), (5, 5)]),
+        ];
+        for (height, expected) in expected_at_heights {
+            let height = BlockHeight(height);
+            let historical =
+                state::HistoricalState::new(state.read_only(), height);
+            assert_eq!(historical.get_block_height().unwrap(), height);
+
+            for i in 1..=5 {
+                let value: Option<i32> = historical.read(&sub_key(i)).unwrap();
+                let expected_value = expected
+                    .iter()
+                    .find(|(key, _)| *key == i)
+                    .map(|(_, value)| *value);
+                assert_eq!(value, expected_value, "key {i} at height {height}");
+            }
+
+            let iter = state::iter_prefix(&historical, &prefix)
+                .unwrap()
+                .map(Result::unwrap);
+            let expected =
+                expected.into_iter().map(|(i, value)| (sub_key(i), value));
+            itertools::assert_equal(iter, expected);
+
+            // The read log records the iteration of a prefix, whose
+            // completeness cannot be proven
+            let read_log = state::ReadLog::default();
+            let logged = state::HistoricalState::new(state.read_only(), height)
+                .with_read_log(Some(&read_log));
+            let _: Option<i32> = logged.read(&sub_key(2)).unwrap();
+            assert!(!read_log.has_prefix_iter());
+            let _ = state::iter_prefix::<i32>(&logged, &prefix).unwrap();
+            assert!(read_log.has_prefix_iter());
+
+            // The keys that are not stored to diffs can only be read at the
+            // last height
+            let masp_prefix = Key::from(address::MASP.to_db_key());
+            let no_diff_read = historical.read::<i32>(&no_diff_key);
+            let no_diff_iter =
+                state::iter_prefix::<i32>(&historical, &masp_prefix);
+            if height.0 == 3 {
+                assert_eq!(no_diff_read.unwrap(), Some(1));
+                assert!(no_diff_iter.is_ok());
+            } else {
+                assert!(no_diff_read.is_err());
+                assert!(no_diff_iter.is_err());
+            }
+        }
+    }
+
     fn test_key_1() -> Key {
         Key::parse("testing1").unwrap()
     }
@@ -0,0 +1,1425 @@
+//! The persistent storage in redb, a pure-Rust embedded key-value store.
+//!
+//! The DB is a single file in the DB directory. It has a table for each of
+//! the column families of the RocksDB storage, with the same layout of the
+//! keys (see the [`super::rocksdb`] module), so that the DB can be migrated
+//! between the two backends by copying the tables.
+
+use std::collections::btree_map::Entry;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use data_encoding::HEXLOWER;
+use itertools::Either;
+use namada_replay_protection as replay_protection;
+use namada_sdk::arith::checked;
+use namada_sdk::borsh::{BorshDeserialize, BorshSerialize};
+use namada_sdk::eth_bridge::storage::bridge_pool;
+use namada_sdk::eth_bridge::storage::proof::BridgePoolRootProof;
+use namada_sdk::gas::Gas;
+use namada_sdk::migrations::{DBUpdateVisitor, DbUpdateType};
+use namada_sdk::state::merkle_tree::{
+    tree_key_prefix_with_epoch, tree_key_prefix_with_height,
+};
+use namada_sdk::state::{
+    BlockStateRead, BlockStateWrite, DBIter, DBWriteBatch, DbError as Error,
+    DbResult as Result, MerkleTreeStoresRead, PatternIterator, StoreType, DB,
+};
+use namada_sdk::storage::{
+    BlockHeader, BlockHeight, DbColFam, Epoch, Key, KeySeg, BLOCK_CF, DIFFS_CF,
+    REPLAY_PROTECTION_CF, ROLLBACK_CF, STATE_CF, SUBSPACE_CF,
+};
+use namada_sdk::{decode, encode, ethereum_events};
+use redb::backends::InMemoryBackend;
+use redb::{Database, ReadableTable, TableDefinition};
+use regex::Regex;
+
+use super::rocksdb::{
+    old_and_new_diff_key, rollback_replay_protection_key, rollback_state_key,
+    ADDRESS_GEN_KEY_SEGMENT, BLOCK_HEADER_KEY_SEGMENT, BLOCK_HEIGHT_KEY,
+    BLOCK_TIME_KEY_SEGMENT, COMMIT_ONLY_DATA_KEY, CONVERSION_STATE_KEY,
+    EPOCH_KEY_SEGMENT, ETHEREUM_HEIGHT_KEY, ETH_EVENTS_QUEUE_KEY,
+    MERKLE_TREE_ROOT_KEY_SEGMENT, MERKLE_TREE_STORE_KEY_SEGMENT,
+    NEW_DIFF_PREFIX, NEXT_EPOCH_MIN_START_HEIGHT_KEY,
+    NEXT_EPOCH_MIN_START_TIME_KEY, OLD_DIFF_PREFIX, PRED_EPOCHS_KEY_SEGMENT,
+    PRED_KEY_PREFIX, PRUNED_HEIGHT_KEY, RESULTS_KEY_PREFIX,
+    UPDATE_EPOCH_BLOCKS_DELAY_KEY,
+};
+
+/// The name of the redb file in the DB directory
+pub const REDB_FILE_NAME: &str = "namada.redb";
+
+/// redb handle
+pub struct RedbDB {
+    /// Handle to the db
+    inner: Database,
+    /// The DB directory
+    path: PathBuf,
+}
+
+/// DB Handle for batch writes. The writes are applied in order in a single
+/// write transaction.
+#[derive(Debug, Default)]
+pub struct RedbWriteBatch(Vec<BatchOp>);
+
+#[derive(Debug)]
+enum BatchOp {
+    Put {
+        cf: &'static str,
+        key: String,
+        value: Vec<u8>,
+    },
+    Delete {
+        cf: &'static str,
+        key: String,
+    },
+    /// Delete the keys from `from` (inclusive) to `to` (exclusive)
+    DeleteRange {
+        cf: &'static str,
+        from: String,
+        to: String,
+    },
+}
+
+impl BatchOp {
+    fn cf(&self) -> &'static str {
+        match self {
+            BatchOp::Put { cf, .. }
+            | BatchOp::Delete { cf, .. }
+            | BatchOp::DeleteRange { cf, .. } => cf,
+        }
+    }
+}
+
+impl RedbWriteBatch {
+    fn put(
+        &mut self,
+        cf: &'static str,
+        key: impl Into<String>,
+        value: impl Into<Vec<u8>>,
+    ) {
+        self.0.push(BatchOp::Put {
+            cf,
+            key: key.into(),
+            value: value.into(),
+        })
+    }
+
+    fn delete(&mut self, cf: &'static str, key: impl Into<String>) {
+        self.0.push(BatchOp::Delete {
+            cf,
+            key: key.into(),
+        })
+    }
+
+    fn delete_range(
+        &mut self,
+        cf: &'static str,
+        from: impl Into<String>,
+        to: impl Into<String>,
+    ) {
+        self.0.push(BatchOp::DeleteRange {
+            cf,
+            from: from.into(),
+            to: to.into(),
+        })
+    }
+}
+
+impl DBWriteBatch for RedbWriteBatch {}
+
+impl fmt::Debug for RedbDB {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RedbDB")
+            .field("path", &self.path)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Open redb for the DB. A read-only DB must already exist. Note that redb
+/// locks the file, so unlike RocksDB, a DB used by a running node cannot be
+/// opened, even read-only.
+pub fn open(path: impl AsRef<Path>, read_only: bool) -> Result<RedbDB> {
+    let path = path.as_ref().to_owned();
+    let file = path.join(REDB_FILE_NAME);
+    let inner = if read_only {
+        Database::open(&file).map_err(db_error)?
+    } else {
+        std::fs::create_dir_all(&path).map_err(db_error)?;
+        let db = Database::create(&file).map_err(db_error)?;
+        // The tables can't be opened in read transactions before they exist
+        let txn = db.begin_write().map_err(db_error)?;
+        for cf in DbColFam::all() {
+            txn.open_table(table(cf)).map_err(db_error)?;
+        }
+        txn.commit().map_err(db_error)?;
+        db
+    };
+    Ok(RedbDB { inner, path })
+}
+
+/// Check if the given directory contains a redb DB
+pub fn exists(path: impl AsRef<Path>) -> bool {
+    path.as_ref().join(REDB_FILE_NAME).is_file()
+}
+
+fn table(cf: &str) -> TableDefinition<'_, &'static str, &'static [u8]> {
+    TableDefinition::new(cf)
+}
+
+fn db_error(err: impl fmt::Display) -> Error {
+    Error::DBError(err.to_string())
+}
+
+/// Get the static name of a column family, as the batch writes refer to them
+fn cf_name(cf: &DbColFam) -> &'static str {
+    match cf {
+        DbColFam::SUBSPACE => SUBSPACE_CF,
+        DbColFam::BLOCK => BLOCK_CF,
+        DbColFam::STATE => STATE_CF,
+        DbColFam::DIFFS => DIFFS_CF,
+        DbColFam::ROLLBACK => ROLLBACK_CF,
+        DbColFam::REPLAYPROT => REPLAY_PROTECTION_CF,
+    }
+}
+
+impl RedbDB {
+    fn read_value<T>(&self, cf: &str, key: impl AsRef<str>) -> Result<Option<T>>
+    where
+        T: BorshDeserialize,
+    {
+        self.read_value_bytes(cf, key)?
+            .map(|bytes| decode(bytes).map_err(Error::CodingError))
+            .transpose()
+    }
+
+    fn read_value_bytes(
+        &self,
+        cf: &str,
+        key: impl AsRef<str>,
+    ) -> Result<Option<Vec<u8>>> {
+        let txn = self.inner.begin_read().map_err(db_error)?;
+        let table = txn.open_table(table(cf)).map_err(db_error)?;
+        let value = table.get(key.as_ref()).map_err(db_error)?;
+        Ok(value.map(|value| value.value().to_vec()))
+    }
+
+    /// Write a state value, keeping the value it replaces as the predecessor
+    /// and in the rollback data of the given height
+    fn add_state_value_to_batch<T>(
+        &self,
+        cf: &'static str,
+        key: &str,
+        value: &T,
+        height: BlockHeight,
+        batch: &mut RedbWriteBatch,
+    ) -> Result<()>
+    where
+        T: BorshSerialize,
+    {
+        if let Some(current_value) = self.read_value_bytes(cf, key)? {
+            batch.put(
+                ROLLBACK_CF,
+                rollback_state_key(height, key),
+                current_value.clone(),
+            );
+            batch.put(cf, format!("{PRED_KEY_PREFIX}/{key}"), current_value);
+        }
+        batch.put(cf, key, encode(value));
+        Ok(())
+    }
+
+    /// Persist the diff of an account subspace key-val under the height where
+    /// it was changed in a batch write.
+    fn batch_write_subspace_diff(
+        &self,
+        batch: &mut RedbWriteBatch,
+        height: BlockHeight,
+        key: &Key,
+        old_value: Option<&[u8]>,
+        new_value: Option<&[u8]>,
+        persist_diffs: bool,
+    ) -> Result<()> {
+        let cf = if persist_diffs { DIFFS_CF } else { ROLLBACK_CF };
+        let (old_val_key, new_val_key) = old_and_new_diff_key(key, height)?;
+
+        if let Some(old_value) = old_value {
+            batch.put(cf, old_val_key, old_value);
+        }
+
+        if let Some(new_value) = new_value {
+            batch.put(cf, new_val_key, new_value);
+        }
+        Ok(())
+    }
+
+    /// Create an iterator over key-vals in the given table matching the given
+    /// prefix(es). If any, the `stripped_prefix` is matched first and will be
+    /// removed from the matched keys. If any, the second `prefix` is matched
+    /// against the stripped keys and remains in the matched keys.
+    fn iter_table(
+        &self,
+        cf: &str,
+        stripped_prefix: Option<&Key>,
+        prefix: Option<&Key>,
+    ) -> RedbPrefixIterator {
+        let stripped_prefix = match stripped_prefix {
+            Some(p) if !p.is_empty() => format!("{p}/"),
+            _ => "".to_owned(),
+        };
+        let prefix = match prefix {
+            Some(p) if !p.is_empty() => {
+                format!("{stripped_prefix}{p}/")
+            }
+            _ => stripped_prefix.clone(),
+        };
+        self.iter_raw_prefix(cf, prefix, stripped_prefix)
+    }
+
+    fn iter_raw_prefix(
+        &self,
+        cf: &str,
+        prefix: String,
+        stripped_prefix: String,
+    ) -> RedbPrefixIterator {
+        let txn = self
+            .inner
+            .begin_read()
+            .expect("Failed to begin a read transaction");
+        let table = txn.open_table(table(cf)).expect("{cf} table should exist");
+        let iter = table
+            .range(prefix.as_str()..)
+            .expect("Prefix iterator shouldn't fail");
+        RedbPrefixIterator {
+            iter,
+            prefix,
+            stripped_prefix,
+        }
+    }
+
+    fn iter_diffs_prefix(
+        &self,
+        cf: &str,
+        height: BlockHeight,
+        prefix: Option<&Key>,
+        is_old: bool,
+    ) -> RedbPrefixIterator {
+        let kind = if is_old {
+            OLD_DIFF_PREFIX
+        } else {
+            NEW_DIFF_PREFIX
+        };
+        let stripped_prefix = Some(
+            Key::from(height.to_db_key())
+                .push(&kind.to_string())
+                .unwrap(),
+        );
+        // get keys without the `stripped_prefix`
+        self.iter_table(cf, stripped_prefix.as_ref(), prefix)
+    }
+
+    /// Iterate over the entries of a column family, with their full keys,
+    /// optionally only the ones under a key prefix
+    pub fn iter_column_family(
+        &self,
+        cf: &DbColFam,
+        prefix: Option<&Key>,
+    ) -> RedbPrefixIterator {
+        let prefix = match prefix {
+            Some(p) if !p.is_empty() => format!("{p}/"),
+            _ => String::new(),
+        };
+        self.iter_raw_prefix(cf.to_str(), prefix, String::new())
+    }
+
+    /// Write an entry of a column family with its full key in a batch
+    pub fn add_raw_to_batch(
+        &self,
+        batch: &mut RedbWriteBatch,
+        cf: &DbColFam,
+        key: &str,
+        value: &[u8],
+    ) {
+        batch.put(cf_name(cf), key, value);
+    }
+
+    /// Delete an entry of a column family with its full key in a batch
+    pub fn delete_raw_from_batch(
+        &self,
+        batch: &mut RedbWriteBatch,
+        cf: &DbColFam,
+        key: &str,
+    ) {
+        batch.delete(cf_name(cf), key);
+    }
+
+    /// Read an entry of a column family with its full key
+    pub fn read_column_family(
+        &self,
+        cf: &DbColFam,
+        key: &str,
+    ) -> Result<Option<Vec<u8>>> {
+        self.read_value_bytes(cf.to_str(), key)
+    }
+
+    /// Dump last known block
+    pub fn dump_block(
+        &self,
+        out_file_path: std::path::PathBuf,
+        historic: bool,
+        height: Option<BlockHeight>,
+    ) {
+        // Find the last block height
+        let last_height: BlockHeight = self
+            .read_value(STATE_CF, BLOCK_HEIGHT_KEY)
+            .expect("Unable to read DB")
+            .expect("No block height found");
+
+        let height = height.unwrap_or(last_height);
+
+        let full_path = out_file_path
+            .with_file_name(format!(
+                "{}_{height}",
+                out_file_path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| "dump_db".to_string())
+            ))
+            .with_extension("toml");
+
+        let file = File::options()
+            .append(true)
+            .create_new(true)
+            .open(&full_path)
+            .expect("Cannot open the output file");
+        let mut buf = BufWriter::new(file);
+
+        println!("Will write to {} ...", full_path.to_string_lossy());
+
+        let mut dump = |iter: RedbPrefixIterator| {
+            for (key, raw_val, _gas) in iter {
+                let val = HEXLOWER.encode(&raw_val);
+                let bytes = format!("\"{key}\" = \"{val}\"\n");
+                buf.write_all(bytes.as_bytes())
+                    .expect("Unable to write to buffer");
+            }
+        };
+
+        if historic {
+            // Dump the keys prepended with the selected block height (includes
+            // subspace diff keys)
+            let prefix = format!("{}/", height.raw());
+            dump(self.iter_raw_prefix(DIFFS_CF, prefix.clone(), String::new()));
+            dump(self.iter_raw_prefix(BLOCK_CF, prefix, String::new()));
+        }
+
+        // subspace
+        if height != last_height {
+            // Restoring subspace at specified height
+            for (key, _value, _gas) in self.iter_prefix(None) {
+                let value = self
+                    .read_subspace_val_with_height(
+                        &Key::from(key.to_db_key()),
+                        height,
+                        last_height,
+                    )
+                    .expect("Unable to find subspace key");
+                if let Some(value) = value {
+                    let val = HEXLOWER.encode(&value);
+                    let bytes = format!("\"{key}\" = \"{val}\"\n");
+                    buf.write_all(bytes.as_bytes())
+                        .expect("Unable to write to buffer");
+                }
+            }
+        } else {
+            // Just dump the current subspace
+            dump(self.iter_column_family(&DbColFam::SUBSPACE));
+        }
+
+        // replay protection
+        // Dump of replay protection keys is possible only at the last height
+        if height == last_height {
+            dump(self.iter_column_family(&DbColFam::REPLAYPROT));
+        }
+
+        buf.flush().expect("Unable to write to output file");
+        println!("Done writing to {}", full_path.to_string_lossy());
+    }
+
+    /// Read diffs of non-persisted key-vals that are only kept for rollback of
+    /// the block heights in the rollback window.
+    #[cfg(test)]
+    pub fn read_rollback_val(
+        &self,
+        key: &Key,
+        height: BlockHeight,
+        is_old: bool,
+    ) -> Result<Option<Vec<u8>>> {
+        let (old_key, new_key) = old_and_new_diff_key(key, height)?;
+        let key = if is_old { old_key } else { new_key };
+        self.read_value_bytes(ROLLBACK_CF, key)
+    }
+
+    /// Read an entry of a column family with its full key
+    #[cfg(test)]
+    pub fn read_raw(&self, cf: &str, key: &str) -> Option<Vec<u8>> {
+        self.read_value_bytes(cf, key).unwrap()
+    }
+}
+
+impl DB for RedbDB {
+    type Cache = ();
+    type Migrator = DbUpdateType;
+    type RestoreSource<'a> = &'a Path;
+    type WriteBatch = RedbWriteBatch;
+
+    fn open(
+        db_path: impl AsRef<std::path::Path>,
+        _cache: Option<&Self::Cache>,
+    ) -> Self {
+        open(db_path, false).expect("cannot open the DB")
+    }
+
+    /// Replace the DB file with the one in the `restored_db` directory
+    fn restore_from(&mut self, restored_db: &Path) -> Result<()> {
+        // Close the current DB file by swapping it for an in-memory DB
+        let in_memory = Database::builder()
+            .create_with_backend(InMemoryBackend::new())
+            .map_err(db_error)?;
+        drop(std::mem::replace(&mut self.inner, in_memory));
+
+        std::fs::rename(
+            restored_db.join(REDB_FILE_NAME),
+            self.path.join(REDB_FILE_NAME),
+        )
+        .map_err(db_error)?;
+        std::fs::remove_dir_all(restored_db).map_err(db_error)?;
+
+        *self = open(&self.path, false)?;
+        Ok(())
+    }
+
+    fn path(&self) -> Option<&Path> {
+        Some(&self.path)
+    }
+
+    fn flush(&self, _wait: bool) -> Result<()> {
+        // Every committed write transaction is already durable
+        Ok(())
+    }
+
+    fn read_last_block(&self) -> Result<Option<BlockStateRead>> {
+        // Block height
+        let height: BlockHeight =
+            match self.read_value(STATE_CF, BLOCK_HEIGHT_KEY)? {
+                Some(h) => h,
+                None => return Ok(None),
+            };
+
+        // Epoch start height and time
+        let next_epoch_min_start_height =
+            match self.read_value(STATE_CF, NEXT_EPOCH_MIN_START_HEIGHT_KEY)? {
+                Some(h) => h,
+                None => return Ok(None),
+            };
+
+        let next_epoch_min_start_time =
+            match self.read_value(STATE_CF, NEXT_EPOCH_MIN_START_TIME_KEY)? {
+                Some(t) => t,
+                None => return Ok(None),
+            };
+
+        let update_epoch_blocks_delay =
+            match self.read_value(STATE_CF, UPDATE_EPOCH_BLOCKS_DELAY_KEY)? {
+                Some(d) => d,
+                None => return Ok(None),
+            };
+
+        let commit_only_data =
+            match self.read_value(STATE_CF, COMMIT_ONLY_DATA_KEY)? {
+                Some(d) => d,
+                None => return Ok(None),
+            };
+
+        let conversion_state =
+            match self.read_value(STATE_CF, CONVERSION_STATE_KEY)? {
+                Some(c) => c,
+                None => return Ok(None),
+            };
+
+        let ethereum_height =
+            match self.read_value(STATE_CF, ETHEREUM_HEIGHT_KEY)? {
+                Some(h) => h,
+                None => return Ok(None),
+            };
+
+        let eth_events_queue =
+            match self.read_value(STATE_CF, ETH_EVENTS_QUEUE_KEY)? {
+                Some(q) => q,
+                None => return Ok(None),
+            };
+
+        // Block results
+        let results_key = format!("{RESULTS_KEY_PREFIX}/{}", height.raw());
+        let results = match self.read_value(BLOCK_CF, results_key)? {
+            Some(r) => r,
+            None => return Ok(None),
+        };
+
+        let prefix = height.raw();
+
+        let time_key = format!("{prefix}/{BLOCK_TIME_KEY_SEGMENT}");
+        let time = match self.read_value(BLOCK_CF, time_key)? {
+            Some(t) => t,
+            None => return Ok(None),
+        };
+
+        let epoch_key = format!("{prefix}/{EPOCH_KEY_SEGMENT}");
+        let epoch = match self.read_value(BLOCK_CF, epoch_key)? {
+            Some(e) => e,
+            None => return Ok(None),
+        };
+
+        let pred_epochs_key = format!("{prefix}/{PRED_EPOCHS_KEY_SEGMENT}");
+        let pred_epochs = match self.read_value(BLOCK_CF, pred_epochs_key)? {
+            Some(e) => e,
+            None => return Ok(None),
+        };
+
+        let address_gen_key = format!("{prefix}/{ADDRESS_GEN_KEY_SEGMENT}");
+        let address_gen = match self.read_value(BLOCK_CF, address_gen_key)? {
+            Some(a) => a,
+            None => return Ok(None),
+        };
+
+        Ok(Some(BlockStateRead {
+            height,
+            time,
+            epoch,
+            pred_epochs,
+            results,
+            conversion_state,
+            next_epoch_min_start_height,
+            next_epoch_min_start_time,
+            update_epoch_blocks_delay,
+            address_gen,
+            ethereum_height,
+            eth_events_queue,
+            commit_only_data,
+        }))
+    }
+
+    fn add_block_to_batch(
+        &self,
+        state: BlockStateWrite<'_>,
+        batch: &mut Self::WriteBatch,
+        is_full_commit: bool,
+    ) -> Result<()> {
+        let BlockStateWrite {
+            merkle_tree_stores,
+            header,
+            height,
+            time,
+            epoch,
+            pred_epochs,
+            next_epoch_min_start_height,
+            next_epoch_min_start_time,
+            update_epoch_blocks_delay,
+            address_gen,
+            results,
+            conversion_state,
+            ethereum_height,
+            eth_events_queue,
+            commit_only_data,
+        }: BlockStateWrite<'_> = state;
+
+        // Epoch start height and time
+        self.add_state_value_to_batch(
+            STATE_CF,
+            NEXT_EPOCH_MIN_START_HEIGHT_KEY,
+            &next_epoch_min_start_height,
+            height,
+            batch,
+        )?;
+        self.add_state_value_to_batch(
+            STATE_CF,
+            NEXT_EPOCH_MIN_START_TIME_KEY,
+            &next_epoch_min_start_time,
+            height,
+            batch,
+        )?;
+        self.add_state_value_to_batch(
+            STATE_CF,
+            UPDATE_EPOCH_BLOCKS_DELAY_KEY,
+            &update_epoch_blocks_delay,
+            height,
+            batch,
+        )?;
+        self.add_state_value_to_batch(
+            STATE_CF,
+            COMMIT_ONLY_DATA_KEY,
+            &commit_only_data,
+            height,
+            batch,
+        )?;
+
+        // Save the conversion state when the epoch is updated
+        if is_full_commit {
+            self.add_state_value_to_batch(
+                STATE_CF,
+                CONVERSION_STATE_KEY,
+                &conversion_state,
+                height,
+                batch,
+            )?;
+        }
+
+        self.add_state_value_to_batch(
+            STATE_CF,
+            ETHEREUM_HEIGHT_KEY,
+            &ethereum_height,
+            height,
+            batch,
+        )?;
+        self.add_state_value_to_batch(
+            STATE_CF,
+            ETH_EVENTS_QUEUE_KEY,
+            &eth_events_queue,
+            height,
+            batch,
+        )?;
+
+        let prefix = height.raw();
+
+        // Merkle tree
+        for st in StoreType::iter() {
+            if st.is_stored_every_block() || is_full_commit {
+                let key_prefix = if st.is_stored_every_block() {
+                    tree_key_prefix_with_height(st, height)
+                } else {
+                    tree_key_prefix_with_epoch(st, epoch)
+                };
+                let root_key =
+                    format!("{key_prefix}/{MERKLE_TREE_ROOT_KEY_SEGMENT}");
+                batch.put(
+                    BLOCK_CF,
+                    root_key,
+                    encode(merkle_tree_stores.root(st)),
+                );
+                let store_key =
+                    format!("{key_prefix}/{MERKLE_TREE_STORE_KEY_SEGMENT}");
+                batch.put(
+                    BLOCK_CF,
+                    store_key,
+                    merkle_tree_stores.store(st).encode(),
+                );
+            }
+        }
+
+        // Block header
+        if let Some(h) = header {
+            let header_key = format!("{prefix}/{BLOCK_HEADER_KEY_SEGMENT}");
+            batch.put(BLOCK_CF, header_key, encode(&h));
+        }
+        // Block time
+        let time_key = format!("{prefix}/{BLOCK_TIME_KEY_SEGMENT}");
+        batch.put(BLOCK_CF, time_key, encode(&time));
+        // Block epoch
+        let epoch_key = format!("{prefix}/{EPOCH_KEY_SEGMENT}");
+        batch.put(BLOCK_CF, epoch_key, encode(&epoch));
+        // Block results
+        let results_key = format!("{RESULTS_KEY_PREFIX}/{}", height.raw());
+        batch.put(BLOCK_CF, results_key, encode(&results));
+        // Predecessor block epochs
+        let pred_epochs_key = format!("{prefix}/{PRED_EPOCHS_KEY_SEGMENT}");
+        batch.put(BLOCK_CF, pred_epochs_key, encode(&pred_epochs));
+        // Address gen
+        let address_gen_key = format!("{prefix}/{ADDRESS_GEN_KEY_SEGMENT}");
+        batch.put(BLOCK_CF, address_gen_key, encode(&address_gen));
+
+        // Block height
+        batch.put(STATE_CF, BLOCK_HEIGHT_KEY, encode(&height));
+
+        Ok(())
+    }
+
+    fn read_block_header(
+        &self,
+        height: BlockHeight,
+    ) -> Result<Option<BlockHeader>> {
+        let header_key = format!("{}/{BLOCK_HEADER_KEY_SEGMENT}", height.raw());
+        self.read_value(BLOCK_CF, header_key)
+    }
+
+    fn read_merkle_tree_stores(
+        &self,
+        epoch: Epoch,
+        base_height: BlockHeight,
+        store_type: Option<StoreType>,
+    ) -> Result<Option<MerkleTreeStoresRead>> {
+        let mut merkle_tree_stores = MerkleTreeStoresRead::default();
+        let store_types = store_type
+            .as_ref()
+            .map(|st| Either::Left(std::iter::once(st)))
+            .unwrap_or_else(|| Either::Right(StoreType::iter()));
+        for st in store_types {
+            let key_prefix = if st.is_stored_every_block() {
+                tree_key_prefix_with_height(st, base_height)
+            } else {
+                tree_key_prefix_with_epoch(st, epoch)
+            };
+            let root_key =
+                format!("{key_prefix}/{MERKLE_TREE_ROOT_KEY_SEGMENT}");
+            match self.read_value(BLOCK_CF, root_key)? {
+                Some(root) => merkle_tree_stores.set_root(st, root),
+                None if store_type.is_some() => return Ok(None),
+                _ => continue,
+            }
+
+            let store_key =
+                format!("{key_prefix}/{MERKLE_TREE_STORE_KEY_SEGMENT}");
+            match self.read_value_bytes(BLOCK_CF, store_key)? {
+                Some(bytes) => {
+                    merkle_tree_stores.set_store(st.decode_store(bytes)?)
+                }
+                None if store_type.is_some() => return Ok(None),
+                _ => continue,
+            }
+        }
+        Ok(Some(merkle_tree_stores))
+    }
+
+    fn has_replay_protection_entry(
+        &self,
+        hash: &namada_sdk::hash::Hash,
+    ) -> Result<bool> {
+        for key in [
+            replay_protection::current_key(hash),
+            replay_protection::key(hash),
+        ] {
+            if self
+                .read_value_bytes(REPLAY_PROTECTION_CF, key.to_string())?
+                .is_some()
+            {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    fn read_diffs_val(
+        &self,
+        key: &Key,
+        height: BlockHeight,
+        is_old: bool,
+    ) -> Result<Option<Vec<u8>>> {
+        let (old_key, new_key) = old_and_new_diff_key(key, height)?;
+        let key = if is_old { old_key } else { new_key };
+        self.read_value_bytes(DIFFS_CF, key)
+    }
+
+    fn read_subspace_val(&self, key: &Key) -> Result<Option<Vec<u8>>> {
+        self.read_value_bytes(SUBSPACE_CF, key.to_string())
+    }
+
+    fn read_subspace_val_with_height(
+        &self,
+        key: &Key,
+        height: BlockHeight,
+        last_height: BlockHeight,
+    ) -> Result<Option<Vec<u8>>> {
+        // The diffs needed to find the value are gone if the height is pruned
+        if let Some(pruned_height) = self.read_pruned_height()? {
+            if height <= pruned_height {
+                return Err(Error::Pruned {
+                    height,
+                    oldest: pruned_height.next_height(),
+                });
+            }
+        }
+
+        // Check if the value changed at this height
+        let (old_val_key, new_val_key) = old_and_new_diff_key(key, height)?;
+
+        // If it has a "new" val, it was written at this height
+        if let Some(new_val) = self.read_value_bytes(DIFFS_CF, new_val_key)? {
+            return Ok(Some(new_val));
+        }
+        // If it has an "old" val, it was deleted at this height
+        if self.read_value_bytes(DIFFS_CF, old_val_key)?.is_some() {
+            return Ok(None);
+        }
+
+        // If the value didn't change at the given height, we try to look for it
+        // at successor heights, up to the `last_height`
+        let mut raw_height = checked!(height.0 + 1)?;
+        loop {
+            // Try to find the next diff on this key
+            let (old_val_key, new_val_key) =
+                old_and_new_diff_key(key, BlockHeight(raw_height))?;
+            // If it has an "old" val, it's the one we're looking for
+            if let Some(bytes) = self.read_value_bytes(DIFFS_CF, old_val_key)? {
+                return Ok(Some(bytes));
+            }
+            // Check if the value was created at this height instead, which
+            // would mean that it wasn't present before
+            if self.read_value_bytes(DIFFS_CF, new_val_key)?.is_some() {
+                return Ok(None);
+            }
+
+            if raw_height >= last_height.0 {
+                // Read from latest height
+                return self.read_subspace_val(key);
+            } else {
+                checked!(raw_height += 1)?
+            }
+        }
+    }
+
+    fn write_subspace_val(
+        &mut self,
+        height: BlockHeight,
+        key: &Key,
+        value: impl AsRef<[u8]>,
+        persist_diffs: bool,
+    ) -> Result<i64> {
+        let mut batch = RedbDB::batch();
+        let size_diff = self.batch_write_subspace_val(
+            &mut batch,
+            height,
+            key,
+            value,
+            persist_diffs,
+        )?;
+        self.exec_batch(batch)?;
+        Ok(size_diff)
+    }
+
+    fn delete_subspace_val(
+        &mut self,
+        height: BlockHeight,
+        key: &Key,
+        persist_diffs: bool,
+    ) -> Result<i64> {
+        let mut batch = RedbDB::batch();
+        let prev_len = self.batch_delete_subspace_val(
+            &mut batch,
+            height,
+            key,
+            persist_diffs,
+        )?;
+        self.exec_batch(batch)?;
+        Ok(prev_len)
+    }
+
+    fn batch() -> Self::WriteBatch {
+        RedbWriteBatch::default()
+    }
+
+    fn exec_batch(&self, batch: Self::WriteBatch) -> Result<()> {
+        let txn = self.inner.begin_write().map_err(db_error)?;
+        {
+            let mut tables = BTreeMap::new();
+            for op in batch.0 {
+                let cf = op.cf();
+                let table = match tables.entry(cf) {
+                    Entry::Occupied(entry) => entry.into_mut(),
+                    Entry::Vacant(entry) => entry
+                        .insert(txn.open_table(table(cf)).map_err(db_error)?),
+                };
+                match op {
+                    BatchOp::Put { key, value, .. } => {
+                        table
+                            .insert(key.as_str(), value.as_slice())
+                            .map_err(db_error)?;
+                    }
+                    BatchOp::Delete { key, .. } => {
+                        table.remove(key.as_str()).map_err(db_error)?;
+                    }
+                    BatchOp::DeleteRange { from, to, .. } => {
+                        let keys = table
+                            .range(from.as_str()..to.as_str())
+                            .map_err(db_error)?
+                            .map(|entry| {
+                                entry.map(|(key, _)| key.value().to_owned())
+                            })
+                            .collect::<std::result::Result<Vec<_>, _>>()
+                            .map_err(db_error)?;
+                        for key in keys {
+                            table.remove(key.as_str()).map_err(db_error)?;
+                        }
+                    }
+                }
+            }
+        }
+        txn.commit().map_err(db_error)
+    }
+
+    fn batch_write_subspace_val(
+        &self,
+        batch: &mut Self::WriteBatch,
+        height: BlockHeight,
+        key: &Key,
+        value: impl AsRef<[u8]>,
+        persist_diffs: bool,
+    ) -> Result<i64> {
+        let value = value.as_ref();
+        let size_diff = match self.read_subspace_val(key)? {
+            Some(old_value) => {
+                let len = i64::try_from(value.len())?;
+                let old_len = i64::try_from(old_value.len())?;
+                let size_diff = checked!(len - old_len)?;
+                // Persist the previous value
+                self.batch_write_subspace_diff(
+                    batch,
+                    height,
+                    key,
+                    Some(&old_value),
+                    Some(value),
+                    persist_diffs,
+                )?;
+                size_diff
+            }
+            None => {
+                self.batch_write_subspace_diff(
+                    batch,
+                    height,
+                    key,
+                    None,
+                    Some(value),
+                    persist_diffs,
+                )?;
+                i64::try_from(value.len())?
+            }
+        };
+
+        // Write the new key-val
+        batch.put(SUBSPACE_CF, key.to_string(), value);
+
+        Ok(size_diff)
+    }
+
+    fn batch_delete_subspace_val(
+        &self,
+        batch: &mut Self::WriteBatch,
+        height: BlockHeight,
+        key: &Key,
+        persist_diffs: bool,
+    ) -> Result<i64> {
+        // Check the length of previous value, if any
+        let prev_len = match self.read_subspace_val(key)? {
+            Some(prev_value) => {
+                let prev_len = i64::try_from(prev_value.len())?;
+                // Persist the previous value
+                self.batch_write_subspace_diff(
+                    batch,
+                    height,
+                    key,
+                    Some(&prev_value),
+                    None,
+                    persist_diffs,
+                )?;
+                prev_len
+            }
+            None => 0,
+        };
+
+        // Delete the key-val
+        batch.delete(SUBSPACE_CF, key.to_string());
+
+        Ok(prev_len)
+    }
+
+    fn prune_merkle_tree_store(
+        &mut self,
+        batch: &mut Self::WriteBatch,
+        store_type: &StoreType,
+        pruned_target: Either<BlockHeight, Epoch>,
+    ) -> Result<()> {
+        let key_prefix = match pruned_target {
+            Either::Left(height) => {
+                tree_key_prefix_with_height(store_type, height)
+            }
+            Either::Right(epoch) => {
+                tree_key_prefix_with_epoch(store_type, epoch)
+            }
+        };
+        batch.delete(
+            BLOCK_CF,
+            format!("{key_prefix}/{MERKLE_TREE_ROOT_KEY_SEGMENT}"),
+        );
+        batch.delete(
+            BLOCK_CF,
+            format!("{key_prefix}/{MERKLE_TREE_STORE_KEY_SEGMENT}"),
+        );
+        Ok(())
+    }
+
+    fn read_bridge_pool_signed_nonce(
+        &self,
+        height: BlockHeight,
+        last_height: BlockHeight,
+    ) -> Result<Option<ethereum_events::Uint>> {
+        let nonce_key = bridge_pool::get_signed_root_key();
+        let bytes = if height == BlockHeight(0) || height >= last_height {
+            self.read_subspace_val(&nonce_key)?
+        } else {
+            self.read_subspace_val_with_height(&nonce_key, height, last_height)?
+        };
+        match bytes {
+            Some(bytes) => {
+                let bp_root_proof = BridgePoolRootProof::try_from_slice(&bytes)
+                    .map_err(Error::BorshCodingError)?;
+                Ok(Some(bp_root_proof.data.1))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn write_replay_protection_entry(
+        &mut self,
+        batch: &mut Self::WriteBatch,
+        key: &Key,
+    ) -> Result<()> {
+        batch.put(REPLAY_PROTECTION_CF, key.to_string(), vec![]);
+        Ok(())
+    }
+
+    fn move_current_replay_protection_entries(
+        &mut self,
+        batch: &mut Self::WriteBatch,
+    ) -> Result<()> {
+        let stripped_prefix = Some(replay_protection::current_prefix());
+        // The current entries were added at the last committed height
+        let last_height: Option<BlockHeight> =
+            self.read_value(STATE_CF, BLOCK_HEIGHT_KEY)?;
+        for (ref hash_str, _, _) in self.iter_table(
+            REPLAY_PROTECTION_CF,
+            stripped_prefix.as_ref(),
+            None,
+        ) {
+            let hash = namada_sdk::hash::Hash::from_str(hash_str)
+                .expect("Failed hash conversion");
+            let current_key = replay_protection::current_key(&hash);
+            let key = replay_protection::key(&hash);
+
+            // Delete the current key and move it to the general bucket
+            batch.delete(REPLAY_PROTECTION_CF, current_key.to_string());
+            batch.put(REPLAY_PROTECTION_CF, key.to_string(), vec![]);
+            // Keep the entry in the rollback data of its height
+            if let Some(height) = last_height {
+                batch.put(
+                    ROLLBACK_CF,
+                    rollback_replay_protection_key(height, &hash),
+                    vec![],
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    fn prune_non_persisted_diffs(
+        &mut self,
+        batch: &mut Self::WriteBatch,
+        height: BlockHeight,
+    ) -> Result<()> {
+        for (key_str, _val, _) in self.iter_raw_prefix(
+            ROLLBACK_CF,
+            format!("{}/", height.raw()),
+            String::new(),
+        ) {
+            batch.delete(ROLLBACK_CF, key_str)
+        }
+        Ok(())
+    }
+
+    fn prune_block_history(
+        &mut self,
+        batch: &mut Self::WriteBatch,
+        from: BlockHeight,
+        to: BlockHeight,
+        prune_headers: bool,
+    ) -> Result<()> {
+        let end = BlockHeight(checked!(to.0 + 1)?);
+
+        // Heights are encoded with a fixed length that preserves their order,
+        // so the diffs and results of the whole range can be deleted by ranges
+        batch.delete_range(
+            DIFFS_CF,
+            format!("{}/", from.raw()),
+            format!("{}/", end.raw()),
+        );
+        batch.delete_range(
+            BLOCK_CF,
+            format!("{RESULTS_KEY_PREFIX}/{}", from.raw()),
+            format!("{RESULTS_KEY_PREFIX}/{}", end.raw()),
+        );
+
+        // The other block data is deleted key by key, as the Merkle tree
+        // stores written once per epoch share the prefix of the heights
+        for height in from.0..=to.0 {
+            let height = BlockHeight(height);
+            let prefix = height.raw();
+            // NB: the block headers are kept for the IBC consensus states
+            // unless they are explicitly pruned
+            let header = prune_headers.then_some(BLOCK_HEADER_KEY_SEGMENT);
+            for segment in [
+                BLOCK_TIME_KEY_SEGMENT,
+                EPOCH_KEY_SEGMENT,
+                PRED_EPOCHS_KEY_SEGMENT,
+                ADDRESS_GEN_KEY_SEGMENT,
+            ]
+            .into_iter()
+            .chain(header)
+            {
+                batch.delete(BLOCK_CF, format!("{prefix}/{segment}"));
+            }
+            for st in StoreType::iter().filter(|st| st.is_stored_every_block())
+            {
+                let key_prefix = tree_key_prefix_with_height(st, height);
+                for segment in [
+                    MERKLE_TREE_ROOT_KEY_SEGMENT,
+                    MERKLE_TREE_STORE_KEY_SEGMENT,
+                ] {
+                    batch.delete(BLOCK_CF, format!("{key_prefix}/{segment}"));
+                }
+            }
+        }
+
+        batch.put(STATE_CF, PRUNED_HEIGHT_KEY, encode(&to));
+        Ok(())
+    }
+
+    fn read_pruned_height(&self) -> Result<Option<BlockHeight>> {
+        self.read_value(STATE_CF, PRUNED_HEIGHT_KEY)
+    }
+
+    fn overwrite_entry(
+        &self,
+        batch: &mut Self::WriteBatch,
+        cf: &DbColFam,
+        key: &Key,
+        new_value: impl AsRef<[u8]>,
+    ) -> Result<()> {
+        batch.put(cf_name(cf), key.to_string(), new_value.as_ref());
+        let last_height: BlockHeight = self
+            .read_value(STATE_CF, BLOCK_HEIGHT_KEY)?
+            .ok_or_else(|| {
+                Error::DBError("No block height found".to_string())
+            })?;
+
+        // If the CF is subspace, additionally update the diffs
+        if cf == &DbColFam::SUBSPACE {
+            let (_, diffs_key) = old_and_new_diff_key(key, last_height)?;
+            batch.put(DIFFS_CF, diffs_key, new_value.as_ref());
+        }
+
+        Ok(())
+    }
+
+    fn apply_migration_to_batch(
+        &self,
+        updates: impl IntoIterator<Item = DbUpdateType>,
+    ) -> Result<RedbWriteBatch> {
+        let mut db_visitor = RedbUpdateVisitor::new(self);
+        for change in updates.into_iter() {
+            match change.update(&mut db_visitor) {
+                Ok(status) => {
+                    tracing::info!("{}", status);
+                }
+                Err(e) => {
+                    let error = format!(
+                        "Attempt to write to key/pattern <{}> failed:\n{}.",
+                        change.pattern(),
+                        e
+                    );
+                    tracing::error!(error);
+                    return Err(Error::DBError(error));
+                }
+            }
+        }
+        Ok(db_visitor.take_batch())
+    }
+}
+
+/// A struct that can visit a set of updates,
+/// registering them all in the batch
+pub struct RedbUpdateVisitor<'db> {
+    db: &'db RedbDB,
+    batch: RedbWriteBatch,
+}
+
+impl<'db> RedbUpdateVisitor<'db> {
+    pub fn new(db: &'db RedbDB) -> Self {
+        Self {
+            db,
+            batch: Default::default(),
+        }
+    }
+
+    pub fn take_batch(self) -> RedbWriteBatch {
+        self.batch
+    }
+}
+
+impl<'db> DBUpdateVisitor for RedbUpdateVisitor<'db> {
+    fn read(&self, key: &Key, cf: &DbColFam) -> Option<Vec<u8>> {
+        self.db
+            .read_value_bytes(cf.to_str(), key.to_string())
+            .expect("Failed to get key from storage")
+    }
+
+    fn write(&mut self, key: &Key, cf: &DbColFam, value: impl AsRef<[u8]>) {
+        self.db
+            .overwrite_entry(&mut self.batch, cf, key, value)
+            .expect("Failed to overwrite a key in storage")
+    }
+
+    fn delete(&mut self, key: &Key, cf: &DbColFam) {
+        match cf {
+            DbColFam::SUBSPACE => {
+                let last_height: BlockHeight = self
+                    .db
+                    .read_value(STATE_CF, BLOCK_HEIGHT_KEY)
+                    .unwrap()
+                    .unwrap();
+                self.db
+                    .batch_delete_subspace_val(
+                        &mut self.batch,
+                        last_height,
+                        key,
+                        true,
+                    )
+                    .expect("Failed to delete key from storage");
+            }
+            _ => self.batch.delete(cf_name(cf), key.to_string()),
+        };
+    }
+
+    fn get_pattern(&self, pattern: Regex) -> Vec<(String, Vec<u8>)> {
+        self.db
+            .iter_pattern(None, pattern)
+            .map(|(k, v, _)| (k, v))
+            .collect()
+    }
+}
+
+impl<'iter> DBIter<'iter> for RedbDB {
+    type PatternIter = RedbPatternIterator;
+    type PrefixIter = RedbPrefixIterator;
+
+    fn iter_prefix(&'iter self, prefix: Option<&Key>) -> RedbPrefixIterator {
+        self.iter_table(SUBSPACE_CF, None, prefix)
+    }
+
+    fn iter_pattern(
+        &'iter self,
+        prefix: Option<&Key>,
+        pattern: Regex,
+    ) -> RedbPatternIterator {
+        RedbPatternIterator {
+            inner: PatternIterator {
+                iter: self.iter_table(SUBSPACE_CF, None, prefix),
+                pattern,
+            },
+        }
+    }
+
+    fn iter_results(&'iter self) -> RedbPrefixIterator {
+        self.iter_raw_prefix(
+            BLOCK_CF,
+            format!("{RESULTS_KEY_PREFIX}/"),
+            format!("{RESULTS_KEY_PREFIX}/"),
+        )
+    }
+
+    fn iter_old_diffs(
+        &'iter self,
+        height: BlockHeight,
+        prefix: Option<&'iter Key>,
+    ) -> RedbPrefixIterator {
+        self.iter_diffs_prefix(DIFFS_CF, height, prefix, true)
+    }
+
+    fn iter_new_diffs(
+        &'iter self,
+        height: BlockHeight,
+        prefix: Option<&'iter Key>,
+    ) -> RedbPrefixIterator {
+        self.iter_diffs_prefix(DIFFS_CF, height, prefix, false)
+    }
+
+    fn iter_current_replay_protection(&'iter self) -> RedbPrefixIterator {
+        let prefix = Some(replay_protection::current_prefix());
+        self.iter_table(REPLAY_PROTECTION_CF, None, prefix.as_ref())
+    }
+
+    fn iter_replay_protection(&'iter self) -> RedbPrefixIterator {
+        self.iter_table(REPLAY_PROTECTION_CF, None, None)
+    }
+}
+
+/// An iterator over the entries of a table whose keys start with a prefix.
+/// The read transaction is kept open until the iterator is dropped, so the
+/// entries are read from a consistent state.
+pub struct RedbPrefixIterator {
+    iter: redb::Range<'static, &'static str, &'static [u8]>,
+    /// The prefix of the iterated keys
+    prefix: String,
+    /// The prefix removed from the keys
+    stripped_prefix: String,
+}
+
+impl fmt::Debug for RedbPrefixIterator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RedbPrefixIterator")
+            .field("prefix", &self.prefix)
+            .field("stripped_prefix", &self.stripped_prefix)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Iterator for RedbPrefixIterator {
+    type Item = (String, Vec<u8>, Gas);
+
+    /// Returns the next pair and the gas cost
+    fn next(&mut self) -> Option<(String, Vec<u8>, Gas)> {
+        let (key, val) =
+            self.iter.next()?.expect("Prefix iterator shouldn't fail");
+        // The keys are sorted, so the first one without the prefix ends the
+        // iteration
+        let key = key.value();
+        if !key.starts_with(&self.prefix) {
+            return None;
+        }
+        // The stripped prefix is a prefix of the matched prefix
+        let key = key[self.stripped_prefix.len()..].to_owned();
+        let val = val.value().to_vec();
+        let gas = key.len().checked_add(val.len())?;
+        Some((key, val, (gas as u64).into()))
+    }
+}
+
+#[derive(Debug)]
+pub struct RedbPatternIterator {
+    inner: PatternIterator<RedbPrefixIterator>,
+}
+
+impl Iterator for RedbPatternIterator {
+    type Item = (String, Vec<u8>, Gas);
+
+    /// Returns the next pair and the gas cost
+    fn next(&mut self) -> Option<(String, Vec<u8>, Gas)> {
+        loop {
+            let next_result = self.inner.iter.next()?;
+            if self.inner.pattern.is_match(&next_result.0) {
+                return Some(next_result);
+            }
+        }
+    }
+}
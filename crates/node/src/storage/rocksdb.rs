@@ -18,6 +18,9 @@
 //!     - `next_epoch_min_start_time`
 //!     - `commit_only_data_commitment`
 //!     - `update_epoch_blocks_delay`
+//!     - `conversion_state`
+//!     - `ethereum_height`
+//!     - `eth_events_queue`
 //!   - `conversion_state`: MASP conversion state
 //! - `subspace`: accounts sub-spaces
 //!   - `{address}/{dyn}`: any byte data associated with accounts
@@ -25,11 +28,15 @@
 //!   == true`
 //!   - `{height}/new/{dyn}`: value set in block height `h`
 //!   - `{height}/old/{dyn}`: value from predecessor block height
-//! - `rollback`: diffs in account subspaces' key-vals for keys modified with
-//!   `persist_diff == false` which are only kept for 1 block to support
-//!   rollback
-//!   - `{height}/new/{dyn}`: value set in block height `h`
-//!   - `{height}/old/{dyn}`: value from predecessor block height
+//! - `rollback`: data which is only kept for the blocks in the rollback window
+//!   to support rollback
+//!   - `{height}/new/{dyn}`: value set in block height `h` for keys modified
+//!     with `persist_diff == false`
+//!   - `{height}/old/{dyn}`: value from predecessor block height for keys
+//!     modified with `persist_diff == false`
+//!   - `{height}/state/{key}`: value of a `state` key replaced in block height
+//!     `h`
+//!   - `{height}/replay_protection/{hash}`: a hash included in block height `h`
 //! - `block`: block state
 //!   - `results/{h}`: block results at height `h`
 //!   - `h`: for each block at height `h`:
@@ -45,18 +52,18 @@
 //!     - `{hash}`: a hash included in previous blocks
 
 use std::fs::File;
-use std::io::{BufWriter, Read, Seek, Write};
+use std::io::{BufWriter, Write};
 use std::mem::ManuallyDrop;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
-use std::sync::Mutex;
 
 use data_encoding::HEXLOWER;
 use itertools::Either;
 use namada_replay_protection as replay_protection;
+use namada_sdk::address::Address;
 use namada_sdk::arith::checked;
 use namada_sdk::borsh::{BorshDeserialize, BorshSerialize, BorshSerializeExt};
-use namada_sdk::collections::HashSet;
+use namada_sdk::chain::ChainId;
 use namada_sdk::eth_bridge::storage::bridge_pool;
 use namada_sdk::eth_bridge::storage::proof::BridgePoolRootProof;
 use namada_sdk::gas::Gas;
@@ -67,8 +74,8 @@ use namada_sdk::state::merkle_tree::{
 };
 use namada_sdk::state::{
     BlockStateRead, BlockStateWrite, DBIter, DBWriteBatch, DbError as Error,
-    DbResult as Result, MerkleTreeStoresRead, PatternIterator, PrefixIterator,
-    StoreType, DB,
+    DbResult as Result, FullAccessState, MerkleTreeStoresRead, PatternIterator,
+    PrefixIterator, Sha256Hasher, StoreType, DB,
 };
 use namada_sdk::storage::{
     BlockHeader, BlockHeight, DbColFam, Epoch, Key, KeySeg, BLOCK_CF, DIFFS_CF,
@@ -84,7 +91,9 @@ use rocksdb::{
 };
 
 use crate::config::utils::num_of_threads;
+use crate::shell::is_key_diff_storable;
 use crate::storage;
+use crate::storage::state_sync;
 
 // TODO the DB schema will probably need some kind of versioning
 
@@ -92,27 +101,33 @@ use crate::storage;
 const ENV_VAR_ROCKSDB_COMPACTION_THREADS: &str =
     "NAMADA_ROCKSDB_COMPACTION_THREADS";
 
-const BLOCK_HEIGHT_KEY: &str = "height";
-const NEXT_EPOCH_MIN_START_HEIGHT_KEY: &str = "next_epoch_min_start_height";
-const NEXT_EPOCH_MIN_START_TIME_KEY: &str = "next_epoch_min_start_time";
-const UPDATE_EPOCH_BLOCKS_DELAY_KEY: &str = "update_epoch_blocks_delay";
-const COMMIT_ONLY_DATA_KEY: &str = "commit_only_data_commitment";
-const CONVERSION_STATE_KEY: &str = "conversion_state";
-const ETHEREUM_HEIGHT_KEY: &str = "ethereum_height";
-const ETH_EVENTS_QUEUE_KEY: &str = "eth_events_queue";
-const RESULTS_KEY_PREFIX: &str = "results";
-const PRED_KEY_PREFIX: &str = "pred";
-
-const MERKLE_TREE_ROOT_KEY_SEGMENT: &str = "root";
-const MERKLE_TREE_STORE_KEY_SEGMENT: &str = "store";
-const BLOCK_HEADER_KEY_SEGMENT: &str = "header";
-const BLOCK_TIME_KEY_SEGMENT: &str = "time";
-const EPOCH_KEY_SEGMENT: &str = "epoch";
-const PRED_EPOCHS_KEY_SEGMENT: &str = "pred_epochs";
-const ADDRESS_GEN_KEY_SEGMENT: &str = "address_gen";
-
-const OLD_DIFF_PREFIX: &str = "old";
-const NEW_DIFF_PREFIX: &str = "new";
+pub(super) const BLOCK_HEIGHT_KEY: &str = "height";
+pub(super) const NEXT_EPOCH_MIN_START_HEIGHT_KEY: &str =
+    "next_epoch_min_start_height";
+pub(super) const NEXT_EPOCH_MIN_START_TIME_KEY: &str =
+    "next_epoch_min_start_time";
+pub(super) const UPDATE_EPOCH_BLOCKS_DELAY_KEY: &str =
+    "update_epoch_blocks_delay";
+pub(super) const COMMIT_ONLY_DATA_KEY: &str = "commit_only_data_commitment";
+pub(super) const CONVERSION_STATE_KEY: &str = "conversion_state";
+pub(super) const ETHEREUM_HEIGHT_KEY: &str = "ethereum_height";
+pub(super) const ETH_EVENTS_QUEUE_KEY: &str = "eth_events_queue";
+pub(super) const PRUNED_HEIGHT_KEY: &str = "pruned_height";
+pub(super) const RESULTS_KEY_PREFIX: &str = "results";
+pub(super) const PRED_KEY_PREFIX: &str = "pred";
+
+pub(super) const MERKLE_TREE_ROOT_KEY_SEGMENT: &str = "root";
+pub(super) const MERKLE_TREE_STORE_KEY_SEGMENT: &str = "store";
+pub(super) const BLOCK_HEADER_KEY_SEGMENT: &str = "header";
+pub(super) const BLOCK_TIME_KEY_SEGMENT: &str = "time";
+pub(super) const EPOCH_KEY_SEGMENT: &str = "epoch";
+pub(super) const PRED_EPOCHS_KEY_SEGMENT: &str = "pred_epochs";
+pub(super) const ADDRESS_GEN_KEY_SEGMENT: &str = "address_gen";
+
+pub(super) const OLD_DIFF_PREFIX: &str = "old";
+pub(super) const NEW_DIFF_PREFIX: &str = "new";
+pub(super) const ROLLBACK_STATE_PREFIX: &str = "state";
+pub(super) const ROLLBACK_REPLAY_PROTECTION_PREFIX: &str = "replay_protection";
 
 // 10 MB
 const MAX_STATE_SYNC_CHUNK_SIZE: usize = 10_000_000;
@@ -296,11 +311,14 @@ impl RocksDB {
             .map_err(|e| Error::DBError(e.into_string()))
     }
 
+    /// Write a state value, keeping the value it replaces as the predecessor
+    /// and in the rollback data of the given height
     fn add_state_value_to_batch<T>(
         &self,
         cf: &ColumnFamily,
         key: impl AsRef<str>,
         value: &T,
+        height: BlockHeight,
         batch: &mut RocksDBWriteBatch,
     ) -> Result<()>
     where
@@ -311,6 +329,12 @@ impl RocksDB {
             .get_cf(cf, key.as_ref())
             .map_err(|e| Error::DBError(e.into_string()))?
         {
+            let rollback_cf = self.get_column_family(ROLLBACK_CF)?;
+            batch.0.put_cf(
+                rollback_cf,
+                rollback_state_key(height, key.as_ref()),
+                &current_value,
+            );
             batch.0.put_cf(
                 cf,
                 format!("{PRED_KEY_PREFIX}/{}", key.as_ref()),
@@ -513,6 +537,18 @@ impl RocksDB {
         buf.flush().expect("Unable to write to output file");
     }
 
+    /// Mark the block history up to the given height as pruned, e.g. when
+    /// the state was restored from a snapshot that has no history
+    pub fn write_pruned_height(
+        &self,
+        batch: &mut RocksDBWriteBatch,
+        height: BlockHeight,
+    ) -> Result<()> {
+        let state_cf = self.get_column_family(STATE_CF)?;
+        self.add_value_to_batch(state_cf, PRUNED_HEIGHT_KEY, &height, batch);
+        Ok(())
+    }
+
     /// Create a checkpoint of the state in RocksDB at block height
     /// `block_height`.
     pub fn checkpoint(
@@ -536,200 +572,52 @@ impl RocksDB {
         Ok(DbSnapshot(snapshot_path))
     }
 
-    /// Rollback to previous block. Given the inner working of tendermint
-    /// rollback and of the key structure of Namada, calling rollback more than
-    /// once without restarting the chain results in a single rollback.
-    pub fn rollback(
-        &mut self,
-        tendermint_block_height: BlockHeight,
-    ) -> Result<()> {
-        let last_block = self.read_last_block()?.ok_or(Error::DBError(
-            "Missing last block in storage".to_string(),
-        ))?;
-        tracing::info!(
-            "Namada last block height: {}, Tendermint last block height: {}",
-            last_block.height,
-            tendermint_block_height
-        );
-
-        // If the block height to which tendermint rolled back matches the
-        // Namada height, there's no need to rollback
-        if tendermint_block_height == last_block.height {
-            tracing::info!(
-                "Namada height already matches the rollback Tendermint \
-                 height, no need to rollback."
-            );
-            return Ok(());
-        }
-
-        let mut batch = RocksDB::batch();
-        let previous_height =
-            last_block.height.prev_height().expect("Must have a pred");
-
-        let state_cf = self.get_column_family(STATE_CF)?;
-        // Revert the non-height-prepended metadata storage keys which get
-        // updated with every block. Because of the way we save these
-        // three keys in storage we can only perform one rollback before
-        // restarting the chain
-        tracing::info!("Reverting non-height-prepended metadata keys");
-        batch
-            .0
-            .put_cf(state_cf, BLOCK_HEIGHT_KEY, encode(&previous_height));
-        for metadata_key in [
-            NEXT_EPOCH_MIN_START_HEIGHT_KEY,
-            NEXT_EPOCH_MIN_START_TIME_KEY,
-            COMMIT_ONLY_DATA_KEY,
-            UPDATE_EPOCH_BLOCKS_DELAY_KEY,
-        ] {
-            let previous_key = format!("{PRED_KEY_PREFIX}/{metadata_key}");
-            let previous_value = self
-                .read_value_bytes(state_cf, &previous_key)?
-                .ok_or(Error::UnknownKey { key: previous_key })?;
-
-            self.add_value_bytes_to_batch(
-                state_cf,
-                metadata_key,
-                previous_value,
-                &mut batch,
-            );
-            // NOTE: we cannot restore the "pred/" keys themselves since we
-            // don't have their predecessors in storage, but there's no need to
-            // since we cannot do more than one rollback anyway because of
-            // CometBFT.
-        }
-
-        // Revert conversion state if the epoch had been changed
-        if last_block.pred_epochs.get_epoch(previous_height)
-            != Some(last_block.epoch)
-        {
-            let previous_key =
-                format!("{PRED_KEY_PREFIX}/{CONVERSION_STATE_KEY}");
-            let previous_value = self
-                .read_value_bytes(state_cf, &previous_key)?
-                .ok_or(Error::UnknownKey { key: previous_key })?;
-            self.add_value_bytes_to_batch(
-                state_cf,
-                CONVERSION_STATE_KEY,
-                previous_value,
-                &mut batch,
-            );
-        }
-
-        // Delete block results for the last block
-        let block_cf = self.get_column_family(BLOCK_CF)?;
-        tracing::info!("Removing last block results");
-        batch.0.delete_cf(
-            block_cf,
-            format!("{RESULTS_KEY_PREFIX}/{}", last_block.height),
-        );
-
-        // Restore the state of replay protection to the last block
-        let reprot_cf = self.get_column_family(REPLAY_PROTECTION_CF)?;
-        tracing::info!("Restoring replay protection state");
-        // Remove the "current" tx hashes
-        for (ref current_key, _, _) in self.iter_current_replay_protection() {
-            batch.0.delete_cf(reprot_cf, current_key);
-        }
-
-        // Execute next step in parallel
-        let batch = Mutex::new(batch);
-
-        tracing::info!("Restoring previous height subspace diffs");
-        self.iter_prefix(None).par_bridge().try_for_each(
-            |(key, _value, _gas)| -> Result<()> {
-                // Restore previous height diff if present, otherwise delete the
-                // subspace key
-                let subspace_cf = self.get_column_family(SUBSPACE_CF)?;
-                match self.read_subspace_val_with_height(
-                    &Key::from(key.to_db_key()),
-                    previous_height,
-                    last_block.height,
-                )? {
-                    Some(previous_value) => batch.lock().unwrap().0.put_cf(
-                        subspace_cf,
-                        &key,
-                        previous_value,
-                    ),
-                    None => {
-                        batch.lock().unwrap().0.delete_cf(subspace_cf, &key)
-                    }
-                }
-
-                Ok(())
-            },
-        )?;
-
-        let mut batch = batch.into_inner().unwrap();
-
-        let subspace_cf = self.get_column_family(SUBSPACE_CF)?;
-        let diffs_cf = self.get_column_family(DIFFS_CF)?;
-        // Look for diffs in this block to find what has been deleted
-        let diff_new_key_prefix = Key {
-            segments: vec![
-                last_block.height.to_db_key(),
-                NEW_DIFF_PREFIX.to_string().to_db_key(),
-            ],
-        };
-        for (key_str, val, _) in
-            iter_diffs_prefix(self, diffs_cf, last_block.height, None, true)
-        {
-            let key = Key::parse(&key_str).unwrap();
-            let diff_new_key = diff_new_key_prefix.join(&key);
-            if self.read_subspace_val(&diff_new_key)?.is_none() {
-                // If there is no new value, it has been deleted in this
-                // block and we have to restore it
-                batch.0.put_cf(subspace_cf, key_str, val)
-            }
-        }
+    /// Iterate over the entries of a column family, with their full keys,
+    /// optionally only the ones under a key prefix
+    pub fn iter_column_family(
+        &self,
+        cf: &DbColFam,
+        prefix: Option<&Key>,
+    ) -> PersistentPrefixIterator<'_> {
+        let cf = self
+            .get_column_family(cf.to_str())
+            .expect("Column family should exist");
+        iter_prefix(self, cf, None, prefix)
+    }
 
-        // Look for non-persisted diffs for rollback
-        let rollback_cf = self.get_column_family(ROLLBACK_CF)?;
-        // Iterate the old keys first and keep a set of keys that have old val
-        let mut keys_with_old_value = HashSet::<String>::new();
-        for (key_str, val, _) in
-            iter_diffs_prefix(self, rollback_cf, last_block.height, None, true)
-        {
-            // If there is no new value, it has been deleted in this
-            // block and we have to restore it
-            keys_with_old_value.insert(key_str.clone());
-            batch.0.put_cf(subspace_cf, key_str, val)
-        }
-        // Then the new keys
-        for (key_str, _val, _) in
-            iter_diffs_prefix(self, rollback_cf, last_block.height, None, false)
-        {
-            if !keys_with_old_value.contains(&key_str) {
-                // If there was no old value it means that the key was newly
-                // written in the last block and we have to delete it
-                batch.0.delete_cf(subspace_cf, key_str)
-            }
-        }
+    /// Write an entry of a column family with its full key in a batch
+    pub fn add_raw_to_batch(
+        &self,
+        batch: &mut RocksDBWriteBatch,
+        cf: &DbColFam,
+        key: &str,
+        value: &[u8],
+    ) -> Result<()> {
+        let cf = self.get_column_family(cf.to_str())?;
+        batch.0.put_cf(cf, key, value);
+        Ok(())
+    }
 
-        tracing::info!("Deleting keys prepended with the last height");
-        let prefix = last_block.height.to_string();
-        let mut delete_keys = |cf: &ColumnFamily| {
-            let read_opts = make_iter_read_opts(Some(prefix.clone()));
-            let iter = self.inner.iterator_cf_opt(
-                cf,
-                read_opts,
-                IteratorMode::From(prefix.as_bytes(), Direction::Forward),
-            );
-            for (key, _value, _gas) in PersistentPrefixIterator(
-                // Empty prefix string to prevent stripping
-                PrefixIterator::new(iter, String::default()),
-            ) {
-                batch.0.delete_cf(cf, key);
-            }
-        };
-        // Delete any height-prepended key in subspace diffs
-        let diffs_cf = self.get_column_family(DIFFS_CF)?;
-        delete_keys(diffs_cf);
-        // Delete any height-prepended key in the block
-        delete_keys(block_cf);
+    /// Delete an entry of a column family with its full key in a batch
+    pub fn delete_raw_from_batch(
+        &self,
+        batch: &mut RocksDBWriteBatch,
+        cf: &DbColFam,
+        key: &str,
+    ) -> Result<()> {
+        let cf = self.get_column_family(cf.to_str())?;
+        batch.0.delete_cf(cf, key);
+        Ok(())
+    }
 
-        // Write the batch and persist changes to disk
-        tracing::info!("Flushing restored state to disk");
-        self.exec_batch(batch)
+    /// Read an entry of a column family with its full key
+    pub fn read_column_family(
+        &self,
+        cf: &DbColFam,
+        key: &str,
+    ) -> Result<Option<Vec<u8>>> {
+        let cf = self.get_column_family(cf.to_str())?;
+        self.read_value_bytes(cf, key)
     }
 
     #[inline]
@@ -750,7 +638,7 @@ impl RocksDB {
     }
 
     /// Read diffs of non-persisted key-vals that are only kept for rollback of
-    /// one block height.
+    /// the block heights in the rollback window.
     #[cfg(test)]
     pub fn read_rollback_val(
         &self,
@@ -770,6 +658,13 @@ impl RocksDB {
             .map_err(|e| Error::DBError(e.into_string()))
     }
 
+    /// Read an entry of a column family with its full key
+    #[cfg(test)]
+    pub fn read_raw(&self, cf: &str, key: &str) -> Option<Vec<u8>> {
+        let cf = self.get_column_family(cf).unwrap();
+        self.inner.get_cf(cf, key).unwrap()
+    }
+
     /// Writes an entry directly to a db batch update
     /// directly
     pub fn insert_entry(
@@ -841,13 +736,11 @@ impl SnapshotPath {
         buf
     }
 
-    /// Return the temporary tarball path associated with this [`SnapshotPath`].
-    ///
-    /// The value of `compression_extension` should reflect the compression
-    /// algorithm used (e.g. `gz` for Gzip).
-    pub fn temp_tarball(&self, compression_extension: &str) -> PathBuf {
-        let mut buf = self.base();
-        buf.push(format!("db.tar.{compression_extension}"));
+    /// Return the path of the new db into which a snapshot fetched from
+    /// peers is restored.
+    pub fn restore_rocksdb(base_dir: PathBuf) -> PathBuf {
+        let mut buf = Self::snapshot_root_path(base_dir);
+        buf.push("restore");
         buf
     }
 
@@ -876,96 +769,49 @@ pub struct DbSnapshot(pub SnapshotPath);
 
 impl DbSnapshot {
     /// The magic number referring to the format of the snapshot.
-    pub const FORMAT_MAGIC: u32 = 0;
+    pub const FORMAT_MAGIC: u32 = 1;
 
-    /// Package and chunk the contents of the db snapshot.
+    /// Write the verifiable chunks of the state in the db snapshot.
     // NB: passing an owned `self` guarantees we don't attempt to call
     // this method again, which removes the temporary checkpoint dir
     // created by rocksdb
-    pub fn package(self) -> std::io::Result<()> {
-        self.build_tarball()?;
-        self.chunk_snapshot(MAX_STATE_SYNC_CHUNK_SIZE)?;
-        Ok(())
-    }
-
-    pub fn unpack(
-        archive_file: &mut std::fs::File,
-        dest: impl AsRef<Path>,
+    pub fn package(
+        self,
+        chain_id: ChainId,
+        native_token: Address,
     ) -> std::io::Result<()> {
-        use zstd::stream::read::Decoder;
-
-        let file_buf_reader = std::io::BufReader::new(archive_file);
-        let zstd_decoder = Decoder::new(file_buf_reader)?;
-
-        let mut archive = tar::Archive::new(zstd_decoder);
-        archive.unpack(dest)?;
-
-        Ok(())
+        self.package_with_chunk_size(
+            chain_id,
+            native_token,
+            MAX_STATE_SYNC_CHUNK_SIZE,
+        )
     }
 
-    pub(crate) fn build_tarball(&self) -> std::io::Result<()> {
-        use zstd::stream::write::Encoder;
-
+    fn package_with_chunk_size(
+        self,
+        chain_id: ChainId,
+        native_token: Address,
+        max_chunk: usize,
+    ) -> std::io::Result<()> {
         let snapshot_temp_db_path = self.0.temp_rocksdb();
 
-        let mut tar_builder = {
-            let file_handle = File::create(self.0.temp_tarball("zst"))?;
-            let zstd_encoder = Encoder::new(file_handle, 0)?.auto_finish();
-            tar::Builder::new(zstd_encoder)
-        };
-
-        // build tarball with rocksdb checkpoint contents
-        tar_builder.append_dir_all("db", &snapshot_temp_db_path)?;
-        tar_builder.finish()?;
-        _ = tar_builder;
-
-        // remove aux checkpoint dir
-        std::fs::remove_dir_all(&snapshot_temp_db_path)
-    }
-
-    fn chunk_snapshot(&self, max_chunk: usize) -> std::io::Result<()> {
-        let tarball_path = self.0.temp_tarball("zst");
-
-        let mut buf = vec![0; max_chunk];
-        let mut file = File::open(&tarball_path)?;
-
-        let mut eof = false;
+        // load the state of the checkpoint to get its Merkle tree
+        let state = FullAccessState::<RocksDB, Sha256Hasher>::open(
+            &snapshot_temp_db_path,
+            None,
+            chain_id,
+            native_token,
+            None,
+            is_key_diff_storable,
+        );
         let mut chunk_hashes = vec![];
-
-        // TODO: we can use tokio here to read chunks
-        // in parallel
-        //
-        // 1. determine tar archive size
-        // 2. spawn len / MAX_STATE_SYNC_CHUNK_SIZE tasks
-        // 3. spawn one more task if necessary to read chunk smaller than
-        //    MAX_STATE_SYNC_CHUNK_SIZE
-        // 4. assemble read data (need to store hash of the chunk)
-
-        for chunk_id in 0.. {
-            let mut read = 0;
-
-            // read up to `MAX_STATE_SYNC_CHUNK_SIZE` bytes
-            while read != max_chunk {
-                match file.read(&mut buf[read..]) {
-                    Ok(0) => {
-                        eof = true;
-                        break;
-                    }
-                    Ok(n) => checked!(read += n).unwrap(),
-                    Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
-                    Err(e) => return Err(e),
-                }
-            }
-
-            let chunk = &buf[..read];
-
-            std::fs::write(self.0.chunk_with_id(chunk_id), chunk)?;
-            chunk_hashes.push(Hash::sha256(chunk));
-
-            if eof {
-                break;
-            }
-        }
+        state_sync::write_snapshot(&state, max_chunk, |chunk| {
+            std::fs::write(self.0.chunk_with_id(chunk_hashes.len()), &chunk)?;
+            chunk_hashes.push(Hash::sha256(&chunk));
+            Ok(())
+        })
+        .map_err(std::io::Error::other)?;
+        drop(state);
 
         let chunk_hashes = chunk_hashes.serialize_to_vec();
         let hash_of_all_chunks = Hash::sha256(&chunk_hashes);
@@ -973,11 +819,11 @@ impl DbSnapshot {
             (Self::FORMAT_MAGIC, hash_of_all_chunks).serialize_to_vec(),
         );
 
-        std::fs::remove_file(tarball_path)?;
         std::fs::write(self.0.chunk_hashes(), chunk_hashes)?;
         std::fs::write(self.0.chunks_root_hash(), snapshot_hash)?;
 
-        Ok(())
+        // remove aux checkpoint dir
+        std::fs::remove_dir_all(&snapshot_temp_db_path)
     }
 
     /// Keep `number_to_keep` latest snapshots. All others
@@ -1077,7 +923,7 @@ impl DbSnapshot {
 impl DB for RocksDB {
     type Cache = rocksdb::Cache;
     type Migrator = DbUpdateType;
-    type RestoreSource<'a> = (&'a rocksdb::Cache, &'a mut std::fs::File);
+    type RestoreSource<'a> = (&'a rocksdb::Cache, &'a Path);
     type WriteBatch = RocksDBWriteBatch;
 
     fn open(
@@ -1089,21 +935,10 @@ impl DB for RocksDB {
 
     fn restore_from(
         &mut self,
-        (cache, snapshot): Self::RestoreSource<'_>,
+        (cache, restored_db): Self::RestoreSource<'_>,
     ) -> Result<()> {
-        snapshot.rewind().map_err(|e| {
-            Error::DBError(format!("Failed to rewind snapshot file: {e}",))
-        })?;
-
         let db_dir = self.inner.path().to_owned();
 
-        let unpack_dir = db_dir.parent().ok_or_else(|| {
-            Error::DBError(format!(
-                "Failed to query parent directory of db: {}",
-                db_dir.to_string_lossy()
-            ))
-        })?;
-
         // NB: close the current database handle.
         // DON'T TRY THIS AT HOME KIDS. we are
         // trained monkeys.
@@ -1114,8 +949,8 @@ impl DB for RocksDB {
 
         std::fs::remove_dir_all(&db_dir)
             .expect("Failed to nuke database directory");
-        DbSnapshot::unpack(snapshot, unpack_dir)
-            .expect("Failed to unpack new db");
+        std::fs::rename(restored_db, &db_dir)
+            .expect("Failed to move the restored db");
 
         *self = Self::open(db_dir, Some(cache));
 
@@ -1274,12 +1109,14 @@ impl DB for RocksDB {
             state_cf,
             NEXT_EPOCH_MIN_START_HEIGHT_KEY,
             &next_epoch_min_start_height,
+            height,
             batch,
         )?;
         self.add_state_value_to_batch(
             state_cf,
             NEXT_EPOCH_MIN_START_TIME_KEY,
             &next_epoch_min_start_time,
+            height,
             batch,
         )?;
 
@@ -1287,6 +1124,7 @@ impl DB for RocksDB {
             state_cf,
             UPDATE_EPOCH_BLOCKS_DELAY_KEY,
             &update_epoch_blocks_delay,
+            height,
             batch,
         )?;
 
@@ -1294,6 +1132,7 @@ impl DB for RocksDB {
             state_cf,
             COMMIT_ONLY_DATA_KEY,
             &commit_only_data,
+            height,
             batch,
         )?;
 
@@ -1303,22 +1142,25 @@ impl DB for RocksDB {
                 state_cf,
                 CONVERSION_STATE_KEY,
                 &conversion_state,
+                height,
                 batch,
             )?;
         }
 
-        self.add_value_to_batch(
+        self.add_state_value_to_batch(
             state_cf,
             ETHEREUM_HEIGHT_KEY,
             &ethereum_height,
+            height,
             batch,
-        );
-        self.add_value_to_batch(
+        )?;
+        self.add_state_value_to_batch(
             state_cf,
             ETH_EVENTS_QUEUE_KEY,
             &eth_events_queue,
+            height,
             batch,
-        );
+        )?;
 
         let block_cf = self.get_column_family(BLOCK_CF)?;
         let prefix = height.raw();
@@ -1475,6 +1317,16 @@ impl DB for RocksDB {
         height: BlockHeight,
         last_height: BlockHeight,
     ) -> Result<Option<Vec<u8>>> {
+        // The diffs needed to find the value are gone if the height is pruned
+        if let Some(pruned_height) = self.read_pruned_height()? {
+            if height <= pruned_height {
+                return Err(Error::Pruned {
+                    height,
+                    oldest: pruned_height.next_height(),
+                });
+            }
+        }
+
         // Check if the value changed at this height
         let diffs_cf = self.get_column_family(DIFFS_CF)?;
         let (old_val_key, new_val_key) = old_and_new_diff_key(key, height)?;
@@ -1723,7 +1575,11 @@ impl DB for RocksDB {
     ) -> Result<()> {
         let replay_protection_cf =
             self.get_column_family(REPLAY_PROTECTION_CF)?;
+        let rollback_cf = self.get_column_family(ROLLBACK_CF)?;
         let stripped_prefix = Some(replay_protection::current_prefix());
+        // The current entries were added at the last committed height
+        let last_height: Option<BlockHeight> = self
+            .read_value(self.get_column_family(STATE_CF)?, BLOCK_HEIGHT_KEY)?;
 
         for (ref hash_str, _, _) in iter_prefix(
             self,
@@ -1743,6 +1599,14 @@ impl DB for RocksDB {
             batch
                 .0
                 .put_cf(replay_protection_cf, key.to_string(), vec![]);
+            // Keep the entry in the rollback data of its height
+            if let Some(height) = last_height {
+                batch.0.put_cf(
+                    rollback_cf,
+                    rollback_replay_protection_key(height, &hash),
+                    vec![],
+                );
+            }
         }
 
         Ok(())
@@ -1755,30 +1619,79 @@ impl DB for RocksDB {
     ) -> Result<()> {
         let rollback_cf = self.get_column_family(ROLLBACK_CF)?;
 
-        let diff_old_key_prefix = Key {
-            segments: vec![
-                height.to_db_key(),
-                OLD_DIFF_PREFIX.to_string().to_db_key(),
-            ],
-        };
+        let height_prefix = Key::from(height.to_db_key());
         for (key_str, _val, _) in
-            iter_prefix(self, rollback_cf, None, Some(&diff_old_key_prefix))
+            iter_prefix(self, rollback_cf, None, Some(&height_prefix))
         {
             batch.0.delete_cf(rollback_cf, key_str)
         }
+        Ok(())
+    }
 
-        let diff_new_key_prefix = Key {
-            segments: vec![
-                height.to_db_key(),
-                NEW_DIFF_PREFIX.to_string().to_db_key(),
-            ],
-        };
-        for (key_str, _val, _) in
-            iter_prefix(self, rollback_cf, None, Some(&diff_new_key_prefix))
-        {
-            batch.0.delete_cf(rollback_cf, key_str)
+    fn prune_block_history(
+        &mut self,
+        batch: &mut Self::WriteBatch,
+        from: BlockHeight,
+        to: BlockHeight,
+        prune_headers: bool,
+    ) -> Result<()> {
+        let end = BlockHeight(checked!(to.0 + 1)?);
+
+        // Heights are encoded with a fixed length that preserves their order,
+        // so the diffs and results of the whole range can be deleted with
+        // range tombstones, which are cheap to write and to compact
+        let diffs_cf = self.get_column_family(DIFFS_CF)?;
+        batch.0.delete_range_cf(
+            diffs_cf,
+            format!("{}/", from.raw()),
+            format!("{}/", end.raw()),
+        );
+        let block_cf = self.get_column_family(BLOCK_CF)?;
+        batch.0.delete_range_cf(
+            block_cf,
+            format!("{RESULTS_KEY_PREFIX}/{}", from.raw()),
+            format!("{RESULTS_KEY_PREFIX}/{}", end.raw()),
+        );
+
+        // The other block data is deleted key by key, as the Merkle tree
+        // stores written once per epoch share the prefix of the heights
+        for height in from.0..=to.0 {
+            let height = BlockHeight(height);
+            let prefix = height.raw();
+            // NB: the block headers are kept for the IBC consensus states
+            // unless they are explicitly pruned
+            let header = prune_headers.then_some(BLOCK_HEADER_KEY_SEGMENT);
+            for segment in [
+                BLOCK_TIME_KEY_SEGMENT,
+                EPOCH_KEY_SEGMENT,
+                PRED_EPOCHS_KEY_SEGMENT,
+                ADDRESS_GEN_KEY_SEGMENT,
+            ]
+            .into_iter()
+            .chain(header)
+            {
+                batch.0.delete_cf(block_cf, format!("{prefix}/{segment}"));
+            }
+            for st in StoreType::iter().filter(|st| st.is_stored_every_block())
+            {
+                let key_prefix = tree_key_prefix_with_height(st, height);
+                for segment in [
+                    MERKLE_TREE_ROOT_KEY_SEGMENT,
+                    MERKLE_TREE_STORE_KEY_SEGMENT,
+                ] {
+                    batch
+                        .0
+                        .delete_cf(block_cf, format!("{key_prefix}/{segment}"));
+                }
+            }
         }
-        Ok(())
+
+        self.write_pruned_height(batch, to)
+    }
+
+    fn read_pruned_height(&self) -> Result<Option<BlockHeight>> {
+        let state_cf = self.get_column_family(STATE_CF)?;
+        self.read_value(state_cf, PRUNED_HEIGHT_KEY)
     }
 
     #[inline]
@@ -1990,6 +1903,13 @@ impl<'iter> DBIter<'iter> for RocksDB {
         let prefix = Some(replay_protection::current_prefix());
         iter_prefix(self, replay_protection_cf, None, prefix.as_ref())
     }
+
+    fn iter_replay_protection(&'iter self) -> Self::PrefixIter {
+        let replay_protection_cf = self
+            .get_column_family(REPLAY_PROTECTION_CF)
+            .expect("{REPLAY_PROTECTION_CF} column family should exist");
+        iter_prefix(self, replay_protection_cf, None, None)
+    }
 }
 
 fn iter_subspace_prefix<'iter>(
@@ -2158,7 +2078,7 @@ fn make_iter_read_opts(prefix: Option<String>) -> ReadOptions {
 
 impl DBWriteBatch for RocksDBWriteBatch {}
 
-fn old_and_new_diff_key(
+pub(super) fn old_and_new_diff_key(
     key: &Key,
     height: BlockHeight,
 ) -> Result<(String, String)> {
@@ -2174,6 +2094,24 @@ fn old_and_new_diff_key(
     Ok((old.to_string(), new.to_string()))
 }
 
+/// The key under which a state value replaced in the block at the given
+/// height is kept for rollback
+pub(super) fn rollback_state_key(height: BlockHeight, key: &str) -> String {
+    format!("{}/{ROLLBACK_STATE_PREFIX}/{key}", height.raw())
+}
+
+/// The key under which a replay protection entry added in the block at the
+/// given height is kept for rollback
+pub(super) fn rollback_replay_protection_key(
+    height: BlockHeight,
+    hash: &Hash,
+) -> String {
+    format!(
+        "{}/{ROLLBACK_REPLAY_PROTECTION_PREFIX}/{hash}",
+        height.raw()
+    )
+}
+
 /// Try to increase NOFILE limit and set the `max_open_files` limit to it in
 /// RocksDB options.
 fn set_max_open_files(cf_opts: &mut rocksdb::Options) {
@@ -2235,607 +2173,62 @@ mod imp {
 #[cfg(test)]
 mod test {
     use namada_apps_lib::collections::HashMap;
-    use namada_sdk::address::EstablishedAddressGen;
-    use namada_sdk::state::{MerkleTree, Sha256Hasher};
-    use namada_sdk::storage::conversion_state::ConversionState;
-    use namada_sdk::storage::types::CommitOnlyData;
-    use namada_sdk::storage::{BlockResults, Epochs, EthEventsQueue};
-    use namada_sdk::time::DateTimeUtc;
-    use tempfile::tempdir;
+    use namada_sdk::address;
+    use namada_sdk::state::{Sha256Hasher, StorageWrite};
 
     use super::*;
 
-    /// Test that a block written can be loaded back from DB.
+    /// Test that the state is split into chunks of about the max chunk size,
+    /// whose hashes are recorded with the snapshot.
     #[test]
-    fn test_load_state() {
-        let dir = tempdir().unwrap();
-        let db = RocksDB::open(dir.path(), None);
-
-        let mut batch = RocksDB::batch();
-        let last_height = BlockHeight::default();
-        db.batch_write_subspace_val(
-            &mut batch,
-            last_height,
-            &Key::parse("test").unwrap(),
-            vec![1_u8, 1, 1, 1],
-            true,
-        )
-        .unwrap();
-
-        add_block_to_batch(
-            &db,
-            &mut batch,
-            BlockHeight::default(),
-            Epoch::default(),
-            Epochs::default(),
-            &ConversionState::default(),
-        )
-        .unwrap();
-        db.exec_batch(batch).unwrap();
-
-        let _state = db
-            .read_last_block()
-            .expect("Should be able to read last block")
-            .expect("Block should have been written");
-    }
-
-    #[test]
-    fn test_read() {
-        let dir = tempdir().unwrap();
-        let mut db = RocksDB::open(dir.path(), None);
-
-        let key = Key::parse("test").unwrap();
-        let batch_key = Key::parse("batch").unwrap();
-
-        let mut batch = RocksDB::batch();
-        let last_height = BlockHeight(100);
-        db.batch_write_subspace_val(
-            &mut batch,
-            last_height,
-            &batch_key,
-            vec![1_u8, 1, 1, 1],
-            true,
-        )
-        .unwrap();
-        db.exec_batch(batch).unwrap();
-
-        db.write_subspace_val(last_height, &key, vec![1_u8, 1, 1, 0], true)
-            .unwrap();
-
-        let mut batch = RocksDB::batch();
-        let last_height = BlockHeight(111);
-        db.batch_write_subspace_val(
-            &mut batch,
-            last_height,
-            &batch_key,
-            vec![2_u8, 2, 2, 2],
-            true,
-        )
-        .unwrap();
-        db.exec_batch(batch).unwrap();
-
-        db.write_subspace_val(last_height, &key, vec![2_u8, 2, 2, 0], true)
-            .unwrap();
-
-        let prev_value = db
-            .read_subspace_val_with_height(
-                &batch_key,
-                BlockHeight(100),
-                last_height,
-            )
-            .expect("read should succeed");
-        assert_eq!(prev_value, Some(vec![1_u8, 1, 1, 1]));
-        let prev_value = db
-            .read_subspace_val_with_height(&key, BlockHeight(100), last_height)
-            .expect("read should succeed");
-        assert_eq!(prev_value, Some(vec![1_u8, 1, 1, 0]));
-
-        let updated_value = db
-            .read_subspace_val_with_height(
-                &batch_key,
-                BlockHeight(111),
-                last_height,
-            )
-            .expect("read should succeed");
-        assert_eq!(updated_value, Some(vec![2_u8, 2, 2, 2]));
-        let updated_value = db
-            .read_subspace_val_with_height(&key, BlockHeight(111), last_height)
-            .expect("read should succeed");
-        assert_eq!(updated_value, Some(vec![2_u8, 2, 2, 0]));
-
-        let latest_value = db
-            .read_subspace_val(&batch_key)
-            .expect("read should succeed");
-        assert_eq!(latest_value, Some(vec![2_u8, 2, 2, 2]));
-        let latest_value =
-            db.read_subspace_val(&key).expect("read should succeed");
-        assert_eq!(latest_value, Some(vec![2_u8, 2, 2, 0]));
-
-        let mut batch = RocksDB::batch();
-        let last_height = BlockHeight(222);
-        db.batch_delete_subspace_val(&mut batch, last_height, &batch_key, true)
-            .unwrap();
-        db.exec_batch(batch).unwrap();
-
-        db.delete_subspace_val(last_height, &key, true).unwrap();
-
-        let deleted_value = db
-            .read_subspace_val_with_height(
-                &batch_key,
-                BlockHeight(222),
-                last_height,
-            )
-            .expect("read should succeed");
-        assert_eq!(deleted_value, None);
-        let deleted_value = db
-            .read_subspace_val_with_height(&key, BlockHeight(222), last_height)
-            .expect("read should succeed");
-        assert_eq!(deleted_value, None);
-
-        let latest_value = db
-            .read_subspace_val(&batch_key)
-            .expect("read should succeed");
-        assert_eq!(latest_value, None);
-        let latest_value =
-            db.read_subspace_val(&key).expect("read should succeed");
-        assert_eq!(latest_value, None);
-    }
+    fn test_chunking() {
+        let temp = tempfile::tempdir().expect("Test failed");
+        let base_dir = temp.path().to_path_buf();
+        let snapshot =
+            DbSnapshot(SnapshotPath(base_dir.clone(), BlockHeight::first()));
+        let db_path = snapshot.0.temp_rocksdb();
 
-    #[test]
-    fn test_prefix_iter() {
-        let dir = tempdir().unwrap();
-        let db = RocksDB::open(dir.path(), None);
-
-        let prefix_0 = Key::parse("0").unwrap();
-        let key_0_a = prefix_0.push(&"a".to_string()).unwrap();
-        let key_0_b = prefix_0.push(&"b".to_string()).unwrap();
-        let key_0_c = prefix_0.push(&"c".to_string()).unwrap();
-        let prefix_1 = Key::parse("1").unwrap();
-        let key_1_a = prefix_1.push(&"a".to_string()).unwrap();
-        let key_1_b = prefix_1.push(&"b".to_string()).unwrap();
-        let key_1_c = prefix_1.push(&"c".to_string()).unwrap();
-        let prefix_01 = Key::parse("01").unwrap();
-        let key_01_a = prefix_01.push(&"a".to_string()).unwrap();
-
-        let keys_0 = vec![key_0_a, key_0_b, key_0_c];
-        let keys_1 = vec![key_1_a, key_1_b, key_1_c];
-        let keys_01 = vec![key_01_a];
-        let all_keys = [keys_0.clone(), keys_01, keys_1.clone()].concat();
-
-        // Write the keys
-        let mut batch = RocksDB::batch();
-        let height = BlockHeight(1);
-        for key in &all_keys {
-            db.batch_write_subspace_val(&mut batch, height, key, [0_u8], true)
-                .unwrap();
+        let mut state = FullAccessState::<RocksDB, Sha256Hasher>::open(
+            &db_path,
+            None,
+            ChainId::default(),
+            address::testing::nam(),
+            None,
+            is_key_diff_storable,
+        );
+        state.in_mem_mut().block.height = BlockHeight::first();
+        for i in 0..10_u8 {
+            let key = Key::parse(format!("key{i}")).expect("Test failed");
+            state.write(&key, [i; 64]).expect("Test failed");
         }
-        db.exec_batch(batch).unwrap();
-
-        // Prefix "0" shouldn't match prefix "01"
-        let itered_keys: Vec<Key> = db
-            .iter_prefix(Some(&prefix_0))
-            .map(|(key, _val, _)| Key::parse(key).unwrap())
-            .collect();
-        itertools::assert_equal(keys_0, itered_keys);
-
-        let itered_keys: Vec<Key> = db
-            .iter_prefix(Some(&prefix_1))
-            .map(|(key, _val, _)| Key::parse(key).unwrap())
-            .collect();
-        itertools::assert_equal(keys_1, itered_keys);
-
-        let itered_keys: Vec<Key> = db
-            .iter_prefix(None)
-            .map(|(key, _val, _)| Key::parse(key).unwrap())
-            .collect();
-        itertools::assert_equal(all_keys, itered_keys);
-    }
-
-    #[test]
-    fn test_rollback() {
-        for persist_diffs in [true, false] {
-            println!("Running with persist_diffs: {persist_diffs}");
-
-            let dir = tempdir().unwrap();
-            let mut db = RocksDB::open(dir.path(), None);
-
-            // A key that's gonna be added on a second block
-            let add_key = Key::parse("add").unwrap();
-            // A key that's gonna be deleted on a second block
-            let delete_key = Key::parse("delete").unwrap();
-            // A key that's gonna be overwritten on a second block
-            let overwrite_key = Key::parse("overwrite").unwrap();
-
-            // Write first block
-            let mut batch = RocksDB::batch();
-            let height_0 = BlockHeight(100);
-            let mut pred_epochs = Epochs::default();
-            pred_epochs.new_epoch(height_0);
-            let conversion_state_0 = ConversionState::default();
-            let to_delete_val = vec![1_u8, 1, 0, 0];
-            let to_overwrite_val = vec![1_u8, 1, 1, 0];
-            db.batch_write_subspace_val(
-                &mut batch,
-                height_0,
-                &delete_key,
-                &to_delete_val,
-                persist_diffs,
-            )
-            .unwrap();
-            db.batch_write_subspace_val(
-                &mut batch,
-                height_0,
-                &overwrite_key,
-                &to_overwrite_val,
-                persist_diffs,
-            )
-            .unwrap();
-            for tx in [b"tx1", b"tx2"] {
-                db.write_replay_protection_entry(
-                    &mut batch,
-                    &replay_protection::key(&Hash::sha256(tx)),
-                )
-                .unwrap();
-            }
-
-            for tx in [b"tx3", b"tx4"] {
-                db.write_replay_protection_entry(
-                    &mut batch,
-                    &replay_protection::current_key(&Hash::sha256(tx)),
-                )
-                .unwrap();
-            }
-
-            add_block_to_batch(
-                &db,
-                &mut batch,
-                height_0,
-                Epoch(1),
-                pred_epochs.clone(),
-                &conversion_state_0,
-            )
-            .unwrap();
-            db.exec_batch(batch).unwrap();
-
-            // Write second block
-            let mut batch = RocksDB::batch();
-            let height_1 = BlockHeight(101);
-            pred_epochs.new_epoch(height_1);
-            let conversion_state_1 = ConversionState::default();
-            let add_val = vec![1_u8, 0, 0, 0];
-            let overwrite_val = vec![1_u8, 1, 1, 1];
-            db.batch_write_subspace_val(
-                &mut batch,
-                height_1,
-                &add_key,
-                &add_val,
-                persist_diffs,
-            )
-            .unwrap();
-            db.batch_write_subspace_val(
-                &mut batch,
-                height_1,
-                &overwrite_key,
-                &overwrite_val,
-                persist_diffs,
+        state.commit_block().expect("Test failed");
+        drop(state);
+
+        snapshot
+            .package_with_chunk_size(
+                ChainId::default(),
+                address::testing::nam(),
+                256,
             )
-            .unwrap();
-            db.batch_delete_subspace_val(
-                &mut batch,
-                height_1,
-                &delete_key,
-                persist_diffs,
-            )
-            .unwrap();
-
-            db.move_current_replay_protection_entries(&mut batch)
-                .unwrap();
-
-            for tx in [b"tx5", b"tx6"] {
-                db.write_replay_protection_entry(
-                    &mut batch,
-                    &replay_protection::current_key(&Hash::sha256(tx)),
-                )
-                .unwrap();
-            }
+            .expect("Test failed");
+        // the checkpoint is removed once packaged
+        assert!(!db_path.exists());
 
-            add_block_to_batch(
-                &db,
-                &mut batch,
-                height_1,
-                Epoch(2),
-                pred_epochs,
-                &conversion_state_1,
+        let meta = DbSnapshot::load_snapshot_metadata(&base_dir, [1])
+            .next()
+            .expect("Test failed")
+            .expect("Test failed");
+        // the block state and several chunks of account keys
+        assert!(meta.chunk_hashes.len() > 2);
+        for (chunk, hash) in meta.chunk_hashes.iter().enumerate() {
+            let chunk = DbSnapshot::load_chunk(
+                BlockHeight::first(),
+                chunk as u64,
+                &base_dir,
             )
-            .unwrap();
-            db.exec_batch(batch).unwrap();
-
-            // Check that the values are as expected from second block
-            let added = db.read_subspace_val(&add_key).unwrap();
-            assert_eq!(added, Some(add_val));
-            let overwritten = db.read_subspace_val(&overwrite_key).unwrap();
-            assert_eq!(overwritten, Some(overwrite_val));
-            let deleted = db.read_subspace_val(&delete_key).unwrap();
-            assert_eq!(deleted, None);
-
-            for tx in [b"tx1", b"tx2", b"tx3", b"tx4", b"tx5", b"tx6"] {
-                assert!(
-                    db.has_replay_protection_entry(&Hash::sha256(tx)).unwrap()
-                );
-            }
-
-            // Rollback to the first block height
-            db.rollback(height_0).unwrap();
-
-            // Check that the values are back to the state at the first block
-            let added = db.read_subspace_val(&add_key).unwrap();
-            assert_eq!(added, None);
-            let overwritten = db.read_subspace_val(&overwrite_key).unwrap();
-            assert_eq!(overwritten, Some(to_overwrite_val));
-            let deleted = db.read_subspace_val(&delete_key).unwrap();
-            assert_eq!(deleted, Some(to_delete_val));
-            // Check the conversion state
-            let state_cf = db.get_column_family(STATE_CF).unwrap();
-            let conversion_state = db
-                .inner
-                .get_cf(state_cf, "conversion_state".as_bytes())
-                .unwrap()
-                .unwrap();
-            assert_eq!(conversion_state, encode(&conversion_state_0));
-            for tx in [b"tx1", b"tx2", b"tx3", b"tx4"] {
-                assert!(
-                    db.has_replay_protection_entry(&Hash::sha256(tx)).unwrap()
-                );
-            }
-
-            for tx in [b"tx5", b"tx6"] {
-                assert!(
-                    !db.has_replay_protection_entry(&Hash::sha256(tx)).unwrap()
-                );
-            }
-        }
-    }
-
-    #[test]
-    fn test_diffs() {
-        let dir = tempdir().unwrap();
-        let mut db = RocksDB::open(dir.path(), None);
-
-        let key_with_diffs = Key::parse("with_diffs").unwrap();
-        let key_without_diffs = Key::parse("without_diffs").unwrap();
-
-        let initial_val = vec![1_u8, 1, 0, 0];
-        let overwrite_val = vec![1_u8, 1, 1, 0];
-
-        // Write first block
-        let mut batch = RocksDB::batch();
-        let height_0 = BlockHeight::first();
-        db.batch_write_subspace_val(
-            &mut batch,
-            height_0,
-            &key_with_diffs,
-            &initial_val,
-            true,
-        )
-        .unwrap();
-        db.batch_write_subspace_val(
-            &mut batch,
-            height_0,
-            &key_without_diffs,
-            &initial_val,
-            false,
-        )
-        .unwrap();
-        db.exec_batch(batch).unwrap();
-
-        {
-            let diffs_cf = db.get_column_family(DIFFS_CF).unwrap();
-            let rollback_cf = db.get_column_family(ROLLBACK_CF).unwrap();
-
-            // Diffs new key for `key_with_diffs` at height_0 must be
-            // present
-            let (old_with_h0, new_with_h0) =
-                old_and_new_diff_key(&key_with_diffs, height_0).unwrap();
-            assert!(db.inner.get_cf(diffs_cf, old_with_h0).unwrap().is_none());
-            assert!(db.inner.get_cf(diffs_cf, new_with_h0).unwrap().is_some());
-
-            // Diffs new key for `key_without_diffs` at height_0 must be
-            // present
-            let (old_wo_h0, new_wo_h0) =
-                old_and_new_diff_key(&key_without_diffs, height_0).unwrap();
-            assert!(db.inner.get_cf(rollback_cf, old_wo_h0).unwrap().is_none());
-            assert!(db.inner.get_cf(rollback_cf, new_wo_h0).unwrap().is_some());
-        }
-
-        // Write second block
-        let mut batch = RocksDB::batch();
-        let height_1 = height_0 + 10u64;
-        db.batch_write_subspace_val(
-            &mut batch,
-            height_1,
-            &key_with_diffs,
-            &overwrite_val,
-            true,
-        )
-        .unwrap();
-        db.batch_write_subspace_val(
-            &mut batch,
-            height_1,
-            &key_without_diffs,
-            &overwrite_val,
-            false,
-        )
-        .unwrap();
-        db.prune_non_persisted_diffs(&mut batch, height_0).unwrap();
-        db.exec_batch(batch).unwrap();
-
-        {
-            let diffs_cf = db.get_column_family(DIFFS_CF).unwrap();
-            let rollback_cf = db.get_column_family(ROLLBACK_CF).unwrap();
-
-            // Diffs keys for `key_with_diffs` at height_0 must be present
-            let (old_with_h0, new_with_h0) =
-                old_and_new_diff_key(&key_with_diffs, height_0).unwrap();
-            assert!(db.inner.get_cf(diffs_cf, old_with_h0).unwrap().is_none());
-            assert!(db.inner.get_cf(diffs_cf, new_with_h0).unwrap().is_some());
-
-            // Diffs keys for `key_without_diffs` at height_0 must be gone
-            let (old_wo_h0, new_wo_h0) =
-                old_and_new_diff_key(&key_without_diffs, height_0).unwrap();
-            assert!(db.inner.get_cf(rollback_cf, old_wo_h0).unwrap().is_none());
-            assert!(db.inner.get_cf(rollback_cf, new_wo_h0).unwrap().is_none());
-
-            // Diffs keys for `key_with_diffs` at height_1 must be present
-            let (old_with_h1, new_with_h1) =
-                old_and_new_diff_key(&key_with_diffs, height_1).unwrap();
-            assert!(db.inner.get_cf(diffs_cf, old_with_h1).unwrap().is_some());
-            assert!(db.inner.get_cf(diffs_cf, new_with_h1).unwrap().is_some());
-
-            // Diffs keys for `key_without_diffs` at height_1 must be
-            // present
-            let (old_wo_h1, new_wo_h1) =
-                old_and_new_diff_key(&key_without_diffs, height_1).unwrap();
-            assert!(db.inner.get_cf(rollback_cf, old_wo_h1).unwrap().is_some());
-            assert!(db.inner.get_cf(rollback_cf, new_wo_h1).unwrap().is_some());
-        }
-
-        // Write third block
-        let mut batch = RocksDB::batch();
-        let height_2 = height_1 + 10;
-        db.batch_write_subspace_val(
-            &mut batch,
-            height_2,
-            &key_with_diffs,
-            &initial_val,
-            true,
-        )
-        .unwrap();
-        db.batch_write_subspace_val(
-            &mut batch,
-            height_2,
-            &key_without_diffs,
-            &initial_val,
-            false,
-        )
-        .unwrap();
-        db.prune_non_persisted_diffs(&mut batch, height_1).unwrap();
-        db.exec_batch(batch).unwrap();
-
-        {
-            let diffs_cf = db.get_column_family(DIFFS_CF).unwrap();
-            let rollback_cf = db.get_column_family(ROLLBACK_CF).unwrap();
-
-            // Diffs keys for `key_with_diffs` at height_1 must be present
-            let (old_with_h1, new_with_h1) =
-                old_and_new_diff_key(&key_with_diffs, height_1).unwrap();
-            assert!(db.inner.get_cf(diffs_cf, old_with_h1).unwrap().is_some());
-            assert!(db.inner.get_cf(diffs_cf, new_with_h1).unwrap().is_some());
-
-            // Diffs keys for `key_without_diffs` at height_1 must be gone
-            let (old_wo_h1, new_wo_h1) =
-                old_and_new_diff_key(&key_without_diffs, height_1).unwrap();
-            assert!(db.inner.get_cf(rollback_cf, old_wo_h1).unwrap().is_none());
-            assert!(db.inner.get_cf(rollback_cf, new_wo_h1).unwrap().is_none());
-
-            // Diffs keys for `key_with_diffs` at height_2 must be present
-            let (old_with_h2, new_with_h2) =
-                old_and_new_diff_key(&key_with_diffs, height_2).unwrap();
-            assert!(db.inner.get_cf(diffs_cf, old_with_h2).unwrap().is_some());
-            assert!(db.inner.get_cf(diffs_cf, new_with_h2).unwrap().is_some());
-
-            // Diffs keys for `key_without_diffs` at height_2 must be
-            // present
-            let (old_wo_h2, new_wo_h2) =
-                old_and_new_diff_key(&key_without_diffs, height_2).unwrap();
-            assert!(db.inner.get_cf(rollback_cf, old_wo_h2).unwrap().is_some());
-            assert!(db.inner.get_cf(rollback_cf, new_wo_h2).unwrap().is_some());
-        }
-    }
-
-    /// A test helper to write a block
-    fn add_block_to_batch(
-        db: &RocksDB,
-        batch: &mut RocksDBWriteBatch,
-        height: BlockHeight,
-        epoch: Epoch,
-        pred_epochs: Epochs,
-        conversion_state: &ConversionState,
-    ) -> Result<()> {
-        let merkle_tree = MerkleTree::<Sha256Hasher>::default();
-        let merkle_tree_stores = merkle_tree.stores();
-        #[allow(clippy::disallowed_methods)]
-        let time = DateTimeUtc::now();
-        let next_epoch_min_start_height = BlockHeight::default();
-        #[allow(clippy::disallowed_methods)]
-        let next_epoch_min_start_time = DateTimeUtc::now();
-        let update_epoch_blocks_delay = None;
-        let address_gen = EstablishedAddressGen::new("whatever");
-        let results = BlockResults::default();
-        let eth_events_queue = EthEventsQueue::default();
-        let commit_only_data = CommitOnlyData::default();
-        let block = BlockStateWrite {
-            merkle_tree_stores,
-            header: None,
-            height,
-            time,
-            epoch,
-            results: &results,
-            conversion_state,
-            pred_epochs: &pred_epochs,
-            next_epoch_min_start_height,
-            next_epoch_min_start_time,
-            update_epoch_blocks_delay,
-            address_gen: &address_gen,
-            ethereum_height: None,
-            eth_events_queue: &eth_events_queue,
-            commit_only_data: &commit_only_data,
-        };
-
-        db.add_block_to_batch(block, batch, true)
-    }
-
-    /// Test that we chunk a file into
-    /// pieces respecting the max chunk size.
-    #[test]
-    fn test_chunking() {
-        let temp = tempfile::tempdir().expect("Test failed");
-        let base_dir = temp.path().to_path_buf();
-
-        let snap_path = SnapshotPath(base_dir, BlockHeight::first());
-        let snapshot_base = snap_path.base().clone();
-        std::fs::create_dir_all(&snapshot_base).expect("Test failed");
-        let snapshot = DbSnapshot(snap_path);
-
-        let tar = snapshot.0.temp_tarball("zst");
-        std::fs::write(tar, vec![16; 21]).expect("Test failed");
-        snapshot.chunk_snapshot(10).unwrap();
-        let mut file_number = 0;
-        for entry in std::fs::read_dir(snapshot_base).expect("Test failed") {
-            let entry = entry.expect("Test failed");
-            file_number += 1;
-            if entry.path().is_file() {
-                let name = entry.file_name();
-                let name = name.to_string_lossy();
-                let Some(("chunk", chunk_nbr)) = name.split_once('-') else {
-                    continue;
-                };
-                let chunk_nbr = u64::from_str(chunk_nbr).expect("Test failed");
-                match chunk_nbr {
-                    0 | 1 => assert_eq!(
-                        entry.metadata().expect("Test failed").len(),
-                        10
-                    ),
-                    2 => assert_eq!(
-                        entry.metadata().expect("Test failed").len(),
-                        1
-                    ),
-                    _ => panic!("Snapshot too chunky"),
-                }
-            } else {
-                panic!("Found unexpected dir in snapshots")
-            }
+            .expect("Test failed");
+            assert_eq!(Hash::sha256(&chunk), *hash);
         }
-        assert_eq!(file_number, 5)
     }
 
     /// Test that we correctly delete snapshots
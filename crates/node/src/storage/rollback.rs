@@ -0,0 +1,553 @@
+//! Rollback of the DB to a past block height.
+//!
+//! The state at a past height is restored from the data kept with the block
+//! history and from the data kept in the rollback column family for the
+//! blocks in the rollback window (see the layout in [`super::rocksdb`]):
+//! - the subspace values from the old values of the diffs of the rolled back
+//!   blocks,
+//! - the state values from the values replaced by the rolled back blocks,
+//! - the replay protection buckets from the entries added by the rolled back
+//!   blocks.
+//!
+//! The Merkle tree stores and the other data of the rolled back blocks are
+//! deleted, so that the Merkle tree gets restored from the stores of the
+//! target height when the state is loaded. Only the height of the last block
+//! is read, so a DB whose last block data is corrupted can be rolled back too.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+use namada_replay_protection as replay_protection;
+use namada_sdk::borsh::BorshDeserialize;
+use namada_sdk::chain::{BlockHeight, Epoch, Epochs};
+use namada_sdk::hash::Hash;
+use namada_sdk::state::merkle_tree::{
+    tree_key_prefix_with_epoch, tree_key_prefix_with_height,
+};
+use namada_sdk::state::{DbError as Error, DbResult as Result, StoreType, DB};
+use namada_sdk::storage::{DbColFam, Key, KeySeg};
+use namada_sdk::{decode, encode};
+
+use super::rocksdb::{
+    rollback_state_key, ADDRESS_GEN_KEY_SEGMENT, BLOCK_HEADER_KEY_SEGMENT,
+    BLOCK_HEIGHT_KEY, BLOCK_TIME_KEY_SEGMENT, COMMIT_ONLY_DATA_KEY,
+    CONVERSION_STATE_KEY, EPOCH_KEY_SEGMENT, ETHEREUM_HEIGHT_KEY,
+    ETH_EVENTS_QUEUE_KEY, MERKLE_TREE_ROOT_KEY_SEGMENT,
+    MERKLE_TREE_STORE_KEY_SEGMENT, NEW_DIFF_PREFIX,
+    NEXT_EPOCH_MIN_START_HEIGHT_KEY, NEXT_EPOCH_MIN_START_TIME_KEY,
+    OLD_DIFF_PREFIX, PRED_EPOCHS_KEY_SEGMENT, PRED_KEY_PREFIX,
+    RESULTS_KEY_PREFIX, ROLLBACK_REPLAY_PROTECTION_PREFIX,
+    UPDATE_EPOCH_BLOCKS_DELAY_KEY,
+};
+use super::PersistentDB;
+
+/// The state keys whose values replaced by a block are kept for rollback
+const ROLLBACK_STATE_KEYS: [&str; 7] = [
+    NEXT_EPOCH_MIN_START_HEIGHT_KEY,
+    NEXT_EPOCH_MIN_START_TIME_KEY,
+    UPDATE_EPOCH_BLOCKS_DELAY_KEY,
+    COMMIT_ONLY_DATA_KEY,
+    CONVERSION_STATE_KEY,
+    ETHEREUM_HEIGHT_KEY,
+    ETH_EVENTS_QUEUE_KEY,
+];
+
+/// The state keys whose previous values are kept as predecessors by the
+/// blocks written before the rollback data was introduced
+const PRED_STATE_KEYS: [&str; 4] = [
+    NEXT_EPOCH_MIN_START_HEIGHT_KEY,
+    NEXT_EPOCH_MIN_START_TIME_KEY,
+    COMMIT_ONLY_DATA_KEY,
+    UPDATE_EPOCH_BLOCKS_DELAY_KEY,
+];
+
+/// A change of an entry of the DB made by a rollback
+#[derive(Debug, Clone, PartialEq)]
+pub struct RollbackChange {
+    /// The column family of the entry
+    pub cf: DbColFam,
+    /// The full key of the entry
+    pub key: String,
+    /// The restored value, or `None` if the entry is deleted
+    pub value: Option<Vec<u8>>,
+}
+
+impl fmt::Display for RollbackChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.value {
+            Some(value) => write!(
+                f,
+                "write {} {} ({} bytes)",
+                self.cf.to_str(),
+                self.key,
+                value.len()
+            ),
+            None => write!(f, "delete {} {}", self.cf.to_str(), self.key),
+        }
+    }
+}
+
+/// The changes that roll the DB back from its last height to a past height
+#[derive(Debug, Clone)]
+pub struct Rollback {
+    /// The height of the last block in the DB
+    pub last_height: BlockHeight,
+    /// The height that the DB is rolled back to
+    pub height: BlockHeight,
+    /// The changes of the DB entries, in the order in which they're applied
+    pub changes: Vec<RollbackChange>,
+}
+
+impl fmt::Display for Rollback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Rollback from height {} to height {}: {} changes",
+            self.last_height,
+            self.height,
+            self.changes.len()
+        )?;
+        for change in &self.changes {
+            write!(f, "\n  {change}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Rollback {
+    /// Find the changes that roll the DB back to the given height, without
+    /// writing anything. Fails if the data needed to restore the state at
+    /// the height is no longer in the DB.
+    pub fn plan(db: &PersistentDB, height: BlockHeight) -> Result<Self> {
+        let last_height = read_last_height(db)?;
+        let mut rollback = Self {
+            last_height,
+            height,
+            changes: vec![],
+        };
+        if height > last_height {
+            return Err(Error::DBError(format!(
+                "Cannot roll back to the height {height} above the last \
+                 height {last_height}"
+            )));
+        }
+        if height == last_height {
+            return Ok(rollback);
+        }
+        if let Some(pruned_height) = db.read_pruned_height()? {
+            if height <= pruned_height {
+                return Err(Error::Pruned {
+                    height,
+                    oldest: pruned_height.next_height(),
+                });
+            }
+        }
+
+        let pred_epochs: Epochs = read_value(
+            db,
+            &DbColFam::BLOCK,
+            &format!("{}/{PRED_EPOCHS_KEY_SEGMENT}", height.raw()),
+        )?
+        .ok_or_else(|| {
+            Error::DBError(format!("Missing the block at the height {height}"))
+        })?;
+        let epoch = pred_epochs.get_epoch(height).ok_or_else(|| {
+            Error::DBError(format!("Missing the epoch of the height {height}"))
+        })?;
+        check_merkle_tree_stores(db, height, epoch, &pred_epochs)?;
+
+        // The blocks written before the rollback data was introduced only
+        // keep the predecessors of the state values, so only their last
+        // block can be rolled back
+        let mut legacy = false;
+        for rolled_back in rollback.rolled_back_heights() {
+            let has_rollback_data = db
+                .read_column_family(
+                    &DbColFam::ROLLBACK,
+                    &rollback_state_key(
+                        rolled_back,
+                        NEXT_EPOCH_MIN_START_HEIGHT_KEY,
+                    ),
+                )?
+                .is_some();
+            if !has_rollback_data {
+                if rolled_back == last_height
+                    && height.next_height() == last_height
+                {
+                    legacy = true;
+                } else {
+                    return Err(Error::DBError(format!(
+                        "The rollback data of the block at the height \
+                         {rolled_back} is missing. Only the blocks in the \
+                         rollback window of the node can be rolled back."
+                    )));
+                }
+            }
+        }
+
+        rollback.restore_subspace(db)?;
+        if legacy {
+            rollback.restore_pred_state(db, epoch)?;
+        } else {
+            rollback.restore_state(db)?;
+        }
+        rollback.restore_replay_protection(db)?;
+        rollback.delete_blocks(db, epoch)?;
+        rollback.changes.push(RollbackChange {
+            cf: DbColFam::STATE,
+            key: BLOCK_HEIGHT_KEY.to_string(),
+            value: Some(encode(&height)),
+        });
+        Ok(rollback)
+    }
+
+    /// Write the changes of the rollback in the DB
+    pub fn apply(&self, db: &PersistentDB) -> Result<()> {
+        let mut batch = PersistentDB::batch();
+        for RollbackChange { cf, key, value } in &self.changes {
+            match value {
+                Some(value) => {
+                    db.add_raw_to_batch(&mut batch, cf, key, value)?
+                }
+                None => db.delete_raw_from_batch(&mut batch, cf, key)?,
+            }
+        }
+        db.exec_batch(batch)?;
+        db.flush(true)
+    }
+
+    /// Restore the subspace values of the keys changed in the rolled back
+    /// blocks. The value of a key at the target height is the old value of
+    /// its first diff above the height, or none if its first diff only has a
+    /// new value.
+    fn restore_subspace(&mut self, db: &PersistentDB) -> Result<()> {
+        let mut restored = BTreeMap::<String, Option<Vec<u8>>>::new();
+        for height in self.rolled_back_heights() {
+            for cf in [DbColFam::DIFFS, DbColFam::ROLLBACK] {
+                for (key, old_value) in
+                    read_height_prefix(db, &cf, height, OLD_DIFF_PREFIX)
+                {
+                    restored.entry(key).or_insert(Some(old_value));
+                }
+                for (key, _new_value) in
+                    read_height_prefix(db, &cf, height, NEW_DIFF_PREFIX)
+                {
+                    restored.entry(key).or_insert(None);
+                }
+            }
+        }
+        for (key, value) in restored {
+            self.change_if_different(db, DbColFam::SUBSPACE, key, value)?;
+        }
+        Ok(())
+    }
+
+    /// Restore the state values replaced in the rolled back blocks. The
+    /// value of a key at the target height is the one replaced by the first
+    /// block above the height that wrote it.
+    fn restore_state(&mut self, db: &PersistentDB) -> Result<()> {
+        for key in ROLLBACK_STATE_KEYS {
+            let mut replaced = None;
+            for height in self.rolled_back_heights() {
+                replaced = db.read_column_family(
+                    &DbColFam::ROLLBACK,
+                    &rollback_state_key(height, key),
+                )?;
+                if replaced.is_some() {
+                    break;
+                }
+            }
+            if let Some(value) = replaced {
+                self.change_if_different(
+                    db,
+                    DbColFam::STATE,
+                    key.to_string(),
+                    Some(value),
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Restore the state values of the previous height from their
+    /// predecessors, for a last block written without rollback data
+    fn restore_pred_state(
+        &mut self,
+        db: &PersistentDB,
+        epoch: Epoch,
+    ) -> Result<()> {
+        let read_pred = |key: &str| {
+            let pred_key = format!("{PRED_KEY_PREFIX}/{key}");
+            db.read_column_family(&DbColFam::STATE, &pred_key)?
+                .ok_or(Error::UnknownKey { key: pred_key })
+        };
+        for key in PRED_STATE_KEYS {
+            let value = read_pred(key)?;
+            self.change_if_different(
+                db,
+                DbColFam::STATE,
+                key.to_string(),
+                Some(value),
+            )?;
+        }
+        // The conversion state is only written with the first block of an
+        // epoch
+        let last_epoch: Epoch = read_value(
+            db,
+            &DbColFam::BLOCK,
+            &format!("{}/{EPOCH_KEY_SEGMENT}", self.last_height.raw()),
+        )?
+        .ok_or_else(|| {
+            Error::DBError(format!(
+                "Missing the epoch of the last height {}",
+                self.last_height
+            ))
+        })?;
+        if last_epoch != epoch {
+            let value = read_pred(CONVERSION_STATE_KEY)?;
+            self.change_if_different(
+                db,
+                DbColFam::STATE,
+                CONVERSION_STATE_KEY.to_string(),
+                Some(value),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Remove the tx hashes added in the rolled back blocks and move the ones
+    /// of the target height back to the current bucket. The hashes of the
+    /// target height stay in the general bucket if they're no longer in its
+    /// rollback data, which doesn't affect the replay protection.
+    fn restore_replay_protection(&mut self, db: &PersistentDB) -> Result<()> {
+        // The hashes of the last block are in the current bucket
+        for (key, _value, _gas) in db.iter_column_family(
+            &DbColFam::REPLAYPROT,
+            Some(&replay_protection::current_prefix()),
+        ) {
+            self.delete(DbColFam::REPLAYPROT, key);
+        }
+        for height in self.rolled_back_heights() {
+            for (hash, _value) in read_height_prefix(
+                db,
+                &DbColFam::ROLLBACK,
+                height,
+                ROLLBACK_REPLAY_PROTECTION_PREFIX,
+            ) {
+                let hash = parse_hash(&hash)?;
+                self.delete(
+                    DbColFam::REPLAYPROT,
+                    replay_protection::key(&hash).to_string(),
+                );
+            }
+        }
+        for (hash, _value) in read_height_prefix(
+            db,
+            &DbColFam::ROLLBACK,
+            self.height,
+            ROLLBACK_REPLAY_PROTECTION_PREFIX,
+        ) {
+            let hash = parse_hash(&hash)?;
+            self.delete(
+                DbColFam::REPLAYPROT,
+                replay_protection::key(&hash).to_string(),
+            );
+            self.changes.push(RollbackChange {
+                cf: DbColFam::REPLAYPROT,
+                key: replay_protection::current_key(&hash).to_string(),
+                value: Some(vec![]),
+            });
+        }
+        Ok(())
+    }
+
+    /// Delete the data of the rolled back blocks: their diffs, rollback
+    /// data, block data and Merkle tree stores, and the Merkle tree stores of
+    /// the epochs that started after the target height
+    fn delete_blocks(&mut self, db: &PersistentDB, epoch: Epoch) -> Result<()> {
+        for height in self.rolled_back_heights() {
+            let height_prefix = Key::from(height.to_db_key());
+            for cf in [DbColFam::DIFFS, DbColFam::ROLLBACK] {
+                for (key, _value, _gas) in
+                    db.iter_column_family(&cf, Some(&height_prefix))
+                {
+                    self.delete(cf, key);
+                }
+            }
+
+            // The Merkle tree stores written once per epoch share the prefix
+            // of the heights, so the block data is deleted key by key
+            let prefix = height.raw();
+            let mut block_keys: Vec<String> = [
+                BLOCK_HEADER_KEY_SEGMENT,
+                BLOCK_TIME_KEY_SEGMENT,
+                EPOCH_KEY_SEGMENT,
+                PRED_EPOCHS_KEY_SEGMENT,
+                ADDRESS_GEN_KEY_SEGMENT,
+            ]
+            .into_iter()
+            .map(|segment| format!("{prefix}/{segment}"))
+            .collect();
+            block_keys.push(format!("{RESULTS_KEY_PREFIX}/{prefix}"));
+            for st in StoreType::iter().filter(|st| st.is_stored_every_block())
+            {
+                block_keys.extend(tree_store_keys(
+                    tree_key_prefix_with_height(st, height),
+                ));
+            }
+            for key in block_keys {
+                self.delete_if_exists(db, DbColFam::BLOCK, key)?;
+            }
+        }
+
+        let mut next_epoch = epoch.next();
+        loop {
+            let mut found = false;
+            for st in StoreType::iter().filter(|st| !st.is_stored_every_block())
+            {
+                for key in
+                    tree_store_keys(tree_key_prefix_with_epoch(st, next_epoch))
+                {
+                    found |= self.delete_if_exists(db, DbColFam::BLOCK, key)?;
+                }
+            }
+            if !found {
+                break;
+            }
+            next_epoch = next_epoch.next();
+        }
+        Ok(())
+    }
+
+    fn rolled_back_heights(&self) -> Vec<BlockHeight> {
+        (self.height.0.saturating_add(1)..=self.last_height.0)
+            .map(BlockHeight)
+            .collect()
+    }
+
+    fn delete(&mut self, cf: DbColFam, key: String) {
+        self.changes.push(RollbackChange {
+            cf,
+            key,
+            value: None,
+        });
+    }
+
+    /// Delete the entry if it's in the DB. Returns whether it was.
+    fn delete_if_exists(
+        &mut self,
+        db: &PersistentDB,
+        cf: DbColFam,
+        key: String,
+    ) -> Result<bool> {
+        let exists = db.read_column_family(&cf, &key)?.is_some();
+        if exists {
+            self.delete(cf, key);
+        }
+        Ok(exists)
+    }
+
+    /// Restore the value of the entry if it's different from the current one
+    fn change_if_different(
+        &mut self,
+        db: &PersistentDB,
+        cf: DbColFam,
+        key: String,
+        value: Option<Vec<u8>>,
+    ) -> Result<()> {
+        if db.read_column_family(&cf, &key)? != value {
+            self.changes.push(RollbackChange { cf, key, value });
+        }
+        Ok(())
+    }
+}
+
+/// Read the height of the last block in the DB
+pub fn read_last_height(db: &PersistentDB) -> Result<BlockHeight> {
+    read_value(db, &DbColFam::STATE, BLOCK_HEIGHT_KEY)?.ok_or_else(|| {
+        Error::DBError("Missing the last block height in storage".to_string())
+    })
+}
+
+/// Check that the Merkle tree stores needed to restore the Merkle tree at the
+/// given height are in the DB
+fn check_merkle_tree_stores(
+    db: &PersistentDB,
+    height: BlockHeight,
+    epoch: Epoch,
+    pred_epochs: &Epochs,
+) -> Result<()> {
+    for st in StoreType::iter() {
+        let key_prefix = if st.is_stored_every_block() {
+            tree_key_prefix_with_height(st, height)
+        } else {
+            tree_key_prefix_with_epoch(st, epoch)
+        };
+        let store_key = format!("{key_prefix}/{MERKLE_TREE_STORE_KEY_SEGMENT}");
+        if db
+            .read_column_family(&DbColFam::BLOCK, &store_key)?
+            .is_none()
+        {
+            let stored_at = if st.is_stored_every_block() {
+                format!("the height {height}")
+            } else {
+                let epoch_start = pred_epochs
+                    .get_start_height_of_epoch(epoch)
+                    .unwrap_or_default();
+                format!("the start of the epoch {epoch} at {epoch_start}")
+            };
+            return Err(Error::DBError(format!(
+                "The {st} Merkle tree store of {stored_at} is missing. Only \
+                 the heights in the rollback window of the node can be rolled \
+                 back to."
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// The keys of the root and the store of a Merkle tree store
+fn tree_store_keys(key_prefix: Key) -> [String; 2] {
+    [
+        format!("{key_prefix}/{MERKLE_TREE_ROOT_KEY_SEGMENT}"),
+        format!("{key_prefix}/{MERKLE_TREE_STORE_KEY_SEGMENT}"),
+    ]
+}
+
+/// Read the entries of a column family under the given segment of a height,
+/// with the height and the segment stripped from their keys
+fn read_height_prefix(
+    db: &PersistentDB,
+    cf: &DbColFam,
+    height: BlockHeight,
+    segment: &str,
+) -> Vec<(String, Vec<u8>)> {
+    let prefix = Key::from(height.to_db_key()).with_segment(segment.to_owned());
+    let stripped_prefix = format!("{prefix}/");
+    db.iter_column_family(cf, Some(&prefix))
+        .map(|(key, value, _gas)| {
+            let key = key
+                .strip_prefix(&stripped_prefix)
+                .map(ToOwned::to_owned)
+                .unwrap_or(key);
+            (key, value)
+        })
+        .collect()
+}
+
+fn read_value<T: BorshDeserialize>(
+    db: &PersistentDB,
+    cf: &DbColFam,
+    key: &str,
+) -> Result<Option<T>> {
+    db.read_column_family(cf, key)?
+        .map(|bytes| decode(bytes).map_err(Error::CodingError))
+        .transpose()
+}
+
+fn parse_hash(hash: &str) -> Result<Hash> {
+    Hash::from_str(hash).map_err(|e| {
+        Error::DBError(format!("Invalid replay protection hash {hash}: {e}"))
+    })
+}
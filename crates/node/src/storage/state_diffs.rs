@@ -0,0 +1,272 @@
+//! Export of the state changes of committed blocks, read from the old and new
+//! diffs that the DB keeps for each height.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::Write;
+
+use data_encoding::HEXLOWER;
+use namada_sdk::chain::BlockHeight;
+use namada_sdk::state::{DBIter, DbError, StoreType, DB};
+use namada_sdk::storage::Key;
+use thiserror::Error;
+
+/// State diffs export error
+#[allow(missing_docs)]
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("DB error: {0}")]
+    Db(#[from] DbError),
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Found an invalid key in the diffs: {0}")]
+    InvalidKey(String),
+    #[error("No block has been committed yet")]
+    NoBlock,
+    #[error(
+        "Invalid height range {from}..={to}, the last committed height is \
+         {last}"
+    )]
+    InvalidRange {
+        from: BlockHeight,
+        to: BlockHeight,
+        last: BlockHeight,
+    },
+}
+
+/// State diffs export result
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// The change of a key's value in a committed block. Only the keys whose diffs
+/// are persisted in the DB are present.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StateDiff {
+    /// The height of the block that changed the key
+    pub height: BlockHeight,
+    /// The changed key
+    pub key: Key,
+    /// The merkle store of the key
+    pub store_type: StoreType,
+    /// The value before the block, if the key was present
+    pub old_value: Option<Vec<u8>>,
+    /// The value after the block, if the key wasn't deleted
+    pub new_value: Option<Vec<u8>>,
+}
+
+impl StateDiff {
+    /// Encode the diff as a JSON object with hex-encoded values
+    pub fn to_json(&self) -> serde_json::Value {
+        let hex = |value: &Option<Vec<u8>>| {
+            value.as_ref().map(|value| HEXLOWER.encode(value))
+        };
+        serde_json::json!({
+            "height": self.height.0,
+            "key": self.key.to_string(),
+            "store_type": self.store_type.to_string(),
+            "old_value": hex(&self.old_value),
+            "new_value": hex(&self.new_value),
+        })
+    }
+}
+
+impl fmt::Display for StateDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let hex = |value: &Option<Vec<u8>>| match value {
+            Some(value) => format!("0x{}", HEXLOWER.encode(value)),
+            None => "<none>".to_string(),
+        };
+        write!(
+            f,
+            "[{}] {} ({}): {} -> {}",
+            self.height,
+            self.key,
+            self.store_type,
+            hex(&self.old_value),
+            hex(&self.new_value)
+        )
+    }
+}
+
+/// Filter of state diffs by key prefixes and store types. An empty list of
+/// prefixes or store types doesn't filter anything.
+#[derive(Clone, Debug, Default)]
+pub struct DiffFilter {
+    /// Keep the keys that start with any of these prefixes
+    pub prefixes: Vec<Key>,
+    /// Keep the keys that belong to any of these merkle stores
+    pub store_types: Vec<StoreType>,
+}
+
+impl DiffFilter {
+    /// Check if the diff passes the filter
+    pub fn matches(&self, diff: &StateDiff) -> bool {
+        (self.prefixes.is_empty()
+            || self
+                .prefixes
+                .iter()
+                .any(|prefix| diff.key.split_prefix(prefix).is_some()))
+            && (self.store_types.is_empty()
+                || self.store_types.contains(&diff.store_type))
+    }
+}
+
+/// Read all the state diffs of the block committed at the given height, sorted
+/// by key
+pub fn read_block_diffs<D>(
+    db: &D,
+    height: BlockHeight,
+) -> Result<Vec<StateDiff>>
+where
+    D: DB + for<'iter> DBIter<'iter>,
+{
+    let mut changes: BTreeMap<String, (Option<Vec<u8>>, Option<Vec<u8>>)> =
+        BTreeMap::new();
+    for (key, value, _gas) in db.iter_old_diffs(height, None) {
+        changes.entry(key).or_default().0 = Some(value);
+    }
+    for (key, value, _gas) in db.iter_new_diffs(height, None) {
+        changes.entry(key).or_default().1 = Some(value);
+    }
+
+    changes
+        .into_iter()
+        .map(|(key, (old_value, new_value))| {
+            let key =
+                Key::parse(&key).map_err(|_| Error::InvalidKey(key.clone()))?;
+            let (store_type, _) = StoreType::sub_key(&key)
+                .map_err(|_| Error::InvalidKey(key.to_string()))?;
+            Ok(StateDiff {
+                height,
+                key,
+                store_type,
+                old_value,
+                new_value,
+            })
+        })
+        .collect()
+}
+
+/// Write the state diffs of the blocks committed in the given range of heights
+/// that pass the filter as newline-delimited JSON records. Returns the number
+/// of written records.
+pub fn export_diffs<D>(
+    db: &D,
+    from: BlockHeight,
+    to: BlockHeight,
+    filter: &DiffFilter,
+    mut out: impl Write,
+) -> Result<u64>
+where
+    D: DB + for<'iter> DBIter<'iter>,
+{
+    let last = db.read_last_block()?.ok_or(Error::NoBlock)?.height;
+    if from.0 == 0 || from > to || to > last {
+        return Err(Error::InvalidRange { from, to, last });
+    }
+    if let Some(pruned_height) = db.read_pruned_height()? {
+        if from <= pruned_height {
+            return Err(DbError::Pruned {
+                height: from,
+                oldest: pruned_height.next_height(),
+            }
+            .into());
+        }
+    }
+
+    let mut records = 0_u64;
+    let mut height = from;
+    while height <= to {
+        for diff in read_block_diffs(db, height)? {
+            if filter.matches(&diff) {
+                writeln!(out, "{}", diff.to_json())?;
+                records = records.saturating_add(1);
+            }
+        }
+        height = height.next_height();
+    }
+    out.flush()?;
+    Ok(records)
+}
+
+#[cfg(test)]
+mod test {
+    use namada_sdk::address;
+    use namada_sdk::borsh::BorshSerializeExt;
+    use namada_sdk::chain::ChainId;
+    use namada_sdk::state::StorageWrite;
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::shell::is_key_diff_storable;
+    use crate::storage::PersistentState;
+
+    #[test]
+    fn test_export_diffs() {
+        let db_path =
+            TempDir::new().expect("Unable to create a temporary DB directory");
+        let mut state = PersistentState::open(
+            db_path.path(),
+            None,
+            ChainId::default(),
+            address::testing::nam(),
+            None,
+            is_key_diff_storable,
+        );
+        let key_a = Key::parse("a").unwrap();
+        let key_b = Key::parse("b/c").unwrap();
+
+        state.in_mem_mut().begin_block(BlockHeight(1)).unwrap();
+        state.write(&key_a, 1_u64).unwrap();
+        state.write(&key_b, 2_u64).unwrap();
+        state.commit_block().unwrap();
+
+        state.in_mem_mut().begin_block(BlockHeight(2)).unwrap();
+        state.write(&key_a, 3_u64).unwrap();
+        state.delete(&key_b).unwrap();
+        state.commit_block().unwrap();
+
+        let db = state.db();
+        let diffs = read_block_diffs(db, BlockHeight(2)).unwrap();
+        assert_eq!(diffs.len(), 2);
+        assert_eq!(diffs[0].key, key_a);
+        assert_eq!(diffs[0].store_type, StoreType::Account);
+        assert_eq!(diffs[0].old_value, Some(1_u64.serialize_to_vec()));
+        assert_eq!(diffs[0].new_value, Some(3_u64.serialize_to_vec()));
+        assert_eq!(diffs[1].key, key_b);
+        assert_eq!(diffs[1].old_value, Some(2_u64.serialize_to_vec()));
+        assert_eq!(diffs[1].new_value, None);
+
+        // Export only the keys under the prefix `b`
+        let filter = DiffFilter {
+            prefixes: vec![Key::parse("b").unwrap()],
+            store_types: vec![],
+        };
+        let mut out = vec![];
+        let records =
+            export_diffs(db, BlockHeight(1), BlockHeight(2), &filter, &mut out)
+                .unwrap();
+        assert_eq!(records, 2);
+        let lines: Vec<serde_json::Value> = String::from_utf8(out)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines[0]["height"], 1);
+        assert_eq!(lines[0]["key"], "b/c");
+        assert_eq!(lines[0]["old_value"], serde_json::Value::Null);
+        assert_eq!(lines[1]["height"], 2);
+        assert_eq!(lines[1]["new_value"], serde_json::Value::Null);
+
+        // Heights that are not committed can't be exported
+        assert!(matches!(
+            export_diffs(
+                db,
+                BlockHeight(1),
+                BlockHeight(3),
+                &filter,
+                std::io::sink()
+            ),
+            Err(Error::InvalidRange { .. })
+        ));
+    }
+}
@@ -0,0 +1,1020 @@
+//! Verifiable state-sync snapshots.
+//!
+//! A snapshot holds the key-value pairs of the subspace at its height, grouped
+//! by the sub-tree of the Merkle tree that they are committed to. Every chunk
+//! carries the proofs of its values against the root of their sub-tree and the
+//! proof of that root against the root of the Merkle tree, i.e. the app hash.
+//! A node restoring a snapshot can thus check each chunk against the app hash
+//! verified by its light client before writing it to a new DB, and reject the
+//! peer that sent an invalid chunk. Once all the chunks have been applied, the
+//! root of the Merkle tree rebuilt from them must be equal to the app hash,
+//! which ensures that no key has been left out.
+//!
+//! The chunks of a snapshot come in the following order:
+//! - the state of the block, which is committed to the Merkle tree as a whole
+//!   in the commit data sub-tree
+//! - the key-value pairs of the account, PoS, IBC and no-diff sub-trees, sorted
+//!   by key in each sub-tree
+//! - the pending transfers of the bridge pool, whose sub-tree doesn't support
+//!   existence proofs and is instead rebuilt and checked as a whole
+//! - the replay protection entries, which can't be verified individually and
+//!   are instead checked as a whole against their set commitment in the
+//!   subspace
+
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use namada_replay_protection::{self as replay_protection, SetCommitment};
+use namada_sdk::borsh::{BorshDeserialize, BorshSerialize, BorshSerializeExt};
+use namada_sdk::collections::HashSet;
+use namada_sdk::eth_bridge_pool::{
+    get_pending_key, is_pending_transfer_key, PendingTransfer,
+};
+use namada_sdk::hash::Hash;
+use namada_sdk::state::merkle_tree::{
+    self, verify_sub_root, verify_sub_tree_existence, CommitmentProof,
+    NO_DIFF_KEY_PREFIX,
+};
+use namada_sdk::state::{
+    BlockHeight, BlockStateRead, BlockStateWrite, DBIter, DbError,
+    FullAccessState, Key, KeySeg, MembershipProof, MerkleTree, StorageHasher,
+    StoreType, DB,
+};
+use prost::Message;
+use thiserror::Error;
+
+use super::rocksdb::{self, RocksDB};
+
+/// The sub-trees whose key-value pairs are sent with their existence proofs,
+/// in the order of the snapshot chunks
+const SUBSPACE_STORE_TYPES: [StoreType; 4] = [
+    StoreType::Account,
+    StoreType::PoS,
+    StoreType::Ibc,
+    StoreType::NoDiff,
+];
+
+/// The size of an encoded replay protection entry
+const REPLAY_PROTECTION_ENTRY_SIZE: usize = 32;
+
+#[allow(missing_docs)]
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("DB error: {0}")]
+    Db(#[from] DbError),
+    #[error("Merkle tree error: {0}")]
+    MerkleTree(#[from] merkle_tree::Error),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("No block has been committed to the DB")]
+    NoBlock,
+    #[error("Failed to decode a snapshot chunk: {0}")]
+    Decoding(std::io::Error),
+    #[error("Invalid snapshot chunk: {0}")]
+    InvalidChunk(String),
+}
+
+/// State-sync result
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    /// Whether the error is due to the content of the snapshot, as opposed to
+    /// a local failure
+    pub fn is_invalid_snapshot(&self) -> bool {
+        matches!(self, Self::Decoding(_) | Self::InvalidChunk(_))
+    }
+}
+
+fn invalid(msg: impl std::fmt::Display) -> Error {
+    Error::InvalidChunk(msg.to_string())
+}
+
+/// A chunk of a state-sync snapshot
+#[derive(Debug, BorshSerialize, BorshDeserialize)]
+pub enum SnapshotChunk {
+    /// The state of the block at the height of the snapshot
+    Block {
+        state: Box<BlockStateRead>,
+        /// Encoded proof of the root of the commit-only data
+        commit_data_proof: Vec<u8>,
+    },
+    /// Key-value pairs of a sub-tree, sorted by key
+    SubTree {
+        store_type: StoreType,
+        sub_root: Hash,
+        /// Encoded proof of the root of the sub-tree
+        sub_root_proof: Vec<u8>,
+        entries: Vec<SnapshotEntry>,
+    },
+    /// All the pending transfers of the bridge pool, sorted by key
+    BridgePool {
+        sub_root: Hash,
+        /// Encoded proof of the root of the sub-tree
+        sub_root_proof: Vec<u8>,
+        transfers: Vec<BridgePoolEntry>,
+    },
+    /// The hashes of the applied txs
+    ReplayProtection(Vec<Hash>),
+}
+
+impl SnapshotChunk {
+    /// The position of the chunk in a snapshot
+    fn rank(&self) -> Option<usize> {
+        match self {
+            Self::Block { .. } => Some(0),
+            Self::SubTree { store_type, .. } => SUBSPACE_STORE_TYPES
+                .iter()
+                .position(|st| st == store_type)
+                .map(|pos| pos.saturating_add(1)),
+            Self::BridgePool { .. } => {
+                Some(SUBSPACE_STORE_TYPES.len().saturating_add(1))
+            }
+            Self::ReplayProtection(_) => {
+                Some(SUBSPACE_STORE_TYPES.len().saturating_add(2))
+            }
+        }
+    }
+
+    /// Whether the snapshot may have several consecutive chunks of this kind
+    fn is_repeatable(&self) -> bool {
+        matches!(self, Self::SubTree { .. } | Self::ReplayProtection(_))
+    }
+}
+
+/// A key-value pair of a sub-tree with the proof of its existence
+#[derive(Debug, BorshSerialize, BorshDeserialize)]
+pub struct SnapshotEntry {
+    pub key: Key,
+    pub value: Vec<u8>,
+    /// Encoded ICS23 commitment proof
+    pub proof: Vec<u8>,
+}
+
+/// A pending transfer of the bridge pool
+#[derive(Debug, BorshSerialize, BorshDeserialize)]
+pub struct BridgePoolEntry {
+    pub key: Key,
+    pub value: Vec<u8>,
+    /// The height at which the transfer was added, which is the value
+    /// committed to the bridge pool sub-tree
+    pub height: BlockHeight,
+}
+
+/// Get the key of the given storage key in the Merkle tree
+fn tree_key(key: &Key, is_diff_storable: bool) -> Key {
+    if is_diff_storable {
+        key.clone()
+    } else {
+        Key::from(NO_DIFF_KEY_PREFIX.to_string().to_db_key()).join(key)
+    }
+}
+
+fn decode_proof(proof: &[u8]) -> Result<CommitmentProof> {
+    CommitmentProof::decode(proof)
+        .map_err(|e| invalid(format!("Failed to decode a proof: {e}")))
+}
+
+/// Write the chunks of a snapshot of the last committed state. A chunk is
+/// closed as soon as its entries take at least `max_chunk_size` bytes.
+pub fn write_snapshot<H>(
+    state: &FullAccessState<RocksDB, H>,
+    max_chunk_size: usize,
+    mut write_chunk: impl FnMut(Vec<u8>) -> std::io::Result<()>,
+) -> Result<()>
+where
+    H: 'static + StorageHasher + Default,
+{
+    let db = state.db();
+    let tree = &state.in_mem().block.tree;
+
+    let block_state = db.read_last_block()?.ok_or(Error::NoBlock)?;
+    let commit_data_proof = tree
+        .get_sub_root_proof(&StoreType::CommitData)?
+        .encode_to_vec();
+    write_chunk(
+        SnapshotChunk::Block {
+            state: Box::new(block_state),
+            commit_data_proof,
+        }
+        .serialize_to_vec(),
+    )?;
+
+    for store_type in SUBSPACE_STORE_TYPES {
+        let sub_root = Hash::from(tree.sub_root(&store_type));
+        let sub_root_proof =
+            tree.get_sub_root_proof(&store_type)?.encode_to_vec();
+        let mut entries = vec![];
+        let mut size = 0_usize;
+        let mut flush = |entries: Vec<SnapshotEntry>| {
+            write_chunk(
+                SnapshotChunk::SubTree {
+                    store_type,
+                    sub_root,
+                    sub_root_proof: sub_root_proof.clone(),
+                    entries,
+                }
+                .serialize_to_vec(),
+            )
+        };
+        let provable_prefix = store_type.provable_prefix();
+        for (key, value, _gas) in db.iter_prefix(provable_prefix.as_ref()) {
+            let key_size = key.len();
+            let key = Key::parse(key).map_err(merkle_tree::Error::from)?;
+            if is_pending_transfer_key(&key) {
+                continue;
+            }
+            let tree_key = tree_key(&key, (state.diff_key_filter)(&key));
+            if StoreType::sub_key(&tree_key)?.0 != store_type {
+                continue;
+            }
+            let proof = match tree.get_sub_tree_existence_proof(
+                std::slice::from_ref(&tree_key),
+                vec![value.as_slice()],
+            )? {
+                MembershipProof::ICS23(proof) => proof.encode_to_vec(),
+                MembershipProof::BridgePool(_) => {
+                    unreachable!("Pending transfers are skipped")
+                }
+            };
+            size = size
+                .saturating_add(key_size)
+                .saturating_add(value.len())
+                .saturating_add(proof.len());
+            entries.push(SnapshotEntry { key, value, proof });
+            if size >= max_chunk_size {
+                flush(std::mem::take(&mut entries))?;
+                size = 0;
+            }
+        }
+        if !entries.is_empty() {
+            flush(entries)?;
+        }
+    }
+
+    let bridge_pool_prefix = StoreType::BridgePool
+        .provable_prefix()
+        .expect("The bridge pool has a provable prefix");
+    let mut transfers = vec![];
+    for (key, value, _gas) in db.iter_prefix(Some(&bridge_pool_prefix)) {
+        let key = Key::parse(key).map_err(merkle_tree::Error::from)?;
+        if !is_pending_transfer_key(&key) {
+            continue;
+        }
+        let height = BlockHeight::try_from_slice(&tree.get(&key)?)?;
+        transfers.push(BridgePoolEntry { key, value, height });
+    }
+    if !transfers.is_empty() {
+        write_chunk(
+            SnapshotChunk::BridgePool {
+                sub_root: tree.sub_root(&StoreType::BridgePool).into(),
+                sub_root_proof: tree
+                    .get_sub_root_proof(&StoreType::BridgePool)?
+                    .encode_to_vec(),
+                transfers,
+            }
+            .serialize_to_vec(),
+        )?;
+    }
+
+    let max_hashes = (max_chunk_size / REPLAY_PROTECTION_ENTRY_SIZE).max(1);
+    let mut hashes = vec![];
+    for (key, _value, _gas) in db.iter_replay_protection() {
+        // The entries of the last block are under the `current` prefix
+        let hash = key.rsplit_once('/').map_or(key.as_str(), |(_, h)| h);
+        hashes.push(Hash::from_str(hash).expect("Failed hash conversion"));
+        if hashes.len() >= max_hashes {
+            write_chunk(
+                SnapshotChunk::ReplayProtection(std::mem::take(&mut hashes))
+                    .serialize_to_vec(),
+            )?;
+        }
+    }
+    if !hashes.is_empty() {
+        write_chunk(
+            SnapshotChunk::ReplayProtection(hashes).serialize_to_vec(),
+        )?;
+    }
+
+    Ok(())
+}
+
+/// The progress of the restoration of a sub-tree
+#[derive(Debug)]
+struct SubTreeProgress {
+    store_type: StoreType,
+    sub_root: Hash,
+    last_key: Option<String>,
+}
+
+/// Restores the state from the chunks of a snapshot into a new DB, verifying
+/// each of them against the app hash at the height of the snapshot
+#[derive(Debug)]
+pub struct SnapshotRestore<H: StorageHasher + Default> {
+    db: RocksDB,
+    db_path: PathBuf,
+    height: BlockHeight,
+    app_hash: Hash,
+    tree: MerkleTree<H>,
+    block_state: Option<BlockStateRead>,
+    /// Whether the commit data covers all the fields of the block state,
+    /// which is the case once the replay protection set commitment has been
+    /// initialized by a migration
+    is_block_state_committed: bool,
+    sub_tree: Option<SubTreeProgress>,
+    last_rank: Option<usize>,
+    /// The commitment to the replay protection entries applied so far
+    replay_protection: SetCommitment,
+}
+
+impl<H: StorageHasher + Default> SnapshotRestore<H> {
+    /// Start restoring a snapshot into a new DB at the given path. Any DB
+    /// left at this path by a previous attempt is removed.
+    pub fn new(
+        db_path: PathBuf,
+        height: BlockHeight,
+        app_hash: Hash,
+    ) -> Result<Self> {
+        if db_path.exists() {
+            std::fs::remove_dir_all(&db_path)?;
+        }
+        let db = rocksdb::open(&db_path, false, None)?;
+        Ok(Self {
+            db,
+            db_path,
+            height,
+            app_hash,
+            tree: MerkleTree::default(),
+            block_state: None,
+            is_block_state_committed: false,
+            sub_tree: None,
+            last_rank: None,
+            replay_protection: SetCommitment::default(),
+        })
+    }
+
+    /// Verify the given chunk and write its content to the DB. The chunks
+    /// must be applied in the order in which they were written.
+    pub fn apply_chunk(&mut self, chunk: &[u8]) -> Result<()> {
+        let chunk =
+            SnapshotChunk::try_from_slice(chunk).map_err(Error::Decoding)?;
+        let rank = chunk
+            .rank()
+            .ok_or_else(|| invalid("Unexpected sub-tree in the snapshot"))?;
+        match self.last_rank {
+            None if rank != 0 => {
+                return Err(invalid(
+                    "The snapshot must start with the block state",
+                ));
+            }
+            Some(last)
+                if rank < last || (rank == last && !chunk.is_repeatable()) =>
+            {
+                return Err(invalid("The chunk is out of order"));
+            }
+            _ => {}
+        }
+        if self.last_rank != Some(rank) {
+            self.complete_sub_tree()?;
+        }
+        self.last_rank = Some(rank);
+
+        match chunk {
+            SnapshotChunk::Block {
+                state,
+                commit_data_proof,
+            } => self.apply_block(*state, &commit_data_proof),
+            SnapshotChunk::SubTree {
+                store_type,
+                sub_root,
+                sub_root_proof,
+                entries,
+            } => self.apply_sub_tree(
+                store_type,
+                sub_root,
+                &sub_root_proof,
+                entries,
+            ),
+            SnapshotChunk::BridgePool {
+                sub_root,
+                sub_root_proof,
+                transfers,
+            } => self.apply_bridge_pool(sub_root, &sub_root_proof, transfers),
+            SnapshotChunk::ReplayProtection(hashes) => {
+                self.apply_replay_protection(hashes)
+            }
+        }
+    }
+
+    /// Check that the restored state matches the app hash and write the
+    /// state of the block. Returns the path of the restored DB.
+    pub fn finish(mut self) -> Result<PathBuf> {
+        self.complete_sub_tree()?;
+        let BlockStateRead {
+            height,
+            time,
+            epoch,
+            pred_epochs,
+            next_epoch_min_start_height,
+            next_epoch_min_start_time,
+            update_epoch_blocks_delay,
+            address_gen,
+            results,
+            conversion_state,
+            ethereum_height,
+            eth_events_queue,
+            commit_only_data,
+        } = self
+            .block_state
+            .take()
+            .ok_or_else(|| invalid("The snapshot has no block state"))?;
+        if Hash::from(self.tree.root()) != self.app_hash {
+            return Err(invalid(
+                "The state restored from the snapshot doesn't match the app \
+                 hash",
+            ));
+        }
+        // The set commitment has been verified with the rest of the subspace.
+        // Before it gets initialized by a migration, the replay protection
+        // entries cannot be verified.
+        match self
+            .db
+            .read_subspace_val(&replay_protection::set_commitment_key())?
+        {
+            Some(bytes) => {
+                if !self.is_block_state_committed {
+                    return Err(invalid(
+                        "The block state is not committed to the app hash",
+                    ));
+                }
+                let set_commitment = SetCommitment::try_from_bytes(&bytes)
+                    .ok_or_else(|| {
+                        invalid("Invalid replay protection commitment")
+                    })?;
+                if set_commitment != self.replay_protection {
+                    return Err(invalid(
+                        "The replay protection entries don't match their \
+                         commitment",
+                    ));
+                }
+            }
+            None if self.is_block_state_committed => {
+                return Err(invalid(
+                    "The replay protection set commitment is missing",
+                ));
+            }
+            None => {}
+        }
+
+        let mut batch = RocksDB::batch();
+        self.db.add_block_to_batch(
+            BlockStateWrite {
+                merkle_tree_stores: self.tree.stores(),
+                header: None,
+                time,
+                height,
+                epoch,
+                pred_epochs: &pred_epochs,
+                next_epoch_min_start_height,
+                next_epoch_min_start_time,
+                update_epoch_blocks_delay,
+                address_gen: &address_gen,
+                results: &results,
+                conversion_state: &conversion_state,
+                ethereum_height: ethereum_height.as_ref(),
+                eth_events_queue: &eth_events_queue,
+                commit_only_data: &commit_only_data,
+            },
+            &mut batch,
+            true,
+        )?;
+        // The history of the blocks before the snapshot is not available
+        if let Some(pruned) = height.prev_height().filter(|h| h.0 > 0) {
+            self.db.write_pruned_height(&mut batch, pruned)?;
+        }
+        self.db.exec_batch(batch)?;
+        self.db.flush(true)?;
+
+        let Self { db, db_path, .. } = self;
+        drop(db);
+        Ok(db_path)
+    }
+
+    fn check_sub_root(
+        &self,
+        store_type: &StoreType,
+        sub_root: Hash,
+        proof: &[u8],
+    ) -> Result<()> {
+        let proof = decode_proof(proof)?;
+        if verify_sub_root::<H>(
+            &self.app_hash.into(),
+            store_type,
+            &sub_root.into(),
+            &proof,
+        ) {
+            Ok(())
+        } else {
+            Err(invalid(format!(
+                "The root of the {store_type} sub-tree is not committed to \
+                 the app hash"
+            )))
+        }
+    }
+
+    /// Check that all the keys of the sub-tree being restored were applied
+    fn complete_sub_tree(&mut self) -> Result<()> {
+        if let Some(SubTreeProgress {
+            store_type,
+            sub_root,
+            ..
+        }) = self.sub_tree.take()
+        {
+            if Hash::from(self.tree.sub_root(&store_type)) != sub_root {
+                return Err(invalid(format!(
+                    "The snapshot is missing keys of the {store_type} sub-tree"
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    fn apply_block(
+        &mut self,
+        state: BlockStateRead,
+        commit_data_proof: &[u8],
+    ) -> Result<()> {
+        if state.height != self.height {
+            return Err(invalid(format!(
+                "The block state is at height {}, expected {}",
+                state.height, self.height
+            )));
+        }
+        // The commit data covers all the fields of the block state, unless
+        // the chain hasn't been migrated to commit to them yet, in which case
+        // `finish` checks that the set commitment isn't initialized either
+        let commit_data = state.commit_data();
+        let (commit_data, is_block_state_committed) = if self
+            .check_sub_root(
+                &StoreType::CommitData,
+                Hash::sha256(&commit_data),
+                commit_data_proof,
+            )
+            .is_ok()
+        {
+            (commit_data, true)
+        } else {
+            let commit_data = state.commit_only_data.serialize();
+            self.check_sub_root(
+                &StoreType::CommitData,
+                Hash::sha256(&commit_data),
+                commit_data_proof,
+            )?;
+            (commit_data, false)
+        };
+        self.tree.update_commit_data(commit_data)?;
+        self.block_state = Some(state);
+        self.is_block_state_committed = is_block_state_committed;
+        Ok(())
+    }
+
+    fn apply_replay_protection(&mut self, hashes: Vec<Hash>) -> Result<()> {
+        let mut batch = RocksDB::batch();
+        let mut chunk_hashes = HashSet::new();
+        for hash in hashes {
+            // Repeating an entry could cancel out its contribution to the
+            // set commitment
+            if !chunk_hashes.insert(hash)
+                || self.db.has_replay_protection_entry(&hash)?
+            {
+                return Err(invalid(format!(
+                    "Repeated replay protection entry {hash}"
+                )));
+            }
+            self.replay_protection.insert(&hash);
+            self.db.write_replay_protection_entry(
+                &mut batch,
+                &replay_protection::key(&hash),
+            )?;
+        }
+        Ok(self.db.exec_batch(batch)?)
+    }
+
+    fn apply_sub_tree(
+        &mut self,
+        store_type: StoreType,
+        sub_root: Hash,
+        sub_root_proof: &[u8],
+        entries: Vec<SnapshotEntry>,
+    ) -> Result<()> {
+        self.check_sub_root(&store_type, sub_root, sub_root_proof)?;
+        let mut last_key = match self.sub_tree.take() {
+            Some(progress) if progress.store_type == store_type => {
+                if progress.sub_root != sub_root {
+                    return Err(invalid(format!(
+                        "Conflicting roots of the {store_type} sub-tree"
+                    )));
+                }
+                progress.last_key
+            }
+            _ => None,
+        };
+
+        let mut tree_keys = Vec::with_capacity(entries.len());
+        for SnapshotEntry { key, value, proof } in &entries {
+            let key_str = key.to_string();
+            if last_key.as_ref().is_some_and(|last| *last >= key_str) {
+                return Err(invalid("The keys are not sorted"));
+            }
+            let tree_key = tree_key(key, store_type != StoreType::NoDiff);
+            let in_sub_tree = StoreType::sub_key(&tree_key)
+                .is_ok_and(|(st, _)| st == store_type);
+            if !in_sub_tree || is_pending_transfer_key(key) {
+                return Err(invalid(format!(
+                    "The key {key} doesn't belong to the {store_type} sub-tree"
+                )));
+            }
+            let proof = decode_proof(proof)?;
+            if !verify_sub_tree_existence::<H>(
+                &sub_root.into(),
+                &tree_key,
+                value,
+                &proof,
+            )? {
+                return Err(invalid(format!("Invalid proof of the key {key}")));
+            }
+            last_key = Some(key_str);
+            tree_keys.push(tree_key);
+        }
+
+        let mut batch = RocksDB::batch();
+        let persist_diffs = store_type != StoreType::NoDiff;
+        for (SnapshotEntry { key, value, .. }, tree_key) in
+            entries.iter().zip(&tree_keys)
+        {
+            self.db.batch_write_subspace_val(
+                &mut batch,
+                self.height,
+                key,
+                value,
+                persist_diffs,
+            )?;
+            self.tree.update(tree_key, value)?;
+        }
+        self.db.exec_batch(batch)?;
+        self.sub_tree = Some(SubTreeProgress {
+            store_type,
+            sub_root,
+            last_key,
+        });
+        Ok(())
+    }
+
+    fn apply_bridge_pool(
+        &mut self,
+        sub_root: Hash,
+        sub_root_proof: &[u8],
+        transfers: Vec<BridgePoolEntry>,
+    ) -> Result<()> {
+        self.check_sub_root(&StoreType::BridgePool, sub_root, sub_root_proof)?;
+        let mut last_key: Option<String> = None;
+        for BridgePoolEntry { key, value, height } in &transfers {
+            let key_str = key.to_string();
+            if last_key.as_ref().is_some_and(|last| *last >= key_str) {
+                return Err(invalid("The keys are not sorted"));
+            }
+            let transfer = PendingTransfer::try_from_slice(value)
+                .map_err(Error::Decoding)?;
+            if !is_pending_transfer_key(key)
+                || get_pending_key(&transfer) != *key
+            {
+                return Err(invalid(format!(
+                    "The key {key} doesn't match its pending transfer"
+                )));
+            }
+            self.tree.update(key, height.serialize_to_vec())?;
+            last_key = Some(key_str);
+        }
+        // The bridge pool has no existence proofs, so its sub-tree is
+        // verified as a whole
+        if Hash::from(self.tree.sub_root(&StoreType::BridgePool)) != sub_root {
+            return Err(invalid(
+                "The pending transfers don't match the root of the bridge pool",
+            ));
+        }
+
+        let mut batch = RocksDB::batch();
+        for BridgePoolEntry { key, value, .. } in &transfers {
+            self.db.batch_write_subspace_val(
+                &mut batch,
+                self.height,
+                key,
+                value,
+                true,
+            )?;
+        }
+        Ok(self.db.exec_batch(batch)?)
+    }
+}
+
+#[allow(clippy::arithmetic_side_effects)]
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use namada_sdk::chain::ChainId;
+    use namada_sdk::state::{Sha256Hasher, StorageWrite};
+    use namada_sdk::storage::InnerEthEventsQueue;
+    use namada_sdk::{address, token};
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::shell::is_key_diff_storable;
+
+    const MAX_CHUNK_SIZE: usize = 256;
+
+    fn open_state(path: &Path) -> FullAccessState<RocksDB, Sha256Hasher> {
+        FullAccessState::open(
+            path,
+            None,
+            ChainId::default(),
+            address::testing::nam(),
+            None,
+            is_key_diff_storable,
+        )
+    }
+
+    /// Commit a block with keys in every sub-tree with existence proofs and
+    /// a replay protection entry. If `is_migrated`, the replay protection set
+    /// commitment is initialized first, as done by the migration that
+    /// commits the block state and the replay protection entries.
+    fn make_state(
+        path: &Path,
+        tx_hash: Hash,
+        is_migrated: bool,
+    ) -> FullAccessState<RocksDB, Sha256Hasher> {
+        let mut state = open_state(path);
+        state.in_mem_mut().block.height = BlockHeight::first();
+        if is_migrated {
+            state
+                .db_write(
+                    &replay_protection::set_commitment_key(),
+                    SetCommitment::default().to_bytes(),
+                )
+                .unwrap();
+        }
+        let mut keys = vec![
+            Key::from(address::POS.to_db_key())
+                .push(&"key".to_owned())
+                .unwrap(),
+            Key::from(address::IBC.to_db_key())
+                .push(&"key".to_owned())
+                .unwrap(),
+            token::storage_key::masp_commitment_tree_key(),
+        ];
+        keys.extend(
+            (0..20).map(|i| Key::parse(format!("account/{i}")).unwrap()),
+        );
+        for (i, key) in keys.iter().enumerate() {
+            state.write(key, [u8::try_from(i).unwrap(); 32]).unwrap();
+        }
+        state.write_log_mut().write_tx_hash(tx_hash).unwrap();
+        state.commit_block().unwrap();
+        state
+    }
+
+    fn snapshot_chunks(
+        state: &FullAccessState<RocksDB, Sha256Hasher>,
+    ) -> Vec<Vec<u8>> {
+        let mut chunks = vec![];
+        write_snapshot(state, MAX_CHUNK_SIZE, |chunk| {
+            chunks.push(chunk);
+            Ok(())
+        })
+        .unwrap();
+        chunks
+    }
+
+    /// Test that a snapshot restores the same state
+    #[test]
+    fn test_snapshot_roundtrip() {
+        let dir = tempdir().unwrap();
+        let tx_hash = Hash::sha256(b"tx");
+        let state = make_state(&dir.path().join("db"), tx_hash, true);
+        let chunks = snapshot_chunks(&state);
+        // the account keys don't fit in a single chunk
+        assert!(chunks.len() > SUBSPACE_STORE_TYPES.len() + 2);
+
+        let mut restore = SnapshotRestore::<Sha256Hasher>::new(
+            dir.path().join("restore"),
+            BlockHeight::first(),
+            state.in_mem().merkle_root().into(),
+        )
+        .unwrap();
+        for chunk in &chunks {
+            restore.apply_chunk(chunk).unwrap();
+        }
+        let restored = open_state(&restore.finish().unwrap());
+
+        assert_eq!(
+            restored.in_mem().merkle_root(),
+            state.in_mem().merkle_root()
+        );
+        assert_eq!(
+            restored.in_mem().get_last_block_height(),
+            BlockHeight::first()
+        );
+        for (key, value, _gas) in state.db().iter_prefix(None) {
+            let key = Key::parse(key).unwrap();
+            assert_eq!(
+                restored.db().read_subspace_val(&key).unwrap(),
+                Some(value)
+            );
+        }
+        assert!(restored.db().has_replay_protection_entry(&tx_hash).unwrap());
+    }
+
+    /// Test that invalid, missing or out-of-order chunks are rejected
+    #[test]
+    fn test_snapshot_invalid_chunks() {
+        let dir = tempdir().unwrap();
+        let state =
+            make_state(&dir.path().join("db"), Hash::sha256(b"tx"), true);
+        let chunks = snapshot_chunks(&state);
+        let restore_path = dir.path().join("restore");
+        let new_restore = |app_hash: Hash| {
+            SnapshotRestore::<Sha256Hasher>::new(
+                restore_path.clone(),
+                BlockHeight::first(),
+                app_hash,
+            )
+            .unwrap()
+        };
+        let app_hash = Hash::from(state.in_mem().merkle_root());
+
+        // a snapshot of another state
+        let mut restore = new_restore(Hash::sha256(b"other"));
+        let err = restore.apply_chunk(&chunks[0]).unwrap_err();
+        assert!(err.is_invalid_snapshot());
+        drop(restore);
+
+        // a tampered value
+        let mut restore = new_restore(app_hash);
+        restore.apply_chunk(&chunks[0]).unwrap();
+        let mut chunk = SnapshotChunk::try_from_slice(&chunks[1]).unwrap();
+        let SnapshotChunk::SubTree { entries, .. } = &mut chunk else {
+            panic!("Expected a sub-tree chunk");
+        };
+        entries[0].value.push(0);
+        let err = restore.apply_chunk(&chunk.serialize_to_vec()).unwrap_err();
+        assert!(err.is_invalid_snapshot());
+        drop(restore);
+
+        // a missing chunk
+        let mut restore = new_restore(app_hash);
+        let result = chunks
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| *i != 1)
+            .try_for_each(|(_, chunk)| restore.apply_chunk(chunk))
+            .and_then(|()| restore.finish().map(|_| ()));
+        assert!(result.unwrap_err().is_invalid_snapshot());
+
+        // chunks out of order
+        let mut restore = new_restore(app_hash);
+        let err = restore.apply_chunk(&chunks[1]).unwrap_err();
+        assert!(err.is_invalid_snapshot());
+        restore.apply_chunk(&chunks[0]).unwrap();
+        let err = restore.apply_chunk(&chunks[0]).unwrap_err();
+        assert!(err.is_invalid_snapshot());
+    }
+
+    /// Test that tampering with any field of the block state is rejected
+    #[test]
+    fn test_snapshot_tampered_block_state() {
+        let dir = tempdir().unwrap();
+        let state =
+            make_state(&dir.path().join("db"), Hash::sha256(b"tx"), true);
+        let chunks = snapshot_chunks(&state);
+        let app_hash = Hash::from(state.in_mem().merkle_root());
+
+        let tampers: [(&str, fn(&mut BlockStateRead)); 11] = [
+            ("time", |state| state.time = state.time.next_second()),
+            ("epoch", |state| state.epoch = state.epoch.next()),
+            ("pred_epochs", |state| {
+                state.pred_epochs.new_epoch(BlockHeight(2))
+            }),
+            ("next_epoch_min_start_height", |state| {
+                state.next_epoch_min_start_height =
+                    state.next_epoch_min_start_height.next_height()
+            }),
+            ("next_epoch_min_start_time", |state| {
+                state.next_epoch_min_start_time =
+                    state.next_epoch_min_start_time.next_second()
+            }),
+            ("update_epoch_blocks_delay", |state| {
+                state.update_epoch_blocks_delay = Some(1)
+            }),
+            ("address_gen", |state| {
+                state.address_gen.generate_address(b"tamper");
+            }),
+            ("results", |state| state.results.reject(0)),
+            ("conversion_state", |state| {
+                state.conversion_state.normed_inflation = Some(1)
+            }),
+            ("ethereum_height", |state| {
+                state.ethereum_height = Some(1_u64.into())
+            }),
+            ("eth_events_queue", |state| {
+                state.eth_events_queue.transfers_to_namada =
+                    InnerEthEventsQueue::new_at(5.into())
+            }),
+        ];
+        for (field, tamper) in tampers {
+            let mut restore = SnapshotRestore::<Sha256Hasher>::new(
+                dir.path().join("restore"),
+                BlockHeight::first(),
+                app_hash,
+            )
+            .unwrap();
+            let mut chunk = SnapshotChunk::try_from_slice(&chunks[0]).unwrap();
+            let SnapshotChunk::Block { state, .. } = &mut chunk else {
+                panic!("Expected a block chunk");
+            };
+            tamper(state);
+            let err = restore
+                .apply_chunk(&chunk.serialize_to_vec())
+                .expect_err(field);
+            assert!(err.is_invalid_snapshot(), "{field}");
+        }
+    }
+
+    /// Test that missing, extra or repeated replay protection entries are
+    /// rejected
+    #[test]
+    fn test_snapshot_tampered_replay_protection() {
+        let dir = tempdir().unwrap();
+        let tx_hash = Hash::sha256(b"tx");
+        let state = make_state(&dir.path().join("db"), tx_hash, true);
+        let chunks = snapshot_chunks(&state);
+        let app_hash = Hash::from(state.in_mem().merkle_root());
+        let (last_chunk, chunks) = chunks.split_last().unwrap();
+        assert!(matches!(
+            SnapshotChunk::try_from_slice(last_chunk).unwrap(),
+            SnapshotChunk::ReplayProtection(hashes) if hashes == vec![tx_hash]
+        ));
+
+        let other_hash = Hash::sha256(b"other tx");
+        for hashes in [
+            vec![],
+            vec![other_hash],
+            vec![tx_hash, other_hash],
+            vec![tx_hash, tx_hash],
+        ] {
+            let mut restore = SnapshotRestore::<Sha256Hasher>::new(
+                dir.path().join("restore"),
+                BlockHeight::first(),
+                app_hash,
+            )
+            .unwrap();
+            let result = chunks
+                .iter()
+                .cloned()
+                .chain([SnapshotChunk::ReplayProtection(hashes.clone())
+                    .serialize_to_vec()])
+                .try_for_each(|chunk| restore.apply_chunk(&chunk))
+                .and_then(|()| restore.finish().map(|_| ()));
+            assert!(result.unwrap_err().is_invalid_snapshot(), "{hashes:?}");
+        }
+    }
+
+    /// Test that a snapshot of a chain that hasn't been migrated to commit
+    /// to the block state and the replay protection entries is restored
+    #[test]
+    fn test_snapshot_not_migrated() {
+        let dir = tempdir().unwrap();
+        let tx_hash = Hash::sha256(b"tx");
+        let state = make_state(&dir.path().join("db"), tx_hash, false);
+        assert!(state
+            .db()
+            .read_subspace_val(&replay_protection::set_commitment_key())
+            .unwrap()
+            .is_none());
+        let chunks = snapshot_chunks(&state);
+        let app_hash = Hash::from(state.in_mem().merkle_root());
+        let mut restore = SnapshotRestore::<Sha256Hasher>::new(
+            dir.path().join("restore"),
+            BlockHeight::first(),
+            app_hash,
+        )
+        .unwrap();
+        for chunk in &chunks {
+            restore.apply_chunk(chunk).unwrap();
+        }
+        let restored = open_state(&restore.finish().unwrap());
+        assert_eq!(
+            restored.in_mem().merkle_root(),
+            state.in_mem().merkle_root()
+        );
+        assert!(restored.db().has_replay_protection_entry(&tx_hash).unwrap());
+    }
+}
@@ -48,3 +48,118 @@ pub fn current_prefix() -> Key {
 pub fn current_key(hash: &Hash) -> Key {
     current_prefix().push(&hash.to_string()).expect(ERROR_MSG)
 }
+
+/// Get the key under which we store an order-independent commitment to the
+/// set of all replay protection entries, against which an unordered list of
+/// entries can be checked.
+///
+/// The set commitment is only maintained once it has been initialized by a
+/// migration with the commitment to the existing entries, precomputed offline
+/// (see the `ledger replay-protection-migration` command). Its presence also
+/// activates the commitment of the block state to the app hash.
+pub fn set_commitment_key() -> Key {
+    Key::from(DbKeySeg::AddressSeg(Address::Internal(
+        InternalAddress::ReplayProtection,
+    )))
+    .push(&"set_commitment".to_string())
+    .expect("Should be able to form this key")
+}
+
+/// Check if the given key is the key of the set commitment
+pub fn is_set_commitment_key(key: &Key) -> bool {
+    *key == set_commitment_key()
+}
+
+/// The number of 16-bit lanes of a [`SetCommitment`]
+const SET_COMMITMENT_LANES: usize = 1024;
+
+/// The size of an encoded [`SetCommitment`]
+const SET_COMMITMENT_SIZE: usize = SET_COMMITMENT_LANES * 2;
+
+/// The number of lanes filled by a single SHA-256 digest
+const LANES_PER_DIGEST: usize = 16;
+
+/// A homomorphic commitment to a set of tx hashes (LtHash16): the lane-wise
+/// sum modulo 2^16 of the expansion of each hash into 1024 lanes. Unlike the
+/// running commitment, it doesn't depend on the order in which the hashes were
+/// added and can be recomputed from the entries in any order, while finding
+/// another set of hashes with the same commitment is infeasible.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SetCommitment([u16; SET_COMMITMENT_LANES]);
+
+impl Default for SetCommitment {
+    fn default() -> Self {
+        Self([0; SET_COMMITMENT_LANES])
+    }
+}
+
+impl SetCommitment {
+    /// Add the given hash to the committed set
+    pub fn insert(&mut self, hash: &Hash) {
+        // Expand the hash with SHA-256 in counter mode
+        for (counter, lanes) in
+            (0..=u8::MAX).zip(self.0.chunks_exact_mut(LANES_PER_DIGEST))
+        {
+            let digest = Hash::sha256([&hash.0[..], &[counter]].concat());
+            for (lane, bytes) in lanes.iter_mut().zip(digest.0.chunks_exact(2))
+            {
+                let bytes = bytes.try_into().expect("Chunks have 2 bytes");
+                *lane = lane.wrapping_add(u16::from_le_bytes(bytes));
+            }
+        }
+    }
+
+    /// Encode the commitment
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.0.iter().flat_map(|lane| lane.to_le_bytes()).collect()
+    }
+
+    /// Decode a commitment, if the given bytes have the right length
+    pub fn try_from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() != SET_COMMITMENT_SIZE {
+            return None;
+        }
+        let mut commitment = Self::default();
+        for (lane, bytes) in commitment.0.iter_mut().zip(bytes.chunks_exact(2))
+        {
+            let bytes = bytes.try_into().expect("Chunks have 2 bytes");
+            *lane = u16::from_le_bytes(bytes);
+        }
+        Some(commitment)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_commitment() {
+        let hashes: Vec<_> = (0_u8..4).map(|i| Hash::sha256([i])).collect();
+        let commit = |hashes: &[Hash]| {
+            let mut commitment = SetCommitment::default();
+            for hash in hashes {
+                commitment.insert(hash);
+            }
+            commitment
+        };
+
+        // The commitment doesn't depend on the order of the hashes
+        let commitment = commit(&hashes);
+        let mut reversed = hashes.clone();
+        reversed.reverse();
+        assert_eq!(commit(&reversed), commitment);
+
+        // Dropping or adding a hash changes the commitment
+        assert_ne!(commit(&hashes[1..]), commitment);
+        let mut added = hashes.clone();
+        added.push(Hash::sha256([4]));
+        assert_ne!(commit(&added), commitment);
+
+        assert_eq!(
+            SetCommitment::try_from_bytes(&commitment.to_bytes()),
+            Some(commitment)
+        );
+        assert_eq!(SetCommitment::try_from_bytes(&[0; 2]), None);
+    }
+}
@@ -15,7 +15,7 @@ use namada_core::dec::Dec;
 use namada_core::ethereum_events::EthAddress;
 use namada_core::keccak::KeccakHash;
 use namada_core::key::{common, SchemeType};
-use namada_core::masp::{MaspEpoch, PaymentAddress};
+use namada_core::masp::{ExtendedViewingKey, MaspEpoch, PaymentAddress};
 use namada_core::time::DateTimeUtc;
 use namada_core::{storage, token};
 use namada_governance::cli::onchain::{
@@ -31,6 +31,8 @@ use zeroize::Zeroizing;
 use crate::eth_bridge::bridge_pool;
 use crate::ibc::core::host::types::identifiers::{ChannelId, PortId};
 use crate::signing::SigningTxData;
+use crate::wallet::address_book::{BookFormat, ContactAddress};
+use crate::wallet::slip39::ShareGroups;
 use crate::wallet::{DatedSpendingKey, DatedViewingKey};
 use crate::{rpc, tx, Namada};
 
@@ -1369,6 +1371,31 @@ impl RevealPk {
     }
 }
 
+/// Sweep the balances of the expired disposable keys
+#[derive(Clone, Debug)]
+pub struct SweepDisposable<C: NamadaTypes = SdkTypes> {
+    /// Common tx arguments
+    pub tx: Tx<C>,
+    /// The address receiving the swept balances
+    pub target: C::Address,
+    /// The token to sweep, the native token if not set
+    pub token: Option<C::Address>,
+    /// Path to the TX WASM code file
+    pub tx_code_path: PathBuf,
+}
+
+impl<C: NamadaTypes> TxBuilder<C> for SweepDisposable<C> {
+    fn tx<F>(self, func: F) -> Self
+    where
+        F: FnOnce(Tx<C>) -> Tx<C>,
+    {
+        SweepDisposable {
+            tx: func(self.tx),
+            ..self
+        }
+    }
+}
+
 /// Generate shell completions
 #[derive(Clone, Debug)]
 pub struct Complete {
@@ -1617,6 +1644,25 @@ pub struct QueryShieldingRewardsEstimate<C: NamadaTypes = SdkTypes> {
     pub owner: C::ViewingKey,
 }
 
+/// Project the MASP rewards of a token over future MASP epochs
+#[derive(Clone, Debug)]
+pub struct QueryShieldingRewardsProjection<C: NamadaTypes = SdkTypes> {
+    /// Common query args
+    pub query: Query<C>,
+    /// Address of the token whose rewards to project
+    pub token: C::Address,
+    /// Viewing key whose shielded balance of the token to project
+    pub owner: Option<C::ViewingKey>,
+    /// Number of future MASP epochs to project
+    pub epochs: u64,
+    /// Assumed change of the amount of the token locked in the shielded pool
+    /// every MASP epoch, in whole units of the token
+    pub locked_amount_change: Option<Dec>,
+    /// Assumed rate of change of the amount of the token locked in the
+    /// shielded pool every MASP epoch
+    pub locked_amount_rate: Option<Dec>,
+}
+
 /// Query historical transfer(s)
 #[derive(Clone, Debug)]
 pub struct QueryTransfers<C: NamadaTypes = SdkTypes> {
@@ -2170,8 +2216,16 @@ pub struct ShieldedSync<C: NamadaTypes = SdkTypes> {
     /// If present, the shielded sync will be performed
     /// using data retrieved from the given indexer
     pub with_indexer: Option<C::MaspIndexerAddress>,
+    /// Path to a local MASP snapshot file
+    ///
+    /// If present, the shielded sync will be performed
+    /// using data read from the given snapshot
+    pub with_snapshot: Option<PathBuf>,
     /// Wait for the last query height.
     pub wait_for_last_query_height: bool,
+    /// Keep syncing new blocks at the given interval, until
+    /// interrupted, reporting the progress of each viewing key
+    pub watch: Option<StdDuration>,
     /// Maximum number of fetch jobs that will ever
     /// execute concurrently during the shielded sync.
     pub max_concurrent_fetches: usize,
@@ -2182,6 +2236,30 @@ pub struct ShieldedSync<C: NamadaTypes = SdkTypes> {
     pub retry_strategy: RetryStrategy,
 }
 
+#[derive(Clone, Debug)]
+/// Export a snapshot of the data required by shielded sync,
+/// covering the given range of block heights
+pub struct ExportMaspSnapshot<C: NamadaTypes = SdkTypes> {
+    /// The ledger address
+    pub ledger_address: C::ConfigRpcTendermintAddress,
+    /// First height covered by the snapshot. Defaults to the first block
+    pub from_height: Option<BlockHeight>,
+    /// Last height covered by the snapshot. Defaults to most recent
+    pub to_height: Option<BlockHeight>,
+    /// Address of a `namada-masp-indexer` live instance
+    ///
+    /// If present, the snapshot will be built from data
+    /// retrieved from the given indexer
+    pub with_indexer: Option<C::MaspIndexerAddress>,
+    /// Path of the snapshot file to write
+    pub output: Option<PathBuf>,
+    /// Maximum number of fetch jobs that will ever
+    /// execute concurrently while building the snapshot.
+    pub max_concurrent_fetches: usize,
+    /// Number of blocks fetched per concurrent fetch job.
+    pub block_batch_size: usize,
+}
+
 /// Query PoS commission rate
 #[derive(Clone, Debug)]
 pub struct QueryCommissionRate<C: NamadaTypes = SdkTypes> {
@@ -2307,6 +2385,8 @@ pub struct Tx<C: NamadaTypes = SdkTypes> {
     pub dry_run: bool,
     /// Simulate applying both the wrapper and inner transactions
     pub dry_run_wrapper: bool,
+    /// Trace the execution of the inner transactions when simulating
+    pub dry_run_trace: bool,
     /// Dump the raw transaction bytes to file
     pub dump_tx: bool,
     /// Dump the wrapper transaction bytes to file
@@ -2353,6 +2433,15 @@ pub struct Tx<C: NamadaTypes = SdkTypes> {
     pub use_device: bool,
     /// Hardware Wallet transport - HID (USB) or TCP
     pub device_transport: DeviceTransport,
+    /// Address of a remote MASP prover server. If absent, the proofs of
+    /// shielded transactions are generated locally
+    pub masp_prover: Option<String>,
+    /// Serialized key share of a shielded multisig. The spends of the
+    /// multisig are left unauthorized, for its participants to sign them
+    pub shielded_key_share: Option<C::Data>,
+    /// Address of a remote signer to sign with the keys that are not in the
+    /// wallet
+    pub remote_signer: Option<String>,
 }
 
 /// Hardware Wallet transport - HID (USB) or TCP
@@ -2397,6 +2486,10 @@ pub trait TxBuilder<C: NamadaTypes>: Sized {
             ..x
         })
     }
+    /// Trace the execution of the inner transactions when simulating
+    fn dry_run_trace(self, dry_run_trace: bool) -> Self {
+        self.tx(|x| Tx { dry_run_trace, ..x })
+    }
     /// Dump the transaction bytes to file
     fn dump_tx(self, dump_tx: bool) -> Self {
         self.tx(|x| Tx { dump_tx, ..x })
@@ -2510,6 +2603,28 @@ pub trait TxBuilder<C: NamadaTypes>: Sized {
             ..x
         })
     }
+    /// Generate the MASP proofs with the given remote prover server
+    fn masp_prover(self, masp_prover: String) -> Self {
+        self.tx(|x| Tx {
+            masp_prover: Some(masp_prover),
+            ..x
+        })
+    }
+    /// Leave the spends of a shielded multisig unauthorized, given the key
+    /// share of one of its participants
+    fn shielded_key_share(self, shielded_key_share: C::Data) -> Self {
+        self.tx(|x| Tx {
+            shielded_key_share: Some(shielded_key_share),
+            ..x
+        })
+    }
+    /// Sign with the given remote signer the keys that are not in the wallet
+    fn remote_signer(self, remote_signer: String) -> Self {
+        self.tx(|x| Tx {
+            remote_signer: Some(remote_signer),
+            ..x
+        })
+    }
 }
 
 impl<C: NamadaTypes> TxBuilder<C> for Tx<C> {
@@ -2545,6 +2660,11 @@ pub struct KeyGen {
     /// Optional block height after which this key was created.
     /// Only used for MASP keys.
     pub birthday: Option<BlockHeight>,
+    /// Split the HD seed into SLIP-39 shares of the given groups instead of
+    /// generating a mnemonic code
+    pub shares: Option<ShareGroups>,
+    /// The number of groups of shares required to recover the HD seed
+    pub share_group_threshold: Option<u8>,
 }
 
 /// Wallet restore key and implicit address arguments
@@ -2575,6 +2695,8 @@ pub struct KeyDerive {
     /// Optional blockheight after which this key was created.
     /// Only used for MASP keys
     pub birthday: Option<BlockHeight>,
+    /// Recover the HD seed from SLIP-39 shares instead of a mnemonic code
+    pub from_shares: bool,
 }
 
 /// Wallet list arguments
@@ -2668,6 +2790,143 @@ pub struct KeyAddressRemove {
     pub do_it: bool,
 }
 
+/// Wallet store decryption arguments
+#[derive(Clone, Debug)]
+pub struct StoreDecrypt {
+    /// Confirmation to save the store in plaintext
+    pub do_it: bool,
+}
+
+/// Wallet watch-only account add arguments
+#[derive(Clone, Debug)]
+pub struct WatchOnlyAdd {
+    /// Account alias
+    pub alias: String,
+    /// Whether to force overwrite the alias
+    pub alias_force: bool,
+    /// The address of the account
+    pub address: Address,
+    /// The public keys of the account
+    pub public_keys: Vec<common::PublicKey>,
+    /// The viewing key of the account
+    pub viewing_key: Option<ExtendedViewingKey>,
+    /// Optional block height after which the viewing key was created
+    pub birthday: Option<BlockHeight>,
+}
+
+/// Wallet address book entry add arguments
+#[derive(Clone, Debug)]
+pub struct AddressBookAdd {
+    /// Contact alias
+    pub alias: String,
+    /// Whether to force overwrite the alias
+    pub alias_force: bool,
+    /// The transparent or payment address of the contact
+    pub address: ContactAddress,
+    /// A human readable label
+    pub label: Option<String>,
+    /// Tags to group the contact by
+    pub tags: Vec<String>,
+    /// A free-form note
+    pub note: Option<String>,
+    /// The preferred fee token of the contact
+    pub fee_token: Option<Address>,
+}
+
+/// Wallet address book listing arguments
+#[derive(Clone, Debug)]
+pub struct AddressBookList {
+    /// Only list the contacts with this tag
+    pub tag: Option<String>,
+}
+
+/// Wallet address book export arguments
+#[derive(Clone, Debug)]
+pub struct BookExport {
+    /// Export the watch-only accounts instead of the address book
+    pub watch_only: bool,
+    /// The file format
+    pub format: BookFormat,
+    /// File name
+    pub file_path: String,
+}
+
+/// Wallet address book import arguments
+#[derive(Clone, Debug)]
+pub struct BookImport {
+    /// Import watch-only accounts instead of address book entries
+    pub watch_only: bool,
+    /// The file format
+    pub format: BookFormat,
+    /// File name
+    pub file_path: String,
+    /// Whether to force overwrite existing aliases
+    pub alias_force: bool,
+}
+
+/// A daily spending limit of a token, written as `<token>=<amount>`
+#[derive(Clone, Debug)]
+pub struct DailyLimit {
+    /// The limited token
+    pub token: Address,
+    /// The maximum amount spent per day
+    pub amount: token::DenominatedAmount,
+}
+
+impl FromStr for DailyLimit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (token, amount) = s.split_once('=').ok_or_else(|| {
+            format!("Expected a daily limit of the form <token>=<amount>: {s}")
+        })?;
+        let token = Address::decode(token).map_err(|err| err.to_string())?;
+        let amount = token::DenominatedAmount::from_str(amount)
+            .map_err(|err| err.to_string())?;
+        Ok(Self { token, amount })
+    }
+}
+
+/// Wallet key policy arguments
+#[derive(Clone, Debug)]
+pub struct KeyPolicySet {
+    /// Key alias
+    pub alias: String,
+    /// The daily spending limits per token
+    pub daily_limits: Vec<DailyLimit>,
+    /// The only addresses that the key may send to, if any
+    pub allowed_targets: Vec<Address>,
+    /// Whether every use of the key must be confirmed
+    pub require_confirmation: bool,
+    /// Remove the policy of the key instead
+    pub remove: bool,
+}
+
+/// Wallet key usage log arguments
+#[derive(Clone, Debug)]
+pub struct KeyUsageList {
+    /// Only list the usages of the key with this alias
+    pub alias: Option<String>,
+}
+
+/// Wallet prune disposable keys arguments
+#[derive(Clone, Debug)]
+pub struct DisposableKeysPrune {
+    /// Set the lifetime of the disposable keys in seconds before pruning
+    pub lifetime: Option<u64>,
+}
+
+/// Wallet restore disposable keys arguments
+#[derive(Clone, Debug)]
+pub struct DisposableKeysRestore {
+    /// The number of disposable keys to derive
+    pub count: u32,
+    /// Recover the HD seed from SLIP-39 shares instead of a mnemonic code
+    pub from_shares: bool,
+    /// Prompt for BIP39 passphrase
+    pub prompt_bip39_passphrase: bool,
+}
+
 /// Generate payment address arguments
 #[derive(Clone, Debug)]
 pub struct PayAddressGen<C: NamadaTypes = SdkTypes> {
@@ -47,8 +47,10 @@ use std::str::FromStr;
 use args::{DeviceTransport, InputAmount, SdkTypes};
 use masp_primitives::zip32::PseudoExtendedKey;
 use namada_core::address::Address;
+use namada_core::collections::HashMap;
 use namada_core::dec::Dec;
 use namada_core::ethereum_events::EthAddress;
+use namada_core::hash::Hash;
 use namada_core::ibc::core::host::types::identifiers::{ChannelId, PortId};
 use namada_core::key::*;
 pub use namada_core::masp::{
@@ -122,11 +124,18 @@ pub trait Namada: NamadaIo {
     /// Return the native token
     fn native_token(&self) -> Address;
 
+    /// Obtain the cache of the hashes of the transaction codes whitelisted on
+    /// chain that the key policies identify, if this context keeps one
+    fn tx_codes_cache(&self) -> Option<&RwLock<HashMap<Hash, &'static str>>> {
+        None
+    }
+
     /// Make a tx builder using no arguments
     fn tx_builder(&self) -> args::Tx {
         args::Tx {
             dry_run: false,
             dry_run_wrapper: false,
+            dry_run_trace: false,
             dump_tx: false,
             dump_wrapper_tx: false,
             output_folder: None,
@@ -152,6 +161,9 @@ pub trait Namada: NamadaIo {
             memo: None,
             use_device: false,
             device_transport: DeviceTransport::default(),
+            masp_prover: None,
+            shielded_key_share: None,
+            remote_signer: None,
         }
     }
 
@@ -619,11 +631,14 @@ pub trait Namada: NamadaIo {
             + MaybeSync
             + std::future::Future<Output = crate::error::Result<Tx>>,
     {
+        let summary_context =
+            signing::tx_summary_context(self, tx, &signing_data).await?;
         signing::sign_tx(
             self.wallet_lock(),
             args,
             tx,
             signing_data,
+            &summary_context,
             with,
             user_data,
         )
@@ -680,6 +695,8 @@ where
     native_token: Address,
     /// The default builder for a Tx
     prototype: args::Tx,
+    /// The hashes of the transaction codes whitelisted on chain
+    tx_codes_cache: RwLock<HashMap<Hash, &'static str>>,
 }
 
 impl<C, U, V, I> NamadaImpl<C, U, V, I>
@@ -706,6 +723,7 @@ where
             prototype: args::Tx {
                 dry_run: false,
                 dry_run_wrapper: false,
+                dry_run_trace: false,
                 dump_tx: false,
                 dump_wrapper_tx: false,
                 output_folder: None,
@@ -731,7 +749,11 @@ where
                 memo: None,
                 use_device: false,
                 device_transport: DeviceTransport::default(),
+                masp_prover: None,
+                shielded_key_share: None,
+                remote_signer: None,
             },
+            tx_codes_cache: Default::default(),
         }
     }
 
@@ -826,6 +848,10 @@ where
         self.native_token.clone()
     }
 
+    fn tx_codes_cache(&self) -> Option<&RwLock<HashMap<Hash, &'static str>>> {
+        Some(&self.tx_codes_cache)
+    }
+
     /// Obtain the prototypical Tx builder
     fn tx_builder(&self) -> args::Tx {
         self.prototype.clone()
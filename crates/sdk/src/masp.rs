@@ -1,5 +1,6 @@
 //! MASP verification wrappers.
 
+pub mod multisig;
 mod utilities;
 
 use masp_primitives::asset_type::AssetType;
@@ -21,7 +22,9 @@ use namada_io::client::Client;
 use namada_token::masp::shielded_wallet::ShieldedQueries;
 pub use namada_token::masp::{utils, *};
 use namada_tx::Tx;
-pub use utilities::{IndexerMaspClient, LedgerMaspClient};
+pub use utilities::{
+    FileMaspClient, IndexerMaspClient, LedgerMaspClient, RemoteMaspProver,
+};
 
 use crate::error::{Error, QueryError};
 use crate::rpc::{
@@ -0,0 +1,747 @@
+//! Threshold authorization of the spends of a shielded multisig.
+//!
+//! The spend authorizing key of a shielded multisig is split among its
+//! participants with FROST over RedJubjub, so that any `threshold` of them can
+//! jointly produce the spend authorization signatures of a MASP transaction.
+//! The other components of the multisig's spending key (its viewing key and
+//! its nullifier deriving key) are known to all the participants, which lets
+//! any of them track the balance of the multisig and construct (and prove)
+//! transactions spending from it.
+//!
+//! Authorizing a transaction takes the following steps:
+//! 1. A coordinator constructs the transaction and a [`SpendAuthRequest`]
+//!    listing the spends to authorize.
+//! 2. Each signer generates its [`SpendAuthCommitments`] with [`commit`],
+//!    keeping the matching [`SpendAuthNonces`] private.
+//! 3. Once the commitments of all the signers are known, each signer produces
+//!    its [`SpendAuthSignatureShares`] with [`sign`].
+//! 4. The coordinator combines the signature shares into the final spend
+//!    authorization signatures with [`aggregate`].
+
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Deref;
+
+use borsh::BorshDeserialize;
+use masp_primitives::group::GroupEncoding;
+use masp_primitives::jubjub;
+use masp_primitives::sapling::redjubjub::Signature;
+use masp_primitives::sapling::{self, ProofGenerationKey};
+use masp_primitives::transaction::components::sapling::builder::StoredBuildParams;
+use masp_primitives::transaction::sighash::{signature_hash, SignableInput};
+use masp_primitives::transaction::txid::TxIdDigester;
+use masp_primitives::transaction::Authorized;
+use masp_primitives::zip32::{
+    ExtendedFullViewingKey, ExtendedSpendingKey, PseudoExtendedKey,
+};
+use namada_core::masp::{ExtendedViewingKey, MaspTxId};
+use namada_token::masp::partial_deauthorize;
+use namada_tx::{Section, Tx};
+use rand_core::{CryptoRng, RngCore};
+use reddsa::frost::redjubjub as frost;
+use serde::{Deserialize, Serialize};
+
+use crate::error::Error;
+
+/// The share of a participant in the key of a shielded multisig
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ShieldedKeyShare {
+    /// The FROST identifier of the participant
+    pub identifier: u16,
+    /// The minimum number of participants required to authorize a spend
+    pub threshold: u16,
+    /// The viewing key of the multisig
+    pub viewing_key: ExtendedViewingKey,
+    /// The nullifier deriving key of the multisig
+    #[serde(with = "hex_data_serde")]
+    pub nsk: Vec<u8>,
+    /// The serialized FROST key package of the participant
+    #[serde(with = "hex_data_serde")]
+    pub key_package: Vec<u8>,
+    /// The serialized FROST public key package of the multisig
+    #[serde(with = "hex_data_serde")]
+    pub public_key_package: Vec<u8>,
+}
+
+impl ShieldedKeyShare {
+    /// Get the proof generation key of the multisig
+    pub fn proof_generation_key(&self) -> Result<ProofGenerationKey, Error> {
+        let nsk: [u8; 32] = self.nsk.as_slice().try_into().map_err(|_| {
+            Error::Other("Invalid nullifier deriving key length".to_string())
+        })?;
+        let nsk =
+            Option::from(jubjub::Fr::from_bytes(&nsk)).ok_or_else(|| {
+                Error::Other("Invalid nullifier deriving key".to_string())
+            })?;
+        let xfvk = ExtendedFullViewingKey::from(self.viewing_key);
+        Ok(ProofGenerationKey {
+            ak: xfvk.fvk.vk.ak,
+            nsk,
+        })
+    }
+
+    /// Get a key of the multisig with which transactions spending its notes
+    /// can be constructed and proven, but not authorized
+    pub fn pseudo_extended_key(&self) -> Result<PseudoExtendedKey, Error> {
+        let mut key = PseudoExtendedKey::from(ExtendedFullViewingKey::from(
+            self.viewing_key,
+        ));
+        key.augment_proof_generation_key(self.proof_generation_key()?)
+            .map_err(|_| {
+                Error::Other(
+                    "The proof generation key of the key share does not \
+                     correspond to its viewing key"
+                        .to_string(),
+                )
+            })?;
+        Ok(key)
+    }
+
+    fn key_package(&self) -> Result<frost::keys::KeyPackage, Error> {
+        frost::keys::KeyPackage::deserialize(&self.key_package)
+            .map_err(frost_error)
+    }
+
+    fn public_key_package(
+        &self,
+    ) -> Result<frost::keys::PublicKeyPackage, Error> {
+        frost::keys::PublicKeyPackage::deserialize(&self.public_key_package)
+            .map_err(frost_error)
+    }
+}
+
+/// Generate a fresh shielded multisig key, split among the given number of
+/// participants such that any `threshold` of them can authorize its spends.
+///
+/// This function acts as a trusted dealer: the generated spend authorizing key
+/// only ever exists in memory, and only the returned shares should be
+/// distributed to the participants.
+pub fn generate_key_shares(
+    threshold: u16,
+    participants: u16,
+    rng: &mut (impl CryptoRng + RngCore),
+) -> Result<Vec<ShieldedKeyShare>, Error> {
+    let mut seed = [0u8; 32];
+    rng.fill_bytes(&mut seed);
+    let xsk = ExtendedSpendingKey::master(&seed);
+    let signing_key = frost::SigningKey::deserialize(xsk.expsk.ask.to_bytes())
+        .map_err(frost_error)?;
+    let (mut secret_shares, public_key_package) = frost::keys::split(
+        &signing_key,
+        participants,
+        threshold,
+        frost::keys::IdentifierList::Default,
+        rng,
+    )
+    .map_err(frost_error)?;
+
+    // The group key must be the spend validating key of the multisig for the
+    // aggregated signatures to authorize its spends
+    let proof_generation_key = xsk.expsk.proof_generation_key();
+    if public_key_package.verifying_key().serialize()
+        != proof_generation_key.ak.to_bytes()
+    {
+        return Err(Error::Other(
+            "The FROST group key does not match the spend validating key"
+                .to_string(),
+        ));
+    }
+    let public_key_package =
+        public_key_package.serialize().map_err(frost_error)?;
+    let viewing_key = ExtendedViewingKey::from(xsk.to_viewing_key());
+
+    (1..=participants)
+        .map(|identifier| {
+            let secret_share = secret_shares
+                .remove(&frost_identifier(identifier)?)
+                .ok_or_else(|| {
+                    Error::Other(format!(
+                        "Missing the key share of participant {identifier}"
+                    ))
+                })?;
+            let key_package = frost::keys::KeyPackage::try_from(secret_share)
+                .map_err(frost_error)?;
+            Ok(ShieldedKeyShare {
+                identifier,
+                threshold,
+                viewing_key,
+                nsk: proof_generation_key.nsk.to_bytes().to_vec(),
+                key_package: key_package.serialize().map_err(frost_error)?,
+                public_key_package: public_key_package.clone(),
+            })
+        })
+        .collect()
+}
+
+/// FROST data pertaining to a single spend description of a transaction
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SpendData {
+    /// The index of the spend description in the MASP transaction
+    pub index: usize,
+    /// The serialized data
+    #[serde(with = "hex_data_serde")]
+    pub data: Vec<u8>,
+}
+
+/// A request for the authorization of the spends of a shielded multisig
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SpendAuthRequest {
+    /// The transaction whose spends must be authorized
+    pub tx: Tx,
+    /// The hash of the MASP section of the transaction
+    pub shielded_hash: MaspTxId,
+    /// The viewing key of the multisig
+    pub viewing_key: ExtendedViewingKey,
+    /// The spends of the multisig, along with the randomizers of their spend
+    /// validating keys
+    pub spends: Vec<SpendData>,
+}
+
+impl SpendAuthRequest {
+    /// Request the authorization of the spends of the multisig with the given
+    /// viewing key in a transaction built with the given parameters
+    pub fn new(
+        tx: Tx,
+        shielded_hash: MaspTxId,
+        viewing_key: ExtendedViewingKey,
+        bparams: &StoredBuildParams,
+    ) -> Result<Self, Error> {
+        let masp_builder =
+            tx.get_masp_builder(&shielded_hash).ok_or_else(|| {
+                Error::Other(
+                    "Expected to find the indicated MASP Builder".to_string(),
+                )
+            })?;
+        let xfvk = ExtendedFullViewingKey::from(viewing_key);
+        let spends = masp_builder
+            .builder
+            .sapling_inputs()
+            .iter()
+            .enumerate()
+            .filter(|(_, input)| *input.key() == xfvk)
+            .map(|(builder_pos, _)| {
+                let index = masp_builder
+                    .metadata
+                    .spend_index(builder_pos)
+                    .ok_or_else(|| {
+                        Error::Other(format!(
+                            "Missing the metadata of spend {builder_pos}"
+                        ))
+                    })?;
+                let alpha = bparams
+                    .spend_params
+                    .get(builder_pos)
+                    .ok_or_else(|| {
+                        Error::Other(format!(
+                            "Missing the build parameters of spend \
+                             {builder_pos}"
+                        ))
+                    })?
+                    .alpha;
+                Ok(SpendData {
+                    index,
+                    data: alpha.to_bytes().to_vec(),
+                })
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        if spends.is_empty() {
+            return Err(Error::Other(
+                "The transaction does not spend any note of the shielded \
+                 multisig"
+                    .to_string(),
+            ));
+        }
+
+        Ok(Self {
+            tx,
+            shielded_hash,
+            viewing_key,
+            spends,
+        })
+    }
+
+    /// Compute the hash signed by the spend authorization signatures
+    pub fn sighash(&self) -> Result<[u8; 32], Error> {
+        let masp_tx = self.masp_tx()?;
+        let unauth_tx_data =
+            partial_deauthorize(masp_tx.deref()).ok_or_else(|| {
+                Error::Other(
+                    "Unable to deauthorize the MASP transaction".to_string(),
+                )
+            })?;
+        let txid_parts = unauth_tx_data.digest(TxIdDigester);
+        let sighash = signature_hash(
+            &unauth_tx_data,
+            &SignableInput::Shielded,
+            &txid_parts,
+        );
+        Ok(*sighash.as_ref())
+    }
+
+    fn masp_tx(
+        &self,
+    ) -> Result<&masp_primitives::transaction::Transaction, Error> {
+        self.tx
+            .get_masp_section(&self.shielded_hash)
+            .ok_or_else(|| {
+                Error::Other(
+                    "Expected to find the indicated MASP Transaction"
+                        .to_string(),
+                )
+            })
+    }
+
+    fn check_key_share(&self, share: &ShieldedKeyShare) -> Result<(), Error> {
+        if share.viewing_key != self.viewing_key {
+            return Err(Error::Other(
+                "The key share does not belong to the requested shielded \
+                 multisig"
+                    .to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn check_shielded_hash(
+        &self,
+        shielded_hash: &MaspTxId,
+    ) -> Result<(), Error> {
+        if *shielded_hash != self.shielded_hash {
+            return Err(Error::Other(format!(
+                "Expected data for MASP transaction {}, got {}",
+                self.shielded_hash, shielded_hash
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// The secret nonces of a signer. These must never be shared, and must be
+/// discarded once used to sign.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SpendAuthNonces {
+    /// The FROST identifier of the signer
+    pub identifier: u16,
+    /// The hash of the MASP transaction being authorized
+    pub shielded_hash: MaspTxId,
+    /// The serialized nonces of each spend
+    pub nonces: Vec<SpendData>,
+}
+
+/// The commitments of a signer to its nonces
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SpendAuthCommitments {
+    /// The FROST identifier of the signer
+    pub identifier: u16,
+    /// The hash of the MASP transaction being authorized
+    pub shielded_hash: MaspTxId,
+    /// The serialized commitments of each spend
+    pub commitments: Vec<SpendData>,
+}
+
+/// The signature shares of a signer
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SpendAuthSignatureShares {
+    /// The FROST identifier of the signer
+    pub identifier: u16,
+    /// The hash of the MASP transaction being authorized
+    pub shielded_hash: MaspTxId,
+    /// The serialized signature shares of each spend
+    pub shares: Vec<SpendData>,
+}
+
+/// Generate the nonces of a signer for the requested spends, along with the
+/// commitments to be shared with the other signers.
+pub fn commit(
+    share: &ShieldedKeyShare,
+    request: &SpendAuthRequest,
+    rng: &mut (impl CryptoRng + RngCore),
+) -> Result<(SpendAuthNonces, SpendAuthCommitments), Error> {
+    request.check_key_share(share)?;
+    let key_package = share.key_package()?;
+
+    let mut nonces = Vec::with_capacity(request.spends.len());
+    let mut commitments = Vec::with_capacity(request.spends.len());
+    for spend in &request.spends {
+        let (spend_nonces, spend_commitments) =
+            frost::round1::commit(key_package.signing_share(), rng);
+        nonces.push(SpendData {
+            index: spend.index,
+            data: spend_nonces.serialize().map_err(frost_error)?,
+        });
+        commitments.push(SpendData {
+            index: spend.index,
+            data: spend_commitments.serialize().map_err(frost_error)?,
+        });
+    }
+
+    Ok((
+        SpendAuthNonces {
+            identifier: share.identifier,
+            shielded_hash: request.shielded_hash,
+            nonces,
+        },
+        SpendAuthCommitments {
+            identifier: share.identifier,
+            shielded_hash: request.shielded_hash,
+            commitments,
+        },
+    ))
+}
+
+/// Produce the signature shares of a signer for the requested spends, given
+/// the commitments of all the signers taking part in the authorization.
+pub fn sign(
+    share: &ShieldedKeyShare,
+    request: &SpendAuthRequest,
+    nonces: &SpendAuthNonces,
+    commitments: &[SpendAuthCommitments],
+) -> Result<SpendAuthSignatureShares, Error> {
+    request.check_key_share(share)?;
+    request.check_shielded_hash(&nonces.shielded_hash)?;
+    if nonces.identifier != share.identifier {
+        return Err(Error::Other(format!(
+            "The nonces of signer {} cannot be used by signer {}",
+            nonces.identifier, share.identifier
+        )));
+    }
+    let key_package = share.key_package()?;
+    let sighash = request.sighash()?;
+
+    let shares = request
+        .spends
+        .iter()
+        .map(|spend| {
+            let signing_package =
+                signing_package(request, spend.index, commitments, &sighash)?;
+            let spend_nonces = frost::round1::SigningNonces::deserialize(
+                spend_data(&nonces.nonces, spend.index)?,
+            )
+            .map_err(frost_error)?;
+            let signature_share = frost::round2::sign(
+                &signing_package,
+                &spend_nonces,
+                &key_package,
+                randomizer(&spend.data)?,
+            )
+            .map_err(frost_error)?;
+            Ok(SpendData {
+                index: spend.index,
+                data: signature_share.serialize().to_vec(),
+            })
+        })
+        .collect::<Result<_, Error>>()?;
+
+    Ok(SpendAuthSignatureShares {
+        identifier: share.identifier,
+        shielded_hash: request.shielded_hash,
+        shares,
+    })
+}
+
+/// Combine the signature shares of the signers into the spend authorization
+/// signatures of the requested spends. Returns the request's transaction with
+/// the signatures applied to its MASP section.
+pub fn aggregate(
+    share: &ShieldedKeyShare,
+    request: &SpendAuthRequest,
+    commitments: &[SpendAuthCommitments],
+    signature_shares: &[SpendAuthSignatureShares],
+) -> Result<Tx, Error> {
+    request.check_key_share(share)?;
+    let public_key_package = share.public_key_package()?;
+    let sighash = request.sighash()?;
+    let masp_tx = request.masp_tx()?;
+    let sapling_bundle = masp_tx.sapling_bundle().ok_or_else(|| {
+        Error::Other("The MASP transaction has no sapling bundle".to_string())
+    })?;
+
+    let mut authorizations = HashMap::new();
+    for spend in &request.spends {
+        let signing_package =
+            signing_package(request, spend.index, commitments, &sighash)?;
+        let shares = signature_shares
+            .iter()
+            .map(|shares| {
+                request.check_shielded_hash(&shares.shielded_hash)?;
+                let bytes = spend_data(&shares.shares, spend.index)?
+                    .try_into()
+                    .map_err(|_| {
+                        Error::Other(
+                            "Invalid signature share length".to_string(),
+                        )
+                    })?;
+                let signature_share =
+                    frost::round2::SignatureShare::deserialize(bytes)
+                        .map_err(frost_error)?;
+                Ok((frost_identifier(shares.identifier)?, signature_share))
+            })
+            .collect::<Result<BTreeMap<_, _>, Error>>()?;
+        let rk = sapling_bundle
+            .shielded_spends
+            .get(spend.index)
+            .ok_or_else(|| {
+                Error::Other(format!(
+                    "Missing spend description {}",
+                    spend.index
+                ))
+            })?
+            .rk;
+        let signature = aggregate_spend(
+            &signing_package,
+            &shares,
+            &public_key_package,
+            &spend.data,
+            &rk.0.to_bytes(),
+        )?;
+        authorizations.insert(spend.index, signature);
+    }
+
+    let masp_tx = masp_tx
+        .deref()
+        .clone()
+        .map_authorization::<Authorized>((), MapSpendAuthSig(authorizations))
+        .freeze()
+        .map_err(|err| {
+            Error::Other(format!(
+                "Unable to apply the spend authorization signatures to the \
+                 MASP transaction: {err}"
+            ))
+        })?;
+    let mut tx = request.tx.clone();
+    tx.remove_masp_section(&request.shielded_hash);
+    tx.add_section(Section::MaspTx(masp_tx));
+    Ok(tx)
+}
+
+// Aggregate the signature shares of a single spend into a spend authorization
+// signature valid under the given randomized spend validating key
+fn aggregate_spend(
+    signing_package: &frost::SigningPackage,
+    shares: &BTreeMap<frost::Identifier, frost::round2::SignatureShare>,
+    public_key_package: &frost::keys::PublicKeyPackage,
+    alpha: &[u8],
+    rk: &[u8; 32],
+) -> Result<Signature, Error> {
+    let randomized_params = frost::RandomizedParams::from_randomizer(
+        public_key_package.verifying_key(),
+        randomizer(alpha)?,
+    );
+    if randomized_params.randomized_verifying_key().serialize() != *rk {
+        return Err(Error::Other(
+            "The randomizer of the spend does not match its randomized spend \
+             validating key"
+                .to_string(),
+        ));
+    }
+    let signature = frost::aggregate(
+        signing_package,
+        shares,
+        public_key_package,
+        &randomized_params,
+    )
+    .map_err(frost_error)?;
+    Signature::try_from_slice(signature.serialize().as_ref()).map_err(|err| {
+        Error::Other(format!("Invalid aggregated signature: {err}"))
+    })
+}
+
+// Construct the FROST signing package of the given spend
+fn signing_package(
+    request: &SpendAuthRequest,
+    index: usize,
+    commitments: &[SpendAuthCommitments],
+    sighash: &[u8; 32],
+) -> Result<frost::SigningPackage, Error> {
+    let commitments = commitments
+        .iter()
+        .map(|commitments| {
+            request.check_shielded_hash(&commitments.shielded_hash)?;
+            let spend_commitments =
+                frost::round1::SigningCommitments::deserialize(spend_data(
+                    &commitments.commitments,
+                    index,
+                )?)
+                .map_err(frost_error)?;
+            Ok((frost_identifier(commitments.identifier)?, spend_commitments))
+        })
+        .collect::<Result<BTreeMap<_, _>, Error>>()?;
+    Ok(frost::SigningPackage::new(commitments, sighash))
+}
+
+fn spend_data(data: &[SpendData], index: usize) -> Result<&[u8], Error> {
+    data.iter()
+        .find(|spend| spend.index == index)
+        .map(|spend| spend.data.as_slice())
+        .ok_or_else(|| Error::Other(format!("Missing data for spend {index}")))
+}
+
+fn randomizer(alpha: &[u8]) -> Result<frost::Randomizer, Error> {
+    let alpha: [u8; 32] = alpha.try_into().map_err(|_| {
+        Error::Other("Invalid spend randomizer length".to_string())
+    })?;
+    frost::Randomizer::deserialize(&alpha).map_err(frost_error)
+}
+
+fn frost_identifier(identifier: u16) -> Result<frost::Identifier, Error> {
+    frost::Identifier::try_from(identifier).map_err(frost_error)
+}
+
+fn frost_error(err: frost::Error) -> Error {
+    Error::Other(format!("FROST error: {err}"))
+}
+
+// A mapper that replaces spend authorization signatures with those in a map
+struct MapSpendAuthSig(
+    HashMap<usize, <sapling::Authorized as sapling::Authorization>::AuthSig>,
+);
+
+impl sapling::MapAuth<sapling::Authorized, sapling::Authorized>
+    for MapSpendAuthSig
+{
+    fn map_proof(
+        &self,
+        p: <sapling::Authorized as sapling::Authorization>::Proof,
+        _pos: usize,
+    ) -> <sapling::Authorized as sapling::Authorization>::Proof {
+        p
+    }
+
+    fn map_auth_sig(
+        &self,
+        s: <sapling::Authorized as sapling::Authorization>::AuthSig,
+        pos: usize,
+    ) -> <sapling::Authorized as sapling::Authorization>::AuthSig {
+        self.0.get(&pos).cloned().unwrap_or(s)
+    }
+
+    fn map_authorization(&self, a: sapling::Authorized) -> sapling::Authorized {
+        a
+    }
+}
+
+mod hex_data_serde {
+    use data_encoding::HEXLOWER;
+    use serde::{de, Deserializer, Serializer};
+
+    pub fn serialize<S>(
+        #[allow(clippy::ptr_arg)] data: &Vec<u8>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&HEXLOWER.encode(data))
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Vec<u8>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let hex_string =
+            <String as serde::Deserialize>::deserialize(deserializer)?;
+        HEXLOWER
+            .decode(hex_string.as_bytes())
+            .map_err(|e| de::Error::custom(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod test_shielded_multisig {
+    use masp_primitives::constants::SPENDING_KEY_GENERATOR;
+    use masp_primitives::sapling::redjubjub::PublicKey;
+    use rand_core::OsRng;
+
+    use super::*;
+
+    /// Check that the key shares of a shielded multisig can augment its
+    /// viewing key with its proof generation key
+    #[test]
+    fn test_key_shares() {
+        let shares = generate_key_shares(2, 3, &mut OsRng).unwrap();
+        assert_eq!(shares.len(), 3);
+        for (share, identifier) in shares.iter().zip(1..) {
+            assert_eq!(share.identifier, identifier);
+            assert_eq!(share.viewing_key, shares[0].viewing_key);
+            let key = share.pseudo_extended_key().unwrap();
+            assert_eq!(
+                key.to_viewing_key(),
+                ExtendedFullViewingKey::from(share.viewing_key)
+            );
+        }
+    }
+
+    /// Check that a threshold of signers produce a spend authorization
+    /// signature that verifies under the randomized spend validating key of
+    /// the multisig, exactly like a signature from a single key would
+    #[test]
+    fn test_threshold_spend_authorization() {
+        let shares = generate_key_shares(2, 3, &mut OsRng).unwrap();
+        let public_key_package = shares[0].public_key_package().unwrap();
+        let ak = shares[0].proof_generation_key().unwrap().ak;
+        let alpha = jubjub::Fr::from(42u64);
+        let rk = PublicKey(ak.into()).randomize(alpha, SPENDING_KEY_GENERATOR);
+        let sighash = [7u8; 32];
+
+        // Only the first and the last participants take part
+        let signers = [&shares[0], &shares[2]];
+        let key_packages: Vec<_> = signers
+            .iter()
+            .map(|share| share.key_package().unwrap())
+            .collect();
+        let (nonces, commitments): (Vec<_>, BTreeMap<_, _>) = key_packages
+            .iter()
+            .map(|key_package| {
+                let (nonces, commitments) = frost::round1::commit(
+                    key_package.signing_share(),
+                    &mut OsRng,
+                );
+                (nonces, (*key_package.identifier(), commitments))
+            })
+            .unzip();
+        let signing_package = frost::SigningPackage::new(commitments, &sighash);
+        let signature_shares = key_packages
+            .iter()
+            .zip(&nonces)
+            .map(|(key_package, nonces)| {
+                let share = frost::round2::sign(
+                    &signing_package,
+                    nonces,
+                    key_package,
+                    randomizer(&alpha.to_bytes()).unwrap(),
+                )
+                .unwrap();
+                (*key_package.identifier(), share)
+            })
+            .collect();
+
+        let signature = aggregate_spend(
+            &signing_package,
+            &signature_shares,
+            &public_key_package,
+            &alpha.to_bytes(),
+            &rk.0.to_bytes(),
+        )
+        .unwrap();
+
+        // Verify the signature as the MASP VP would
+        let mut data_to_be_signed = [0u8; 64];
+        data_to_be_signed[0..32].copy_from_slice(&rk.0.to_bytes());
+        data_to_be_signed[32..64].copy_from_slice(&sighash);
+        assert!(rk.verify(
+            &data_to_be_signed,
+            &signature,
+            SPENDING_KEY_GENERATOR
+        ));
+
+        // A mismatching randomizer must be rejected
+        let other_alpha = jubjub::Fr::from(43u64);
+        assert!(aggregate_spend(
+            &signing_package,
+            &signature_shares,
+            &public_key_package,
+            &other_alpha.to_bytes(),
+            &rk.0.to_bytes(),
+        )
+        .is_err());
+    }
+}
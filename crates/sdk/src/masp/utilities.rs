@@ -1,12 +1,15 @@
 //! Helper functions and types
 
 use std::collections::BTreeMap;
+use std::path::Path;
 use std::sync::{Arc, RwLock};
 
 use borsh::BorshDeserialize;
+use eyre::{eyre, WrapErr};
 use masp_primitives::merkle_tree::{CommitmentTree, IncrementalWitness};
 use masp_primitives::sapling::Node;
 use masp_primitives::transaction::Transaction as MaspTx;
+use namada_core::borsh::BorshSerializeExt;
 use namada_core::chain::BlockHeight;
 use namada_core::collections::HashMap;
 use namada_core::control_flow::time::{
@@ -15,6 +18,10 @@ use namada_core::control_flow::time::{
 use namada_core::storage::TxIndex;
 use namada_events::extend::IndexedMaspData;
 use namada_io::Client;
+use namada_token::masp::prover::{
+    GrothProof, MaspProver, ProofRequest, ProofResponse,
+};
+use namada_token::masp::snapshot::{MaspSnapshot, MaspSnapshotState};
 use namada_token::masp::utils::{
     IndexedNoteEntry, MaspClient, MaspClientCapabilities,
 };
@@ -730,6 +737,138 @@ impl MaspClient for IndexerMaspClient {
     }
 }
 
+#[derive(Debug)]
+struct FileMaspClientShared {
+    snapshot: MaspSnapshot,
+    /// State of the note commitment tree at the last height of the
+    /// snapshot, which is the one requested by shielded sync by default.
+    last_state: MaspSnapshotState,
+}
+
+/// MASP client implementation that reads shielded sync data from a local
+/// [`MaspSnapshot`], without connecting to any remote server.
+#[derive(Clone, Debug)]
+pub struct FileMaspClient {
+    shared: Arc<FileMaspClientShared>,
+}
+
+impl FileMaspClient {
+    /// Create a new [`FileMaspClient`] serving data from the given snapshot.
+    #[allow(clippy::result_large_err)]
+    pub fn new(snapshot: MaspSnapshot) -> Result<Self, Error> {
+        let last_state = snapshot
+            .state_at(snapshot.header().last_height)
+            .map_err(|err| Error::Other(err.to_string()))?;
+        Ok(Self {
+            shared: Arc::new(FileMaspClientShared {
+                snapshot,
+                last_state,
+            }),
+        })
+    }
+
+    /// Create a new [`FileMaspClient`] serving data from the snapshot
+    /// file at the given path.
+    #[allow(clippy::result_large_err)]
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let bytes = std::fs::read(path).map_err(|err| {
+            Error::Other(format!(
+                "Failed to read MASP snapshot file {}: {err}",
+                path.to_string_lossy()
+            ))
+        })?;
+        let snapshot = MaspSnapshot::try_from_bytes(&bytes).map_err(|err| {
+            Error::Other(format!(
+                "Invalid MASP snapshot file {}: {err}",
+                path.to_string_lossy()
+            ))
+        })?;
+        Self::new(snapshot)
+    }
+
+    /// Return the snapshot served by this client.
+    pub fn snapshot(&self) -> &MaspSnapshot {
+        &self.shared.snapshot
+    }
+
+    #[allow(clippy::result_large_err)]
+    fn with_state_at<T>(
+        &self,
+        height: BlockHeight,
+        f: impl FnOnce(&MaspSnapshotState) -> T,
+    ) -> Result<T, Error> {
+        if height == self.shared.snapshot.header().last_height {
+            return Ok(f(&self.shared.last_state));
+        }
+        let state = self
+            .shared
+            .snapshot
+            .state_at(height)
+            .map_err(|err| Error::Other(err.to_string()))?;
+        Ok(f(&state))
+    }
+}
+
+impl MaspClient for FileMaspClient {
+    type Error = Error;
+
+    async fn last_block_height(&self) -> Result<Option<BlockHeight>, Error> {
+        Ok(Some(self.shared.snapshot.header().last_height))
+    }
+
+    async fn fetch_shielded_transfers(
+        &self,
+        from: BlockHeight,
+        to: BlockHeight,
+    ) -> Result<Vec<IndexedNoteEntry>, Error> {
+        let snapshot = &self.shared.snapshot;
+        if from > to {
+            return Err(Error::Other(format!(
+                "Invalid block range {from}-{to}: Beginning height {from} is \
+                 greater than ending height {to}"
+            )));
+        }
+        if !snapshot.contains_height(from) || !snapshot.contains_height(to) {
+            let header = snapshot.header();
+            return Err(Error::Other(format!(
+                "The block range {from}-{to} is not covered by the MASP \
+                 snapshot, which holds the shielded transfers of the heights \
+                 {}-{}",
+                header.base_height.next_height(),
+                header.last_height
+            )));
+        }
+        Ok(snapshot.shielded_transfers(from, to).collect())
+    }
+
+    #[inline(always)]
+    fn capabilities(&self) -> MaspClientCapabilities {
+        MaspClientCapabilities::AllData
+    }
+
+    async fn fetch_commitment_tree(
+        &self,
+        height: BlockHeight,
+    ) -> Result<CommitmentTree<Node>, Error> {
+        self.with_state_at(height, |state| state.tree.clone())
+    }
+
+    async fn fetch_note_index(
+        &self,
+        height: BlockHeight,
+    ) -> Result<BTreeMap<IndexedTx, usize>, Error> {
+        self.with_state_at(height, |state| state.note_index.clone())
+    }
+
+    async fn fetch_witness_map(
+        &self,
+        height: BlockHeight,
+    ) -> Result<HashMap<usize, IncrementalWitness<Node>>, Error> {
+        self.with_state_at(height, |state| state.witness_map.clone())
+    }
+}
+
 #[allow(clippy::result_large_err)]
 fn index_txs(
     txs: &mut Vec<(IndexedTx, MaspTx)>,
@@ -977,3 +1116,67 @@ mod test_block_index {
         };
     }
 }
+
+/// A [`MaspProver`] that delegates the generation of MASP proofs to a remote
+/// prover server. Only the inputs of the circuits are sent to the server,
+/// while the transaction is still signed locally.
+#[derive(Clone)]
+pub struct RemoteMaspProver {
+    client: reqwest::Client,
+    prover_api: reqwest::Url,
+}
+
+impl RemoteMaspProver {
+    /// Create a new [`RemoteMaspProver`].
+    #[inline]
+    pub fn new(client: reqwest::Client, prover_api: reqwest::Url) -> Self {
+        Self { client, prover_api }
+    }
+
+    fn endpoint(&self, which: &str) -> String {
+        format!("{}{which}", self.prover_api.as_str().trim_end_matches('/'))
+    }
+}
+
+#[cfg_attr(feature = "async-send", async_trait::async_trait)]
+#[cfg_attr(not(feature = "async-send"), async_trait::async_trait(?Send))]
+impl MaspProver for RemoteMaspProver {
+    async fn prove(
+        &self,
+        requests: Vec<ProofRequest>,
+    ) -> Result<Vec<GrothProof>, eyre::Error> {
+        let num_requests = requests.len();
+        let response = self
+            .client
+            .post(self.endpoint("/prove"))
+            .body(requests.serialize_to_vec())
+            .send()
+            .await
+            .wrap_err("Failed to send the MASP proof requests")?;
+
+        if !response.status().is_success() {
+            return Err(eyre!(
+                "The MASP prover server replied with status {}",
+                response.status()
+            ));
+        }
+
+        let payload = response
+            .bytes()
+            .await
+            .wrap_err("Failed to read the MASP prover server's response")?;
+        let proofs = ProofResponse::try_from_slice(&payload)
+            .wrap_err("Could not decode the MASP prover server's response")?
+            .map_err(|err| eyre!("The MASP prover server failed: {err}"))?;
+
+        if proofs.len() != num_requests {
+            return Err(eyre!(
+                "Requested {num_requests} MASP proofs, but the prover server \
+                 returned {}",
+                proofs.len()
+            ));
+        }
+
+        Ok(proofs)
+    }
+}
@@ -1,8 +1,14 @@
 //! Ledger read-only queries can be handled and dispatched via the [`RPC`]
 //! defined via `router!` macro.
 
+use namada_core::arith::checked;
+use namada_core::chain::BlockHeight;
+use namada_core::hints;
+use namada_core::storage::{self, KeySeg};
+use namada_state::merkle_tree::NO_DIFF_KEY_PREFIX;
 // Re-export to show in rustdoc!
-use namada_state::{DBIter, StorageHasher, DB};
+use namada_state::{DBIter, ReadLog, StorageHasher, WlState, DB};
+use namada_storage::ResultExt;
 pub use shell::Shell;
 use shell::SHELL;
 pub use types::{
@@ -15,6 +21,7 @@ pub use self::shell::eth_bridge::{
     Erc20FlowControl, GenBridgePoolProofReq, GenBridgePoolProofRsp,
     TransferToErcArgs, TransferToEthereumStatus,
 };
+use crate::tendermint::merkle::proof::ProofOps;
 
 #[macro_use]
 mod router;
@@ -46,20 +53,55 @@ where
 
 // Handler helpers:
 
-/// For queries that only support latest height, check that the given height is
-/// not different from latest height, otherwise return an error.
-pub fn require_latest_height<D, H, V, T>(
+/// Find the committed block height at which the request should be handled.
+/// The height `0` is a special value that means the latest committed block
+/// height. Heights that are not committed yet or that are further in the past
+/// than `storage_read_past_height_limit` are rejected.
+pub(crate) fn queried_height<D, H, V, T>(
     ctx: &RequestCtx<'_, D, H, V, T>,
     request: &RequestQuery,
+) -> namada_storage::Result<BlockHeight>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+{
+    let last_committed_height = ctx.state.in_mem().get_last_block_height();
+    let height: BlockHeight = request.height.into();
+    if hints::likely(height.0 == 0) {
+        return Ok(last_committed_height);
+    }
+
+    if height > last_committed_height {
+        return Err(namada_storage::Error::new_alloc(format!(
+            "Cannot query the block height {height} which is not committed \
+             yet, the last committed block height is {last_committed_height}"
+        )));
+    }
+    if let Some(past_height_limit) = ctx.storage_read_past_height_limit {
+        if checked!(height + past_height_limit)? < last_committed_height {
+            return Err(namada_storage::Error::new(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "Cannot query more than {past_height_limit} blocks in the \
+                     past (configured via \
+                     `shell.storage_read_past_height_limit`)."
+                ),
+            )));
+        }
+    }
+    Ok(height)
+}
+
+/// For queries that only support latest height, check that the queried height
+/// is not different from latest height, otherwise return an error.
+pub fn require_latest_height<D, H, V, T>(
+    ctx: &RequestCtx<'_, D, H, V, T>,
 ) -> namada_storage::Result<()>
 where
     D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
     H: 'static + StorageHasher + Sync,
 {
-    if request.height.value() != 0
-        && request.height.value()
-            != ctx.state.in_mem().get_last_block_height().0
-    {
+    if ctx.height != ctx.state.in_mem().get_last_block_height() {
         return Err(namada_storage::Error::new_const(
             "This query doesn't support arbitrary block heights, only the \
              latest committed block height ('0' can be used as a special \
@@ -80,6 +122,72 @@ pub fn require_no_proof(request: &RequestQuery) -> namada_storage::Result<()> {
     Ok(())
 }
 
+/// Prove the given values read at the queried height. The proof is made of
+/// the proofs of the keys in order, each of them made of a proof of the value
+/// of the key, or of its absence, in its sub-tree followed by a proof of the
+/// sub-tree root in the base tree, whose root is the app hash. The absence of
+/// a key can only be proven in the sub-trees that have non-existence proofs.
+pub(crate) fn prove_values<'a, D, H>(
+    state: &WlState<D, H>,
+    values: impl IntoIterator<Item = (&'a storage::Key, Option<&'a [u8]>)>,
+    height: BlockHeight,
+) -> namada_storage::Result<ProofOps>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+{
+    let mut ops = vec![];
+    for (key, value) in values {
+        // The values not stored to diffs are merklized under a prefix
+        let tree_key = if (state.diff_key_filter)(key) {
+            key.clone()
+        } else {
+            storage::Key::from(NO_DIFF_KEY_PREFIX.to_string().to_db_key())
+                .join(key)
+        };
+        let mut proof = match value {
+            Some(value) => state.get_existence_proof(&tree_key, value, height),
+            None => state.get_non_existence_proof(&tree_key, height),
+        }
+        .into_storage_result()?;
+        ops.append(&mut proof.ops);
+    }
+    Ok(ProofOps { ops })
+}
+
+/// Prove the values read by a query handler through
+/// [`RequestCtx::storage`]. The queries that don't read any values from
+/// storage, but e.g. from the block metadata, don't support proofs. Neither do
+/// the queries that iterate a storage prefix, as only the yielded entries
+/// would be proven, but not the absence of any other key with the prefix.
+pub fn prove_read_log<D, H>(
+    state: &WlState<D, H>,
+    read_log: ReadLog,
+    height: BlockHeight,
+) -> namada_storage::Result<ProofOps>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+{
+    if read_log.has_prefix_iter() {
+        return Err(namada_storage::Error::new_const(
+            "This query iterates a storage prefix, hence it doesn't support \
+             proofs",
+        ));
+    }
+    let values = read_log.into_values();
+    if values.is_empty() {
+        return Err(namada_storage::Error::new_const(
+            "This query doesn't support proofs",
+        ));
+    }
+    prove_values(
+        state,
+        values.iter().map(|(key, value)| (key, value.as_deref())),
+        height,
+    )
+}
+
 /// For queries that don't use request data, require that there are no data
 /// attached.
 pub fn require_no_data(request: &RequestQuery) -> namada_storage::Result<()> {
@@ -179,6 +287,8 @@ pub(crate) mod testing {
                 vp_wasm_cache: (),
                 tx_wasm_cache: (),
                 storage_read_past_height_limit: None,
+                height: BlockHeight::default(),
+                read_log: None,
             };
             self.rpc.handle(ctx, &request).map_err(|err| {
                 std::io::Error::new(std::io::ErrorKind::Other, err.to_string())
@@ -82,21 +82,35 @@ macro_rules! handle_match {
                 break
         }
         // Check that the request is not sent with unsupported non-default
-        $crate::queries::require_latest_height(&$ctx, $request)?;
-        $crate::queries::require_no_proof($request)?;
         $crate::queries::require_no_data($request)?;
 
-        let last_committed_height = $ctx.state.in_mem().get_last_block_height();
+        let queried_height = $ctx.height;
+        let state = $ctx.state;
+        // Log the values read by the handler to prove them, if requested
+        let read_log = $crate::state::ReadLog::default();
+        let $ctx = $crate::queries::RequestCtx {
+            read_log: $request.prove.then_some(&read_log),
+            ..$ctx
+        };
         // If you get a compile error from here with `expected function, found
         // queries::Storage`, you're probably missing the marker `(sub _)`
         let data = $handle($ctx, $( $matched_args ),* )?;
         // Encode the returned data with borsh
         let data = borsh::to_vec(&data).into_storage_result()?;
+        let proof = if $request.prove {
+            Some($crate::queries::prove_read_log(
+                state,
+                read_log,
+                queried_height,
+            )?)
+        } else {
+            None
+        };
         return Ok($crate::queries::EncodedResponseQuery {
             data,
             info: Default::default(),
-            proof: None,
-            height: last_committed_height,
+            proof,
+            height: queried_height,
         });
     };
 }
@@ -1022,7 +1036,7 @@ mod test_rpc {
 
 #[cfg(test)]
 mod test {
-    use namada_core::chain::Epoch;
+    use namada_core::chain::{BlockHeight, Epoch};
     use namada_core::tendermint::block;
     use namada_core::token;
     use namada_core::token::NATIVE_MAX_DECIMAL_PLACES;
@@ -1049,6 +1063,8 @@ mod test {
             vp_wasm_cache: (),
             tx_wasm_cache: (),
             storage_read_past_height_limit: None,
+            height: BlockHeight::default(),
+            read_log: None,
         };
         let result = TEST_RPC.handle(ctx, &request);
         assert!(result.is_err());
@@ -1068,6 +1084,8 @@ mod test {
             vp_wasm_cache: (),
             tx_wasm_cache: (),
             storage_read_past_height_limit: None,
+            height: BlockHeight::default(),
+            read_log: None,
         };
         let result = TEST_RPC.handle(ctx, &request);
         assert!(result.is_err());
@@ -1085,6 +1103,8 @@ mod test {
             vp_wasm_cache: (),
             tx_wasm_cache: (),
             storage_read_past_height_limit: None,
+            height: BlockHeight::default(),
+            read_log: None,
         };
         let result = TEST_RPC.handle(ctx, &request);
         assert!(matches!(
@@ -1094,6 +1114,30 @@ mod test {
             ))
         ));
 
+        // Test a request for a proof of a query that doesn't read storage
+        let request = RequestQuery {
+            path: "/a".to_owned(),
+            data: Default::default(),
+            height: block::Height::from(0_u32),
+            prove: true,
+        };
+        let ctx = RequestCtx {
+            event_log: &client.event_log,
+            state: &client.state,
+            vp_wasm_cache: (),
+            tx_wasm_cache: (),
+            storage_read_past_height_limit: None,
+            height: BlockHeight::default(),
+            read_log: None,
+        };
+        let result = TEST_RPC.handle(ctx, &request);
+        assert!(matches!(
+            result,
+            Err(namada_storage::Error::SimpleMessage(
+                "This query doesn't support proofs",
+            ))
+        ));
+
         // Test requests to valid paths using the router's methods
         let result = TEST_RPC.a(&client).await.unwrap();
         assert_eq!(result, "a");
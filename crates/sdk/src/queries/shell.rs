@@ -8,11 +8,9 @@ use masp_primitives::merkle_tree::MerklePath;
 use masp_primitives::sapling::Node;
 use namada_account::{Account, AccountPublicKeysMap};
 use namada_core::address::Address;
-use namada_core::arith::checked;
 use namada_core::chain::{BlockHeader, BlockHeight, Epoch};
 use namada_core::dec::Dec;
 use namada_core::hash::Hash;
-use namada_core::hints;
 use namada_core::masp::{MaspEpoch, TokenMap};
 use namada_core::storage::{self, BlockResults, KeySeg, PrefixValue};
 use namada_core::time::DurationSecs;
@@ -23,6 +21,7 @@ use namada_state::{DBIter, LastBlock, StateRead, StorageHasher, DB};
 use namada_storage::{ResultExt, StorageRead};
 use namada_token::masp::MaspTokenRewardData;
 use namada_token::storage_key::masp_token_map_key;
+use namada_tx::data::trace::DryRunTrace;
 use namada_tx::data::DryRunResult;
 
 use self::eth_bridge::{EthBridge, ETH_BRIDGE};
@@ -33,8 +32,9 @@ use crate::ibc::core::host::types::identifiers::{
     ChannelId, ClientId, PortId, Sequence,
 };
 use crate::queries::types::{RequestCtx, RequestQuery};
-use crate::queries::{require_latest_height, EncodedResponseQuery};
-use crate::tendermint::merkle::proof::ProofOps;
+use crate::queries::{
+    prove_values, require_latest_height, EncodedResponseQuery,
+};
 
 type ConversionWithoutPath = (
     Address,
@@ -84,6 +84,9 @@ router! {SHELL,
     // Dry run a transaction
     ( "dry_run_tx" ) -> DryRunResult = (with_options dry_run_tx),
 
+    // Dry run a transaction and trace the execution of its inner txs
+    ( "dry_run_tx_trace" ) -> DryRunTrace = (with_options dry_run_tx_trace),
+
     // Raw storage access - prefix iterator
     ( "prefix" / [storage_key: storage::Key] )
         -> Vec<PrefixValue> = (with_options storage_prefix),
@@ -139,6 +142,17 @@ where
     unimplemented!("Dry running tx requires \"wasm-runtime\" feature.")
 }
 
+fn dry_run_tx_trace<D, H, V, T>(
+    _ctx: RequestCtx<'_, D, H, V, T>,
+    _request: &RequestQuery,
+) -> namada_storage::Result<EncodedResponseQuery>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+{
+    unimplemented!("Dry running tx requires \"wasm-runtime\" feature.")
+}
+
 /// Return an estimate of the maximum time taken to decide a block
 fn max_block_time<D, H, V, T>(
     ctx: RequestCtx<'_, D, H, V, T>,
@@ -151,8 +165,8 @@ where
     const NUM_BLOCKS_TO_READ: u64 = 5;
 
     namada_parameters::estimate_max_block_time_from_blocks_and_params(
-        ctx.state,
-        ctx.state.in_mem().get_last_block_height(),
+        &ctx.storage(),
+        ctx.height,
         NUM_BLOCKS_TO_READ,
     )
 }
@@ -214,6 +228,9 @@ where
     D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
     H: 'static + StorageHasher + Sync,
 {
+    // The conversion state is only kept in memory for the last height
+    require_latest_height(&ctx)?;
+
     Ok(ctx
         .state
         .in_mem()
@@ -244,6 +261,9 @@ where
     D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
     H: 'static + StorageHasher + Sync,
 {
+    // The conversion state is only kept in memory for the last height
+    require_latest_height(&ctx)?;
+
     // Conversion values are constructed on request
     if let Some(asset) =
         ctx.state.in_mem().conversion_state.assets.get(&asset_type)
@@ -275,13 +295,12 @@ where
     D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
     H: 'static + StorageHasher + Sync,
 {
+    let storage = ctx.storage();
     let token_map_key = masp_token_map_key();
-    let token_map: TokenMap =
-        ctx.state.read(&token_map_key)?.unwrap_or_default();
+    let token_map: TokenMap = storage.read(&token_map_key)?.unwrap_or_default();
     let mut data = Vec::<MaspTokenRewardData>::new();
     for (name, token) in token_map {
-        let max_reward_rate = ctx
-            .state
+        let max_reward_rate = storage
             .read::<Dec>(&namada_token::storage_key::masp_max_reward_rate_key(
                 &token,
             ))?
@@ -294,8 +313,7 @@ where
                     ),
                 ))
             })?;
-        let kd_gain = ctx
-            .state
+        let kd_gain = storage
             .read::<Dec>(&namada_token::storage_key::masp_kd_gain_key(&token))?
             .ok_or_else(|| {
                 namada_storage::Error::new(std::io::Error::new(
@@ -306,8 +324,7 @@ where
                     ),
                 ))
             })?;
-        let kp_gain = ctx
-            .state
+        let kp_gain = storage
             .read::<Dec>(&namada_token::storage_key::masp_kp_gain_key(&token))?
             .ok_or_else(|| {
                 namada_storage::Error::new(std::io::Error::new(
@@ -318,8 +335,7 @@ where
                     ),
                 ))
             })?;
-        let locked_amount_target = ctx
-            .state
+        let locked_amount_target = storage
             .read::<Uint>(
                 &namada_token::storage_key::masp_locked_amount_target_key(
                     &token,
@@ -355,8 +371,7 @@ where
     D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
     H: 'static + StorageHasher + Sync,
 {
-    let data = ctx.state.in_mem().last_epoch;
-    Ok(data)
+    ctx.storage().get_block_epoch()
 }
 
 fn masp_epoch<D, H, V, T>(
@@ -366,9 +381,10 @@ where
     D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
     H: 'static + StorageHasher + Sync,
 {
-    let epoch = ctx.state.in_mem().last_epoch;
+    let storage = ctx.storage();
+    let epoch = storage.get_block_epoch()?;
     let masp_epoch_multiplier =
-        namada_parameters::read_masp_epoch_multiplier_parameter(ctx.state)?;
+        namada_parameters::read_masp_epoch_multiplier_parameter(&storage)?;
     MaspEpoch::try_from_epoch(epoch, masp_epoch_multiplier)
         .map_err(namada_storage::Error::new_const)
 }
@@ -402,6 +418,7 @@ where
     D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
     H: 'static + StorageHasher + Sync,
 {
+    require_latest_height(&ctx)?;
     Ok(ctx.state.in_mem().last_block.clone())
 }
 
@@ -416,13 +433,11 @@ where
         .in_mem()
         .block
         .pred_epochs
-        .first_block_heights
-        .last()
+        .get_epoch_start_height(ctx.height)
         .ok_or(namada_storage::Error::new(std::io::Error::new(
             std::io::ErrorKind::InvalidData,
             "The pred_epochs is unexpectedly empty",
         )))
-        .cloned()
 }
 
 /// Returns data with `vec![]` when the storage key is not found. For all
@@ -438,31 +453,7 @@ where
     D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
     H: 'static + StorageHasher + Sync,
 {
-    let last_committed_height = ctx.state.in_mem().get_last_block_height();
-    let queried_height = {
-        let height: BlockHeight = request.height.into();
-        let is_last_height_query = height.0 == 0;
-
-        if hints::likely(is_last_height_query) {
-            last_committed_height
-        } else {
-            height
-        }
-    };
-
-    if let Some(past_height_limit) = ctx.storage_read_past_height_limit {
-        if checked!(queried_height + past_height_limit)? < last_committed_height
-        {
-            return Err(namada_storage::Error::new(std::io::Error::new(
-                std::io::ErrorKind::InvalidInput,
-                format!(
-                    "Cannot query more than {past_height_limit} blocks in the \
-                     past (configured via \
-                     `shell.storage_read_past_height_limit`)."
-                ),
-            )));
-        }
-    }
+    let queried_height = ctx.height;
 
     match ctx
         .state
@@ -471,10 +462,11 @@ where
     {
         (Some(value), _gas) => {
             let proof = if request.prove {
-                let proof = ctx
-                    .state
-                    .get_existence_proof(&storage_key, &value, queried_height)
-                    .into_storage_result()?;
+                let proof = prove_values(
+                    ctx.state,
+                    [(&storage_key, Some(value.as_slice()))],
+                    queried_height,
+                )?;
                 Some(proof)
             } else {
                 None
@@ -488,10 +480,11 @@ where
         }
         (None, _gas) => {
             let proof = if request.prove {
-                let proof = ctx
-                    .state
-                    .get_non_existence_proof(&storage_key, queried_height)
-                    .into_storage_result()?;
+                let proof = prove_values(
+                    ctx.state,
+                    [(&storage_key, None)],
+                    queried_height,
+                )?;
                 Some(proof)
             } else {
                 None
@@ -515,9 +508,8 @@ where
     D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
     H: 'static + StorageHasher + Sync,
 {
-    require_latest_height(&ctx, request)?;
-
-    let iter = namada_storage::iter_prefix_bytes(ctx.state, &storage_key)?;
+    let storage = ctx.storage();
+    let iter = namada_storage::iter_prefix_bytes(&storage, &storage_key)?;
     let data: namada_storage::Result<Vec<PrefixValue>> = iter
         .map(|iter_result| {
             let (key, value) = iter_result?;
@@ -525,28 +517,12 @@ where
         })
         .collect();
     let data = data?;
-    let queried_height = {
-        let height: BlockHeight = request.height.into();
-        let is_last_height_query = height.0 == 0;
-
-        if hints::likely(is_last_height_query) {
-            ctx.state.in_mem().get_last_block_height()
-        } else {
-            height
-        }
-    };
+    let queried_height = ctx.height;
     let proof = if request.prove {
-        let mut ops = vec![];
-        for PrefixValue { key, value } in &data {
-            let mut proof = ctx
-                .state
-                .get_existence_proof(key, value, queried_height)
-                .into_storage_result()?;
-            ops.append(&mut proof.ops);
-        }
-        // ops is not empty in this case
-        let proof = ProofOps { ops };
-        Some(proof)
+        let values = data
+            .iter()
+            .map(|PrefixValue { key, value }| (key, Some(value.as_slice())));
+        Some(prove_values(ctx.state, values, queried_height)?)
     } else {
         None
     };
@@ -567,7 +543,7 @@ where
     D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
     H: 'static + StorageHasher + Sync,
 {
-    let data = StorageRead::has_key(ctx.state, &storage_key)?;
+    let data = StorageRead::has_key(&ctx.storage(), &storage_key)?;
     Ok(data)
 }
 
@@ -631,11 +607,12 @@ where
     D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
     H: 'static + StorageHasher + Sync,
 {
-    let account_exists = namada_account::exists(ctx.state, &owner)?;
+    let storage = ctx.storage();
+    let account_exists = namada_account::exists(&storage, &owner)?;
 
     if account_exists {
-        let public_keys = namada_account::public_keys(ctx.state, &owner)?;
-        let threshold = namada_account::threshold(ctx.state, &owner)?;
+        let public_keys = namada_account::public_keys(&storage, &owner)?;
+        let threshold = namada_account::threshold(&storage, &owner)?;
 
         Ok(Some(Account {
             public_keys_map: AccountPublicKeysMap::from_iter(public_keys),
@@ -655,7 +632,7 @@ where
     D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
     H: 'static + StorageHasher + Sync,
 {
-    let public_keys = namada_account::public_keys(ctx.state, &owner)?;
+    let public_keys = namada_account::public_keys(&ctx.storage(), &owner)?;
 
     Ok(!public_keys.is_empty())
 }
@@ -681,6 +658,9 @@ mod test {
         let path = RPC.shell().dry_run_tx_path();
         assert_eq!("/shell/dry_run_tx", path);
 
+        let path = RPC.shell().dry_run_tx_trace_path();
+        assert_eq!("/shell/dry_run_tx_trace", path);
+
         let path = RPC.shell().storage_prefix_path(&key);
         assert_eq!(format!("/shell/prefix/{}", key), path);
 
@@ -44,7 +44,9 @@ use serde::{Deserialize, Serialize};
 use crate::borsh::BorshSerializeExt;
 use crate::eth_bridge::ethers::abi::AbiDecode;
 use crate::governance;
-use crate::queries::{EncodedResponseQuery, RequestCtx, RequestQuery};
+use crate::queries::{
+    require_latest_height, EncodedResponseQuery, RequestCtx, RequestQuery,
+};
 
 /// Container for the status of queried transfers to Ethereum.
 #[derive(
@@ -350,6 +352,8 @@ where
     D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
     H: 'static + StorageHasher + Sync,
 {
+    require_latest_height(&ctx)?;
+
     let ethbridge_queries = ctx.state.ethbridge_queries();
 
     let whitelisted = ethbridge_queries.is_token_whitelisted(&asset);
@@ -375,7 +379,7 @@ where
     H: 'static + StorageHasher + Sync,
     T: BorshDeserialize,
 {
-    let Some(contract) = StorageRead::read(ctx.state, key)? else {
+    let Some(contract) = StorageRead::read(&ctx.storage(), key)? else {
         return Err(namada_storage::Error::SimpleMessage(
             "Failed to read contract: The Ethereum bridge storage is not \
              initialized",
@@ -419,10 +423,7 @@ where
     D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
     H: 'static + StorageHasher + Sync,
 {
-    Ok(read_ethereum_bridge_pool_at_height(
-        ctx.state.in_mem().get_last_block_height(),
-        ctx,
-    ))
+    Ok(read_ethereum_bridge_pool_at_height(ctx.height, ctx))
 }
 
 /// Read the contents of the Ethereum bridge
@@ -434,6 +435,8 @@ where
     D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
     H: 'static + StorageHasher + Sync,
 {
+    require_latest_height(&ctx)?;
+
     // get the latest signed merkle root of the Ethereum bridge pool
     let (_, height) = ctx
         .state
@@ -624,6 +627,8 @@ where
     D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
     H: 'static + StorageHasher + Sync,
 {
+    require_latest_height(&ctx)?;
+
     let mut pending_events = HashMap::new();
     for (mut key, value) in ctx
         .state
@@ -696,6 +701,8 @@ where
     D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
     H: 'static + StorageHasher + Sync,
 {
+    require_latest_height(&ctx)?;
+
     if epoch.0 == 0 {
         return Err(namada_storage::Error::Custom(CustomError(
             "Validator set update proofs should only be requested from epoch \
@@ -746,6 +753,8 @@ where
     D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
     H: 'static + StorageHasher + Sync,
 {
+    require_latest_height(&ctx)?;
+
     let current_epoch = ctx.state.in_mem().last_epoch;
     if epoch > current_epoch.next() {
         Err(namada_storage::Error::Custom(CustomError(
@@ -776,6 +785,8 @@ where
     D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
     H: 'static + StorageHasher + Sync,
 {
+    require_latest_height(&ctx)?;
+
     let current_epoch = ctx.state.in_mem().last_epoch;
     if epoch > current_epoch.next() {
         Err(namada_storage::Error::Custom(CustomError(
@@ -823,6 +834,8 @@ where
     D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
     H: 'static + StorageHasher + Sync,
 {
+    require_latest_height(&ctx)?;
+
     let current_epoch = ctx.state.in_mem().get_current_epoch().0;
     if epoch > checked!(current_epoch + 1u64)? {
         return Err(namada_storage::Error::SimpleMessage(
@@ -1,7 +1,10 @@
 use std::fmt::Debug;
 
+use namada_core::chain::BlockHeight;
 pub use namada_io::client::{EncodedResponseQuery, Error, ResponseQuery};
-use namada_state::{DBIter, StorageHasher, WlState, DB};
+use namada_state::{
+    DBIter, HistoricalState, ReadLog, StorageHasher, WlState, DB,
+};
 
 use crate::events::log::EventLog;
 pub use crate::tendermint::abci::request::Query as RequestQuery;
@@ -25,6 +28,25 @@ where
     /// limit how many block heights in the past can the storage be
     /// queried for reading values.
     pub storage_read_past_height_limit: Option<u64>,
+    /// The committed block height at which the state is queried. This is
+    /// set by [`Router::handle`] from the height of the request.
+    pub height: BlockHeight,
+    /// Log of the values read through [`RequestCtx::storage`]. This is set by
+    /// the router for the requests that ask for a proof of the values that
+    /// the response was computed from.
+    pub read_log: Option<&'shell ReadLog>,
+}
+
+impl<'shell, D, H, V, T> RequestCtx<'shell, D, H, V, T>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+{
+    /// Read-only view of the state at the queried height.
+    pub fn storage(&self) -> HistoricalState<'shell, D, H> {
+        HistoricalState::new(self.state, self.height)
+            .with_read_log(self.read_log)
+    }
 }
 
 /// A `Router` handles parsing read-only query requests and dispatching them to
@@ -46,7 +68,8 @@ pub trait Router {
                 "Non-ascii request paths are unsupported",
             ));
         }
-        self.internal_handle(ctx, request, 0)
+        let height = super::queried_height(&ctx, request)?;
+        self.internal_handle(RequestCtx { height, ..ctx }, request, 0)
     }
 
     /// Internal method which shouldn't be invoked directly. Instead, you may
@@ -22,7 +22,7 @@ where
     D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
     H: 'static + StorageHasher + Sync,
 {
-    namada_governance::pgf::storage::get_stewards(ctx.state)
+    namada_governance::pgf::storage::get_stewards(&ctx.storage())
 }
 
 /// Check if an address is a pgf steward
@@ -34,7 +34,7 @@ where
     D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
     H: 'static + StorageHasher + Sync,
 {
-    namada_governance::pgf::storage::is_steward(ctx.state, &address)
+    namada_governance::pgf::storage::is_steward(&ctx.storage(), &address)
 }
 
 /// Query the continuous pgf fundings
@@ -45,7 +45,7 @@ where
     D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
     H: 'static + StorageHasher + Sync,
 {
-    namada_governance::pgf::storage::get_continuous_pgf_payments(ctx.state)
+    namada_governance::pgf::storage::get_continuous_pgf_payments(&ctx.storage())
 }
 
 /// Query the PGF parameters
@@ -56,5 +56,5 @@ where
     D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
     H: 'static + StorageHasher + Sync,
 {
-    namada_governance::pgf::storage::get_parameters(ctx.state)
+    namada_governance::pgf::storage::get_parameters(&ctx.storage())
 }
@@ -34,11 +34,12 @@ use namada_proof_of_stake::types::{
     WeightedValidator,
 };
 use namada_proof_of_stake::{bond_amount, query_reward_tokens};
-use namada_state::{DBIter, KeySeg, StorageHasher, DB};
+use namada_state::{DBIter, KeySeg, StorageHasher, StorageRead, DB};
 use namada_storage::collections::lazy_map;
 use namada_storage::OptionExt;
 
 use crate::governance;
+use crate::queries::require_latest_height;
 use crate::queries::types::RequestCtx;
 
 // PoS validity predicate queries
@@ -192,7 +193,7 @@ where
     D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
     H: 'static + StorageHasher + Sync,
 {
-    read_pos_params::<_, governance::Store<_>>(ctx.state)
+    read_pos_params::<_, governance::Store<_>>(&ctx.storage())
 }
 
 /// Find if the given address belongs to a validator account.
@@ -204,7 +205,7 @@ where
     D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
     H: 'static + StorageHasher + Sync,
 {
-    namada_proof_of_stake::is_validator(ctx.state, &addr)
+    namada_proof_of_stake::is_validator(&ctx.storage(), &addr)
 }
 
 /// Find a consensus key of a validator account.
@@ -216,9 +217,10 @@ where
     D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
     H: 'static + StorageHasher + Sync,
 {
-    let current_epoch = ctx.state.in_mem().last_epoch;
+    let storage = ctx.storage();
+    let current_epoch = storage.get_block_epoch()?;
     namada_proof_of_stake::storage::get_consensus_key::<_, governance::Store<_>>(
-        ctx.state,
+        &storage,
         &addr,
         current_epoch,
     )
@@ -234,7 +236,7 @@ where
     D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
     H: 'static + StorageHasher + Sync,
 {
-    namada_proof_of_stake::is_delegator(ctx.state, &addr, epoch)
+    namada_proof_of_stake::is_delegator(&ctx.storage(), &addr, epoch)
 }
 
 /// Get all the validator known addresses. These validators may be in any state,
@@ -247,8 +249,9 @@ where
     D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
     H: 'static + StorageHasher + Sync,
 {
-    let epoch = epoch.unwrap_or(ctx.state.in_mem().last_epoch);
-    read_all_validator_addresses(ctx.state, epoch)
+    let storage = ctx.storage();
+    let epoch = epoch.unwrap_or(storage.get_block_epoch()?);
+    read_all_validator_addresses(&storage, epoch)
 }
 
 /// Get liveness information for all consensus validators in the current epoch.
@@ -259,20 +262,21 @@ where
     D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
     H: 'static + StorageHasher + Sync,
 {
-    let epoch = ctx.state.in_mem().last_epoch;
+    let storage = ctx.storage();
+    let epoch = storage.get_block_epoch()?;
     let consensus_validators =
-        read_consensus_validator_set_addresses(ctx.state, epoch)?;
-    let params = read_pos_params::<_, governance::Store<_>>(ctx.state)?;
+        read_consensus_validator_set_addresses(&storage, epoch)?;
+    let params = read_pos_params::<_, governance::Store<_>>(&storage)?;
 
     let mut result = Vec::with_capacity(consensus_validators.len());
     for validator in consensus_validators {
         if let Some(pubkey) = get_consensus_key::<_, governance::Store<_>>(
-            ctx.state, &validator, epoch,
+            &storage, &validator, epoch,
         )? {
             let comet_address = tm_consensus_key_raw_hash(&pubkey);
             let sum_liveness_handle = liveness_sum_missed_votes_handle();
             let missed_votes = sum_liveness_handle
-                .get(ctx.state, &validator)?
+                .get(&storage, &validator)?
                 .unwrap_or_default();
             result.push(ValidatorLiveness {
                 native_address: validator,
@@ -299,12 +303,13 @@ where
     D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
     H: 'static + StorageHasher + Sync,
 {
-    let epoch = epoch.unwrap_or(ctx.state.in_mem().last_epoch);
-    let params = read_pos_params::<_, governance::Store<_>>(ctx.state)?;
+    let storage = ctx.storage();
+    let epoch = epoch.unwrap_or(storage.get_block_epoch()?);
+    let params = read_pos_params::<_, governance::Store<_>>(&storage)?;
     let commission_rate = validator_commission_rate_handle(&validator)
-        .get(ctx.state, epoch, &params)?;
+        .get(&storage, epoch, &params)?;
     let max_commission_change_per_epoch =
-        read_validator_max_commission_rate_change(ctx.state, &validator)?;
+        read_validator_max_commission_rate_change(&storage, &validator)?;
 
     Ok(CommissionPair {
         commission_rate,
@@ -322,7 +327,7 @@ where
     D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
     H: 'static + StorageHasher + Sync,
 {
-    read_validator_metadata(ctx.state, &validator)
+    read_validator_metadata(&ctx.storage(), &validator)
 }
 
 /// Get the validator state
@@ -335,11 +340,12 @@ where
     D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
     H: 'static + StorageHasher + Sync,
 {
-    let epoch = epoch.unwrap_or(ctx.state.in_mem().last_epoch);
+    let storage = ctx.storage();
+    let epoch = epoch.unwrap_or(storage.get_block_epoch()?);
     let state = namada_proof_of_stake::storage::read_validator_state::<
         _,
         governance::Store<_>,
-    >(ctx.state, &validator, epoch)?;
+    >(&storage, &validator, epoch)?;
     Ok((state, epoch))
 }
 
@@ -352,7 +358,7 @@ where
     D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
     H: 'static + StorageHasher + Sync,
 {
-    read_validator_last_slash_epoch(ctx.state, &validator)
+    read_validator_last_slash_epoch(&ctx.storage(), &validator)
 }
 
 /// Get the total stake of a validator at the given epoch or current when
@@ -369,11 +375,11 @@ where
     D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
     H: 'static + StorageHasher + Sync,
 {
-    let epoch = epoch.unwrap_or(ctx.state.in_mem().last_epoch);
-    let params = read_pos_params::<_, governance::Store<_>>(ctx.state)?;
-    if namada_proof_of_stake::is_validator(ctx.state, &validator)? {
-        let stake =
-            read_validator_stake(ctx.state, &params, &validator, epoch)?;
+    let storage = ctx.storage();
+    let epoch = epoch.unwrap_or(storage.get_block_epoch()?);
+    let params = read_pos_params::<_, governance::Store<_>>(&storage)?;
+    if namada_proof_of_stake::is_validator(&storage, &validator)? {
+        let stake = read_validator_stake(&storage, &params, &validator, epoch)?;
         Ok(Some(stake))
     } else {
         Ok(None)
@@ -392,7 +398,7 @@ where
     H: 'static + StorageHasher + Sync,
 {
     let handle = validator_incoming_redelegations_handle(&src_validator);
-    handle.get(ctx.state, &delegator)
+    handle.get(&ctx.storage(), &delegator)
 }
 
 /// Get all the validator in the consensus set with their bonded stake.
@@ -404,8 +410,9 @@ where
     D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
     H: 'static + StorageHasher + Sync,
 {
-    let epoch = epoch.unwrap_or(ctx.state.in_mem().last_epoch);
-    read_consensus_validator_set_addresses_with_stake(ctx.state, epoch)
+    let storage = ctx.storage();
+    let epoch = epoch.unwrap_or(storage.get_block_epoch()?);
+    read_consensus_validator_set_addresses_with_stake(&storage, epoch)
 }
 
 /// Get all the validator in the below-capacity set with their bonded stake.
@@ -417,8 +424,9 @@ where
     D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
     H: 'static + StorageHasher + Sync,
 {
-    let epoch = epoch.unwrap_or(ctx.state.in_mem().last_epoch);
-    read_below_capacity_validator_set_addresses_with_stake(ctx.state, epoch)
+    let storage = ctx.storage();
+    let epoch = epoch.unwrap_or(storage.get_block_epoch()?);
+    read_below_capacity_validator_set_addresses_with_stake(&storage, epoch)
 }
 
 /// Get the total stake in PoS system at the given epoch or current when `None`.
@@ -430,9 +438,10 @@ where
     D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
     H: 'static + StorageHasher + Sync,
 {
-    let epoch = epoch.unwrap_or(ctx.state.in_mem().last_epoch);
-    let params = read_pos_params::<_, governance::Store<_>>(ctx.state)?;
-    read_total_stake(ctx.state, &params, epoch)
+    let storage = ctx.storage();
+    let epoch = epoch.unwrap_or(storage.get_block_epoch()?);
+    let params = read_pos_params::<_, governance::Store<_>>(&storage)?;
+    read_total_stake(&storage, &params, epoch)
 }
 
 /// Get the total active voting power in PoS system at the given epoch or
@@ -445,9 +454,10 @@ where
     D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
     H: 'static + StorageHasher + Sync,
 {
-    let epoch = epoch.unwrap_or(ctx.state.in_mem().last_epoch);
-    let params = read_pos_params::<_, governance::Store<_>>(ctx.state)?;
-    read_total_active_stake(ctx.state, &params, epoch)
+    let storage = ctx.storage();
+    let epoch = epoch.unwrap_or(storage.get_block_epoch()?);
+    let params = read_pos_params::<_, governance::Store<_>>(&storage)?;
+    read_total_active_stake(&storage, &params, epoch)
 }
 
 fn bond_deltas<D, H, V, T>(
@@ -459,7 +469,7 @@ where
     D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
     H: 'static + StorageHasher + Sync,
 {
-    bond_handle(&source, &validator).to_hashmap(ctx.state)
+    bond_handle(&source, &validator).to_hashmap(&ctx.storage())
 }
 
 /// Find the sum of bond amount up the given epoch when `Some`, or up to the
@@ -474,17 +484,17 @@ where
     D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
     H: 'static + StorageHasher + Sync,
 {
-    let params = read_pos_params::<_, governance::Store<_>>(ctx.state)?;
+    let storage = ctx.storage();
+    let params = read_pos_params::<_, governance::Store<_>>(&storage)?;
     let epoch = epoch.unwrap_or(
-        ctx.state
-            .in_mem()
-            .last_epoch
+        storage
+            .get_block_epoch()?
             .unchecked_add(params.pipeline_len),
     );
 
     let handle = bond_handle(&source, &validator);
     handle
-        .get_sum(ctx.state, epoch, &params)?
+        .get_sum(&storage, epoch, &params)?
         .ok_or_err_msg("Cannot find bond")
 }
 
@@ -498,10 +508,11 @@ where
     D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
     H: 'static + StorageHasher + Sync,
 {
-    let epoch = epoch.unwrap_or(ctx.state.in_mem().last_epoch);
+    let storage = ctx.storage();
+    let epoch = epoch.unwrap_or(storage.get_block_epoch()?);
     let bond_id = BondId { source, validator };
 
-    bond_amount::<_, governance::Store<_>>(ctx.state, &bond_id, epoch)
+    bond_amount::<_, governance::Store<_>>(&storage, &bond_id, epoch)
 }
 
 fn unbond<D, H, V, T>(
@@ -514,7 +525,8 @@ where
     H: 'static + StorageHasher + Sync,
 {
     let handle = unbond_handle(&source, &validator);
-    let iter = handle.iter(ctx.state)?;
+    let storage = ctx.storage();
+    let iter = handle.iter(&storage)?;
     iter.map(|next_result| {
         next_result.map(
             |(
@@ -540,7 +552,8 @@ where
 {
     // TODO slashes
     let handle = unbond_handle(&source, &validator);
-    let iter = handle.iter(ctx.state)?;
+    let storage = ctx.storage();
+    let iter = handle.iter(&storage)?;
     iter.map(|next_result| {
         next_result.map(
             |(
@@ -565,11 +578,12 @@ where
     D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
     H: 'static + StorageHasher + Sync,
 {
-    let epoch = epoch.unwrap_or(ctx.state.in_mem().last_epoch);
+    let storage = ctx.storage();
+    let epoch = epoch.unwrap_or(storage.get_block_epoch()?);
 
     let handle = unbond_handle(&source, &validator);
     let mut total = token::Amount::zero();
-    for result in handle.iter(ctx.state)? {
+    for result in handle.iter(&storage)? {
         let (
             lazy_map::NestedSubKey::Data {
                 key: _start,
@@ -593,9 +607,10 @@ where
     D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
     H: 'static + StorageHasher + Sync,
 {
-    let current_epoch = ctx.state.in_mem().last_epoch;
+    let storage = ctx.storage();
+    let current_epoch = storage.get_block_epoch()?;
     query_reward_tokens::<_, governance::Store<_>>(
-        ctx.state,
+        &storage,
         source.as_ref(),
         &validator,
         current_epoch,
@@ -612,12 +627,16 @@ where
     H: 'static + StorageHasher + Sync,
 {
     namada_proof_of_stake::queries::bonds_and_unbonds::<_, governance::Store<_>>(
-        ctx.state, source, validator,
+        &ctx.storage(),
+        source,
+        validator,
     )
 }
 
 /// Find all the validator addresses to whom the given `owner` address has
-/// some delegation in any epoch
+/// some delegation in any epoch. The delegation targets are not stored to
+/// diffs, so they are only available at the latest height. The delegations at
+/// past epochs can be found with the `epoch` argument instead.
 fn delegation_validators<D, H, V, T>(
     ctx: RequestCtx<'_, D, H, V, T>,
     owner: Address,
@@ -627,12 +646,15 @@ where
     D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
     H: 'static + StorageHasher + Sync,
 {
-    let epoch = epoch.unwrap_or(ctx.state.in_mem().last_epoch);
-    find_delegation_validators(ctx.state, &owner, &epoch)
+    require_latest_height(&ctx)?;
+    let storage = ctx.storage();
+    let epoch = epoch.unwrap_or(storage.get_block_epoch()?);
+    find_delegation_validators(&storage, &owner, &epoch)
 }
 
 /// Find all the validator addresses to whom the given `owner` address has
-/// some delegation in any epoch
+/// some delegation in any epoch. Like [`delegation_validators`], this is only
+/// available at the latest height.
 fn delegations<D, H, V, T>(
     ctx: RequestCtx<'_, D, H, V, T>,
     owner: Address,
@@ -642,8 +664,10 @@ where
     D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
     H: 'static + StorageHasher + Sync,
 {
-    let epoch: Epoch = epoch.unwrap_or(ctx.state.in_mem().last_epoch);
-    find_delegations::<_, governance::Store<_>>(ctx.state, &owner, &epoch)
+    require_latest_height(&ctx)?;
+    let storage = ctx.storage();
+    let epoch: Epoch = epoch.unwrap_or(storage.get_block_epoch()?);
+    find_delegations::<_, governance::Store<_>>(&storage, &owner, &epoch)
 }
 
 /// Validator slashes
@@ -656,7 +680,7 @@ where
     H: 'static + StorageHasher + Sync,
 {
     let slash_handle = validator_slashes_handle(&validator);
-    slash_handle.iter(ctx.state)?.collect()
+    slash_handle.iter(&ctx.storage())?.collect()
 }
 
 /// All slashes
@@ -667,7 +691,7 @@ where
     D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
     H: 'static + StorageHasher + Sync,
 {
-    find_all_slashes(ctx.state)
+    find_all_slashes(&ctx.storage())
 }
 
 /// Enqueued slashes
@@ -678,8 +702,9 @@ where
     D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
     H: 'static + StorageHasher + Sync,
 {
-    let current_epoch = ctx.state.in_mem().last_epoch;
-    find_all_enqueued_slashes(ctx.state, current_epoch)
+    let storage = ctx.storage();
+    let current_epoch = storage.get_block_epoch()?;
+    find_all_enqueued_slashes(&storage, current_epoch)
 }
 
 /// Native validator address by looking up the Tendermint address
@@ -699,7 +724,8 @@ where
         ));
     }
     namada_proof_of_stake::storage::find_validator_by_raw_hash(
-        ctx.state, tm_addr,
+        &ctx.storage(),
+        tm_addr,
     )
 }
 
@@ -711,7 +737,7 @@ where
     D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
     H: 'static + StorageHasher + Sync,
 {
-    namada_proof_of_stake::storage::get_consensus_key_set(ctx.state)
+    namada_proof_of_stake::storage::get_consensus_key_set(&ctx.storage())
 }
 
 /// Find if the given source address has any bonds.
@@ -724,7 +750,8 @@ where
     H: 'static + StorageHasher + Sync,
 {
     namada_proof_of_stake::queries::has_bonds::<_, governance::Store<_>>(
-        ctx.state, &source,
+        &ctx.storage(),
+        &source,
     )
 }
 
@@ -832,6 +859,11 @@ fn enrich_bonds_and_unbonds(
 
 #[cfg(test)]
 mod test {
+    use namada_core::address::testing::established_address_1;
+    use namada_core::chain::BlockHeight;
+    use namada_core::time::DateTimeUtc;
+    use namada_state::LastBlock;
+
     use super::*;
     use crate::queries::testing::TestClient;
     use crate::queries::{RequestCtx, RequestQuery, Router};
@@ -858,6 +890,8 @@ mod test {
             vp_wasm_cache: (),
             tx_wasm_cache: (),
             storage_read_past_height_limit: None,
+            height: BlockHeight::default(),
+            read_log: None,
         };
         let result = POS.handle(ctx, &request);
         assert!(result.is_err());
@@ -868,4 +902,48 @@ mod test {
                 .contains("Invalid Tendermint address")
         )
     }
+
+    /// Test that the delegations, whose targets are not stored to diffs, can
+    /// only be queried at the latest height.
+    #[tokio::test]
+    async fn test_delegations_at_past_height() {
+        let mut client = TestClient::new(POS);
+        client.state.in_mem_mut().last_block = Some(LastBlock {
+            height: BlockHeight(2),
+            time: DateTimeUtc::unix_epoch(),
+        });
+        let owner = established_address_1();
+
+        for path in ["delegations", "delegations_at"] {
+            for height in [BlockHeight(1), BlockHeight(2)] {
+                let request = RequestQuery {
+                    path: format!("/{path}/{owner}"),
+                    data: Default::default(),
+                    height: height.try_into().unwrap(),
+                    prove: Default::default(),
+                };
+                let ctx = RequestCtx {
+                    event_log: &client.event_log,
+                    state: &client.state,
+                    vp_wasm_cache: (),
+                    tx_wasm_cache: (),
+                    storage_read_past_height_limit: None,
+                    height,
+                    read_log: None,
+                };
+                let result = POS.handle(ctx, &request);
+                if height == BlockHeight(2) {
+                    assert!(result.is_ok(), "{path} at the latest height");
+                } else {
+                    assert!(
+                        result
+                            .unwrap_err()
+                            .to_string()
+                            .contains("only the latest committed block height"),
+                        "{path} at a past height"
+                    );
+                }
+            }
+        }
+    }
 }
@@ -6,6 +6,9 @@ use namada_proof_of_stake::rewards::{
     estimate_staking_reward_rate, PosRewardsRates,
 };
 use namada_state::{DBIter, StorageHasher, DB};
+use namada_token::conversion::{
+    read_masp_rewards_projection_params, MaspRewardsProjectionParams,
+};
 use namada_token::{
     get_effective_total_native_supply, read_denom, read_total_supply,
 };
@@ -17,6 +20,7 @@ router! {TOKEN,
     ( "total_supply" / [token: Address] ) -> token::Amount = total_supply,
     ( "effective_native_supply" ) -> token::Amount = effective_native_supply,
     ( "staking_rewards_rate" ) -> PosRewardsRates = staking_rewards_rate,
+    ( "masp_rewards_projection_params" / [token: Address] ) -> MaspRewardsProjectionParams = masp_rewards_projection_params,
 }
 
 /// Get the number of decimal places (in base 10) for a
@@ -29,7 +33,7 @@ where
     D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
     H: 'static + StorageHasher + Sync,
 {
-    read_denom(ctx.state, &token)
+    read_denom(&ctx.storage(), &token)
 }
 
 /// Get the total supply for a token address
@@ -41,7 +45,7 @@ where
     D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
     H: 'static + StorageHasher + Sync,
 {
-    read_total_supply(ctx.state, &token)
+    read_total_supply(&ctx.storage(), &token)
 }
 
 /// Get the effective total supply of the native token
@@ -52,7 +56,7 @@ where
     D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
     H: 'static + StorageHasher + Sync,
 {
-    get_effective_total_native_supply(ctx.state)
+    get_effective_total_native_supply(&ctx.storage())
 }
 
 /// Get the effective total supply of the native token
@@ -67,7 +71,23 @@ where
         _,
         crate::token::Store<_>,
         crate::parameters::Store<_>,
-    >(ctx.state)
+    >(&ctx.storage())
+}
+
+/// Get the parameters from which the MASP rewards of a token are projected
+fn masp_rewards_projection_params<D, H, V, T>(
+    ctx: RequestCtx<'_, D, H, V, T>,
+    token: Address,
+) -> namada_storage::Result<MaspRewardsProjectionParams>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+{
+    read_masp_rewards_projection_params::<
+        _,
+        crate::parameters::Store<_>,
+        crate::token::Store<_>,
+    >(&ctx.storage(), &token)
 }
 
 pub mod client_only_methods {
@@ -48,7 +48,11 @@ use namada_proof_of_stake::types::{
     WeightedValidator,
 };
 use namada_state::{BlockHeader, LastBlock};
+use namada_token::conversion::{
+    project_masp_rewards, LockedAmountGrowth, MaspRewardsProjection,
+};
 use namada_token::masp::MaspTokenRewardData;
+use namada_tx::data::trace::DryRunTrace;
 use namada_tx::data::{BatchedTxResult, DryRunResult, ResultCode, TxResult};
 use namada_tx::event::{Batch as BatchAttr, Code as CodeAttr};
 use serde::Serialize;
@@ -257,6 +261,27 @@ pub async fn get_staking_rewards_rate<C: Client + Sync>(
     )
 }
 
+/// Project the MASP rewards of a token, and of the given shielded balance of
+/// it, over the given number of future MASP epochs assuming the given growth
+/// of the amount of the token locked in the shielded pool.
+pub async fn query_masp_rewards_projection<C: Client + Sync>(
+    client: &C,
+    token: &Address,
+    growth: LockedAmountGrowth,
+    balance: token::Amount,
+    epochs: u64,
+) -> Result<Vec<MaspRewardsProjection>, error::Error> {
+    let params = convert_response::<C, _>(
+        RPC.vp()
+            .token()
+            .masp_rewards_projection_params(client, token)
+            .await,
+    )?;
+    let native_token = query_native_token(client).await?;
+    project_masp_rewards(&params, growth, balance, epochs, &native_token)
+        .map_err(|err| error::Error::Other(err.to_string()))
+}
+
 /// Check if the given address is a known validator.
 pub async fn is_validator<C: namada_io::Client + Sync>(
     client: &C,
@@ -596,7 +621,36 @@ pub async fn dry_run_tx<N: Namada>(
             .await,
     )?
     .data;
+    display_dry_run_result(context, &result);
+
+    Ok(result)
+}
 
+/// Dry run a transaction, tracing the execution of its inner transactions
+pub async fn dry_run_tx_trace<N: Namada>(
+    context: &N,
+    tx_bytes: Vec<u8>,
+) -> Result<DryRunTrace, Error> {
+    let (data, height, prove) = (Some(tx_bytes), None, false);
+    let trace = convert_response::<N::Client, _>(
+        RPC.shell()
+            .dry_run_tx_trace(context.client(), data, height, prove)
+            .await,
+    )?
+    .data;
+    display_dry_run_result(context, &trace.result);
+    for (inner_hash, inner_tx_trace) in &trace.traces {
+        display_line!(
+            context.io(),
+            "Trace of transaction {inner_hash}:\n{inner_tx_trace}"
+        );
+    }
+
+    Ok(trace)
+}
+
+/// Display the results of the inner transactions of a dry run
+fn display_dry_run_result<N: Namada>(context: &N, result: &DryRunResult) {
     display_line!(context.io(), "Dry-run result:");
     let mut all_inners_successful = true;
     for (inner_hash, cmt_result) in result.0.iter() {
@@ -647,8 +701,6 @@ pub async fn dry_run_tx<N: Namada>(
             result.1
         );
     }
-
-    Ok(result)
 }
 
 /// Data needed for broadcasting a tx and monitoring its progress on chain.
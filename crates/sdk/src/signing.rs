@@ -5,7 +5,7 @@
 use std::collections::BTreeMap;
 use std::fmt::Display;
 
-use borsh::BorshDeserialize;
+use borsh::{BorshDeserialize, BorshSerialize};
 use data_encoding::HEXLOWER;
 use itertools::Itertools;
 use masp_primitives::asset_type::AssetType;
@@ -16,6 +16,7 @@ use namada_account::{AccountPublicKeysMap, InitAccount, UpdateAccount};
 use namada_core::address::{Address, ImplicitAddress, InternalAddress, MASP};
 use namada_core::arith::checked;
 use namada_core::collections::{HashMap, HashSet};
+use namada_core::hash::Hash;
 use namada_core::key::*;
 use namada_core::masp::{
     AssetData, ExtendedViewingKey, MaspTxId, PaymentAddress,
@@ -60,10 +61,15 @@ use crate::tx::{
     TX_UPDATE_STEWARD_COMMISSION, TX_VOTE_PROPOSAL, TX_WITHDRAW_WASM,
     VP_USER_WASM,
 };
+use crate::wallet::audit::{KeyUsage, TxSummary};
 pub use crate::wallet::store::AddressVpType;
-use crate::wallet::{Wallet, WalletIo};
+use crate::wallet::{Wallet, WalletIo, WalletStorage};
 use crate::{args, rpc, Namada};
 
+pub mod bundle;
+#[cfg(not(target_family = "wasm"))]
+pub mod remote;
+
 /// A structure holding the signing data to craft a transaction
 #[derive(Clone)]
 pub struct SigningTxData {
@@ -202,7 +208,9 @@ pub async fn tx_signers(
 
 /// The different parts of a transaction that can be signed. Note that it's
 /// impossible to sign the fee header without signing the raw header.
-#[derive(Eq, Hash, PartialEq)]
+#[derive(
+    Clone, Copy, Debug, Eq, Hash, PartialEq, BorshSerialize, BorshDeserialize,
+)]
 pub enum Signable {
     /// Fee and raw header
     FeeRawHeader,
@@ -230,6 +238,11 @@ pub async fn default_sign(
 /// It also takes a second, optional keypair to sign the wrapper header
 /// separately.
 ///
+/// The local policies of the keys that are about to be used are checked first
+/// against the summary of the transaction in the given context (see
+/// [`tx_summary_context`]) and, unless this is a dry run, the produced
+/// signatures are recorded in the key usage log of the wallet.
+///
 /// If this is not a dry run, the tx is put in a wrapper and returned along with
 /// hashes needed for monitoring the tx on chain.
 ///
@@ -239,9 +252,92 @@ pub async fn sign_tx<'a, D, F, U>(
     args: &args::Tx,
     tx: &mut Tx,
     signing_data: SigningTxData,
+    summary_context: &TxSummaryContext,
     sign: impl Fn(Tx, common::PublicKey, Signable, D) -> F,
     user_data: D,
 ) -> Result<(), Error>
+where
+    D: Clone + MaybeSend,
+    U: WalletIo + WalletStorage,
+    F: std::future::Future<Output = Result<Tx, Error>>,
+{
+    // The keys whose signatures are supplied aren't used by the wallet
+    let supplied_pubkeys: HashSet<_> = args
+        .signatures
+        .iter()
+        .filter_map(|bytes| SignatureIndex::try_from_json_bytes(bytes).ok())
+        .map(|sigidx| sigidx.pubkey)
+        .collect();
+    let fee_payer = args
+        .wrapper_signature
+        .is_none()
+        .then_some(&signing_data.fee_payer);
+
+    // Check the local policies of the keys that are about to be used
+    let key_summaries = summarize_tx_by_key(
+        tx,
+        summary_context,
+        signing_data
+            .public_keys
+            .iter()
+            .filter(|pubkey| !supplied_pubkeys.contains(*pubkey)),
+        fee_payer,
+    );
+    {
+        let wallet = wallet.read().await;
+        for (pubkey, summary) in &key_summaries {
+            wallet
+                .check_key_policy(pubkey, summary)
+                .map_err(|err| Error::Other(err.to_string()))?;
+        }
+    }
+    let fee_payer = fee_payer.cloned();
+
+    let used_pubkeys =
+        sign_tx_with_used_keys(wallet, args, tx, signing_data, sign, user_data)
+            .await?;
+
+    // Record the produced signatures, except for dry runs whose spendings
+    // never reach the chain. The wrapper is always signed by the fee payer
+    // unless its signature is supplied.
+    if !(args.dry_run || args.dry_run_wrapper) {
+        let tx_hash = tx.header_hash();
+        #[allow(clippy::disallowed_methods)]
+        let timestamp = DateTimeUtc::now();
+        let usages = key_summaries
+            .into_iter()
+            .filter(|(pubkey, _)| {
+                (used_pubkeys.contains(pubkey)
+                    && !supplied_pubkeys.contains(pubkey))
+                    || fee_payer.as_ref() == Some(pubkey)
+            })
+            .map(|(signer, summary)| KeyUsage {
+                tx_hash,
+                signer,
+                actions: summary.actions,
+                amounts: summary.amounts,
+                timestamp,
+            })
+            .collect();
+        wallet
+            .read()
+            .await
+            .record_key_usage(usages)
+            .map_err(|err| Error::Other(err.to_string()))?;
+    }
+    Ok(())
+}
+
+/// Sign the given transaction and return the public keys whose signatures of
+/// the raw header it carries
+async fn sign_tx_with_used_keys<'a, D, F, U>(
+    wallet: &RwLock<Wallet<U>>,
+    args: &args::Tx,
+    tx: &mut Tx,
+    signing_data: SigningTxData,
+    sign: impl Fn(Tx, common::PublicKey, Signable, D) -> F,
+    user_data: D,
+) -> Result<HashSet<common::PublicKey>, Error>
 where
     D: Clone + MaybeSend,
     U: WalletIo,
@@ -361,8 +457,338 @@ where
             used_pubkeys_len,
         )))
     } else {
-        Ok(())
+        Ok(used_pubkeys)
+    }
+}
+
+/// The codes of the transactions whose effects on the balances of the signers
+/// are known, either because their data can be decoded into amounts and
+/// targets or because they don't spend any tokens
+pub const SUMMARIZED_TX_CODES: &[&str] = &[
+    TX_TRANSFER_WASM,
+    TX_IBC_WASM,
+    TX_BOND_WASM,
+    TX_UNBOND_WASM,
+    TX_WITHDRAW_WASM,
+    TX_REDELEGATE_WASM,
+    TX_CLAIM_REWARDS_WASM,
+    TX_VOTE_PROPOSAL,
+    TX_INIT_PROPOSAL,
+    TX_REVEAL_PK,
+    TX_INIT_ACCOUNT_WASM,
+    TX_UPDATE_ACCOUNT_WASM,
+    TX_BECOME_VALIDATOR_WASM,
+    TX_CHANGE_COMMISSION_WASM,
+    TX_CHANGE_CONSENSUS_KEY_WASM,
+    TX_CHANGE_METADATA_WASM,
+    TX_DEACTIVATE_VALIDATOR_WASM,
+    TX_REACTIVATE_VALIDATOR_WASM,
+    TX_UNJAIL_VALIDATOR_WASM,
+    TX_RESIGN_STEWARD,
+    TX_UPDATE_STEWARD_COMMISSION,
+];
+
+/// The chain data needed to summarize the effects of transactions on the
+/// balances of their signers, see [`summarize_tx`]
+#[derive(Clone, Debug, Default)]
+pub struct TxSummaryContext {
+    /// The paths of the codes of [`SUMMARIZED_TX_CODES`] by their hashes
+    pub known_codes: HashMap<Hash, &'static str>,
+    /// The native token, in which bonds and proposal deposits are paid
+    pub native_token: Option<Address>,
+    /// The amount deposited by the author of a governance proposal
+    pub proposal_deposit: Option<Amount>,
+}
+
+impl TxSummaryContext {
+    /// Identify the codes of the given transaction only by the tags that they
+    /// declare, which anyone can forge. This is only good enough for the key
+    /// usage log of keys without a policy.
+    pub fn from_declared_tags(tx: &Tx, native_token: Address) -> Self {
+        let known_codes = tx
+            .commitments()
+            .iter()
+            .filter_map(|cmt| {
+                let code = tx.get_section(cmt.code_sechash())?.code_sec()?;
+                let tag = code.tag.as_ref()?;
+                let code_path = SUMMARIZED_TX_CODES
+                    .iter()
+                    .find(|path| **path == tag.as_str())?;
+                Some((code.code.hash(), *code_path))
+            })
+            .collect();
+        Self {
+            known_codes,
+            native_token: Some(native_token),
+            proposal_deposit: None,
+        }
+    }
+}
+
+/// Get the context for summarizing the given transaction before signing it
+/// with [`sign_tx`]. If none of the keys that are about to sign it has a
+/// policy, its codes are only identified by the tags that they declare and
+/// nothing is queried. Otherwise, they are identified by the hashes of
+/// [`SUMMARIZED_TX_CODES`] whitelisted on chain, which are cached by the
+/// context and only queried again if the transaction has a code missing from
+/// the cache.
+pub async fn tx_summary_context(
+    context: &impl Namada,
+    tx: &Tx,
+    signing_data: &SigningTxData,
+) -> Result<TxSummaryContext, Error> {
+    let native_token = context.native_token();
+    let has_policy = {
+        let wallet = context.wallet().await;
+        signing_data
+            .public_keys
+            .iter()
+            .chain([&signing_data.fee_payer])
+            .any(|pubkey| {
+                wallet
+                    .store()
+                    .find_key_policy_by_pkh(&pubkey.into())
+                    .is_some()
+            })
+    };
+    if !has_policy {
+        return Ok(TxSummaryContext::from_declared_tags(tx, native_token));
+    }
+
+    let code_hashes: HashSet<Hash> = tx
+        .commitments()
+        .iter()
+        .filter_map(|cmt| {
+            let code = tx.get_section(cmt.code_sechash())?.code_sec()?;
+            Some(code.code.hash())
+        })
+        .collect();
+    let known_codes = match context.tx_codes_cache() {
+        Some(cache) => {
+            let mut cache = cache.write().await;
+            if code_hashes.iter().any(|hash| !cache.contains_key(hash)) {
+                *cache = query_summarized_tx_codes(context).await?;
+            }
+            cache.clone()
+        }
+        None => query_summarized_tx_codes(context).await?,
+    };
+    // The deposit is a governance parameter that may change at any time
+    let proposal_deposit = if code_hashes
+        .iter()
+        .any(|hash| known_codes.get(hash) == Some(&TX_INIT_PROPOSAL))
+    {
+        Some(
+            rpc::query_storage_value(
+                context.client(),
+                &namada_governance::storage::keys::get_min_proposal_fund_key(),
+            )
+            .await?,
+        )
+    } else {
+        None
+    };
+    Ok(TxSummaryContext {
+        known_codes,
+        native_token: Some(native_token),
+        proposal_deposit,
+    })
+}
+
+/// Query the hashes of the codes of [`SUMMARIZED_TX_CODES`] that are
+/// whitelisted on chain, for identifying inner transactions by their code
+pub async fn query_summarized_tx_codes(
+    context: &impl Namada,
+) -> Result<HashMap<Hash, &'static str>, Error> {
+    let mut known_codes = HashMap::new();
+    for code_path in SUMMARIZED_TX_CODES {
+        let hash_key = namada_core::storage::Key::wasm_hash(code_path);
+        if let (Some(hash), _) = rpc::query_storage_value_bytes(
+            context.client(),
+            &hash_key,
+            None,
+            false,
+        )
+        .await?
+        {
+            let hash = Hash::try_from(&hash[..]).map_err(|err| {
+                Error::Other(format!(
+                    "Invalid code hash of {code_path} on chain: {err}"
+                ))
+            })?;
+            known_codes.insert(hash, *code_path);
+        }
+    }
+    Ok(known_codes)
+}
+
+/// Summarize the effects of the inner transactions of the given transaction
+/// for the key usage log and the key policies. The inner transactions are
+/// identified by the hash of their code in the known codes of the given
+/// context; the tags that they declare are only displayed. Bonds and proposal
+/// deposits are spendings of the native token. The transactions whose code is
+/// unknown, whose data can't be decoded or whose spendings are missing from
+/// the context are counted as unsummarized.
+pub fn summarize_tx(tx: &Tx, context: &TxSummaryContext) -> TxSummary {
+    fn decode<T: BorshDeserialize>(data: &[u8]) -> Option<T> {
+        T::try_from_slice(data).ok()
+    }
+
+    let mut summary = TxSummary::default();
+    for cmt in tx.commitments() {
+        let code = tx
+            .get_section(cmt.code_sechash())
+            .and_then(|section| section.code_sec());
+        let code_path = code
+            .as_ref()
+            .and_then(|code| context.known_codes.get(&code.code.hash()))
+            .copied();
+        let data = tx.data(cmt).unwrap_or_default();
+        let action = match code_path {
+            Some(TX_TRANSFER_WASM) => {
+                decode::<token::Transfer>(&data).map(|transfer| {
+                    for (account, amount) in transfer.sources {
+                        summary.add_amount(account.token, amount);
+                    }
+                    summary.targets.extend(
+                        transfer.targets.into_keys().map(|acc| acc.owner),
+                    );
+                    "Transfer".to_string()
+                })
+            }
+            Some(TX_IBC_WASM) => decode::<MsgTransfer<token::Transfer>>(&data)
+                .and_then(|transfer| {
+                    let coin = &transfer.message.packet_data.token;
+                    let token = namada_ibc::trace::convert_to_address(
+                        coin.denom.to_string(),
+                    )
+                    .ok()?;
+                    let amount = Amount::try_from(coin.amount).ok()?;
+                    summary.add_amount(
+                        token,
+                        DenominatedAmount::new(amount, 0.into()),
+                    );
+                    // Foreign receivers are accounted as the IBC address
+                    summary
+                        .targets
+                        .insert(Address::Internal(InternalAddress::Ibc));
+                    Some(format!(
+                        "IBC transfer of {} to {}",
+                        coin, transfer.message.packet_data.receiver
+                    ))
+                }),
+            Some(TX_BOND_WASM) => decode::<pos::Bond>(&data).and_then(|bond| {
+                let native_token = context.native_token.clone()?;
+                summary.add_amount(
+                    native_token,
+                    DenominatedAmount::native(bond.amount),
+                );
+                summary.targets.insert(bond.validator.clone());
+                Some(format!(
+                    "Bond {} to {}",
+                    bond.amount.to_string_native(),
+                    bond.validator
+                ))
+            }),
+            Some(TX_UNBOND_WASM) => {
+                decode::<pos::Unbond>(&data).map(|unbond| {
+                    format!(
+                        "Unbond {} from {}",
+                        unbond.amount.to_string_native(),
+                        unbond.validator
+                    )
+                })
+            }
+            Some(TX_WITHDRAW_WASM) => {
+                decode::<pos::Withdraw>(&data).map(|withdraw| {
+                    format!("Withdraw from {}", withdraw.validator)
+                })
+            }
+            Some(TX_REDELEGATE_WASM) => {
+                decode::<pos::Redelegation>(&data).map(|redelegation| {
+                    summary.targets.insert(redelegation.dest_validator.clone());
+                    format!(
+                        "Redelegate {} from {} to {}",
+                        redelegation.amount.to_string_native(),
+                        redelegation.src_validator,
+                        redelegation.dest_validator
+                    )
+                })
+            }
+            Some(TX_CLAIM_REWARDS_WASM) => decode::<pos::ClaimRewards>(&data)
+                .map(|claim| format!("Claim rewards from {}", claim.validator)),
+            Some(TX_VOTE_PROPOSAL) => {
+                decode::<VoteProposalData>(&data).map(|vote| {
+                    format!("Vote {} on proposal {}", vote.vote, vote.id)
+                })
+            }
+            Some(TX_INIT_PROPOSAL) => decode::<InitProposalData>(&data)
+                .and_then(|proposal| {
+                    let native_token = context.native_token.clone()?;
+                    let deposit = context.proposal_deposit?;
+                    summary.add_amount(
+                        native_token,
+                        DenominatedAmount::native(deposit),
+                    );
+                    summary
+                        .targets
+                        .insert(Address::Internal(InternalAddress::Governance));
+                    Some(format!(
+                        "Init proposal by {} with a deposit of {}",
+                        proposal.author,
+                        deposit.to_string_native()
+                    ))
+                }),
+            // The remaining known codes don't spend any tokens
+            Some(code_path) => {
+                Some(code_path.trim_end_matches(".wasm").to_string())
+            }
+            None => None,
+        };
+        let action = match action {
+            Some(action) => action,
+            None => {
+                summary.unsummarized = summary.unsummarized.saturating_add(1);
+                match (code_path, code.and_then(|code| code.tag)) {
+                    (Some(code_path), _) => format!("Undecodable {code_path}"),
+                    (None, Some(tag)) => {
+                        format!("Unrecognized code tagged {tag}")
+                    }
+                    (None, None) => "Custom transaction".to_string(),
+                }
+            }
+        };
+        summary.actions.push(action);
     }
+    summary
+}
+
+/// Split the summary of the given transaction between the keys that sign it:
+/// the signers of the raw header are accountable for the inner transactions,
+/// the fee payer for the fees.
+fn summarize_tx_by_key<'a>(
+    tx: &Tx,
+    context: &TxSummaryContext,
+    signers: impl IntoIterator<Item = &'a common::PublicKey>,
+    fee_payer: Option<&common::PublicKey>,
+) -> HashMap<common::PublicKey, TxSummary> {
+    let summary = summarize_tx(tx, context);
+    let mut summaries: HashMap<_, _> = signers
+        .into_iter()
+        .map(|signer| (signer.clone(), summary.clone()))
+        .collect();
+    if let (Some(fee_payer), Some(wrapper)) = (fee_payer, tx.header.wrapper()) {
+        let fee_summary = summaries.entry(fee_payer.clone()).or_default();
+        match wrapper.get_tx_fee() {
+            Ok(fee) => fee_summary.add_amount(wrapper.fee.token, fee),
+            Err(_) => {
+                fee_summary.unsummarized =
+                    fee_summary.unsummarized.saturating_add(1)
+            }
+        }
+        fee_summary.actions.push("Fee payment".to_string());
+    }
+    summaries
 }
 
 /// Return the necessary data regarding an account to be able to generate a
@@ -2140,6 +2566,7 @@ mod test_signing {
         args::Tx {
             dry_run: false,
             dry_run_wrapper: false,
+            dry_run_trace: false,
             dump_tx: false,
             dump_wrapper_tx: false,
             output_folder: None,
@@ -2165,6 +2592,9 @@ mod test_signing {
             memo: None,
             use_device: false,
             device_transport: Default::default(),
+            masp_prover: None,
+            shielded_key_share: None,
+            remote_signer: None,
         }
     }
 
@@ -2487,6 +2917,7 @@ mod test_signing {
             &args,
             &mut tx,
             signing_data,
+            &TxSummaryContext::default(),
             |tx, pk, _, _| {
                 let pkf = public_key_fee.clone();
                 async move {
@@ -2522,6 +2953,7 @@ mod test_signing {
             &args,
             &mut tx,
             signing_data,
+            &TxSummaryContext::default(),
             |tx, _, _, _| async { Ok(tx) },
             (),
         )
@@ -2529,6 +2961,125 @@ mod test_signing {
         .expect("Test failed");
     }
 
+    #[test]
+    fn test_summarize_tx_by_code_hash() {
+        let transfer_hash = Hash::sha256(b"tx_transfer");
+        let context = TxSummaryContext {
+            known_codes: HashMap::from([(transfer_hash, TX_TRANSFER_WASM)]),
+            ..Default::default()
+        };
+        let transfer = token::Transfer {
+            sources: BTreeMap::from([(
+                Account {
+                    owner: Address::Internal(InternalAddress::Governance),
+                    token: Address::Internal(InternalAddress::Governance),
+                },
+                DenominatedAmount::new(Amount::from_u64(1), 0.into()),
+            )]),
+            targets: BTreeMap::from([(
+                Account {
+                    owner: Address::Internal(InternalAddress::Pgf),
+                    token: Address::Internal(InternalAddress::Governance),
+                },
+                DenominatedAmount::new(Amount::from_u64(1), 0.into()),
+            )]),
+            shielded_section_hash: None,
+        };
+
+        // A transfer identified by its code hash is fully summarized
+        let mut tx = Tx::new(ChainId::default(), None);
+        tx.add_code_from_hash(transfer_hash, None)
+            .add_data(transfer.clone());
+        let summary = summarize_tx(&tx, &context);
+        assert_eq!(summary.unsummarized, 0);
+        assert_eq!(
+            summary.targets,
+            BTreeSet::from([Address::Internal(InternalAddress::Pgf)])
+        );
+        assert_eq!(summary.amounts.len(), 1);
+
+        // Other code that claims to be a transfer is not summarized
+        let mut tx = Tx::new(ChainId::default(), None);
+        tx.add_code_from_hash(
+            Hash::sha256(b"custom"),
+            Some(TX_TRANSFER_WASM.to_string()),
+        )
+        .add_data(transfer);
+        let summary = summarize_tx(&tx, &context);
+        assert_eq!(summary.unsummarized, 1);
+        assert!(summary.targets.is_empty());
+        assert!(summary.amounts.is_empty());
+
+        // Undecodable data of known code is not summarized either
+        let mut tx = Tx::new(ChainId::default(), None);
+        tx.add_code_from_hash(transfer_hash, None)
+            .add_serialized_data(vec![0xff]);
+        assert_eq!(summarize_tx(&tx, &context).unsummarized, 1);
+    }
+
+    #[test]
+    fn test_summarize_tx_deposits() {
+        let native_token = Address::Internal(InternalAddress::Governance);
+        let validator = Address::Internal(InternalAddress::PoS);
+        let bond_hash = Hash::sha256(b"tx_bond");
+        let proposal_hash = Hash::sha256(b"tx_init_proposal");
+        let mut context = TxSummaryContext {
+            known_codes: HashMap::from([
+                (bond_hash, TX_BOND_WASM),
+                (proposal_hash, TX_INIT_PROPOSAL),
+            ]),
+            native_token: Some(native_token.clone()),
+            proposal_deposit: Some(Amount::native_whole(500)),
+        };
+
+        // Bonds are spendings of the native token
+        let mut tx = Tx::new(ChainId::default(), None);
+        tx.add_code_from_hash(bond_hash, None).add_data(pos::Bond {
+            validator: validator.clone(),
+            amount: Amount::native_whole(10),
+            source: None,
+        });
+        let summary = summarize_tx(&tx, &context);
+        assert_eq!(summary.unsummarized, 0);
+        assert_eq!(
+            summary.amounts,
+            BTreeMap::from([(
+                native_token.clone(),
+                DenominatedAmount::native(Amount::native_whole(10))
+            )])
+        );
+        assert_eq!(summary.targets, BTreeSet::from([validator.clone()]));
+
+        // So are the deposits of governance proposals
+        let mut tx = Tx::new(ChainId::default(), None);
+        tx.add_code_from_hash(proposal_hash, None)
+            .add_data(InitProposalData {
+                content: Hash::zero(),
+                author: validator,
+                r#type: ProposalType::Default,
+                voting_start_epoch: Default::default(),
+                voting_end_epoch: Default::default(),
+                activation_epoch: Default::default(),
+            });
+        let summary = summarize_tx(&tx, &context);
+        assert_eq!(summary.unsummarized, 0);
+        assert_eq!(
+            summary.amounts,
+            BTreeMap::from([(
+                native_token,
+                DenominatedAmount::native(Amount::native_whole(500))
+            )])
+        );
+        assert_eq!(
+            summary.targets,
+            BTreeSet::from([Address::Internal(InternalAddress::Governance)])
+        );
+
+        // The deposit of a proposal can't be summarized without its amount
+        context.proposal_deposit = None;
+        assert_eq!(summarize_tx(&tx, &context).unsummarized, 1);
+    }
+
     #[tokio::test]
     async fn test_make_transfer_endpoints() {
         let tf = token::Transfer {
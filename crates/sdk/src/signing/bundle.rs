@@ -0,0 +1,380 @@
+//! Partially signed transactions.
+//!
+//! A [`PartiallySignedTx`] bundles a transaction with the public keys and the
+//! threshold of the account on whose behalf it is signed, and with the
+//! signatures of the account's members collected so far. Every signature is
+//! verified as it is added, so that a bundle can be passed around the members
+//! of a multisig until the threshold is met, and then finalized into a
+//! transaction that is ready to be submitted.
+
+use std::collections::BTreeMap;
+use std::io;
+
+use namada_account::AccountPublicKeysMap;
+use namada_core::address::Address;
+use namada_core::key::{common, SigScheme};
+use namada_tx::{Authorization, Section, SignatureIndex, Signer, Tx};
+use serde::{Deserialize, Serialize};
+
+use crate::error::Error;
+use crate::signing::SigningTxData;
+
+/// A transaction with the signatures collected so far for its owner
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct PartiallySignedTx {
+    /// The transaction being signed
+    pub tx: Tx,
+    /// The account on whose behalf the transaction is signed, if any
+    pub owner: Option<Address>,
+    /// The public keys of the account, ordered by their index
+    pub public_keys: Vec<common::PublicKey>,
+    /// The number of signatures required by the account
+    pub threshold: u8,
+    /// The verified signatures over the raw header of the transaction
+    pub signatures: Vec<SignatureIndex>,
+    /// The verified signature of the fee payer over the wrapper
+    pub wrapper_signature: Option<Authorization>,
+}
+
+impl PartiallySignedTx {
+    /// Create a bundle without any signatures for the given account
+    pub fn new(
+        tx: Tx,
+        owner: Option<Address>,
+        public_keys_map: &AccountPublicKeysMap,
+        threshold: u8,
+    ) -> Self {
+        let public_keys = (0..=u8::MAX)
+            .map_while(|idx| public_keys_map.get_public_key_from_index(idx))
+            .collect();
+        Self {
+            tx,
+            owner,
+            public_keys,
+            threshold,
+            signatures: vec![],
+            wrapper_signature: None,
+        }
+    }
+
+    /// Create a bundle without any signatures from the signing data of the
+    /// transaction
+    pub fn from_signing_data(tx: Tx, signing_data: &SigningTxData) -> Self {
+        let public_keys_map = signing_data
+            .account_public_keys_map
+            .clone()
+            .unwrap_or_else(|| {
+                AccountPublicKeysMap::from_iter(
+                    signing_data.public_keys.clone(),
+                )
+            });
+        Self::new(
+            tx,
+            signing_data.owner.clone(),
+            &public_keys_map,
+            signing_data.threshold,
+        )
+    }
+
+    /// Get the map from the public keys of the account to their index
+    pub fn account_public_keys_map(&self) -> AccountPublicKeysMap {
+        AccountPublicKeysMap::from_iter(self.public_keys.clone())
+    }
+
+    /// Get the public keys of the account that have already signed
+    pub fn signers(&self) -> Vec<common::PublicKey> {
+        self.signatures
+            .iter()
+            .map(|sig| sig.pubkey.clone())
+            .collect()
+    }
+
+    /// Get the public keys of the account that have not signed yet
+    pub fn missing_signers(&self) -> Vec<common::PublicKey> {
+        self.public_keys
+            .iter()
+            .filter(|pk| !self.signatures.iter().any(|sig| sig.pubkey == **pk))
+            .cloned()
+            .collect()
+    }
+
+    /// Check whether enough signatures have been collected
+    pub fn threshold_met(&self) -> bool {
+        self.signatures.len() >= usize::from(self.threshold)
+    }
+
+    /// Verify the given signature and add it to the bundle, replacing any
+    /// previous signature by the same key. Returns whether the key had not
+    /// signed yet.
+    pub fn add_signature(
+        &mut self,
+        signature: SignatureIndex,
+    ) -> Result<bool, Error> {
+        if self.wrapper_signature.is_some() {
+            return Err(Error::Other(
+                "The wrapper signature covers the collected signatures, which \
+                 can no longer be changed"
+                    .to_string(),
+            ));
+        }
+        let signature = self.check_signature(signature)?;
+        let previous = self
+            .signatures
+            .iter()
+            .position(|sig| sig.pubkey == signature.pubkey);
+        match previous {
+            Some(pos) => {
+                self.signatures[pos] = signature;
+                Ok(false)
+            }
+            None => {
+                self.signatures.push(signature);
+                self.signatures.sort();
+                Ok(true)
+            }
+        }
+    }
+
+    /// Verify the given signature of the fee payer over the wrapper, after
+    /// the threshold has been met, and add it to the bundle
+    pub fn add_wrapper_signature(
+        &mut self,
+        signature: Authorization,
+    ) -> Result<(), Error> {
+        let fee_payer = self
+            .tx
+            .header
+            .wrapper()
+            .ok_or_else(|| {
+                Error::Other("The transaction is not a wrapper".to_string())
+            })?
+            .pk;
+        if !self.threshold_met() {
+            return Err(Error::Other(
+                "The wrapper can only be signed once the threshold is met"
+                    .to_string(),
+            ));
+        }
+        let mut tx = self.signed_tx();
+        let hashes = tx.sechashes();
+        tx.add_section(Section::Authorization(signature.clone()));
+        tx.verify_signature(&fee_payer, &hashes).map_err(|err| {
+            Error::Other(format!(
+                "Invalid wrapper signature by {fee_payer}: {err}"
+            ))
+        })?;
+        self.wrapper_signature = Some(signature);
+        Ok(())
+    }
+
+    /// Verify all the signatures of the bundle and that they meet the
+    /// threshold
+    pub fn verify(&self) -> Result<(), Error> {
+        for signature in &self.signatures {
+            self.check_signature(signature.clone())?;
+        }
+        if !self.threshold_met() {
+            return Err(Error::Other(format!(
+                "Only {} of the {} required signatures have been collected",
+                self.signatures.len(),
+                self.threshold
+            )));
+        }
+        Ok(())
+    }
+
+    /// Get the transaction with the collected signatures attached, but not
+    /// the wrapper signature
+    pub fn signed_tx(&self) -> Tx {
+        let mut tx = self.tx.clone();
+        tx.add_signatures(self.signatures.clone());
+        tx
+    }
+
+    /// Verify the bundle and get the transaction with all its signatures
+    /// attached. Wrappers must have been signed by their fee payer.
+    pub fn finalize(&self) -> Result<Tx, Error> {
+        self.verify()?;
+        let mut tx = self.signed_tx();
+        if tx.header.wrapper().is_some() {
+            let signature =
+                self.wrapper_signature.clone().ok_or_else(|| {
+                    Error::Other(
+                        "The wrapper has not been signed by the fee payer"
+                            .to_string(),
+                    )
+                })?;
+            tx.add_section(Section::Authorization(signature));
+        }
+        Ok(tx)
+    }
+
+    /// Serialize the bundle to pretty JSON into an I/O stream
+    pub fn to_writer_json<W>(&self, writer: W) -> serde_json::Result<()>
+    where
+        W: io::Write,
+    {
+        serde_json::to_writer_pretty(writer, self)
+    }
+
+    /// Try to parse a bundle from JSON string bytes
+    pub fn try_from_json_bytes(bytes: &[u8]) -> serde_json::Result<Self> {
+        serde_json::from_slice(bytes)
+    }
+
+    /// Check that the given signature is by a key of the account and over
+    /// the raw header of the transaction, and assign it the index of its key
+    fn check_signature(
+        &self,
+        mut signature: SignatureIndex,
+    ) -> Result<SignatureIndex, Error> {
+        let idx = self
+            .account_public_keys_map()
+            .get_index_from_public_key(&signature.pubkey)
+            .ok_or_else(|| {
+                Error::Other(format!(
+                    "{} is not a public key of the account",
+                    signature.pubkey
+                ))
+            })?;
+        // The signer and the signatures are left out of the signed hash
+        let raw_hash = Authorization {
+            targets: vec![self.tx.raw_header_hash()],
+            signer: Signer::PubKeys(vec![]),
+            signatures: BTreeMap::new(),
+        }
+        .get_raw_hash();
+        common::SigScheme::verify_signature(
+            &signature.pubkey,
+            &raw_hash,
+            &signature.signature,
+        )
+        .map_err(|err| {
+            Error::Other(format!(
+                "Invalid signature by {}: {err}",
+                signature.pubkey
+            ))
+        })?;
+        signature.index = self.owner.clone().map(|owner| (owner, idx));
+        Ok(signature)
+    }
+}
+
+#[cfg(test)]
+mod test_partially_signed_tx {
+    use namada_core::address::testing::established_address_1;
+    use namada_core::key::testing::{keypair_1, keypair_2, keypair_3};
+    use namada_core::key::RefTo;
+    use namada_core::token::{Amount, DenominatedAmount};
+    use namada_tx::data::{Fee, TxType, WrapperTx};
+
+    use super::*;
+
+    fn wrapper_tx(fee_payer: common::PublicKey) -> Tx {
+        let mut tx = Tx::from_type(TxType::Wrapper(Box::new(WrapperTx::new(
+            Fee {
+                amount_per_gas_unit: DenominatedAmount::native(Amount::zero()),
+                token: established_address_1(),
+            },
+            fee_payer,
+            100_000.into(),
+        ))));
+        tx.add_code(vec![], None);
+        tx.add_serialized_data(vec![1, 2, 3]);
+        tx
+    }
+
+    fn sign(
+        bundle: &PartiallySignedTx,
+        keypair: &common::SecretKey,
+    ) -> SignatureIndex {
+        bundle
+            .tx
+            .compute_section_signature(
+                &[keypair.clone()],
+                &AccountPublicKeysMap::from_iter([keypair.ref_to()]),
+                None,
+            )
+            .remove(0)
+    }
+
+    #[test]
+    fn test_collect_signatures() {
+        let owner = established_address_1();
+        let keys = [keypair_1(), keypair_2(), keypair_3()];
+        let public_keys_map = AccountPublicKeysMap::from_iter(
+            keys.iter().map(|key| key.ref_to()),
+        );
+        let mut bundle = PartiallySignedTx::new(
+            wrapper_tx(keypair_1().ref_to()),
+            Some(owner.clone()),
+            &public_keys_map,
+            2,
+        );
+        assert_eq!(bundle.public_keys.len(), 3);
+        assert!(bundle.verify().is_err());
+
+        assert!(bundle.add_signature(sign(&bundle, &keys[2])).unwrap());
+        assert!(!bundle.add_signature(sign(&bundle, &keys[2])).unwrap());
+        assert!(!bundle.threshold_met());
+        assert!(bundle.finalize().is_err());
+        assert_eq!(bundle.signatures[0].index, Some((owner.clone(), 2)));
+
+        // Signatures by keys outside of the account or over another
+        // transaction are rejected
+        let other = keypair_1();
+        let outsider = PartiallySignedTx::new(
+            wrapper_tx(keypair_2().ref_to()),
+            None,
+            &AccountPublicKeysMap::from_iter([other.ref_to()]),
+            1,
+        );
+        assert!(bundle.add_signature(sign(&outsider, &other)).is_err());
+        let mut foreign = sign(&bundle, &keys[1]);
+        foreign.pubkey = keys[0].ref_to();
+        assert!(bundle.add_signature(foreign).is_err());
+
+        assert!(bundle.add_signature(sign(&bundle, &keys[0])).unwrap());
+        assert!(bundle.threshold_met());
+        bundle.verify().unwrap();
+        assert_eq!(bundle.missing_signers(), vec![keys[1].ref_to()]);
+        // The fee payer must sign before the bundle can be finalized
+        assert!(bundle.finalize().is_err());
+
+        let mut signed = bundle.signed_tx();
+        signed.protocol_filter();
+        let wrapper_signature = Authorization::new(
+            signed.sechashes(),
+            [(0, keypair_2())].into_iter().collect(),
+            None,
+        );
+        assert!(bundle.add_wrapper_signature(wrapper_signature).is_err());
+        let wrapper_signature = Authorization::new(
+            signed.sechashes(),
+            [(0, keypair_1())].into_iter().collect(),
+            None,
+        );
+        bundle.add_wrapper_signature(wrapper_signature).unwrap();
+        assert!(bundle.add_signature(sign(&bundle, &keys[1])).is_err());
+
+        let tx = bundle.finalize().unwrap();
+        tx.verify_signatures(
+            &[tx.raw_header_hash()],
+            public_keys_map,
+            &Some(owner),
+            2,
+            || Ok(()),
+        )
+        .unwrap();
+        tx.verify_signature(&keypair_1().ref_to(), &tx.sechashes())
+            .unwrap();
+
+        // The bundle survives a JSON round-trip
+        let mut json = vec![];
+        bundle.to_writer_json(&mut json).unwrap();
+        assert_eq!(
+            PartiallySignedTx::try_from_json_bytes(&json).unwrap(),
+            bundle
+        );
+    }
+}
@@ -0,0 +1,441 @@
+//! Remote signer protocol.
+//!
+//! Lets keys be held by a separate signing daemon instead of the client's
+//! wallet or hardware wallet, in the spirit of CometBFT's privval. The client
+//! connects to the signer either over TCP (`tcp://host:port`, or just
+//! `host:port`) or over a Unix domain socket (`unix:///path/to/socket`) and
+//! sends a single [`SignerRequest`] per connection, to which the signer
+//! replies with a single [`SignerResponse`].
+//!
+//! Every message is framed as a 4 bytes big-endian length prefix followed by
+//! the borsh encoding of the message, and may not exceed
+//! [`MAX_MESSAGE_LEN`] bytes.
+//!
+//! Signature requests carry the whole transaction along with a human-readable
+//! summary of it, such as the one displayed by the Ledger app, so that the
+//! signer can enforce its own policy before signing. The signer is expected
+//! to recompute the signed section hashes itself from the transaction, which
+//! is what [`sign_request`] does, rather than trusting the summary.
+//!
+//! The protocol is neither authenticated nor encrypted. Signers must thus
+//! only accept connections from trusted local clients, i.e. listen on
+//! loopback addresses or on Unix domain sockets restricted to their user,
+//! and remote clients should reach them through an authenticated tunnel such
+//! as an SSH port forwarding.
+
+use std::collections::BTreeMap;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use namada_core::key::{common, SigScheme};
+use namada_tx::{Authorization, Section, Signer, Tx};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::borsh::BorshSerializeExt;
+use crate::error::Error;
+use crate::signing::Signable;
+
+/// The maximum length of a protocol message, in bytes
+pub const MAX_MESSAGE_LEN: u32 = 64 * 1024 * 1024;
+
+/// A request sent to a remote signer
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
+pub enum SignerRequest {
+    /// Request the public keys that the signer can sign with
+    PublicKeys,
+    /// Request the signature of a transaction
+    Sign(SignRequest),
+}
+
+/// A request for the signature of a transaction with a given key
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
+pub struct SignRequest {
+    /// The transaction to sign
+    pub tx: Tx,
+    /// The public key of the key to sign with
+    pub public_key: common::PublicKey,
+    /// The parts of the transaction to sign
+    pub parts: Signable,
+    /// A human-readable summary of the transaction
+    pub summary: Vec<String>,
+}
+
+/// A response of a remote signer
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
+pub enum SignerResponse {
+    /// The public keys that the signer can sign with
+    PublicKeys(Vec<common::PublicKey>),
+    /// The authorizations produced for a signature request, the raw header
+    /// one first followed by the wrapper one if requested
+    Signatures(Vec<Authorization>),
+    /// The request was rejected by the signer
+    Rejected(String),
+}
+
+/// The address of a remote signer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RemoteSignerAddr {
+    /// A TCP address
+    Tcp(String),
+    /// The path of a Unix domain socket
+    #[cfg(unix)]
+    Unix(std::path::PathBuf),
+}
+
+impl std::str::FromStr for RemoteSignerAddr {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(path) = s.strip_prefix("unix://") {
+            #[cfg(unix)]
+            return Ok(Self::Unix(path.into()));
+            #[cfg(not(unix))]
+            return Err(Error::Other(format!(
+                "Unix domain sockets are not supported on this platform: \
+                 {path}"
+            )));
+        }
+        let addr = s.strip_prefix("tcp://").unwrap_or(s);
+        if addr.is_empty() || addr.contains("://") {
+            return Err(Error::Other(format!(
+                "Invalid remote signer address: {s}"
+            )));
+        }
+        Ok(Self::Tcp(addr.to_string()))
+    }
+}
+
+/// A client of a remote signer
+#[derive(Debug, Clone)]
+pub struct RemoteSigner {
+    addr: RemoteSignerAddr,
+}
+
+impl RemoteSigner {
+    /// Create a client of the remote signer at the given address
+    pub fn new(addr: &str) -> Result<Self, Error> {
+        Ok(Self {
+            addr: addr.parse()?,
+        })
+    }
+
+    /// Send a request to the remote signer and wait for its response
+    pub async fn request(
+        &self,
+        request: &SignerRequest,
+    ) -> Result<SignerResponse, Error> {
+        let io_error = |err: std::io::Error| {
+            Error::Other(format!(
+                "Failed to communicate with the remote signer at {:?}: {err}",
+                self.addr
+            ))
+        };
+        match &self.addr {
+            RemoteSignerAddr::Tcp(addr) => {
+                let mut stream = tokio::net::TcpStream::connect(addr)
+                    .await
+                    .map_err(io_error)?;
+                write_message(&mut stream, request)
+                    .await
+                    .map_err(io_error)?;
+                read_message(&mut stream).await.map_err(io_error)
+            }
+            #[cfg(unix)]
+            RemoteSignerAddr::Unix(path) => {
+                let mut stream = tokio::net::UnixStream::connect(path)
+                    .await
+                    .map_err(io_error)?;
+                write_message(&mut stream, request)
+                    .await
+                    .map_err(io_error)?;
+                read_message(&mut stream).await.map_err(io_error)
+            }
+        }
+    }
+
+    /// Get the public keys that the remote signer can sign with
+    pub async fn public_keys(&self) -> Result<Vec<common::PublicKey>, Error> {
+        match self.request(&SignerRequest::PublicKeys).await? {
+            SignerResponse::PublicKeys(keys) => Ok(keys),
+            SignerResponse::Rejected(reason) => Err(Error::Other(format!(
+                "The remote signer rejected the request: {reason}"
+            ))),
+            SignerResponse::Signatures(_) => Err(unexpected_response()),
+        }
+    }
+
+    /// Get the given parts of the transaction signed with the given key, and
+    /// attach the signatures to it after checking them
+    pub async fn sign(
+        &self,
+        mut tx: Tx,
+        public_key: common::PublicKey,
+        parts: Signable,
+        summary: Vec<String>,
+    ) -> Result<Tx, Error> {
+        tx.protocol_filter();
+        let request = SignerRequest::Sign(SignRequest {
+            tx: tx.clone(),
+            public_key: public_key.clone(),
+            parts,
+            summary,
+        });
+        let authorizations = match self.request(&request).await? {
+            SignerResponse::Signatures(authorizations) => authorizations,
+            SignerResponse::Rejected(reason) => {
+                return Err(Error::Other(format!(
+                    "The remote signer refused to sign with {public_key}: \
+                     {reason}"
+                )));
+            }
+            SignerResponse::PublicKeys(_) => {
+                return Err(unexpected_response());
+            }
+        };
+        let expected = match parts {
+            Signable::RawHeader => 1,
+            Signable::FeeRawHeader => 2,
+        };
+        if authorizations.len() != expected {
+            return Err(Error::Other(format!(
+                "Expected {expected} signatures from the remote signer, got {}",
+                authorizations.len()
+            )));
+        }
+        for (idx, authorization) in authorizations.into_iter().enumerate() {
+            // The raw header must be signed first, as the wrapper signature
+            // also covers the former
+            let targets = if idx == 0 {
+                vec![tx.raw_header_hash()]
+            } else {
+                tx.sechashes()
+            };
+            check_authorization(&authorization, &targets, &public_key)?;
+            tx.add_section(Section::Authorization(authorization));
+        }
+        Ok(tx)
+    }
+}
+
+/// Produce the authorizations answering a signature request with the given
+/// secret key. This is meant to be used by remote signer implementations
+/// once they have validated the request against their policy.
+pub fn sign_request(
+    request: &SignRequest,
+    secret_key: &common::SecretKey,
+) -> Vec<Authorization> {
+    let mut tx = request.tx.clone();
+    tx.protocol_filter();
+    let secret_keys: BTreeMap<_, _> = [(0, secret_key.clone())].into();
+    let raw = Authorization::new(
+        vec![tx.raw_header_hash()],
+        secret_keys.clone(),
+        None,
+    );
+    let mut authorizations = vec![raw.clone()];
+    if request.parts == Signable::FeeRawHeader {
+        tx.add_section(Section::Authorization(raw));
+        authorizations.push(Authorization::new(
+            tx.sechashes(),
+            secret_keys,
+            None,
+        ));
+    }
+    authorizations
+}
+
+/// Read a length prefixed borsh encoded message from the given stream
+pub async fn read_message<T: BorshDeserialize>(
+    stream: &mut (impl AsyncRead + Unpin),
+) -> std::io::Result<T> {
+    let len = stream.read_u32().await?;
+    if len > MAX_MESSAGE_LEN {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("Message of {len} bytes exceeds the maximum length"),
+        ));
+    }
+    let mut buf = vec![0; len as usize];
+    stream.read_exact(&mut buf).await?;
+    T::try_from_slice(&buf)
+}
+
+/// Write a length prefixed borsh encoded message to the given stream
+pub async fn write_message<T: BorshSerialize>(
+    stream: &mut (impl AsyncWrite + Unpin),
+    message: &T,
+) -> std::io::Result<()> {
+    let buf = message.serialize_to_vec();
+    let len = u32::try_from(buf.len())
+        .ok()
+        .filter(|len| *len <= MAX_MESSAGE_LEN)
+        .ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "Message exceeds the maximum length",
+            )
+        })?;
+    stream.write_u32(len).await?;
+    stream.write_all(&buf).await?;
+    stream.flush().await
+}
+
+/// Check that the given authorization is a valid signature over the given
+/// targets with the given key
+fn check_authorization(
+    authorization: &Authorization,
+    targets: &[namada_core::hash::Hash],
+    public_key: &common::PublicKey,
+) -> Result<(), Error> {
+    let invalid = |reason: &str| {
+        Error::Other(format!(
+            "Invalid signature from the remote signer: {reason}"
+        ))
+    };
+    if authorization.targets != targets {
+        return Err(invalid("unexpected signed sections"));
+    }
+    if authorization.signer != Signer::PubKeys(vec![public_key.clone()]) {
+        return Err(invalid("unexpected signer"));
+    }
+    let signature = match authorization.signatures.get(&0) {
+        Some(signature) if authorization.signatures.len() == 1 => signature,
+        _ => return Err(invalid("expected exactly one signature")),
+    };
+    common::SigScheme::verify_signature(
+        public_key,
+        &authorization.get_raw_hash(),
+        signature,
+    )
+    .map_err(|err| invalid(&err.to_string()))
+}
+
+fn unexpected_response() -> Error {
+    Error::Other("Unexpected response from the remote signer".to_string())
+}
+
+#[cfg(test)]
+mod test_remote_signer {
+    use namada_core::address::testing::established_address_1;
+    use namada_core::key::testing::keypair_1;
+    use namada_core::token::{Amount, DenominatedAmount};
+    use namada_tx::data::{Fee, TxType, WrapperTx};
+
+    use super::*;
+
+    fn wrapper_tx(fee_payer: common::PublicKey) -> Tx {
+        let mut tx = Tx::from_type(TxType::Wrapper(Box::new(WrapperTx::new(
+            Fee {
+                amount_per_gas_unit: DenominatedAmount::native(Amount::zero()),
+                token: established_address_1(),
+            },
+            fee_payer,
+            100_000.into(),
+        ))));
+        tx.add_code(vec![], None);
+        tx.add_serialized_data(vec![1, 2, 3]);
+        tx
+    }
+
+    #[test]
+    fn test_parse_remote_signer_addr() {
+        assert_eq!(
+            "127.0.0.1:26659".parse::<RemoteSignerAddr>().unwrap(),
+            RemoteSignerAddr::Tcp("127.0.0.1:26659".to_string())
+        );
+        assert_eq!(
+            "tcp://localhost:26659".parse::<RemoteSignerAddr>().unwrap(),
+            RemoteSignerAddr::Tcp("localhost:26659".to_string())
+        );
+        #[cfg(unix)]
+        assert_eq!(
+            "unix:///tmp/signer.sock"
+                .parse::<RemoteSignerAddr>()
+                .unwrap(),
+            RemoteSignerAddr::Unix("/tmp/signer.sock".into())
+        );
+        assert!("http://localhost:26659"
+            .parse::<RemoteSignerAddr>()
+            .is_err());
+        assert!("".parse::<RemoteSignerAddr>().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_remote_signing() {
+        let keypair = keypair_1();
+        let public_key = keypair.ref_to();
+        let listener =
+            tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server_key = keypair.clone();
+        let server = tokio::spawn(async move {
+            // Serve the public keys request, then the signing request
+            for _ in 0..2 {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let request: SignerRequest =
+                    read_message(&mut stream).await.unwrap();
+                let response = match request {
+                    SignerRequest::PublicKeys => {
+                        SignerResponse::PublicKeys(vec![server_key.ref_to()])
+                    }
+                    SignerRequest::Sign(request) => SignerResponse::Signatures(
+                        sign_request(&request, &server_key),
+                    ),
+                };
+                write_message(&mut stream, &response).await.unwrap();
+            }
+        });
+
+        let signer = RemoteSigner::new(&format!("tcp://{addr}")).unwrap();
+        assert_eq!(
+            signer.public_keys().await.unwrap(),
+            vec![public_key.clone()]
+        );
+        let tx = signer
+            .sign(
+                wrapper_tx(public_key.clone()),
+                public_key.clone(),
+                Signable::FeeRawHeader,
+                vec!["Type : Custom".to_string()],
+            )
+            .await
+            .unwrap();
+        server.await.unwrap();
+
+        tx.verify_signature(&public_key, &[tx.raw_header_hash()])
+            .unwrap();
+        tx.validate_tx().unwrap();
+    }
+
+    #[test]
+    fn test_reject_invalid_authorization() {
+        let keypair = keypair_1();
+        let other = namada_core::key::testing::keypair_2();
+        let tx = wrapper_tx(keypair.ref_to());
+        let request = SignRequest {
+            tx: tx.clone(),
+            public_key: keypair.ref_to(),
+            parts: Signable::RawHeader,
+            summary: vec![],
+        };
+        let authorization = sign_request(&request, &other).remove(0);
+        assert!(check_authorization(
+            &authorization,
+            &[tx.raw_header_hash()],
+            &keypair.ref_to()
+        )
+        .is_err());
+        let authorization = sign_request(&request, &keypair).remove(0);
+        check_authorization(
+            &authorization,
+            &[tx.raw_header_hash()],
+            &keypair.ref_to(),
+        )
+        .unwrap();
+        assert!(check_authorization(
+            &authorization,
+            &tx.sechashes(),
+            &keypair.ref_to()
+        )
+        .is_err());
+    }
+}
@@ -61,6 +61,7 @@ use namada_proof_of_stake::parameters::{
 };
 use namada_proof_of_stake::types::{CommissionPair, ValidatorState};
 use namada_token as token;
+use namada_token::masp::prover::MaspProver;
 use namada_token::masp::shielded_wallet::ShieldedApi;
 use namada_token::masp::{MaspFeeData, MaspTransferData, ShieldedTransfer};
 use namada_token::storage_key::balance_key;
@@ -81,6 +82,7 @@ use crate::args::{
 use crate::borsh::BorshSerializeExt;
 use crate::control_flow::time;
 use crate::error::{EncodingError, Error, QueryError, Result, TxSubmitError};
+use crate::masp::RemoteMaspProver;
 use crate::rpc::{
     self, get_validator_stake, query_wasm_code_hash, validate_amount,
     InnerTxResult, TxBroadcastData, TxResponse,
@@ -261,7 +263,12 @@ pub async fn process_tx(
     // println!("HTTP request body: {}", request_body);
 
     if args.dry_run || args.dry_run_wrapper {
-        expect_dry_broadcast(TxBroadcastData::DryRun(tx), context).await
+        expect_dry_broadcast(
+            TxBroadcastData::DryRun(tx),
+            context,
+            args.dry_run_trace,
+        )
+        .await
     } else {
         // We use this to determine when the wrapper tx makes it on-chain
         let tx_hash = tx.header_hash().to_string();
@@ -2713,6 +2720,7 @@ pub async fn build_ibc_transfer(
         masp_fee_data,
         args.tx.expiration.to_datetime(),
         bparams,
+        args.tx.masp_prover.as_deref(),
     )
     .await?;
     let shielded_tx_epoch = shielded_parts.as_ref().map(|trans| trans.0.epoch);
@@ -3180,6 +3188,7 @@ pub async fn build_shielded_transfer<N: Namada>(
         masp_fee_data,
         args.tx.expiration.to_datetime(),
         bparams,
+        args.tx.masp_prover.as_deref(),
     )
     .await?
     .expect("Shielded transfer must have shielded parts");
@@ -3349,6 +3358,7 @@ pub async fn build_shielding_transfer<N: Namada>(
         None,
         args.tx.expiration.to_datetime(),
         bparams,
+        args.tx.masp_prover.as_deref(),
     )
     .await?
     .expect("Shielding transfer must have shielded parts");
@@ -3473,6 +3483,7 @@ pub async fn build_unshielding_transfer<N: Namada>(
         masp_fee_data,
         args.tx.expiration.to_datetime(),
         bparams,
+        args.tx.masp_prover.as_deref(),
     )
     .await?
     .expect("Shielding transfer must have shielded parts");
@@ -3527,7 +3538,19 @@ async fn construct_shielded_parts<N: Namada>(
     fee_data: Option<MaspFeeData>,
     expiration: Option<DateTimeUtc>,
     bparams: &mut impl BuildParams,
+    masp_prover: Option<&str>,
 ) -> Result<Option<(ShieldedTransfer, HashSet<AssetData>)>> {
+    let remote_prover = masp_prover
+        .map(|endpoint| {
+            let url = endpoint.try_into().map_err(|err| {
+                Error::Other(format!(
+                    "Failed to parse MASP prover address {endpoint:?}: {err}"
+                ))
+            })?;
+            Ok::<_, Error>(RemoteMaspProver::new(reqwest::Client::new(), url))
+        })
+        .transpose()?;
+
     // Precompute asset types to increase chances of success in decoding
     let token_map = context.wallet().await.get_addresses();
     let tokens = token_map.values().collect();
@@ -3539,7 +3562,16 @@ async fn construct_shielded_parts<N: Namada>(
             .await;
 
         shielded
-            .gen_shielded_transfer(context, data, fee_data, expiration, bparams)
+            .gen_shielded_transfer(
+                context,
+                data,
+                fee_data,
+                expiration,
+                bparams,
+                remote_prover
+                    .as_ref()
+                    .map(|prover| prover as &dyn MaspProver),
+            )
             .await
     };
 
@@ -3928,6 +3960,7 @@ pub async fn gen_ibc_shielding_transfer<N: Namada>(
                 None,
                 args.expiration.to_datetime(),
                 &mut RngBuildParams::new(OsRng),
+                None,
             )
             .await
             .map_err(|err| TxSubmitError::MaspError(err.to_string()))?
@@ -3981,10 +4014,15 @@ async fn get_ibc_src_port_channel(
 async fn expect_dry_broadcast(
     to_broadcast: TxBroadcastData,
     context: &impl Namada,
+    trace: bool,
 ) -> Result<ProcessTxResponse> {
     match to_broadcast {
         TxBroadcastData::DryRun(tx) => {
-            let result = rpc::dry_run_tx(context, tx.to_bytes()).await?;
+            let result = if trace {
+                rpc::dry_run_tx_trace(context, tx.to_bytes()).await?.result
+            } else {
+                rpc::dry_run_tx(context, tx.to_bytes()).await?
+            };
             Ok(ProcessTxResponse::DryRun(result))
         }
         TxBroadcastData::Live { tx, tx_hash: _ } => {
@@ -5,12 +5,17 @@ use namada_core::address::{Address, MASP};
 use namada_core::arith::checked;
 #[cfg(any(feature = "multicore", test))]
 use namada_core::borsh::BorshSerializeExt;
+use namada_core::borsh::{BorshDeserialize, BorshSerialize};
 use namada_core::dec::Dec;
 #[cfg(any(feature = "multicore", test))]
 use namada_core::hash::Hash;
-use namada_core::token::{Amount, DenominatedAmount, Denomination};
+use namada_core::token::{Amount, Change, DenominatedAmount, Denomination};
 use namada_core::uint::Uint;
+use namada_macros::BorshDeserializer;
+#[cfg(feature = "migrations")]
+use namada_migrations::*;
 use namada_systems::{parameters, trans_token};
+use serde::{Deserialize, Serialize};
 
 #[cfg(any(feature = "multicore", test))]
 use crate::storage_key::{masp_assets_hash_key, masp_token_map_key};
@@ -19,7 +24,9 @@ use crate::storage_key::{
     masp_last_locked_amount_key, masp_locked_amount_target_key,
     masp_max_reward_rate_key,
 };
-use crate::{Result, StorageRead, StorageWrite, WithConversionState};
+use crate::{
+    Error, Key, Result, StorageRead, StorageWrite, WithConversionState,
+};
 
 /// Compute shielded token inflation amount
 #[allow(clippy::too_many_arguments)]
@@ -79,10 +86,16 @@ where
     // the threshold of holdings required in order to receive non-zero rewards.
     // This value should be fixed constant for each asset type. Here we choose
     // a thousandth of the given asset.
+    Ok((masp_rewards_precision(denomination)?, denomination))
+}
+
+/// Compute the precision of MASP rewards for a token of the given
+/// denomination, that is a thousandth of the token.
+pub fn masp_rewards_precision(denomination: Denomination) -> Result<u128> {
     let precision_denom = std::cmp::max(u32::from(denomination.0), 3)
         .checked_sub(3)
         .expect("Cannot underflow");
-    Ok((checked!(10u128 ^ precision_denom)?, denomination))
+    Ok(checked!(10u128 ^ precision_denom)?)
 }
 
 /// Compute the MASP rewards by applying the PD-controller to the genesis
@@ -155,37 +168,8 @@ where
         last_locked_dec,
     );
 
-    // inflation-per-token = inflation / locked tokens = n/PRECISION
-    // ∴ n = (inflation * PRECISION) / locked tokens
-    // Since we must put the notes in a compatible format with the
-    // note format, we must make the inflation amount discrete.
-    let noterized_inflation = if total_tokens_in_masp.is_zero() {
-        0u128
-    } else {
-        inflation
-            .checked_mul_div(
-                Uint::from(precision),
-                total_tokens_in_masp.raw_amount(),
-            )
-            .and_then(|x| x.0.try_into().ok())
-            .unwrap_or_else(|| {
-                tracing::warn!(
-                    "MASP inflation for {} assumed to be 0 because the \
-                     computed value is too large. Please check the inflation \
-                     parameters.",
-                    *token
-                );
-                0u128
-            })
-    };
-    let inflation_amount = Amount::from_uint(
-        checked!(
-            total_tokens_in_masp.raw_amount() / precision.into()
-                * Uint::from(noterized_inflation)
-        )?,
-        0,
-    )
-    .unwrap();
+    let (noterized_inflation, inflation_amount) =
+        noterize_inflation(token, inflation, total_tokens_in_masp, precision)?;
     let denom_amount = DenominatedAmount::new(inflation_amount, denomination);
     tracing::info!("MASP inflation for {token} is {denom_amount}");
 
@@ -227,6 +211,288 @@ where
     Ok(((noterized_inflation, precision), denomination))
 }
 
+/// Make the inflation of a token discrete with respect to the given precision,
+/// as required by the note format. Returns the inflation per `precision` units
+/// of the locked token and the total amount of inflation that it amounts to.
+fn noterize_inflation(
+    token: &Address,
+    inflation: Uint,
+    locked_amount: Amount,
+    precision: u128,
+) -> Result<(u128, Amount)> {
+    // inflation-per-token = inflation / locked tokens = n/PRECISION
+    // ∴ n = (inflation * PRECISION) / locked tokens
+    // Since we must put the notes in a compatible format with the
+    // note format, we must make the inflation amount discrete.
+    let noterized_inflation = if locked_amount.is_zero() {
+        0u128
+    } else {
+        inflation
+            .checked_mul_div(Uint::from(precision), locked_amount.raw_amount())
+            .and_then(|x| x.0.try_into().ok())
+            .unwrap_or_else(|| {
+                tracing::warn!(
+                    "MASP inflation for {} assumed to be 0 because the \
+                     computed value is too large. Please check the inflation \
+                     parameters.",
+                    *token
+                );
+                0u128
+            })
+    };
+    let inflation_amount = Amount::from_uint(
+        checked!(
+            locked_amount.raw_amount() / precision.into()
+                * Uint::from(noterized_inflation)
+        )?,
+        0,
+    )
+    .unwrap();
+    Ok((noterized_inflation, inflation_amount))
+}
+
+/// An assumption about the evolution of the amount of a token locked in the
+/// shielded pool over the projected MASP epochs
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LockedAmountGrowth {
+    /// The locked amount stays the same
+    #[default]
+    Constant,
+    /// The locked amount changes by the given (possibly negative) raw amount
+    /// every MASP epoch
+    Linear(Change),
+    /// The locked amount changes by the given (possibly negative) rate every
+    /// MASP epoch
+    Geometric(Dec),
+}
+
+impl LockedAmountGrowth {
+    /// Apply the growth of a single MASP epoch to the given locked amount.
+    /// The locked amount saturates at zero.
+    pub fn apply(&self, locked_amount: Amount) -> Result<Amount> {
+        Ok(match self {
+            Self::Constant => locked_amount,
+            Self::Linear(change) => {
+                let locked = checked!(locked_amount.change() + *change)?;
+                if locked.is_negative() {
+                    Amount::zero()
+                } else {
+                    Amount::from_change(locked)
+                }
+            }
+            Self::Geometric(rate) => {
+                let factor = checked!(Dec::one() + *rate)?;
+                if factor.is_negative() {
+                    Amount::zero()
+                } else {
+                    locked_amount.mul_floor(factor)?
+                }
+            }
+        })
+    }
+}
+
+/// The on-chain state and parameters from which the MASP rewards of a token
+/// are projected
+#[allow(missing_docs)]
+#[derive(
+    Clone,
+    Debug,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshDeserializer,
+    Serialize,
+    Deserialize,
+)]
+pub struct MaspRewardsProjectionParams {
+    pub token: Address,
+    pub denomination: Denomination,
+    pub precision: u128,
+    pub masp_epochs_per_year: u64,
+    pub total_native_amount: Amount,
+    pub locked_amount: Amount,
+    pub last_inflation: Amount,
+    pub last_locked_amount: Amount,
+    pub max_reward_rate: Dec,
+    pub kp_gain_nom: Dec,
+    pub kd_gain_nom: Dec,
+    pub locked_amount_target: Amount,
+}
+
+/// Read the parameters from which the MASP rewards of the given token are
+/// projected
+pub fn read_masp_rewards_projection_params<S, Params, TransToken>(
+    storage: &S,
+    token: &Address,
+) -> Result<MaspRewardsProjectionParams>
+where
+    S: StorageRead,
+    Params: parameters::Read<S>,
+    TransToken: trans_token::Keys + trans_token::Read<S>,
+{
+    let denomination =
+        TransToken::read_denom(storage, token)?.ok_or_else(|| {
+            Error::new_alloc(format!(
+                "Did not find the denomination of token {token}"
+            ))
+        })?;
+    let precision = masp_rewards_precision(denomination)?;
+    let masp_epoch_multiplier = Params::masp_epoch_multiplier(storage)?;
+    let epochs_per_year = Params::epochs_per_year(storage)?;
+    let masp_epochs_per_year =
+        checked!(epochs_per_year / masp_epoch_multiplier)?;
+    Ok(MaspRewardsProjectionParams {
+        token: token.clone(),
+        denomination,
+        precision,
+        masp_epochs_per_year,
+        total_native_amount: TransToken::get_effective_total_native_supply(
+            storage,
+        )?,
+        locked_amount: TransToken::read_balance(storage, token, &MASP)?,
+        last_inflation: read_masp_param(
+            storage,
+            &masp_last_inflation_key::<TransToken>(token),
+            token,
+            "last inflation",
+        )?,
+        last_locked_amount: read_masp_param(
+            storage,
+            &masp_last_locked_amount_key::<TransToken>(token),
+            token,
+            "last locked amount",
+        )?,
+        max_reward_rate: read_masp_param(
+            storage,
+            &masp_max_reward_rate_key::<TransToken>(token),
+            token,
+            "max reward rate",
+        )?,
+        kp_gain_nom: read_masp_param(
+            storage,
+            &masp_kp_gain_key::<TransToken>(token),
+            token,
+            "kp gain",
+        )?,
+        kd_gain_nom: read_masp_param(
+            storage,
+            &masp_kd_gain_key::<TransToken>(token),
+            token,
+            "kd gain",
+        )?,
+        locked_amount_target: read_masp_param(
+            storage,
+            &masp_locked_amount_target_key::<TransToken>(token),
+            token,
+            "locked amount target",
+        )?,
+    })
+}
+
+fn read_masp_param<S, T>(
+    storage: &S,
+    key: &Key,
+    token: &Address,
+    name: &str,
+) -> Result<T>
+where
+    S: StorageRead,
+    T: BorshDeserialize,
+{
+    storage.read(key)?.ok_or_else(|| {
+        Error::new_alloc(format!("Did not find the {name} of token {token}"))
+    })
+}
+
+/// The projected MASP rewards of a token at a future MASP epoch
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MaspRewardsProjection {
+    /// The number of MASP epochs from the current one
+    pub epoch_offset: u64,
+    /// The amount of the token assumed to be locked in the shielded pool
+    pub locked_amount: Amount,
+    /// The total amount of native tokens minted as rewards
+    pub inflation: Amount,
+    /// The reward in native tokens for every `precision` units of the token
+    pub reward: (u128, u128),
+    /// The projected shielded balance of the token
+    pub balance: Amount,
+    /// The native token rewards earned by the balance in this epoch
+    pub balance_reward: Amount,
+    /// The native token rewards earned by the balance since the current
+    /// epoch
+    pub cumulative_reward: Amount,
+}
+
+/// Project the MASP rewards of a token, and of the given shielded balance of
+/// it, over the given number of future MASP epochs. This replays the
+/// PD-controller of [`calculate_masp_rewards`] on the locked amounts obtained
+/// by applying the given growth assumption. Rewards earned on the native token
+/// are compounded into the balance, while rewards earned on other tokens are
+/// only accumulated. The total native supply is assumed to stay the same.
+pub fn project_masp_rewards(
+    params: &MaspRewardsProjectionParams,
+    growth: LockedAmountGrowth,
+    balance: Amount,
+    epochs: u64,
+    native_token: &Address,
+) -> Result<Vec<MaspRewardsProjection>> {
+    let target_locked_dec =
+        Dec::try_from(params.locked_amount_target.raw_amount())
+            .expect("Should not fail to convert Uint to Dec");
+    let mut locked_amount = params.locked_amount;
+    let mut last_inflation = params.last_inflation;
+    let mut last_locked_amount = params.last_locked_amount;
+    let mut balance = balance;
+    let mut cumulative_reward = Amount::zero();
+    let mut projections = Vec::new();
+
+    for epoch_offset in 1..=epochs {
+        let last_locked_dec = Dec::try_from(last_locked_amount.raw_amount())
+            .expect("Should not fail to convert Uint to Dec");
+        let inflation = compute_inflation(
+            locked_amount.raw_amount(),
+            params.total_native_amount.raw_amount(),
+            params.max_reward_rate,
+            last_inflation.raw_amount(),
+            params.kp_gain_nom,
+            params.kd_gain_nom,
+            params.masp_epochs_per_year,
+            target_locked_dec,
+            last_locked_dec,
+        );
+        let (reward, inflation_amount) = noterize_inflation(
+            &params.token,
+            inflation,
+            locked_amount,
+            params.precision,
+        )?;
+        let balance_reward = balance
+            .u128_eucl_div_rem((reward, params.precision))
+            .ok_or_else(|| Error::new_const("Balance reward overflow"))?
+            .0;
+        cumulative_reward = checked!(cumulative_reward + balance_reward)?;
+        if params.token == *native_token {
+            balance = checked!(balance + balance_reward)?;
+        }
+        projections.push(MaspRewardsProjection {
+            epoch_offset,
+            locked_amount,
+            inflation: inflation_amount,
+            reward: (reward, params.precision),
+            balance,
+            balance_reward,
+            cumulative_reward,
+        });
+
+        last_inflation = inflation_amount;
+        last_locked_amount = locked_amount;
+        locked_amount = growth.apply(locked_amount)?;
+    }
+
+    Ok(projections)
+}
+
 // This is only enabled when "wasm-runtime" is on, because we're using rayon
 #[cfg(not(any(feature = "multicore", test)))]
 /// Update the MASP's allowed conversions
@@ -758,6 +1024,113 @@ mod tests {
         .collect()
     }
 
+    #[test]
+    fn test_masp_rewards_projection() {
+        const EPOCHS: u64 = 5;
+
+        let mut s = TestStorage::default();
+        namada_parameters::init_test_storage(&mut s).unwrap();
+        let token_params = ShieldedParams {
+            max_reward_rate: Dec::from_str("0.1").unwrap(),
+            kp_gain_nom: Dec::from_str("0.1").unwrap(),
+            kd_gain_nom: Dec::from_str("0.1").unwrap(),
+            locked_amount_target: 10_000_u64,
+        };
+        let token = address::testing::btc();
+        let denom = 8.into();
+        namada_trans_token::write_params(&mut s, &token).unwrap();
+        crate::write_params::<_, namada_trans_token::Store<()>>(
+            &token_params,
+            &mut s,
+            &token,
+            &denom,
+        )
+        .unwrap();
+        write_denom(&mut s, &token, denom).unwrap();
+        let total_token_balance = Amount::native_whole(1_000_000);
+        s.write(&minted_balance_key(&token), total_token_balance)
+            .unwrap();
+        s.write(
+            &balance_key(&token, &address::MASP),
+            Amount::native_whole(2_000),
+        )
+        .unwrap();
+
+        let params = read_masp_rewards_projection_params::<
+            _,
+            namada_parameters::Store<_>,
+            namada_trans_token::Store<_>,
+        >(&s, &token)
+        .unwrap();
+        let balance = Amount::native_whole(100);
+        let projections = project_masp_rewards(
+            &params,
+            LockedAmountGrowth::Constant,
+            balance,
+            EPOCHS,
+            &address::testing::nam(),
+        )
+        .unwrap();
+        assert_eq!(projections.len(), EPOCHS as usize);
+
+        // With a constant locked amount, the projection must match the
+        // rewards actually computed at each of the next epochs
+        let mut cumulative_reward = Amount::zero();
+        for projection in projections {
+            let (reward, _) = calculate_masp_rewards::<
+                _,
+                namada_trans_token::Store<_>,
+            >(
+                &mut s, &token, params.masp_epochs_per_year
+            )
+            .unwrap();
+            assert_eq!(projection.reward, reward);
+            assert_eq!(projection.locked_amount, params.locked_amount);
+            // Rewards earned on a non-native token are not compounded
+            assert_eq!(projection.balance, balance);
+            let balance_reward = balance.u128_eucl_div_rem(reward).unwrap().0;
+            assert_eq!(projection.balance_reward, balance_reward);
+            cumulative_reward += balance_reward;
+            assert_eq!(projection.cumulative_reward, cumulative_reward);
+        }
+    }
+
+    #[test]
+    fn test_locked_amount_growth() {
+        let amount = Amount::from_u64(1_000);
+        assert_eq!(LockedAmountGrowth::Constant.apply(amount).unwrap(), amount);
+        assert_eq!(
+            LockedAmountGrowth::Linear(Change::from(100_i64))
+                .apply(amount)
+                .unwrap(),
+            Amount::from_u64(1_100)
+        );
+        assert_eq!(
+            LockedAmountGrowth::Linear(Change::from(-2_000_i64))
+                .apply(amount)
+                .unwrap(),
+            Amount::zero()
+        );
+        assert_eq!(
+            LockedAmountGrowth::Geometric(Dec::from_str("0.1").unwrap())
+                .apply(amount)
+                .unwrap(),
+            Amount::from_u64(1_100)
+        );
+        assert_eq!(
+            LockedAmountGrowth::Geometric(Dec::from_str("-0.5").unwrap())
+                .apply(amount)
+                .unwrap(),
+            Amount::from_u64(500)
+        );
+        assert_eq!(
+            LockedAmountGrowth::Geometric(Dec::from_str("-2").unwrap())
+                .apply(amount)
+                .unwrap(),
+            Amount::zero()
+        );
+    }
+
     #[test]
     fn test_masp_inflation_playground() {
         let denom = Uint::from(1_000_000); // token denomination (usually 6)
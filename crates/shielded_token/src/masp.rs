@@ -2,6 +2,7 @@
 #![allow(clippy::arithmetic_side_effects)]
 #![allow(clippy::cast_possible_truncation)]
 #![allow(clippy::cast_sign_loss)]
+pub mod prover;
 mod shielded_sync;
 pub mod shielded_wallet;
 #[cfg(test)]
@@ -49,7 +50,7 @@ pub use crate::masp::shielded_sync::dispatcher::{Dispatcher, DispatcherCache};
 #[cfg(not(target_family = "wasm"))]
 pub use crate::masp::shielded_sync::MaspLocalTaskEnv;
 pub use crate::masp::shielded_sync::{
-    utils, ShieldedSyncConfig, ShieldedSyncConfigBuilder,
+    snapshot, utils, ShieldedSyncConfig, ShieldedSyncConfigBuilder,
 };
 pub use crate::validation::{
     partial_deauthorize, preload_verifying_keys, PVKs, CONVERT_NAME,
@@ -484,6 +485,7 @@ pub mod testing {
     use proptest::{collection, option, prop_compose};
 
     use super::*;
+    use crate::masp::prover::{GrothProof, MaspProver, ProofRequest};
 
     /// This function computes `value` in the exponent of the value commitment
     /// base
@@ -733,6 +735,44 @@ pub mod testing {
         }
     }
 
+    /// An implementation of [`MaspProver`] that, like [`MockTxProver`],
+    /// does not generate valid zero-knowledge proofs.
+    #[derive(Debug, Clone, Default)]
+    pub struct MockMaspProver;
+
+    impl MockMaspProver {
+        /// Produce a placeholder proof for each of the given requests
+        pub fn prove_requests(
+            &self,
+            requests: Vec<ProofRequest>,
+        ) -> Result<Vec<GrothProof>, eyre::Error> {
+            let mut zkproof = [0u8; GROTH_PROOF_SIZE];
+            let proof = Proof::<Bls12> {
+                a: G1Affine::generator(),
+                b: G2Affine::generator(),
+                c: G1Affine::generator(),
+            };
+            proof
+                .write(&mut zkproof[..])
+                .expect("should be able to serialize a proof");
+            Ok(vec![zkproof; requests.len()])
+        }
+    }
+
+    #[cfg_attr(feature = "async-send", async_trait::async_trait)]
+    #[cfg_attr(
+        not(feature = "async-send"),
+        async_trait::async_trait(?Send)
+    )]
+    impl MaspProver for MockMaspProver {
+        async fn prove(
+            &self,
+            requests: Vec<ProofRequest>,
+        ) -> Result<Vec<GrothProof>, eyre::Error> {
+            self.prove_requests(requests)
+        }
+    }
+
     #[derive(Debug, Clone)]
     /// Adapts a CSPRNG from a PRNG for proptesting
     pub struct TestCsprng<R: RngCore>(pub R);
@@ -0,0 +1,566 @@
+//! Abstractions over the generation of the zero-knowledge proofs of MASP
+//! transactions.
+//!
+//! Proving is split from the rest of the transaction construction, so that
+//! it can be delegated to a remote service. Only the private inputs of the
+//! circuits are ever handed to a [`MaspProver`]: these include the proof
+//! generation keys of the spent notes, but never their spend authorizing
+//! keys. Spend authorization and binding signatures are always produced by
+//! the client.
+
+use std::collections::VecDeque;
+use std::ops::{AddAssign, Neg};
+use std::sync::Mutex;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use eyre::{eyre, WrapErr};
+use masp_primitives::asset_type::AssetType;
+use masp_primitives::constants::{
+    SPENDING_KEY_GENERATOR, VALUE_COMMITMENT_RANDOMNESS_GENERATOR,
+};
+use masp_primitives::convert::AllowedConversion;
+use masp_primitives::group::GroupEncoding;
+use masp_primitives::jubjub;
+use masp_primitives::merkle_tree::MerklePath;
+use masp_primitives::sapling::prover::TxProver;
+use masp_primitives::sapling::redjubjub::{PrivateKey, PublicKey, Signature};
+use masp_primitives::sapling::{
+    Diversifier, Node, PaymentAddress, ProofGenerationKey, Rseed,
+};
+use masp_primitives::transaction::components::{I128Sum, GROTH_PROOF_SIZE};
+use masp_proofs::bls12_381;
+use masp_proofs::prover::LocalTxProver;
+use namada_core::borsh::BorshSerializeExt;
+use namada_io::{MaybeSend, MaybeSync};
+use rand_core::{CryptoRng, RngCore};
+
+/// A serialized Groth16 proof
+pub type GrothProof = [u8; GROTH_PROOF_SIZE];
+
+/// The response of a remote MASP prover to a batch of [`ProofRequest`]s
+pub type ProofResponse = Result<Vec<GrothProof>, String>;
+
+/// The private inputs of a spend circuit
+#[derive(Clone, BorshSerialize, BorshDeserialize)]
+pub struct SpendProofRequest {
+    /// The spend validating key of the proof generation key
+    pub ak: [u8; 32],
+    /// The nullifier deriving key of the proof generation key
+    pub nsk: [u8; 32],
+    /// The diversifier of the spent note's address
+    pub diversifier: Diversifier,
+    /// The commitment randomness of the spent note
+    pub rseed: Rseed,
+    /// The randomizer of the spend validating key
+    pub ar: [u8; 32],
+    /// The asset type of the spent note
+    pub asset_type: AssetType,
+    /// The value of the spent note
+    pub value: u64,
+    /// The anchor of the commitment tree
+    pub anchor: [u8; 32],
+    /// The path of the spent note in the commitment tree
+    pub merkle_path: MerklePath<Node>,
+    /// The value commitment randomness
+    pub rcv: [u8; 32],
+}
+
+/// The private inputs of an output circuit
+#[derive(Clone, BorshSerialize, BorshDeserialize)]
+pub struct OutputProofRequest {
+    /// The ephemeral secret key of the note encryption
+    pub esk: [u8; 32],
+    /// The recipient of the note
+    pub payment_address: PaymentAddress,
+    /// The commitment randomness of the note
+    pub rcm: [u8; 32],
+    /// The asset type of the note
+    pub asset_type: AssetType,
+    /// The value of the note
+    pub value: u64,
+    /// The value commitment randomness
+    pub rcv: [u8; 32],
+}
+
+/// The private inputs of a convert circuit
+#[derive(Clone, BorshSerialize, BorshDeserialize)]
+pub struct ConvertProofRequest {
+    /// The applied conversion
+    pub allowed_conversion: AllowedConversion,
+    /// The number of times the conversion is applied
+    pub value: u64,
+    /// The anchor of the conversion tree
+    pub anchor: [u8; 32],
+    /// The path of the conversion in the conversion tree
+    pub merkle_path: MerklePath<Node>,
+    /// The value commitment randomness
+    pub rcv: [u8; 32],
+}
+
+/// A request for a single MASP circuit proof
+#[derive(Clone, BorshSerialize, BorshDeserialize)]
+pub enum ProofRequest {
+    /// Prove a spend description
+    Spend(SpendProofRequest),
+    /// Prove an output description
+    Output(OutputProofRequest),
+    /// Prove a convert description
+    Convert(ConvertProofRequest),
+}
+
+/// Generates the proofs of MASP transactions
+#[cfg_attr(feature = "async-send", async_trait::async_trait)]
+#[cfg_attr(not(feature = "async-send"), async_trait::async_trait(?Send))]
+pub trait MaspProver: MaybeSend + MaybeSync {
+    /// Generate a proof for each of the given requests, in the same order
+    async fn prove(
+        &self,
+        requests: Vec<ProofRequest>,
+    ) -> Result<Vec<GrothProof>, eyre::Error>;
+}
+
+/// A [`MaspProver`] generating proofs in-process, with the MASP parameters
+/// available on the local machine
+pub struct LocalMaspProver(pub LocalTxProver);
+
+impl LocalMaspProver {
+    /// Generate a proof for each of the given requests, in the same order
+    pub fn prove_requests(
+        &self,
+        requests: Vec<ProofRequest>,
+    ) -> Result<Vec<GrothProof>, eyre::Error> {
+        requests
+            .into_iter()
+            .enumerate()
+            .map(|(index, request)| {
+                self.prove_request(request).wrap_err_with(|| {
+                    format!("Failed to prove request {index}")
+                })
+            })
+            .collect()
+    }
+
+    fn prove_request(
+        &self,
+        request: ProofRequest,
+    ) -> Result<GrothProof, eyre::Error> {
+        let mut ctx = self.0.new_sapling_proving_context();
+        match request {
+            ProofRequest::Spend(req) => {
+                let proof_generation_key = ProofGenerationKey {
+                    ak: decode_point(&req.ak)?,
+                    nsk: decode_scalar(&req.nsk)?,
+                };
+                let (proof, _, _) = self
+                    .0
+                    .spend_proof(
+                        &mut ctx,
+                        proof_generation_key,
+                        req.diversifier,
+                        req.rseed,
+                        decode_scalar(&req.ar)?,
+                        req.asset_type,
+                        req.value,
+                        decode_anchor(&req.anchor)?,
+                        req.merkle_path,
+                        decode_scalar(&req.rcv)?,
+                    )
+                    .map_err(|()| eyre!("Invalid spend proof inputs"))?;
+                Ok(proof)
+            }
+            ProofRequest::Output(req) => {
+                let (proof, _) = self.0.output_proof(
+                    &mut ctx,
+                    decode_scalar(&req.esk)?,
+                    req.payment_address,
+                    decode_scalar(&req.rcm)?,
+                    req.asset_type,
+                    req.value,
+                    decode_scalar(&req.rcv)?,
+                );
+                Ok(proof)
+            }
+            ProofRequest::Convert(req) => {
+                let (proof, _) = self
+                    .0
+                    .convert_proof(
+                        &mut ctx,
+                        req.allowed_conversion,
+                        req.value,
+                        decode_anchor(&req.anchor)?,
+                        req.merkle_path,
+                        decode_scalar(&req.rcv)?,
+                    )
+                    .map_err(|()| eyre!("Invalid convert proof inputs"))?;
+                Ok(proof)
+            }
+        }
+    }
+}
+
+#[cfg_attr(feature = "async-send", async_trait::async_trait)]
+#[cfg_attr(not(feature = "async-send"), async_trait::async_trait(?Send))]
+impl MaspProver for LocalMaspProver {
+    async fn prove(
+        &self,
+        requests: Vec<ProofRequest>,
+    ) -> Result<Vec<GrothProof>, eyre::Error> {
+        self.prove_requests(requests)
+    }
+}
+
+fn decode_scalar(bytes: &[u8; 32]) -> Result<jubjub::Fr, eyre::Error> {
+    Option::from(jubjub::Fr::from_bytes(bytes))
+        .ok_or_else(|| eyre!("Invalid Jubjub scalar in proof request"))
+}
+
+fn decode_point(
+    bytes: &[u8; 32],
+) -> Result<jubjub::SubgroupPoint, eyre::Error> {
+    Option::from(jubjub::SubgroupPoint::from_bytes(bytes))
+        .ok_or_else(|| eyre!("Invalid Jubjub point in proof request"))
+}
+
+fn decode_anchor(bytes: &[u8; 32]) -> Result<bls12_381::Scalar, eyre::Error> {
+    Option::from(bls12_381::Scalar::from_bytes(bytes))
+        .ok_or_else(|| eyre!("Invalid anchor in proof request"))
+}
+
+/// The accumulated value commitments of a transaction being built
+pub(crate) struct ProvingContext {
+    bsk: jubjub::Fr,
+    cv_sum: jubjub::ExtendedPoint,
+}
+
+enum ProofSource {
+    /// Record the proof requests, returning placeholder proofs
+    Collect(Vec<ProofRequest>),
+    /// Return the proofs of previously collected requests
+    Replay {
+        proofs: VecDeque<(ProofRequest, GrothProof)>,
+        mismatch: bool,
+    },
+}
+
+/// A [`TxProver`] that leaves the proving to a [`MaspProver`].
+///
+/// A transaction is first built while collecting the inputs of its circuits.
+/// Once these have been proven, the same transaction is built again from the
+/// same randomness, this time replaying the obtained proofs.
+pub(crate) struct ExternalTxProver<R> {
+    rng: Mutex<R>,
+    source: Mutex<ProofSource>,
+}
+
+impl<R: RngCore + CryptoRng> ExternalTxProver<R> {
+    /// Create a prover that collects the proof requests of a transaction
+    pub fn collect(rng: R) -> Self {
+        Self {
+            rng: Mutex::new(rng),
+            source: Mutex::new(ProofSource::Collect(vec![])),
+        }
+    }
+
+    /// Create a prover that replays the given proofs of the collected
+    /// requests
+    pub fn replay(
+        rng: R,
+        requests: Vec<ProofRequest>,
+        proofs: Vec<GrothProof>,
+    ) -> Result<Self, eyre::Error> {
+        if requests.len() != proofs.len() {
+            return Err(eyre!(
+                "Expected {} MASP proofs, but got {}",
+                requests.len(),
+                proofs.len()
+            ));
+        }
+        Ok(Self {
+            rng: Mutex::new(rng),
+            source: Mutex::new(ProofSource::Replay {
+                proofs: requests.into_iter().zip(proofs).collect(),
+                mismatch: false,
+            }),
+        })
+    }
+
+    /// Get the collected proof requests
+    pub fn into_requests(self) -> Vec<ProofRequest> {
+        match self.source.into_inner().unwrap() {
+            ProofSource::Collect(requests) => requests,
+            ProofSource::Replay { .. } => vec![],
+        }
+    }
+
+    /// Check that every replayed proof was requested with the same inputs
+    /// it was generated from
+    pub fn check_replay(self) -> Result<(), eyre::Error> {
+        match self.source.into_inner().unwrap() {
+            ProofSource::Replay { proofs, mismatch } => {
+                if mismatch || !proofs.is_empty() {
+                    Err(eyre!(
+                        "The MASP transaction could not be rebuilt from the \
+                         provided proofs"
+                    ))
+                } else {
+                    Ok(())
+                }
+            }
+            ProofSource::Collect(_) => Ok(()),
+        }
+    }
+
+    fn proof_for(&self, request: ProofRequest) -> GrothProof {
+        let mut source = self.source.lock().unwrap();
+        match &mut *source {
+            ProofSource::Collect(requests) => {
+                requests.push(request);
+                [0; GROTH_PROOF_SIZE]
+            }
+            ProofSource::Replay { proofs, mismatch } => {
+                match proofs.pop_front() {
+                    Some((expected, proof))
+                        if expected.serialize_to_vec()
+                            == request.serialize_to_vec() =>
+                    {
+                        proof
+                    }
+                    _ => {
+                        *mismatch = true;
+                        [0; GROTH_PROOF_SIZE]
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<R: RngCore + CryptoRng> TxProver for ExternalTxProver<R> {
+    type SaplingProvingContext = ProvingContext;
+
+    fn new_sapling_proving_context(&self) -> Self::SaplingProvingContext {
+        ProvingContext {
+            bsk: jubjub::Fr::zero(),
+            cv_sum: jubjub::ExtendedPoint::identity(),
+        }
+    }
+
+    fn spend_proof(
+        &self,
+        ctx: &mut Self::SaplingProvingContext,
+        proof_generation_key: ProofGenerationKey,
+        diversifier: Diversifier,
+        rseed: Rseed,
+        ar: jubjub::Fr,
+        asset_type: AssetType,
+        value: u64,
+        anchor: bls12_381::Scalar,
+        merkle_path: MerklePath<Node>,
+        rcv: jubjub::Fr,
+    ) -> Result<(GrothProof, jubjub::ExtendedPoint, PublicKey), ()> {
+        // Accumulate the value commitment randomness in the context
+        ctx.bsk.add_assign(&rcv);
+
+        // Compute the value commitment, and accumulate it in the context
+        let value_commitment: jubjub::ExtendedPoint =
+            asset_type.value_commitment(value, rcv).commitment().into();
+        ctx.cv_sum += value_commitment;
+
+        // This is the result of the re-randomization, we compute it for the
+        // caller
+        let rk = PublicKey(proof_generation_key.ak.into())
+            .randomize(ar, SPENDING_KEY_GENERATOR);
+
+        let proof = self.proof_for(ProofRequest::Spend(SpendProofRequest {
+            ak: proof_generation_key.ak.to_bytes(),
+            nsk: proof_generation_key.nsk.to_bytes(),
+            diversifier,
+            rseed,
+            ar: ar.to_bytes(),
+            asset_type,
+            value,
+            anchor: anchor.to_bytes(),
+            merkle_path,
+            rcv: rcv.to_bytes(),
+        }));
+
+        Ok((proof, value_commitment, rk))
+    }
+
+    fn output_proof(
+        &self,
+        ctx: &mut Self::SaplingProvingContext,
+        esk: jubjub::Fr,
+        payment_address: PaymentAddress,
+        rcm: jubjub::Fr,
+        asset_type: AssetType,
+        value: u64,
+        rcv: jubjub::Fr,
+    ) -> (GrothProof, jubjub::ExtendedPoint) {
+        // Outputs subtract from the total
+        ctx.bsk.add_assign(&rcv.neg());
+
+        let value_commitment: jubjub::ExtendedPoint =
+            asset_type.value_commitment(value, rcv).commitment().into();
+        ctx.cv_sum -= value_commitment;
+
+        let proof = self.proof_for(ProofRequest::Output(OutputProofRequest {
+            esk: esk.to_bytes(),
+            payment_address,
+            rcm: rcm.to_bytes(),
+            asset_type,
+            value,
+            rcv: rcv.to_bytes(),
+        }));
+
+        (proof, value_commitment)
+    }
+
+    fn convert_proof(
+        &self,
+        ctx: &mut Self::SaplingProvingContext,
+        allowed_conversion: AllowedConversion,
+        value: u64,
+        anchor: bls12_381::Scalar,
+        merkle_path: MerklePath<Node>,
+        rcv: jubjub::Fr,
+    ) -> Result<(GrothProof, jubjub::ExtendedPoint), ()> {
+        ctx.bsk.add_assign(&rcv);
+
+        let value_commitment: jubjub::ExtendedPoint = allowed_conversion
+            .value_commitment(value, rcv)
+            .commitment()
+            .into();
+        ctx.cv_sum += value_commitment;
+
+        let proof =
+            self.proof_for(ProofRequest::Convert(ConvertProofRequest {
+                allowed_conversion,
+                value,
+                anchor: anchor.to_bytes(),
+                merkle_path,
+                rcv: rcv.to_bytes(),
+            }));
+
+        Ok((proof, value_commitment))
+    }
+
+    fn binding_sig(
+        &self,
+        ctx: &mut Self::SaplingProvingContext,
+        _assets_and_values: &I128Sum,
+        sighash: &[u8; 32],
+    ) -> Result<Signature, ()> {
+        let mut rng = self.rng.lock().unwrap();
+
+        // Grab the current `bsk` from the context, and derive `bvk`
+        let bsk = PrivateKey(ctx.bsk);
+        let bvk = PublicKey::from_private(
+            &bsk,
+            VALUE_COMMITMENT_RANDOMNESS_GENERATOR,
+        );
+
+        // Construct signature message
+        let mut data_to_be_signed = [0u8; 64];
+        data_to_be_signed[0..32].copy_from_slice(&bvk.0.to_bytes());
+        data_to_be_signed[32..64].copy_from_slice(&sighash[..]);
+
+        Ok(bsk.sign(
+            &data_to_be_signed,
+            &mut *rng,
+            VALUE_COMMITMENT_RANDOMNESS_GENERATOR,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test_masp_prover {
+    use masp_primitives::zip32::ExtendedSpendingKey;
+    use rand_core::OsRng;
+
+    use super::*;
+
+    fn arbitrary_spend_request() -> SpendProofRequest {
+        let esk = ExtendedSpendingKey::master(&[0; 32]);
+        let pgk = esk.expsk.proof_generation_key();
+        let (_, address) = esk.default_address();
+        SpendProofRequest {
+            ak: pgk.ak.to_bytes(),
+            nsk: pgk.nsk.to_bytes(),
+            diversifier: *address.diversifier(),
+            rseed: Rseed::AfterZip212([1; 32]),
+            ar: jubjub::Fr::one().to_bytes(),
+            asset_type: AssetType::new(b"test").unwrap(),
+            value: 1,
+            anchor: bls12_381::Scalar::one().to_bytes(),
+            merkle_path: MerklePath::from_path(vec![], 0),
+            rcv: jubjub::Fr::one().to_bytes(),
+        }
+    }
+
+    /// Test that the collected proof requests are replayed in order, and
+    /// that the replay fails if the requests differ.
+    #[test]
+    fn test_collect_and_replay_proofs() {
+        let request = arbitrary_spend_request();
+        let pgk = ProofGenerationKey {
+            ak: decode_point(&request.ak).unwrap(),
+            nsk: decode_scalar(&request.nsk).unwrap(),
+        };
+        let spend = |prover: &ExternalTxProver<OsRng>, value| {
+            let mut ctx = prover.new_sapling_proving_context();
+            prover
+                .spend_proof(
+                    &mut ctx,
+                    pgk.clone(),
+                    request.diversifier,
+                    request.rseed,
+                    decode_scalar(&request.ar).unwrap(),
+                    request.asset_type,
+                    value,
+                    decode_anchor(&request.anchor).unwrap(),
+                    request.merkle_path.clone(),
+                    decode_scalar(&request.rcv).unwrap(),
+                )
+                .unwrap()
+        };
+
+        let collector = ExternalTxProver::collect(OsRng);
+        let (placeholder, cv, rk) = spend(&collector, 1);
+        assert_eq!(placeholder, [0; GROTH_PROOF_SIZE]);
+        let requests = collector.into_requests();
+        assert_eq!(requests.len(), 1);
+
+        let replayer = ExternalTxProver::replay(
+            OsRng,
+            requests.clone(),
+            vec![[7; GROTH_PROOF_SIZE]],
+        )
+        .unwrap();
+        let (proof, replayed_cv, replayed_rk) = spend(&replayer, 1);
+        assert_eq!(proof, [7; GROTH_PROOF_SIZE]);
+        assert_eq!(cv, replayed_cv);
+        assert_eq!(rk.0, replayed_rk.0);
+        assert!(replayer.check_replay().is_ok());
+
+        let replayer = ExternalTxProver::replay(
+            OsRng,
+            requests.clone(),
+            vec![[7; GROTH_PROOF_SIZE]],
+        )
+        .unwrap();
+        _ = spend(&replayer, 2);
+        assert!(replayer.check_replay().is_err());
+
+        assert!(ExternalTxProver::replay(OsRng, requests, vec![]).is_err());
+    }
+
+    /// Test that proof requests survive an encoding roundtrip.
+    #[test]
+    fn test_proof_request_encoding() {
+        let request = ProofRequest::Spend(arbitrary_spend_request());
+        let bytes = request.serialize_to_vec();
+        let decoded = ProofRequest::try_from_slice(&bytes).unwrap();
+        assert_eq!(decoded.serialize_to_vec(), bytes);
+    }
+}
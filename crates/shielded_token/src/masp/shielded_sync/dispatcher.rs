@@ -13,7 +13,6 @@ use futures::future::{select, Either};
 use futures::task::AtomicWaker;
 use masp_primitives::merkle_tree::{CommitmentTree, IncrementalWitness};
 use masp_primitives::sapling::{Node, ViewingKey};
-use masp_primitives::transaction::Transaction;
 use namada_core::chain::BlockHeight;
 use namada_core::collections::HashMap;
 use namada_core::control_flow::time::{Duration, LinearBackoff, Sleep};
@@ -24,15 +23,15 @@ use namada_io::{MaybeSend, MaybeSync, ProgressBar};
 use namada_tx::IndexedTx;
 use namada_wallet::{DatedKeypair, DatedSpendingKey};
 
-use super::utils::{IndexedNoteEntry, MaspClient};
-use crate::masp::shielded_sync::trial_decrypt;
-use crate::masp::utils::{
-    blocks_left_to_fetch, DecryptedData, Fetched, RetryStrategy, TrialDecrypted,
+use super::utils::{IndexedNoteEntry, IndexedNoteEntryRefs, MaspClient};
+use crate::masp::shielded_sync::{
+    trial_decrypt_batch, TrialDecryptedBatch, TrialDecryptionBatch,
 };
-use crate::masp::{
-    to_viewing_key, MaspExtendedSpendingKey, NoteIndex, ShieldedUtils,
-    ShieldedWallet, WitnessMap,
+use crate::masp::shielded_wallet::register_key_birthdays;
+use crate::masp::utils::{
+    blocks_left_to_fetch, Fetched, RetryStrategy, TrialDecrypted,
 };
+use crate::masp::{NoteIndex, ShieldedUtils, ShieldedWallet, WitnessMap};
 
 struct AsyncCounterInner {
     waker: AtomicWaker,
@@ -166,11 +165,7 @@ enum Message {
             TaskError<[BlockHeight; 2]>,
         >,
     ),
-    TrialDecrypt(
-        IndexedTx,
-        ViewingKey,
-        ControlFlow<(), BTreeMap<usize, DecryptedData>>,
-    ),
+    TrialDecrypt(u64, ControlFlow<(), TrialDecryptedBatch>),
 }
 
 struct DispatcherTasks<Spawner> {
@@ -251,6 +246,8 @@ where
     cache: DispatcherCache,
     /// We are syncing up to this height
     height_to_sync: BlockHeight,
+    /// We are syncing from this height
+    start_height: BlockHeight,
     interrupt_flag: AtomicFlag,
 }
 
@@ -296,6 +293,7 @@ where
 
     Dispatcher {
         height_to_sync: BlockHeight(0),
+        start_height: BlockHeight(0),
         state,
         ctx,
         tasks,
@@ -385,8 +383,8 @@ where
         &mut self,
         InitialState {
             last_witnessed_tx,
+            start_height,
             last_query_height,
-            ..
         }: &InitialState,
     ) -> Result<(), eyre::Error> {
         if let Some((_, cmt)) = self.cache.commitment_tree.take() {
@@ -414,8 +412,12 @@ where
             std::mem::swap(&mut vk_heights, &mut self.ctx.vk_heights);
             for (vk, _) in vk_heights
                 .iter()
-                // NB: skip keys that are synced past the given `indexed_tx`
-                .filter(|(_vk, h)| h.as_ref() < Some(&indexed_tx))
+                // NB: skip keys that are synced past the given `indexed_tx`,
+                // or that lag behind the synced blocks
+                .filter(|(_vk, h)| {
+                    h.as_ref() < Some(&indexed_tx)
+                        && is_key_in_sync_range(*start_height, h.as_ref())
+                })
             {
                 for (note_pos_offset, (note, pa, memo)) in self
                     .cache
@@ -440,9 +442,11 @@ where
             .ctx
             .vk_heights
             .iter_mut()
-            // NB: skip keys that are synced past the last input height
+            // NB: skip keys that are synced past the last input height, or
+            // that lag behind the synced blocks
             .filter(|(_vk, h)| {
                 h.as_ref().map(|itx| &itx.height) < Some(last_query_height)
+                    && is_key_in_sync_range(*start_height, h.as_ref())
             })
         {
             // NB: the entire block is synced
@@ -459,35 +463,19 @@ where
         sks: &[DatedSpendingKey],
         fvks: &[DatedKeypair<ViewingKey>],
     ) -> Result<InitialState, eyre::Error> {
-        if start_query_height > last_query_height {
-            return Err(eyre!(
-                "The start height {start_query_height:?} cannot be higher \
-                 than the ending height {last_query_height:?} in the shielded \
-                 sync"
-            ));
-        }
-
-        for vk in sks
-            .iter()
-            .map(|esk| {
-                esk.map(|k| {
-                    to_viewing_key(&MaspExtendedSpendingKey::from(k)).vk
-                })
-            })
-            .chain(fvks.iter().copied())
+        if let (Some(start), Some(last)) =
+            (start_query_height, last_query_height)
         {
-            if let Some(h) = self.ctx.vk_heights.entry(vk.key).or_default() {
-                let birthday = IndexedTx::entire_block(vk.birthday);
-                if birthday > *h {
-                    *h = birthday;
-                }
-            } else if vk.birthday >= BlockHeight::first() {
-                self.ctx
-                    .vk_heights
-                    .insert(vk.key, Some(IndexedTx::entire_block(vk.birthday)));
+            if start > last {
+                return Err(eyre!(
+                    "The start height {start} cannot be higher than the \
+                     ending height {last} in the shielded sync"
+                ));
             }
         }
 
+        register_key_birthdays(&mut self.ctx.vk_heights, sks, fvks);
+
         // the latest block height which has been added to the witness Merkle
         // tree
         let last_witnessed_tx = self.ctx.note_index.keys().max().cloned();
@@ -543,6 +531,17 @@ where
             // NB: limit fetching until the last committed height
             .min(last_block_height);
 
+        // NB: without a pre-built commitment tree, the tree is updated with
+        // the fetched txs, hence no block past the last witnessed one may be
+        // skipped
+        let start_query_height = match last_witnessed_tx.as_ref() {
+            Some(itx)
+                if self.client.capabilities().needs_witness_map_update() =>
+            {
+                start_query_height.map(|height| height.min(itx.height))
+            }
+            _ => start_query_height,
+        };
         let start_height = start_query_height
             .map_or_else(|| self.ctx.min_height_to_sync_from(), Ok)?
             // NB: the start height cannot be greater than
@@ -556,6 +555,7 @@ where
         };
 
         self.height_to_sync = initial_state.last_query_height;
+        self.start_height = initial_state.start_height;
         self.spawn_initial_set_of_tasks(&initial_state);
 
         self.config
@@ -620,9 +620,7 @@ where
             .fetched_tracker
             .set_upper_limit(number_of_fetches);
 
-        for (itx, tx) in self.cache.fetched.iter() {
-            self.spawn_trial_decryptions(*itx, tx);
-        }
+        self.spawn_trial_decryptions(self.cache.fetched.iter());
     }
 
     fn handle_incoming_message(&mut self, message: Message) {
@@ -664,9 +662,9 @@ where
                 }
             }
             Message::FetchTxs(Ok((from, to, tx_batch))) => {
-                for (itx, txs) in &tx_batch {
-                    self.spawn_trial_decryptions(*itx, txs);
-                }
+                self.spawn_trial_decryptions(
+                    tx_batch.iter().map(|(itx, tx)| (itx, tx)),
+                );
                 self.cache.fetched.extend(tx_batch);
 
                 self.config.fetched_tracker.increment_by(to.0 - from.0 + 1);
@@ -682,14 +680,20 @@ where
                     self.spawn_fetch_txs(from, to);
                 }
             }
-            Message::TrialDecrypt(itx, vk, decrypted_data) => {
-                if let ControlFlow::Continue(decrypted_data) = decrypted_data {
-                    self.config.applied_tracker.set_upper_limit(
-                        self.config.applied_tracker.upper_limit()
-                            + decrypted_data.len() as u64,
-                    );
-                    self.cache.trial_decrypted.insert(itx, vk, decrypted_data);
-                    self.config.scanned_tracker.increment_by(1);
+            Message::TrialDecrypt(scanned_txs, decrypted) => {
+                if let ControlFlow::Continue(decrypted) = decrypted {
+                    for (itx, vk, decrypted_data) in decrypted {
+                        self.config.applied_tracker.set_upper_limit(
+                            self.config.applied_tracker.upper_limit()
+                                + decrypted_data.len() as u64,
+                        );
+                        self.cache.trial_decrypted.insert(
+                            itx,
+                            vk,
+                            decrypted_data,
+                        );
+                    }
+                    self.config.scanned_tracker.increment_by(scanned_txs);
                 }
             }
         }
@@ -794,24 +798,51 @@ where
         spawned_tasks
     }
 
-    fn spawn_trial_decryptions(&self, itx: IndexedTx, tx: &Transaction) {
-        for (vk, vk_height) in self.ctx.vk_heights.iter() {
-            let key_is_outdated = vk_height.as_ref() < Some(&itx);
-            let cached = self.cache.trial_decrypted.get(&itx, vk).is_some();
-
-            if key_is_outdated && !cached {
-                let tx = tx.clone();
-                let vk = *vk;
-
-                self.spawn_sync(move |interrupt| {
-                    Message::TrialDecrypt(
-                        itx,
-                        vk,
-                        trial_decrypt(tx, vk, || interrupt.get()),
-                    )
+    /// Spawn one trial decryption job per fetched block, covering
+    /// every viewing key that has yet to scan the block's transactions.
+    fn spawn_trial_decryptions<'txs>(
+        &self,
+        txs: impl IntoIterator<Item = IndexedNoteEntryRefs<'txs>>,
+    ) {
+        let mut blocks: BTreeMap<BlockHeight, TrialDecryptionBatch> =
+            BTreeMap::new();
+
+        for (itx, tx) in txs {
+            let vks: Vec<ViewingKey> = self
+                .ctx
+                .vk_heights
+                .iter()
+                .filter(|(vk, vk_height)| {
+                    let key_is_outdated = vk_height.as_ref() < Some(itx)
+                        && is_key_in_sync_range(
+                            self.start_height,
+                            vk_height.as_ref(),
+                        );
+                    let cached =
+                        self.cache.trial_decrypted.get(itx, vk).is_some();
+                    key_is_outdated && !cached
                 })
+                .map(|(vk, _)| *vk)
+                .collect();
+
+            if !vks.is_empty() {
+                blocks.entry(itx.height).or_default().push((
+                    *itx,
+                    tx.clone(),
+                    vks,
+                ));
             }
         }
+
+        for batch in blocks.into_values() {
+            let scanned_txs = batch.len() as u64;
+            self.spawn_sync(move |interrupt| {
+                Message::TrialDecrypt(
+                    scanned_txs,
+                    trial_decrypt_batch(batch, || interrupt.get()),
+                )
+            })
+        }
     }
 
     fn spawn_async<F>(&self, mut fut: F)
@@ -856,6 +887,16 @@ where
     }
 }
 
+/// Check if a sync starting from `start_height` scans all the blocks that a
+/// key synced up to `vk_height` has yet to scan. The keys lagging behind the
+/// start height keep their sync cursors, to be caught up by a later sync.
+fn is_key_in_sync_range(
+    start_height: BlockHeight,
+    vk_height: Option<&IndexedTx>,
+) -> bool {
+    vk_height.map_or_else(BlockHeight::first, |itx| itx.height) >= start_height
+}
+
 #[inline(always)]
 fn pre_built_in_cache<T>(
     pre_built_data: Option<&(BlockHeight, T)>,
@@ -881,11 +922,15 @@ mod dispatcher_tests {
 
     use super::*;
     use crate::masp::fs::FsShieldedUtils;
+    use crate::masp::shielded_sync::trial_decrypt;
     use crate::masp::test_utils::{
         arbitrary_masp_tx, arbitrary_masp_tx_with_fee_unshielding,
         arbitrary_vk, dated_arbitrary_vk, TestingMaspClient,
     };
-    use crate::masp::{MaspLocalTaskEnv, ShieldedSyncConfig};
+    use crate::masp::{
+        to_viewing_key, MaspExtendedSpendingKey, MaspLocalTaskEnv,
+        ShieldedSyncConfig,
+    };
 
     #[tokio::test]
     async fn test_applying_cache_drains_decrypted_data() {
@@ -942,6 +987,66 @@ mod dispatcher_tests {
             .await;
     }
 
+    /// Test that the keys lagging behind the start height of a sync keep
+    /// their sync cursors, while the other keys advance.
+    #[tokio::test]
+    async fn test_applying_cache_skips_lagging_keys() {
+        let (client, _) = TestingMaspClient::new(BlockHeight::first());
+        let (_sender, shutdown_sig) = shutdown_signal();
+        let config = ShieldedSyncConfig::builder()
+            .client(client)
+            .fetched_tracker(DevNullProgressBar)
+            .scanned_tracker(DevNullProgressBar)
+            .applied_tracker(DevNullProgressBar)
+            .shutdown_signal(shutdown_sig)
+            .build();
+        let temp_dir = tempdir().unwrap();
+        let utils = FsShieldedUtils {
+            context_dir: temp_dir.path().to_path_buf(),
+        };
+        let synced_vk = arbitrary_vk();
+        let lagging_vk =
+            to_viewing_key(&MaspExtendedSpendingKey::master(&[1; 32])).vk;
+        MaspLocalTaskEnv::new(4)
+            .expect("Test failed")
+            .run(|s| async {
+                let mut dispatcher = config.dispatcher(s, &utils).await;
+                dispatcher.ctx.vk_heights = BTreeMap::from([
+                    (synced_vk, Some(IndexedTx::entire_block(5.into()))),
+                    (lagging_vk, Some(IndexedTx::entire_block(2.into()))),
+                ]);
+                for h in 5u64..10 {
+                    let itx = IndexedTx {
+                        height: h.into(),
+                        index: Default::default(),
+                        batch_index: None,
+                    };
+                    dispatcher.cache.fetched.insert((itx, arbitrary_masp_tx()));
+                    dispatcher.ctx.note_index.insert(itx, h as usize);
+                    dispatcher.cache.trial_decrypted.insert(
+                        itx,
+                        synced_vk,
+                        BTreeMap::new(),
+                    )
+                }
+
+                dispatcher
+                    .apply_cache_to_shielded_context(&InitialState {
+                        last_witnessed_tx: None,
+                        start_height: 5.into(),
+                        last_query_height: 9.into(),
+                    })
+                    .expect("Test failed");
+                assert!(dispatcher.cache.trial_decrypted.is_empty());
+                let expected = BTreeMap::from([
+                    (synced_vk, Some(IndexedTx::entire_block(9.into()))),
+                    (lagging_vk, Some(IndexedTx::entire_block(2.into()))),
+                ]);
+                assert_eq!(expected, dispatcher.ctx.vk_heights);
+            })
+            .await;
+    }
+
     #[tokio::test]
     async fn test_async_counter_on_async_interrupt() {
         MaspLocalTaskEnv::new(1)
@@ -1127,6 +1232,35 @@ mod dispatcher_tests {
         assert_eq!(height, BlockHeight(6));
     }
 
+    /// Test that the leading height to sync from is only returned when some
+    /// keys, including the newly registered ones, lag behind the others.
+    #[test]
+    fn test_leading_height_to_sync_from() {
+        let temp_dir = tempdir().unwrap();
+        let mut shielded_ctx =
+            FsShieldedUtils::new(temp_dir.path().to_path_buf());
+        let vk = arbitrary_vk();
+        let new_vk = DatedKeypair::new(
+            to_viewing_key(&MaspExtendedSpendingKey::master(&[1; 32])).vk,
+            Some(3.into()),
+        );
+
+        assert_eq!(shielded_ctx.leading_height_to_sync_from(&[], &[]), None);
+
+        shielded_ctx
+            .vk_heights
+            .insert(vk, Some(IndexedTx::entire_block(6.into())));
+        assert_eq!(shielded_ctx.leading_height_to_sync_from(&[], &[]), None);
+
+        // A new key with an old birthday lags behind the synced one
+        assert_eq!(
+            shielded_ctx.leading_height_to_sync_from(&[], &[new_vk]),
+            Some(BlockHeight(6))
+        );
+        // The new key is not registered in the context
+        assert_eq!(shielded_ctx.vk_heights.len(), 1);
+    }
+
     /// We test that if a masp transaction is only partially trial-decrypted
     /// before the process is interrupted, we discard the partial results.
     #[test]
@@ -1146,6 +1280,54 @@ mod dispatcher_tests {
         assert_eq!(res, ControlFlow::Break(()));
     }
 
+    /// Test that a batch of transactions is trial-decrypted with every
+    /// key paired to each transaction, and that an interrupted batch
+    /// is discarded as a whole.
+    #[test]
+    fn test_trial_decrypt_batch() {
+        let vk = arbitrary_vk();
+        let other_vk =
+            to_viewing_key(&MaspExtendedSpendingKey::master(&[1; 32])).vk;
+        let itx = |index| IndexedTx {
+            height: 1.into(),
+            index: TxIndex(index),
+            batch_index: None,
+        };
+        let batch = vec![
+            (itx(0), arbitrary_masp_tx(), vec![vk, other_vk]),
+            (itx(1), arbitrary_masp_tx(), vec![vk]),
+        ];
+
+        let ControlFlow::Continue(decrypted) =
+            trial_decrypt_batch(batch, || false)
+        else {
+            panic!("Trial decryption should not have been interrupted");
+        };
+        let decrypted: Vec<_> = decrypted
+            .into_iter()
+            .map(|(itx, vk, _)| (itx, vk))
+            .collect();
+        assert_eq!(
+            decrypted,
+            vec![(itx(0), vk), (itx(0), other_vk), (itx(1), vk)]
+        );
+
+        let guard = AtomicFlag::default();
+        let interrupt = || {
+            if guard.get() {
+                true
+            } else {
+                guard.set();
+                false
+            }
+        };
+        let res = trial_decrypt_batch(
+            vec![(itx(0), arbitrary_masp_tx_with_fee_unshielding(), vec![vk])],
+            interrupt,
+        );
+        assert_eq!(res, ControlFlow::Break(()));
+    }
+
     /// Test that if fetching fails before finishing,
     /// we re-establish the fetching process
     #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
@@ -1427,6 +1609,7 @@ mod dispatcher_tests {
                 MaspLocalTaskEnv::new(4).unwrap(),
                 config.clone(),
                 None,
+                None,
                 &[sk],
                 &[vk],
             )
@@ -1465,6 +1648,7 @@ mod dispatcher_tests {
                 MaspLocalTaskEnv::new(4).unwrap(),
                 config,
                 None,
+                None,
                 &[sk],
                 &[vk],
             )
@@ -11,11 +11,13 @@ use masp_primitives::sapling::note_encryption::{
 use masp_primitives::sapling::ViewingKey;
 use masp_primitives::transaction::components::OutputDescription;
 use masp_primitives::transaction::{Authorization, Authorized, Transaction};
+use namada_core::collections::HashMap;
 #[cfg(not(target_family = "wasm"))]
 use namada_core::task_env::{
     LocalSetSpawner, LocalSetTaskEnvironment, TaskEnvironment,
 };
 use namada_io::{MaybeSend, MaybeSync};
+use namada_tx::IndexedTx;
 use typed_builder::TypedBuilder;
 
 use super::shielded_sync::utils::{MaspClient, RetryStrategy};
@@ -24,6 +26,7 @@ use crate::masp::utils::DecryptedData;
 use crate::masp::{ShieldedUtils, NETWORK};
 
 pub mod dispatcher;
+pub mod snapshot;
 pub mod utils;
 
 const DEFAULT_BUF_SIZE: usize = 32;
@@ -117,6 +120,59 @@ pub fn trial_decrypt(
     shielded: Transaction,
     vk: ViewingKey,
     mut interrupted: impl FnMut() -> bool,
+) -> ControlFlow<(), BTreeMap<usize, DecryptedData>> {
+    trial_decrypt_with_prepared_key(
+        &shielded,
+        &PreparedIncomingViewingKey::new(&vk.ivk()),
+        &mut interrupted,
+    )
+}
+
+/// Transactions of a single block, each paired with the viewing
+/// keys that should attempt to decrypt it.
+pub type TrialDecryptionBatch = Vec<(IndexedTx, Transaction, Vec<ViewingKey>)>;
+
+/// The notes decrypted by each viewing key of a [`TrialDecryptionBatch`].
+pub type TrialDecryptedBatch =
+    Vec<(IndexedTx, ViewingKey, BTreeMap<usize, DecryptedData>)>;
+
+/// Try to decrypt a batch of MASP transactions with many keys at once.
+///
+/// Each viewing key is prepared only once for the whole batch, which
+/// amortizes the cost of trial decryption when syncing a large number
+/// of keys.
+pub fn trial_decrypt_batch(
+    batch: TrialDecryptionBatch,
+    mut interrupted: impl FnMut() -> bool,
+) -> ControlFlow<(), TrialDecryptedBatch> {
+    let mut prepared_keys = HashMap::new();
+    let mut decrypted = Vec::with_capacity(batch.len());
+
+    for (itx, shielded, vks) in batch {
+        for vk in vks {
+            let ivk = prepared_keys
+                .entry(vk)
+                .or_insert_with(|| PreparedIncomingViewingKey::new(&vk.ivk()));
+            match trial_decrypt_with_prepared_key(
+                &shielded,
+                ivk,
+                &mut interrupted,
+            ) {
+                ControlFlow::Continue(notes) => {
+                    decrypted.push((itx, vk, notes));
+                }
+                ControlFlow::Break(()) => return ControlFlow::Break(()),
+            }
+        }
+    }
+
+    ControlFlow::Continue(decrypted)
+}
+
+fn trial_decrypt_with_prepared_key(
+    shielded: &Transaction,
+    ivk: &PreparedIncomingViewingKey,
+    interrupted: &mut impl FnMut() -> bool,
 ) -> ControlFlow<(), BTreeMap<usize, DecryptedData>> {
     type Proof = OutputDescription<
         <
@@ -139,7 +195,7 @@ pub fn trial_decrypt(
             if let Some(decrypted) = try_sapling_note_decryption::<_, Proof>(
                 &NETWORK,
                 1.into(),
-                &PreparedIncomingViewingKey::new(&vk.ivk()),
+                ivk,
                 so,
             ) {
                 accum.insert(note_pos_offset, decrypted);
@@ -0,0 +1,565 @@
+//! Portable snapshots of the data required to run shielded sync.
+//!
+//! A snapshot contains the shielded transfers committed in a range of block
+//! heights, along with the state of the note commitment tree (commitment
+//! tree, note index and witness map) right before the start of that range.
+//! This is enough to serve all the data a [`MaspClient`] with
+//! [`MaspClientCapabilities::AllData`] is expected to provide, for any
+//! height within the range.
+//!
+//! Snapshots are encoded as a manifest followed by a set of blobs, each of
+//! them addressed by its sha256 hash. Every blob is checked against its
+//! hash upon decoding, and the hash of the manifest uniquely identifies the
+//! contents of the whole snapshot.
+//!
+//! [`MaspClientCapabilities::AllData`]: super::utils::MaspClientCapabilities::AllData
+
+use std::collections::BTreeMap;
+use std::io::Read;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use eyre::{eyre, WrapErr};
+use futures::stream::{self, StreamExt};
+use masp_primitives::merkle_tree::CommitmentTree;
+use masp_primitives::sapling::Node;
+use masp_primitives::transaction::Transaction;
+use namada_core::borsh::BorshSerializeExt;
+use namada_core::chain::{BlockHeight, ChainId};
+use namada_core::collections::HashMap;
+use namada_core::hash::Hash;
+use namada_tx::{IndexedTx, IndexedTxRange};
+
+use super::utils::{
+    append_note_commitments, IndexedNoteData, IndexedNoteEntry, MaspClient,
+};
+use crate::masp::{NoteIndex, WitnessMap};
+
+/// Version of the encoding of [`MaspSnapshot`] instances.
+pub const MASP_SNAPSHOT_VERSION: u8 = 1;
+
+/// The state of the note commitment tree at some block height.
+#[derive(Clone, Debug, BorshSerialize, BorshDeserialize)]
+pub struct MaspSnapshotState {
+    /// The note commitment tree
+    pub tree: CommitmentTree<Node>,
+    /// Maps a shielded tx to the index of its first output note
+    pub note_index: NoteIndex,
+    /// Maps note positions to their witness
+    pub witness_map: WitnessMap,
+}
+
+impl Default for MaspSnapshotState {
+    fn default() -> Self {
+        Self {
+            tree: CommitmentTree::empty(),
+            note_index: NoteIndex::default(),
+            witness_map: HashMap::default(),
+        }
+    }
+}
+
+impl MaspSnapshotState {
+    /// Append the notes of the given shielded transaction to this state.
+    pub fn append(
+        &mut self,
+        indexed_tx: IndexedTx,
+        shielded: &Transaction,
+    ) -> Result<(), eyre::Error> {
+        append_note_commitments(
+            &mut self.tree,
+            &mut self.note_index,
+            &mut self.witness_map,
+            indexed_tx,
+            shielded,
+        )
+    }
+}
+
+/// Metadata identifying the contents of a [`MaspSnapshot`].
+#[derive(Clone, Debug, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct MaspSnapshotHeader {
+    /// The chain the snapshot was taken from
+    pub chain_id: ChainId,
+    /// Height of the state from which the snapshot starts off. Shielded
+    /// transfers are available from the next height onwards.
+    pub base_height: BlockHeight,
+    /// Last height covered by the snapshot
+    pub last_height: BlockHeight,
+}
+
+/// Manifest of an encoded [`MaspSnapshot`], listing the hashes of its blobs.
+#[derive(BorshSerialize, BorshDeserialize)]
+struct Manifest {
+    header: MaspSnapshotHeader,
+    base_state: Hash,
+    blocks: BTreeMap<BlockHeight, Hash>,
+}
+
+/// A snapshot of the shielded transfers committed within a range of block
+/// heights, along with the state of the note commitment tree at the start
+/// of the range.
+#[derive(Clone, Debug)]
+pub struct MaspSnapshot {
+    header: MaspSnapshotHeader,
+    base_state: MaspSnapshotState,
+    txs: IndexedNoteData,
+}
+
+impl MaspSnapshot {
+    /// Create a new snapshot covering the heights in the range
+    /// `(base_height, last_height]`, given the state of the note
+    /// commitment tree at `base_height`.
+    pub fn new(
+        header: MaspSnapshotHeader,
+        base_state: MaspSnapshotState,
+        txs: IndexedNoteData,
+    ) -> Result<Self, eyre::Error> {
+        if header.base_height >= header.last_height {
+            return Err(eyre!(
+                "Invalid MASP snapshot range: base height {} must be lower \
+                 than the last height {}",
+                header.base_height,
+                header.last_height
+            ));
+        }
+        if let Some((itx, _)) = txs.iter().find(|(itx, _)| {
+            itx.height <= header.base_height || itx.height > header.last_height
+        }) {
+            return Err(eyre!(
+                "MASP transaction at height {} is out of the snapshot range \
+                 ({}, {}]",
+                itx.height,
+                header.base_height,
+                header.last_height
+            ));
+        }
+        Ok(Self {
+            header,
+            base_state,
+            txs,
+        })
+    }
+
+    /// Build a snapshot of the heights in the inclusive range `[from, to]`
+    /// with data fetched from the given client.
+    ///
+    /// If the client is unable to fetch pre-built commitment trees, all
+    /// the shielded transfers preceding `from` are fetched as well, in
+    /// order to rebuild the state of the note commitment tree.
+    pub async fn fetch<M: MaspClient>(
+        client: &M,
+        chain_id: ChainId,
+        from: BlockHeight,
+        to: BlockHeight,
+        block_batch_size: usize,
+        max_concurrent_fetches: usize,
+    ) -> Result<Self, eyre::Error> {
+        if from == BlockHeight(0) || from > to {
+            return Err(eyre!(
+                "Invalid block range {from}-{to} for the MASP snapshot"
+            ));
+        }
+        let base_height = BlockHeight(from.0 - 1);
+
+        let capabilities = client.capabilities();
+        let use_pre_built_state = base_height > BlockHeight(0)
+            && capabilities.may_fetch_pre_built_tree()
+            && capabilities.may_fetch_pre_built_notes_index()
+            && capabilities.may_fetch_pre_built_witness_map();
+
+        let mut base_state = MaspSnapshotState::default();
+        let fetch_from = if use_pre_built_state {
+            base_state = MaspSnapshotState {
+                tree: client
+                    .fetch_commitment_tree(base_height)
+                    .await
+                    .wrap_err("Failed to fetch commitment tree")?,
+                note_index: client
+                    .fetch_note_index(base_height)
+                    .await
+                    .wrap_err("Failed to fetch note index")?,
+                witness_map: client
+                    .fetch_witness_map(base_height)
+                    .await
+                    .wrap_err("Failed to fetch witness map")?,
+            };
+            from
+        } else {
+            BlockHeight::first()
+        };
+
+        let batch_size = block_batch_size.max(1) as u64;
+        let ranges = (fetch_from.0..=to.0).step_by(batch_size as usize).map(
+            |batch_from| {
+                let batch_to = (batch_from + batch_size - 1).min(to.0);
+                (BlockHeight(batch_from), BlockHeight(batch_to))
+            },
+        );
+        let mut fetches = stream::iter(ranges)
+            .map(|(from, to)| async move {
+                client
+                    .fetch_shielded_transfers(from, to)
+                    .await
+                    .wrap_err_with(|| {
+                        format!(
+                            "Failed to fetch shielded transfers in the height \
+                             range {from}-{to}"
+                        )
+                    })
+            })
+            .buffered(max_concurrent_fetches.max(1));
+
+        let mut fetched = IndexedNoteData::new();
+        while let Some(batch) = fetches.next().await {
+            fetched.extend(batch?);
+        }
+
+        let mut txs = IndexedNoteData::new();
+        for (itx, tx) in fetched {
+            if itx.height <= base_height {
+                base_state.append(itx, &tx)?;
+            } else {
+                txs.insert(itx, tx);
+            }
+        }
+
+        Self::new(
+            MaspSnapshotHeader {
+                chain_id,
+                base_height,
+                last_height: to,
+            },
+            base_state,
+            txs,
+        )
+    }
+
+    /// Return the header of this snapshot.
+    pub fn header(&self) -> &MaspSnapshotHeader {
+        &self.header
+    }
+
+    /// Return the number of shielded transfers in this snapshot.
+    pub fn len(&self) -> usize {
+        self.txs.len()
+    }
+
+    /// Check if this snapshot contains no shielded transfers.
+    pub fn is_empty(&self) -> bool {
+        self.txs.is_empty()
+    }
+
+    /// Check if the given height is covered by this snapshot.
+    pub fn contains_height(&self, height: BlockHeight) -> bool {
+        height > self.header.base_height && height <= self.header.last_height
+    }
+
+    /// Iterate over the shielded transfers in the inclusive range of
+    /// heights `[from, to]`.
+    pub fn shielded_transfers(
+        &self,
+        from: BlockHeight,
+        to: BlockHeight,
+    ) -> impl Iterator<Item = IndexedNoteEntry> + '_ {
+        self.txs
+            .range(IndexedTxRange::between_heights(from, to))
+            .map(|(itx, tx)| (*itx, tx.clone()))
+    }
+
+    /// Compute the state of the note commitment tree at the given height,
+    /// by applying the shielded transfers in the snapshot on top of its
+    /// base state.
+    pub fn state_at(
+        &self,
+        height: BlockHeight,
+    ) -> Result<MaspSnapshotState, eyre::Error> {
+        if height < self.header.base_height || height > self.header.last_height
+        {
+            return Err(eyre!(
+                "Height {height} is not covered by the MASP snapshot, which \
+                 spans the heights {}-{}",
+                self.header.base_height,
+                self.header.last_height
+            ));
+        }
+        let mut state = self.base_state.clone();
+        for (itx, tx) in self.txs.range(..=IndexedTx::entire_block(height)) {
+            state.append(*itx, tx)?;
+        }
+        Ok(state)
+    }
+
+    /// Compute the id of this snapshot, i.e. the hash of its manifest.
+    pub fn id(&self) -> Hash {
+        let (manifest, _) = self.manifest_and_blobs();
+        Hash::sha256(manifest.serialize_to_vec())
+    }
+
+    fn manifest_and_blobs(&self) -> (Manifest, BTreeMap<Hash, Vec<u8>>) {
+        let mut blobs = BTreeMap::new();
+        let mut add_blob = |bytes: Vec<u8>| {
+            let hash = Hash::sha256(&bytes);
+            blobs.insert(hash, bytes);
+            hash
+        };
+
+        let base_state = add_blob(self.base_state.serialize_to_vec());
+
+        let mut per_block: BTreeMap<BlockHeight, Vec<IndexedNoteEntry>> =
+            BTreeMap::new();
+        for (itx, tx) in &self.txs {
+            per_block
+                .entry(itx.height)
+                .or_default()
+                .push((*itx, tx.clone()));
+        }
+        let blocks = per_block
+            .into_iter()
+            .map(|(height, entries)| {
+                (height, add_blob(entries.serialize_to_vec()))
+            })
+            .collect();
+
+        (
+            Manifest {
+                header: self.header.clone(),
+                base_state,
+                blocks,
+            },
+            blobs,
+        )
+    }
+
+    /// Encode this snapshot.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let (manifest, blobs) = self.manifest_and_blobs();
+        (MASP_SNAPSHOT_VERSION, manifest, blobs).serialize_to_vec()
+    }
+
+    /// Read the version and manifest of an encoded snapshot, and return its
+    /// header. The blobs of the snapshot are neither read nor validated.
+    pub fn read_header<R: Read>(
+        reader: &mut R,
+    ) -> Result<MaspSnapshotHeader, eyre::Error> {
+        read_version(reader)?;
+        let manifest = Manifest::deserialize_reader(reader)
+            .wrap_err("Failed to decode the MASP snapshot manifest")?;
+        Ok(manifest.header)
+    }
+
+    /// Decode a snapshot, validating all of its blobs against the hashes in
+    /// its manifest.
+    pub fn try_from_bytes(mut bytes: &[u8]) -> Result<Self, eyre::Error> {
+        let reader = &mut bytes;
+        read_version(reader)?;
+        let (manifest, blobs): (Manifest, BTreeMap<Hash, Vec<u8>>) =
+            BorshDeserialize::deserialize_reader(reader)
+                .wrap_err("Failed to decode the MASP snapshot")?;
+        if !reader.is_empty() {
+            return Err(eyre!(
+                "Unexpected trailing bytes in the MASP snapshot"
+            ));
+        }
+
+        let base_state = MaspSnapshotState::try_from_slice(get_blob(
+            &blobs,
+            &manifest.base_state,
+        )?)
+        .wrap_err("Failed to decode the MASP snapshot base state")?;
+
+        let mut txs = IndexedNoteData::new();
+        for (height, hash) in &manifest.blocks {
+            let entries = Vec::<IndexedNoteEntry>::try_from_slice(get_blob(
+                &blobs, hash,
+            )?)
+            .wrap_err_with(|| {
+                format!(
+                    "Failed to decode the MASP snapshot transactions at \
+                     height {height}"
+                )
+            })?;
+            for (itx, tx) in entries {
+                if itx.height != *height {
+                    return Err(eyre!(
+                        "MASP transaction at height {} found in the snapshot \
+                         blob of height {height}",
+                        itx.height
+                    ));
+                }
+                txs.insert(itx, tx);
+            }
+        }
+
+        Self::new(manifest.header, base_state, txs)
+    }
+}
+
+/// Look up a blob by its hash, checking that its contents match the hash.
+fn get_blob<'blobs>(
+    blobs: &'blobs BTreeMap<Hash, Vec<u8>>,
+    hash: &Hash,
+) -> Result<&'blobs [u8], eyre::Error> {
+    let bytes = blobs
+        .get(hash)
+        .ok_or_else(|| eyre!("Missing blob {hash} from the MASP snapshot"))?;
+    let actual = Hash::sha256(bytes);
+    if actual != *hash {
+        return Err(eyre!(
+            "Corrupted blob in the MASP snapshot: expected hash {hash}, got \
+             {actual}"
+        ));
+    }
+    Ok(bytes)
+}
+
+fn read_version<R: Read>(reader: &mut R) -> Result<(), eyre::Error> {
+    let version = u8::deserialize_reader(reader)
+        .wrap_err("Failed to decode the MASP snapshot version")?;
+    if version != MASP_SNAPSHOT_VERSION {
+        return Err(eyre!(
+            "Unsupported MASP snapshot version {version}, expected \
+             {MASP_SNAPSHOT_VERSION}"
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test_masp_snapshot {
+    use namada_core::storage::TxIndex;
+
+    use super::*;
+    use crate::masp::test_utils::{arbitrary_masp_tx, TestingMaspClient};
+
+    fn itx(height: u64, index: u32) -> IndexedTx {
+        IndexedTx {
+            height: BlockHeight(height),
+            index: TxIndex(index),
+            batch_index: Some(0),
+        }
+    }
+
+    fn header(base_height: u64, last_height: u64) -> MaspSnapshotHeader {
+        MaspSnapshotHeader {
+            chain_id: ChainId("masp-snapshot-test".to_string()),
+            base_height: BlockHeight(base_height),
+            last_height: BlockHeight(last_height),
+        }
+    }
+
+    fn test_snapshot() -> MaspSnapshot {
+        let masp_tx = arbitrary_masp_tx();
+        let txs = [itx(2, 0), itx(2, 1), itx(5, 0)]
+            .into_iter()
+            .map(|itx| (itx, masp_tx.clone()))
+            .collect();
+        MaspSnapshot::new(header(0, 6), MaspSnapshotState::default(), txs)
+            .expect("Test failed")
+    }
+
+    #[test]
+    fn test_snapshot_range_is_validated() {
+        let txs: IndexedNoteData = [(itx(7, 0), arbitrary_masp_tx())].into();
+        assert!(
+            MaspSnapshot::new(header(0, 6), Default::default(), txs).is_err()
+        );
+        assert!(
+            MaspSnapshot::new(header(6, 6), Default::default(), [].into())
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_snapshot_encoding_roundtrip() {
+        let snapshot = test_snapshot();
+        let bytes = snapshot.to_bytes();
+
+        let header = MaspSnapshot::read_header(&mut bytes.as_slice())
+            .expect("Test failed");
+        assert_eq!(&header, snapshot.header());
+
+        let decoded =
+            MaspSnapshot::try_from_bytes(&bytes).expect("Test failed");
+        assert_eq!(decoded.header(), snapshot.header());
+        assert_eq!(decoded.id(), snapshot.id());
+        assert_eq!(
+            decoded
+                .shielded_transfers(BlockHeight(1), BlockHeight(6))
+                .map(|(itx, _)| itx)
+                .collect::<Vec<_>>(),
+            vec![itx(2, 0), itx(2, 1), itx(5, 0)],
+        );
+    }
+
+    #[test]
+    fn test_snapshot_detects_corrupted_blobs() {
+        let snapshot = test_snapshot();
+        let (manifest, mut blobs) = snapshot.manifest_and_blobs();
+        let block_hash = manifest.blocks[&BlockHeight(5)];
+        *blobs
+            .get_mut(&block_hash)
+            .and_then(|blob| blob.last_mut())
+            .expect("Test failed") ^= 1;
+        let bytes = (MASP_SNAPSHOT_VERSION, manifest, blobs).serialize_to_vec();
+        assert!(MaspSnapshot::try_from_bytes(&bytes).is_err());
+
+        let mut bytes = snapshot.to_bytes();
+        bytes[0] = MASP_SNAPSHOT_VERSION + 1;
+        assert!(MaspSnapshot::try_from_bytes(&bytes).is_err());
+    }
+
+    #[test]
+    fn test_snapshot_state_at_height() {
+        let snapshot = test_snapshot();
+        let masp_tx = arbitrary_masp_tx();
+
+        let mut expected = MaspSnapshotState::default();
+        expected.append(itx(2, 0), &masp_tx).expect("Test failed");
+        expected.append(itx(2, 1), &masp_tx).expect("Test failed");
+
+        for height in [2, 3, 4] {
+            let state = snapshot.state_at(BlockHeight(height)).unwrap();
+            assert_eq!(state.note_index, expected.note_index);
+            assert_eq!(state.tree.size(), expected.tree.size());
+            assert_eq!(state.witness_map.len(), expected.witness_map.len());
+        }
+
+        let state = snapshot.state_at(BlockHeight(0)).unwrap();
+        assert!(state.note_index.is_empty());
+        assert!(snapshot.state_at(BlockHeight(7)).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_fetch_snapshot_rebuilds_base_state() {
+        let (client, masp_tx_sender) = TestingMaspClient::new(5.into());
+        let masp_tx = arbitrary_masp_tx();
+        for height in 1..=5 {
+            masp_tx_sender
+                .send(Some((itx(height, 0), masp_tx.clone())))
+                .expect("Test failed");
+        }
+        masp_tx_sender.send(None).expect("Test failed");
+
+        let snapshot = MaspSnapshot::fetch(
+            &client,
+            ChainId("masp-snapshot-test".to_string()),
+            BlockHeight(3),
+            BlockHeight(5),
+            10,
+            1,
+        )
+        .await
+        .expect("Test failed");
+
+        assert_eq!(snapshot.header(), &header(2, 5));
+        assert_eq!(snapshot.len(), 3);
+
+        let base_state = snapshot.state_at(BlockHeight(2)).unwrap();
+        assert_eq!(
+            base_state.note_index.keys().copied().collect::<Vec<_>>(),
+            vec![itx(1, 0), itx(2, 0)],
+        );
+        let last_state = snapshot.state_at(BlockHeight(5)).unwrap();
+        assert_eq!(last_state.note_index.len(), 5);
+    }
+}
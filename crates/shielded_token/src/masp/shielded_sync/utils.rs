@@ -2,6 +2,8 @@
 use std::collections::BTreeMap;
 
 use borsh::{BorshDeserialize, BorshSerialize};
+use eyre::eyre;
+use masp_primitives::ff::PrimeField;
 use masp_primitives::memo::MemoBytes;
 use masp_primitives::merkle_tree::{CommitmentTree, IncrementalWitness};
 use masp_primitives::sapling::{Node, Note, PaymentAddress, ViewingKey};
@@ -10,6 +12,8 @@ use namada_core::chain::BlockHeight;
 use namada_core::collections::HashMap;
 use namada_tx::{IndexedTx, IndexedTxRange};
 
+use crate::masp::{NoteIndex, WitnessMap};
+
 /// Type alias for convenience and profit
 pub type IndexedNoteData = BTreeMap<IndexedTx, Transaction>;
 
@@ -264,6 +268,45 @@ pub trait MaspClient: Clone {
     ) -> Result<HashMap<usize, IncrementalWitness<Node>>, Self::Error>;
 }
 
+/// Append the note commitments of the shielded outputs in `shielded` to
+/// the given commitment tree, updating the note index and the witnesses of
+/// all the notes that were previously added to the tree.
+pub fn append_note_commitments(
+    tree: &mut CommitmentTree<Node>,
+    note_index: &mut NoteIndex,
+    witness_map: &mut WitnessMap,
+    indexed_tx: IndexedTx,
+    shielded: &Transaction,
+) -> Result<(), eyre::Error> {
+    let mut note_pos = tree.size();
+    note_index.insert(indexed_tx, note_pos);
+
+    for so in shielded
+        .sapling_bundle()
+        .map_or(&vec![], |x| &x.shielded_outputs)
+    {
+        // Create merkle tree leaf node from note commitment
+        let node = Node::new(so.cmu.to_repr());
+        // Update each merkle tree in the witness map with the latest
+        // addition
+        for (_, witness) in witness_map.iter_mut() {
+            witness.append(node).map_err(|()| {
+                eyre!("note commitment tree is full".to_string())
+            })?;
+        }
+        tree.append(node)
+            .map_err(|()| eyre!("note commitment tree is full".to_string()))?;
+        // Finally, make it easier to construct merkle paths to this new
+        // note
+        let witness = IncrementalWitness::<Node>::from_tree(tree);
+        witness_map.insert(note_pos, witness);
+        note_pos = note_pos
+            .checked_add(1)
+            .ok_or_else(|| eyre!("note position overflow".to_string()))?;
+    }
+    Ok(())
+}
+
 /// Given a block height range we wish to request and a cache of fetched block
 /// heights, returns the set of sub-ranges we need to request so that all blocks
 /// in the inclusive range `[from, to]` get cached.
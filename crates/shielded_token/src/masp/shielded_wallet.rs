@@ -8,16 +8,15 @@ use masp_primitives::consensus::MainNetwork as Network;
 #[cfg(not(feature = "mainnet"))]
 use masp_primitives::consensus::TestNetwork as Network;
 use masp_primitives::convert::AllowedConversion;
-use masp_primitives::ff::PrimeField;
 use masp_primitives::memo::MemoBytes;
-use masp_primitives::merkle_tree::{
-    CommitmentTree, IncrementalWitness, MerklePath,
-};
+use masp_primitives::merkle_tree::{CommitmentTree, MerklePath};
 use masp_primitives::sapling::{
     Diversifier, Node, Note, Nullifier, ViewingKey,
 };
 use masp_primitives::transaction::builder::Builder;
-use masp_primitives::transaction::components::sapling::builder::BuildParams;
+use masp_primitives::transaction::components::sapling::builder::{
+    BuildParams, SaplingMetadata,
+};
 use masp_primitives::transaction::components::{
     I128Sum, TxOut, U64Sum, ValueSum,
 };
@@ -48,17 +47,45 @@ use namada_wallet::{DatedKeypair, DatedSpendingKey};
 use rand::prelude::StdRng;
 use rand_core::{OsRng, SeedableRng};
 
-use crate::masp::utils::MaspClient;
+use crate::masp::prover::{ExternalTxProver, MaspProver};
+use crate::masp::utils::{append_note_commitments, MaspClient};
 use crate::masp::{
-    cloned_pair, ContextSyncStatus, Conversions, MaspAmount, MaspDataLogEntry,
-    MaspFeeData, MaspSourceTransferData, MaspTargetTransferData,
-    MaspTransferData, MaspTxReorderedData, NoteIndex, ShieldedSyncConfig,
-    ShieldedTransfer, ShieldedUtils, SpentNotesTracker, TransferErr, WalletMap,
-    WitnessMap, NETWORK,
+    cloned_pair, to_viewing_key, ContextSyncStatus, Conversions, MaspAmount,
+    MaspDataLogEntry, MaspExtendedSpendingKey, MaspFeeData,
+    MaspSourceTransferData, MaspTargetTransferData, MaspTransferData,
+    MaspTxReorderedData, NoteIndex, ShieldedSyncConfig, ShieldedTransfer,
+    ShieldedUtils, SpentNotesTracker, TransferErr, WalletMap, WitnessMap,
+    NETWORK,
 };
 #[cfg(any(test, feature = "testing"))]
 use crate::masp::{testing, ENV_VAR_MASP_TEST_SEED};
 
+/// Register the given keys in the key sync cursors. The cursors of the keys
+/// are moved up to their birthdays, as no notes can be found before them.
+pub(crate) fn register_key_birthdays(
+    vk_heights: &mut BTreeMap<ViewingKey, Option<IndexedTx>>,
+    sks: &[DatedSpendingKey],
+    fvks: &[DatedKeypair<ViewingKey>],
+) {
+    for vk in sks
+        .iter()
+        .map(|esk| {
+            esk.map(|k| to_viewing_key(&MaspExtendedSpendingKey::from(k)).vk)
+        })
+        .chain(fvks.iter().copied())
+    {
+        if let Some(h) = vk_heights.entry(vk.key).or_default() {
+            let birthday = IndexedTx::entire_block(vk.birthday);
+            if birthday > *h {
+                *h = birthday;
+            }
+        } else if vk.birthday >= BlockHeight::first() {
+            vk_heights
+                .insert(vk.key, Some(IndexedTx::entire_block(vk.birthday)));
+        }
+    }
+}
+
 /// Represents the current state of the shielded pool from the perspective of
 /// the chosen viewing keys.
 #[derive(BorshSerialize, BorshDeserialize, Debug)]
@@ -148,40 +175,26 @@ impl<U: ShieldedUtils + MaybeSend + MaybeSync> ShieldedWallet<U> {
         indexed_tx: IndexedTx,
         shielded: &Transaction,
     ) -> Result<(), eyre::Error> {
-        let mut note_pos = self.tree.size();
-        self.note_index.insert(indexed_tx, note_pos);
-
-        for so in shielded
-            .sapling_bundle()
-            .map_or(&vec![], |x| &x.shielded_outputs)
-        {
-            // Create merkle tree leaf node from note commitment
-            let node = Node::new(so.cmu.to_repr());
-            // Update each merkle tree in the witness map with the latest
-            // addition
-            for (_, witness) in self.witness_map.iter_mut() {
-                witness.append(node).map_err(|()| {
-                    eyre!("note commitment tree is full".to_string())
-                })?;
-            }
-            self.tree.append(node).map_err(|()| {
-                eyre!("note commitment tree is full".to_string())
-            })?;
-            // Finally, make it easier to construct merkle paths to this new
-            // note
-            let witness = IncrementalWitness::<Node>::from_tree(&self.tree);
-            self.witness_map.insert(note_pos, witness);
-            note_pos = checked!(note_pos + 1).unwrap();
-        }
-        Ok(())
+        append_note_commitments(
+            &mut self.tree,
+            &mut self.note_index,
+            &mut self.witness_map,
+            indexed_tx,
+            shielded,
+        )
     }
 
     /// Sync the current state of the multi-asset shielded pool in a
     /// ShieldedContext with the state on-chain.
+    ///
+    /// Every key is synced from its own cursor in `vk_heights`, unless a
+    /// `start_query_height` is given. The keys whose cursors lag behind it are
+    /// then left as they are, to be caught up by a later sync.
     pub async fn sync<M, T, I>(
         &mut self,
         env: impl TaskEnvironment,
         config: ShieldedSyncConfig<M, T, I>,
+        start_query_height: Option<BlockHeight>,
         last_query_height: Option<BlockHeight>,
         sks: &[DatedSpendingKey],
         fvks: &[DatedKeypair<ViewingKey>],
@@ -194,8 +207,9 @@ impl<U: ShieldedUtils + MaybeSend + MaybeSync> ShieldedWallet<U> {
         env.run(|spawner| async move {
             let dispatcher = config.dispatcher(spawner, &self.utils).await;
 
-            if let Some(updated_ctx) =
-                dispatcher.run(None, last_query_height, sks, fvks).await?
+            if let Some(updated_ctx) = dispatcher
+                .run(start_query_height, last_query_height, sks, fvks)
+                .await?
             {
                 *self = updated_ctx;
             }
@@ -221,6 +235,24 @@ impl<U: ShieldedUtils + MaybeSend + MaybeSync> ShieldedWallet<U> {
             .map_or_else(BlockHeight::first, |itx| itx.height))
     }
 
+    /// Get the height from which the most synced keys resume syncing, if
+    /// some other keys lag behind them, e.g. keys added with an old
+    /// birthday. The given keys are accounted for with their birthdays.
+    pub fn leading_height_to_sync_from(
+        &self,
+        sks: &[DatedSpendingKey],
+        fvks: &[DatedKeypair<ViewingKey>],
+    ) -> Option<BlockHeight> {
+        let mut vk_heights = self.vk_heights.clone();
+        register_key_birthdays(&mut vk_heights, sks, fvks);
+        let heights = vk_heights.values().map(|h| {
+            h.as_ref().map_or_else(BlockHeight::first, |itx| itx.height)
+        });
+        let least_synced = heights.clone().min()?;
+        let most_synced = heights.max()?;
+        (least_synced < most_synced).then_some(most_synced)
+    }
+
     #[allow(missing_docs)]
     pub fn save_decrypted_shielded_outputs(
         &mut self,
@@ -1157,6 +1189,7 @@ pub trait ShieldedApi<U: ShieldedUtils + MaybeSend + MaybeSync>:
         fee_data: Option<MaspFeeData>,
         expiration: Option<DateTimeUtc>,
         bparams: &mut impl BuildParams,
+        prover: Option<&dyn MaspProver>,
     ) -> Result<Option<ShieldedTransfer>, TransferErr> {
         // Determine epoch in which to submit potential shielded transaction
         let epoch = Self::query_masp_epoch(context.client())
@@ -1337,18 +1370,22 @@ pub trait ShieldedApi<U: ShieldedUtils + MaybeSend + MaybeSync>:
 
         let builder_clone = builder.clone().map_builder(WalletMap);
         // Build and return the constructed transaction
-        #[cfg(not(feature = "testing"))]
-        let prover = self.utils.local_tx_prover();
-        #[cfg(feature = "testing")]
-        let prover = testing::MockTxProver(std::sync::Mutex::new(OsRng));
-        let (masp_tx, metadata) = builder
-            .build(
-                &prover,
-                &FeeRule::non_standard(U64Sum::zero()),
-                &mut rng,
-                bparams,
-            )
-            .map_err(|error| TransferErr::Build { error })?;
+        let (masp_tx, metadata) = if let Some(prover) = prover {
+            Self::build_with_masp_prover(builder, prover, rng, bparams).await?
+        } else {
+            #[cfg(not(feature = "testing"))]
+            let prover = self.utils.local_tx_prover();
+            #[cfg(feature = "testing")]
+            let prover = testing::MockTxProver(std::sync::Mutex::new(OsRng));
+            builder
+                .build(
+                    &prover,
+                    &FeeRule::non_standard(U64Sum::zero()),
+                    &mut rng,
+                    bparams,
+                )
+                .map_err(|error| TransferErr::Build { error })?
+        };
 
         Ok(Some(ShieldedTransfer {
             builder: builder_clone,
@@ -1358,6 +1395,52 @@ pub trait ShieldedApi<U: ShieldedUtils + MaybeSend + MaybeSync>:
         }))
     }
 
+    /// Build a MASP transaction whose proofs are generated by the given
+    /// [`MaspProver`]. The transaction is built twice from the same
+    /// randomness: once to collect the inputs of its circuits, and once
+    /// more to fill in the proofs obtained from the prover.
+    #[allow(async_fn_in_trait)]
+    async fn build_with_masp_prover(
+        builder: Builder<Network, PseudoExtendedKey>,
+        prover: &dyn MaspProver,
+        rng: StdRng,
+        bparams: &mut impl BuildParams,
+    ) -> Result<(Transaction, SaplingMetadata), TransferErr> {
+        let collector = ExternalTxProver::collect(OsRng);
+        builder
+            .clone()
+            .build(
+                &collector,
+                &FeeRule::non_standard(U64Sum::zero()),
+                &mut rng.clone(),
+                bparams,
+            )
+            .map_err(|error| TransferErr::Build { error })?;
+        let requests = collector.into_requests();
+
+        let proofs = prover.prove(requests.clone()).await.map_err(|err| {
+            TransferErr::General(format!(
+                "Failed to prove MASP transaction: {err}"
+            ))
+        })?;
+
+        let replayer = ExternalTxProver::replay(OsRng, requests, proofs)
+            .map_err(|err| TransferErr::General(err.to_string()))?;
+        let built = builder
+            .build(
+                &replayer,
+                &FeeRule::non_standard(U64Sum::zero()),
+                &mut rng.clone(),
+                bparams,
+            )
+            .map_err(|error| TransferErr::Build { error })?;
+        replayer
+            .check_replay()
+            .map_err(|err| TransferErr::General(err.to_string()))?;
+
+        Ok(built)
+    }
+
     /// Either get the denomination from the cache or query it
     #[allow(async_fn_in_trait)]
     async fn get_denom(
@@ -0,0 +1,313 @@
+//! Read-only view of the committed state at a past block height.
+
+use std::cell::{Cell, RefCell};
+use std::collections::BTreeMap;
+
+use namada_core::address::Address;
+use namada_core::chain::{BlockHeader, BlockHeight, ChainId, Epoch, Epochs};
+use namada_core::storage::{self, TxIndex};
+use namada_storage::{
+    DBIter, DbError, ResultExt, StorageHasher, StorageRead, DB,
+};
+
+use crate::{PrefixIter, WlState};
+
+/// The maximum number of blocks that a prefix can be iterated in the past of
+/// the last committed height. Iterating at a past height replays the diffs
+/// of every block since then, so it is capped regardless of the configured
+/// `storage_read_past_height_limit`.
+pub const MAX_PAST_HEIGHT_PREFIX_ITER_BLOCKS: u64 = 10_000;
+
+/// A read-only view of the state committed at some block height. At the last
+/// committed height, the view reads the state like [`WlState`] does. At past
+/// heights, the values that changed since then are read from the diffs, so the
+/// block history at the height must not have been pruned. The values of the
+/// keys that are not stored to diffs are only known at the last height, hence
+/// reading them at past heights fails.
+#[derive(Debug)]
+pub struct HistoricalState<'a, D, H>
+where
+    D: DB + for<'iter> DBIter<'iter>,
+    H: StorageHasher,
+{
+    state: &'a WlState<D, H>,
+    height: BlockHeight,
+    read_log: Option<&'a ReadLog>,
+}
+
+/// Log of the values read through a [`HistoricalState`], e.g. to prove the
+/// storage reads that a query response was computed from. Absent values are
+/// logged as `None`. The entries yielded by prefix iterators are logged, but
+/// not the absence of other keys with the prefix, so the log also records
+/// whether any prefix was iterated, in which case the logged values don't
+/// prove the reads.
+#[derive(Debug, Default)]
+pub struct ReadLog {
+    values: RefCell<BTreeMap<storage::Key, Option<Vec<u8>>>>,
+    has_prefix_iter: Cell<bool>,
+}
+
+impl ReadLog {
+    fn log(&self, key: &storage::Key, value: Option<&[u8]>) {
+        self.values
+            .borrow_mut()
+            .entry(key.clone())
+            .or_insert_with(|| value.map(<[u8]>::to_vec));
+    }
+
+    /// Check if a prefix was iterated. The completeness of the entries
+    /// yielded by an iterator cannot be proven.
+    pub fn has_prefix_iter(&self) -> bool {
+        self.has_prefix_iter.get()
+    }
+
+    /// Get the logged values, ordered by their keys
+    pub fn into_values(self) -> BTreeMap<storage::Key, Option<Vec<u8>>> {
+        self.values.into_inner()
+    }
+}
+
+/// Prefix iterator of a [`HistoricalState`]
+#[derive(Debug)]
+pub enum HistoricalPrefixIter<'iter, D>
+where
+    D: DB + DBIter<'iter>,
+{
+    /// Iterator of the state at the last committed height
+    Latest(PrefixIter<'iter, D>),
+    /// Key-value pairs read at a past height
+    Past(std::vec::IntoIter<(String, Vec<u8>)>),
+}
+
+impl<'a, D, H> HistoricalState<'a, D, H>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter>,
+    H: 'static + StorageHasher,
+{
+    /// Create a view of the state at the given height. Heights past the last
+    /// committed one are read at the last committed height.
+    pub fn new(state: &'a WlState<D, H>, height: BlockHeight) -> Self {
+        let height =
+            std::cmp::min(height, state.in_mem().get_last_block_height());
+        Self {
+            state,
+            height,
+            read_log: None,
+        }
+    }
+
+    /// Log the values read through the view in the given log, if any
+    pub fn with_read_log(mut self, read_log: Option<&'a ReadLog>) -> Self {
+        self.read_log = read_log;
+        self
+    }
+
+    /// The height at which the state is read
+    pub fn height(&self) -> BlockHeight {
+        self.height
+    }
+
+    fn is_latest(&self) -> bool {
+        self.height == self.state.in_mem().get_last_block_height()
+    }
+
+    /// Fail if the value of the given key is not known at the past height of
+    /// the view, as it is not stored to diffs
+    fn check_diff_storable(
+        &self,
+        key: &storage::Key,
+    ) -> namada_storage::Result<()> {
+        if (self.state.diff_key_filter)(key) {
+            return Ok(());
+        }
+        Err(namada_storage::Error::new_alloc(format!(
+            "The key {key} is not stored to diffs, hence it cannot be read at \
+             the past height {}",
+            self.height
+        )))
+    }
+
+    /// Read all the key-value pairs with the given prefix at a past height.
+    /// The keys present at that height are either still present or were
+    /// deleted later, in which case their value is an old diff.
+    fn read_prefix_at_past_height(
+        &self,
+        prefix: &storage::Key,
+    ) -> namada_storage::Result<Vec<(String, Vec<u8>)>> {
+        let db = self.state.db();
+        if let Some(pruned_height) =
+            db.read_pruned_height().into_storage_result()?
+        {
+            if self.height <= pruned_height {
+                return Err(DbError::Pruned {
+                    height: self.height,
+                    oldest: pruned_height.next_height(),
+                })
+                .into_storage_result();
+            }
+        }
+
+        let last_height = self.state.in_mem().get_last_block_height();
+        if last_height.0.saturating_sub(self.height.0)
+            > MAX_PAST_HEIGHT_PREFIX_ITER_BLOCKS
+        {
+            return Err(namada_storage::Error::new_alloc(format!(
+                "Cannot iterate a prefix more than \
+                 {MAX_PAST_HEIGHT_PREFIX_ITER_BLOCKS} blocks in the past of \
+                 the last committed height {last_height}, at the height {}",
+                self.height
+            )));
+        }
+
+        // The value of a key at the height is set by the first change after
+        // it: an old diff holds the value, while a new diff without an old one
+        // means that the key was created later
+        let mut values: BTreeMap<String, Option<Vec<u8>>> = BTreeMap::new();
+        let mut height = self.height;
+        while height < last_height {
+            height = height.next_height();
+            let old_diffs: BTreeMap<String, Vec<u8>> = db
+                .iter_old_diffs(height, Some(prefix))
+                .map(|(key, value, _gas)| (key, value))
+                .collect();
+            for (key, _value, _gas) in db.iter_new_diffs(height, Some(prefix)) {
+                if !old_diffs.contains_key(&key) {
+                    values.entry(key).or_insert(None);
+                }
+            }
+            for (key, value) in old_diffs {
+                values.entry(key).or_insert(Some(value));
+            }
+        }
+        // The keys that didn't change since the height have their latest value
+        for (key, value, _gas) in db.iter_prefix(Some(prefix)) {
+            values.entry(key).or_insert(Some(value));
+        }
+
+        let mut entries = Vec::with_capacity(values.len());
+        for (key, value) in values {
+            let storage_key =
+                storage::Key::parse(&key).into_storage_result()?;
+            self.check_diff_storable(&storage_key)?;
+            if let Some(value) = value {
+                entries.push((key, value));
+            }
+        }
+        Ok(entries)
+    }
+}
+
+impl<D, H> StorageRead for HistoricalState<'_, D, H>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter>,
+    H: 'static + StorageHasher,
+{
+    type PrefixIter<'iter>
+        = HistoricalPrefixIter<'iter, D>
+    where
+        Self: 'iter;
+
+    fn read_bytes(
+        &self,
+        key: &storage::Key,
+    ) -> namada_storage::Result<Option<Vec<u8>>> {
+        let value = if self.is_latest() {
+            self.state.read_bytes(key)?
+        } else {
+            self.check_diff_storable(key)?;
+            let (value, _gas) = self
+                .state
+                .db_read_with_height(key, self.height)
+                .into_storage_result()?;
+            value
+        };
+        if let Some(read_log) = self.read_log {
+            read_log.log(key, value.as_deref());
+        }
+        Ok(value)
+    }
+
+    fn has_key(&self, key: &storage::Key) -> namada_storage::Result<bool> {
+        // The value is read to be logged
+        if self.is_latest() && self.read_log.is_none() {
+            return self.state.has_key(key);
+        }
+        Ok(self.read_bytes(key)?.is_some())
+    }
+
+    fn iter_prefix<'iter>(
+        &'iter self,
+        prefix: &storage::Key,
+    ) -> namada_storage::Result<Self::PrefixIter<'iter>> {
+        if let Some(read_log) = self.read_log {
+            read_log.has_prefix_iter.set(true);
+        }
+        if self.is_latest() {
+            return Ok(HistoricalPrefixIter::Latest(
+                self.state.iter_prefix(prefix)?,
+            ));
+        }
+        Ok(HistoricalPrefixIter::Past(
+            self.read_prefix_at_past_height(prefix)?.into_iter(),
+        ))
+    }
+
+    fn iter_next<'iter>(
+        &'iter self,
+        iter: &mut Self::PrefixIter<'iter>,
+    ) -> namada_storage::Result<Option<(String, Vec<u8>)>> {
+        let next = match iter {
+            HistoricalPrefixIter::Latest(iter) => self.state.iter_next(iter)?,
+            HistoricalPrefixIter::Past(iter) => iter.next(),
+        };
+        if let (Some(read_log), Some((key, value))) = (self.read_log, &next) {
+            let key = storage::Key::parse(key).into_storage_result()?;
+            read_log.log(&key, Some(value));
+        }
+        Ok(next)
+    }
+
+    fn get_chain_id(&self) -> namada_storage::Result<ChainId> {
+        self.state.get_chain_id()
+    }
+
+    fn get_block_height(&self) -> namada_storage::Result<BlockHeight> {
+        Ok(self.height)
+    }
+
+    fn get_block_header(
+        &self,
+        height: BlockHeight,
+    ) -> namada_storage::Result<Option<BlockHeader>> {
+        self.state.get_block_header(height)
+    }
+
+    fn get_block_epoch(&self) -> namada_storage::Result<Epoch> {
+        if self.is_latest() {
+            return Ok(self.state.in_mem().last_epoch);
+        }
+        self.state
+            .in_mem()
+            .block
+            .pred_epochs
+            .get_epoch(self.height)
+            .ok_or_else(|| {
+                namada_storage::Error::new_alloc(format!(
+                    "No epoch found for the block height {}",
+                    self.height
+                ))
+            })
+    }
+
+    fn get_pred_epochs(&self) -> namada_storage::Result<Epochs> {
+        self.state.get_pred_epochs()
+    }
+
+    fn get_tx_index(&self) -> namada_storage::Result<TxIndex> {
+        self.state.get_tx_index()
+    }
+
+    fn get_native_token(&self) -> namada_storage::Result<Address> {
+        self.state.get_native_token()
+    }
+}
@@ -18,8 +18,10 @@ use namada_storage::tx_queue::ExpiredTxsQueue;
 use namada_storage::types::CommitOnlyData;
 use namada_storage::{
     BlockHeader, BlockHeight, BlockResults, Epoch, Epochs, EthEventsQueue, Key,
-    KeySeg, StorageHasher, TxIndex, EPOCH_TYPE_LENGTH,
+    KeySeg, PruningMode, StorageHasher, TxIndex, DEFAULT_ROLLBACK_WINDOW,
+    EPOCH_TYPE_LENGTH,
 };
+use serde::{Deserialize, Serialize};
 
 use crate::Result;
 
@@ -74,6 +76,14 @@ where
     pub eth_events_queue: EthEventsQueue,
     /// How many block heights in the past can the storage be queried
     pub storage_read_past_height_limit: Option<u64>,
+    /// How much of the block history is kept in the DB
+    pub pruning_mode: PruningMode,
+    /// How many of the most recent blocks can be rolled back
+    pub rollback_window: u64,
+    /// How the validity predicates of a transaction are evaluated
+    pub vp_evaluation: VpEvaluation,
+    /// How the transaction batches of a block are executed
+    pub tx_batch_execution: TxBatchExecution,
     /// Data that needs to be committed to the merkle tree
     pub commit_only_data: CommitOnlyData,
     /// Cache of the results of process proposal for the next height to decide.
@@ -86,6 +96,37 @@ where
     pub block_proposals_cache: CLruCache<Hash, ProcessProposalCachedResult>,
 }
 
+/// How the validity predicates triggered by a transaction are evaluated. The
+/// VPs only read the same pre and post state, so the outcome and the gas
+/// consumed are the same in both modes.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum VpEvaluation {
+    /// Evaluate the VPs one after another
+    #[default]
+    Sequential,
+    /// Evaluate the VPs concurrently on the global worker pool
+    Parallel,
+}
+
+/// How the transaction batches of a block are executed. The batches executed
+/// in parallel that conflict with the ones before them are executed again in
+/// order, so the outcome is the same in both modes.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum TxBatchExecution {
+    /// Execute the batches one after another
+    #[default]
+    Sequential,
+    /// Execute the batches speculatively on the global worker pool, then
+    /// commit them in order
+    Parallel,
+}
+
 /// Last committed block
 #[derive(Clone, Debug, BorshSerialize, BorshDeserialize, BorshDeserializer)]
 pub struct LastBlock {
@@ -164,6 +205,10 @@ where
             ethereum_height: None,
             eth_events_queue: EthEventsQueue::default(),
             storage_read_past_height_limit,
+            pruning_mode: PruningMode::default(),
+            rollback_window: DEFAULT_ROLLBACK_WINDOW,
+            vp_evaluation: VpEvaluation::default(),
+            tx_batch_execution: TxBatchExecution::default(),
             commit_only_data: CommitOnlyData::default(),
             block_proposals_cache: CLruCache::new(
                 NonZeroUsize::new(10).unwrap(),
@@ -17,6 +17,7 @@
     clippy::print_stderr
 )]
 
+mod historical;
 mod host_env;
 mod in_memory;
 pub mod prefix_iter;
@@ -26,9 +27,14 @@ pub mod write_log;
 use std::fmt::Debug;
 use std::iter::Peekable;
 
+pub use historical::{
+    HistoricalPrefixIter, HistoricalState, ReadLog,
+    MAX_PAST_HEIGHT_PREFIX_ITER_BLOCKS,
+};
 pub use host_env::{TxHostEnvState, VpHostEnvState};
 pub use in_memory::{
     BlockStorage, InMemory, LastBlock, ProcessProposalCachedResult,
+    TxBatchExecution, VpEvaluation,
 };
 use namada_core::address::Address;
 use namada_core::arith::checked;
@@ -60,8 +66,8 @@ pub use namada_storage::types::{KVBytes, PatternIterator, PrefixIterator};
 pub use namada_storage::{
     collections, iter_prefix, iter_prefix_bytes, iter_prefix_with_filter,
     mockdb, tx_queue, BlockStateRead, BlockStateWrite, DBIter, DBWriteBatch,
-    DbError, DbResult, Error, OptionExt, Result, ResultExt, StorageHasher,
-    StorageRead, StorageWrite, DB,
+    DbError, DbResult, Error, OptionExt, PruningMode, Result, ResultExt,
+    StorageHasher, StorageRead, StorageWrite, DB, DEFAULT_ROLLBACK_WINDOW,
 };
 use namada_systems::parameters;
 use thiserror::Error;
@@ -202,7 +208,7 @@ pub trait State: StateRead + StorageWrite {
     /// reference to the `InMemory` state and DB when in need of both (avoids
     /// complain from the borrow checker)
     fn split_borrow(&mut self)
-    -> (&mut WriteLog, &InMemory<Self::H>, &Self::D);
+        -> (&mut WriteLog, &InMemory<Self::H>, &Self::D);
 
     /// Write the provided tx hash to write log.
     fn write_tx_hash(&mut self, hash: Hash) -> write_log::Result<()> {
@@ -591,6 +597,7 @@ where
 pub mod testing {
 
     use std::num::NonZeroUsize;
+    use std::sync::Arc;
 
     use clru::CLruCache;
     use namada_core::address;
@@ -610,8 +617,8 @@ pub mod testing {
         fn default() -> Self {
             Self(WlState {
                 write_log: Default::default(),
-                db: MockDB::default(),
-                in_mem: Default::default(),
+                db: Arc::new(MockDB::default()),
+                in_mem: Arc::new(Default::default()),
                 diff_key_filter: diff_all_keys,
             })
         }
@@ -655,6 +662,10 @@ pub mod testing {
                 ethereum_height: None,
                 eth_events_queue: EthEventsQueue::default(),
                 storage_read_past_height_limit: Some(1000),
+                pruning_mode: PruningMode::default(),
+                rollback_window: DEFAULT_ROLLBACK_WINDOW,
+                vp_evaluation: VpEvaluation::default(),
+                tx_batch_execution: TxBatchExecution::default(),
                 commit_only_data: CommitOnlyData::default(),
                 block_proposals_cache: CLruCache::new(
                     NonZeroUsize::new(10).unwrap(),
@@ -895,6 +906,59 @@ mod tests {
         key == &test_key_1()
     }
 
+    /// Test that the block history to prune keeps the blocks of the current
+    /// epoch and at least the blocks in the rollback window.
+    #[test]
+    fn test_block_history_to_prune() {
+        let mut state = TestState::default();
+        let in_mem = state.in_mem_mut();
+        in_mem.last_block = Some(LastBlock {
+            height: BlockHeight(100),
+            time: DateTimeUtc::unix_epoch(),
+        });
+        in_mem.block.epoch = Epoch(1);
+        in_mem.block.pred_epochs.first_block_heights =
+            vec![BlockHeight(1), BlockHeight(90)];
+        // Nothing is pruned in the archive mode
+        assert_eq!(state.block_history_to_prune().unwrap(), None);
+
+        // The number of blocks to keep is clamped to the rollback window
+        state.in_mem_mut().pruning_mode = PruningMode::Pruned {
+            keep_recent: 5,
+            prune_headers: false,
+        };
+        state.in_mem_mut().rollback_window = 20;
+        assert_eq!(
+            state.block_history_to_prune().unwrap(),
+            Some((BlockHeight(1), BlockHeight(80)))
+        );
+
+        // The blocks of the current epoch are kept
+        state.in_mem_mut().rollback_window = 1;
+        assert_eq!(
+            state.block_history_to_prune().unwrap(),
+            Some((BlockHeight(1), BlockHeight(89)))
+        );
+        state.in_mem_mut().pruning_mode = PruningMode::Minimal;
+        assert_eq!(
+            state.block_history_to_prune().unwrap(),
+            Some((BlockHeight(1), BlockHeight(89)))
+        );
+
+        // The pruning resumes after the pruned heights
+        state
+            .prune_block_history(BlockHeight(1), BlockHeight(50))
+            .unwrap();
+        assert_eq!(
+            state.block_history_to_prune().unwrap(),
+            Some((BlockHeight(51), BlockHeight(89)))
+        );
+        state
+            .prune_block_history(BlockHeight(51), BlockHeight(89))
+            .unwrap();
+        assert_eq!(state.block_history_to_prune().unwrap(), None);
+    }
+
     #[test]
     fn test_writing_without_diffs() {
         let mut state = TestState::default();
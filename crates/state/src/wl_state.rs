@@ -1,5 +1,6 @@
 use std::cmp::Ordering;
 use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
 
 use itertools::Either;
 use namada_core::address::Address;
@@ -18,7 +19,8 @@ use namada_storage::conversion_state::{
     ConversionState, ReadConversionState, WithConversionState,
 };
 use namada_storage::{
-    BlockHeight, BlockStateRead, BlockStateWrite, ResultExt, StorageRead,
+    BlockHeight, BlockStateRead, BlockStateWrite, DbError, ResultExt,
+    StorageRead, UnmerklizedBlockState,
 };
 
 use crate::in_memory::InMemory;
@@ -48,10 +50,10 @@ where
     pub(crate) write_log: WriteLog,
     /// DB (usually a MockDB or PersistentDB)
     /// In public API this is immutable in WlState (only mutable in
-    /// `FullAccessState`).
-    pub(crate) db: D,
-    /// State in memory
-    pub(crate) in_mem: InMemory<H>,
+    /// `FullAccessState`). Shared with the forks of the write-log.
+    pub(crate) db: Arc<D>,
+    /// State in memory. Shared with the forks of the write-log.
+    pub(crate) in_mem: Arc<InMemory<H>>,
     /// Static diff storage key filter
     pub diff_key_filter: fn(&storage::Key) -> bool,
 }
@@ -93,11 +95,37 @@ where
     /// Write log
     pub(crate) write_log: WriteLog,
     // DB
-    pub(crate) db: &'a D,
+    pub(crate) db: StateRef<'a, D>,
     /// State
-    pub(crate) in_mem: &'a InMemory<H>,
+    pub(crate) in_mem: StateRef<'a, InMemory<H>>,
+}
+
+/// A part of the state that is either borrowed by a [`TempWlState`] or shared
+/// with a fork of the write-log
+#[derive(Debug)]
+pub(crate) enum StateRef<'a, T> {
+    /// Borrowed from the source state
+    Borrowed(&'a T),
+    /// Shared with the source state
+    Shared(Arc<T>),
+}
+
+impl<T> Deref for StateRef<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        match self {
+            Self::Borrowed(value) => *value,
+            Self::Shared(value) => value.as_ref(),
+        }
+    }
 }
 
+/// The message of the panic on mutable access to a part of the state that is
+/// shared with a fork of the write-log
+const SHARED_STATE_MSG: &str =
+    "The state must not be shared with a fork of the write-log";
+
 impl<D, H> ReadConversionState for TempWlState<'_, D, H>
 where
     D: DB + for<'iter> DBIter<'iter>,
@@ -120,12 +148,12 @@ where
 
     /// Mutably borrow in-memory state
     pub fn in_mem_mut(&mut self) -> &mut InMemory<H> {
-        &mut self.0.in_mem
+        self.0.in_mem_mut()
     }
 
     /// Mutably borrow DB handle
     pub fn db_mut(&mut self) -> &mut D {
-        &mut self.0.db
+        Arc::get_mut(&mut self.0.db).expect(SHARED_STATE_MSG)
     }
 
     /// Borrow state with mutable write-log.
@@ -156,8 +184,8 @@ where
         );
         let mut state = Self(WlState {
             write_log,
-            db,
-            in_mem,
+            db: Arc::new(db),
+            in_mem: Arc::new(in_mem),
             diff_key_filter,
         });
         state.load_last_state();
@@ -179,7 +207,8 @@ where
         time: DateTimeUtc,
         parameters: &Parameters,
     ) -> Result<bool> {
-        match self.in_mem.update_epoch_blocks_delay.as_mut() {
+        let in_mem = self.in_mem_mut();
+        match in_mem.update_epoch_blocks_delay.as_mut() {
             None => {
                 // Check if the new epoch minimum start height and start time
                 // have been fulfilled. If so, queue the next
@@ -188,10 +217,10 @@ where
                 // tendermint. This is because tendermint has a two block delay
                 // to validator changes.
                 let current_epoch_duration_satisfied = height
-                    >= self.in_mem.next_epoch_min_start_height
-                    && time >= self.in_mem.next_epoch_min_start_time;
+                    >= in_mem.next_epoch_min_start_height
+                    && time >= in_mem.next_epoch_min_start_time;
                 if current_epoch_duration_satisfied {
-                    self.in_mem.update_epoch_blocks_delay =
+                    in_mem.update_epoch_blocks_delay =
                         Some(EPOCH_SWITCH_BLOCKS_DELAY);
                 }
             }
@@ -199,30 +228,29 @@ where
                 *blocks_until_switch = checked!(blocks_until_switch - 1)?;
             }
         };
-        let new_epoch =
-            matches!(self.in_mem.update_epoch_blocks_delay, Some(0));
+        let new_epoch = matches!(in_mem.update_epoch_blocks_delay, Some(0));
 
         if new_epoch {
             // Reset the delay tracker
-            self.in_mem.update_epoch_blocks_delay = None;
+            in_mem.update_epoch_blocks_delay = None;
 
             // Begin a new epoch
-            self.in_mem.block.epoch = self.in_mem.block.epoch.next();
+            in_mem.block.epoch = in_mem.block.epoch.next();
             let EpochDuration {
                 min_num_of_blocks,
                 min_duration,
             } = parameters.epoch_duration;
-            self.in_mem.next_epoch_min_start_height = height
+            in_mem.next_epoch_min_start_height = height
                 .checked_add(min_num_of_blocks)
                 .expect("Next epoch min block height shouldn't overflow");
             // Time must not overflow
             #[allow(clippy::arithmetic_side_effects)]
             {
-                self.in_mem.next_epoch_min_start_time = time + min_duration;
+                in_mem.next_epoch_min_start_time = time + min_duration;
             }
 
-            self.in_mem.block.pred_epochs.new_epoch(height);
-            tracing::info!("Began a new epoch {}", self.in_mem.block.epoch);
+            in_mem.block.pred_epochs.new_epoch(height);
+            tracing::info!("Began a new epoch {}", in_mem.block.epoch);
         }
 
         Ok(new_epoch)
@@ -273,9 +301,9 @@ where
         &mut self,
         batch: &mut D::WriteBatch,
     ) -> Result<()> {
-        for (key, entry) in
-            std::mem::take(&mut self.0.write_log.block_write_log).into_iter()
-        {
+        for (key, entry) in Arc::unwrap_or_clone(std::mem::take(
+            &mut self.0.write_log.block_write_log,
+        )) {
             match entry {
                 StorageModification::Write { value } => {
                     self.batch_write_subspace_val(batch, &key, value)?;
@@ -299,6 +327,9 @@ where
             .read(&replay_prot_key)
             .expect("Could not read db")
             .unwrap_or_default();
+        // The set commitment is only maintained once it has been initialized
+        // by a migration (see `replay_protection::set_commitment_key`)
+        let mut set_commitment = self.read_set_commitment()?;
         let new_commitment =
             std::mem::take(&mut self.0.write_log.replay_protection)
                 .iter()
@@ -307,19 +338,45 @@ where
                         batch,
                         &replay_protection::current_key(hash),
                     )?;
+                    if let Some(set_commitment) = set_commitment.as_mut() {
+                        set_commitment.insert(hash);
+                    }
                     acc = acc.concat(hash);
                     Ok::<_, Error>(acc)
                 })?;
         self.batch_write_subspace_val(batch, &replay_prot_key, new_commitment)?;
+        if let Some(set_commitment) = set_commitment {
+            self.batch_write_subspace_val(
+                batch,
+                &replay_protection::set_commitment_key(),
+                set_commitment.to_bytes(),
+            )?;
+        }
 
         debug_assert!(self.0.write_log.replay_protection.is_empty());
 
         if let Some(address_gen) = self.0.write_log.block_address_gen.take() {
-            self.0.in_mem.address_gen = address_gen
+            self.in_mem_mut().address_gen = address_gen
         }
         Ok(())
     }
 
+    /// Read the committed replay protection set commitment, if it has been
+    /// initialized.
+    fn read_set_commitment(
+        &self,
+    ) -> Result<Option<replay_protection::SetCommitment>> {
+        let set_commitment_key = replay_protection::set_commitment_key();
+        Ok(self
+            .db
+            .read_subspace_val(&set_commitment_key)?
+            .map(|bytes| {
+                replay_protection::SetCommitment::try_from_bytes(&bytes).expect(
+                    "The replay protection set commitment must be valid",
+                )
+            }))
+    }
+
     /// Start write batch.
     pub fn batch() -> D::WriteBatch {
         D::batch()
@@ -346,15 +403,18 @@ where
             // The tree of the bridge pool stores the current height for the
             // pending transfer
             let height = self.in_mem.block.height.serialize_to_vec();
-            self.in_mem.block.tree.update(key, height)?;
+            self.in_mem_mut().block.tree.update(key, height)?;
         } else {
             // Update the merkle tree
             if !persist_diffs {
                 let prefix =
                     Key::from(NO_DIFF_KEY_PREFIX.to_string().to_db_key());
-                self.in_mem.block.tree.update(&prefix.join(key), value)?;
+                self.in_mem_mut()
+                    .block
+                    .tree
+                    .update(&prefix.join(key), value)?;
             } else {
-                self.in_mem.block.tree.update(key, value)?;
+                self.in_mem_mut().block.tree.update(key, value)?;
             };
         }
         Ok(self.db.batch_write_subspace_val(
@@ -378,9 +438,9 @@ where
         // Update the merkle tree
         if !persist_diffs {
             let prefix = Key::from(NO_DIFF_KEY_PREFIX.to_string().to_db_key());
-            self.in_mem.block.tree.delete(&prefix.join(key))?;
+            self.in_mem_mut().block.tree.delete(&prefix.join(key))?;
         } else {
-            self.in_mem.block.tree.delete(key)?;
+            self.in_mem_mut().block.tree.delete(key)?;
         }
         Ok(self.db.batch_delete_subspace_val(
             batch,
@@ -397,18 +457,23 @@ where
         is_full_commit: bool,
         batch: &mut D::WriteBatch,
     ) -> Result<()> {
-        if let Some(prev_height) = self
+        // The stores needed to restore the Merkle tree are kept at the heights
+        // that the state can be rolled back to
+        let oldest_rollback_height = self
             .in_mem
             .block
             .height
-            .prev_height()
-            .and_then(|h| h.prev_height())
+            .0
+            .checked_sub(self.in_mem.rollback_window)
+            .map(BlockHeight);
+        if let Some(prev_height) =
+            oldest_rollback_height.and_then(|h| h.prev_height())
         {
             for st in StoreType::iter().filter(|st| st.is_stored_every_block())
             {
                 match st {
                     StoreType::Base => continue,
-                    _ => self.0.db.prune_merkle_tree_store(
+                    _ => self.db_mut().prune_merkle_tree_store(
                         batch,
                         st,
                         Either::Left(prev_height),
@@ -417,20 +482,32 @@ where
             }
         }
 
+        // Prune non-provable stores at the previous epoch, once the oldest
+        // height that can be rolled back to is in the following epoch
+        if let Some(oldest_rollback_height) = oldest_rollback_height {
+            let pred_epochs = &self.in_mem.block.pred_epochs;
+            if let Some(prev_epoch) = pred_epochs
+                .get_epoch(oldest_rollback_height)
+                .filter(|epoch| {
+                    pred_epochs.get_start_height_of_epoch(*epoch)
+                        == Some(oldest_rollback_height)
+                })
+                .and_then(|epoch| epoch.prev())
+            {
+                for st in StoreType::iter_non_provable() {
+                    self.db_mut().prune_merkle_tree_store(
+                        batch,
+                        st,
+                        Either::Right(prev_epoch),
+                    )?;
+                }
+            }
+        }
+
         if !is_full_commit {
             return Ok(());
         }
 
-        // Prune non-provable stores at the previous epoch
-        if let Some(prev_epoch) = self.in_mem.block.epoch.prev() {
-            for st in StoreType::iter_non_provable() {
-                self.0.db.prune_merkle_tree_store(
-                    batch,
-                    st,
-                    Either::Right(prev_epoch),
-                )?;
-            }
-        }
         // Prune provable stores
         let oldest_epoch = self.in_mem.get_oldest_epoch();
         if oldest_epoch.0 > 0 {
@@ -438,7 +515,7 @@ where
             // stores at the starting height of the epoch would be used to
             // restore stores at a height (> oldest_height) in the epoch
             for st in StoreType::iter_provable() {
-                self.db.prune_merkle_tree_store(
+                self.db_mut().prune_merkle_tree_store(
                     batch,
                     st,
                     Either::Right(
@@ -456,7 +533,7 @@ where
             };
             while oldest_epoch < epoch {
                 epoch = epoch.prev().unwrap();
-                self.db.prune_merkle_tree_store(
+                self.db_mut().prune_merkle_tree_store(
                     batch,
                     &StoreType::BridgePool,
                     Either::Right(epoch),
@@ -467,6 +544,66 @@ where
         Ok(())
     }
 
+    /// Get the range of heights whose block history is no longer kept
+    /// according to the pruning mode and has not been pruned yet, if any.
+    /// The blocks in the rollback window and the blocks of the current epoch,
+    /// that the Merkle tree is restored from, are kept regardless of the
+    /// pruning mode.
+    pub fn block_history_to_prune(
+        &self,
+    ) -> Result<Option<(BlockHeight, BlockHeight)>> {
+        let Some(keep_recent) = self.in_mem.pruning_mode.keep_recent() else {
+            return Ok(None);
+        };
+        let keep_recent = keep_recent.max(self.in_mem.rollback_window);
+        // The Merkle tree at any height of the current epoch is restored from
+        // the stores at the start of the epoch and the following diffs, so
+        // those must be kept regardless of the pruning mode
+        let Some(epoch_start) = self
+            .in_mem
+            .block
+            .pred_epochs
+            .get_start_height_of_epoch(self.in_mem.block.epoch)
+        else {
+            return Ok(None);
+        };
+        let Some(target) = self
+            .in_mem
+            .get_last_block_height()
+            .0
+            .checked_sub(keep_recent)
+            .map(|h| h.min(epoch_start.0.saturating_sub(1)))
+        else {
+            return Ok(None);
+        };
+        let from = match self.db.read_pruned_height()? {
+            Some(pruned) => checked!(pruned.0 + 1)?,
+            None => 1,
+        };
+        if target < from {
+            return Ok(None);
+        }
+        Ok(Some((BlockHeight(from), BlockHeight(target))))
+    }
+
+    /// Prune the block history from the `from` height up to and including
+    /// the `to` height in a batch of its own, outside of the block commits.
+    pub fn prune_block_history(
+        &mut self,
+        from: BlockHeight,
+        to: BlockHeight,
+    ) -> Result<()> {
+        let mut batch = D::batch();
+        let prune_headers = self.in_mem.pruning_mode.prune_headers();
+        self.db_mut().prune_block_history(
+            &mut batch,
+            from,
+            to,
+            prune_headers,
+        )?;
+        Ok(self.db.exec_batch(batch)?)
+    }
+
     /// Check it the given transaction's hash is already present in storage
     pub fn has_replay_protection_entry(&self, hash: &Hash) -> Result<bool> {
         Ok(self.db.has_replay_protection_entry(hash)?)
@@ -478,7 +615,7 @@ where
         batch: &mut D::WriteBatch,
         key: &Key,
     ) -> Result<()> {
-        self.db.write_replay_protection_entry(batch, key)?;
+        self.db_mut().write_replay_protection_entry(batch, key)?;
         Ok(())
     }
 
@@ -487,7 +624,9 @@ where
         &mut self,
         batch: &mut D::WriteBatch,
     ) -> Result<()> {
-        Ok(self.db.move_current_replay_protection_entries(batch)?)
+        Ok(self
+            .db_mut()
+            .move_current_replay_protection_entries(batch)?)
     }
 
     /// Get oldest epoch which has the valid signed nonce of the bridge pool
@@ -520,11 +659,13 @@ where
             };
             let nonce = match self
                 .db
-                .read_bridge_pool_signed_nonce(height, last_height)?
+                .read_bridge_pool_signed_nonce(height, last_height)
             {
-                Some(nonce) => nonce,
-                // skip pruning when the old epoch doesn't have the signed nonce
-                None => break,
+                Ok(Some(nonce)) => nonce,
+                // skip pruning when the old epoch doesn't have the signed
+                // nonce, or its history has been pruned
+                Ok(None) | Err(DbError::Pruned { .. }) => break,
+                Err(e) => return Err(e.into()),
             };
             if nonce < current_nonce {
                 break;
@@ -565,7 +706,7 @@ where
             .expect("Read block call must not fail")
         {
             {
-                let in_mem = &mut self.0.in_mem;
+                let in_mem = self.in_mem_mut();
                 in_mem.block.height = height;
                 in_mem.block.epoch = epoch;
                 in_mem.block.results = results;
@@ -587,7 +728,7 @@ where
 
             tree.validate().unwrap();
 
-            let in_mem = &mut self.0.in_mem;
+            let in_mem = self.in_mem_mut();
             in_mem.block.tree = tree;
             in_mem.conversion_state = conversion_state;
             in_mem.ethereum_height = ethereum_height;
@@ -599,8 +740,35 @@ where
     }
 
     /// Commit the data from in-memory state into the block's merkle tree.
+    /// Once the replay protection set commitment has been initialized by a
+    /// migration, this also commits to the parts of the block's state that
+    /// are not stored in the subspace.
     pub fn commit_only_data(&mut self) -> Result<()> {
-        let data = self.in_mem().commit_only_data.serialize();
+        let is_activated = self.read_set_commitment()?.is_some();
+        let in_mem = self.in_mem();
+        let data = if !is_activated {
+            in_mem.commit_only_data.serialize()
+        } else {
+            UnmerklizedBlockState {
+                height: in_mem.block.height,
+                time: in_mem
+                    .header
+                    .as_ref()
+                    .expect("Must have a block header on commit")
+                    .time,
+                epoch: in_mem.block.epoch,
+                pred_epochs: &in_mem.block.pred_epochs,
+                next_epoch_min_start_height: in_mem.next_epoch_min_start_height,
+                next_epoch_min_start_time: in_mem.next_epoch_min_start_time,
+                update_epoch_blocks_delay: in_mem.update_epoch_blocks_delay,
+                address_gen: &in_mem.address_gen,
+                results: &in_mem.block.results,
+                conversion_state: &in_mem.conversion_state,
+                ethereum_height: in_mem.ethereum_height.as_ref(),
+                eth_events_queue: &in_mem.eth_events_queue,
+            }
+            .commit_data(&in_mem.commit_only_data)
+        };
         self.in_mem_mut()
             .block
             .tree
@@ -624,12 +792,13 @@ where
         #[cfg(any(test, feature = "testing", feature = "benches"))]
         {
             if self.in_mem.header.is_none() {
-                self.in_mem.header = Some(namada_core::chain::BlockHeader {
-                    hash: Hash::default(),
-                    #[allow(clippy::disallowed_methods)]
-                    time: DateTimeUtc::now(),
-                    next_validators_hash: Hash::default(),
-                });
+                self.in_mem_mut().header =
+                    Some(namada_core::chain::BlockHeader {
+                        hash: Hash::default(),
+                        #[allow(clippy::disallowed_methods)]
+                        time: DateTimeUtc::now(),
+                        next_validators_hash: Hash::default(),
+                    });
             }
         }
 
@@ -661,21 +830,29 @@ where
         };
         self.db
             .add_block_to_batch(state, &mut batch, is_full_commit)?;
-        let header = self
-            .in_mem
+        let in_mem = self.in_mem_mut();
+        let header = in_mem
             .header
             .take()
             .expect("Must have a block header on commit");
-        self.in_mem.last_block = Some(LastBlock {
-            height: self.in_mem.block.height,
+        in_mem.last_block = Some(LastBlock {
+            height: in_mem.block.height,
             time: header.time,
         });
-        self.in_mem.last_epoch = self.in_mem.block.epoch;
+        in_mem.last_epoch = in_mem.block.epoch;
         // prune old merkle tree stores
         self.prune_merkle_tree_stores(is_full_commit, &mut batch)?;
-        // If there's a previous block, prune non-persisted diffs from it
-        if let Some(height) = self.in_mem.block.height.prev_height() {
-            self.db.prune_non_persisted_diffs(&mut batch, height)?;
+        // Prune the non-persisted diffs of the block that can no longer be
+        // rolled back, if any
+        if let Some(height) = self
+            .in_mem
+            .block
+            .height
+            .0
+            .checked_sub(self.in_mem.rollback_window)
+        {
+            self.db_mut()
+                .prune_non_persisted_diffs(&mut batch, BlockHeight(height))?;
         }
         self.db.exec_batch(batch)?;
         Ok(())
@@ -699,7 +876,7 @@ where
 
     /// Mutably borrow in-memory state
     pub fn in_mem_mut(&mut self) -> &mut InMemory<H> {
-        &mut self.in_mem
+        Arc::get_mut(&mut self.in_mem).expect(SHARED_STATE_MSG)
     }
 
     /// Borrow DB handle
@@ -708,6 +885,12 @@ where
         &self.db
     }
 
+    /// Mutably borrow DB handle, for the writes that bypass the write-log
+    #[cfg(any(test, feature = "testing", feature = "benches"))]
+    fn db_mut(&mut self) -> &mut D {
+        Arc::get_mut(&mut self.db).expect(SHARED_STATE_MSG)
+    }
+
     /// Mutably borrow write-log
     pub fn write_log_mut(&mut self) -> &mut WriteLog {
         &mut self.write_log
@@ -717,8 +900,8 @@ where
     pub fn with_temp_write_log(&self) -> TempWlState<'_, D, H> {
         TempWlState {
             write_log: WriteLog::default(),
-            db: &self.db,
-            in_mem: &self.in_mem,
+            db: StateRef::Borrowed(&*self.db),
+            in_mem: StateRef::Borrowed(&*self.in_mem),
         }
     }
 
@@ -741,11 +924,34 @@ where
     ) -> TempWlState<'static, D, H> {
         TempWlState {
             write_log: WriteLog::default(),
-            db: &*(&self.db as *const _),
-            in_mem: &*(&self.in_mem as *const _),
+            db: StateRef::Borrowed(&*Arc::as_ptr(&self.db)),
+            in_mem: StateRef::Borrowed(&*Arc::as_ptr(&self.in_mem)),
         }
     }
 
+    /// Speculatively execute a transaction batch with `f` on a fork of the
+    /// write-log, on top of the changes committed to the block so far.
+    /// Returns the result of `f` together with the detached fork of the
+    /// write-log.
+    ///
+    /// The fork shares the in-memory state and DB handle with this state, so
+    /// that it has a `'static` lifetime, as required by the VM. They cannot be
+    /// accessed mutably until every fork is dropped.
+    pub fn with_write_log_fork<T>(
+        &self,
+        f: impl FnOnce(&mut TempWlState<'static, D, H>) -> T,
+    ) -> (T, WriteLog) {
+        let mut fork = TempWlState {
+            write_log: self.write_log.fork(),
+            db: StateRef::Shared(Arc::clone(&self.db)),
+            in_mem: StateRef::Shared(Arc::clone(&self.in_mem)),
+        };
+        let result = f(&mut fork);
+        let mut write_log = fork.write_log;
+        write_log.detach_fork();
+        (result, write_log)
+    }
+
     /// Commit the current transaction's write log and the entire batch to the
     /// block. Starts a new transaction and batch write log.
     pub fn commit_tx_batch(&mut self) {
@@ -854,23 +1060,27 @@ where
             // The tree of the bright pool stores the current height for the
             // pending transfer
             let height = self.in_mem.block.height.serialize_to_vec();
-            self.in_mem.block.tree.update(key, height)?;
+            self.in_mem_mut().block.tree.update(key, height)?;
         } else {
             // Update the merkle tree
             if !persist_diffs {
                 let prefix =
                     Key::from(NO_DIFF_KEY_PREFIX.to_string().to_db_key());
-                self.in_mem.block.tree.update(&prefix.join(key), value)?;
+                self.in_mem_mut()
+                    .block
+                    .tree
+                    .update(&prefix.join(key), value)?;
             } else {
-                self.in_mem.block.tree.update(key, value)?;
+                self.in_mem_mut().block.tree.update(key, value)?;
             }
         }
 
         let len = value.len();
         let gas =
             (key.len() + len) as u64 * namada_gas::STORAGE_WRITE_GAS_PER_BYTE;
-        let size_diff = self.db.write_subspace_val(
-            self.in_mem.block.height,
+        let height = self.in_mem.block.height;
+        let size_diff = self.db_mut().write_subspace_val(
+            height,
             key,
             value,
             persist_diffs,
@@ -895,12 +1105,13 @@ where
             if !persist_diffs {
                 let prefix =
                     Key::from(NO_DIFF_KEY_PREFIX.to_string().to_db_key());
-                self.in_mem.block.tree.delete(&prefix.join(key))?;
+                self.in_mem_mut().block.tree.delete(&prefix.join(key))?;
             } else {
-                self.in_mem.block.tree.delete(key)?;
+                self.in_mem_mut().block.tree.delete(key)?;
             }
-            deleted_bytes_len = self.db.delete_subspace_val(
-                self.in_mem.block.height,
+            let height = self.in_mem.block.height;
+            deleted_bytes_len = self.db_mut().delete_subspace_val(
+                height,
                 key,
                 persist_diffs,
             )?;
@@ -1006,6 +1217,15 @@ where
         } else {
             height
         };
+        if let Some(pruned_height) = self.db.read_pruned_height()? {
+            if height <= pruned_height {
+                return Err(DbError::Pruned {
+                    height,
+                    oldest: pruned_height.next_height(),
+                }
+                .into());
+            }
+        }
 
         let epoch = self
             .in_mem
@@ -1181,12 +1401,12 @@ where
 
     /// Borrow in-memory state
     pub fn in_mem(&self) -> &InMemory<H> {
-        self.in_mem
+        &self.in_mem
     }
 
     /// Borrow DB handle
     pub fn db(&self) -> &D {
-        self.db
+        &self.db
     }
 
     /// Mutably borrow write-log
@@ -1253,7 +1473,7 @@ where
     fn split_borrow(
         &mut self,
     ) -> (&mut WriteLog, &InMemory<Self::H>, &Self::D) {
-        (&mut self.0.write_log, &self.0.in_mem, &self.0.db)
+        (&mut self.0.write_log, &*self.0.in_mem, &*self.0.db)
     }
 }
 
@@ -1338,7 +1558,7 @@ where
     fn split_borrow(
         &mut self,
     ) -> (&mut WriteLog, &InMemory<Self::H>, &Self::D) {
-        (&mut self.write_log, &self.in_mem, &self.db)
+        (&mut self.write_log, &*self.in_mem, &*self.db)
     }
 }
 
@@ -1434,11 +1654,11 @@ where
     }
 
     fn db(&self) -> &D {
-        self.db
+        &self.db
     }
 
     fn in_mem(&self) -> &InMemory<Self::H> {
-        self.in_mem
+        &self.in_mem
     }
 
     fn charge_gas(&self, _gas: Gas) -> Result<()> {
@@ -1458,7 +1678,7 @@ where
     fn split_borrow(
         &mut self,
     ) -> (&mut WriteLog, &InMemory<Self::H>, &Self::D) {
-        (&mut self.write_log, (self.in_mem), (self.db))
+        (&mut self.write_log, &*self.in_mem, &*self.db)
     }
 }
 
@@ -1470,8 +1690,8 @@ where
     fn with_tx_writes(&mut self) -> TxWlState<'_, Self::D, Self::H> {
         TxWlState {
             write_log: &mut self.write_log,
-            db: self.db,
-            in_mem: self.in_mem,
+            db: &self.db,
+            in_mem: &self.in_mem,
         }
     }
 }
@@ -2,6 +2,7 @@
 //! before they are committed to the ledger's storage.
 
 use std::collections::{BTreeMap, BTreeSet};
+use std::sync::{Arc, Mutex};
 
 use itertools::Itertools;
 use namada_core::address::{Address, EstablishedAddressGen};
@@ -139,8 +140,10 @@ pub struct WriteLog {
     /// The generator of established addresses
     pub(crate) block_address_gen: Option<EstablishedAddressGen>,
     /// All the storage modification accepted by validity predicates are stored
-    /// in block write-log, before being committed to the storage
-    pub(crate) block_write_log: HashMap<storage::Key, StorageModification>,
+    /// in block write-log, before being committed to the storage. This is
+    /// shared with the forks of the write log and only copied if written to
+    /// while a fork is alive.
+    pub(crate) block_write_log: Arc<HashMap<storage::Key, StorageModification>>,
     /// The write log of the transactions of the current batch
     /// INVARIANT: this has to be sorted by the insertion
     /// order to correctly read values
@@ -149,10 +152,88 @@ pub struct WriteLog {
     pub(crate) tx_write_log: TxWriteLog,
     /// Storage modifications for the replay protection storage, cannot be
     /// managed in the normal write log because we need to commit them
-    /// sometimes even on batch failure
-    pub(crate) replay_protection: HashSet<Hash>,
+    /// sometimes even on batch failure. This is shared with the forks of the
+    /// write log like the block write-log.
+    pub(crate) replay_protection: Arc<HashSet<Hash>>,
+    /// Tracks the keys read through a fork of the write log
+    pub(crate) read_tracker: ReadTracker,
 }
 
+/// The storage keys read through a fork of the write log, including the
+/// prefixes of the iterated keys. This is used to detect the conflicts of the
+/// transactions executed speculatively on forks of the same write log.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReadSet {
+    keys: BTreeSet<storage::Key>,
+    prefixes: BTreeSet<storage::Key>,
+}
+
+impl ReadSet {
+    /// Check if any of the given changed keys has been read, either directly
+    /// or by iterating a matching prefix.
+    pub fn intersects(&self, changed_keys: &BTreeSet<storage::Key>) -> bool {
+        changed_keys.iter().any(|key| {
+            self.keys.contains(key)
+                || self
+                    .prefixes
+                    .iter()
+                    .any(|prefix| key.split_prefix(prefix).is_some())
+        })
+    }
+}
+
+/// Tracker of the keys read through a write log, only enabled in forks. The
+/// reads may happen concurrently, e.g. in validity predicates.
+#[derive(Debug, Default)]
+pub(crate) struct ReadTracker(Option<Mutex<ReadSet>>);
+
+impl ReadTracker {
+    fn enabled() -> Self {
+        Self(Some(Mutex::new(ReadSet::default())))
+    }
+
+    fn with_read_set(&self, f: impl FnOnce(&mut ReadSet)) {
+        if let Some(read_set) = &self.0 {
+            f(&mut read_set.lock().expect("The read set lock is poisoned"))
+        }
+    }
+
+    fn read_set(&self) -> Option<ReadSet> {
+        self.0.as_ref().map(|read_set| {
+            read_set
+                .lock()
+                .expect("The read set lock is poisoned")
+                .clone()
+        })
+    }
+
+    fn key(&self, key: &storage::Key) {
+        self.with_read_set(|read_set| {
+            read_set.keys.insert(key.clone());
+        })
+    }
+
+    fn prefix(&self, prefix: &storage::Key) {
+        self.with_read_set(|read_set| {
+            read_set.prefixes.insert(prefix.clone());
+        })
+    }
+}
+
+impl Clone for ReadTracker {
+    fn clone(&self) -> Self {
+        Self(self.read_set().map(Mutex::new))
+    }
+}
+
+impl PartialEq for ReadTracker {
+    fn eq(&self, other: &Self) -> bool {
+        self.read_set() == other.read_set()
+    }
+}
+
+impl Eq for ReadTracker {}
+
 /// Write log prefix iterator
 #[derive(Debug)]
 pub struct PrefixIter {
@@ -173,10 +254,11 @@ impl Default for WriteLog {
     fn default() -> Self {
         Self {
             block_address_gen: None,
-            block_write_log: HashMap::with_capacity(100_000),
+            block_write_log: Arc::new(HashMap::with_capacity(100_000)),
             batch_write_log: Vec::with_capacity(5),
             tx_write_log: Default::default(),
-            replay_protection: HashSet::with_capacity(1_000),
+            replay_protection: Arc::new(HashSet::with_capacity(1_000)),
+            read_tracker: ReadTracker::default(),
         }
     }
 }
@@ -189,6 +271,7 @@ impl WriteLog {
         key: &storage::Key,
     ) -> std::result::Result<(Option<&StorageModification>, Gas), arith::Error>
     {
+        self.read_tracker.key(key);
         // try to read from tx write log first
         match self
             .tx_write_log
@@ -236,12 +319,13 @@ impl WriteLog {
         key: &storage::Key,
     ) -> std::result::Result<(Option<&StorageModification>, Gas), arith::Error>
     {
+        self.read_tracker.key(key);
         for bucket in self
             .batch_write_log
             .iter()
             .rev()
             .map(|batch_log| &batch_log.write_log)
-            .chain([&self.block_write_log])
+            .chain([&*self.block_write_log])
         {
             if let Some(v) = bucket.get(key) {
                 let gas = match v {
@@ -350,8 +434,7 @@ impl WriteLog {
         if self.tx_write_log.tx_temp_log.contains_key(key) {
             return Err(Error::UpdateTemporaryValue);
         }
-        if let Some(prev) = self
-            .block_write_log
+        if let Some(prev) = Arc::make_mut(&mut self.block_write_log)
             .insert(key.clone(), StorageModification::Write { value })
         {
             match prev {
@@ -459,8 +542,7 @@ impl WriteLog {
         if key.is_validity_predicate().is_some() {
             return Err(Error::DeleteVp);
         }
-        if let Some(prev) = self
-            .block_write_log
+        if let Some(prev) = Arc::make_mut(&mut self.block_write_log)
             .insert(key.clone(), StorageModification::Delete)
         {
             match prev {
@@ -647,7 +729,7 @@ impl WriteLog {
     /// separately.
     pub fn commit_batch_only(&mut self) {
         for log in std::mem::take(&mut self.batch_write_log) {
-            self.block_write_log.extend(log.write_log);
+            Arc::make_mut(&mut self.block_write_log).extend(log.write_log);
             self.block_address_gen = log.address_gen;
         }
     }
@@ -658,6 +740,59 @@ impl WriteLog {
         self.batch_write_log = Default::default();
     }
 
+    /// Fork the write log to speculatively execute a batch on top of the
+    /// changes committed to the block so far. The fork starts with no pending
+    /// batch and tracks the keys read through it. The block changes are
+    /// shared with the fork instead of being copied, hence the fork should be
+    /// detached before the block of this write log is written to.
+    pub fn fork(&self) -> Self {
+        Self {
+            block_address_gen: self.block_address_gen.clone(),
+            block_write_log: Arc::clone(&self.block_write_log),
+            batch_write_log: Default::default(),
+            tx_write_log: Default::default(),
+            replay_protection: Arc::clone(&self.replay_protection),
+            read_tracker: ReadTracker::enabled(),
+        }
+    }
+
+    /// Release the block changes shared by a fork with the write log it was
+    /// forked from, keeping only the pending batch, the current transaction
+    /// and the tracked reads of the fork.
+    pub fn detach_fork(&mut self) {
+        self.block_write_log = Default::default();
+        self.replay_protection = Default::default();
+    }
+
+    /// Get the keys read through a fork of the write log, if this is a fork.
+    pub fn get_read_set(&self) -> Option<ReadSet> {
+        self.read_tracker.read_set()
+    }
+
+    /// Take over the pending batch and current transaction of a fork, as if
+    /// they were executed on this write log. This is only valid if none of the
+    /// keys read through the fork has been changed in this write log since it
+    /// was forked.
+    pub fn join_fork(&mut self, fork: Self) {
+        debug_assert!(
+            self.batch_write_log.is_empty(),
+            "The pending batch must be empty to join a fork"
+        );
+        self.batch_write_log = fork.batch_write_log;
+        self.tx_write_log = fork.tx_write_log;
+    }
+
+    /// Get the storage keys changed by the pending batch, including the
+    /// current transaction.
+    pub fn get_batch_keys(&self) -> BTreeSet<storage::Key> {
+        self.batch_write_log
+            .iter()
+            .flat_map(|log| log.write_log.keys())
+            .chain(self.tx_write_log.write_log.keys())
+            .cloned()
+            .collect()
+    }
+
     /// Get the verifiers set whose validity predicates should validate the
     /// current transaction changes and the storage keys that have been
     /// modified created, updated and deleted via the write log.
@@ -694,6 +829,7 @@ impl WriteLog {
     /// Iterate modifications prior to the current transaction, whose storage
     /// key matches the given prefix, sorted by their storage key.
     pub fn iter_prefix_pre(&self, prefix: &storage::Key) -> PrefixIter {
+        self.read_tracker.prefix(prefix);
         let mut matches = BTreeMap::new();
 
         for (key, modification) in self.block_write_log.iter().chain(
@@ -713,6 +849,7 @@ impl WriteLog {
     /// Iterate modifications posterior of the current tx, whose storage key
     /// matches the given prefix, sorted by their storage key.
     pub fn iter_prefix_post(&self, prefix: &storage::Key) -> PrefixIter {
+        self.read_tracker.prefix(prefix);
         let mut matches = BTreeMap::new();
 
         for (key, modification) in self.block_write_log.iter().chain(
@@ -737,7 +874,7 @@ impl WriteLog {
 
     /// Write the transaction hash
     pub fn write_tx_hash(&mut self, hash: Hash) -> Result<()> {
-        if !self.replay_protection.insert(hash) {
+        if !Arc::make_mut(&mut self.replay_protection).insert(hash) {
             // Cannot write an hash if it's already present in the set
             return Err(Error::ReplayProtection(format!(
                 "Requested a write of hash {hash} which has already been \
@@ -750,7 +887,7 @@ impl WriteLog {
 
     /// Remove the transaction hash because redundant
     pub(crate) fn redundant_tx_hash(&mut self, hash: &Hash) -> Result<()> {
-        if !self.replay_protection.swap_remove(hash) {
+        if !Arc::make_mut(&mut self.replay_protection).swap_remove(hash) {
             return Err(Error::ReplayProtection(format!(
                 "Requested a redundant modification on hash {hash} which is \
                  unknown"
@@ -1092,6 +1229,50 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_fork() {
+        let mut write_log = WriteLog::default();
+        let key1 = storage::Key::parse("key1").unwrap();
+        let key2 = storage::Key::parse("key2").unwrap();
+        let prefix = storage::Key::parse("prefix").unwrap();
+        let prefixed_key = prefix.push(&"key".to_string()).unwrap();
+
+        // commit a value to the block
+        let _ = write_log.write(&key1, vec![1]).unwrap();
+        write_log.commit_batch_and_current_tx();
+        assert!(write_log.get_read_set().is_none());
+
+        // the fork shares the changes committed to the block and tracks reads
+        let mut fork = write_log.fork();
+        assert!(Arc::ptr_eq(
+            &fork.block_write_log,
+            &write_log.block_write_log
+        ));
+        let (value, _) = fork.read(&key1).unwrap();
+        assert_eq!(value, Some(&StorageModification::Write { value: vec![1] }));
+        let _ = fork.iter_prefix_post(&prefix);
+        let _ = fork.write(&key2, vec![2]).unwrap();
+        fork.commit_tx_to_batch();
+        let read_set = fork.get_read_set().unwrap();
+        assert!(read_set.intersects(&BTreeSet::from([key1.clone()])));
+        assert!(read_set.intersects(&BTreeSet::from([prefixed_key])));
+        assert!(!read_set.intersects(&BTreeSet::from([key2.clone()])));
+        assert_eq!(fork.get_batch_keys(), BTreeSet::from([key2.clone()]));
+
+        // the detached fork keeps its batch, but not the block changes
+        fork.detach_fork();
+        assert!(fork.block_write_log.is_empty());
+        assert_eq!(fork.get_read_set(), Some(read_set));
+        assert_eq!(fork.get_batch_keys(), BTreeSet::from([key2.clone()]));
+
+        // the forked batch is committed after joining the fork
+        write_log.join_fork(fork);
+        write_log.commit_batch_and_current_tx();
+        let (value, _) = write_log.read(&key2).unwrap();
+        assert_eq!(value, Some(&StorageModification::Write { value: vec![2] }));
+        assert!(write_log.get_batch_keys().is_empty());
+    }
+
     // Test that writing a value on top of a temporary write is not allowed
     #[test]
     fn test_write_after_temp_disallowed() {
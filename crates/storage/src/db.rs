@@ -3,6 +3,7 @@ use std::num::TryFromIntError;
 
 use itertools::Either;
 use namada_core::address::EstablishedAddressGen;
+use namada_core::borsh::{BorshDeserialize, BorshSerialize, BorshSerializeExt};
 use namada_core::chain::{BlockHeader, BlockHeight, Epoch, Epochs};
 use namada_core::hash::{Error as HashError, Hash};
 use namada_core::storage::{BlockResults, DbColFam, EthEventsQueue, Key};
@@ -15,7 +16,7 @@ use namada_merkle_tree::{
 };
 use regex::Regex;
 use serde::de::DeserializeOwned;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 use crate::conversion_state::ConversionState;
@@ -46,12 +47,78 @@ pub enum Error {
     NumConversionError(#[from] TryFromIntError),
     #[error("Arithmetic {0}")]
     Arith(#[from] arith::Error),
+    #[error(
+        "The state at the height {height} has been pruned, the oldest \
+         available height is {oldest}"
+    )]
+    Pruned {
+        height: BlockHeight,
+        oldest: BlockHeight,
+    },
 }
 
 /// A result of a function that may fail
 pub type Result<T> = std::result::Result<T, Error>;
 
+/// How much of the history of past blocks a node keeps
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum PruningMode {
+    /// Keep the full history, so that the state can be queried at any height
+    #[default]
+    Archive,
+    /// Keep the history of the given number of most recent blocks
+    Pruned {
+        /// The number of most recent blocks whose history is kept
+        keep_recent: u64,
+        /// Whether to prune the block headers with the rest of the history.
+        /// They are kept by default, as the IBC consensus states of this
+        /// chain at past heights are looked up from them. A node that prunes
+        /// them fails the IBC transactions that refer to a pruned height,
+        /// which the nodes keeping them may accept, so this is only safe on
+        /// nodes whose chain doesn't use IBC.
+        #[serde(default)]
+        prune_headers: bool,
+    },
+    /// Keep only the history needed to run the node, i.e. the blocks of the
+    /// current epoch and of the rollback window, and the block headers
+    Minimal,
+}
+
+impl PruningMode {
+    /// The number of most recent blocks whose history must be kept, if any
+    /// history may be pruned at all. The history of the blocks of the
+    /// current epoch and of the blocks in the rollback window is always kept
+    /// regardless, as it's needed to restore the Merkle tree and to roll
+    /// back.
+    pub fn keep_recent(&self) -> Option<u64> {
+        match self {
+            PruningMode::Archive => None,
+            PruningMode::Pruned { keep_recent, .. } => Some(*keep_recent),
+            PruningMode::Minimal => Some(0),
+        }
+    }
+
+    /// Whether the block headers are pruned with the rest of the history
+    pub fn prune_headers(&self) -> bool {
+        matches!(
+            self,
+            PruningMode::Pruned {
+                prune_headers: true,
+                ..
+            }
+        )
+    }
+}
+
+/// The default number of most recent blocks that can be rolled back, i.e.
+/// whose non-persisted diffs and other rollback data are kept
+pub const DEFAULT_ROLLBACK_WINDOW: u64 = 1;
+
 /// The block's state as stored in the database.
+#[derive(Debug, BorshSerialize, BorshDeserialize)]
 pub struct BlockStateRead {
     /// Height of the block
     pub height: BlockHeight,
@@ -82,6 +149,74 @@ pub struct BlockStateRead {
     pub commit_only_data: CommitOnlyData,
 }
 
+impl BlockStateRead {
+    /// Get the parts of the block's state that are not stored in the subspace
+    pub fn unmerklized(&self) -> UnmerklizedBlockState<'_> {
+        UnmerklizedBlockState {
+            height: self.height,
+            time: self.time,
+            epoch: self.epoch,
+            pred_epochs: &self.pred_epochs,
+            next_epoch_min_start_height: self.next_epoch_min_start_height,
+            next_epoch_min_start_time: self.next_epoch_min_start_time,
+            update_epoch_blocks_delay: self.update_epoch_blocks_delay,
+            address_gen: &self.address_gen,
+            results: &self.results,
+            conversion_state: &self.conversion_state,
+            ethereum_height: self.ethereum_height.as_ref(),
+            eth_events_queue: &self.eth_events_queue,
+        }
+    }
+
+    /// Get the data committed to the commit data sub-tree of the merkle tree
+    pub fn commit_data(&self) -> Vec<u8> {
+        self.unmerklized().commit_data(&self.commit_only_data)
+    }
+}
+
+/// The parts of the block's state that are not stored in the subspace. They
+/// are committed to the merkle tree by their hash along with the commit-only
+/// data, so that they can be verified against the app hash, e.g. when they
+/// are received in a state-sync snapshot.
+#[derive(Debug, BorshSerialize)]
+pub struct UnmerklizedBlockState<'a> {
+    /// Height of the block
+    pub height: BlockHeight,
+    /// Time of the block
+    pub time: DateTimeUtc,
+    /// Epoch of the block
+    pub epoch: Epoch,
+    /// Predecessor block epochs
+    pub pred_epochs: &'a Epochs,
+    /// Minimum block height at which the next epoch may start
+    pub next_epoch_min_start_height: BlockHeight,
+    /// Minimum block time at which the next epoch may start
+    pub next_epoch_min_start_time: DateTimeUtc,
+    /// Update epoch delay
+    pub update_epoch_blocks_delay: Option<u32>,
+    /// Established address generator
+    pub address_gen: &'a EstablishedAddressGen,
+    /// Results of applying transactions
+    pub results: &'a BlockResults,
+    /// The conversion state
+    pub conversion_state: &'a ConversionState,
+    /// The latest block height on Ethereum processed, if
+    /// the bridge is enabled.
+    pub ethereum_height: Option<&'a ethereum_structs::BlockHeight>,
+    /// The queue of Ethereum events to be processed in order.
+    pub eth_events_queue: &'a EthEventsQueue,
+}
+
+impl UnmerklizedBlockState<'_> {
+    /// Get the data committed to the commit data sub-tree of the merkle tree:
+    /// the given commit-only data followed by the hash of this state
+    pub fn commit_data(&self, commit_only_data: &CommitOnlyData) -> Vec<u8> {
+        let mut data = commit_only_data.serialize();
+        data.extend(Hash::sha256(self.serialize_to_vec()).0);
+        data
+    }
+}
+
 /// The block's state to write into the database.
 pub struct BlockStateWrite<'a> {
     /// Merkle tree stores
@@ -117,8 +252,9 @@ pub struct BlockStateWrite<'a> {
     pub commit_only_data: &'a CommitOnlyData,
 }
 
-/// A database backend.
-pub trait DB: Debug {
+/// A database backend. The handle is shared with the forks of the write-log
+/// that execute transactions in parallel, hence it must be `Send`.
+pub trait DB: Debug + Send {
     /// A DB's cache
     type Cache;
     /// A handle for batch writes
@@ -282,13 +418,32 @@ pub trait DB: Debug {
         batch: &mut Self::WriteBatch,
     ) -> Result<()>;
 
-    /// Prune non-persisted diffs that are only kept for one block for rollback
+    /// Prune the data that is only kept to roll back the block at the given
+    /// height: the non-persisted diffs, the state values it replaced and the
+    /// replay protection entries it added
     fn prune_non_persisted_diffs(
         &mut self,
         batch: &mut Self::WriteBatch,
         height: BlockHeight,
     ) -> Result<()>;
 
+    /// Prune the history of the blocks from the `from` height up to and
+    /// including the `to` height: the diffs, the block results and the
+    /// Merkle tree stores written at every height. Values can no longer be
+    /// read at these heights afterwards. The block headers are only pruned
+    /// if `prune_headers` is set, as the IBC consensus states of past heights
+    /// are looked up from them.
+    fn prune_block_history(
+        &mut self,
+        batch: &mut Self::WriteBatch,
+        from: BlockHeight,
+        to: BlockHeight,
+        prune_headers: bool,
+    ) -> Result<()>;
+
+    /// Read the last height whose block history has been pruned, if any
+    fn read_pruned_height(&self) -> Result<Option<BlockHeight>>;
+
     /// Overwrite a new value in storage, taking into
     /// account values stored at a previous height
     fn overwrite_entry(
@@ -355,6 +510,10 @@ pub trait DBIter<'iter> {
 
     /// Read replay protection storage from the current bucket
     fn iter_current_replay_protection(&'iter self) -> Self::PrefixIter;
+
+    /// Read replay protection storage from all the buckets. The keys of the
+    /// current bucket are prefixed with `current/`.
+    fn iter_replay_protection(&'iter self) -> Self::PrefixIter;
 }
 
 /// Atomic batch write.
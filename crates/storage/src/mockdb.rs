@@ -35,6 +35,7 @@ const COMMIT_ONLY_DATA_KEY: &str = "commit_only_data_commitment";
 const CONVERSION_STATE_KEY: &str = "conversion_state";
 const ETHEREUM_HEIGHT_KEY: &str = "ethereum_height";
 const ETH_EVENTS_QUEUE_KEY: &str = "eth_events_queue";
+const PRUNED_HEIGHT_KEY: &str = "pruned_height";
 const RESULTS_KEY_PREFIX: &str = "results";
 
 const MERKLE_TREE_ROOT_KEY_SEGMENT: &str = "root";
@@ -380,9 +381,17 @@ impl DB for MockDB {
     fn read_subspace_val_with_height(
         &self,
         key: &Key,
-        _height: BlockHeight,
+        height: BlockHeight,
         _last_height: BlockHeight,
     ) -> Result<Option<Vec<u8>>> {
+        if let Some(pruned_height) = self.read_pruned_height()? {
+            if height <= pruned_height {
+                return Err(Error::Pruned {
+                    height,
+                    oldest: pruned_height.next_height(),
+                });
+            }
+        }
         tracing::warn!(
             "read_subspace_val_with_height is not implemented, will read \
              subspace value from latest height"
@@ -564,6 +573,59 @@ impl DB for MockDB {
         Ok(())
     }
 
+    fn prune_block_history(
+        &mut self,
+        _batch: &mut Self::WriteBatch,
+        from: BlockHeight,
+        to: BlockHeight,
+        prune_headers: bool,
+    ) -> Result<()> {
+        let mut db = self.0.borrow_mut();
+        for height in from.0..=to.0 {
+            let height = BlockHeight(height);
+            let prefix = height.raw();
+            let diffs_prefixes = [
+                format!("{prefix}/{OLD_DIFF_PREFIX}/"),
+                format!("{prefix}/{NEW_DIFF_PREFIX}/"),
+            ];
+            db.retain(|key, _| {
+                !diffs_prefixes.iter().any(|diffs| key.starts_with(diffs))
+            });
+            // NB: the block headers are kept for the IBC consensus states
+            // unless they are explicitly pruned
+            let header = prune_headers.then_some(BLOCK_HEADER_KEY_SEGMENT);
+            for segment in [
+                BLOCK_TIME_KEY_SEGMENT,
+                EPOCH_KEY_SEGMENT,
+                PRED_EPOCHS_KEY_SEGMENT,
+                ADDRESS_GEN_KEY_SEGMENT,
+            ]
+            .into_iter()
+            .chain(header)
+            {
+                db.remove(&format!("{prefix}/{segment}"));
+            }
+            db.remove(&format!("{RESULTS_KEY_PREFIX}/{prefix}"));
+            for st in StoreType::iter().filter(|st| st.is_stored_every_block())
+            {
+                let key_prefix = tree_key_prefix_with_height(st, height);
+                db.remove(&format!(
+                    "{key_prefix}/{MERKLE_TREE_ROOT_KEY_SEGMENT}"
+                ));
+                db.remove(&format!(
+                    "{key_prefix}/{MERKLE_TREE_STORE_KEY_SEGMENT}"
+                ));
+            }
+        }
+        drop(db);
+        self.write_value(PRUNED_HEIGHT_KEY, &to);
+        Ok(())
+    }
+
+    fn read_pruned_height(&self) -> Result<Option<BlockHeight>> {
+        self.read_value(PRUNED_HEIGHT_KEY)
+    }
+
     fn read_bridge_pool_signed_nonce(
         &self,
         _height: BlockHeight,
@@ -742,6 +804,13 @@ impl<'iter> DBIter<'iter> for MockDB {
         let iter = self.0.borrow().clone().into_iter();
         MockPrefixIterator::new(MockIterator { prefix, iter }, stripped_prefix)
     }
+
+    fn iter_replay_protection(&'iter self) -> Self::PrefixIter {
+        let stripped_prefix = "replay_protection/".to_string();
+        let prefix = stripped_prefix.clone();
+        let iter = self.0.borrow().clone().into_iter();
+        MockPrefixIterator::new(MockIterator { prefix, iter }, stripped_prefix)
+    }
 }
 
 /// A prefix iterator base for the [`MockPrefixIterator`].
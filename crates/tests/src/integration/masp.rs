@@ -1,5 +1,7 @@
 use std::path::PathBuf;
 use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 
 use color_eyre::eyre::Result;
 use color_eyre::owo_colors::OwoColorize;
@@ -14,6 +16,7 @@ use namada_node::shell::testing::node::NodeResults;
 use namada_node::shell::testing::utils::{Bin, CapturedOutput};
 use namada_sdk::account::AccountPublicKeysMap;
 use namada_sdk::masp::fs::FsShieldedUtils;
+use namada_sdk::masp::testing::MockMaspProver;
 use namada_sdk::signing::SigningTxData;
 use namada_sdk::state::{StorageRead, StorageWrite};
 use namada_sdk::time::DateTimeUtc;
@@ -3287,6 +3290,148 @@ fn cross_epoch_unshield() -> Result<()> {
     Ok(())
 }
 
+// Test that MASP transactions can be built with their proofs generated by a
+// remote MASP prover.
+#[test]
+fn remote_masp_prover() -> Result<()> {
+    // This address doesn't matter for tests. But an argument is required.
+    let validator_one_rpc = "http://127.0.0.1:26567";
+    // Download the shielded pool parameters before starting node
+    let _ = FsShieldedUtils::new(PathBuf::new());
+    let (mut node, _services) = setup::setup()?;
+    _ = node.next_epoch();
+
+    // Start a stand-in prover server counting the circuits it proves
+    let served = Arc::new(AtomicUsize::new(0));
+    let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+    let prover_url = format!("http://{}", listener.local_addr()?);
+    let (shutdown_send, shutdown_recv) = tokio::sync::oneshot::channel();
+    let server = std::thread::spawn({
+        let served = served.clone();
+        move || {
+            tokio::runtime::Runtime::new().unwrap().block_on(
+                namada_apps_lib::masp_prover::serve(
+                    listener,
+                    move |requests| {
+                        served.fetch_add(requests.len(), Ordering::SeqCst);
+                        MockMaspProver.prove_requests(requests)
+                    },
+                    async move {
+                        _ = shutdown_recv.await;
+                    },
+                ),
+            )
+        }
+    });
+
+    // 1. Shield some tokens with the output proofs generated remotely
+    let captured = CapturedOutput::of(|| {
+        run(
+            &node,
+            Bin::Client,
+            apply_use_device(vec![
+                "shield",
+                "--source",
+                ALBERT,
+                "--target",
+                AA_PAYMENT_ADDRESS,
+                "--token",
+                NAM,
+                "--amount",
+                "1000",
+                "--signing-keys",
+                ALBERT_KEY,
+                "--masp-prover",
+                &prover_url,
+                "--ledger-address",
+                validator_one_rpc,
+            ]),
+        )
+    });
+    assert!(captured.result.is_ok());
+    assert!(captured.contains(TX_APPLIED_SUCCESS));
+    let shield_proofs = served.load(Ordering::SeqCst);
+    assert!(shield_proofs > 0);
+
+    // sync the shielded context
+    run(
+        &node,
+        Bin::Client,
+        vec![
+            "shielded-sync",
+            "--viewing-keys",
+            AA_VIEWING_KEY,
+            AB_VIEWING_KEY,
+            "--node",
+            validator_one_rpc,
+        ],
+    )?;
+
+    // 2. Make a shielded transfer with the spend proofs generated remotely
+    let captured = CapturedOutput::of(|| {
+        run(
+            &node,
+            Bin::Client,
+            apply_use_device(vec![
+                "transfer",
+                "--source",
+                A_SPENDING_KEY,
+                "--target",
+                AB_PAYMENT_ADDRESS,
+                "--token",
+                NAM,
+                "--amount",
+                "100",
+                "--gas-payer",
+                ALBERT_KEY,
+                "--masp-prover",
+                &prover_url,
+                "--ledger-address",
+                validator_one_rpc,
+            ]),
+        )
+    });
+    assert!(captured.result.is_ok());
+    assert!(captured.contains(TX_APPLIED_SUCCESS));
+    assert!(served.load(Ordering::SeqCst) > shield_proofs);
+
+    // 3. Check that the transfer went through
+    run(
+        &node,
+        Bin::Client,
+        vec![
+            "shielded-sync",
+            "--viewing-keys",
+            AA_VIEWING_KEY,
+            AB_VIEWING_KEY,
+            "--node",
+            validator_one_rpc,
+        ],
+    )?;
+    let captured = CapturedOutput::of(|| {
+        run(
+            &node,
+            Bin::Client,
+            vec![
+                "balance",
+                "--owner",
+                AB_VIEWING_KEY,
+                "--token",
+                NAM,
+                "--node",
+                validator_one_rpc,
+            ],
+        )
+    });
+    assert!(captured.result.is_ok());
+    assert!(captured.contains("nam: 100"));
+
+    shutdown_send.send(()).unwrap();
+    server.join().unwrap()?;
+
+    Ok(())
+}
+
 /// In this test we verify that users of the MASP receive the correct rewards
 /// for leaving their assets in the pool for varying periods of time.
 #[test]
@@ -21,11 +21,13 @@ mod tests {
 
     use std::collections::BTreeSet;
     use std::panic;
+    use std::str::FromStr;
 
+    use data_encoding::HEXLOWER;
     use itertools::Itertools;
     use namada_core::chain::testing::get_dummy_header;
     use namada_sdk::account::pks_handle;
-    use namada_sdk::borsh::BorshSerializeExt;
+    use namada_sdk::borsh::{BorshDeserialize, BorshSerializeExt};
     use namada_sdk::hash::Hash;
     use namada_sdk::ibc::context::nft_transfer_mod::testing::DummyNftTransferModule;
     use namada_sdk::ibc::context::transfer_mod::testing::DummyTransferModule;
@@ -364,6 +366,80 @@ mod tests {
         assert_eq!(expected, pred_epochs);
     }
 
+    /// The digests of `b"abc"` with SHA-256, Keccak-256 and BLAKE2b-256
+    const ABC_DIGESTS: [&str; 3] = [
+        "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+        "4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45",
+        "bddd813c634239723171ef3fee98579b94964e3bb1cb3e427262c8c068d52319",
+    ];
+
+    #[test]
+    fn test_tx_hash_functions() {
+        // The environment must be initialized first
+        tx_host_env::init();
+
+        let digests = [
+            tx::ctx().sha256(b"abc").unwrap(),
+            tx::ctx().keccak256(b"abc").unwrap(),
+            tx::ctx().blake2b(b"abc").unwrap(),
+        ];
+        for (digest, expected) in digests.into_iter().zip(ABC_DIGESTS) {
+            assert_eq!(digest, Hash::from_str(expected).unwrap().0);
+        }
+    }
+
+    /// A signature produced outside of Namada, with its public key and message
+    type ExternalSignature = (common::PublicKey, common::Signature, Vec<u8>);
+
+    /// An ed25519 signature from the test vectors of RFC 8032 (test 2), and a
+    /// secp256k1 signature of the message "Some data" produced by
+    /// `web3.eth.accounts.sign`, whose message is the Keccak-256 digest of the
+    /// Ethereum `personal_sign` message
+    fn external_signatures() -> [ExternalSignature; 2] {
+        let decode = |hex: &str| HEXLOWER.decode(hex.as_bytes()).unwrap();
+        let ed25519_pk = common::PublicKey::try_from_slice(&decode(
+            "003d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c",
+        ))
+        .unwrap();
+        let ed25519_sig = common::Signature::try_from_slice(&decode(
+            "0092a009a9f0d4cab8720e820b5f642540a2b27b5416503f8fb3762223ebdb69da\
+             085ac1e43e15996e458f3613d0f11d8c387b2eaeb4302aeeb00d291612bb0c00",
+        ))
+        .unwrap();
+        let secp256k1_pk = common::PublicKey::try_from_slice(&decode(
+            "01024e3b81af9c2234cad09d679ce6035ed1392347ce64ce405f5dcd36228a25de6e",
+        ))
+        .unwrap();
+        let secp256k1_sig = common::Signature::try_from_slice(&decode(
+            "01b91467e570a6466aa9e9876cbcd013baba02900b8979d43fe208a4a4f339f5fd\
+             6007e74cd82e037b800186422fc2da167c747ef045e5d18a5f5d4300f8e1a02901",
+        ))
+        .unwrap();
+        let prehash = namada_core::keccak::keccak_hash(
+            b"\x19Ethereum Signed Message:\n9Some data",
+        );
+        [
+            (ed25519_pk, ed25519_sig, vec![0x72]),
+            (secp256k1_pk, secp256k1_sig, prehash.0.to_vec()),
+        ]
+    }
+
+    #[test]
+    fn test_tx_verify_signature() {
+        // The environment must be initialized first
+        tx_host_env::init();
+
+        for (pk, sig, msg) in external_signatures() {
+            assert!(tx::ctx().verify_signature(&pk, &sig, &msg).unwrap());
+            let mut other_msg = msg.clone();
+            other_msg[0] ^= 1;
+            assert!(
+                !tx::ctx().verify_signature(&pk, &sig, &other_msg).unwrap(),
+                "The signature of another message must be rejected"
+            );
+        }
+    }
+
     /// An example how to write a VP host environment integration test
     #[test]
     fn test_vp_host_env() {
@@ -588,6 +664,71 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_vp_hash_functions() {
+        // The environment must be initialized first
+        vp_host_env::init();
+
+        let digests = [
+            vp::CTX.sha256(b"abc").unwrap(),
+            vp::CTX.keccak256(b"abc").unwrap(),
+            vp::CTX.blake2b(b"abc").unwrap(),
+        ];
+        for (digest, expected) in digests.into_iter().zip(ABC_DIGESTS) {
+            assert_eq!(digest, Hash::from_str(expected).unwrap().0);
+        }
+    }
+
+    #[test]
+    fn test_vp_verify_signature() {
+        // The environment must be initialized first
+        vp_host_env::init();
+
+        let [ed25519, secp256k1] = external_signatures();
+        for (pk, sig, msg) in [ed25519.clone(), secp256k1.clone()] {
+            assert!(vp::CTX.verify_signature(&pk, &sig, &msg).unwrap());
+            let mut other_msg = msg.clone();
+            other_msg[0] ^= 1;
+            assert!(
+                !vp::CTX.verify_signature(&pk, &sig, &other_msg).unwrap(),
+                "The signature of another message must be rejected"
+            );
+        }
+        let (ed25519_pk, _, ed25519_msg) = ed25519;
+        let (secp256k1_pk, secp256k1_sig, secp256k1_msg) = secp256k1;
+        assert!(
+            !vp::CTX
+                .verify_signature(&ed25519_pk, &secp256k1_sig, &ed25519_msg)
+                .unwrap(),
+            "The signature of another scheme must be rejected"
+        );
+        let other_pk = key::testing::keypair_3().ref_to();
+        assert!(
+            !vp::CTX
+                .verify_signature(&other_pk, &secp256k1_sig, &secp256k1_msg)
+                .unwrap(),
+            "The signature of another key must be rejected"
+        );
+        // Ed25519 signatures are not verified over the hash of the message,
+        // and secp256k1 signatures only over a 32 bytes prehash
+        let sig =
+            common::SigScheme::sign(&key::testing::keypair_1(), b"attestation");
+        assert!(
+            !vp::CTX
+                .verify_signature(
+                    &key::testing::keypair_1().ref_to(),
+                    &sig,
+                    b"attestation"
+                )
+                .unwrap()
+        );
+        assert!(
+            !vp::CTX
+                .verify_signature(&secp256k1_pk, &secp256k1_sig, b"Some data")
+                .unwrap()
+        );
+    }
+
     #[test]
     fn test_vp_get_metadata() {
         // The environment must be initialized first
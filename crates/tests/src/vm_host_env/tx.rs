@@ -528,6 +528,21 @@ mod native_tx_host_env {
         public_keys_map_len: u64,
         threshold: u8,
     ) -> i64);
+    native_host_fn!(tx_sha256(data_ptr: u64, data_len: u64, result_ptr: u64));
+    native_host_fn!(tx_keccak256(
+        data_ptr: u64,
+        data_len: u64,
+        result_ptr: u64
+    ));
+    native_host_fn!(tx_blake2b(data_ptr: u64, data_len: u64, result_ptr: u64));
+    native_host_fn!(tx_verify_signature(
+        pk_ptr: u64,
+        pk_len: u64,
+        sig_ptr: u64,
+        sig_len: u64,
+        msg_ptr: u64,
+        msg_len: u64,
+    ) -> i64);
     native_host_fn!(tx_update_masp_note_commitment_tree(
         transaction_ptr: u64,
         transaction_len: u64,
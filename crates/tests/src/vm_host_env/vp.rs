@@ -348,6 +348,21 @@ mod native_vp_host_env {
         signer_len: u64,
         threshold: u8,
     ));
+    native_host_fn!(vp_sha256(data_ptr: u64, data_len: u64, result_ptr: u64));
+    native_host_fn!(vp_keccak256(
+        data_ptr: u64,
+        data_len: u64,
+        result_ptr: u64
+    ));
+    native_host_fn!(vp_blake2b(data_ptr: u64, data_len: u64, result_ptr: u64));
+    native_host_fn!(vp_verify_signature(
+        pk_ptr: u64,
+        pk_len: u64,
+        sig_ptr: u64,
+        sig_len: u64,
+        msg_ptr: u64,
+        msg_len: u64,
+    ) -> i64);
     native_host_fn!(vp_charge_gas(used_gas: u64));
     native_host_fn!(vp_yield_value(buf_ptr: u64, buf_len: u64));
 }
@@ -7,6 +7,7 @@ pub mod pgf;
 pub mod pos;
 /// transaction protocols made by validators
 pub mod protocol;
+pub mod trace;
 /// wrapper txs
 pub mod wrapper;
 
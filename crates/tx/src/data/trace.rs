@@ -0,0 +1,207 @@
+//! Execution traces of txs, recorded when dry-running a tx to find out which
+//! host functions it called, which storage keys it accessed, where its gas was
+//! spent and why its validity predicates rejected it.
+
+use std::collections::BTreeMap;
+use std::fmt::{self, Display};
+
+use namada_core::address::Address;
+use namada_core::borsh::{BorshDeserialize, BorshSerialize};
+use namada_core::hash::Hash;
+use namada_core::storage;
+use namada_gas::Gas;
+use serde::{Deserialize, Serialize};
+
+use super::DryRunResult;
+
+/// A recorded call of a host function or of a storage access
+#[derive(
+    Debug,
+    Clone,
+    Default,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+    Serialize,
+    Deserialize,
+)]
+pub struct TraceCall {
+    /// The name of the called function
+    pub name: String,
+    /// The storage key accessed by the call, if any
+    pub key: Option<storage::Key>,
+    /// The length of the value read or written by the call, if any
+    pub value_len: Option<u64>,
+    /// The gas consumed by the call, including its nested calls
+    pub gas: Gas,
+    /// The calls nested in this call
+    pub calls: Vec<TraceCall>,
+}
+
+/// The verdict of a validity predicate on an inner tx
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+    Serialize,
+    Deserialize,
+)]
+pub enum VpVerdict {
+    /// The VP accepted the tx
+    Accepted,
+    /// The VP rejected the tx or failed to run
+    Rejected {
+        /// The reason of the rejection
+        reason: String,
+    },
+}
+
+/// The recorded execution of a validity predicate
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+    Serialize,
+    Deserialize,
+)]
+pub struct VpTrace {
+    /// The address of the VP owner
+    pub addr: Address,
+    /// The verdict of the VP
+    pub verdict: VpVerdict,
+    /// The gas consumed by the VP
+    pub gas: Gas,
+    /// The calls made by the VP
+    pub calls: Vec<TraceCall>,
+}
+
+/// The recorded execution of an inner tx and of its validity predicates
+#[derive(
+    Debug,
+    Clone,
+    Default,
+    PartialEq,
+    BorshSerialize,
+    BorshDeserialize,
+    Serialize,
+    Deserialize,
+)]
+pub struct InnerTxTrace {
+    /// The calls made by the tx code
+    pub calls: Vec<TraceCall>,
+    /// The VPs triggered by the tx, in order of evaluation
+    pub vps: Vec<VpTrace>,
+}
+
+/// The result of a dry run together with the traces of the inner txs that
+/// were executed, indexed by the inner tx hash
+#[derive(Debug, BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
+pub struct DryRunTrace {
+    /// The result of the dry run
+    pub result: DryRunResult,
+    /// The traces of the executed inner txs
+    pub traces: BTreeMap<Hash, InnerTxTrace>,
+}
+
+impl Display for TraceCall {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+        if let Some(key) = &self.key {
+            write!(f, " {key}")?;
+        }
+        if let Some(value_len) = self.value_len {
+            write!(f, " ({value_len} bytes)")?;
+        }
+        write!(f, ", gas: {}", u64::from(self.gas.clone()))
+    }
+}
+
+impl Display for VpVerdict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VpVerdict::Accepted => write!(f, "accepted"),
+            VpVerdict::Rejected { reason } => write!(f, "rejected: {reason}"),
+        }
+    }
+}
+
+impl Display for InnerTxTrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Tx code")?;
+        fmt_calls(f, &self.calls, "  ")?;
+        for vp in &self.vps {
+            writeln!(
+                f,
+                "VP of {} {}, gas: {}",
+                vp.addr,
+                vp.verdict,
+                u64::from(vp.gas.clone())
+            )?;
+            fmt_calls(f, &vp.calls, "  ")?;
+        }
+        Ok(())
+    }
+}
+
+/// Write the tree of the given calls, one call per line
+fn fmt_calls(
+    f: &mut fmt::Formatter<'_>,
+    calls: &[TraceCall],
+    indent: &str,
+) -> fmt::Result {
+    for call in calls {
+        writeln!(f, "{indent}{call}")?;
+        fmt_calls(f, &call.calls, &format!("{indent}  "))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display_inner_tx_trace() {
+        let key = storage::Key::parse("key").unwrap();
+        let trace = InnerTxTrace {
+            calls: vec![TraceCall {
+                name: "namada_tx_read".to_string(),
+                key: Some(key.clone()),
+                value_len: Some(8),
+                gas: 10.into(),
+                calls: vec![],
+            }],
+            vps: vec![VpTrace {
+                addr: namada_core::address::testing::established_address_1(),
+                verdict: VpVerdict::Rejected {
+                    reason: "Unauthorized".to_string(),
+                },
+                gas: 30.into(),
+                calls: vec![TraceCall {
+                    name: "namada_vp_read_post".to_string(),
+                    gas: 20.into(),
+                    calls: vec![TraceCall {
+                        name: "read_post".to_string(),
+                        key: Some(key),
+                        gas: 5.into(),
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                }],
+            }],
+        };
+        let addr = namada_core::address::testing::established_address_1();
+        assert_eq!(
+            trace.to_string(),
+            format!(
+                "Tx code\n  namada_tx_read key (8 bytes), gas: 10\nVP of \
+                 {addr} rejected: Unauthorized, gas: 30\n  \
+                 namada_vp_read_post, gas: 20\n    read_post key, gas: 5\n"
+            )
+        );
+    }
+}
@@ -488,6 +488,36 @@ impl Authorization {
         }
     }
 
+    /// Sign the given section hash with the given signing keys, which need
+    /// not expose their secret material, and return a section identifying the
+    /// signer by the keys' public keys. Fails if any of the keys fails to sign.
+    pub fn from_signing_keys<K: common::SigningKey>(
+        targets: Vec<namada_core::hash::Hash>,
+        signing_keys: Vec<K>,
+    ) -> std::result::Result<Self, K::Error> {
+        let partial = Self {
+            targets,
+            signer: Signer::PubKeys(
+                signing_keys.iter().map(K::public_key).collect(),
+            ),
+            signatures: BTreeMap::new(),
+        };
+        let target = partial.get_raw_hash();
+        let signatures = signing_keys
+            .iter()
+            .enumerate()
+            .map(|(index, signing_key)| {
+                let index = u8::try_from(index)
+                    .expect("Number of SKs must not exceed `u8::MAX`");
+                Ok((index, signing_key.try_sign(target)?))
+            })
+            .collect::<std::result::Result<_, _>>()?;
+        Ok(Self {
+            signatures,
+            ..partial
+        })
+    }
+
     /// Hash this signature section
     pub fn hash<'a>(&self, hasher: &'a mut Sha256) -> &'a mut Sha256 {
         hasher.update(self.serialize_to_vec());
@@ -116,6 +116,17 @@ impl<T, S: Signable<T>> Signed<T, S> {
         Self::new_from(data, sig)
     }
 
+    /// Initialize a new [`Signed`] instance with a signing key that may fail
+    /// to sign, e.g. a key held in an HSM.
+    pub fn try_new<K: common::SigningKey>(
+        signing_key: &K,
+        data: T,
+    ) -> std::result::Result<Self, K::Error> {
+        let to_sign = S::as_signable(&data);
+        let sig = signing_key.try_sign_with_hasher::<S::Hasher>(to_sign)?;
+        Ok(Self::new_from(data, sig))
+    }
+
     /// Verify that the data has been signed by the secret key
     /// counterpart of the given public key.
     pub fn verify(
@@ -22,6 +22,8 @@ pub use namada_core::address::Address;
 pub use namada_core::borsh::{
     BorshDeserialize, BorshSerialize, BorshSerializeExt,
 };
+use namada_core::hash::HASH_LENGTH;
+use namada_core::key::common;
 pub use namada_core::masp::MaspTransaction;
 pub use namada_core::storage;
 pub use namada_events::{Event, EventToEmit, EventType};
@@ -105,6 +107,29 @@ pub trait TxEnv: StorageRead + StorageWrite {
     /// Set the sentinel for an invalid section commitment
     fn set_commitment_sentinel(&mut self);
 
+    /// Hash the data with SHA-256 in the host environment
+    fn sha256(&self, data: &[u8]) -> Result<[u8; HASH_LENGTH]>;
+
+    /// Hash the data with Keccak-256 in the host environment
+    fn keccak256(&self, data: &[u8]) -> Result<[u8; HASH_LENGTH]>;
+
+    /// Hash the data with BLAKE2b in the host environment, with a 32 bytes
+    /// digest
+    fn blake2b(&self, data: &[u8]) -> Result<[u8; HASH_LENGTH]>;
+
+    /// Verify a signature produced outside of Namada over an arbitrary
+    /// message in the host environment. Ed25519 signatures are verified over
+    /// the message itself, while the message of a secp256k1 signature must be
+    /// the 32 bytes prehash that was signed, e.g. the Keccak-256 digest of an
+    /// Ethereum `personal_sign` message. Returns `false` if the signature is
+    /// not valid.
+    fn verify_signature(
+        &self,
+        pk: &common::PublicKey,
+        sig: &common::Signature,
+        msg: &[u8],
+    ) -> Result<bool>;
+
     /// Update the masp note commitment tree in storage with the new notes
     fn update_masp_note_commitment_tree(
         transaction: &MaspTransaction,
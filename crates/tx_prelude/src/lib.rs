@@ -34,6 +34,7 @@ pub use namada_core::chain::{
     BlockHash, BlockHeader, BlockHeight, Epoch, BLOCK_HASH_LENGTH,
 };
 pub use namada_core::ethereum_events::EthAddress;
+use namada_core::hash::HASH_LENGTH;
 use namada_core::internal::HostEnvResult;
 use namada_core::key::common;
 use namada_core::storage::TxIndex;
@@ -53,7 +54,10 @@ use namada_token::MaspTransaction;
 pub use namada_tx::{action, data as transaction, BatchedTx, Section, Tx};
 pub use namada_tx_env::TxEnv;
 use namada_vm_env::tx::*;
-use namada_vm_env::{read_from_buffer, read_key_val_bytes_from_buffer};
+use namada_vm_env::{
+    hash_with, read_from_buffer, read_key_val_bytes_from_buffer,
+    verify_signature_with,
+};
 pub use {
     namada_gas as gas, namada_governance as governance,
     namada_parameters as parameters,
@@ -407,6 +411,32 @@ impl TxEnv for Ctx {
         unsafe { namada_tx_set_commitment_sentinel() }
     }
 
+    fn sha256(&self, data: &[u8]) -> Result<[u8; HASH_LENGTH]> {
+        Ok(hash_with(data, namada_tx_sha256))
+    }
+
+    fn keccak256(&self, data: &[u8]) -> Result<[u8; HASH_LENGTH]> {
+        Ok(hash_with(data, namada_tx_keccak256))
+    }
+
+    fn blake2b(&self, data: &[u8]) -> Result<[u8; HASH_LENGTH]> {
+        Ok(hash_with(data, namada_tx_blake2b))
+    }
+
+    fn verify_signature(
+        &self,
+        pk: &common::PublicKey,
+        sig: &common::Signature,
+        msg: &[u8],
+    ) -> Result<bool> {
+        Ok(verify_signature_with(
+            pk,
+            sig,
+            msg,
+            namada_tx_verify_signature,
+        ))
+    }
+
     fn update_masp_note_commitment_tree(
         transaction: &MaspTransaction,
     ) -> Result<bool> {
@@ -14,6 +14,7 @@ use namada_core::chain::BlockHeight;
 use namada_core::decode;
 use namada_core::hash::Hash;
 use namada_core::internal::{HostEnvResult, KeyVal};
+use namada_core::key::common;
 use namada_core::storage::{Key, TxIndex, TX_INDEX_LENGTH};
 use namada_events::{Event, EventTypeBuilder};
 use namada_gas::{
@@ -34,7 +35,7 @@ use namada_token::storage_key::{
 use namada_token::MaspTransaction;
 use namada_tx::data::TxSentinel;
 use namada_tx::{BatchedTx, BatchedTxRef, Tx, TxCommitments};
-use namada_vp::vp_host_fns;
+use namada_vp::{trace, vp_host_fns};
 use thiserror::Error;
 
 #[cfg(feature = "wasm-runtime")]
@@ -657,6 +658,8 @@ where
 
     let key = Key::parse(key)?;
 
+    trace::storage_access(&key, None);
+
     // try to read from the write log first
     let state = env.state();
     let present = state.has_key(&key)?;
@@ -691,6 +694,7 @@ where
 
     let state = env.state();
     let value = state.read_bytes(&key)?;
+    trace::storage_access(&key, value.as_ref().map(Vec::len));
     match value {
         Some(value) => {
             let len: i64 = value
@@ -735,6 +739,7 @@ where
     let write_log = unsafe { env.ctx.write_log.get() };
     let (log_val, gas) = write_log.read_temp(&key).into_storage_result()?;
     consume_tx_gas::<MEM, D, H, CA>(env, gas)?;
+    trace::storage_access(&key, log_val.map(Vec::len));
     match log_val {
         Some(value) => {
             let len: i64 = value
@@ -801,6 +806,7 @@ where
     tracing::debug!("tx_iter_prefix {}", prefix);
 
     let prefix = Key::parse(prefix)?;
+    trace::storage_access(&prefix, None);
 
     let write_log = unsafe { env.ctx.write_log.get() };
     let db = unsafe { env.ctx.db.get() };
@@ -835,17 +841,17 @@ where
     let iterators = unsafe { env.ctx.iterators.get_mut() };
     let iter_id = PrefixIteratorId::new(iter_id);
     while let Some((key, val, iter_gas)) = iterators.next(iter_id) {
+        let storage_key = Key::parse(key.clone())?;
         let (log_val, log_gas) = {
             let state = env.state();
-            let (log_val, log_gas) = state
-                .write_log()
-                .read(&Key::parse(key.clone())?)
-                .into_storage_result()?;
+            let (log_val, log_gas) =
+                state.write_log().read(&storage_key).into_storage_result()?;
             (log_val.cloned(), log_gas)
         };
         consume_tx_gas::<MEM, D, H, CA>(env, checked!(iter_gas + log_gas)?)?;
         match log_val {
             Some(write_log::StorageModification::Write { value }) => {
+                trace::storage_access(&storage_key, Some(value.len()));
                 let key_val = borsh::to_vec(&KeyVal { key, val: value })
                     .map_err(TxRuntimeError::EncodingError)?;
                 let len: i64 = key_val
@@ -865,6 +871,7 @@ where
                 continue;
             }
             None => {
+                trace::storage_access(&storage_key, Some(val.len()));
                 let key_val = borsh::to_vec(&KeyVal { key, val })
                     .map_err(TxRuntimeError::EncodingError)?;
                 let len: i64 = key_val
@@ -914,6 +921,7 @@ where
     }
 
     check_address_existence::<MEM, D, H, CA>(env, &key)?;
+    trace::storage_access(&key, Some(value.len()));
 
     let mut state = env.state();
     state.write_bytes(&key, value)
@@ -951,6 +959,7 @@ where
     let key = Key::parse(key)?;
 
     check_address_existence::<MEM, D, H, CA>(env, &key)?;
+    trace::storage_access(&key, Some(value.len()));
 
     let mut state = env.state();
     let (gas, _size_diff) = state.write_log_mut().write_temp(&key, value)?;
@@ -1028,6 +1037,7 @@ where
     if key.is_validity_predicate().is_some() {
         return Err(TxRuntimeError::CannotDeleteVp.into());
     }
+    trace::storage_access(&key, None);
 
     let mut state = env.state();
     state.delete(&key)
@@ -1993,6 +2003,149 @@ where
     }
 }
 
+/// Hash the data with the given hash function and write the digest to the
+/// result pointer
+fn vp_hash<MEM, D, H, EVAL, CA>(
+    env: &mut VpVmEnv<MEM, D, H, EVAL, CA>,
+    function: vp_host_fns::HashFunction,
+    data_ptr: u64,
+    data_len: u64,
+    result_ptr: u64,
+) -> Result<()>
+where
+    MEM: VmMemory,
+    D: 'static + DB + for<'iter> DBIter<'iter>,
+    H: 'static + StorageHasher,
+    EVAL: VpEvaluator,
+    CA: WasmCacheAccess,
+{
+    let (data, gas) = env
+        .memory
+        .read_bytes(data_ptr, data_len.try_into()?)
+        .map_err(Into::into)?;
+    let gas_meter = env.ctx.gas_meter();
+    vp_host_fns::add_gas(gas_meter, gas)?;
+
+    let digest = vp_host_fns::hash(gas_meter, function, &data)?;
+
+    let gas = env
+        .memory
+        .write_bytes(result_ptr, digest)
+        .map_err(Into::into)?;
+    vp_host_fns::add_gas(gas_meter, gas)
+}
+
+/// SHA-256 hash function exposed to the wasm VM VP environment
+pub fn vp_sha256<MEM, D, H, EVAL, CA>(
+    env: &mut VpVmEnv<MEM, D, H, EVAL, CA>,
+    data_ptr: u64,
+    data_len: u64,
+    result_ptr: u64,
+) -> Result<()>
+where
+    MEM: VmMemory,
+    D: 'static + DB + for<'iter> DBIter<'iter>,
+    H: 'static + StorageHasher,
+    EVAL: VpEvaluator,
+    CA: WasmCacheAccess,
+{
+    vp_hash(
+        env,
+        vp_host_fns::HashFunction::Sha256,
+        data_ptr,
+        data_len,
+        result_ptr,
+    )
+}
+
+/// Keccak-256 hash function exposed to the wasm VM VP environment
+pub fn vp_keccak256<MEM, D, H, EVAL, CA>(
+    env: &mut VpVmEnv<MEM, D, H, EVAL, CA>,
+    data_ptr: u64,
+    data_len: u64,
+    result_ptr: u64,
+) -> Result<()>
+where
+    MEM: VmMemory,
+    D: 'static + DB + for<'iter> DBIter<'iter>,
+    H: 'static + StorageHasher,
+    EVAL: VpEvaluator,
+    CA: WasmCacheAccess,
+{
+    vp_hash(
+        env,
+        vp_host_fns::HashFunction::Keccak256,
+        data_ptr,
+        data_len,
+        result_ptr,
+    )
+}
+
+/// BLAKE2b hash function exposed to the wasm VM VP environment
+pub fn vp_blake2b<MEM, D, H, EVAL, CA>(
+    env: &mut VpVmEnv<MEM, D, H, EVAL, CA>,
+    data_ptr: u64,
+    data_len: u64,
+    result_ptr: u64,
+) -> Result<()>
+where
+    MEM: VmMemory,
+    D: 'static + DB + for<'iter> DBIter<'iter>,
+    H: 'static + StorageHasher,
+    EVAL: VpEvaluator,
+    CA: WasmCacheAccess,
+{
+    vp_hash(
+        env,
+        vp_host_fns::HashFunction::Blake2b,
+        data_ptr,
+        data_len,
+        result_ptr,
+    )
+}
+
+/// Verify a signature over an arbitrary message
+pub fn vp_verify_signature<MEM, D, H, EVAL, CA>(
+    env: &mut VpVmEnv<MEM, D, H, EVAL, CA>,
+    pk_ptr: u64,
+    pk_len: u64,
+    sig_ptr: u64,
+    sig_len: u64,
+    msg_ptr: u64,
+    msg_len: u64,
+) -> Result<i64>
+where
+    MEM: VmMemory,
+    D: 'static + DB + for<'iter> DBIter<'iter>,
+    H: 'static + StorageHasher,
+    EVAL: VpEvaluator,
+    CA: WasmCacheAccess,
+{
+    let (pk, gas) = env
+        .memory
+        .read_bytes(pk_ptr, pk_len.try_into()?)
+        .map_err(Into::into)?;
+    let gas_meter = env.ctx.gas_meter();
+    vp_host_fns::add_gas(gas_meter, gas)?;
+    let pk: common::PublicKey = decode(pk)?;
+
+    let (sig, gas) = env
+        .memory
+        .read_bytes(sig_ptr, sig_len.try_into()?)
+        .map_err(Into::into)?;
+    vp_host_fns::add_gas(gas_meter, gas)?;
+    let sig: common::Signature = decode(sig)?;
+
+    let (msg, gas) = env
+        .memory
+        .read_bytes(msg_ptr, msg_len.try_into()?)
+        .map_err(Into::into)?;
+    vp_host_fns::add_gas(gas_meter, gas)?;
+
+    let valid = vp_host_fns::verify_signature(gas_meter, &pk, &sig, &msg)?;
+    Ok(HostEnvResult::from(valid).to_i64())
+}
+
 /// Log a string from exposed to the wasm VM Tx environment. The message will be
 /// printed at the [`tracing::Level::INFO`]. This function is for development
 /// only.
@@ -2149,6 +2302,149 @@ where
     .into_storage_result()
 }
 
+/// Hash the data with the given hash function and write the digest to the
+/// result pointer
+fn tx_hash<MEM, D, H, CA>(
+    env: &mut TxVmEnv<MEM, D, H, CA>,
+    function: vp_host_fns::HashFunction,
+    data_ptr: u64,
+    data_len: u64,
+    result_ptr: u64,
+) -> TxResult<()>
+where
+    MEM: VmMemory,
+    D: 'static + DB + for<'iter> DBIter<'iter>,
+    H: 'static + StorageHasher,
+    CA: WasmCacheAccess,
+{
+    let (data, gas) = env
+        .memory
+        .read_bytes(data_ptr, data_len.try_into()?)
+        .map_err(|e| TxRuntimeError::MemoryError(Box::new(e)))?;
+    consume_tx_gas::<MEM, D, H, CA>(env, gas)?;
+
+    consume_tx_gas::<MEM, D, H, CA>(env, function.gas(data.len())?)?;
+    let digest = function.digest(&data);
+
+    let gas = env
+        .memory
+        .write_bytes(result_ptr, digest)
+        .map_err(|e| TxRuntimeError::MemoryError(Box::new(e)))?;
+    consume_tx_gas::<MEM, D, H, CA>(env, gas)
+}
+
+/// SHA-256 hash function exposed to the wasm VM Tx environment
+pub fn tx_sha256<MEM, D, H, CA>(
+    env: &mut TxVmEnv<MEM, D, H, CA>,
+    data_ptr: u64,
+    data_len: u64,
+    result_ptr: u64,
+) -> TxResult<()>
+where
+    MEM: VmMemory,
+    D: 'static + DB + for<'iter> DBIter<'iter>,
+    H: 'static + StorageHasher,
+    CA: WasmCacheAccess,
+{
+    tx_hash(
+        env,
+        vp_host_fns::HashFunction::Sha256,
+        data_ptr,
+        data_len,
+        result_ptr,
+    )
+}
+
+/// Keccak-256 hash function exposed to the wasm VM Tx environment
+pub fn tx_keccak256<MEM, D, H, CA>(
+    env: &mut TxVmEnv<MEM, D, H, CA>,
+    data_ptr: u64,
+    data_len: u64,
+    result_ptr: u64,
+) -> TxResult<()>
+where
+    MEM: VmMemory,
+    D: 'static + DB + for<'iter> DBIter<'iter>,
+    H: 'static + StorageHasher,
+    CA: WasmCacheAccess,
+{
+    tx_hash(
+        env,
+        vp_host_fns::HashFunction::Keccak256,
+        data_ptr,
+        data_len,
+        result_ptr,
+    )
+}
+
+/// BLAKE2b hash function exposed to the wasm VM Tx environment
+pub fn tx_blake2b<MEM, D, H, CA>(
+    env: &mut TxVmEnv<MEM, D, H, CA>,
+    data_ptr: u64,
+    data_len: u64,
+    result_ptr: u64,
+) -> TxResult<()>
+where
+    MEM: VmMemory,
+    D: 'static + DB + for<'iter> DBIter<'iter>,
+    H: 'static + StorageHasher,
+    CA: WasmCacheAccess,
+{
+    tx_hash(
+        env,
+        vp_host_fns::HashFunction::Blake2b,
+        data_ptr,
+        data_len,
+        result_ptr,
+    )
+}
+
+/// Verify a signature over an arbitrary message
+pub fn tx_verify_signature<MEM, D, H, CA>(
+    env: &mut TxVmEnv<MEM, D, H, CA>,
+    pk_ptr: u64,
+    pk_len: u64,
+    sig_ptr: u64,
+    sig_len: u64,
+    msg_ptr: u64,
+    msg_len: u64,
+) -> TxResult<i64>
+where
+    MEM: VmMemory,
+    D: 'static + DB + for<'iter> DBIter<'iter>,
+    H: 'static + StorageHasher,
+    CA: WasmCacheAccess,
+{
+    let (pk, gas) = env
+        .memory
+        .read_bytes(pk_ptr, pk_len.try_into()?)
+        .map_err(|e| TxRuntimeError::MemoryError(Box::new(e)))?;
+    consume_tx_gas::<MEM, D, H, CA>(env, gas)?;
+    let pk = common::PublicKey::try_from_slice(&pk)
+        .map_err(TxRuntimeError::EncodingError)?;
+
+    let (sig, gas) = env
+        .memory
+        .read_bytes(sig_ptr, sig_len.try_into()?)
+        .map_err(|e| TxRuntimeError::MemoryError(Box::new(e)))?;
+    consume_tx_gas::<MEM, D, H, CA>(env, gas)?;
+    let sig = common::Signature::try_from_slice(&sig)
+        .map_err(TxRuntimeError::EncodingError)?;
+
+    let (msg, gas) = env
+        .memory
+        .read_bytes(msg_ptr, msg_len.try_into()?)
+        .map_err(|e| TxRuntimeError::MemoryError(Box::new(e)))?;
+    consume_tx_gas::<MEM, D, H, CA>(env, gas)?;
+
+    consume_tx_gas::<MEM, D, H, CA>(
+        env,
+        vp_host_fns::verify_signature_gas(&pk, msg.len())?,
+    )?;
+    let valid = common::verify_raw_signature(&pk, &msg, &sig).is_ok();
+    Ok(HostEnvResult::from(valid).to_i64())
+}
+
 /// Appends the new note commitments to the tree in storage
 pub fn tx_update_masp_note_commitment_tree<MEM, D, H, CA>(
     env: &mut TxVmEnv<MEM, D, H, CA>,
@@ -17,8 +17,10 @@
     clippy::print_stderr
 )]
 
-use namada_core::borsh::BorshDeserialize;
+use namada_core::borsh::{BorshDeserialize, BorshSerializeExt};
+use namada_core::hash::HASH_LENGTH;
 pub use namada_core::internal::{HostEnvResult, KeyVal};
+use namada_core::key::common;
 
 /// Transaction environment imports
 pub mod tx {
@@ -146,6 +148,34 @@ pub mod tx {
             threshold: u8,
         ) -> i64;
 
+        /// Hash the data with SHA-256 and write the 32 bytes digest to the
+        /// result pointer
+        pub fn namada_tx_sha256(data_ptr: u64, data_len: u64, result_ptr: u64);
+
+        /// Hash the data with Keccak-256 and write the 32 bytes digest to
+        /// the result pointer
+        pub fn namada_tx_keccak256(
+            data_ptr: u64,
+            data_len: u64,
+            result_ptr: u64,
+        );
+
+        /// Hash the data with BLAKE2b and write the 32 bytes digest to the
+        /// result pointer
+        pub fn namada_tx_blake2b(data_ptr: u64, data_len: u64, result_ptr: u64);
+
+        /// Verify a signature over an arbitrary message, which is a 32 bytes
+        /// prehash for secp256k1 signatures. Returns 1 if the signature is
+        /// valid, -1 otherwise.
+        pub fn namada_tx_verify_signature(
+            pk_ptr: u64,
+            pk_len: u64,
+            sig_ptr: u64,
+            sig_len: u64,
+            msg_ptr: u64,
+            msg_len: u64,
+        ) -> i64;
+
         /// Update the masp note commitment tree with the new notes
         pub fn namada_tx_update_masp_note_commitment_tree(
             transaction_ptr: u64,
@@ -260,6 +290,34 @@ pub mod vp {
             threshold: u8,
         );
 
+        /// Hash the data with SHA-256 and write the 32 bytes digest to the
+        /// result pointer
+        pub fn namada_vp_sha256(data_ptr: u64, data_len: u64, result_ptr: u64);
+
+        /// Hash the data with Keccak-256 and write the 32 bytes digest to
+        /// the result pointer
+        pub fn namada_vp_keccak256(
+            data_ptr: u64,
+            data_len: u64,
+            result_ptr: u64,
+        );
+
+        /// Hash the data with BLAKE2b and write the 32 bytes digest to the
+        /// result pointer
+        pub fn namada_vp_blake2b(data_ptr: u64, data_len: u64, result_ptr: u64);
+
+        /// Verify a signature over an arbitrary message, which is a 32 bytes
+        /// prehash for secp256k1 signatures. Returns 1 if the signature is
+        /// valid, -1 otherwise.
+        pub fn namada_vp_verify_signature(
+            pk_ptr: u64,
+            pk_len: u64,
+            sig_ptr: u64,
+            sig_len: u64,
+            msg_ptr: u64,
+            msg_len: u64,
+        ) -> i64;
+
         /// Evaluate a validity-predicate
         pub fn namada_vp_eval(
             vp_code_hash_ptr: u64,
@@ -306,3 +364,38 @@ pub fn read_key_val_bytes_from_buffer(
         .and_then(|t| KeyVal::try_from_slice(&t[..]).ok());
     key_val.map(|key_val| (key_val.key, key_val.val))
 }
+
+/// This function is a helper to hash data with one of the hash functions of
+/// the host, which writes the digest to a pre-allocated buffer.
+pub fn hash_with(
+    data: &[u8],
+    hash_fn: unsafe extern "C" fn(u64, u64, u64),
+) -> [u8; HASH_LENGTH] {
+    let mut digest = [0u8; HASH_LENGTH];
+    let offset = digest.as_mut_ptr() as u64;
+    unsafe { hash_fn(data.as_ptr() as _, data.len() as _, offset) };
+    digest
+}
+
+/// This function is a helper to verify a signature over an arbitrary message
+/// with the signature verification function of the host.
+pub fn verify_signature_with(
+    pk: &common::PublicKey,
+    sig: &common::Signature,
+    msg: &[u8],
+    verify_fn: unsafe extern "C" fn(u64, u64, u64, u64, u64, u64) -> i64,
+) -> bool {
+    let pk = pk.serialize_to_vec();
+    let sig = sig.serialize_to_vec();
+    let valid = unsafe {
+        verify_fn(
+            pk.as_ptr() as _,
+            pk.len() as _,
+            sig.as_ptr() as _,
+            sig.len() as _,
+            msg.as_ptr() as _,
+            msg.len() as _,
+        )
+    };
+    HostEnvResult::is_success(valid)
+}
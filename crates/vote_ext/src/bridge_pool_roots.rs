@@ -92,6 +92,16 @@ impl Vext {
     pub fn sign(&self, sk: &common::SecretKey) -> SignedVext {
         SignedVext(Signed::new(sk, self.clone()))
     }
+
+    /// Creates a new signed [`Vext`] with a signing key that may fail to
+    /// sign, e.g. a key held in an HSM.
+    #[inline]
+    pub fn try_sign<K: common::SigningKey>(
+        &self,
+        signing_key: &K,
+    ) -> Result<SignedVext, K::Error> {
+        Signed::try_new(signing_key, self.clone()).map(SignedVext)
+    }
 }
 
 /// A collection of validator signatures over the
@@ -88,6 +88,15 @@ impl Vext {
     pub fn sign(self, signing_key: &common::SecretKey) -> Signed<Self> {
         Signed::new(signing_key, self)
     }
+
+    /// Sign a [`Vext`] with a validator's `signing_key`, which may fail to
+    /// sign, e.g. if it is held in an HSM.
+    pub fn try_sign<K: common::SigningKey>(
+        self,
+        signing_key: &K,
+    ) -> Result<Signed<Self>, K::Error> {
+        Signed::try_new(signing_key, self)
+    }
 }
 
 /// Aggregates an Ethereum event with the corresponding
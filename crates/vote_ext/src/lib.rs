@@ -190,6 +190,30 @@ impl EthereumTxData {
         outer_tx
     }
 
+    /// Sign transaction Ethereum data and wrap it in a [`Tx`], with a signing
+    /// key that may fail to sign, e.g. a key held in an HSM.
+    pub fn try_sign<K: common::SigningKey>(
+        &self,
+        signing_key: &K,
+        chain_id: ChainId,
+    ) -> Result<Tx, K::Error> {
+        let (tx_data, tx_type) = self.serialize();
+        let mut outer_tx =
+            Tx::from_type(TxType::Protocol(Box::new(ProtocolTx {
+                pk: signing_key.public_key(),
+                tx: tx_type,
+            })));
+        outer_tx.header.chain_id = chain_id;
+        outer_tx.set_data(namada_tx::Data::new(tx_data));
+        outer_tx.add_section(namada_tx::Section::Authorization(
+            Authorization::from_signing_keys(
+                outer_tx.sechashes(),
+                vec![signing_key],
+            )?,
+        ));
+        Ok(outer_tx)
+    }
+
     /// Serialize Ethereum protocol transaction data.
     pub fn serialize(&self) -> (Vec<u8>, ProtocolTxType) {
         macro_rules! match_of_type {
@@ -19,6 +19,7 @@
 )]
 
 pub mod native_vp;
+pub mod trace;
 pub mod vp_host_fns;
 
 pub use namada_core::address::Address;
@@ -10,11 +10,13 @@ use std::marker::PhantomData;
 use namada_core::borsh;
 use namada_core::borsh::BorshDeserialize;
 use namada_core::chain::{ChainId, Epochs};
+use namada_core::hash::HASH_LENGTH;
+use namada_core::key::common;
 use namada_gas::{Gas, GasMetering, VpGasMeter};
 use namada_state::{ConversionState, ReadConversionState};
 use namada_tx::{BatchedTxRef, Tx, TxCommitments};
 
-use super::vp_host_fns;
+use super::vp_host_fns::{self, HashFunction};
 use crate::state::prefix_iter::PrefixIterators;
 use crate::state::{
     BlockHeader, BlockHeight, Epoch, Key, PrefixIter, StateRead, StorageRead,
@@ -424,6 +426,31 @@ where
         .into_storage_result()
     }
 
+    fn sha256(&self, data: &[u8]) -> Result<[u8; HASH_LENGTH]> {
+        vp_host_fns::hash(self.gas_meter, HashFunction::Sha256, data)
+            .into_storage_result()
+    }
+
+    fn keccak256(&self, data: &[u8]) -> Result<[u8; HASH_LENGTH]> {
+        vp_host_fns::hash(self.gas_meter, HashFunction::Keccak256, data)
+            .into_storage_result()
+    }
+
+    fn blake2b(&self, data: &[u8]) -> Result<[u8; HASH_LENGTH]> {
+        vp_host_fns::hash(self.gas_meter, HashFunction::Blake2b, data)
+            .into_storage_result()
+    }
+
+    fn verify_signature(
+        &self,
+        pk: &common::PublicKey,
+        sig: &common::Signature,
+        msg: &[u8],
+    ) -> Result<bool> {
+        vp_host_fns::verify_signature(self.gas_meter, pk, sig, msg)
+            .into_storage_result()
+    }
+
     fn read_pre<T: borsh::BorshDeserialize>(
         &self,
         key: &Key,
@@ -0,0 +1,320 @@
+//! Recording of the execution traces of txs and validity predicates.
+//!
+//! Tracing is opt-in: the host functions are only recorded while a trace is
+//! being recorded by [`record`] on the thread that runs them. The VPs may run
+//! on other threads than their tx, so they are recorded separately with
+//! [`record_vp`] and added to the trace of their tx with [`push_vp`].
+//!
+//! As the host functions are traced on every call, including the gas metering
+//! hook, the recorders are only looked up while some thread is recording.
+
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use namada_core::hash::Hash;
+use namada_core::storage::Key;
+use namada_gas::Gas;
+pub use namada_tx::data::trace::{InnerTxTrace, TraceCall, VpTrace, VpVerdict};
+
+/// The name of the wasm gas metering hook. Its consecutive calls are merged
+/// into a single call to keep the traces readable.
+const GAS_HOOK: &str = "gas";
+
+thread_local! {
+    static RECORDER: RefCell<Option<Recorder>> = const { RefCell::new(None) };
+}
+
+/// The number of recorders installed across all threads. The thread-local
+/// recorder is never installed while this is zero, which lets the untraced
+/// executions skip it with a single atomic load.
+static INSTALLED_RECORDERS: AtomicUsize = AtomicUsize::new(0);
+
+/// The recorder of the traces of a thread
+#[derive(Debug)]
+struct Recorder {
+    /// The calls being recorded, innermost last. The first one is a dummy
+    /// call collecting the top-level calls.
+    stack: Vec<TraceCall>,
+    /// The traces of the VPs of the inner tx being recorded
+    vps: Vec<VpTrace>,
+    /// The traces of the recorded inner txs, with the hashes of their
+    /// commitments
+    inner_txs: Vec<(Hash, InnerTxTrace)>,
+}
+
+impl Recorder {
+    fn new() -> Self {
+        Self {
+            stack: vec![TraceCall::default()],
+            vps: vec![],
+            inner_txs: vec![],
+        }
+    }
+
+    /// Take the top-level calls, resetting the stack
+    fn take_calls(&mut self) -> Vec<TraceCall> {
+        std::mem::replace(&mut self.stack, vec![TraceCall::default()])
+            .into_iter()
+            .next()
+            .map(|root| root.calls)
+            .unwrap_or_default()
+    }
+
+    /// Finish the innermost call with the gas it consumed
+    fn exit(&mut self, gas: Gas) {
+        if self.stack.len() < 2 {
+            return;
+        }
+        let Some(mut call) = self.stack.pop() else {
+            return;
+        };
+        call.gas = gas;
+        let Some(parent) = self.stack.last_mut() else {
+            return;
+        };
+        match parent.calls.last_mut() {
+            Some(last) if call.name == GAS_HOOK && last.name == GAS_HOOK => {
+                last.gas = last
+                    .gas
+                    .checked_add(call.gas)
+                    .unwrap_or_else(|| u64::MAX.into());
+            }
+            _ => parent.calls.push(call),
+        }
+    }
+}
+
+/// Apply `f` to the recorder of this thread, if a trace is being recorded
+fn with_recorder<T>(f: impl FnOnce(&mut Recorder) -> T) -> Option<T> {
+    if INSTALLED_RECORDERS.load(Ordering::Relaxed) == 0 {
+        return None;
+    }
+    RECORDER.with(|recorder| recorder.borrow_mut().as_mut().map(f))
+}
+
+/// Run `f` with the given recorder installed on this thread, restoring the
+/// previous one afterwards
+fn run_with_recorder<T>(
+    recorder: Recorder,
+    f: impl FnOnce() -> T,
+) -> (T, Recorder) {
+    /// Restores the previous recorder when dropped, also if `f` panics
+    struct Restore(Option<Recorder>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            let previous = self.0.take();
+            RECORDER.with(|recorder| *recorder.borrow_mut() = previous);
+            INSTALLED_RECORDERS.fetch_sub(1, Ordering::Relaxed);
+        }
+    }
+
+    INSTALLED_RECORDERS.fetch_add(1, Ordering::Relaxed);
+    let _restore = Restore(
+        RECORDER.with(|current| current.borrow_mut().replace(recorder)),
+    );
+    let res = f();
+    let recorder = RECORDER
+        .with(|current| current.borrow_mut().take())
+        .unwrap_or_else(Recorder::new);
+    (res, recorder)
+}
+
+/// Run `f`, recording the traces of the inner txs it executes on this thread.
+/// Returns the traces with the hashes of the commitments of their inner txs.
+pub fn record<T>(f: impl FnOnce() -> T) -> (T, Vec<(Hash, InnerTxTrace)>) {
+    let (res, recorder) = run_with_recorder(Recorder::new(), f);
+    (res, recorder.inner_txs)
+}
+
+/// Check if a trace is being recorded on this thread
+pub fn is_recording() -> bool {
+    with_recorder(|_| ()).is_some()
+}
+
+/// Run the inner tx with the given commitments hash in `f`, recording its
+/// trace if a trace is being recorded on this thread
+pub fn inner_tx<T>(cmt_hash: Hash, f: impl FnOnce() -> T) -> T {
+    let Some((outer_calls, outer_vps)) = with_recorder(|recorder| {
+        (recorder.take_calls(), std::mem::take(&mut recorder.vps))
+    }) else {
+        return f();
+    };
+    let res = f();
+    with_recorder(|recorder| {
+        let calls = recorder.take_calls();
+        let vps = std::mem::replace(&mut recorder.vps, outer_vps);
+        recorder
+            .inner_txs
+            .push((cmt_hash, InnerTxTrace { calls, vps }));
+        if let Some(root) = recorder.stack.first_mut() {
+            root.calls = outer_calls;
+        }
+    });
+    res
+}
+
+/// Run the VP in `f`, recording its calls if `enabled`. A VP may run on
+/// another thread than its tx, hence `enabled` has to be obtained from
+/// [`is_recording`] on the thread of the tx.
+pub fn record_vp<T>(
+    enabled: bool,
+    f: impl FnOnce() -> T,
+) -> (T, Option<Vec<TraceCall>>) {
+    if !enabled {
+        return (f(), None);
+    }
+    let (res, mut recorder) = run_with_recorder(Recorder::new(), f);
+    (res, Some(recorder.take_calls()))
+}
+
+/// Add the trace of a VP to the inner tx being recorded on this thread
+pub fn push_vp(vp: VpTrace) {
+    with_recorder(|recorder| recorder.vps.push(vp));
+}
+
+/// Annotate the innermost call being recorded on this thread with the storage
+/// key it accessed and the length of the value it read or wrote
+pub fn storage_access(key: &Key, value_len: Option<usize>) {
+    with_recorder(|recorder| {
+        if recorder.stack.len() < 2 {
+            return;
+        }
+        if let Some(call) = recorder.stack.last_mut() {
+            call.key = Some(key.clone());
+            call.value_len = value_len.and_then(|len| u64::try_from(len).ok());
+        }
+    });
+}
+
+/// A call being recorded
+#[must_use = "A call must be exited to be recorded"]
+#[derive(Debug)]
+pub struct Call {
+    /// The gas consumed before the call, `None` when not recording
+    gas_before: Option<Gas>,
+}
+
+impl Call {
+    /// Start recording a call named `name`, given a function returning the
+    /// gas consumed so far
+    pub fn enter(
+        name: &'static str,
+        consumed_gas: impl FnOnce() -> Gas,
+    ) -> Self {
+        let gas_before = with_recorder(|recorder| {
+            recorder.stack.push(TraceCall {
+                name: name.to_string(),
+                ..Default::default()
+            })
+        })
+        .map(|()| consumed_gas());
+        Self { gas_before }
+    }
+
+    /// Check if the call is being recorded
+    pub fn is_recording(&self) -> bool {
+        self.gas_before.is_some()
+    }
+
+    /// Finish recording the call, given a function returning the gas
+    /// consumed so far
+    pub fn exit(self, consumed_gas: impl FnOnce() -> Gas) {
+        if let Some(gas_before) = self.gas_before {
+            let gas =
+                consumed_gas().checked_sub(gas_before).unwrap_or_default();
+            with_recorder(|recorder| recorder.exit(gas));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use namada_core::address::{Address, InternalAddress};
+
+    use super::*;
+
+    /// Record a call consuming the given gas, running `f` inside of it
+    fn call(name: &'static str, gas: u64, f: impl FnOnce()) {
+        let call = Call::enter(name, || 0.into());
+        f();
+        call.exit(|| gas.into());
+    }
+
+    #[test]
+    fn test_record_inner_tx() {
+        let key = Key::parse("key").unwrap();
+        let cmt_hash = Hash::sha256(b"cmt");
+        let pos = Address::Internal(InternalAddress::PoS);
+
+        // Nothing is recorded without a recorder
+        call("namada_tx_read", 1, || storage_access(&key, Some(1)));
+        assert!(!is_recording());
+
+        let ((), traces) = record(|| {
+            assert!(is_recording());
+            inner_tx(cmt_hash, || {
+                call(GAS_HOOK, 1, || {});
+                call(GAS_HOOK, 2, || {});
+                call("namada_tx_read", 3, || storage_access(&key, Some(8)));
+                call(GAS_HOOK, 4, || {});
+
+                let ((), vp_calls) = record_vp(true, || {
+                    call("namada_vp_eval", 10, || {
+                        call("read_post", 5, || storage_access(&key, None))
+                    })
+                });
+                push_vp(VpTrace {
+                    addr: pos.clone(),
+                    verdict: VpVerdict::Accepted,
+                    gas: 10.into(),
+                    calls: vp_calls.unwrap(),
+                });
+                let ((), no_calls) = record_vp(false, || {
+                    call("namada_vp_eval", 10, || {});
+                });
+                assert!(no_calls.is_none());
+            });
+        });
+        assert!(!is_recording());
+
+        let read = TraceCall {
+            name: "namada_tx_read".to_string(),
+            key: Some(key.clone()),
+            value_len: Some(8),
+            gas: 3.into(),
+            calls: vec![],
+        };
+        let gas_hook = |gas: u64| TraceCall {
+            name: GAS_HOOK.to_string(),
+            gas: gas.into(),
+            ..Default::default()
+        };
+        let vp_read = TraceCall {
+            name: "read_post".to_string(),
+            key: Some(key),
+            gas: 5.into(),
+            ..Default::default()
+        };
+        assert_eq!(
+            traces,
+            vec![(
+                cmt_hash,
+                InnerTxTrace {
+                    calls: vec![gas_hook(3), read, gas_hook(4)],
+                    vps: vec![VpTrace {
+                        addr: pos,
+                        verdict: VpVerdict::Accepted,
+                        gas: 10.into(),
+                        calls: vec![TraceCall {
+                            name: "namada_vp_eval".to_string(),
+                            gas: 10.into(),
+                            calls: vec![vp_read],
+                            ..Default::default()
+                        }],
+                    }],
+                }
+            )]
+        );
+    }
+}
@@ -7,6 +7,8 @@ use namada_core::address::{Address, ESTABLISHED_ADDRESS_BYTES_LEN};
 use namada_core::arith::checked;
 use namada_core::chain::{BlockHeader, BlockHeight, ChainId, Epoch, Epochs};
 use namada_core::hash::{Hash, HASH_LENGTH};
+use namada_core::keccak::keccak_hash;
+use namada_core::key::common;
 use namada_core::storage::{Key, TxIndex, TX_INDEX_LENGTH};
 use namada_events::{Event, EventTypeBuilder};
 use namada_gas::{
@@ -18,6 +20,7 @@ use thiserror::Error;
 use crate::state::write_log::WriteLog;
 use crate::state::{write_log, DBIter, PrefixIter, ResultExt, StateRead, DB};
 pub use crate::state::{Error, Result};
+use crate::trace;
 
 /// These runtime errors will abort VP execution immediately
 #[allow(missing_docs)]
@@ -50,6 +53,27 @@ pub fn add_gas(gas_meter: &RefCell<VpGasMeter>, used_gas: Gas) -> Result<()> {
     })
 }
 
+/// Run the storage access `f` of a VP, recording it as a call named `name` if
+/// a trace is being recorded. On success, `record` is called with the result
+/// to annotate the call with the accessed key.
+fn traced<T>(
+    name: &'static str,
+    gas_meter: &RefCell<VpGasMeter>,
+    f: impl FnOnce() -> Result<T>,
+    record: impl FnOnce(&T),
+) -> Result<T> {
+    let consumed_gas = || gas_meter.borrow().get_vp_consumed_gas();
+    let call = trace::Call::enter(name, consumed_gas);
+    let res = f();
+    if call.is_recording() {
+        if let Ok(value) = &res {
+            record(value);
+        }
+    }
+    call.exit(consumed_gas);
+    res
+}
+
 /// Storage read prior state (before tx execution). It will try to read from the
 /// storage.
 pub fn read_pre<S>(
@@ -60,30 +84,37 @@ pub fn read_pre<S>(
 where
     S: StateRead + Debug,
 {
-    let (log_val, gas) =
-        state.write_log().read_pre(key).into_storage_result()?;
-    add_gas(gas_meter, gas)?;
-    match log_val {
-        Some(write_log::StorageModification::Write { ref value }) => {
-            Ok(Some(value.clone()))
-        }
-        Some(&write_log::StorageModification::Delete) => {
-            // Given key has been deleted
-            Ok(None)
-        }
-        Some(write_log::StorageModification::InitAccount {
-            ref vp_code_hash,
-        }) => {
-            // Read the VP of a new account
-            Ok(Some(vp_code_hash.to_vec()))
-        }
-        None => {
-            // When not found in write log, try to read from the storage
-            let (value, gas) = state.db_read(key)?;
+    traced(
+        "read_pre",
+        gas_meter,
+        || {
+            let (log_val, gas) =
+                state.write_log().read_pre(key).into_storage_result()?;
             add_gas(gas_meter, gas)?;
-            Ok(value)
-        }
-    }
+            match log_val {
+                Some(write_log::StorageModification::Write { ref value }) => {
+                    Ok(Some(value.clone()))
+                }
+                Some(&write_log::StorageModification::Delete) => {
+                    // Given key has been deleted
+                    Ok(None)
+                }
+                Some(write_log::StorageModification::InitAccount {
+                    ref vp_code_hash,
+                }) => {
+                    // Read the VP of a new account
+                    Ok(Some(vp_code_hash.to_vec()))
+                }
+                None => {
+                    // When not found in write log, try to read from the storage
+                    let (value, gas) = state.db_read(key)?;
+                    add_gas(gas_meter, gas)?;
+                    Ok(value)
+                }
+            }
+        },
+        |value| trace::storage_access(key, value.as_ref().map(Vec::len)),
+    )
 }
 
 /// Storage read posterior state (after tx execution). It will try to read from
@@ -96,29 +127,39 @@ pub fn read_post<S>(
 where
     S: StateRead + Debug,
 {
-    // Try to read from the write log first
-    let (log_val, gas) = state.write_log().read(key).into_storage_result()?;
-    add_gas(gas_meter, gas)?;
-    match log_val {
-        Some(write_log::StorageModification::Write { value }) => {
-            Ok(Some(value.clone()))
-        }
-        Some(write_log::StorageModification::Delete) => {
-            // Given key has been deleted
-            Ok(None)
-        }
-        Some(write_log::StorageModification::InitAccount { vp_code_hash }) => {
-            // Read the VP code hash of a new account
-            Ok(Some(vp_code_hash.to_vec()))
-        }
-        None => {
-            // When not found in write log, try
-            // to read from the storage
-            let (value, gas) = state.db_read(key)?;
+    traced(
+        "read_post",
+        gas_meter,
+        || {
+            // Try to read from the write log first
+            let (log_val, gas) =
+                state.write_log().read(key).into_storage_result()?;
             add_gas(gas_meter, gas)?;
-            Ok(value)
-        }
-    }
+            match log_val {
+                Some(write_log::StorageModification::Write { value }) => {
+                    Ok(Some(value.clone()))
+                }
+                Some(write_log::StorageModification::Delete) => {
+                    // Given key has been deleted
+                    Ok(None)
+                }
+                Some(write_log::StorageModification::InitAccount {
+                    vp_code_hash,
+                }) => {
+                    // Read the VP code hash of a new account
+                    Ok(Some(vp_code_hash.to_vec()))
+                }
+                None => {
+                    // When not found in write log, try
+                    // to read from the storage
+                    let (value, gas) = state.db_read(key)?;
+                    add_gas(gas_meter, gas)?;
+                    Ok(value)
+                }
+            }
+        },
+        |value| trace::storage_access(key, value.as_ref().map(Vec::len)),
+    )
 }
 
 /// Storage read temporary state (after tx execution). It will try to read from
@@ -131,10 +172,17 @@ pub fn read_temp<S>(
 where
     S: StateRead + Debug,
 {
-    let (log_val, gas) =
-        state.write_log().read_temp(key).into_storage_result()?;
-    add_gas(gas_meter, gas)?;
-    Ok(log_val.cloned())
+    traced(
+        "read_temp",
+        gas_meter,
+        || {
+            let (log_val, gas) =
+                state.write_log().read_temp(key).into_storage_result()?;
+            add_gas(gas_meter, gas)?;
+            Ok(log_val.cloned())
+        },
+        |value| trace::storage_access(key, value.as_ref().map(Vec::len)),
+    )
 }
 
 /// Storage `has_key` in prior state (before tx execution). It will try to read
@@ -147,24 +195,33 @@ pub fn has_key_pre<S>(
 where
     S: StateRead + Debug,
 {
-    // Try to read from the write log first
-    let (log_val, gas) =
-        state.write_log().read_pre(key).into_storage_result()?;
-    add_gas(gas_meter, gas)?;
-    match log_val {
-        Some(&write_log::StorageModification::Write { .. }) => Ok(true),
-        Some(&write_log::StorageModification::Delete) => {
-            // The given key has been deleted
-            Ok(false)
-        }
-        Some(&write_log::StorageModification::InitAccount { .. }) => Ok(true),
-        None => {
-            // When not found in write log, try to check the storage
-            let (present, gas) = state.db_has_key(key)?;
+    traced(
+        "has_key_pre",
+        gas_meter,
+        || {
+            // Try to read from the write log first
+            let (log_val, gas) =
+                state.write_log().read_pre(key).into_storage_result()?;
             add_gas(gas_meter, gas)?;
-            Ok(present)
-        }
-    }
+            match log_val {
+                Some(&write_log::StorageModification::Write { .. }) => Ok(true),
+                Some(&write_log::StorageModification::Delete) => {
+                    // The given key has been deleted
+                    Ok(false)
+                }
+                Some(&write_log::StorageModification::InitAccount {
+                    ..
+                }) => Ok(true),
+                None => {
+                    // When not found in write log, try to check the storage
+                    let (present, gas) = state.db_has_key(key)?;
+                    add_gas(gas_meter, gas)?;
+                    Ok(present)
+                }
+            }
+        },
+        |_| trace::storage_access(key, None),
+    )
 }
 
 /// Storage `has_key` in posterior state (after tx execution). It will try to
@@ -177,24 +234,34 @@ pub fn has_key_post<S>(
 where
     S: StateRead + Debug,
 {
-    // Try to read from the write log first
-    let (log_val, gas) = state.write_log().read(key).into_storage_result()?;
-    add_gas(gas_meter, gas)?;
-    match log_val {
-        Some(write_log::StorageModification::Write { .. }) => Ok(true),
-        Some(write_log::StorageModification::Delete) => {
-            // The given key has been deleted
-            Ok(false)
-        }
-        Some(write_log::StorageModification::InitAccount { .. }) => Ok(true),
-        None => {
-            // When not found in write log, try
-            // to check the storage
-            let (present, gas) = state.db_has_key(key)?;
+    traced(
+        "has_key_post",
+        gas_meter,
+        || {
+            // Try to read from the write log first
+            let (log_val, gas) =
+                state.write_log().read(key).into_storage_result()?;
             add_gas(gas_meter, gas)?;
-            Ok(present)
-        }
-    }
+            match log_val {
+                Some(write_log::StorageModification::Write { .. }) => Ok(true),
+                Some(write_log::StorageModification::Delete) => {
+                    // The given key has been deleted
+                    Ok(false)
+                }
+                Some(write_log::StorageModification::InitAccount {
+                    ..
+                }) => Ok(true),
+                None => {
+                    // When not found in write log, try
+                    // to check the storage
+                    let (present, gas) = state.db_has_key(key)?;
+                    add_gas(gas_meter, gas)?;
+                    Ok(present)
+                }
+            }
+        },
+        |_| trace::storage_access(key, None),
+    )
 }
 
 /// Getting the chain ID.
@@ -355,9 +422,17 @@ pub fn iter_prefix_pre<'a, D>(
 where
     D: DB + for<'iter> DBIter<'iter>,
 {
-    let (iter, gas) = namada_state::iter_prefix_pre(write_log, db, prefix)?;
-    add_gas(gas_meter, gas)?;
-    Ok(iter)
+    traced(
+        "iter_prefix_pre",
+        gas_meter,
+        || {
+            let (iter, gas) =
+                namada_state::iter_prefix_pre(write_log, db, prefix)?;
+            add_gas(gas_meter, gas)?;
+            Ok(iter)
+        },
+        |_| trace::storage_access(prefix, None),
+    )
 }
 
 /// Storage prefix iterator for posterior state (after tx execution), ordered by
@@ -374,9 +449,17 @@ pub fn iter_prefix_post<'a, D>(
 where
     D: DB + for<'iter> DBIter<'iter>,
 {
-    let (iter, gas) = namada_state::iter_prefix_post(write_log, db, prefix)?;
-    add_gas(gas_meter, gas)?;
-    Ok(iter)
+    traced(
+        "iter_prefix_post",
+        gas_meter,
+        || {
+            let (iter, gas) =
+                namada_state::iter_prefix_post(write_log, db, prefix)?;
+            add_gas(gas_meter, gas)?;
+            Ok(iter)
+        },
+        |_| trace::storage_access(prefix, None),
+    )
 }
 
 /// Get the next item in a storage prefix iterator (pre or post).
@@ -387,9 +470,111 @@ pub fn iter_next<DB>(
 where
     DB: namada_state::DB + for<'iter> namada_state::DBIter<'iter>,
 {
-    if let Some((key, val, gas)) = iter.next() {
-        add_gas(gas_meter, gas)?;
-        return Ok(Some((key, val)));
+    traced(
+        "iter_next",
+        gas_meter,
+        || {
+            if let Some((key, val, gas)) = iter.next() {
+                add_gas(gas_meter, gas)?;
+                return Ok(Some((key, val)));
+            }
+            Ok(None)
+        },
+        |next| {
+            if let Some((key, val)) = next {
+                if let Ok(key) = Key::parse(key) {
+                    trace::storage_access(&key, Some(val.len()));
+                }
+            }
+        },
+    )
+}
+
+/// The hash functions that can be run in the host environment
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashFunction {
+    /// SHA-256
+    Sha256,
+    /// Keccak-256, as used by Ethereum
+    Keccak256,
+    /// BLAKE2b with a 32 bytes digest
+    Blake2b,
+}
+
+impl HashFunction {
+    /// The gas cost of hashing data of the given length
+    pub fn gas(self, len: usize) -> Result<Gas> {
+        let gas_per_byte = match self {
+            Self::Sha256 => gas::SHA256_GAS_PER_BYTE,
+            Self::Keccak256 => gas::KECCAK256_GAS_PER_BYTE,
+            Self::Blake2b => gas::BLAKE2B_GAS_PER_BYTE,
+        };
+        let len = len as u64;
+        Ok(checked!(gas::HASH_FIXED_GAS + len * gas_per_byte)?.into())
     }
-    Ok(None)
+
+    /// Hash the given data
+    pub fn digest(self, data: &[u8]) -> [u8; HASH_LENGTH] {
+        match self {
+            Self::Sha256 => Hash::sha256(data).0,
+            Self::Keccak256 => keccak_hash(data).0,
+            Self::Blake2b => {
+                let mut digest = [0; HASH_LENGTH];
+                digest.copy_from_slice(
+                    blake2b_simd::Params::new()
+                        .hash_length(HASH_LENGTH)
+                        .hash(data)
+                        .as_bytes(),
+                );
+                digest
+            }
+        }
+    }
+}
+
+/// Hash the given data with the given hash function.
+pub fn hash(
+    gas_meter: &RefCell<VpGasMeter>,
+    function: HashFunction,
+    data: &[u8],
+) -> Result<[u8; HASH_LENGTH]> {
+    add_gas(gas_meter, function.gas(data.len())?)?;
+    Ok(function.digest(data))
+}
+
+/// The gas cost of verifying a signature of the given public key over a
+/// message of the given length. Ed25519 signatures are verified over the raw
+/// message, which is hashed with SHA-512 as part of the verification, while
+/// secp256k1 signatures are verified over a 32 bytes prehash.
+pub fn verify_signature_gas(
+    pk: &common::PublicKey,
+    msg_len: usize,
+) -> Result<Gas> {
+    let gas = match pk {
+        common::PublicKey::Ed25519(_) => {
+            let msg_len = msg_len as u64;
+            checked!(
+                gas::VERIFY_ED25519_SIG_GAS
+                    + msg_len * gas::VERIFY_ED25519_SIG_GAS_PER_BYTE
+            )?
+        }
+        common::PublicKey::Secp256k1(_) => gas::VERIFY_SECP256K1_SIG_GAS,
+    };
+    Ok(gas.into())
+}
+
+/// Verify a signature that was produced outside of Namada over an arbitrary
+/// message. For ed25519 keys, the signature is verified over the message
+/// itself. For secp256k1 keys, the message must be the 32 bytes prehash that
+/// was signed, e.g. the Keccak-256 digest of an Ethereum `personal_sign`
+/// message. Returns `false` if the signature is not valid for the given public
+/// key and message.
+pub fn verify_signature(
+    gas_meter: &RefCell<VpGasMeter>,
+    pk: &common::PublicKey,
+    sig: &common::Signature,
+    msg: &[u8],
+) -> Result<bool> {
+    add_gas(gas_meter, verify_signature_gas(pk, msg.len())?)?;
+    Ok(common::verify_raw_signature(pk, msg, sig).is_ok())
 }
@@ -24,7 +24,8 @@ use namada_core::address::Address;
 use namada_core::borsh::BorshDeserialize;
 use namada_core::chain::ChainId;
 pub use namada_core::chain::{BlockHeader, BlockHeight, Epoch, Epochs};
-use namada_core::hash::Hash;
+use namada_core::hash::{Hash, HASH_LENGTH};
+use namada_core::key::common;
 use namada_events::{Event, EventType};
 use namada_gas::Gas;
 pub use namada_storage::{Error, Key, Result, StorageRead, TxIndex};
@@ -111,6 +112,29 @@ where
     /// Charge the provided gas for the current vp
     fn charge_gas(&self, used_gas: Gas) -> Result<()>;
 
+    /// Hash the data with SHA-256 in the host environment
+    fn sha256(&self, data: &[u8]) -> Result<[u8; HASH_LENGTH]>;
+
+    /// Hash the data with Keccak-256 in the host environment
+    fn keccak256(&self, data: &[u8]) -> Result<[u8; HASH_LENGTH]>;
+
+    /// Hash the data with BLAKE2b in the host environment, with a 32 bytes
+    /// digest
+    fn blake2b(&self, data: &[u8]) -> Result<[u8; HASH_LENGTH]>;
+
+    /// Verify a signature produced outside of Namada over an arbitrary
+    /// message in the host environment. Ed25519 signatures are verified over
+    /// the message itself, while the message of a secp256k1 signature must be
+    /// the 32 bytes prehash that was signed, e.g. the Keccak-256 digest of an
+    /// Ethereum `personal_sign` message. Returns `false` if the signature is
+    /// not valid.
+    fn verify_signature(
+        &self,
+        pk: &common::PublicKey,
+        sig: &common::Signature,
+        msg: &[u8],
+    ) -> Result<bool>;
+
     // ---- Methods below have default implementation via `pre/post` ----
 
     /// Storage read prior state Borsh encoded value (before tx execution). It
@@ -36,6 +36,7 @@ use namada_core::chain::{
 pub use namada_core::collections::HashSet;
 use namada_core::hash::{Hash, HASH_LENGTH};
 use namada_core::internal::HostEnvResult;
+use namada_core::key::common;
 use namada_core::storage::TxIndex;
 pub use namada_core::validity_predicate::{VpError, VpErrorExtResult};
 pub use namada_core::*;
@@ -49,7 +50,10 @@ pub use namada_storage::{
 };
 pub use namada_tx::{BatchedTx, Section, Tx};
 use namada_vm_env::vp::*;
-use namada_vm_env::{read_from_buffer, read_key_val_bytes_from_buffer};
+use namada_vm_env::{
+    hash_with, read_from_buffer, read_key_val_bytes_from_buffer,
+    verify_signature_with,
+};
 pub use namada_vp_env::{collection_validation, VpEnv};
 pub use sha2::{Digest, Sha256, Sha384, Sha512};
 use tx::{BatchedTxRef, TxCommitments};
@@ -414,6 +418,32 @@ impl<'view> VpEnv<'view> for Ctx {
         unsafe { namada_vp_charge_gas(used_gas.into()) };
         Ok(())
     }
+
+    fn sha256(&self, data: &[u8]) -> Result<[u8; HASH_LENGTH], Error> {
+        Ok(hash_with(data, namada_vp_sha256))
+    }
+
+    fn keccak256(&self, data: &[u8]) -> Result<[u8; HASH_LENGTH], Error> {
+        Ok(hash_with(data, namada_vp_keccak256))
+    }
+
+    fn blake2b(&self, data: &[u8]) -> Result<[u8; HASH_LENGTH], Error> {
+        Ok(hash_with(data, namada_vp_blake2b))
+    }
+
+    fn verify_signature(
+        &self,
+        pk: &common::PublicKey,
+        sig: &common::Signature,
+        msg: &[u8],
+    ) -> Result<bool, Error> {
+        Ok(verify_signature_with(
+            pk,
+            sig,
+            msg,
+            namada_vp_verify_signature,
+        ))
+    }
 }
 
 impl namada_tx::action::Read for Ctx {
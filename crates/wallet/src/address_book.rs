@@ -0,0 +1,485 @@
+//! Watch-only accounts and the address book of other parties.
+//!
+//! Both are kept in the wallet store next to the addresses, payment addresses
+//! and viewing keys that they register under the same alias, so that the rest
+//! of the client can use them by alias like any other address or viewing key.
+//! Both sections can be exported to and imported from JSON and CSV files.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+use namada_core::address::Address;
+use namada_core::chain::BlockHeight;
+use namada_core::key::common;
+use namada_core::masp::{ExtendedViewingKey, PaymentAddress};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::alias::Alias;
+
+/// The separator of the items of a list in a CSV field
+const CSV_LIST_SEPARATOR: char = ';';
+
+/// Errors of address book import
+#[derive(Error, Debug)]
+pub enum BookError {
+    /// The JSON file could not be decoded
+    #[error("Failed to decode the JSON file: {0}")]
+    Json(serde_json::Error),
+    /// The CSV file is malformed
+    #[error("Failed to decode the CSV file at line {line}: {reason}")]
+    Csv {
+        /// The line of the record at fault
+        line: usize,
+        /// The reason of the failure
+        reason: String,
+    },
+}
+
+/// An account of which the wallet tracks the address, the public keys and
+/// optionally the viewing key, without holding any of its secrets
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct WatchOnlyAccount {
+    /// The address of the account
+    pub address: Address,
+    /// The public keys of the account
+    #[serde(default)]
+    pub public_keys: Vec<common::PublicKey>,
+    /// The viewing key used to track the shielded balance of the account
+    pub viewing_key: Option<ExtendedViewingKey>,
+    /// The block height after which the viewing key was created
+    pub birthday: Option<BlockHeight>,
+}
+
+/// The transparent address or shielded payment address of an address book
+/// entry
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ContactAddress {
+    /// A transparent address
+    Transparent(Address),
+    /// A shielded payment address
+    Shielded(PaymentAddress),
+}
+
+impl Display for ContactAddress {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Transparent(address) => write!(f, "{}", address),
+            Self::Shielded(address) => write!(f, "{}", address),
+        }
+    }
+}
+
+impl FromStr for ContactAddress {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Address::from_str(s)
+            .map(Self::Transparent)
+            .or_else(|_| PaymentAddress::from_str(s).map(Self::Shielded))
+            .map_err(|_| {
+                format!(
+                    "\"{s}\" is neither a transparent address nor a payment \
+                     address"
+                )
+            })
+    }
+}
+
+impl Serialize for ContactAddress {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        self.to_string().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for ContactAddress {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        use serde::de::Error;
+
+        let raw: String = Deserialize::deserialize(deserializer)?;
+        Self::from_str(&raw).map_err(D::Error::custom)
+    }
+}
+
+/// An address of another party with its metadata
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct AddressBookEntry {
+    /// The address of the contact
+    pub address: ContactAddress,
+    /// A human readable label
+    pub label: Option<String>,
+    /// Tags to group entries by
+    #[serde(default)]
+    pub tags: BTreeSet<String>,
+    /// A free-form note
+    pub note: Option<String>,
+    /// The token preferred to pay the fees of transactions with the contact
+    pub fee_token: Option<Address>,
+}
+
+/// The file formats of address book import and export
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BookFormat {
+    /// A JSON object from aliases to entries
+    Json,
+    /// A CSV file with a header and one record per alias
+    Csv,
+}
+
+impl FromStr for BookFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "json" => Ok(Self::Json),
+            "csv" => Ok(Self::Csv),
+            _ => Err(format!("Unknown format {s}, expected json or csv")),
+        }
+    }
+}
+
+/// An entry of one of the sections of the address book that can be written
+/// as a CSV record
+pub trait BookRecord: Serialize + DeserializeOwned + Sized {
+    /// The names of the CSV fields, following the alias
+    const CSV_FIELDS: &'static [&'static str];
+
+    /// Get the CSV fields of the entry
+    fn to_csv_fields(&self) -> Vec<String>;
+
+    /// Parse an entry from its CSV fields
+    fn from_csv_fields(fields: &[String]) -> Result<Self, String>;
+}
+
+impl BookRecord for WatchOnlyAccount {
+    const CSV_FIELDS: &'static [&'static str] =
+        &["address", "public_keys", "viewing_key", "birthday"];
+
+    fn to_csv_fields(&self) -> Vec<String> {
+        vec![
+            self.address.to_string(),
+            join_list(self.public_keys.iter()),
+            opt_to_string(self.viewing_key.as_ref()),
+            opt_to_string(self.birthday.as_ref()),
+        ]
+    }
+
+    fn from_csv_fields(fields: &[String]) -> Result<Self, String> {
+        Ok(Self {
+            address: parse_field(&fields[0], "address")?,
+            public_keys: split_list(&fields[1])
+                .map(|pk| parse_field(pk, "public key"))
+                .collect::<Result<_, _>>()?,
+            viewing_key: parse_opt_field(&fields[2], "viewing key")?,
+            birthday: parse_opt_field(&fields[3], "birthday")?,
+        })
+    }
+}
+
+impl BookRecord for AddressBookEntry {
+    const CSV_FIELDS: &'static [&'static str] =
+        &["address", "label", "tags", "note", "fee_token"];
+
+    fn to_csv_fields(&self) -> Vec<String> {
+        vec![
+            self.address.to_string(),
+            self.label.clone().unwrap_or_default(),
+            join_list(self.tags.iter()),
+            self.note.clone().unwrap_or_default(),
+            opt_to_string(self.fee_token.as_ref()),
+        ]
+    }
+
+    fn from_csv_fields(fields: &[String]) -> Result<Self, String> {
+        let non_empty =
+            |field: &String| (!field.is_empty()).then(|| field.clone());
+        Ok(Self {
+            address: parse_field(&fields[0], "address")?,
+            label: non_empty(&fields[1]),
+            tags: split_list(&fields[2]).map(ToString::to_string).collect(),
+            note: non_empty(&fields[3]),
+            fee_token: parse_opt_field(&fields[4], "fee token")?,
+        })
+    }
+}
+
+/// Export the given entries in the given format
+pub fn export_book<T: BookRecord>(
+    entries: &BTreeMap<Alias, T>,
+    format: BookFormat,
+) -> String {
+    match format {
+        BookFormat::Json => serde_json::to_string_pretty(entries)
+            .expect("Serializing the address book shouldn't fail"),
+        BookFormat::Csv => {
+            let header = std::iter::once("alias")
+                .chain(T::CSV_FIELDS.iter().copied())
+                .map(ToString::to_string)
+                .collect::<Vec<_>>();
+            let records = entries.iter().map(|(alias, entry)| {
+                std::iter::once(alias.to_string())
+                    .chain(entry.to_csv_fields())
+                    .collect::<Vec<_>>()
+            });
+            std::iter::once(header)
+                .chain(records)
+                .map(|record| {
+                    let fields: Vec<_> =
+                        record.iter().map(|f| escape_csv_field(f)).collect();
+                    format!("{}\n", fields.join(","))
+                })
+                .collect()
+        }
+    }
+}
+
+/// Import entries from data in the given format
+pub fn import_book<T: BookRecord>(
+    data: &str,
+    format: BookFormat,
+) -> Result<BTreeMap<Alias, T>, BookError> {
+    match format {
+        BookFormat::Json => serde_json::from_str(data).map_err(BookError::Json),
+        BookFormat::Csv => {
+            let mut records = parse_csv(data)?.into_iter();
+            let expected_len = T::CSV_FIELDS.len() + 1;
+            let header_matches = records.next().is_some_and(|(_, header)| {
+                header.len() == expected_len
+                    && header[0] == "alias"
+                    && header[1..] == *T::CSV_FIELDS
+            });
+            if !header_matches {
+                return Err(BookError::Csv {
+                    line: 1,
+                    reason: format!(
+                        "Expected the header alias,{}",
+                        T::CSV_FIELDS.join(",")
+                    ),
+                });
+            }
+            records
+                .map(|(line, record)| {
+                    if record.len() != expected_len {
+                        return Err(BookError::Csv {
+                            line,
+                            reason: format!(
+                                "Expected {expected_len} fields, got {}",
+                                record.len()
+                            ),
+                        });
+                    }
+                    let entry = T::from_csv_fields(&record[1..])
+                        .map_err(|reason| BookError::Csv { line, reason })?;
+                    Ok((Alias::from(record[0].as_str()), entry))
+                })
+                .collect()
+        }
+    }
+}
+
+/// Join the items of a list into a CSV field
+fn join_list(items: impl Iterator<Item = impl ToString>) -> String {
+    items
+        .map(|item| item.to_string())
+        .collect::<Vec<_>>()
+        .join(&CSV_LIST_SEPARATOR.to_string())
+}
+
+/// Split a CSV field into the items of a list
+fn split_list(field: &str) -> impl Iterator<Item = &str> {
+    field
+        .split(CSV_LIST_SEPARATOR)
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+}
+
+/// Format an optional value into a CSV field, empty if there is no value
+fn opt_to_string(value: Option<&impl ToString>) -> String {
+    value.map(ToString::to_string).unwrap_or_default()
+}
+
+/// Parse a CSV field
+fn parse_field<T: FromStr>(field: &str, name: &str) -> Result<T, String> {
+    field
+        .trim()
+        .parse()
+        .map_err(|_| format!("Invalid {name} \"{field}\""))
+}
+
+/// Parse an optional CSV field, empty if there is no value
+fn parse_opt_field<T: FromStr>(
+    field: &str,
+    name: &str,
+) -> Result<Option<T>, String> {
+    if field.trim().is_empty() {
+        Ok(None)
+    } else {
+        parse_field(field, name).map(Some)
+    }
+}
+
+/// Quote a CSV field if it contains any special character
+fn escape_csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Parse CSV data into records of fields, along with the line on which each
+/// record starts. Quoted fields may contain separators, escaped quotes and
+/// line breaks. Empty lines are skipped.
+fn parse_csv(data: &str) -> Result<Vec<(usize, Vec<String>)>, BookError> {
+    let mut records = vec![];
+    let mut record = vec![];
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut line = 1;
+    let mut record_line = 1;
+    let mut chars = data.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes => {
+                if chars.peek() == Some(&'"') {
+                    chars.next();
+                    field.push('"');
+                } else {
+                    in_quotes = false;
+                }
+            }
+            '"' if field.is_empty() => in_quotes = true,
+            '\n' if in_quotes => {
+                line += 1;
+                field.push(c);
+            }
+            ',' if !in_quotes => record.push(std::mem::take(&mut field)),
+            '\r' if !in_quotes && chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                record.push(std::mem::take(&mut field));
+                if record.len() > 1 || !record[0].is_empty() {
+                    records.push((record_line, std::mem::take(&mut record)));
+                } else {
+                    record.clear();
+                }
+                line += 1;
+                record_line = line;
+            }
+            _ => field.push(c),
+        }
+    }
+    if in_quotes {
+        return Err(BookError::Csv {
+            line: record_line,
+            reason: "Unterminated quoted field".to_string(),
+        });
+    }
+    if !record.is_empty() || !field.is_empty() {
+        record.push(field);
+        records.push((record_line, record));
+    }
+    Ok(records)
+}
+
+#[cfg(test)]
+mod test_address_book {
+    use namada_core::address::testing::{established_address_1, nam};
+    use namada_core::key::testing::{keypair_1, keypair_2};
+    use namada_core::key::RefTo;
+
+    use super::*;
+
+    fn address_book() -> BTreeMap<Alias, AddressBookEntry> {
+        BTreeMap::from([
+            (
+                Alias::from("alice"),
+                AddressBookEntry {
+                    address: ContactAddress::Transparent(
+                        established_address_1(),
+                    ),
+                    label: Some("Alice, from \"work\"".to_string()),
+                    tags: BTreeSet::from([
+                        "friends".to_string(),
+                        "work".to_string(),
+                    ]),
+                    note: Some("Pays back\nin NAM".to_string()),
+                    fee_token: Some(nam()),
+                },
+            ),
+            (
+                Alias::from("bob"),
+                AddressBookEntry {
+                    address: ContactAddress::Transparent(
+                        (&keypair_2().ref_to()).into(),
+                    ),
+                    label: None,
+                    tags: BTreeSet::new(),
+                    note: None,
+                    fee_token: None,
+                },
+            ),
+        ])
+    }
+
+    #[test]
+    fn test_address_book_round_trip() {
+        let entries = address_book();
+        for format in [BookFormat::Json, BookFormat::Csv] {
+            let exported = export_book(&entries, format);
+            let imported: BTreeMap<Alias, AddressBookEntry> =
+                import_book(&exported, format).unwrap();
+            assert_eq!(imported, entries);
+        }
+    }
+
+    #[test]
+    fn test_watch_only_round_trip() {
+        let entries = BTreeMap::from([(
+            Alias::from("multisig"),
+            WatchOnlyAccount {
+                address: established_address_1(),
+                public_keys: vec![keypair_1().ref_to(), keypair_2().ref_to()],
+                viewing_key: None,
+                birthday: Some(BlockHeight(42)),
+            },
+        )]);
+        for format in [BookFormat::Json, BookFormat::Csv] {
+            let exported = export_book(&entries, format);
+            let imported: BTreeMap<Alias, WatchOnlyAccount> =
+                import_book(&exported, format).unwrap();
+            assert_eq!(imported, entries);
+        }
+    }
+
+    #[test]
+    fn test_invalid_csv() {
+        let header = "alias,address,label,tags,note,fee_token\n";
+        let import =
+            |data: &str| import_book::<AddressBookEntry>(data, BookFormat::Csv);
+        assert!(import(header).unwrap().is_empty());
+        assert!(matches!(
+            import("alias,address\n"),
+            Err(BookError::Csv { line: 1, .. })
+        ));
+        assert!(matches!(
+            import(&format!("{header}\ncarol,not-an-address,,,,\n")),
+            Err(BookError::Csv { line: 3, .. })
+        ));
+        assert!(matches!(
+            import(&format!("{header}carol,\"unterminated\n")),
+            Err(BookError::Csv { line: 2, .. })
+        ));
+        assert!(matches!(
+            import(&format!("{header}carol,{}\n", established_address_1())),
+            Err(BookError::Csv { line: 2, .. })
+        ));
+    }
+}
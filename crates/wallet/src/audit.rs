@@ -0,0 +1,422 @@
+//! Key usage audit log and per-key spending policies.
+//!
+//! Every signature that the wallet produces is recorded in an append-only log
+//! in which each record commits to the hash of its predecessor, so that any
+//! removed or altered record breaks the chain. The same log is used to account
+//! for the daily spending limits of the key policies.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use namada_core::address::Address;
+use namada_core::borsh::{BorshDeserialize, BorshSerialize, BorshSerializeExt};
+use namada_core::hash::Hash;
+use namada_core::key::common;
+use namada_core::time::DateTimeUtc;
+use namada_core::token::{Amount, DenominatedAmount};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::alias::Alias;
+
+/// Errors of the key usage log
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum AuditError {
+    /// A record does not commit to its predecessor or its own content
+    #[error(
+        "The key usage log has been tampered with: the hash chain is broken \
+         at record {index}"
+    )]
+    BrokenChain {
+        /// The index of the first invalid record
+        index: usize,
+    },
+}
+
+/// Reasons for refusing to sign with a key
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum PolicyViolation {
+    /// The transaction sends to a target that the policy doesn't allow
+    #[error(
+        "The policy of key \"{alias}\" does not allow sending to {target}"
+    )]
+    TargetNotAllowed {
+        /// The alias of the key
+        alias: Alias,
+        /// The disallowed target
+        target: Address,
+    },
+    /// The transaction would exceed the daily spending limit of a token
+    #[error(
+        "Spending {amount} of token {token} with key \"{alias}\" would exceed \
+         its daily limit of {limit}, of which {spent} has already been spent \
+         today"
+    )]
+    DailyLimitExceeded {
+        /// The alias of the key
+        alias: Alias,
+        /// The token being spent
+        token: Address,
+        /// The amount spent by the transaction
+        amount: DenominatedAmount,
+        /// The amount already spent today
+        spent: DenominatedAmount,
+        /// The daily limit
+        limit: DenominatedAmount,
+    },
+    /// The effects of the transaction could not be fully determined, so the
+    /// restrictions of the policy cannot be enforced
+    #[error(
+        "The policy of key \"{alias}\" restricts its use, but the effects of \
+         {count} inner transaction(s) could not be determined"
+    )]
+    Unsummarizable {
+        /// The alias of the key
+        alias: Alias,
+        /// The number of inner transactions that could not be summarized
+        count: usize,
+    },
+    /// The user refused to confirm the use of the key
+    #[error("The use of key \"{alias}\" was not confirmed")]
+    NotConfirmed {
+        /// The alias of the key
+        alias: Alias,
+    },
+    /// The key usage log could not be used to account for the daily limits
+    #[error("Unable to check the daily limits of key \"{alias}\": {reason}")]
+    UnreadableLog {
+        /// The alias of the key
+        alias: Alias,
+        /// The reason of the failure
+        reason: String,
+    },
+}
+
+/// A summary of the effects of a transaction, as relevant to the key usage
+/// log and the key policies
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TxSummary {
+    /// Human readable descriptions of the actions of the inner transactions
+    pub actions: Vec<String>,
+    /// The amounts spent per token
+    pub amounts: BTreeMap<Address, DenominatedAmount>,
+    /// The addresses that receive tokens or delegations
+    pub targets: BTreeSet<Address>,
+    /// The number of inner transactions whose code isn't recognized or whose
+    /// data couldn't be decoded, and thus whose amounts and targets are
+    /// unknown
+    pub unsummarized: usize,
+}
+
+impl TxSummary {
+    /// Add the given amount to the amount spent of the given token
+    pub fn add_amount(&mut self, token: Address, amount: DenominatedAmount) {
+        add_amount(&mut self.amounts, token, amount)
+    }
+}
+
+/// A single use of a key, as recorded in the key usage log
+#[derive(
+    Serialize,
+    Deserialize,
+    BorshSerialize,
+    BorshDeserialize,
+    Debug,
+    Clone,
+    PartialEq,
+    Eq,
+)]
+pub struct KeyUsage {
+    /// The hash of the signed transaction
+    pub tx_hash: Hash,
+    /// The public key of the signer
+    pub signer: common::PublicKey,
+    /// Human readable descriptions of the actions of the inner transactions
+    pub actions: Vec<String>,
+    /// The amounts spent per token
+    pub amounts: BTreeMap<Address, DenominatedAmount>,
+    /// The time of the signature
+    pub timestamp: DateTimeUtc,
+}
+
+/// A record of the key usage log, chained to its predecessor
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct KeyUsageRecord {
+    /// The hash of the previous record, zero for the first record
+    pub prev_hash: Hash,
+    /// The recorded key usage
+    pub usage: KeyUsage,
+    /// The hash of this record
+    pub hash: Hash,
+}
+
+impl KeyUsageRecord {
+    /// Create a record of the given usage following the given hash
+    pub fn new(prev_hash: Hash, usage: KeyUsage) -> Self {
+        let hash = Self::digest(&prev_hash, &usage);
+        Self {
+            prev_hash,
+            usage,
+            hash,
+        }
+    }
+
+    fn digest(prev_hash: &Hash, usage: &KeyUsage) -> Hash {
+        Hash::sha256((prev_hash, usage).serialize_to_vec())
+    }
+}
+
+/// Chain the given key usages to the end of the given log
+pub fn chain_records(
+    log: &[KeyUsageRecord],
+    usages: impl IntoIterator<Item = KeyUsage>,
+) -> Vec<KeyUsageRecord> {
+    let mut prev_hash =
+        log.last().map(|record| record.hash).unwrap_or_default();
+    usages
+        .into_iter()
+        .map(|usage| {
+            let record = KeyUsageRecord::new(prev_hash, usage);
+            prev_hash = record.hash;
+            record
+        })
+        .collect()
+}
+
+/// Check that every record of the log commits to its predecessor and to its
+/// own content
+pub fn verify_log(log: &[KeyUsageRecord]) -> Result<(), AuditError> {
+    let mut prev_hash = Hash::default();
+    for (index, record) in log.iter().enumerate() {
+        if record.prev_hash != prev_hash
+            || record.hash != KeyUsageRecord::digest(&prev_hash, &record.usage)
+        {
+            return Err(AuditError::BrokenChain { index });
+        }
+        prev_hash = record.hash;
+    }
+    Ok(())
+}
+
+/// The amounts spent per token by the given signer on the UTC day of the
+/// given time
+pub fn spent_on_day(
+    log: &[KeyUsageRecord],
+    signer: &common::PublicKey,
+    day: &DateTimeUtc,
+) -> BTreeMap<Address, DenominatedAmount> {
+    let day = day.0.date_naive();
+    let mut spent = BTreeMap::new();
+    for record in log {
+        let usage = &record.usage;
+        if usage.signer == *signer && usage.timestamp.0.date_naive() == day {
+            for (token, amount) in &usage.amounts {
+                add_amount(&mut spent, token.clone(), *amount);
+            }
+        }
+    }
+    spent
+}
+
+/// Local restrictions on the use of a key
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct KeyPolicy {
+    /// The maximum amount per token that the key may spend per UTC day
+    #[serde(default)]
+    pub daily_limits: BTreeMap<Address, DenominatedAmount>,
+    /// If not empty, the only addresses that the key may send to
+    #[serde(default)]
+    pub allowed_targets: BTreeSet<Address>,
+    /// Whether every use of the key must be confirmed
+    #[serde(default)]
+    pub require_confirmation: bool,
+}
+
+impl KeyPolicy {
+    /// Whether this policy restricts the targets or the amounts of the
+    /// transactions signed with the key
+    pub fn restricts_spending(&self) -> bool {
+        !self.daily_limits.is_empty() || !self.allowed_targets.is_empty()
+    }
+
+    /// Check the given transaction summary against the targets and the daily
+    /// limits of this policy, given the amounts already spent today. A
+    /// summary that is missing some inner transactions is rejected by any
+    /// policy that restricts spending. The confirmation is left to the
+    /// caller.
+    pub fn check(
+        &self,
+        alias: &Alias,
+        summary: &TxSummary,
+        spent_today: &BTreeMap<Address, DenominatedAmount>,
+    ) -> Result<(), PolicyViolation> {
+        if self.restricts_spending() && summary.unsummarized > 0 {
+            return Err(PolicyViolation::Unsummarizable {
+                alias: alias.clone(),
+                count: summary.unsummarized,
+            });
+        }
+        if !self.allowed_targets.is_empty() {
+            if let Some(target) = summary
+                .targets
+                .iter()
+                .find(|target| !self.allowed_targets.contains(target))
+            {
+                return Err(PolicyViolation::TargetNotAllowed {
+                    alias: alias.clone(),
+                    target: target.clone(),
+                });
+            }
+        }
+        for (token, limit) in &self.daily_limits {
+            let Some(amount) = summary.amounts.get(token) else {
+                continue;
+            };
+            let spent = spent_today.get(token).copied().unwrap_or_else(|| {
+                DenominatedAmount::new(Amount::zero(), amount.denom())
+            });
+            let exceeded = spent
+                .checked_add(*amount)
+                .map_or(true, |total| total > *limit);
+            if exceeded {
+                return Err(PolicyViolation::DailyLimitExceeded {
+                    alias: alias.clone(),
+                    token: token.clone(),
+                    amount: *amount,
+                    spent,
+                    limit: *limit,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+fn add_amount(
+    amounts: &mut BTreeMap<Address, DenominatedAmount>,
+    token: Address,
+    amount: DenominatedAmount,
+) {
+    let total = amounts.entry(token).or_insert_with(|| {
+        DenominatedAmount::new(Amount::zero(), amount.denom())
+    });
+    // Saturate on overflow, which can only overstate the amount spent
+    *total = total.checked_add(amount).unwrap_or_else(|| {
+        DenominatedAmount::new(Amount::max(), total.denom())
+    });
+}
+
+#[cfg(test)]
+mod test_audit {
+    use namada_core::address::testing::{established_address_1, nam};
+    use namada_core::key::testing::keypair_1;
+    use namada_core::key::RefTo;
+
+    use super::*;
+
+    fn usage(amount: u64, timestamp: DateTimeUtc) -> KeyUsage {
+        KeyUsage {
+            tx_hash: Hash::sha256(amount.to_le_bytes()),
+            signer: keypair_1().ref_to(),
+            actions: vec!["Transfer".to_string()],
+            amounts: BTreeMap::from([(
+                nam(),
+                DenominatedAmount::native(Amount::native_whole(amount)),
+            )]),
+            timestamp,
+        }
+    }
+
+    #[test]
+    fn test_log_chaining() {
+        let now = DateTimeUtc::from_unix_timestamp(1_700_000_000).unwrap();
+        let mut log = chain_records(&[], [usage(1, now), usage(2, now)]);
+        log.extend(chain_records(&log, [usage(3, now)]));
+        assert_eq!(log[0].prev_hash, Hash::default());
+        assert_eq!(log[2].prev_hash, log[1].hash);
+        assert_eq!(verify_log(&log), Ok(()));
+
+        // Altering a record breaks the chain at that record
+        let mut altered = log.clone();
+        altered[1].usage.amounts.clear();
+        assert_eq!(
+            verify_log(&altered),
+            Err(AuditError::BrokenChain { index: 1 })
+        );
+
+        // Removing a record breaks the chain at its successor
+        let mut removed = log.clone();
+        removed.remove(1);
+        assert_eq!(
+            verify_log(&removed),
+            Err(AuditError::BrokenChain { index: 1 })
+        );
+    }
+
+    #[test]
+    fn test_policy_check() {
+        let alias = Alias::from("key");
+        let now = DateTimeUtc::from_unix_timestamp(1_700_000_000).unwrap();
+        let yesterday =
+            DateTimeUtc::from_unix_timestamp(1_699_900_000).unwrap();
+        let log = chain_records(&[], [usage(6, yesterday), usage(4, now)]);
+        let spent = spent_on_day(&log, &keypair_1().ref_to(), &now);
+        assert_eq!(
+            spent.get(&nam()),
+            Some(&DenominatedAmount::native(Amount::native_whole(4)))
+        );
+
+        let policy = KeyPolicy {
+            daily_limits: BTreeMap::from([(
+                nam(),
+                DenominatedAmount::native(Amount::native_whole(10)),
+            )]),
+            allowed_targets: BTreeSet::from([established_address_1()]),
+            require_confirmation: false,
+        };
+        let mut summary = TxSummary::default();
+        summary.add_amount(
+            nam(),
+            DenominatedAmount::native(Amount::native_whole(6)),
+        );
+        summary.targets.insert(established_address_1());
+        assert_eq!(policy.check(&alias, &summary, &spent), Ok(()));
+
+        summary.add_amount(
+            nam(),
+            DenominatedAmount::native(Amount::native_whole(1)),
+        );
+        assert!(matches!(
+            policy.check(&alias, &summary, &spent),
+            Err(PolicyViolation::DailyLimitExceeded { .. })
+        ));
+
+        let mut summary = TxSummary::default();
+        summary.targets.insert(nam());
+        assert_eq!(
+            policy.check(&alias, &summary, &spent),
+            Err(PolicyViolation::TargetNotAllowed {
+                alias: alias.clone(),
+                target: nam()
+            })
+        );
+
+        // A transaction with unknown effects is rejected by any policy that
+        // restricts spending, but not by one that only requires confirmation
+        let summary = TxSummary {
+            unsummarized: 1,
+            ..TxSummary::default()
+        };
+        assert_eq!(
+            policy.check(&alias, &summary, &spent),
+            Err(PolicyViolation::Unsummarizable {
+                alias: alias.clone(),
+                count: 1
+            })
+        );
+        let confirm_only = KeyPolicy {
+            require_confirmation: true,
+            ..KeyPolicy::default()
+        };
+        assert_eq!(confirm_only.check(&alias, &summary, &spent), Ok(()));
+    }
+}
@@ -18,6 +18,9 @@ const ZIP32_PURPOSE: u32 = 32;
 const ETH_COIN_TYPE: u32 = 60;
 const NAMADA_COIN_TYPE: u32 = 877;
 
+/// The BIP44 account reserved for the root of the disposable keys
+const DISPOSABLE_KEY_ACCOUNT: u32 = (1 << 31) - 1;
+
 #[allow(missing_docs)]
 #[derive(Error, Debug)]
 pub enum DerivationPathError {
@@ -212,6 +215,21 @@ impl DerivationPath {
         Self::zip32(0, None)
     }
 
+    /// The reserved path of the root of the disposable keys, derived from an
+    /// HD seed
+    pub fn disposable_key_root() -> Self {
+        let path =
+            Self::bip44(SchemeType::Ed25519, DISPOSABLE_KEY_ACCOUNT, 0, 0);
+        path.hardened(SchemeType::Ed25519)
+    }
+
+    /// The path of the disposable key with the given index, derived from the
+    /// root of the disposable keys
+    pub fn disposable_key(index: u32) -> Self {
+        let path = Self::bip44(SchemeType::Ed25519, 0, 0, index);
+        path.hardened(SchemeType::Ed25519)
+    }
+
     /// Try to parse a derivation path from a string
     pub fn from_path_string(path: &str) -> Result<Self, DerivationPathError> {
         let inner = DerivationPathInner::from_str(path).map_err(|err| {
@@ -383,17 +383,8 @@ where
 impl<T: BorshSerialize + BorshDeserialize> EncryptedKeypair<T> {
     /// Encrypt a keypair and store it with its salt.
     pub fn new(keypair: &T, password: Zeroizing<String>) -> Self {
-        let salt = encryption_salt();
-        let encryption_key = encryption_key(&salt, &password);
-
         let data = keypair.serialize_to_vec();
-
-        let encrypted_keypair = aead::seal(&encryption_key, &data)
-            .expect("Encryption of data shouldn't fail");
-
-        let encrypted_data = [salt.as_ref(), &encrypted_keypair].concat();
-
-        Self(encrypted_data, PhantomData)
+        Self(encrypt_data(&data, &password), PhantomData)
     }
 
     /// Decrypt an encrypted keypair
@@ -401,20 +392,7 @@ impl<T: BorshSerialize + BorshDeserialize> EncryptedKeypair<T> {
         &self,
         password: Zeroizing<String>,
     ) -> Result<T, DecryptionError> {
-        if password.is_empty() {
-            return Err(DecryptionError::EmptyPassword);
-        }
-
-        let salt_len = encryption_salt().len();
-        let (raw_salt, cipher) = self.0.split_at(salt_len);
-
-        let salt = kdf::Salt::from_slice(raw_salt)
-            .map_err(|_| DecryptionError::BadSalt)?;
-
-        let encryption_key = encryption_key(&salt, &password);
-
-        let decrypted_data = aead::open(&encryption_key, cipher)
-            .map_err(|_| DecryptionError::DecryptionError)?;
+        let decrypted_data = decrypt_data(&self.0, &password)?;
 
         T::try_from_slice(&decrypted_data)
             .map_err(|_| DecryptionError::DeserializingError)
@@ -429,6 +407,42 @@ impl<T: BorshSerialize + BorshDeserialize> EncryptedKeypair<T> {
     }
 }
 
+/// Encrypt the given data with a key derived from the password, and prefix
+/// the ciphertext with the salt of the derivation.
+pub(crate) fn encrypt_data(data: &[u8], password: &str) -> Vec<u8> {
+    let salt = encryption_salt();
+    let encryption_key = encryption_key(&salt, password);
+
+    let encrypted_data = aead::seal(&encryption_key, data)
+        .expect("Encryption of data shouldn't fail");
+
+    [salt.as_ref(), &encrypted_data].concat()
+}
+
+/// Decrypt data encrypted by [`encrypt_data`] with the same password.
+pub(crate) fn decrypt_data(
+    data: &[u8],
+    password: &str,
+) -> Result<Vec<u8>, DecryptionError> {
+    if password.is_empty() {
+        return Err(DecryptionError::EmptyPassword);
+    }
+
+    let salt_len = encryption_salt().len();
+    if data.len() < salt_len {
+        return Err(DecryptionError::BadSalt);
+    }
+    let (raw_salt, cipher) = data.split_at(salt_len);
+
+    let salt = kdf::Salt::from_slice(raw_salt)
+        .map_err(|_| DecryptionError::BadSalt)?;
+
+    let encryption_key = encryption_key(&salt, password);
+
+    aead::open(&encryption_key, cipher)
+        .map_err(|_| DecryptionError::DecryptionError)
+}
+
 /// Keypair encryption salt
 fn encryption_salt() -> kdf::Salt {
     kdf::Salt::default()
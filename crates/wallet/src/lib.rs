@@ -1,21 +1,27 @@
 #![allow(clippy::print_stdout)]
 
 //! Provides functionality for managing keys and addresses for a user
+pub mod address_book;
 pub mod alias;
+pub mod audit;
 mod derivation_path;
 mod keys;
 pub mod pre_genesis;
+pub mod slip39;
 pub mod store;
 
 use std::collections::BTreeMap;
 use std::fmt::Display;
 use std::str::FromStr;
 
+use address_book::{AddressBookEntry, WatchOnlyAccount};
 use alias::Alias;
+use audit::{KeyPolicy, KeyUsage, KeyUsageRecord, PolicyViolation, TxSummary};
 use bip39::{Language, Mnemonic, MnemonicType, Seed};
 use borsh::{BorshDeserialize, BorshSerialize};
 use namada_core::address::{Address, ImplicitAddress};
 use namada_core::arith::checked;
+use namada_core::borsh::BorshSerializeExt;
 use namada_core::chain::BlockHeight;
 use namada_core::collections::{HashMap, HashSet};
 use namada_core::key::*;
@@ -27,7 +33,8 @@ use namada_ibc::trace::is_ibc_denom;
 pub use pre_genesis::gen_key_to_store;
 use rand::CryptoRng;
 use rand_core::RngCore;
-pub use store::{AddressVpType, Store};
+use slip39::{GroupSpec, Slip39Error};
+pub use store::{AddressVpType, EncryptedStore, Store};
 use thiserror::Error;
 use zeroize::Zeroizing;
 
@@ -36,10 +43,17 @@ pub use self::keys::{
     DatedKeypair, DatedSpendingKey, DatedViewingKey, DecryptionError,
     StoreSpendingKey, StoredKeypair,
 };
-pub use self::store::{ConfirmationResponse, ValidatorData, ValidatorKeys};
+pub use self::store::{
+    ConfirmationResponse, DisposableKeys, StoredValidatorKeys, ValidatorData,
+    ValidatorKeys,
+};
 use crate::store::{derive_hd_secret_key, derive_hd_spending_key};
 
-const DISPOSABLE_KEY_LIFETIME_IN_SECONDS: i64 = 7 * 24 * 60 * 60; // 1 week
+/// The lifetime of disposable keys, one week, unless configured otherwise in
+/// the wallet
+pub const DEFAULT_DISPOSABLE_KEY_LIFETIME_IN_SECONDS: i64 = 7 * 24 * 60 * 60;
+/// The SLIP-39 iteration exponent of the generated shares
+const SLIP39_ITERATION_EXPONENT: u8 = 1;
 
 /// Captures the interactive parts of the wallet's functioning
 pub trait WalletIo: Sized + Clone {
@@ -84,6 +98,17 @@ pub trait WalletIo: Sized + Clone {
         panic!("attempted to prompt for alias in non-interactive mode");
     }
 
+    /// Read SLIP-39 share mnemonics from the file/env/stdin.
+    fn read_slip39_shares() -> Vec<Zeroizing<String>> {
+        panic!("attempted to prompt for shares in non-interactive mode");
+    }
+
+    /// Ask the user to confirm the use of a key whose policy requires it.
+    fn confirm_key_usage(_alias: &Alias, _summary: &TxSummary) -> bool {
+        // Refuse to use such keys in non-interactive mode
+        false
+    }
+
     /// The given alias has been selected but conflicts with another alias in
     /// the store. Offer the user to either replace existing mapping, alter the
     /// chosen alias to a name of their choice, or cancel the aliasing.
@@ -106,12 +131,24 @@ pub enum LoadStoreError {
     /// Wallet store decoding error
     #[error("Failed decoding the wallet store: {0}")]
     Decode(toml::de::Error),
+    /// Encrypted wallet store decryption error
+    #[error("Failed decrypting the wallet store: {0}")]
+    DecryptStore(DecryptionError),
+    /// Encrypted wallet store loaded without a password
+    #[error(
+        "The wallet store at \"{path}\" is encrypted, but no password was \
+         given to decrypt it"
+    )]
+    PasswordRequired { path: String },
     /// Wallet store reading error
     #[error("Failed to read the wallet store from {0}: {1}")]
     ReadWallet(String, String),
     /// Wallet store writing error
     #[error("Failed to write the wallet store: {0}")]
     StoreNewWallet(String),
+    /// Key usage log reading or writing error
+    #[error("Failed to access the key usage log: {0}")]
+    KeyUsageLog(String),
 }
 
 /// Captures the permanent storage parts of the wallet's functioning
@@ -121,6 +158,20 @@ pub trait WalletStorage: Sized + Clone {
 
     /// Load a wallet from the store file.
     fn load<U>(&self, wallet: &mut Wallet<U>) -> Result<(), LoadStoreError>;
+
+    /// Append the given key usages to the key usage log. Storages that keep
+    /// no log ignore them.
+    fn log_key_usage(
+        &self,
+        _usages: Vec<KeyUsage>,
+    ) -> Result<(), LoadStoreError> {
+        Ok(())
+    }
+
+    /// Load the key usage log.
+    fn load_key_usage(&self) -> Result<Vec<KeyUsageRecord>, LoadStoreError> {
+        Ok(vec![])
+    }
 }
 
 #[cfg(feature = "std")]
@@ -143,10 +194,17 @@ pub mod fs {
 
     /// Wallet file name
     const FILE_NAME: &str = "wallet.toml";
+    /// Key usage log file name
+    const KEY_USAGE_FILE_NAME: &str = "key_usage.log";
 
     impl<F: FsWalletStorage> WalletStorage for F {
         fn save<U>(&self, wallet: &Wallet<U>) -> Result<(), LoadStoreError> {
-            let data = wallet.store.encode();
+            let data = match &wallet.store_password {
+                Some(password) => {
+                    EncryptedStore::encrypt(&wallet.store, password).encode()
+                }
+                None => wallet.store.encode(),
+            };
             let wallet_path = self.store_dir().join(FILE_NAME);
             // Make sure the dir exists
             let wallet_dir = wallet_path.parent().unwrap();
@@ -201,10 +259,97 @@ pub mod fs {
                     err.to_string(),
                 )
             })?;
-            wallet.store =
-                Store::decode(store).map_err(LoadStoreError::Decode)?;
+            match EncryptedStore::decode(&store) {
+                Some(encrypted) => {
+                    // The password is kept to encrypt the store again when it
+                    // is saved
+                    let password =
+                        wallet.store_password.as_ref().ok_or_else(|| {
+                            LoadStoreError::PasswordRequired {
+                                path: wallet_file
+                                    .to_string_lossy()
+                                    .into_owned(),
+                            }
+                        })?;
+                    wallet.store = encrypted
+                        .decrypt(password)
+                        .map_err(LoadStoreError::DecryptStore)?;
+                }
+                None => {
+                    wallet.store =
+                        Store::decode(store).map_err(LoadStoreError::Decode)?;
+                    wallet.store_password = None;
+                }
+            }
             Ok(())
         }
+
+        fn log_key_usage(
+            &self,
+            usages: Vec<KeyUsage>,
+        ) -> Result<(), LoadStoreError> {
+            let log_path = self.store_dir().join(KEY_USAGE_FILE_NAME);
+            fs::create_dir_all(self.store_dir())
+                .map_err(|err| LoadStoreError::KeyUsageLog(err.to_string()))?;
+            // Hold the lock from reading the last record until the new ones
+            // are appended so that concurrent writers don't fork the chain
+            let mut options = fs::OpenOptions::new();
+            options.create(true).read(true).append(true);
+            let mut lock =
+                RwLock::new(options.open(log_path).map_err(|err| {
+                    LoadStoreError::KeyUsageLog(err.to_string())
+                })?);
+            let mut guard = lock
+                .write()
+                .map_err(|err| LoadStoreError::KeyUsageLog(err.to_string()))?;
+            let log = read_key_usage(&guard)?;
+            audit::verify_log(&log)
+                .map_err(|err| LoadStoreError::KeyUsageLog(err.to_string()))?;
+            let mut data = String::new();
+            for record in audit::chain_records(&log, usages) {
+                let line = serde_json::to_string(&record).map_err(|err| {
+                    LoadStoreError::KeyUsageLog(err.to_string())
+                })?;
+                data.push_str(&line);
+                data.push('\n');
+            }
+            guard
+                .write_all(data.as_bytes())
+                .map_err(|err| LoadStoreError::KeyUsageLog(err.to_string()))
+        }
+
+        fn load_key_usage(
+            &self,
+        ) -> Result<Vec<KeyUsageRecord>, LoadStoreError> {
+            let log_path = self.store_dir().join(KEY_USAGE_FILE_NAME);
+            if !log_path.exists() {
+                return Ok(vec![]);
+            }
+            let lock =
+                RwLock::new(fs::File::open(log_path).map_err(|err| {
+                    LoadStoreError::KeyUsageLog(err.to_string())
+                })?);
+            let guard = lock
+                .read()
+                .map_err(|err| LoadStoreError::KeyUsageLog(err.to_string()))?;
+            read_key_usage(&guard)
+        }
+    }
+
+    /// Read the records of a key usage log file, one JSON record per line
+    fn read_key_usage(
+        mut file: &fs::File,
+    ) -> Result<Vec<KeyUsageRecord>, LoadStoreError> {
+        let mut data = String::new();
+        file.read_to_string(&mut data)
+            .map_err(|err| LoadStoreError::KeyUsageLog(err.to_string()))?;
+        data.lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                serde_json::from_str(line)
+                    .map_err(|err| LoadStoreError::KeyUsageLog(err.to_string()))
+            })
+            .collect()
     }
 
     /// For a non-interactive filesystem based wallet
@@ -273,6 +418,8 @@ pub struct Wallet<U> {
     /// Location where this shielded context is saved
     utils: U,
     store: Store,
+    /// The password of the store if it is encrypted as a whole
+    store_password: Option<Zeroizing<String>>,
     decrypted_key_cache: HashMap<Alias, common::SecretKey>,
     decrypted_spendkey_cache: HashMap<Alias, ExtendedSpendingKey>,
 }
@@ -289,6 +436,7 @@ impl<U> Wallet<U> {
         Self {
             utils,
             store,
+            store_password: None,
             decrypted_key_cache: HashMap::default(),
             decrypted_spendkey_cache: HashMap::default(),
         }
@@ -313,6 +461,23 @@ impl<U> Wallet<U> {
         self.store.get_validator_data_mut()
     }
 
+    /// Check whether the store is encrypted as a whole when it is saved
+    pub fn is_store_encrypted(&self) -> bool {
+        self.store_password.is_some()
+    }
+
+    /// Encrypt the whole store with the given password from the next time it
+    /// is saved, or change the password of an already encrypted store.
+    pub fn encrypt_store(&mut self, password: Zeroizing<String>) {
+        self.store_password = Some(password);
+    }
+
+    /// Save the whole store in plaintext from the next time it is saved. The
+    /// individually encrypted keys remain encrypted.
+    pub fn decrypt_store(&mut self) {
+        self.store_password = None;
+    }
+
     /// Take the validator data, if it exists.
     pub fn take_validator_data(&mut self) -> Option<ValidatorData> {
         self.store.take_validator_data()
@@ -499,6 +664,45 @@ impl<U> Wallet<U> {
             .collect()
     }
 
+    /// Get all watch-only accounts by their alias
+    pub fn get_watch_only_accounts(
+        &self,
+    ) -> &BTreeMap<Alias, WatchOnlyAccount> {
+        self.store.get_watch_only_accounts()
+    }
+
+    /// Get all address book entries by their alias
+    pub fn get_address_book(&self) -> &BTreeMap<Alias, AddressBookEntry> {
+        self.store.get_address_book()
+    }
+
+    /// Get all key policies by the alias of their key
+    pub fn get_key_policies(&self) -> &BTreeMap<Alias, KeyPolicy> {
+        self.store.get_key_policies()
+    }
+
+    /// Set the policy of the key with the given alias
+    pub fn insert_key_policy(
+        &mut self,
+        alias: impl AsRef<str>,
+        policy: KeyPolicy,
+    ) -> Result<(), FindKeyError> {
+        let alias = alias.as_ref();
+        if self.store.insert_key_policy(alias.into(), policy) {
+            Ok(())
+        } else {
+            Err(FindKeyError::KeyNotFound(alias.to_string()))
+        }
+    }
+
+    /// Remove the policy of the key with the given alias
+    pub fn remove_key_policy(
+        &mut self,
+        alias: impl AsRef<str>,
+    ) -> Option<KeyPolicy> {
+        self.store.remove_key_policy(&alias.into())
+    }
+
     /// Get all known payment addresses by their alias
     pub fn get_payment_addrs(&self) -> HashMap<String, PaymentAddress> {
         self.store
@@ -550,15 +754,84 @@ impl<U> Wallet<U> {
 }
 
 impl<U: WalletStorage> Wallet<U> {
-    /// Load a wallet from the store file.
+    /// Load a wallet from the store file. Fails with
+    /// [`LoadStoreError::PasswordRequired`] if the store is encrypted as a
+    /// whole and no password was given to this wallet before.
     pub fn load(&mut self) -> Result<(), LoadStoreError> {
         self.utils.clone().load(self)
     }
 
+    /// Load a wallet from the store file, which is decrypted with the given
+    /// password if it is encrypted as a whole.
+    pub fn load_with_password(
+        &mut self,
+        password: Zeroizing<String>,
+    ) -> Result<(), LoadStoreError> {
+        self.store_password = Some(password);
+        self.load()
+    }
+
     /// Save the wallet store to a file.
     pub fn save(&self) -> Result<(), LoadStoreError> {
         self.utils.save(self)
     }
+
+    /// Append the given key usages to the key usage log.
+    pub fn record_key_usage(
+        &self,
+        usages: Vec<KeyUsage>,
+    ) -> Result<(), LoadStoreError> {
+        self.utils.log_key_usage(usages)
+    }
+
+    /// Load the key usage log.
+    pub fn load_key_usage(
+        &self,
+    ) -> Result<Vec<KeyUsageRecord>, LoadStoreError> {
+        self.utils.load_key_usage()
+    }
+}
+
+impl<U: WalletIo + WalletStorage> Wallet<U> {
+    /// Check that the policy of the given key, if any, allows it to sign a
+    /// transaction with the given summary. The amounts spent today are taken
+    /// from the key usage log, which must be intact. Prompts for confirmation
+    /// if the policy requires it.
+    pub fn check_key_policy(
+        &self,
+        public_key: &common::PublicKey,
+        summary: &TxSummary,
+    ) -> Result<(), PolicyViolation> {
+        let Some((alias, policy)) =
+            self.store.find_key_policy_by_pkh(&public_key.into())
+        else {
+            return Ok(());
+        };
+        let spent_today = if policy.daily_limits.is_empty() {
+            Default::default()
+        } else {
+            let unreadable = |reason: String| PolicyViolation::UnreadableLog {
+                alias: alias.clone(),
+                reason,
+            };
+            let log = self
+                .load_key_usage()
+                .map_err(|err| unreadable(err.to_string()))?;
+            audit::verify_log(&log)
+                .map_err(|err| unreadable(err.to_string()))?;
+            #[allow(clippy::disallowed_methods)]
+            let now = DateTimeUtc::now();
+            audit::spent_on_day(&log, public_key, &now)
+        };
+        policy.check(alias, summary, &spent_today)?;
+        if policy.require_confirmation && !U::confirm_key_usage(alias, summary)
+        {
+            return Err(PolicyViolation::NotConfirmed {
+                alias: alias.clone(),
+            });
+        }
+        Ok(())
+    }
 }
 
 impl<U: WalletIo> Wallet<U> {
@@ -670,6 +943,7 @@ impl<U: WalletIo> Wallet<U> {
                 (mnemonic, passphrase)
             };
         let seed = Seed::new(&mnemonic, &passphrase);
+        self.init_disposable_key_root(seed.as_bytes());
         let sk = derive_hd_secret_key(
             scheme,
             seed.as_bytes(),
@@ -764,6 +1038,81 @@ impl<U: WalletIo> Wallet<U> {
         (mnemonic, seed)
     }
 
+    /// Generate a random master secret and split it into SLIP-39 share
+    /// mnemonics, a `group_threshold` of the given groups being required to
+    /// recover it, after encrypting it with the given passphrase. If no
+    /// passphrase is provided, optionally prompt for a passphrase. The shares
+    /// are printed and the master secret is returned to be used as the HD
+    /// wallet seed.
+    pub fn gen_hd_seed_shares(
+        group_threshold: u8,
+        groups: &[GroupSpec],
+        passphrase: Option<Zeroizing<String>>,
+        rng: &mut U::Rng,
+        prompt_bip39_passphrase: bool,
+    ) -> Result<Zeroizing<Vec<u8>>, Slip39Error> {
+        const MASTER_SECRET_LENGTH: usize = 32;
+        let mut master_secret = Zeroizing::new(vec![0; MASTER_SECRET_LENGTH]);
+        rng.fill_bytes(&mut master_secret);
+        let passphrase = passphrase.unwrap_or_else(|| {
+            if prompt_bip39_passphrase {
+                U::read_mnemonic_passphrase(true)
+            } else {
+                Zeroizing::default()
+            }
+        });
+        let shares = slip39::generate_mnemonics(
+            group_threshold,
+            groups,
+            &master_secret,
+            &passphrase,
+            SLIP39_ITERATION_EXPONENT,
+            rng,
+        )?;
+
+        println!(
+            "Safely store each of the following shares separately. Shares \
+             from {} of the {} groups are required to recover the key.",
+            group_threshold,
+            groups.len()
+        );
+        for (idx, (group, shares)) in groups.iter().zip(shares).enumerate() {
+            println!(
+                "Group {} of {}, any {} of its {} shares are required:",
+                idx + 1,
+                groups.len(),
+                group.member_threshold,
+                group.member_count
+            );
+            for share in shares {
+                println!("  {}", share.as_str());
+            }
+        }
+        Ok(master_secret)
+    }
+
+    /// Recover the HD wallet seed from SLIP-39 share mnemonics and the
+    /// passphrase they were encrypted with. If none are provided, the shares
+    /// are read from stdin and the passphrase is optionally prompted for.
+    pub fn recover_hd_seed_from_shares(
+        shares_passphrase: Option<(Vec<Zeroizing<String>>, Zeroizing<String>)>,
+        prompt_bip39_passphrase: bool,
+    ) -> Result<Zeroizing<Vec<u8>>, Slip39Error> {
+        let (shares, passphrase) =
+            if let Some(shares_passphrase) = shares_passphrase {
+                shares_passphrase
+            } else {
+                let shares = U::read_slip39_shares();
+                let passphrase = if prompt_bip39_passphrase {
+                    U::read_mnemonic_passphrase(false)
+                } else {
+                    Zeroizing::default()
+                };
+                (shares, passphrase)
+            };
+        slip39::combine_mnemonics(&shares, &passphrase)
+    }
+
     /// Derive a keypair from the given seed and path, derive an implicit
     /// address from this keypair, and insert them into the store with the
     /// provided alias, converted to lower case. If none provided, the alias
@@ -778,15 +1127,12 @@ impl<U: WalletIo> Wallet<U> {
         scheme: SchemeType,
         alias: Option<String>,
         alias_force: bool,
-        seed: Seed,
+        seed: &[u8],
         derivation_path: DerivationPath,
         password: Option<Zeroizing<String>>,
     ) -> Option<(String, common::SecretKey)> {
-        let sk = derive_hd_secret_key(
-            scheme,
-            seed.as_bytes(),
-            derivation_path.clone(),
-        );
+        self.init_disposable_key_root(seed);
+        let sk = derive_hd_secret_key(scheme, seed, derivation_path.clone());
         self.insert_keypair(
             alias.unwrap_or_default(),
             alias_force,
@@ -816,12 +1162,11 @@ impl<U: WalletIo> Wallet<U> {
         alias: String,
         force_alias: bool,
         birthday: Option<BlockHeight>,
-        seed: Seed,
+        seed: &[u8],
         derivation_path: DerivationPath,
         password: Option<Zeroizing<String>>,
     ) -> Option<(String, ExtendedSpendingKey)> {
-        let spend_key =
-            derive_hd_spending_key(seed.as_bytes(), derivation_path.clone());
+        let spend_key = derive_hd_spending_key(seed, derivation_path.clone());
         self.insert_spending_key(
             alias,
             force_alias,
@@ -833,21 +1178,55 @@ impl<U: WalletIo> Wallet<U> {
         .map(|alias| (alias, spend_key))
     }
 
-    /// Generate a disposable signing key for fee payment and store it under the
-    /// precomputed alias in the wallet. This is simply a wrapper around
-    /// `gen_key` to manage the alias
-    pub fn gen_disposable_signing_key(
-        &mut self,
-        rng: &mut (impl CryptoRng + RngCore),
-    ) -> common::SecretKey {
-        #[allow(clippy::disallowed_methods)]
-        let current_unix_timestamp = DateTimeUtc::now().to_unix_timestamp();
+    /// Read a BIP39 mnemonic code from stdin and derive the HD wallet seed
+    /// from it, optionally prompting for its passphrase.
+    pub fn read_hd_seed(prompt_bip39_passphrase: bool) -> Option<Seed> {
+        let mnemonic = U::read_mnemonic_code()?;
+        let passphrase = if prompt_bip39_passphrase {
+            U::read_mnemonic_passphrase(false)
+        } else {
+            Zeroizing::default()
+        };
+        Some(Seed::new(&mnemonic, &passphrase))
+    }
 
-        let disposable_keys_to_gc = self
-            .store
+    /// Derive the root of the disposable keys from the given HD seed, unless
+    /// the wallet already has one
+    fn init_disposable_key_root(&mut self, seed: &[u8]) {
+        let disposable_keys = self.store.get_disposable_keys_mut();
+        if disposable_keys.root.is_none() {
+            disposable_keys.root = Some(derive_hd_secret_key(
+                SchemeType::Ed25519,
+                seed,
+                DerivationPath::disposable_key_root(),
+            ));
+        }
+    }
+
+    /// Get the lifetime of the disposable keys in seconds
+    pub fn disposable_key_lifetime(&self) -> i64 {
+        self.store
+            .get_disposable_keys()
+            .lifetime
+            .unwrap_or(DEFAULT_DISPOSABLE_KEY_LIFETIME_IN_SECONDS)
+    }
+
+    /// Set the lifetime of the disposable keys in seconds
+    pub fn set_disposable_key_lifetime(&mut self, lifetime: i64) {
+        self.store.get_disposable_keys_mut().lifetime = Some(lifetime);
+    }
+
+    /// Find the disposable keys that have outlived their lifetime at the given
+    /// unix timestamp, with their public keys
+    pub fn find_expired_disposable_keys(
+        &self,
+        current_unix_timestamp: i64,
+    ) -> Vec<(Alias, common::PublicKey)> {
+        let lifetime = self.disposable_key_lifetime();
+        self.store
             .get_public_keys()
-            .keys()
-            .filter(|key_alias| {
+            .iter()
+            .filter(|(key_alias, _pk)| {
                 check_if_disposable_key_and(
                     key_alias,
                     |_pkh, key_creation_unix_timestamp| {
@@ -859,19 +1238,56 @@ impl<U: WalletIo> Wallet<U> {
                             "Key should have been created before the current \
                              time instant!",
                         );
-                        seconds_since_key_creation
-                            > DISPOSABLE_KEY_LIFETIME_IN_SECONDS
+                        seconds_since_key_creation > lifetime
                     },
                 )
             })
-            .cloned()
-            .collect::<Vec<_>>();
+            .map(|(key_alias, pk)| (key_alias.clone(), pk.clone()))
+            .collect()
+    }
 
-        for key_alias in disposable_keys_to_gc {
-            self.store.remove_alias(&key_alias);
+    /// Remove the disposable keys that have outlived their lifetime at the
+    /// given unix timestamp and return their aliases. Keys derived from the
+    /// root of the disposable keys can be brought back with
+    /// [`Self::restore_disposable_keys`].
+    pub fn prune_disposable_keys(
+        &mut self,
+        current_unix_timestamp: i64,
+    ) -> Vec<Alias> {
+        let disposable_keys_to_gc = self
+            .find_expired_disposable_keys(current_unix_timestamp)
+            .into_iter()
+            .map(|(key_alias, _pk)| key_alias)
+            .collect::<Vec<_>>();
+        for key_alias in &disposable_keys_to_gc {
+            self.store.remove_alias(key_alias);
         }
+        disposable_keys_to_gc
+    }
 
-        let sk = gen_secret_key(SchemeType::Ed25519, rng);
+    /// Generate a disposable signing key for fee payment and store it under the
+    /// precomputed alias in the wallet, after pruning the expired disposable
+    /// keys. The key is derived from the root of the disposable keys, if the
+    /// wallet has one, so that it can be restored from the HD seed.
+    pub fn gen_disposable_signing_key(
+        &mut self,
+        rng: &mut (impl CryptoRng + RngCore),
+    ) -> common::SecretKey {
+        #[allow(clippy::disallowed_methods)]
+        let current_unix_timestamp = DateTimeUtc::now().to_unix_timestamp();
+
+        self.prune_disposable_keys(current_unix_timestamp);
+
+        let disposable_keys = self.store.get_disposable_keys_mut();
+        let sk = match &disposable_keys.root {
+            Some(root) => {
+                let index = disposable_keys.next_index;
+                disposable_keys.next_index = checked!(index + 1)
+                    .expect("Disposable key indices should not overflow");
+                derive_disposable_key(root, index)
+            }
+            None => gen_secret_key(SchemeType::Ed25519, rng),
+        };
         let key_alias = {
             let pkh: PublicKeyHash = (&sk.to_public()).into();
             disposable_key_alias(&pkh, current_unix_timestamp)
@@ -889,6 +1305,48 @@ impl<U: WalletIo> Wallet<U> {
         sk
     }
 
+    /// Derive the first `count` disposable keys from the given HD seed and
+    /// store the ones missing from the wallet. The restored keys are dated
+    /// back to the unix epoch, so that they are considered expired and their
+    /// balances can be swept. The root of the disposable keys is set from the
+    /// seed if the wallet doesn't have one yet. Returns the aliases of the
+    /// restored keys.
+    pub fn restore_disposable_keys(
+        &mut self,
+        seed: &[u8],
+        count: u32,
+    ) -> Vec<String> {
+        self.init_disposable_key_root(seed);
+        let root = derive_hd_secret_key(
+            SchemeType::Ed25519,
+            seed,
+            DerivationPath::disposable_key_root(),
+        );
+        let disposable_keys = self.store.get_disposable_keys_mut();
+        let same_root = disposable_keys
+            .root
+            .as_ref()
+            .is_some_and(|key| key.to_public() == root.to_public());
+        if same_root {
+            // Don't reuse any of the restored keys
+            disposable_keys.next_index = disposable_keys.next_index.max(count);
+        }
+
+        let mut restored = vec![];
+        for index in 0..count {
+            let sk = derive_disposable_key(&root, index);
+            let pkh: PublicKeyHash = (&sk.to_public()).into();
+            if self.store.find_key_by_pkh(&pkh).is_some() {
+                continue;
+            }
+            let key_alias = disposable_key_alias(&pkh, 0);
+            restored.extend(
+                self.insert_keypair(key_alias, false, sk, None, None, None),
+            );
+        }
+        restored
+    }
+
     /// Find the stored key by an alias, a public key hash or a public key.
     /// If the key is encrypted and password not supplied, then password will be
     /// interactively prompted. Any keys that are decrypted are stored in and
@@ -1118,6 +1576,34 @@ impl<U: WalletIo> Wallet<U> {
             .map(Into::into)
     }
 
+    /// Add a watch-only account with the given alias. If the alias is already
+    /// used, will prompt for overwrite/reselection confirmation. Return the
+    /// selected alias if the account has been added.
+    pub fn insert_watch_only(
+        &mut self,
+        alias: impl AsRef<str>,
+        account: WatchOnlyAccount,
+        force_alias: bool,
+    ) -> Option<String> {
+        self.store
+            .insert_watch_only::<U>(alias.into(), account, force_alias)
+            .map(Into::into)
+    }
+
+    /// Add an address book entry with the given alias. If the alias is
+    /// already used, will prompt for overwrite/reselection confirmation.
+    /// Return the selected alias if the entry has been added.
+    pub fn insert_address_book_entry(
+        &mut self,
+        alias: impl AsRef<str>,
+        entry: AddressBookEntry,
+        force_alias: bool,
+    ) -> Option<String> {
+        self.store
+            .insert_address_book_entry::<U>(alias.into(), entry, force_alias)
+            .map(Into::into)
+    }
+
     /// Add a new keypair with the given alias. If the alias is already used,
     /// will ask whether the existing alias should be replaced, a different
     /// alias is desired, or the alias creation should be cancelled. Return
@@ -1240,6 +1726,19 @@ impl<U: WalletIo> Wallet<U> {
     }
 }
 
+/// Derive the disposable key with the given index from the root of the
+/// disposable keys
+fn derive_disposable_key(
+    root: &common::SecretKey,
+    index: u32,
+) -> common::SecretKey {
+    derive_hd_secret_key(
+        SchemeType::Ed25519,
+        &root.serialize_to_vec(),
+        DerivationPath::disposable_key(index),
+    )
+}
+
 #[inline]
 fn disposable_key_alias(pkh: &PublicKeyHash, timestamp: i64) -> String {
     format!("disposable-key-{pkh}-created-at-{timestamp}")
@@ -1328,6 +1827,7 @@ mod tests {
         let mut wallet = Wallet {
             utils: TestWalletUtils,
             store: Default::default(),
+            store_password: None,
             decrypted_key_cache: Default::default(),
             decrypted_spendkey_cache: Default::default(),
         };
@@ -1376,4 +1876,90 @@ mod tests {
                 .any(|pk| *pk == new_key_pk)
         );
     }
+
+    #[test]
+    fn test_disposable_keys_are_restorable() {
+        let new_wallet = || Wallet {
+            utils: TestWalletUtils,
+            store: Default::default(),
+            store_password: None,
+            decrypted_key_cache: Default::default(),
+            decrypted_spendkey_cache: Default::default(),
+        };
+        let seed = [7u8; 64];
+
+        // Deriving a key from the seed sets the root of the disposable keys
+        let mut wallet = new_wallet();
+        wallet
+            .derive_store_hd_secret_key(
+                SchemeType::Ed25519,
+                Some("albert".to_string()),
+                false,
+                &seed,
+                DerivationPath::default_for_transparent_scheme(
+                    SchemeType::Ed25519,
+                ),
+                None,
+            )
+            .unwrap();
+        let disposable_pks = [
+            wallet.gen_disposable_signing_key(&mut OsRng).to_public(),
+            wallet.gen_disposable_signing_key(&mut OsRng).to_public(),
+        ];
+        assert_ne!(disposable_pks[0], disposable_pks[1]);
+
+        // The keys are restored from the seed and are expired right away
+        let mut restored_wallet = new_wallet();
+        let restored = restored_wallet.restore_disposable_keys(&seed, 2);
+        assert_eq!(restored.len(), 2);
+        #[allow(clippy::disallowed_methods)]
+        let now = DateTimeUtc::now().to_unix_timestamp();
+        let expired = restored_wallet
+            .find_expired_disposable_keys(now)
+            .into_iter()
+            .map(|(_alias, pk)| pk)
+            .collect::<Vec<_>>();
+        assert_eq!(expired.len(), 2);
+        assert!(disposable_pks.iter().all(|pk| expired.contains(pk)));
+
+        // New disposable keys don't reuse the restored ones
+        let new_pk = restored_wallet
+            .gen_disposable_signing_key(&mut OsRng)
+            .to_public();
+        assert!(!disposable_pks.contains(&new_pk));
+
+        // The lifetime is configurable
+        wallet.set_disposable_key_lifetime(0);
+        assert_eq!(wallet.prune_disposable_keys(now + 1).len(), 2);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_load_encrypted_store() {
+        use crate::fs::FsWalletUtils;
+
+        let dir = tempfile::tempdir().unwrap();
+        let address = Address::from(&keypair_1().ref_to());
+        let mut wallet = FsWalletUtils::new(dir.path().to_path_buf());
+        wallet.insert_address("alias", address.clone(), false);
+        wallet.encrypt_store(Zeroizing::new("password".to_string()));
+        wallet.save().unwrap();
+
+        // A non-interactive wallet fails to load the store without a
+        // password, rather than prompting for one
+        let mut wallet = FsWalletUtils::new(dir.path().to_path_buf());
+        assert!(matches!(
+            wallet.load(),
+            Err(LoadStoreError::PasswordRequired { .. })
+        ));
+        assert!(matches!(
+            wallet.load_with_password(Zeroizing::new("wrong".to_string())),
+            Err(LoadStoreError::DecryptStore(_))
+        ));
+        wallet
+            .load_with_password(Zeroizing::new("password".to_string()))
+            .unwrap();
+        assert!(wallet.is_store_encrypted());
+        assert_eq!(wallet.find_address("alias").as_deref(), Some(&address));
+    }
 }
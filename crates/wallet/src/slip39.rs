@@ -0,0 +1,939 @@
+//! Shamir backups of HD wallet seeds following
+//! [SLIP-39](https://github.com/satoshilabs/slips/blob/master/slip-0039.md).
+//!
+//! A master secret is encrypted with an optional passphrase and split in two
+//! levels: into groups, a threshold of which is required to recover the
+//! secret, and each group into member shares, a threshold of which is
+//! required to recover the group. Every share is encoded as a mnemonic from
+//! the SLIP-39 wordlist. The recovered master secret is used directly as the
+//! seed of the transparent and shielded HD derivations.
+
+use std::collections::BTreeMap;
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use orion::hazardous::kdf::pbkdf2;
+use orion::hazardous::mac::hmac::sha256::{HmacSha256, SecretKey};
+use rand_core::RngCore;
+use thiserror::Error;
+use zeroize::Zeroizing;
+
+/// The SLIP-39 wordlist, one word per line
+const WORDLIST: &str = include_str!("slip39_wordlist.txt");
+/// The number of bits encoded by a word
+const RADIX_BITS: usize = 10;
+/// The number of words in the wordlist
+const RADIX: u16 = 1 << RADIX_BITS;
+/// The number of bits of the random identifier of a backup
+const ID_LENGTH_BITS: u32 = 15;
+/// The number of words of the share header
+const HEADER_LENGTH_WORDS: usize = 4;
+/// The number of words of the checksum
+const CHECKSUM_LENGTH_WORDS: usize = 3;
+/// The minimal strength of a master secret in bits
+const MIN_STRENGTH_BITS: usize = 128;
+/// The minimal number of words of a share mnemonic
+const MIN_MNEMONIC_LENGTH_WORDS: usize = HEADER_LENGTH_WORDS
+    + CHECKSUM_LENGTH_WORDS
+    + MIN_STRENGTH_BITS.div_ceil(RADIX_BITS);
+/// The maximal number of groups and of member shares in a group
+const MAX_SHARE_COUNT: u8 = 16;
+/// The length of the digest of the shared secret in bytes
+const DIGEST_LENGTH_BYTES: usize = 4;
+/// The x-coordinate of the shared secret
+const SECRET_INDEX: u8 = 255;
+/// The x-coordinate of the digest of the shared secret
+const DIGEST_INDEX: u8 = 254;
+/// The number of rounds of the Feistel cipher
+const ROUND_COUNT: u8 = 4;
+/// The total number of PBKDF2 iterations of the Feistel cipher for an
+/// iteration exponent of zero
+const BASE_ITERATION_COUNT: usize = 10_000;
+/// The checksum customization string of non-extendable backups
+const CUSTOMIZATION_STRING_ORIG: &[u8] = b"shamir";
+/// The checksum customization string of extendable backups
+const CUSTOMIZATION_STRING_EXTENDABLE: &[u8] = b"shamir_extendable";
+
+/// Errors of SLIP-39 share generation and recovery
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum Slip39Error {
+    /// A share mnemonic could not be decoded
+    #[error("Invalid share mnemonic: {0}")]
+    InvalidMnemonic(String),
+    /// The requested sharing scheme or master secret is not supported
+    #[error("Invalid SLIP-39 parameters: {0}")]
+    InvalidParameters(String),
+    /// The shares do not belong together or are not enough
+    #[error("Invalid set of shares: {0}")]
+    InvalidShares(String),
+    /// The recovered secret does not match its digest
+    #[error(
+        "Invalid digest of the shared secret, the shares may be corrupted"
+    )]
+    InvalidDigest,
+}
+
+/// The member threshold and member count of a group of shares, written as
+/// `<threshold>-of-<count>`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GroupSpec {
+    /// The number of member shares required to recover the group
+    pub member_threshold: u8,
+    /// The number of member shares of the group
+    pub member_count: u8,
+}
+
+impl Display for GroupSpec {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-of-{}", self.member_threshold, self.member_count)
+    }
+}
+
+impl FromStr for GroupSpec {
+    type Err = Slip39Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            Slip39Error::InvalidParameters(format!(
+                "Expected a group of shares as <threshold>-of-<count>, got \
+                 \"{s}\""
+            ))
+        };
+        let (threshold, count) =
+            s.trim().split_once("-of-").ok_or_else(invalid)?;
+        let spec = Self {
+            member_threshold: threshold.parse().map_err(|_| invalid())?,
+            member_count: count.parse().map_err(|_| invalid())?,
+        };
+        spec.validate()?;
+        Ok(spec)
+    }
+}
+
+impl GroupSpec {
+    /// Check that the group can be generated
+    fn validate(&self) -> Result<(), Slip39Error> {
+        if self.member_count == 0 || self.member_count > MAX_SHARE_COUNT {
+            return Err(Slip39Error::InvalidParameters(format!(
+                "The number of shares of a group must be between 1 and \
+                 {MAX_SHARE_COUNT}, got {}",
+                self.member_count
+            )));
+        }
+        if self.member_threshold == 0
+            || self.member_threshold > self.member_count
+        {
+            return Err(Slip39Error::InvalidParameters(format!(
+                "The threshold of group {self} must be between 1 and the \
+                 number of shares"
+            )));
+        }
+        if self.member_threshold == 1 && self.member_count > 1 {
+            return Err(Slip39Error::InvalidParameters(format!(
+                "Creating several shares with a threshold of 1 is not \
+                 allowed, use 1-of-1 instead of {self}"
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// The groups of shares of a backup, written as comma separated
+/// `<threshold>-of-<count>` specifications
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ShareGroups(pub Vec<GroupSpec>);
+
+impl Display for ShareGroups {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let groups: Vec<String> =
+            self.0.iter().map(ToString::to_string).collect();
+        write!(f, "{}", groups.join(","))
+    }
+}
+
+impl FromStr for ShareGroups {
+    type Err = Slip39Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let groups = s
+            .split(',')
+            .map(GroupSpec::from_str)
+            .collect::<Result<Vec<_>, _>>()?;
+        if groups.len() > usize::from(MAX_SHARE_COUNT) {
+            return Err(Slip39Error::InvalidParameters(format!(
+                "At most {MAX_SHARE_COUNT} groups of shares are supported"
+            )));
+        }
+        Ok(Self(groups))
+    }
+}
+
+/// A decoded share mnemonic
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Share {
+    identifier: u16,
+    extendable: bool,
+    iteration_exponent: u8,
+    group_index: u8,
+    group_threshold: u8,
+    group_count: u8,
+    member_index: u8,
+    member_threshold: u8,
+    value: Zeroizing<Vec<u8>>,
+}
+
+impl Share {
+    /// Decode a share from its mnemonic
+    fn from_mnemonic(mnemonic: &str) -> Result<Self, Slip39Error> {
+        let indices = mnemonic
+            .split_whitespace()
+            .map(|word| {
+                word_index(word).ok_or_else(|| {
+                    Slip39Error::InvalidMnemonic(format!(
+                        "Unknown word \"{word}\""
+                    ))
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if indices.len() < MIN_MNEMONIC_LENGTH_WORDS {
+            return Err(Slip39Error::InvalidMnemonic(format!(
+                "A share must have at least {MIN_MNEMONIC_LENGTH_WORDS} \
+                 words, got {}",
+                indices.len()
+            )));
+        }
+        let value_words = &indices
+            [HEADER_LENGTH_WORDS..indices.len() - CHECKSUM_LENGTH_WORDS];
+        let padding_bits = (RADIX_BITS * value_words.len()) % 16;
+        if padding_bits > 8 {
+            return Err(Slip39Error::InvalidMnemonic(
+                "Invalid number of words".to_string(),
+            ));
+        }
+
+        let id_exp =
+            (u32::from(indices[0]) << RADIX_BITS) | u32::from(indices[1]);
+        let extendable = (id_exp >> 4) & 1 == 1;
+        if rs1024_polymod(customization_string(extendable), &indices) != 1 {
+            return Err(Slip39Error::InvalidMnemonic(
+                "Invalid checksum".to_string(),
+            ));
+        }
+        let params =
+            (u32::from(indices[2]) << RADIX_BITS) | u32::from(indices[3]);
+        let nibble = |shift: u32| (params >> shift & 0xf) as u8;
+        let share = Self {
+            identifier: (id_exp >> 5) as u16,
+            extendable,
+            iteration_exponent: (id_exp & 0xf) as u8,
+            group_index: nibble(16),
+            group_threshold: nibble(12) + 1,
+            group_count: nibble(8) + 1,
+            member_index: nibble(4),
+            member_threshold: nibble(0) + 1,
+            value: words_to_bytes(value_words, padding_bits).ok_or_else(
+                || Slip39Error::InvalidMnemonic("Invalid padding".to_string()),
+            )?,
+        };
+        if share.group_threshold > share.group_count {
+            return Err(Slip39Error::InvalidMnemonic(
+                "The group threshold exceeds the number of groups".to_string(),
+            ));
+        }
+        Ok(share)
+    }
+
+    /// Encode the share into a mnemonic
+    fn to_mnemonic(&self) -> Zeroizing<String> {
+        let id_exp = (u32::from(self.identifier) << 5)
+            | (u32::from(self.extendable) << 4)
+            | u32::from(self.iteration_exponent);
+        let params = (u32::from(self.group_index) << 16)
+            | (u32::from(self.group_threshold - 1) << 12)
+            | (u32::from(self.group_count - 1) << 8)
+            | (u32::from(self.member_index) << 4)
+            | u32::from(self.member_threshold - 1);
+        let mut indices = vec![
+            (id_exp >> RADIX_BITS) as u16,
+            (id_exp % u32::from(RADIX)) as u16,
+            (params >> RADIX_BITS) as u16,
+            (params % u32::from(RADIX)) as u16,
+        ];
+        indices.extend(bytes_to_words(&self.value));
+        let checksum = rs1024_polymod(
+            customization_string(self.extendable),
+            &[&indices[..], &[0; CHECKSUM_LENGTH_WORDS][..]].concat(),
+        ) ^ 1;
+        indices.extend((0..CHECKSUM_LENGTH_WORDS).rev().map(|i| {
+            (checksum >> (RADIX_BITS * i) & u32::from(RADIX - 1)) as u16
+        }));
+        let words = wordlist();
+        Zeroizing::new(
+            indices
+                .iter()
+                .map(|idx| words[usize::from(*idx)])
+                .collect::<Vec<_>>()
+                .join(" "),
+        )
+    }
+}
+
+/// Split the given master secret into share mnemonics, encrypting it with
+/// the given passphrase first. A `group_threshold` of the `groups` is
+/// required to recover the secret. Returns the mnemonics of each group.
+pub fn generate_mnemonics(
+    group_threshold: u8,
+    groups: &[GroupSpec],
+    master_secret: &[u8],
+    passphrase: &str,
+    iteration_exponent: u8,
+    rng: &mut impl RngCore,
+) -> Result<Vec<Vec<Zeroizing<String>>>, Slip39Error> {
+    if master_secret.len() * 8 < MIN_STRENGTH_BITS
+        || master_secret.len() % 2 == 1
+    {
+        return Err(Slip39Error::InvalidParameters(format!(
+            "The master secret must be an even number of bytes and at least \
+             {} bytes long",
+            MIN_STRENGTH_BITS / 8
+        )));
+    }
+    check_passphrase(passphrase)?;
+    if iteration_exponent > 0xf {
+        return Err(Slip39Error::InvalidParameters(
+            "The iteration exponent must be at most 15".to_string(),
+        ));
+    }
+    let group_count = u8::try_from(groups.len())
+        .ok()
+        .filter(|count| (1..=MAX_SHARE_COUNT).contains(count))
+        .ok_or_else(|| {
+            Slip39Error::InvalidParameters(format!(
+                "The number of groups must be between 1 and {MAX_SHARE_COUNT}"
+            ))
+        })?;
+    if group_threshold == 0 || group_threshold > group_count {
+        return Err(Slip39Error::InvalidParameters(
+            "The group threshold must be between 1 and the number of groups"
+                .to_string(),
+        ));
+    }
+    for group in groups {
+        group.validate()?;
+    }
+
+    // The new backups are extendable, i.e. their identifier is not part of
+    // the encryption so that more shares can later be made for the same
+    // secret
+    let extendable = true;
+    let identifier = (rng.next_u32() & ((1 << ID_LENGTH_BITS) - 1)) as u16;
+    let encrypted_secret = encrypt(
+        master_secret,
+        passphrase,
+        iteration_exponent,
+        identifier,
+        extendable,
+    );
+    let group_secrets =
+        split_secret(group_threshold, group_count, &encrypted_secret, rng)?;
+    groups
+        .iter()
+        .zip(group_secrets)
+        .map(|(group, (group_index, group_secret))| {
+            let member_secrets = split_secret(
+                group.member_threshold,
+                group.member_count,
+                &group_secret,
+                rng,
+            )?;
+            Ok(member_secrets
+                .into_iter()
+                .map(|(member_index, value)| {
+                    Share {
+                        identifier,
+                        extendable,
+                        iteration_exponent,
+                        group_index,
+                        group_threshold,
+                        group_count,
+                        member_index,
+                        member_threshold: group.member_threshold,
+                        value,
+                    }
+                    .to_mnemonic()
+                })
+                .collect())
+        })
+        .collect()
+}
+
+/// Recover the master secret from the given share mnemonics, decrypting it
+/// with the given passphrase. Extra shares beyond the thresholds are ignored.
+pub fn combine_mnemonics(
+    mnemonics: &[impl AsRef<str>],
+    passphrase: &str,
+) -> Result<Zeroizing<Vec<u8>>, Slip39Error> {
+    check_passphrase(passphrase)?;
+    let shares = mnemonics
+        .iter()
+        .map(|mnemonic| Share::from_mnemonic(mnemonic.as_ref()))
+        .collect::<Result<Vec<_>, _>>()?;
+    let first = shares.first().ok_or_else(|| {
+        Slip39Error::InvalidShares("No shares were provided".to_string())
+    })?;
+    let mut groups: BTreeMap<u8, BTreeMap<u8, &Share>> = BTreeMap::new();
+    for share in &shares {
+        if (
+            share.identifier,
+            share.extendable,
+            share.iteration_exponent,
+            share.group_threshold,
+            share.group_count,
+        ) != (
+            first.identifier,
+            first.extendable,
+            first.iteration_exponent,
+            first.group_threshold,
+            first.group_count,
+        ) {
+            return Err(Slip39Error::InvalidShares(
+                "The shares do not belong to the same backup".to_string(),
+            ));
+        }
+        let members = groups.entry(share.group_index).or_default();
+        if members.values().any(|member: &&Share| {
+            member.member_threshold != share.member_threshold
+        }) {
+            return Err(Slip39Error::InvalidShares(format!(
+                "The shares of group {} have different thresholds",
+                share.group_index
+            )));
+        }
+        if let Some(member) = members.insert(share.member_index, share) {
+            if member.value != share.value {
+                return Err(Slip39Error::InvalidShares(format!(
+                    "Group {} contains different shares with the same index",
+                    share.group_index
+                )));
+            }
+        }
+    }
+
+    let group_secrets = groups
+        .iter()
+        .filter_map(|(group_index, members)| {
+            let threshold = members.values().next()?.member_threshold;
+            (members.len() >= usize::from(threshold)).then(|| {
+                let members: Vec<_> = members
+                    .iter()
+                    .take(usize::from(threshold))
+                    .map(|(idx, share)| (*idx, share.value.clone()))
+                    .collect();
+                recover_secret(threshold, &members)
+                    .map(|secret| (*group_index, secret))
+            })
+        })
+        .take(usize::from(first.group_threshold))
+        .collect::<Result<Vec<_>, _>>()?;
+    if group_secrets.len() < usize::from(first.group_threshold) {
+        return Err(Slip39Error::InvalidShares(format!(
+            "{} of the {} groups must be complete, but only {} are",
+            first.group_threshold,
+            first.group_count,
+            group_secrets.len()
+        )));
+    }
+    let encrypted_secret =
+        recover_secret(first.group_threshold, &group_secrets)?;
+    Ok(decrypt(
+        &encrypted_secret,
+        passphrase,
+        first.iteration_exponent,
+        first.identifier,
+        first.extendable,
+    ))
+}
+
+/// Get the words of the wordlist
+fn wordlist() -> &'static [&'static str] {
+    static WORDS: OnceLock<Vec<&'static str>> = OnceLock::new();
+    WORDS.get_or_init(|| WORDLIST.lines().collect())
+}
+
+/// Get the index of the given word in the sorted wordlist
+fn word_index(word: &str) -> Option<u16> {
+    let word = word.to_lowercase();
+    wordlist()
+        .binary_search(&word.as_str())
+        .ok()
+        .and_then(|idx| u16::try_from(idx).ok())
+}
+
+/// Convert bytes to big endian words, left padding them with zero bits
+fn bytes_to_words(bytes: &[u8]) -> Vec<u16> {
+    let word_count = (bytes.len() * 8).div_ceil(RADIX_BITS);
+    let padding_bits = word_count * RADIX_BITS - bytes.len() * 8;
+    let bits = vec![false; padding_bits].into_iter().chain(
+        bytes
+            .iter()
+            .flat_map(|byte| (0..8).rev().map(move |i| byte >> i & 1 == 1)),
+    );
+    let bits: Vec<bool> = bits.collect();
+    bits.chunks(RADIX_BITS)
+        .map(|chunk| {
+            chunk
+                .iter()
+                .fold(0u16, |word, bit| (word << 1) | u16::from(*bit))
+        })
+        .collect()
+}
+
+/// Convert big endian words to bytes, checking that the given number of
+/// leading padding bits are zero
+fn words_to_bytes(
+    words: &[u16],
+    padding_bits: usize,
+) -> Option<Zeroizing<Vec<u8>>> {
+    let bits: Vec<bool> = words
+        .iter()
+        .flat_map(|word| (0..RADIX_BITS).rev().map(move |i| word >> i & 1 == 1))
+        .collect();
+    let (padding, bits) = bits.split_at(padding_bits);
+    if padding.iter().any(|bit| *bit) {
+        return None;
+    }
+    Some(Zeroizing::new(
+        bits.chunks(8)
+            .map(|chunk| {
+                chunk
+                    .iter()
+                    .fold(0u8, |byte, bit| (byte << 1) | u8::from(*bit))
+            })
+            .collect(),
+    ))
+}
+
+/// Get the customization string of the checksum
+fn customization_string(extendable: bool) -> &'static [u8] {
+    if extendable {
+        CUSTOMIZATION_STRING_EXTENDABLE
+    } else {
+        CUSTOMIZATION_STRING_ORIG
+    }
+}
+
+/// Evaluate the RS1024 checksum polynomial over the customization string
+/// and the given words. A valid mnemonic evaluates to one.
+fn rs1024_polymod(customization: &[u8], words: &[u16]) -> u32 {
+    const GEN: [u32; 10] = [
+        0xE0E040, 0x1C1C080, 0x3838100, 0x7070200, 0xE0E0009, 0x1C0C2412,
+        0x38086C24, 0x3090FC48, 0x21B1F890, 0x3F3F120,
+    ];
+    customization
+        .iter()
+        .map(|c| u32::from(*c))
+        .chain(words.iter().map(|w| u32::from(*w)))
+        .fold(1, |chk, value| {
+            let b = chk >> 20;
+            let chk = ((chk & 0xFFFFF) << 10) ^ value;
+            GEN.iter()
+                .enumerate()
+                .filter(|(i, _)| (b >> i) & 1 == 1)
+                .fold(chk, |chk, (_, gen)| chk ^ gen)
+        })
+}
+
+/// Check that the passphrase only contains printable ASCII characters
+fn check_passphrase(passphrase: &str) -> Result<(), Slip39Error> {
+    if passphrase.bytes().all(|c| (32..=126).contains(&c)) {
+        Ok(())
+    } else {
+        Err(Slip39Error::InvalidParameters(
+            "The passphrase must only contain printable ASCII characters"
+                .to_string(),
+        ))
+    }
+}
+
+/// The round function of the Feistel cipher
+fn round_function(
+    round: u8,
+    passphrase: &str,
+    iteration_exponent: u8,
+    salt: &[u8],
+    r: &[u8],
+) -> Zeroizing<Vec<u8>> {
+    let password =
+        Zeroizing::new([&[round][..], passphrase.as_bytes()].concat());
+    let password = pbkdf2::sha256::Password::from_slice(&password)
+        .expect("The PBKDF2 password should be valid");
+    let iterations =
+        (BASE_ITERATION_COUNT << iteration_exponent) / usize::from(ROUND_COUNT);
+    let mut out = Zeroizing::new(vec![0; r.len()]);
+    pbkdf2::sha256::derive_key(
+        &password,
+        &[salt, r].concat(),
+        iterations,
+        &mut out,
+    )
+    .expect("The PBKDF2 parameters should be valid");
+    out
+}
+
+/// Get the salt of the Feistel cipher
+fn cipher_salt(identifier: u16, extendable: bool) -> Vec<u8> {
+    if extendable {
+        vec![]
+    } else {
+        [CUSTOMIZATION_STRING_ORIG, &identifier.to_be_bytes()[..]].concat()
+    }
+}
+
+/// Apply the Feistel cipher with the given order of rounds
+fn feistel(
+    data: &[u8],
+    passphrase: &str,
+    iteration_exponent: u8,
+    salt: &[u8],
+    rounds: impl Iterator<Item = u8>,
+) -> Zeroizing<Vec<u8>> {
+    let (l, r) = data.split_at(data.len() / 2);
+    let (mut l, mut r) =
+        (Zeroizing::new(l.to_vec()), Zeroizing::new(r.to_vec()));
+    for round in rounds {
+        let f = round_function(round, passphrase, iteration_exponent, salt, &r);
+        let next_r = Zeroizing::new(
+            l.iter()
+                .zip(f.iter())
+                .map(|(a, b)| a ^ b)
+                .collect::<Vec<_>>(),
+        );
+        l = std::mem::replace(&mut r, next_r);
+    }
+    Zeroizing::new([&r[..], &l[..]].concat())
+}
+
+/// Encrypt the master secret with the passphrase
+fn encrypt(
+    master_secret: &[u8],
+    passphrase: &str,
+    iteration_exponent: u8,
+    identifier: u16,
+    extendable: bool,
+) -> Zeroizing<Vec<u8>> {
+    feistel(
+        master_secret,
+        passphrase,
+        iteration_exponent,
+        &cipher_salt(identifier, extendable),
+        0..ROUND_COUNT,
+    )
+}
+
+/// Decrypt the encrypted master secret with the passphrase
+fn decrypt(
+    encrypted_secret: &[u8],
+    passphrase: &str,
+    iteration_exponent: u8,
+    identifier: u16,
+    extendable: bool,
+) -> Zeroizing<Vec<u8>> {
+    feistel(
+        encrypted_secret,
+        passphrase,
+        iteration_exponent,
+        &cipher_salt(identifier, extendable),
+        (0..ROUND_COUNT).rev(),
+    )
+}
+
+/// A share of a secret with its x-coordinate
+type PointShare = (u8, Zeroizing<Vec<u8>>);
+
+/// The exponent and logarithm tables of GF(256) with the Rijndael
+/// polynomial, using 3 as the generator
+const GF256_TABLES: ([u8; 255], [u8; 256]) = {
+    let mut exp = [0u8; 255];
+    let mut log = [0u8; 256];
+    let mut poly: u16 = 1;
+    let mut i = 0;
+    while i < 255 {
+        exp[i] = poly as u8;
+        log[poly as usize] = i as u8;
+        // Multiply by the generator 3 = x + 1
+        poly = (poly << 1) ^ poly;
+        if poly & 0x100 != 0 {
+            poly ^= 0x11b;
+        }
+        i += 1;
+    }
+    (exp, log)
+};
+
+/// Evaluate at `x` the polynomial of the lowest degree that passes through
+/// the given points
+fn interpolate(
+    shares: &[PointShare],
+    x: u8,
+) -> Result<Zeroizing<Vec<u8>>, Slip39Error> {
+    let (exp, log) = &GF256_TABLES;
+    let length = shares.first().map(|(_, value)| value.len()).unwrap_or(0);
+    if shares.iter().any(|(_, value)| value.len() != length) {
+        return Err(Slip39Error::InvalidShares(
+            "The shares have different lengths".to_string(),
+        ));
+    }
+    if let Some((_, value)) = shares.iter().find(|(xi, _)| *xi == x) {
+        return Ok(value.clone());
+    }
+    let log_prod: u32 = shares
+        .iter()
+        .map(|(xi, _)| u32::from(log[usize::from(xi ^ x)]))
+        .sum();
+    let mut result = Zeroizing::new(vec![0u8; length]);
+    for (xi, value) in shares {
+        let log_denominator: u32 = shares
+            .iter()
+            .filter(|(xj, _)| xj != xi)
+            .map(|(xj, _)| u32::from(log[usize::from(xi ^ xj)]))
+            .sum();
+        let log_basis = (log_prod + 255 * 255
+            - u32::from(log[usize::from(xi ^ x)])
+            - log_denominator)
+            % 255;
+        for (res, y) in result.iter_mut().zip(value.iter()) {
+            if *y != 0 {
+                let log_y = u32::from(log[usize::from(*y)]);
+                *res ^= exp[((log_y + log_basis) % 255) as usize];
+            }
+        }
+    }
+    Ok(result)
+}
+
+/// Get the digest of the shared secret, prefixing the random bytes
+fn create_digest(random: &[u8], secret: &[u8]) -> Vec<u8> {
+    let key =
+        SecretKey::from_slice(random).expect("The digest key should be valid");
+    let tag = HmacSha256::hmac(&key, secret)
+        .expect("Computing the digest should not fail");
+    tag.unprotected_as_bytes()[..DIGEST_LENGTH_BYTES].to_vec()
+}
+
+/// Split the secret into `count` shares, `threshold` of which are required
+/// to recover it
+fn split_secret(
+    threshold: u8,
+    count: u8,
+    secret: &[u8],
+    rng: &mut impl RngCore,
+) -> Result<Vec<PointShare>, Slip39Error> {
+    if threshold == 0 || threshold > count || count > MAX_SHARE_COUNT {
+        return Err(Slip39Error::InvalidParameters(format!(
+            "Cannot split a secret into {count} shares with a threshold of \
+             {threshold}"
+        )));
+    }
+    if threshold == 1 {
+        return Ok((0..count)
+            .map(|idx| (idx, Zeroizing::new(secret.to_vec())))
+            .collect());
+    }
+    let random_share_count = threshold - 2;
+    let mut shares: Vec<_> = (0..random_share_count)
+        .map(|idx| {
+            let mut value = Zeroizing::new(vec![0; secret.len()]);
+            rng.fill_bytes(&mut value);
+            (idx, value)
+        })
+        .collect();
+    let mut random =
+        Zeroizing::new(vec![0; secret.len() - DIGEST_LENGTH_BYTES]);
+    rng.fill_bytes(&mut random);
+    let digest = Zeroizing::new(
+        [&create_digest(&random, secret)[..], &random[..]].concat(),
+    );
+    let mut base_shares = shares.clone();
+    base_shares.push((DIGEST_INDEX, digest));
+    base_shares.push((SECRET_INDEX, Zeroizing::new(secret.to_vec())));
+    for idx in random_share_count..count {
+        shares.push((idx, interpolate(&base_shares, idx)?));
+    }
+    Ok(shares)
+}
+
+/// Recover a secret from `threshold` of its shares and check its digest
+fn recover_secret(
+    threshold: u8,
+    shares: &[PointShare],
+) -> Result<Zeroizing<Vec<u8>>, Slip39Error> {
+    if threshold == 1 {
+        return shares.first().map(|(_, value)| value.clone()).ok_or_else(
+            || {
+                Slip39Error::InvalidShares(
+                    "No shares were provided".to_string(),
+                )
+            },
+        );
+    }
+    let secret = interpolate(shares, SECRET_INDEX)?;
+    let digest = interpolate(shares, DIGEST_INDEX)?;
+    let (digest, random) = digest.split_at(DIGEST_LENGTH_BYTES);
+    if create_digest(random, &secret) != digest {
+        return Err(Slip39Error::InvalidDigest);
+    }
+    Ok(secret)
+}
+
+#[cfg(test)]
+mod test_slip39 {
+    use data_encoding::HEXLOWER;
+    use rand_core::OsRng;
+
+    use super::*;
+
+    fn groups(specs: &str) -> Vec<GroupSpec> {
+        ShareGroups::from_str(specs).unwrap().0
+    }
+
+    #[test]
+    fn test_wordlist() {
+        let words = wordlist();
+        assert_eq!(words.len(), usize::from(RADIX));
+        assert!(words.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(word_index("academic"), Some(0));
+        assert_eq!(word_index("Zero"), Some(RADIX - 1));
+        assert_eq!(word_index("bitcoin"), None);
+    }
+
+    #[test]
+    fn test_reference_vectors() {
+        // 1-of-1 share of a 128 bits secret
+        let mnemonic = "duckling enlarge academic academic agency result \
+                        length solution fridge kidney coal piece deal husband \
+                        erode duke ajar critical decision keyboard";
+        let secret = combine_mnemonics(&[mnemonic], "TREZOR").unwrap();
+        assert_eq!(
+            HEXLOWER.encode(&secret),
+            "bb54aac4b89dc868ba37d9cc21b2cece"
+        );
+
+        // Invalid checksum
+        let mnemonic = "duckling enlarge academic academic agency result \
+                        length solution fridge kidney coal piece deal husband \
+                        erode duke ajar critical decision kidney";
+        assert!(matches!(
+            combine_mnemonics(&[mnemonic], "TREZOR"),
+            Err(Slip39Error::InvalidMnemonic(_))
+        ));
+
+        // 2-of-3 shares of a 128 bits secret
+        let mnemonics = [
+            "shadow pistol academic always adequate wildlife fancy gross \
+             oasis cylinder mustang wrist rescue view short owner flip making \
+             coding armed",
+            "shadow pistol academic acid actress prayer class unknown \
+             daughter sweater depict flip twice unkind craft early superior \
+             advocate guest smoking",
+        ];
+        let secret = combine_mnemonics(&mnemonics, "TREZOR").unwrap();
+        assert_eq!(
+            HEXLOWER.encode(&secret),
+            "b43ceb7e57a0ea8766221624d01b0864"
+        );
+        assert!(matches!(
+            combine_mnemonics(&mnemonics[..1], "TREZOR"),
+            Err(Slip39Error::InvalidShares(_))
+        ));
+
+        // The decoded shares are encoded back into the same mnemonics
+        for mnemonic in mnemonics {
+            let share = Share::from_mnemonic(mnemonic).unwrap();
+            assert!(!share.extendable);
+            assert_eq!(share.to_mnemonic().as_str(), mnemonic);
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let master_secret = [7u8; 32];
+        let shares = generate_mnemonics(
+            2,
+            &groups("1-of-1,2-of-3,3-of-5"),
+            &master_secret,
+            "passphrase",
+            0,
+            &mut OsRng,
+        )
+        .unwrap();
+        assert_eq!(
+            shares.iter().map(Vec::len).collect::<Vec<_>>(),
+            vec![1, 3, 5]
+        );
+        for share in shares.iter().flatten() {
+            assert_eq!(share.split_whitespace().count(), 33);
+        }
+
+        let recover = |mnemonics: Vec<&Zeroizing<String>>| {
+            let mnemonics: Vec<&str> =
+                mnemonics.into_iter().map(|m| m.as_str()).collect();
+            combine_mnemonics(&mnemonics, "passphrase")
+        };
+        let secret =
+            recover(vec![&shares[0][0], &shares[1][2], &shares[1][0]]).unwrap();
+        assert_eq!(&secret[..], &master_secret);
+        let secret = recover(vec![
+            &shares[2][4],
+            &shares[1][1],
+            &shares[2][0],
+            &shares[1][2],
+            &shares[2][2],
+        ])
+        .unwrap();
+        assert_eq!(&secret[..], &master_secret);
+
+        // A single group is not enough
+        assert!(recover(vec![&shares[1][0], &shares[1][1]]).is_err());
+        // Neither is an incomplete group
+        assert!(
+            recover(vec![&shares[0][0], &shares[2][0], &shares[2][1]]).is_err()
+        );
+        // A wrong passphrase gives another secret
+        let mnemonics = [
+            shares[0][0].as_str(),
+            shares[1][0].as_str(),
+            shares[1][1].as_str(),
+        ];
+        let secret = combine_mnemonics(&mnemonics, "").unwrap();
+        assert_ne!(&secret[..], &master_secret);
+    }
+
+    #[test]
+    fn test_invalid_parameters() {
+        let secret = [0u8; 16];
+        let gen = |group_threshold, specs: &str, secret: &[u8]| {
+            generate_mnemonics(
+                group_threshold,
+                &groups(specs),
+                secret,
+                "",
+                0,
+                &mut OsRng,
+            )
+        };
+        assert!(gen(1, "2-of-3", &secret).is_ok());
+        assert!(gen(3, "2-of-3,2-of-3", &secret).is_err());
+        assert!(gen(0, "2-of-3", &secret).is_err());
+        assert!(gen(1, "2-of-3", &secret[..14]).is_err());
+        assert!(gen(1, "2-of-3", &[0u8; 17]).is_err());
+        assert!(ShareGroups::from_str("1-of-2").is_err());
+        assert!(ShareGroups::from_str("3-of-2").is_err());
+        assert!(ShareGroups::from_str("2-of-17").is_err());
+        assert!(ShareGroups::from_str("2of3").is_err());
+        assert_eq!(
+            ShareGroups::from_str("1-of-1, 2-of-3").unwrap().to_string(),
+            "1-of-1,2-of-3"
+        );
+    }
+}
@@ -7,6 +7,7 @@ use std::fmt::Display;
 use std::str::FromStr;
 
 use bimap::BiBTreeMap;
+use data_encoding::HEXLOWER;
 use itertools::Itertools;
 use masp_primitives::zip32;
 use namada_core::address::{Address, ImplicitAddress};
@@ -19,9 +20,12 @@ use namada_core::masp::{
 use serde::{Deserialize, Serialize};
 use zeroize::Zeroizing;
 
+use super::address_book::{AddressBookEntry, ContactAddress, WatchOnlyAccount};
 use super::alias::{self, Alias};
+use super::audit::KeyPolicy;
 use super::derivation_path::DerivationPath;
 use super::pre_genesis;
+use crate::keys::{decrypt_data, encrypt_data, DecryptionError};
 use crate::{StoreSpendingKey, StoredKeypair, WalletIo};
 
 /// Actions that can be taken when there is an alias conflict
@@ -50,13 +54,51 @@ impl ValidatorKeys {
     }
 }
 
+/// Special keys of a validator stored in its wallet. A key that is held in a
+/// hardware security module (HSM) may be left out of the wallet.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct StoredValidatorKeys {
+    /// Special keypair for signing protocol txs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub protocol_keypair: Option<common::SecretKey>,
+    /// Special hot keypair for signing Ethereum bridge txs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub eth_bridge_keypair: Option<common::SecretKey>,
+}
+
+impl From<ValidatorKeys> for StoredValidatorKeys {
+    fn from(keys: ValidatorKeys) -> Self {
+        Self {
+            protocol_keypair: Some(keys.protocol_keypair),
+            eth_bridge_keypair: Some(keys.eth_bridge_keypair),
+        }
+    }
+}
+
 /// Special data associated with a validator
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ValidatorData {
     /// The address associated to a validator
     pub address: Address,
     /// special keys for a validator
-    pub keys: ValidatorKeys,
+    pub keys: StoredValidatorKeys,
+}
+
+/// The state of the disposable keys of a wallet
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct DisposableKeys {
+    /// The root from which the disposable keys are derived, itself derived
+    /// from an HD seed of the wallet under a reserved path. Disposable keys
+    /// are generated randomly if it is not set.
+    #[serde(default)]
+    pub root: Option<common::SecretKey>,
+    /// The derivation index of the next disposable key
+    #[serde(default)]
+    pub next_index: u32,
+    /// The lifetime of the disposable keys in seconds, after which they are
+    /// pruned. The default lifetime applies if it is not set.
+    #[serde(default)]
+    pub lifetime: Option<i64>,
 }
 
 /// A Storage area for keys and addresses
@@ -85,6 +127,18 @@ pub struct Store {
     pub(crate) validator_data: Option<ValidatorData>,
     /// Namada address vp type
     address_vp_types: BTreeMap<AddressVpType, HashSet<Address>>,
+    /// Accounts tracked without any of their secrets
+    #[serde(default)]
+    watch_only: BTreeMap<Alias, WatchOnlyAccount>,
+    /// Addresses of other parties with their metadata
+    #[serde(default)]
+    address_book: BTreeMap<Alias, AddressBookEntry>,
+    /// Local restrictions on the use of keys, by the alias of the key
+    #[serde(default)]
+    key_policies: BTreeMap<Alias, KeyPolicy>,
+    /// The state of the disposable keys
+    #[serde(default)]
+    disposable_keys: DisposableKeys,
 }
 
 /// Grouping of addresses by validity predicate.
@@ -275,6 +329,42 @@ impl Store {
         &self.payment_addrs
     }
 
+    /// Get all watch-only accounts by their alias.
+    pub fn get_watch_only_accounts(
+        &self,
+    ) -> &BTreeMap<Alias, WatchOnlyAccount> {
+        &self.watch_only
+    }
+
+    /// Get all address book entries by their alias.
+    pub fn get_address_book(&self) -> &BTreeMap<Alias, AddressBookEntry> {
+        &self.address_book
+    }
+
+    /// Get all key policies by the alias of their key.
+    pub fn get_key_policies(&self) -> &BTreeMap<Alias, KeyPolicy> {
+        &self.key_policies
+    }
+
+    /// Find the policy of the key with the given public key hash.
+    pub fn find_key_policy_by_pkh(
+        &self,
+        pkh: &PublicKeyHash,
+    ) -> Option<(&Alias, &KeyPolicy)> {
+        let alias = self.pkhs.get(pkh)?;
+        self.key_policies.get_key_value(alias)
+    }
+
+    /// Get the state of the disposable keys.
+    pub fn get_disposable_keys(&self) -> &DisposableKeys {
+        &self.disposable_keys
+    }
+
+    /// Get the mutable state of the disposable keys.
+    pub fn get_disposable_keys_mut(&mut self) -> &mut DisposableKeys {
+        &mut self.disposable_keys
+    }
+
     /// Get all known viewing keys by their alias.
     pub fn get_viewing_keys(&self) -> &BTreeMap<Alias, ExtendedViewingKey> {
         &self.view_keys
@@ -293,7 +383,10 @@ impl Store {
         address: Address,
         keys: ValidatorKeys,
     ) {
-        self.validator_data = Some(ValidatorData { address, keys });
+        self.validator_data = Some(ValidatorData {
+            address,
+            keys: keys.into(),
+        });
     }
 
     /// Returns a reference to the validator data, if it exists.
@@ -599,6 +692,140 @@ impl Store {
         Some(alias)
     }
 
+    /// Insert a watch-only account. Its address and viewing key, if any, are
+    /// added under the same alias so that its balances can be queried and
+    /// its notes can be synced like those of owned accounts.
+    pub fn insert_watch_only<U: WalletIo>(
+        &mut self,
+        alias: Alias,
+        account: WatchOnlyAccount,
+        force: bool,
+    ) -> Option<Alias> {
+        // abort if the alias is reserved
+        if Alias::is_reserved(&alias).is_some() {
+            println!("The alias {} is reserved.", alias);
+            return None;
+        }
+        if alias.is_empty() {
+            eprintln!("Empty alias given.");
+            return None;
+        }
+        // abort if the address already exists in the wallet
+        if let Some(existing) = self.addresses.get_by_right(&account.address) {
+            if !force && *existing != alias {
+                println!(
+                    "Address {} already exists in the wallet with alias {}",
+                    account.address.encode(),
+                    existing
+                );
+                return None;
+            }
+        }
+        if self.contains_alias(&alias) && !force {
+            match U::show_overwrite_confirmation(&alias, "a watch-only account")
+            {
+                ConfirmationResponse::Replace => {}
+                ConfirmationResponse::Reselect(new_alias) => {
+                    return self
+                        .insert_watch_only::<U>(new_alias, account, false);
+                }
+                ConfirmationResponse::Skip => return None,
+            }
+        }
+        self.remove_alias(&alias);
+        self.addresses
+            .insert(alias.clone(), account.address.clone());
+        if let Some(viewing_key) = account.viewing_key {
+            self.view_keys.insert(alias.clone(), viewing_key);
+            account
+                .birthday
+                .map(|x| self.birthdays.insert(alias.clone(), x));
+        }
+        self.watch_only.insert(alias.clone(), account);
+        Some(alias)
+    }
+
+    /// Insert an address book entry. Its address is added under the same
+    /// alias so that it can be used as the target of transactions.
+    pub fn insert_address_book_entry<U: WalletIo>(
+        &mut self,
+        alias: Alias,
+        entry: AddressBookEntry,
+        force: bool,
+    ) -> Option<Alias> {
+        // abort if the alias is reserved
+        if Alias::is_reserved(&alias).is_some() {
+            println!("The alias {} is reserved.", alias);
+            return None;
+        }
+        if alias.is_empty() {
+            eprintln!("Empty alias given.");
+            return None;
+        }
+        // abort if the address already exists in the wallet
+        let existing = match &entry.address {
+            ContactAddress::Transparent(address) => {
+                self.addresses.get_by_right(address)
+            }
+            ContactAddress::Shielded(address) => {
+                self.payment_addrs.get_by_right(address)
+            }
+        };
+        if let Some(existing) = existing {
+            if !force && *existing != alias {
+                println!(
+                    "Address {} already exists in the wallet with alias {}",
+                    entry.address, existing
+                );
+                return None;
+            }
+        }
+        if self.contains_alias(&alias) && !force {
+            match U::show_overwrite_confirmation(
+                &alias,
+                "an address book entry",
+            ) {
+                ConfirmationResponse::Replace => {}
+                ConfirmationResponse::Reselect(new_alias) => {
+                    return self.insert_address_book_entry::<U>(
+                        new_alias, entry, false,
+                    );
+                }
+                ConfirmationResponse::Skip => return None,
+            }
+        }
+        self.remove_alias(&alias);
+        match &entry.address {
+            ContactAddress::Transparent(address) => {
+                self.addresses.insert(alias.clone(), address.clone());
+            }
+            ContactAddress::Shielded(address) => {
+                self.payment_addrs.insert(alias.clone(), *address);
+            }
+        }
+        self.address_book.insert(alias.clone(), entry);
+        Some(alias)
+    }
+
+    /// Set the policy of the key with the given alias. Return `false` if no
+    /// such key is known.
+    pub fn insert_key_policy(
+        &mut self,
+        alias: Alias,
+        policy: KeyPolicy,
+    ) -> bool {
+        if !self.public_keys.contains_key(&alias) {
+            return false;
+        }
+        self.key_policies.insert(alias, policy);
+        true
+    }
+
+    /// Remove the policy of the key with the given alias.
+    pub fn remove_key_policy(&mut self, alias: &Alias) -> Option<KeyPolicy> {
+        self.key_policies.remove(alias)
+    }
+
     /// Check if any map of the wallet contains the given alias
     pub fn contains_alias(&self, alias: &Alias) -> bool {
         self.payment_addrs.contains_left(alias)
@@ -610,6 +837,8 @@ impl Store {
             || self.public_keys.contains_key(alias)
             || self.derivation_paths.contains_key(alias)
             || self.birthdays.contains_key(alias)
+            || self.watch_only.contains_key(alias)
+            || self.address_book.contains_key(alias)
     }
 
     /// Completely remove the given alias from all maps in the wallet
@@ -623,10 +852,14 @@ impl Store {
         self.public_keys.remove(alias);
         self.derivation_paths.remove(alias);
         self.birthdays.remove(alias);
+        self.watch_only.remove(alias);
+        self.address_book.remove(alias);
+        self.key_policies.remove(alias);
     }
 
     /// Extend this store from another store (typically pre-genesis).
-    /// Note that this method ignores `validator_data` if any.
+    /// Note that this method ignores `validator_data` if any, as well as the
+    /// state of the disposable keys.
     pub fn extend(&mut self, store: Store) {
         let Self {
             birthdays,
@@ -640,6 +873,10 @@ impl Store {
             pkhs,
             validator_data: _,
             address_vp_types,
+            watch_only,
+            address_book,
+            key_policies,
+            disposable_keys: _,
         } = self;
         birthdays.extend(store.birthdays);
         view_keys.extend(store.view_keys);
@@ -651,6 +888,9 @@ impl Store {
         addresses.extend(store.addresses);
         pkhs.extend(store.pkhs);
         address_vp_types.extend(store.address_vp_types);
+        watch_only.extend(store.watch_only);
+        address_book.extend(store.address_book);
+        key_policies.extend(store.key_policies);
     }
 
     /// Extend this store from pre-genesis validator wallet.
@@ -704,7 +944,7 @@ impl Store {
 
         self.validator_data = Some(ValidatorData {
             address: validator_address,
-            keys: other.store.validator_keys,
+            keys: other.store.validator_keys.into(),
         });
     }
 
@@ -750,6 +990,45 @@ impl Store {
     }
 }
 
+/// A store encrypted as a whole, so that none of its aliases, addresses,
+/// viewing keys and payment addresses can be read without the password
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct EncryptedStore {
+    /// The hex-encoded salt and ciphertext of the encoded store
+    encrypted_store: String,
+}
+
+impl EncryptedStore {
+    /// Encrypt the given store with a key derived from the password
+    pub fn encrypt(store: &Store, password: &str) -> Self {
+        let data = Zeroizing::new(store.encode());
+        Self {
+            encrypted_store: HEXLOWER.encode(&encrypt_data(&data, password)),
+        }
+    }
+
+    /// Decrypt the store with the password it was encrypted with
+    pub fn decrypt(&self, password: &str) -> Result<Store, DecryptionError> {
+        let data = HEXLOWER
+            .decode(self.encrypted_store.as_bytes())
+            .map_err(|_| DecryptionError::DeserializingError)?;
+        let data = Zeroizing::new(decrypt_data(&data, password)?);
+        Store::decode(data.to_vec())
+            .map_err(|_| DecryptionError::DeserializingError)
+    }
+
+    /// Decode an encrypted store from the given bytes, if they hold one
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        toml::from_slice(data).ok()
+    }
+
+    /// Encode an encrypted store into a string of bytes
+    pub fn encode(&self) -> Vec<u8> {
+        toml::to_vec(self)
+            .expect("Serializing of encrypted store shouldn't fail")
+    }
+}
+
 /// Generate a new secret key from the seed.
 pub fn derive_hd_secret_key(
     scheme: SchemeType,
@@ -950,6 +1229,43 @@ mod test_wallet {
         assert_eq!(&sk.to_string()[2..], SK_EXPECTED);
     }
 
+    #[test]
+    fn test_encrypted_store() {
+        use namada_core::key::testing::keypair_1;
+
+        use crate::test_utils::TestWalletUtils;
+
+        let mut store = Store::default();
+        let address = Address::from(&keypair_1().ref_to());
+        store.insert_address::<TestWalletUtils>(
+            "secret-alias".into(),
+            address.clone(),
+            false,
+        );
+
+        let encrypted = EncryptedStore::encrypt(&store, "password");
+        let encoded = encrypted.encode();
+        // Neither the aliases nor the addresses are readable at rest
+        let text = String::from_utf8(encoded.clone()).unwrap();
+        assert!(!text.contains("secret-alias"));
+        assert!(!text.contains(&address.encode()));
+        // An encrypted store is told apart from a plaintext one
+        assert!(EncryptedStore::decode(&store.encode()).is_none());
+        let decoded = EncryptedStore::decode(&encoded).unwrap();
+        assert_eq!(decoded, encrypted);
+
+        let decrypted = decoded.decrypt("password").unwrap();
+        assert_eq!(decrypted.find_address("secret-alias"), Some(&address));
+        assert!(matches!(
+            decoded.decrypt("wrong"),
+            Err(DecryptionError::DecryptionError)
+        ));
+        assert!(matches!(
+            decoded.decrypt(""),
+            Err(DecryptionError::EmptyPassword)
+        ));
+    }
+
     #[test]
     fn gen_sk_from_mnemonic_code_ed25519() {
         const SCHEME: SchemeType = SchemeType::Ed25519;
@@ -1073,4 +1389,69 @@ mod test_wallet {
             do_test_gen_sk_from_seed_and_derivation_path(SCHEME, SEED, "m/0'/2147483647'/1'/2147483646'/2'", "551d333177df541ad876a60ea71f00447931c0a9da16f227c11ea080d7391b8d");
         }
     }
+
+    #[test]
+    fn test_watch_only_and_address_book() {
+        use namada_core::address::testing::{established_address_1, nam};
+        use namada_core::key::testing::keypair_1;
+
+        use crate::test_utils::TestWalletUtils;
+
+        let mut store = Store::default();
+        let pk = keypair_1().ref_to();
+        let account = WatchOnlyAccount {
+            address: (&pk).into(),
+            public_keys: vec![pk],
+            viewing_key: None,
+            birthday: None,
+        };
+        let alias = store
+            .insert_watch_only::<TestWalletUtils>(
+                "cold".into(),
+                account.clone(),
+                false,
+            )
+            .unwrap();
+        // The account can be used by alias like any other address
+        assert_eq!(store.find_address("cold"), Some(&account.address));
+
+        // The same address cannot be added under another alias
+        let mut entry = AddressBookEntry {
+            address: ContactAddress::Transparent(account.address.clone()),
+            label: Some("Cold wallet".to_string()),
+            tags: ["savings".to_string()].into(),
+            note: None,
+            fee_token: Some(nam()),
+        };
+        assert!(
+            store
+                .insert_address_book_entry::<TestWalletUtils>(
+                    "savings".into(),
+                    entry.clone(),
+                    false,
+                )
+                .is_none()
+        );
+        entry.address = ContactAddress::Transparent(established_address_1());
+        store
+            .insert_address_book_entry::<TestWalletUtils>(
+                "alice".into(),
+                entry.clone(),
+                false,
+            )
+            .unwrap();
+        assert_eq!(store.find_address("alice"), Some(&established_address_1()));
+
+        // Both sections survive the encoding of the store
+        let decoded = Store::decode(store.encode()).unwrap();
+        assert_eq!(
+            decoded.get_watch_only_accounts(),
+            store.get_watch_only_accounts()
+        );
+        assert_eq!(decoded.get_address_book(), store.get_address_book());
+
+        store.remove_alias(&alias);
+        assert!(store.get_watch_only_accounts().is_empty());
+        assert!(store.find_address("cold").is_none());
+    }
 }